@@ -20,7 +20,9 @@ struct CaptureConsState {
 pub struct Capture {
     backend: CaptureBackend,
     cons: Mutex<CaptureConsState>,
-    frame_samples: usize,
+    /// Largest frame `read_frame` will serve; the session frame size can
+    /// change at runtime without reopening the device.
+    max_frame_samples: usize,
 }
 
 pub const CAPTURE_MODE_AUTO: &str = "Automatically use best mode";
@@ -74,7 +76,9 @@ impl Capture {
         preferred_mode: Option<&str>,
         tx_event: Option<Sender<UiEvent>>,
     ) -> Result<Self> {
-        let frame_samples = (sample_rate as usize * frame_ms as usize / 1000) * channels as usize;
+        let max_frame_ms = frame_ms.max(crate::audio::MAX_FRAME_MS);
+        let frame_samples =
+            (sample_rate as usize * max_frame_ms as usize / 1000) * channels as usize;
         let rb = HeapRb::<i16>::new(frame_samples * 50);
         let (prod, cons) = rb.split();
 
@@ -105,25 +109,28 @@ impl Capture {
                 stash: Vec::with_capacity(frame_samples * 2),
                 underflow_counter: 0,
             }),
-            frame_samples,
+            max_frame_samples: frame_samples,
         })
     }
 
+    /// Fill `out` with exactly `out.len()` samples, or return false and keep
+    /// what was read for the next call.
     pub fn read_frame(&self, out: &mut [i16]) -> bool {
         let _ = &self.backend;
-        if out.len() != self.frame_samples {
+        let frame_samples = out.len();
+        if frame_samples == 0 || frame_samples > self.max_frame_samples {
             return false;
         }
         let mut state = self.cons.lock();
 
-        let mut tmp = Vec::with_capacity(self.frame_samples);
+        let mut tmp = Vec::with_capacity(frame_samples);
         if !state.stash.is_empty() {
-            let take = state.stash.len().min(self.frame_samples);
+            let take = state.stash.len().min(frame_samples);
             tmp.extend_from_slice(&state.stash[..take]);
             state.stash.drain(..take);
         }
 
-        while tmp.len() < self.frame_samples {
+        while tmp.len() < frame_samples {
             if let Some(v) = state.cons.try_pop() {
                 tmp.push(v);
             } else {
@@ -131,7 +138,7 @@ impl Capture {
             }
         }
 
-        if tmp.len() < self.frame_samples {
+        if tmp.len() < frame_samples {
             let mut new_stash = tmp;
             new_stash.extend_from_slice(&state.stash);
             state.stash = new_stash;
@@ -140,15 +147,15 @@ impl Capture {
                 tracing::warn!(
                     "[audio] capture underflow: waiting for full frame (stash_len={} frame={})",
                     state.stash.len(),
-                    self.frame_samples
+                    frame_samples
                 );
             }
             return false;
         }
 
-        out.copy_from_slice(&tmp[..self.frame_samples]);
-        if tmp.len() > self.frame_samples {
-            state.stash.extend_from_slice(&tmp[self.frame_samples..]);
+        out.copy_from_slice(&tmp[..frame_samples]);
+        if tmp.len() > frame_samples {
+            state.stash.extend_from_slice(&tmp[frame_samples..]);
         }
         state.underflow_counter = 0;
         true
@@ -157,6 +164,12 @@ impl Capture {
     pub fn is_healthy(&self) -> bool {
         self.backend.is_healthy()
     }
+
+    /// Halt the device stream ahead of drop so shutdown doesn't race a
+    /// live callback. Idempotent; `read_frame` just underflows afterwards.
+    pub fn stop(&self) {
+        self.backend.stop();
+    }
 }
 
 pub fn enumerate_input_devices() -> Vec<AudioDeviceInfo> {
@@ -379,6 +392,13 @@ mod linux {
                 LinuxCaptureBackend::Pulse(cpal) => cpal.is_healthy(),
             }
         }
+
+        pub fn stop(&self) {
+            self.stop.store(true, Ordering::Relaxed);
+            if let LinuxCaptureBackend::Pulse(cpal) = &self.backend {
+                cpal.stop();
+            }
+        }
     }
 
     impl Drop for LinuxCapture {
@@ -714,7 +734,7 @@ mod linux {
     use crate::audio::windows::mmdevice;
 
     struct CpalCapture {
-        _stream: cpal::Stream,
+        stream: cpal::Stream,
         unhealthy: Arc<AtomicBool>,
     }
 
@@ -852,10 +872,7 @@ mod linux {
                 other => return Err(anyhow!("unsupported input sample format: {other:?}")),
             };
             stream.play()?;
-            Ok(Self {
-                _stream: stream,
-                unhealthy,
-            })
+            Ok(Self { stream, unhealthy })
         }
 
         fn enumerate_input_devices() -> Vec<AudioDeviceInfo> {
@@ -868,6 +885,10 @@ mod linux {
         fn is_healthy(&self) -> bool {
             !self.unhealthy.load(Ordering::Relaxed)
         }
+
+        fn stop(&self) {
+            let _ = self.stream.pause();
+        }
     }
 
     fn device_label(device: &cpal::Device) -> Option<String> {
@@ -1075,7 +1096,7 @@ mod non_linux {
     };
 
     pub struct CpalCapture {
-        _stream: cpal::Stream,
+        stream: cpal::Stream,
         unhealthy: Arc<AtomicBool>,
     }
 
@@ -1212,10 +1233,7 @@ mod non_linux {
                 other => return Err(anyhow!("unsupported input sample format: {other:?}")),
             };
             stream.play()?;
-            Ok(Self {
-                _stream: stream,
-                unhealthy,
-            })
+            Ok(Self { stream, unhealthy })
         }
 
         pub fn enumerate_input_devices() -> Vec<AudioDeviceInfo> {
@@ -1241,6 +1259,10 @@ mod non_linux {
         pub fn is_healthy(&self) -> bool {
             !self.unhealthy.load(Ordering::Relaxed)
         }
+
+        pub fn stop(&self) {
+            let _ = self.stream.pause();
+        }
     }
 
     fn device_label(device: &cpal::Device) -> Option<String> {
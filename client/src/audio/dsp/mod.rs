@@ -10,14 +10,20 @@
 pub mod aec;
 pub mod agc;
 pub mod rnnoise;
+pub mod spatial;
 pub mod vad;
 
 use anyhow::Result;
 #[cfg(feature = "aec")]
+use std::collections::VecDeque;
+#[cfg(feature = "aec")]
 use std::time::{Duration, Instant};
 #[cfg(feature = "aec")]
 use tracing::warn;
 
+/// Longest echo reference delay, 200 ms at 48 kHz.
+pub const MAX_ECHO_REFERENCE_DELAY_SAMPLES: u32 = 9_600;
+
 /// Full DSP pipeline for the capture (microphone) path.
 pub struct CaptureDsp {
     agc: agc::Agc,
@@ -35,6 +41,14 @@ pub struct CaptureDsp {
     last_valid_echo_reference_at: Option<Instant>,
     #[cfg(feature = "aec")]
     last_echo_reference_warning_at: Option<Instant>,
+    /// Reference audio held back so it lines up with the mic picking up
+    /// the speaker; see [`CaptureDsp::set_echo_reference_delay_samples`].
+    #[cfg(feature = "aec")]
+    echo_ref_delay: ReferenceDelay,
+    /// Smoothed dB the canceller takes out of the mic signal while the
+    /// far end is audible.
+    #[cfg(feature = "aec")]
+    echo_return_loss_db: Option<f32>,
 }
 
 impl CaptureDsp {
@@ -61,18 +75,32 @@ impl CaptureDsp {
             last_valid_echo_reference_at: None,
             #[cfg(feature = "aec")]
             last_echo_reference_warning_at: None,
+            #[cfg(feature = "aec")]
+            echo_ref_delay: ReferenceDelay::default(),
+            #[cfg(feature = "aec")]
+            echo_return_loss_db: None,
         })
     }
 
     /// Process a frame of PCM samples in-place. Returns VAD probability (0.0..1.0).
-    /// Frame must be exactly 480 samples (10ms at 48kHz) for RNNoise.
-    /// For 20ms frames (960 samples), call twice with each half.
+    /// Any multiple of 480 samples (10ms at 48kHz) works: RNNoise runs per
+    /// 480-sample sub-frame and the frame's VAD is the highest among them.
     pub fn process_frame(&mut self, pcm: &mut [i16]) -> f32 {
         #[cfg(feature = "aec")]
         if self.echo_cancellation_enabled {
-            self.maybe_warn_if_reference_missing();
+            let reference_live = !self.maybe_warn_if_reference_missing();
             if let Some(aec) = self.aec.as_mut() {
+                let before = mean_square(pcm);
                 aec.process(pcm);
+                // Only meaningful while the far end plays and the mic hears
+                // something; silence would read as perfect cancellation.
+                if reference_live && before > ECHO_LOSS_MIN_POWER {
+                    let loss_db = 10.0 * (before / mean_square(pcm).max(1.0)).log10();
+                    self.echo_return_loss_db = Some(match self.echo_return_loss_db {
+                        Some(prev) => prev + (loss_db - prev) * ECHO_LOSS_SMOOTHING,
+                        None => loss_db,
+                    });
+                }
             }
         }
 
@@ -139,6 +167,35 @@ impl CaptureDsp {
     /// Enable or disable acoustic echo cancellation.
     pub fn set_echo_cancellation(&mut self, enabled: bool) {
         self.echo_cancellation_enabled = enabled;
+        #[cfg(feature = "aec")]
+        {
+            self.echo_return_loss_db = None;
+        }
+    }
+
+    /// Delay the echo reference by `samples` (48 kHz) before the canceller
+    /// sees it, for output paths whose latency exceeds what the canceller's
+    /// own delay search covers. Clamped to [`MAX_ECHO_REFERENCE_DELAY_SAMPLES`].
+    pub fn set_echo_reference_delay_samples(&mut self, samples: u32) {
+        #[cfg(feature = "aec")]
+        {
+            self.echo_ref_delay
+                .set_delay(samples.min(MAX_ECHO_REFERENCE_DELAY_SAMPLES) as usize);
+        }
+        #[cfg(not(feature = "aec"))]
+        {
+            let _ = samples;
+        }
+    }
+
+    /// How many dB echo cancellation currently removes from the mic while
+    /// the far end is playing; `None` when off or not measured yet.
+    pub fn echo_return_loss_db(&self) -> Option<f32> {
+        #[cfg(feature = "aec")]
+        if self.echo_cancellation_enabled {
+            return self.echo_return_loss_db;
+        }
+        None
     }
 
     pub fn set_echo_reference_enabled(&mut self, enabled: bool) {
@@ -160,7 +217,7 @@ impl CaptureDsp {
         if self.echo_cancellation_enabled && self.echo_reference_enabled {
             if let Some(aec) = self.aec.as_mut() {
                 self.echo_ref_scratch.clear();
-                self.echo_ref_scratch.extend_from_slice(pcm);
+                self.echo_ref_delay.push(pcm, &mut self.echo_ref_scratch);
                 aec.feed_reference(&self.echo_ref_scratch);
                 if self.echo_ref_scratch.iter().any(|s| s.unsigned_abs() > 8) {
                     self.last_valid_echo_reference_at = Some(Instant::now());
@@ -170,17 +227,18 @@ impl CaptureDsp {
         }
     }
 
+    /// Returns whether the reference is missing or stale.
     #[cfg(feature = "aec")]
-    fn maybe_warn_if_reference_missing(&mut self) {
+    fn maybe_warn_if_reference_missing(&mut self) -> bool {
         if !self.echo_reference_enabled {
-            return;
+            return true;
         }
         let now = Instant::now();
         let reference_stale = self
             .last_valid_echo_reference_at
             .is_none_or(|last| now.duration_since(last) > Duration::from_secs(2));
         if !reference_stale {
-            return;
+            return false;
         }
         let should_warn = self
             .last_echo_reference_warning_at
@@ -191,9 +249,50 @@ impl CaptureDsp {
             );
             self.last_echo_reference_warning_at = Some(now);
         }
+        true
+    }
+}
+
+/// Mic power (mean square, i16 units) below which a frame is too quiet to
+/// say anything about echo loss.
+#[cfg(feature = "aec")]
+const ECHO_LOSS_MIN_POWER: f32 = 100.0 * 100.0;
+/// Per-frame weight of a new echo loss reading (~0.5 s at 10 ms frames).
+#[cfg(feature = "aec")]
+const ECHO_LOSS_SMOOTHING: f32 = 0.02;
+
+/// Holds echo reference audio back by a fixed number of samples.
+#[cfg(feature = "aec")]
+#[derive(Default)]
+struct ReferenceDelay {
+    pending: VecDeque<i16>,
+    delay: usize,
+}
+
+#[cfg(feature = "aec")]
+impl ReferenceDelay {
+    /// Change the delay, dropping audio held back under the old one.
+    fn set_delay(&mut self, samples: usize) {
+        self.delay = samples;
+        self.pending.clear();
+    }
+
+    /// Queue `pcm` and move whatever is now `delay` samples old into `out`.
+    fn push(&mut self, pcm: &[i16], out: &mut Vec<i16>) {
+        self.pending.extend(pcm.iter().copied());
+        let ready = self.pending.len().saturating_sub(self.delay);
+        out.extend(self.pending.drain(..ready));
     }
 }
 
+#[cfg(feature = "aec")]
+fn mean_square(pcm: &[i16]) -> f32 {
+    if pcm.is_empty() {
+        return 0.0;
+    }
+    pcm.iter().map(|&s| (s as f32) * (s as f32)).sum::<f32>() / pcm.len() as f32
+}
+
 /// DSP pipeline for the playout (speaker) path.
 pub struct PlayoutDsp {
     agc: agc::Agc,
@@ -216,3 +315,62 @@ impl PlayoutDsp {
         pcm.copy_from_slice(&self.frame_scratch);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::CaptureDsp;
+    #[cfg(feature = "aec")]
+    use super::{ReferenceDelay, MAX_ECHO_REFERENCE_DELAY_SAMPLES};
+
+    #[test]
+    fn twenty_ms_frames_are_denoised_per_sub_frame() {
+        let mut dsp = CaptureDsp::new(48_000).unwrap();
+        let mut pcm: Vec<i16> = (0..960)
+            .map(|i| ((i as f32 * 0.07).sin() * 6_000.0) as i16)
+            .collect();
+        for _ in 0..10 {
+            let vad = dsp.process_frame(&mut pcm);
+            assert!((0.0..=1.0).contains(&vad), "{vad}");
+            assert_eq!(vad, dsp.last_vad_probability());
+        }
+
+        let mut silence = vec![0i16; 960];
+        let vad = dsp.process_frame(&mut silence);
+        assert!(vad < 0.5, "{vad}");
+        assert!(!dsp.is_voice_active());
+    }
+
+    #[cfg(feature = "aec")]
+    #[test]
+    fn echo_reference_comes_out_delay_samples_late() {
+        let mut delay = ReferenceDelay::default();
+        delay.set_delay(3);
+        let mut out = Vec::new();
+        delay.push(&[1, 2], &mut out);
+        assert!(out.is_empty());
+        delay.push(&[3, 4, 5], &mut out);
+        assert_eq!(out, [1, 2]);
+        delay.push(&[6], &mut out);
+        assert_eq!(out, [1, 2, 3]);
+
+        // A new delay starts over instead of replaying audio held for the old one.
+        out.clear();
+        delay.set_delay(1);
+        delay.push(&[7, 8], &mut out);
+        assert_eq!(out, [7]);
+        delay.set_delay(0);
+        delay.push(&[9], &mut out);
+        assert_eq!(out, [7, 9]);
+    }
+
+    #[cfg(feature = "aec")]
+    #[test]
+    fn echo_reference_delay_is_clamped() {
+        let mut dsp = CaptureDsp::new(48_000).unwrap();
+        dsp.set_echo_reference_delay_samples(u32::MAX);
+        assert_eq!(
+            dsp.echo_ref_delay.delay,
+            MAX_ECHO_REFERENCE_DELAY_SAMPLES as usize
+        );
+    }
+}
@@ -41,9 +41,10 @@ impl Denoiser {
         }
     }
 
-    /// Process a frame of i16 PCM in-place. The frame length must be
-    /// a multiple of 480 (RNNoise frame size). Returns the VAD probability
-    /// of the last sub-frame processed.
+    /// Process a frame of i16 PCM in-place, 480 samples (one RNNoise frame)
+    /// at a time; a partial tail is left untouched. Returns the highest VAD
+    /// probability across the sub-frames, so speech in either half of a
+    /// 20 ms frame counts.
     pub fn process_frame(&mut self, pcm: &mut [i16]) -> f32 {
         let frame_size = DenoiseState::FRAME_SIZE; // 480
         let mut vad = 0.0f32;
@@ -59,9 +60,10 @@ impl Denoiser {
             }
 
             // Denoise in-place, get VAD
-            vad = self
+            let chunk_vad = self
                 .state
                 .process_frame(&mut self.output_buf[..frame_size], &self.f32_buf);
+            vad = vad.max(chunk_vad);
             self.f32_buf[..frame_size].copy_from_slice(&self.output_buf[..frame_size]);
 
             // f32 → i16 (clamp)
@@ -74,7 +76,7 @@ impl Denoiser {
         vad
     }
 
-    /// Last VAD probability from the most recent `process_frame` call.
+    /// VAD probability returned by the most recent `process_frame` call.
     pub fn last_vad(&self) -> f32 {
         self.last_vad
     }
@@ -4,6 +4,8 @@
 //! This module provides additional energy-based VAD as a fallback
 //! and a hysteresis wrapper to avoid rapid on/off switching.
 
+use std::collections::VecDeque;
+
 /// Hysteresis wrapper around a VAD probability source.
 /// Requires the probability to exceed `on_threshold` to activate,
 /// and drop below `off_threshold` to deactivate. This prevents
@@ -50,6 +52,13 @@ impl VadHysteresis {
         Self::new(on_threshold, off_threshold, attack_frames, hangover_frames)
     }
 
+    /// Change how long the gate stays open after speech drops below
+    /// `off_threshold`, without resetting the current state.
+    pub fn set_hangover_ms(&mut self, hangover_ms: u32, frame_ms: u32) {
+        self.hangover_frames = (hangover_ms / frame_ms.max(1)).max(1);
+        self.hangover_counter = self.hangover_counter.min(self.hangover_frames);
+    }
+
     /// Update with a new VAD probability. Returns whether voice is active.
     pub fn update(&mut self, probability: f32) -> bool {
         if probability >= self.on_threshold {
@@ -81,6 +90,46 @@ impl VadHysteresis {
     }
 }
 
+/// The last few frames captured while the gate was shut. The attack time
+/// means the gate opens a few frames into a word; replaying these when it
+/// does keeps the first syllable from being clipped.
+pub struct PreRoll {
+    frames: VecDeque<Vec<i16>>,
+    capacity: usize,
+}
+
+impl PreRoll {
+    pub fn from_timing(pre_roll_ms: u32, frame_ms: u32) -> Self {
+        let frame_ms = frame_ms.max(1);
+        let capacity = pre_roll_ms.div_ceil(frame_ms).max(1) as usize;
+        Self {
+            frames: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Remember `pcm`, dropping the oldest frame once full.
+    pub fn push(&mut self, pcm: &[i16]) {
+        let mut frame = if self.frames.len() >= self.capacity {
+            self.frames.pop_front().unwrap_or_default()
+        } else {
+            Vec::with_capacity(pcm.len())
+        };
+        frame.clear();
+        frame.extend_from_slice(pcm);
+        self.frames.push_back(frame);
+    }
+
+    /// Take the buffered frames, oldest first.
+    pub fn take(&mut self) -> Vec<Vec<i16>> {
+        self.frames.drain(..).collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+}
+
 /// Simple energy-based VAD as a fallback when RNNoise is not available.
 pub fn energy_vad(pcm: &[i16], threshold_db: f32) -> bool {
     if pcm.is_empty() {
@@ -95,3 +144,30 @@ pub fn energy_vad(pcm: &[i16], threshold_db: f32) -> bool {
     };
     db > threshold_db
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hangover_holds_the_gate_open_for_the_configured_time() {
+        let mut vad = VadHysteresis::from_timing(0.6, 0.45, 20, 300, 20);
+        assert!(vad.update(0.9));
+        vad.set_hangover_ms(100, 20);
+        for _ in 0..4 {
+            assert!(vad.update(0.0));
+        }
+        assert!(!vad.update(0.0));
+    }
+
+    #[test]
+    fn pre_roll_keeps_the_most_recent_frames() {
+        let mut pre_roll = PreRoll::from_timing(50, 20);
+        for i in 0..5 {
+            pre_roll.push(&[i; 4]);
+        }
+        let frames = pre_roll.take();
+        assert_eq!(frames, vec![vec![2; 4], vec![3; 4], vec![4; 4]]);
+        assert!(pre_roll.take().is_empty());
+    }
+}
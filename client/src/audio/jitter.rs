@@ -6,14 +6,32 @@ pub enum PopResult {
     Waiting,
 }
 
+/// Running totals since the buffer was created.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct JitterStats {
+    /// Frames handed to [`JitterBuffer::push`], late ones included.
+    pub received: u64,
+    /// Frames that arrived after a later sequence number was already buffered.
+    pub reordered: u64,
+    /// Frames discarded because playout had already moved past them.
+    pub late_dropped: u64,
+    /// Sequence numbers given up on ([`PopResult::Missing`]); the caller conceals them.
+    pub concealed: u64,
+}
+
 pub struct JitterBuffer {
     max_frames: usize,
+    /// Sender frame duration, for turning sequence slots into time.
+    frame_ms: u32,
     expected_seq: u32,
     expected_wait_started_ms: Option<u64>,
     buf: BTreeMap<u32, Vec<u8>>,
+    stats: JitterStats,
 }
 
 impl JitterBuffer {
+    const DEFAULT_FRAME_MS: u32 = 20;
+
     #[inline]
     fn seq_before(a: u32, b: u32) -> bool {
         const HALF_RANGE: u32 = 1 << 31;
@@ -23,16 +41,28 @@ impl JitterBuffer {
     pub fn new(max_frames: usize) -> Self {
         Self {
             max_frames,
+            frame_ms: Self::DEFAULT_FRAME_MS,
             expected_seq: 0,
             expected_wait_started_ms: None,
             buf: BTreeMap::new(),
+            stats: JitterStats::default(),
         }
     }
 
     pub fn push(&mut self, seq: u32, payload: Vec<u8>) {
+        self.stats.received += 1;
         if Self::seq_before(seq, self.expected_seq) {
+            self.stats.late_dropped += 1;
             return;
         }
+        if self
+            .buf
+            .keys()
+            .next_back()
+            .is_some_and(|&last| Self::seq_before(seq, last))
+        {
+            self.stats.reordered += 1;
+        }
 
         if self.buf.is_empty() {
             self.expected_seq = seq;
@@ -75,6 +105,7 @@ impl JitterBuffer {
             if timed_out || Self::seq_before(self.expected_seq, min_seq) {
                 self.expected_seq = self.expected_seq.wrapping_add(1);
                 self.expected_wait_started_ms = Some(now_ms);
+                self.stats.concealed += 1;
                 return PopResult::Missing;
             }
         }
@@ -89,6 +120,33 @@ impl JitterBuffer {
     pub fn depth(&self) -> usize {
         self.buf.len()
     }
+
+    /// Frame duration of what the sender is pushing; 20 ms until set.
+    pub fn set_frame_ms(&mut self, frame_ms: u32) {
+        self.frame_ms = frame_ms;
+    }
+
+    /// How long until the newest buffered frame plays out: the sequence slots
+    /// from the next frame due up to it, at the sender's frame duration. Gaps
+    /// count too, since playout waits on or conceals them first.
+    pub fn current_delay_ms(&self) -> u32 {
+        let newest = self
+            .buf
+            .keys()
+            .copied()
+            .reduce(|a, b| if Self::seq_before(a, b) { b } else { a });
+        match newest {
+            Some(seq) => seq
+                .wrapping_sub(self.expected_seq)
+                .saturating_add(1)
+                .saturating_mul(self.frame_ms),
+            None => 0,
+        }
+    }
+
+    pub fn stats(&self) -> JitterStats {
+        self.stats
+    }
     pub fn peek_expected(&self) -> Option<&[u8]> {
         self.buf.get(&self.expected_seq).map(Vec::as_slice)
     }
@@ -102,7 +160,7 @@ impl JitterBuffer {
 
 #[cfg(test)]
 mod tests {
-    use super::{JitterBuffer, PopResult};
+    use super::{JitterBuffer, JitterStats, PopResult};
 
     #[test]
     fn initializes_expected_seq_from_first_packet() {
@@ -163,4 +221,50 @@ mod tests {
         assert!(matches!(jitter.pop_ready(1_001, 40), PopResult::Missing));
         assert!(matches!(jitter.pop_ready(1_002, 40), PopResult::Frame(_)));
     }
+
+    #[test]
+    fn current_delay_counts_slots_up_to_the_newest_frame() {
+        let mut jitter = JitterBuffer::new(8);
+        assert_eq!(jitter.current_delay_ms(), 0);
+
+        jitter.set_expected(u32::MAX);
+        jitter.push(u32::MAX, vec![0]);
+        assert_eq!(jitter.current_delay_ms(), 20);
+
+        // The missing frame at seq 0 still holds up playout of seq 1.
+        jitter.set_frame_ms(40);
+        jitter.push(1, vec![1]);
+        assert_eq!(jitter.current_delay_ms(), 120);
+
+        assert!(matches!(jitter.pop_ready(1_000, 40), PopResult::Frame(_)));
+        assert_eq!(jitter.current_delay_ms(), 80);
+        assert!(matches!(jitter.pop_ready(1_000, 40), PopResult::Missing));
+        assert!(matches!(jitter.pop_ready(1_000, 40), PopResult::Frame(_)));
+        assert_eq!(jitter.current_delay_ms(), 0);
+    }
+
+    #[test]
+    fn stats_count_reordered_late_and_concealed_frames() {
+        let mut jitter = JitterBuffer::new(8);
+        jitter.set_expected(10);
+        jitter.push(10, vec![0]);
+        jitter.push(12, vec![2]);
+        jitter.push(11, vec![1]);
+        jitter.push(14, vec![4]);
+        for _ in 0..3 {
+            assert!(matches!(jitter.pop_ready(1_000, 40), PopResult::Frame(_)));
+        }
+        assert!(matches!(jitter.pop_ready(1_020, 40), PopResult::Missing));
+        jitter.push(9, vec![9]);
+
+        assert_eq!(
+            jitter.stats(),
+            JitterStats {
+                received: 5,
+                reordered: 1,
+                late_dropped: 1,
+                concealed: 1,
+            }
+        );
+    }
 }
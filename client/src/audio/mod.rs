@@ -3,10 +3,84 @@ pub mod dsp;
 pub mod jitter;
 pub mod opus;
 pub mod playout;
+pub mod probe;
 pub(crate) mod resample;
 #[cfg(target_os = "windows")]
 pub(crate) mod windows;
 
+/// Opus frame durations the voice pipeline supports, in preference order.
+pub const SUPPORTED_FRAME_MS: [u32; 3] = [20, 10, 40];
+pub const MAX_FRAME_MS: u32 = 40;
+
+/// Playout always opens in stereo so spatial voice placement can be toggled
+/// without reopening the device; mono audio is duplicated to both sides.
+pub const PLAYOUT_CHANNELS: u16 = 2;
+
+/// Opus bitrate cap in low-bandwidth mode: about the floor for intelligible
+/// wideband speech.
+pub const LOW_BANDWIDTH_BITRATE_BPS: u32 = 12_000;
+/// Shortest frame low-bandwidth mode allows; 10 ms frames double the
+/// per-packet overhead.
+pub const LOW_BANDWIDTH_MIN_FRAME_MS: u32 = 20;
+/// Band ceiling in low-bandwidth mode: at ~12 kbps wideband keeps speech
+/// clear where a wider band would spread the bits too thin.
+pub const LOW_BANDWIDTH_MAX_BANDWIDTH: opus::OpusBandwidth = opus::OpusBandwidth::Wideband;
+
+/// Capture/codec/mixer format for a voice session. Every stage derives its
+/// frame length from here instead of repeating the arithmetic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SessionAudioConfig {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub frame_ms: u32,
+}
+
+impl Default for SessionAudioConfig {
+    fn default() -> Self {
+        Self {
+            sample_rate: 48_000,
+            channels: 1,
+            frame_ms: SUPPORTED_FRAME_MS[0],
+        }
+    }
+}
+
+impl SessionAudioConfig {
+    /// Default format with `frame_ms` snapped to a supported duration.
+    pub fn with_frame_ms(frame_ms: u32) -> Self {
+        Self {
+            frame_ms: normalize_frame_ms(frame_ms),
+            ..Self::default()
+        }
+    }
+
+    /// Interleaved samples in one frame.
+    pub fn frame_samples(&self) -> usize {
+        (self.sample_rate as usize * self.frame_ms as usize / 1000) * self.channels as usize
+    }
+
+    pub fn frame_duration(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(u64::from(self.frame_ms))
+    }
+
+    /// `AudioCaps.frame_ms_preference`: the configured size first, then the
+    /// other sizes we can still decode.
+    pub fn frame_ms_preference(&self) -> Vec<u32> {
+        let mut out = vec![self.frame_ms];
+        out.extend(SUPPORTED_FRAME_MS.iter().filter(|ms| **ms != self.frame_ms));
+        out
+    }
+}
+
+/// Unsupported values fall back to the default 20 ms.
+pub fn normalize_frame_ms(frame_ms: u32) -> u32 {
+    if SUPPORTED_FRAME_MS.contains(&frame_ms) {
+        frame_ms
+    } else {
+        SUPPORTED_FRAME_MS[0]
+    }
+}
+
 pub(crate) fn pcm_peak_level(pcm: &[i16]) -> f32 {
     let peak = pcm
         .iter()
@@ -15,9 +89,19 @@ pub(crate) fn pcm_peak_level(pcm: &[i16]) -> f32 {
     peak.clamp(0.0, 1.0)
 }
 
+/// RMS level in dBFS; silence is negative infinity.
+pub(crate) fn pcm_rms_dbfs(pcm: &[i16]) -> f32 {
+    if pcm.is_empty() {
+        return f32::NEG_INFINITY;
+    }
+    let sum_sq: f64 = pcm.iter().map(|&s| (s as f64) * (s as f64)).sum();
+    let rms = (sum_sq / pcm.len() as f64).sqrt() / 32768.0;
+    20.0 * rms.log10() as f32
+}
+
 #[cfg(test)]
 mod tests {
-    use super::pcm_peak_level;
+    use super::{pcm_peak_level, pcm_rms_dbfs, SessionAudioConfig};
 
     #[test]
     fn pcm_peak_level_zero_input() {
@@ -33,4 +117,24 @@ mod tests {
     fn pcm_peak_level_i16_min_does_not_panic() {
         assert!((pcm_peak_level(&[i16::MIN]) - 1.0).abs() < 1e-6);
     }
+
+    #[test]
+    fn pcm_rms_dbfs_full_scale_square_is_zero_and_silence_floors() {
+        assert!(pcm_rms_dbfs(&[i16::MIN, i16::MIN]).abs() < 1e-3);
+        assert!((pcm_rms_dbfs(&[16384; 4]) + 6.02).abs() < 0.01);
+        assert_eq!(pcm_rms_dbfs(&[0; 8]), f32::NEG_INFINITY);
+        assert_eq!(vp_voice::loudness_from_dbfs(pcm_rms_dbfs(&[0; 8])), 0);
+    }
+
+    #[test]
+    fn session_audio_config_derives_frame_length() {
+        assert_eq!(SessionAudioConfig::with_frame_ms(10).frame_samples(), 480);
+        assert_eq!(SessionAudioConfig::with_frame_ms(20).frame_samples(), 960);
+        assert_eq!(SessionAudioConfig::with_frame_ms(40).frame_samples(), 1920);
+        assert_eq!(SessionAudioConfig::with_frame_ms(25).frame_ms, 20);
+        assert_eq!(
+            SessionAudioConfig::with_frame_ms(40).frame_ms_preference(),
+            vec![40, 20, 10]
+        );
+    }
 }
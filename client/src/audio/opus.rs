@@ -1,4 +1,26 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
+use std::ffi::CStr;
+use std::os::raw::c_int;
+use std::ptr::NonNull;
+
+// libopus encoder CTL request ids and values (opus_defines.h). The `opus`
+// crate wraps only a subset of the encoder CTLs, so the encoder talks to
+// libopus directly; the decoder still goes through the crate.
+const OPUS_OK: c_int = 0;
+const OPUS_APPLICATION_VOIP: c_int = 2048;
+const OPUS_APPLICATION_AUDIO: c_int = 2049;
+const OPUS_SET_BITRATE_REQUEST: c_int = 4002;
+const OPUS_SET_MAX_BANDWIDTH_REQUEST: c_int = 4004;
+const OPUS_SET_COMPLEXITY_REQUEST: c_int = 4010;
+const OPUS_SET_INBAND_FEC_REQUEST: c_int = 4012;
+const OPUS_SET_PACKET_LOSS_PERC_REQUEST: c_int = 4014;
+const OPUS_SET_DTX_REQUEST: c_int = 4016;
+const OPUS_RESET_STATE: c_int = 4028;
+const OPUS_GET_IN_DTX_REQUEST: c_int = 4049;
+
+/// With DTX on, an encoded frame this short carries no audio and needn't be
+/// sent; the decoder conceals the gap.
+pub const DTX_SKIPPABLE_FRAME_BYTES: usize = 2;
 
 #[derive(Debug, Clone, Copy)]
 pub enum OpusEncoderProfile {
@@ -6,59 +28,217 @@ pub enum OpusEncoderProfile {
     Music,
 }
 
+/// Widest audio band the encoder may use (`OPUS_SET_MAX_BANDWIDTH`). Opus
+/// still narrows on its own at low bitrates; this only sets the ceiling.
+/// Ordered narrowest first so caps combine with `min`.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Default,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub enum OpusBandwidth {
+    Narrowband,
+    Mediumband,
+    Wideband,
+    SuperWideband,
+    #[default]
+    Fullband,
+}
+
+impl OpusBandwidth {
+    pub const ALL: [OpusBandwidth; 5] = [
+        OpusBandwidth::Narrowband,
+        OpusBandwidth::Mediumband,
+        OpusBandwidth::Wideband,
+        OpusBandwidth::SuperWideband,
+        OpusBandwidth::Fullband,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            OpusBandwidth::Narrowband => "Narrowband (4 kHz)",
+            OpusBandwidth::Mediumband => "Mediumband (6 kHz)",
+            OpusBandwidth::Wideband => "Wideband (8 kHz)",
+            OpusBandwidth::SuperWideband => "Super-wideband (12 kHz)",
+            OpusBandwidth::Fullband => "Fullband (20 kHz)",
+        }
+    }
+
+    /// `OPUS_BANDWIDTH_*` value.
+    pub fn ctl_value(self) -> i32 {
+        match self {
+            OpusBandwidth::Narrowband => 1101,
+            OpusBandwidth::Mediumband => 1102,
+            OpusBandwidth::Wideband => 1103,
+            OpusBandwidth::SuperWideband => 1104,
+            OpusBandwidth::Fullband => 1105,
+        }
+    }
+
+    /// Inverse of [`Self::ctl_value`]; `None` for anything libopus would
+    /// reject, including `OPUS_AUTO`.
+    pub fn from_ctl_value(value: i32) -> Option<Self> {
+        Self::ALL.into_iter().find(|bw| bw.ctl_value() == value)
+    }
+}
+
 pub struct OpusEncoder {
-    enc: opus::Encoder,
+    st: NonNull<audiopus_sys::OpusEncoder>,
+    channels: usize,
     encoded_scratch: Vec<u8>,
 }
 
+// SAFETY: the libopus encoder state has no thread affinity and is only
+// reached through `&mut self`.
+unsafe impl Send for OpusEncoder {}
+
 pub struct OpusDecoder {
     dec: opus::Decoder,
     decoded_scratch: Vec<i16>,
 }
 
+fn opus_error(code: c_int) -> String {
+    // SAFETY: opus_strerror returns a static NUL-terminated string for any code.
+    unsafe { CStr::from_ptr(audiopus_sys::opus_strerror(code)) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn encode_raw(
+    st: NonNull<audiopus_sys::OpusEncoder>,
+    channels: usize,
+    pcm: &[i16],
+    out: &mut [u8],
+) -> Result<usize> {
+    let frame_size = (pcm.len() / channels) as c_int;
+    // SAFETY: `pcm` holds `frame_size * channels` samples and `out` is
+    // writable for the length passed; libopus validates the frame size.
+    let n = unsafe {
+        audiopus_sys::opus_encode(
+            st.as_ptr(),
+            pcm.as_ptr(),
+            frame_size,
+            out.as_mut_ptr(),
+            out.len().min(i32::MAX as usize) as i32,
+        )
+    };
+    if n < 0 {
+        bail!("opus_encode failed: {}", opus_error(n));
+    }
+    Ok(n as usize)
+}
+
 impl OpusEncoder {
     pub fn new(sample_rate: u32, channels: u8, profile: OpusEncoderProfile) -> Result<Self> {
-        let ch = if channels == 2 {
-            opus::Channels::Stereo
-        } else {
-            opus::Channels::Mono
-        };
+        let channels = if channels == 2 { 2 } else { 1 };
         let application = match profile {
-            OpusEncoderProfile::Voice => opus::Application::Voip,
-            OpusEncoderProfile::Music => opus::Application::Audio,
+            OpusEncoderProfile::Voice => OPUS_APPLICATION_VOIP,
+            OpusEncoderProfile::Music => OPUS_APPLICATION_AUDIO,
+        };
+        let mut err = OPUS_OK;
+        // SAFETY: all arguments are plain values; `err` outlives the call.
+        let st = unsafe {
+            audiopus_sys::opus_encoder_create(sample_rate as i32, channels, application, &mut err)
+        };
+        let Some(st) = NonNull::new(st).filter(|_| err == OPUS_OK) else {
+            bail!("opus_encoder_create failed: {}", opus_error(err));
         };
-        let enc = opus::Encoder::new(sample_rate, ch, application)?;
         Ok(Self {
-            enc,
+            st,
+            channels: channels as usize,
             encoded_scratch: vec![0u8; 4000],
         })
     }
 
     pub fn encode(&mut self, pcm: &[i16], out: &mut [u8]) -> Result<usize> {
-        Ok(self.enc.encode(pcm, out)?)
+        encode_raw(self.st, self.channels, pcm, out)
     }
 
     pub fn encode_reuse(&mut self, pcm: &[i16]) -> Result<&[u8]> {
-        let n = self.enc.encode(pcm, &mut self.encoded_scratch)?;
+        let n = encode_raw(self.st, self.channels, pcm, &mut self.encoded_scratch)?;
         Ok(&self.encoded_scratch[..n])
     }
 
-    pub fn set_bitrate(&mut self, bps: i32) -> Result<()> {
-        self.enc.set_bitrate(opus::Bitrate::Bits(bps.max(8_000)))?;
+    fn set_ctl(&mut self, request: c_int, value: c_int) -> Result<()> {
+        // SAFETY: every request used here is a SET taking one opus_int32.
+        let code = unsafe { audiopus_sys::opus_encoder_ctl(self.st.as_ptr(), request, value) };
+        if code != OPUS_OK {
+            bail!("opus_encoder_ctl({request}) failed: {}", opus_error(code));
+        }
         Ok(())
     }
 
+    pub fn set_bitrate(&mut self, bps: i32) -> Result<()> {
+        self.set_ctl(OPUS_SET_BITRATE_REQUEST, bps.max(8_000))
+    }
+
+    /// Discontinuous transmission: during silence the encoder emits
+    /// [`DTX_SKIPPABLE_FRAME_BYTES`]-sized frames plus a comfort-noise update
+    /// every 400 ms.
+    pub fn set_dtx(&mut self, enabled: bool) -> Result<()> {
+        self.set_ctl(OPUS_SET_DTX_REQUEST, c_int::from(enabled))
+    }
+
+    /// Whether the last encoded frame was silence under DTX: either one that
+    /// needn't be sent or a comfort-noise update. False when DTX is off.
+    pub fn in_dtx(&mut self) -> bool {
+        let mut in_dtx: c_int = 0;
+        // SAFETY: OPUS_GET_IN_DTX writes one opus_int32 through the pointer,
+        // which outlives the call.
+        let code = unsafe {
+            audiopus_sys::opus_encoder_ctl(
+                self.st.as_ptr(),
+                OPUS_GET_IN_DTX_REQUEST,
+                &mut in_dtx as *mut c_int,
+            )
+        };
+        code == OPUS_OK && in_dtx != 0
+    }
+
+    /// Encoder effort, 0 (cheapest) to 10 (best quality per bit).
+    pub fn set_complexity(&mut self, complexity: i32) -> Result<()> {
+        self.set_ctl(OPUS_SET_COMPLEXITY_REQUEST, complexity.clamp(0, 10))
+    }
+
+    pub fn set_max_bandwidth(&mut self, bandwidth: OpusBandwidth) -> Result<()> {
+        self.set_ctl(OPUS_SET_MAX_BANDWIDTH_REQUEST, bandwidth.ctl_value())
+    }
+
     pub fn set_inband_fec(&mut self, enabled: bool) -> Result<()> {
-        self.enc.set_inband_fec(enabled)?;
-        Ok(())
+        self.set_ctl(OPUS_SET_INBAND_FEC_REQUEST, c_int::from(enabled))
     }
 
     pub fn set_packet_loss_perc(&mut self, loss_perc: i32) -> Result<()> {
-        self.enc.set_packet_loss_perc(loss_perc.clamp(0, 100))?;
+        self.set_ctl(OPUS_SET_PACKET_LOSS_PERC_REQUEST, loss_perc.clamp(0, 100))
+    }
+
+    /// Clear the codec history (`OPUS_RESET_STATE`). Bitrate, FEC and
+    /// bandwidth settings are kept.
+    pub fn reset(&mut self) -> Result<()> {
+        // SAFETY: OPUS_RESET_STATE takes no argument.
+        let code = unsafe { audiopus_sys::opus_encoder_ctl(self.st.as_ptr(), OPUS_RESET_STATE) };
+        if code != OPUS_OK {
+            bail!("opus encoder reset failed: {}", opus_error(code));
+        }
         Ok(())
     }
 }
 
+impl Drop for OpusEncoder {
+    fn drop(&mut self) {
+        // SAFETY: `st` came from opus_encoder_create and is destroyed once.
+        unsafe { audiopus_sys::opus_encoder_destroy(self.st.as_ptr()) };
+    }
+}
+
 impl OpusDecoder {
     pub fn new(sample_rate: u32, channels: u8) -> Result<Self> {
         let ch = if channels == 2 {
@@ -69,7 +249,7 @@ impl OpusDecoder {
         let dec = opus::Decoder::new(sample_rate, ch)?;
         Ok(Self {
             dec,
-            decoded_scratch: vec![0i16; (sample_rate as usize * 20 / 1000) * channels as usize],
+            decoded_scratch: vec![0i16; (sample_rate as usize * 120 / 1000) * channels as usize],
         })
     }
 
@@ -89,4 +269,245 @@ impl OpusDecoder {
     pub fn decode_fec(&mut self, data: &[u8], pcm_out: &mut [i16]) -> Result<usize> {
         Ok(self.dec.decode(data, pcm_out, true)?)
     }
+
+    /// Clear the decoder history, as if the stream had just started.
+    pub fn reset(&mut self) -> Result<()> {
+        Ok(self.dec.reset_state()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        OpusBandwidth, OpusDecoder, OpusEncoder, OpusEncoderProfile, DTX_SKIPPABLE_FRAME_BYTES,
+    };
+
+    const RATE: u32 = 48_000;
+    /// Frames discarded at the start while the encoder converges.
+    const WARMUP_FRAMES: usize = 10;
+
+    fn sine(samples: usize, freq: f32, amplitude: f32) -> Vec<i16> {
+        (0..samples)
+            .map(|i| {
+                let t = i as f32 / RATE as f32;
+                ((t * freq * 2.0 * std::f32::consts::PI).sin() * amplitude * i16::MAX as f32) as i16
+            })
+            .collect()
+    }
+
+    fn energy(pcm: &[i16]) -> f64 {
+        pcm.iter().map(|&s| (s as f64).powi(2)).sum::<f64>() / pcm.len().max(1) as f64
+    }
+
+    /// Best normalized cross-correlation of `output` against `input` over the
+    /// codec's algorithmic delay.
+    fn best_correlation(input: &[i16], output: &[i16], max_lag: usize) -> f64 {
+        let len = input.len().min(output.len()).saturating_sub(max_lag);
+        (0..=max_lag)
+            .map(|lag| {
+                let a = &input[..len];
+                let b = &output[lag..lag + len];
+                let dot: f64 = a.iter().zip(b).map(|(&x, &y)| x as f64 * y as f64).sum();
+                let norm = (energy(a) * energy(b)).sqrt() * len as f64;
+                if norm == 0.0 {
+                    0.0
+                } else {
+                    dot / norm
+                }
+            })
+            .fold(f64::MIN, f64::max)
+    }
+
+    fn round_trip(frame_ms: usize, fec: bool) {
+        let frame = RATE as usize * frame_ms / 1000;
+        let frames = 50;
+        let input = sine(frame * frames, 440.0, 0.5);
+
+        let mut enc = OpusEncoder::new(RATE, 1, OpusEncoderProfile::Voice).unwrap();
+        enc.set_bitrate(32_000).unwrap();
+        enc.set_inband_fec(fec).unwrap();
+        enc.set_packet_loss_perc(if fec { 10 } else { 0 }).unwrap();
+        let mut dec = OpusDecoder::new(RATE, 1).unwrap();
+
+        let mut packets = Vec::with_capacity(frames);
+        let mut output = Vec::with_capacity(input.len());
+        for chunk in input.chunks_exact(frame) {
+            let packet = enc.encode_reuse(chunk).unwrap().to_vec();
+            assert!(!packet.is_empty());
+            let decoded = dec.decode_reuse(&packet).unwrap();
+            assert_eq!(
+                decoded.len(),
+                frame,
+                "{frame_ms}ms frame decoded to wrong length"
+            );
+            output.extend_from_slice(decoded);
+            packets.push(packet);
+        }
+        assert_eq!(output.len(), input.len());
+
+        let skip = frame * WARMUP_FRAMES;
+        let corr = best_correlation(&input[skip..], &output[skip..], RATE as usize / 100);
+        assert!(corr > 0.9, "{frame_ms}ms fec={fec}: correlation {corr:.3}");
+        let ratio = energy(&output[skip..]) / energy(&input[skip..]);
+        assert!(
+            (0.5..2.0).contains(&ratio),
+            "{frame_ms}ms fec={fec}: energy ratio {ratio:.3}"
+        );
+
+        if fec {
+            // Recover the previous frame from the redundancy in the next one.
+            let mut dec = OpusDecoder::new(RATE, 1).unwrap();
+            let mut pcm = vec![0i16; frame];
+            for packet in &packets[..WARMUP_FRAMES] {
+                dec.decode(packet, &mut pcm).unwrap();
+            }
+            let n = dec
+                .decode_fec(&packets[WARMUP_FRAMES + 1], &mut pcm)
+                .unwrap();
+            assert_eq!(n, frame);
+        }
+    }
+
+    #[test]
+    fn round_trip_20ms_mono() {
+        round_trip(20, false);
+    }
+
+    #[test]
+    fn round_trip_10ms_mono() {
+        round_trip(10, false);
+    }
+
+    #[test]
+    fn round_trip_20ms_mono_with_fec() {
+        round_trip(20, true);
+    }
+
+    #[test]
+    fn round_trip_10ms_mono_with_fec() {
+        round_trip(10, true);
+    }
+
+    #[test]
+    fn plc_fills_a_whole_frame() {
+        let frame = RATE as usize / 50;
+        let mut enc = OpusEncoder::new(RATE, 1, OpusEncoderProfile::Voice).unwrap();
+        let mut dec = OpusDecoder::new(RATE, 1).unwrap();
+        for chunk in sine(frame * 5, 440.0, 0.5).chunks_exact(frame) {
+            let packet = enc.encode_reuse(chunk).unwrap().to_vec();
+            dec.decode_reuse(&packet).unwrap();
+        }
+        let mut pcm = vec![0i16; frame];
+        assert_eq!(dec.decode_plc(&mut pcm).unwrap(), frame);
+    }
+
+    #[test]
+    fn reset_codecs_keep_working() {
+        let frame = RATE as usize / 50;
+        let mut enc = OpusEncoder::new(RATE, 1, OpusEncoderProfile::Voice).unwrap();
+        let mut dec = OpusDecoder::new(RATE, 1).unwrap();
+        let input = sine(frame * 4, 440.0, 0.5);
+        for (i, chunk) in input.chunks_exact(frame).enumerate() {
+            if i == 2 {
+                enc.reset().unwrap();
+                dec.reset().unwrap();
+            }
+            let packet = enc.encode_reuse(chunk).unwrap().to_vec();
+            assert_eq!(dec.decode_reuse(&packet).unwrap().len(), frame);
+        }
+    }
+
+    #[test]
+    fn bitrate_bounds_packet_size_at_any_complexity() {
+        let frame = RATE as usize / 50;
+        let input = sine(frame * 50, 440.0, 0.5);
+        for complexity in [0, 5, 10] {
+            let mut enc = OpusEncoder::new(RATE, 1, OpusEncoderProfile::Voice).unwrap();
+            enc.set_bitrate(16_000).unwrap();
+            enc.set_complexity(complexity).unwrap();
+            let bytes: usize = input
+                .chunks_exact(frame)
+                .map(|chunk| enc.encode_reuse(chunk).unwrap().len())
+                .sum();
+            // 16 kbps over 1 s, with slack for the encoder's rate control.
+            assert!(
+                bytes <= 16_000 / 8 * 5 / 4,
+                "complexity {complexity}: {bytes} bytes"
+            );
+        }
+    }
+
+    /// Bytes that go on the wire for 3 s of a quiet room after a short
+    /// phrase, leaving out frames DTX says needn't be sent.
+    fn transmitted_bytes_for_mostly_silence(dtx: bool) -> usize {
+        let frame = RATE as usize / 50;
+        let mut input = sine(frame * 15, 300.0, 0.4);
+        let mut rng = 0x1234_5678u32;
+        input.extend((0..frame * 135).map(|_| {
+            rng = rng.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            ((rng >> 16) as i16) >> 10
+        }));
+        let mut enc = OpusEncoder::new(RATE, 1, OpusEncoderProfile::Voice).unwrap();
+        enc.set_bitrate(32_000).unwrap();
+        enc.set_dtx(dtx).unwrap();
+        let mut dec = OpusDecoder::new(RATE, 1).unwrap();
+        let mut pcm = vec![0i16; frame];
+        let mut sent = 0;
+        for chunk in input.chunks_exact(frame) {
+            let packet = enc.encode_reuse(chunk).unwrap().to_vec();
+            if dtx && packet.len() <= DTX_SKIPPABLE_FRAME_BYTES {
+                assert!(enc.in_dtx());
+                // The receiver conceals the frames that weren't sent.
+                assert_eq!(dec.decode_plc(&mut pcm).unwrap(), frame);
+            } else {
+                sent += packet.len();
+                assert_eq!(dec.decode_reuse(&packet).unwrap().len(), frame);
+            }
+        }
+        sent
+    }
+
+    #[test]
+    fn dtx_sends_far_less_for_mostly_silent_capture() {
+        let without = transmitted_bytes_for_mostly_silence(false);
+        let with = transmitted_bytes_for_mostly_silence(true);
+        assert!(with * 3 < without, "dtx {with} bytes vs {without} without");
+    }
+
+    /// Decoded energy of a 6 kHz tone, which lies above the narrowband and
+    /// mediumband cutoffs but inside wideband.
+    fn tone_energy_with_cap(cap: OpusBandwidth) -> f64 {
+        let frame = RATE as usize / 50;
+        let input = sine(frame * 50, 6_000.0, 0.5);
+        let mut enc = OpusEncoder::new(RATE, 1, OpusEncoderProfile::Voice).unwrap();
+        enc.set_bitrate(32_000).unwrap();
+        enc.set_max_bandwidth(cap).unwrap();
+        let mut dec = OpusDecoder::new(RATE, 1).unwrap();
+        let mut output = Vec::with_capacity(input.len());
+        for chunk in input.chunks_exact(frame) {
+            let packet = enc.encode_reuse(chunk).unwrap().to_vec();
+            output.extend_from_slice(dec.decode_reuse(&packet).unwrap());
+        }
+        energy(&output[frame * WARMUP_FRAMES..])
+    }
+
+    #[test]
+    fn max_bandwidth_caps_encoded_band() {
+        let narrow = tone_energy_with_cap(OpusBandwidth::Narrowband);
+        let wide = tone_energy_with_cap(OpusBandwidth::Wideband);
+        assert!(
+            narrow < wide * 0.1,
+            "narrowband energy {narrow:.0} vs wideband {wide:.0}"
+        );
+    }
+
+    #[test]
+    fn bandwidth_ctl_values_round_trip_and_reject_unknown() {
+        for bw in OpusBandwidth::ALL {
+            assert_eq!(OpusBandwidth::from_ctl_value(bw.ctl_value()), Some(bw));
+        }
+        assert_eq!(OpusBandwidth::from_ctl_value(-1000), None);
+        assert_eq!(OpusBandwidth::from_ctl_value(1106), None);
+        assert!(OpusBandwidth::Wideband < OpusBandwidth::Fullband);
+    }
 }
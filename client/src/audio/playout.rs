@@ -1,11 +1,14 @@
+use std::collections::VecDeque;
+
 use anyhow::Result;
 use crossbeam_channel::Sender;
 use parking_lot::Mutex;
 use ringbuf::{
-    traits::{Producer, Split},
+    traits::{Consumer, Producer, Split},
     HeapProd, HeapRb,
 };
 
+use crate::audio::resample::{ResamplerImpl, ResamplerMode};
 use crate::ui::{
     model::{disambiguate_display_labels, AudioBackend, AudioDeviceId, AudioDeviceInfo},
     UiEvent,
@@ -14,6 +17,7 @@ use crate::ui::{
 pub struct Playout {
     backend: PlayoutBackend,
     prod: Mutex<HeapProd<i16>>,
+    channels: u16,
 }
 
 pub const PLAYBACK_MODE_AUTO: &str = "Automatically use best mode";
@@ -84,9 +88,16 @@ impl Playout {
         Ok(Self {
             backend,
             prod: Mutex::new(prod),
+            channels: channels.max(1),
         })
     }
 
+    /// Channels per frame the playout was opened with; `push_pcm` expects
+    /// samples interleaved at this width.
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
     pub fn push_pcm(&self, pcm: &[i16]) {
         let _ = &self.backend;
         let mut prod = self.prod.lock();
@@ -95,9 +106,25 @@ impl Playout {
         }
     }
 
+    /// Queue mono PCM, duplicating each sample across the playout channels.
+    pub fn push_mono(&self, pcm: &[i16]) {
+        let mut prod = self.prod.lock();
+        for &s in pcm {
+            for _ in 0..self.channels {
+                let _ = prod.try_push(s);
+            }
+        }
+    }
+
     pub fn is_healthy(&self) -> bool {
         self.backend.is_healthy()
     }
+
+    /// Halt the device stream ahead of drop so shutdown doesn't race a
+    /// live callback. Idempotent; pushed PCM is simply never played.
+    pub fn stop(&self) {
+        self.backend.stop();
+    }
 }
 
 pub fn enumerate_output_devices() -> Vec<AudioDeviceInfo> {
@@ -133,6 +160,172 @@ fn cpal_backend() -> AudioBackend {
     AudioBackend::Unknown
 }
 
+/// Device-callback side of the playout ring, shared by every backend.
+///
+/// Pops engine frames (mono, or interleaved stereo when the mixer places
+/// talkers spatially), resamples each side to the device rate and hands out
+/// `(left, right)` frames laid out for however many channels the device has.
+pub(crate) struct PlayoutFeed {
+    source_rate: u32,
+    target_rate: u32,
+    source_channels: usize,
+    mode: ResamplerMode,
+    /// One resampler per side; `None` while the device runs at the engine rate.
+    resamplers: Option<[ResamplerImpl; 2]>,
+    in_left: Vec<f32>,
+    in_right: Vec<f32>,
+    out_left: Vec<f32>,
+    out_right: Vec<f32>,
+    fifo: VecDeque<(f32, f32)>,
+}
+
+impl PlayoutFeed {
+    /// Bound on refill rounds per callback when the resampler is still
+    /// priming and returns nothing.
+    const MAX_REFILL_ROUNDS: usize = 16;
+
+    pub fn new(
+        source_rate: u32,
+        target_rate: u32,
+        source_channels: u16,
+        mode: ResamplerMode,
+    ) -> Self {
+        let mut feed = Self {
+            source_rate,
+            target_rate,
+            source_channels: source_channels.max(1) as usize,
+            mode,
+            resamplers: None,
+            in_left: Vec::new(),
+            in_right: Vec::new(),
+            out_left: Vec::new(),
+            out_right: Vec::new(),
+            fifo: VecDeque::new(),
+        };
+        feed.set_target_rate(target_rate);
+        feed
+    }
+
+    /// Follow a device rate change; drops audio already resampled for the old rate.
+    pub fn set_target_rate(&mut self, target_rate: u32) {
+        self.target_rate = target_rate.max(1);
+        self.resamplers = (self.target_rate != self.source_rate).then(|| {
+            [
+                ResamplerImpl::new(self.source_rate, self.target_rate, 1, self.mode),
+                ResamplerImpl::new(self.source_rate, self.target_rate, 1, self.mode),
+            ]
+        });
+        self.fifo.clear();
+    }
+
+    pub fn source_channels(&self) -> usize {
+        self.source_channels
+    }
+
+    /// Pull from `cons` until `frames` device frames are queued or the
+    /// resampler stops producing, and return how many are queued. A drained
+    /// ring reads as silence.
+    pub fn fill(&mut self, cons: &mut impl Consumer<Item = i16>, frames: usize) -> usize {
+        let mut rounds = 0;
+        while self.fifo.len() < frames && rounds < Self::MAX_REFILL_ROUNDS {
+            rounds += 1;
+            let in_needed = needed_input_frames_for_output(
+                self.source_rate,
+                self.target_rate,
+                frames - self.fifo.len(),
+                2,
+            );
+
+            self.in_left.clear();
+            self.in_right.clear();
+            for _ in 0..in_needed {
+                let left = pop_sample(cons);
+                let right = if self.source_channels >= 2 {
+                    pop_sample(cons)
+                } else {
+                    left
+                };
+                for _ in 2..self.source_channels {
+                    let _ = cons.try_pop();
+                }
+                self.in_left.push(left);
+                self.in_right.push(right);
+            }
+
+            self.out_left.clear();
+            self.out_right.clear();
+            match self.resamplers.as_mut() {
+                Some([left, right]) => {
+                    left.process_mono(&self.in_left, &mut self.out_left);
+                    right.process_mono(&self.in_right, &mut self.out_right);
+                }
+                None => {
+                    self.out_left.extend_from_slice(&self.in_left);
+                    self.out_right.extend_from_slice(&self.in_right);
+                }
+            }
+            if self.out_left.is_empty() {
+                break;
+            }
+            self.fifo.extend(
+                self.out_left
+                    .iter()
+                    .copied()
+                    .zip(self.out_right.iter().copied()),
+            );
+        }
+        self.fifo.len()
+    }
+
+    /// Next `(left, right)` frame; silence once the queue runs dry.
+    pub fn next_frame(&mut self) -> (f32, f32) {
+        self.fifo.pop_front().unwrap_or((0.0, 0.0))
+    }
+
+    /// Write the next frame into one interleaved device frame.
+    pub fn write_frame<T>(&mut self, frame: &mut [T], convert: impl Fn(f32) -> T) {
+        let next = self.next_frame();
+        let channels = frame.len();
+        for (channel, out) in frame.iter_mut().enumerate() {
+            *out = convert(device_sample(next, channel, channels).clamp(-1.0, 1.0));
+        }
+    }
+}
+
+/// Engine frames to pop so the resampler can produce `missing_out_frames`
+/// device frames, plus `slack` to cover its rounding.
+pub(crate) fn needed_input_frames_for_output(
+    src_rate: u32,
+    dst_rate: u32,
+    missing_out_frames: usize,
+    slack: usize,
+) -> usize {
+    if missing_out_frames == 0 {
+        return 0;
+    }
+
+    let ratio = src_rate as f64 / dst_rate as f64;
+    ((missing_out_frames as f64) * ratio).ceil() as usize + slack
+}
+
+fn pop_sample(cons: &mut impl Consumer<Item = i16>) -> f32 {
+    cons.try_pop()
+        .map(|s| s as f32 / i16::MAX as f32)
+        .unwrap_or(0.0)
+}
+
+/// Sample for `channel` of a `channels`-wide device frame: left and right go
+/// to the first two channels, mono devices and any extra channels get the
+/// downmix.
+pub(crate) fn device_sample((left, right): (f32, f32), channel: usize, channels: usize) -> f32 {
+    match (channels, channel) {
+        (0 | 1, _) => (left + right) * 0.5,
+        (_, 0) => left,
+        (_, 1) => right,
+        _ => (left + right) * 0.5,
+    }
+}
+
 #[cfg(target_os = "linux")]
 mod linux {
     use anyhow::{anyhow, Context, Result};
@@ -142,14 +335,14 @@ mod linux {
     use crossbeam_channel::Sender;
     use pipewire as pw;
     use pw::properties::properties;
-    use ringbuf::{traits::Consumer, HeapCons};
-    use std::collections::VecDeque;
+    use ringbuf::HeapCons;
     use std::sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
     };
     use tracing::info;
 
+    use super::PlayoutFeed;
     use crate::ui::{
         model::{AudioBackend, AudioDeviceId, AudioDeviceInfo, AudioDirection},
         UiEvent,
@@ -158,13 +351,8 @@ mod linux {
     struct PipeWirePlayoutState {
         format: pw::spa::param::audio::AudioInfoRaw,
         engine_rate: u32,
-        engine_channels: u16,
-        sink_rate: u32,
         sink_channels: u32,
-        resampler: Option<ResamplerImpl>,
-        in_mono: Vec<f32>,
-        out_mono_tmp: Vec<f32>,
-        out_fifo: VecDeque<f32>,
+        feed: PlayoutFeed,
         log_once: bool,
         resampler_mode: ResamplerMode,
     }
@@ -177,7 +365,8 @@ mod linux {
     const PULSE_FALLBACK_PLAYOUT_LATENCY_MS: u32 = 60;
 
     pub struct LinuxPlayout {
-        _thread: Option<std::thread::JoinHandle<()>>,
+        thread: Option<std::thread::JoinHandle<()>>,
+        stop: Arc<AtomicBool>,
         backend: LinuxPlayoutBackend,
     }
 
@@ -202,6 +391,8 @@ mod linux {
                 let tx_event_thread = tx_event.clone();
                 let reported = Arc::new(AtomicBool::new(false));
                 let reported_thread = reported.clone();
+                let stop = Arc::new(AtomicBool::new(false));
+                let stop_thread = stop.clone();
                 let thread = std::thread::Builder::new()
                     .name("tsod-pipewire-playout".to_string())
                     .spawn(move || {
@@ -210,6 +401,7 @@ mod linux {
                             channels,
                             cons,
                             preferred_device_owned,
+                            stop_thread,
                         ) {
                             eprintln!("pipewire playout thread failed: {e:#}");
                             if reported_thread
@@ -227,7 +419,8 @@ mod linux {
                     .context("spawn PipeWire playout thread")?;
 
                 return Ok(Self {
-                    _thread: Some(thread),
+                    thread: Some(thread),
+                    stop,
                     backend: LinuxPlayoutBackend::PipeWire,
                 });
             }
@@ -246,7 +439,8 @@ mod linux {
             let pulse =
                 CpalPlayout::start(sample_rate, channels, cons, preferred_device, tx_event)?;
             Ok(Self {
-                _thread: None,
+                thread: None,
+                stop: Arc::new(AtomicBool::new(false)),
                 backend: LinuxPlayoutBackend::Pulse(pulse),
             })
         }
@@ -275,6 +469,22 @@ mod linux {
                 LinuxPlayoutBackend::Pulse(cpal) => cpal.is_healthy(),
             }
         }
+
+        pub fn stop(&self) {
+            self.stop.store(true, Ordering::Relaxed);
+            if let LinuxPlayoutBackend::Pulse(cpal) = &self.backend {
+                cpal.stop();
+            }
+        }
+    }
+
+    impl Drop for LinuxPlayout {
+        fn drop(&mut self) {
+            self.stop.store(true, Ordering::Relaxed);
+            if let Some(thread) = self.thread.take() {
+                let _ = thread.join();
+            }
+        }
     }
 
     fn pipewire_is_available() -> bool {
@@ -294,6 +504,7 @@ mod linux {
         channels: u16,
         mut cons: HeapCons<i16>,
         preferred_device: Option<String>,
+        stop: Arc<AtomicBool>,
     ) -> Result<()> {
         pw::init();
 
@@ -321,19 +532,15 @@ mod linux {
             .context("create PipeWire playout stream")?;
 
         let requested_format = pw::spa::param::audio::AudioFormat::S16LE;
+        let resampler_mode = ResamplerMode::from_env();
         let listener = stream
             .add_local_listener_with_user_data(PipeWirePlayoutState {
                 format: pw::spa::param::audio::AudioInfoRaw::new(),
                 engine_rate: sample_rate,
-                engine_channels: channels,
-                sink_rate: sample_rate,
                 sink_channels: channels as u32,
-                resampler: None,
-                in_mono: Vec::new(),
-                out_mono_tmp: Vec::new(),
-                out_fifo: VecDeque::new(),
+                feed: PlayoutFeed::new(sample_rate, sample_rate, channels, resampler_mode),
                 log_once: false,
-                resampler_mode: ResamplerMode::from_env(),
+                resampler_mode,
             })
             .param_changed(move |_, state, id, param| {
                 if id != pw::spa::param::ParamType::Format.as_raw() {
@@ -357,7 +564,7 @@ mod linux {
                         state.resampler_mode.as_str(),
                         state.engine_rate,
                         negotiated_rate,
-                        1,
+                        state.feed.source_channels(),
                         negotiated_format
                     );
                     state.log_once = true;
@@ -371,19 +578,8 @@ mod linux {
                     );
                 }
 
-                let old_rate = state.sink_rate;
-                state.sink_rate = negotiated_rate;
                 state.sink_channels = negotiated_channels;
-                state.out_fifo.clear();
-                state.out_mono_tmp.clear();
-                if negotiated_rate != old_rate {
-                    state.in_mono.clear();
-                }
-                state.resampler = if negotiated_rate != state.engine_rate {
-                    Some(ResamplerImpl::new(state.engine_rate, negotiated_rate, 1, state.resampler_mode))
-                } else {
-                    None
-                };
+                state.feed.set_target_rate(negotiated_rate);
             })
             .process({
                 move |stream: &pw::stream::Stream, state: &mut PipeWirePlayoutState| {
@@ -411,48 +607,11 @@ mod linux {
                     };
 
                     let frames_needed = out.len() / sink_channels;
-                    while state.out_fifo.len() < frames_needed {
-                        let remaining = frames_needed - state.out_fifo.len();
-                        let in_needed = ((remaining as u64 * state.engine_rate as u64)
-                            .div_ceil(state.sink_rate.max(1) as u64)
-                            + 2) as usize;
-
-                        state.in_mono.clear();
-                        state.in_mono.reserve(in_needed);
-                        let engine_channels = state.engine_channels.max(1) as usize;
-                        for _ in 0..in_needed {
-                            let sample = cons
-                                .try_pop()
-                                .map(|v| v as f32 / i16::MAX as f32)
-                                .unwrap_or(0.0);
-                            for _ in 1..engine_channels {
-                                let _ = cons.try_pop();
-                            }
-                            state.in_mono.push(sample);
-                        }
-
-                        state.out_mono_tmp.clear();
-                        if let Some(resampler) = state.resampler.as_mut() {
-                            resampler.process_mono(&state.in_mono, &mut state.out_mono_tmp);
-                        } else {
-                            state.out_mono_tmp.extend_from_slice(&state.in_mono);
-                        }
-
-                        if state.out_mono_tmp.is_empty() && in_needed == 0 {
-                            break;
-                        }
-                        state.out_fifo.extend(state.out_mono_tmp.iter().copied());
-                        if state.out_mono_tmp.is_empty() {
-                            break;
-                        }
-                    }
-
+                    state.feed.fill(&mut cons, frames_needed);
                     for frame in out.chunks_mut(sink_channels) {
-                        let sample = state.out_fifo.pop_front().unwrap_or(0.0);
-                        let v = (sample.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16;
-                        for o in frame.iter_mut() {
-                            *o = v;
-                        }
+                        state
+                            .feed
+                            .write_frame(frame, |s| (s * i16::MAX as f32).round() as i16);
                     }
                 }
             })
@@ -493,7 +652,11 @@ mod linux {
             .context("connect PipeWire playout stream")?;
 
         let _listener = listener;
-        mainloop.run();
+        while !stop.load(Ordering::Relaxed) {
+            let _ = mainloop
+                .loop_()
+                .iterate(std::time::Duration::from_millis(100));
+        }
         Ok(())
     }
 
@@ -554,12 +717,12 @@ mod linux {
         devices
     }
 
-    use crate::audio::resample::{ResamplerImpl, ResamplerMode};
+    use crate::audio::resample::ResamplerMode;
     #[cfg(target_os = "windows")]
     use crate::audio::windows::mmdevice;
 
     struct CpalPlayout {
-        _stream: cpal::Stream,
+        stream: cpal::Stream,
         unhealthy: Arc<AtomicBool>,
     }
 
@@ -702,10 +865,7 @@ mod linux {
                 other => return Err(anyhow!("unsupported output sample format: {other:?}")),
             };
             stream.play()?;
-            Ok(Self {
-                _stream: stream,
-                unhealthy,
-            })
+            Ok(Self { stream, unhealthy })
         }
 
         fn enumerate_output_devices() -> Vec<AudioDeviceInfo> {
@@ -718,6 +878,10 @@ mod linux {
         fn is_healthy(&self) -> bool {
             !self.unhealthy.load(Ordering::Relaxed)
         }
+
+        fn stop(&self) {
+            let _ = self.stream.pause();
+        }
     }
 
     fn device_label(device: &cpal::Device) -> Option<String> {
@@ -846,55 +1010,22 @@ mod linux {
     {
         let target_rate = stream_cfg.sample_rate;
         let target_channels = stream_cfg.channels.max(1) as usize;
-        let source_channels = source_channels.max(1) as usize;
         let resampler_mode = ResamplerMode::from_env();
+        let mut feed = PlayoutFeed::new(source_rate, target_rate, source_channels, resampler_mode);
         tracing::info!(
-            "[audio] cpal playout resampler={} in_rate={} out_rate={} channels=1",
+            "[audio] cpal playout resampler={} in_rate={} out_rate={} channels={}",
             resampler_mode.as_str(),
             source_rate,
-            target_rate
+            target_rate,
+            feed.source_channels()
         );
-        let mut resampler = ResamplerImpl::new(source_rate, target_rate, 1, resampler_mode);
-        let mut source_mono = Vec::<f32>::new();
-        let mut source_resampled = Vec::<f32>::new();
-        let mut out_fifo = VecDeque::<f32>::new();
 
         dev.build_output_stream(
             stream_cfg,
             move |data: &mut [T], _| {
-                let frames_needed = data.len() / target_channels;
-                while out_fifo.len() < frames_needed {
-                    let remaining = frames_needed - out_fifo.len();
-                    let in_needed = ((remaining as u64 * source_rate as u64)
-                        .div_ceil(target_rate.max(1) as u64)
-                        + 2) as usize;
-                    source_mono.clear();
-                    source_mono.reserve(in_needed);
-                    for _ in 0..in_needed {
-                        let sample = cons
-                            .try_pop()
-                            .map(|s| s as f32 / i16::MAX as f32)
-                            .unwrap_or(0.0);
-                        for _ in 1..source_channels {
-                            let _ = cons.try_pop();
-                        }
-                        source_mono.push(sample);
-                    }
-
-                    source_resampled.clear();
-                    resampler.process_mono(&source_mono, &mut source_resampled);
-                    if source_resampled.is_empty() {
-                        break;
-                    }
-                    out_fifo.extend(source_resampled.iter().copied());
-                }
-
+                feed.fill(&mut cons, data.len() / target_channels);
                 for frame in data.chunks_mut(target_channels) {
-                    let s = out_fifo.pop_front().unwrap_or(0.0);
-                    let out = T::from_sample(s.clamp(-1.0, 1.0));
-                    for ch in frame {
-                        *ch = out;
-                    }
+                    feed.write_frame(frame, T::from_sample);
                 }
             },
             move |err| {
@@ -924,14 +1055,15 @@ mod non_linux {
         traits::DeviceTrait, traits::HostTrait, traits::StreamTrait, FromSample, SizedSample,
     };
     use crossbeam_channel::Sender;
-    use ringbuf::{traits::Consumer, HeapCons};
+    use ringbuf::HeapCons;
     use std::sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
     };
     use tracing::{debug, info, warn};
 
-    use crate::audio::resample::{ResamplerImpl, ResamplerMode};
+    use super::PlayoutFeed;
+    use crate::audio::resample::ResamplerMode;
     #[cfg(target_os = "windows")]
     use crate::audio::windows::mmdevice;
     use crate::ui::{
@@ -940,7 +1072,7 @@ mod non_linux {
     };
 
     pub struct CpalPlayout {
-        _stream: cpal::Stream,
+        stream: cpal::Stream,
         unhealthy: Arc<AtomicBool>,
     }
 
@@ -1078,10 +1210,7 @@ mod non_linux {
                 other => return Err(anyhow!("unsupported output sample format: {other:?}")),
             };
             stream.play()?;
-            Ok(Self {
-                _stream: stream,
-                unhealthy,
-            })
+            Ok(Self { stream, unhealthy })
         }
 
         pub fn enumerate_output_devices() -> Vec<AudioDeviceInfo> {
@@ -1104,6 +1233,10 @@ mod non_linux {
             !self.unhealthy.load(Ordering::Relaxed)
         }
 
+        pub fn stop(&self) {
+            let _ = self.stream.pause();
+        }
+
         pub fn enumerate_playback_modes() -> Vec<String> {
             vec![
                 super::PLAYBACK_MODE_AUTO.to_string(),
@@ -1221,51 +1354,23 @@ mod non_linux {
     {
         let target_rate = stream_cfg.sample_rate;
         let target_channels = stream_cfg.channels.max(1) as usize;
-        let source_channels = source_channels.max(1) as usize;
 
         let resampler_mode = ResamplerMode::from_env();
+        let mut feed = PlayoutFeed::new(source_rate, target_rate, source_channels, resampler_mode);
         tracing::info!(
-            "[audio] cpal playout resampler={} in_rate={} out_rate={} channels=1",
+            "[audio] cpal playout resampler={} in_rate={} out_rate={} channels={}",
             resampler_mode.as_str(),
             source_rate,
-            target_rate
+            target_rate,
+            feed.source_channels()
         );
-        let mut resampler = ResamplerImpl::new(source_rate, target_rate, 1, resampler_mode);
-        let mut source_mono = Vec::<f32>::new();
-        let mut resampled = Vec::<f32>::new();
 
         dev.build_output_stream(
             stream_cfg,
             move |out: &mut [T], _| {
-                source_mono.clear();
-                let needed_mono = out.len().div_ceil(target_channels);
-
-                for _ in 0..needed_mono {
-                    let mut sum = 0.0f32;
-                    let mut count = 0usize;
-                    for _ in 0..source_channels {
-                        match cons.try_pop() {
-                            Some(sample) => {
-                                sum += sample as f32 / i16::MAX as f32;
-                                count += 1;
-                            }
-                            None => break,
-                        }
-                    }
-                    source_mono.push(if count == 0 { 0.0 } else { sum / count as f32 });
-                }
-
-                resampled.clear();
-                resampler.process_mono(&source_mono, &mut resampled);
-
-                let mut idx = 0usize;
+                feed.fill(&mut cons, out.len().div_ceil(target_channels));
                 for frame in out.chunks_mut(target_channels) {
-                    let sample = resampled.get(idx).copied().unwrap_or(0.0);
-                    idx += 1;
-                    let converted = T::from_sample(sample.clamp(-1.0, 1.0));
-                    for o in frame.iter_mut() {
-                        *o = converted;
-                    }
+                    feed.write_frame(frame, T::from_sample);
                 }
             },
             move |err| {
@@ -1287,3 +1392,41 @@ mod non_linux {
         .context("build output stream")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn needed_input_frames_covers_rate_ratio_with_slack() {
+        let src = 48_000;
+        let dst = 44_100;
+        let missing_out = 441usize;
+        let slack = 10usize;
+        let need_in = needed_input_frames_for_output(src, dst, missing_out, slack);
+        let min_needed = ((missing_out as f64) * (src as f64 / dst as f64)).ceil() as usize;
+
+        assert!(need_in >= min_needed);
+        assert!(need_in >= 480 + slack);
+    }
+
+    #[test]
+    fn feed_keeps_stereo_sides_and_downmixes_for_mono_devices() {
+        let rb = HeapRb::<i16>::new(64);
+        let (mut prod, mut cons) = rb.split();
+        for s in [i16::MAX, 0, i16::MAX, 0] {
+            let _ = prod.try_push(s);
+        }
+
+        let mut feed = PlayoutFeed::new(48_000, 48_000, 2, ResamplerMode::Linear);
+        assert!(feed.fill(&mut cons, 2) >= 2);
+
+        let mut stereo = [0.0f32; 3];
+        feed.write_frame(&mut stereo, |s| s);
+        assert_eq!(stereo, [1.0, 0.0, 0.5]);
+
+        let mut mono = [0.0f32; 1];
+        feed.write_frame(&mut mono, |s| s);
+        assert_eq!(mono, [0.5]);
+    }
+}
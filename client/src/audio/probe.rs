@@ -0,0 +1,156 @@
+use cpal::traits::{DeviceTrait, HostTrait};
+
+use crate::ui::model::{AudioDeviceId, AudioDirection};
+
+/// Sample rate the voice pipeline runs at; devices that can't open at this
+/// rate go through the resampler.
+pub const NATIVE_SAMPLE_RATE: u32 = 48_000;
+
+/// What a device reports it can open, merged across sample formats.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DeviceCapabilities {
+    /// Inclusive `(min, max)` sample-rate ranges, sorted and non-overlapping.
+    pub sample_rates: Vec<(u32, u32)>,
+    /// Supported channel counts, sorted.
+    pub channels: Vec<u16>,
+}
+
+impl DeviceCapabilities {
+    /// Build from `(channels, min_rate, max_rate)` triples as cpal reports them.
+    pub fn from_ranges(ranges: impl IntoIterator<Item = (u16, u32, u32)>) -> Self {
+        let mut channels = Vec::new();
+        let mut rates: Vec<(u32, u32)> = Vec::new();
+        for (ch, min, max) in ranges {
+            if !channels.contains(&ch) {
+                channels.push(ch);
+            }
+            rates.push((min.min(max), min.max(max)));
+        }
+        channels.sort_unstable();
+        rates.sort_unstable();
+
+        let mut sample_rates: Vec<(u32, u32)> = Vec::with_capacity(rates.len());
+        for (min, max) in rates {
+            match sample_rates.last_mut() {
+                Some(last) if min <= last.1.saturating_add(1) => last.1 = last.1.max(max),
+                _ => sample_rates.push((min, max)),
+            }
+        }
+        Self {
+            sample_rates,
+            channels,
+        }
+    }
+
+    pub fn supports_rate(&self, rate: u32) -> bool {
+        self.sample_rates
+            .iter()
+            .any(|(min, max)| (*min..=*max).contains(&rate))
+    }
+
+    /// False when capture/playout at 48 kHz would need the resampler.
+    pub fn supports_native_rate(&self) -> bool {
+        self.supports_rate(NATIVE_SAMPLE_RATE)
+    }
+
+    /// One-line summary for the settings panel, e.g. `44.1–48 kHz · 1, 2 ch`.
+    pub fn summary(&self) -> String {
+        if self.sample_rates.is_empty() {
+            return "no supported configurations reported".to_string();
+        }
+        let rates = self
+            .sample_rates
+            .iter()
+            .map(|(min, max)| {
+                if min == max {
+                    format_khz(*min)
+                } else {
+                    format!("{}–{}", format_khz_value(*min), format_khz(*max))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let channels = self
+            .channels
+            .iter()
+            .map(|ch| ch.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("{rates} · {channels} ch")
+    }
+}
+
+fn format_khz_value(rate: u32) -> String {
+    let khz = format!("{:.2}", rate as f64 / 1000.0);
+    khz.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+fn format_khz(rate: u32) -> String {
+    format!("{} kHz", format_khz_value(rate))
+}
+
+/// Ask cpal which configurations `device` supports.
+///
+/// Returns `None` when the device can't be resolved through cpal, e.g. a
+/// PipeWire node id, or when the driver refuses to list its configurations.
+pub fn probe_device(device: &AudioDeviceId) -> Option<DeviceCapabilities> {
+    let host = cpal::default_host();
+    let dev = resolve_device(&host, device)?;
+    let ranges: Vec<(u16, u32, u32)> = match device.direction {
+        AudioDirection::Input => dev
+            .supported_input_configs()
+            .ok()?
+            .map(|c| (c.channels(), c.min_sample_rate(), c.max_sample_rate()))
+            .collect(),
+        AudioDirection::Output => dev
+            .supported_output_configs()
+            .ok()?
+            .map(|c| (c.channels(), c.min_sample_rate(), c.max_sample_rate()))
+            .collect(),
+    };
+    Some(DeviceCapabilities::from_ranges(ranges))
+}
+
+fn resolve_device(host: &cpal::Host, device: &AudioDeviceId) -> Option<cpal::Device> {
+    if device.is_default() {
+        return match device.direction {
+            AudioDirection::Input => host.default_input_device(),
+            AudioDirection::Output => host.default_output_device(),
+        };
+    }
+    if let Ok(device_id) = device.id.parse::<cpal::DeviceId>() {
+        if let Some(dev) = host.device_by_id(&device_id) {
+            return Some(dev);
+        }
+    }
+    let mut devices = match device.direction {
+        AudioDirection::Input => host.input_devices().ok()?,
+        AudioDirection::Output => host.output_devices().ok()?,
+    };
+    devices.find(|d| d.id().is_ok_and(|id| id.to_string() == device.id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges_merge_and_flag_missing_48k() {
+        let caps = DeviceCapabilities::from_ranges([
+            (2, 44_100, 44_100),
+            (1, 44_100, 44_100),
+            (2, 8_000, 16_000),
+            (2, 16_000, 22_050),
+        ]);
+        assert_eq!(caps.sample_rates, vec![(8_000, 22_050), (44_100, 44_100)]);
+        assert_eq!(caps.channels, vec![1, 2]);
+        assert!(!caps.supports_native_rate());
+        assert_eq!(caps.summary(), "8–22.05 kHz, 44.1 kHz · 1, 2 ch");
+
+        let caps = DeviceCapabilities::from_ranges([(2, 44_100, 96_000)]);
+        assert!(caps.supports_native_rate());
+        assert!(DeviceCapabilities::default()
+            .summary()
+            .contains("no supported"));
+    }
+}
@@ -1,6 +1,6 @@
 use anyhow::{anyhow, Context, Result};
 use crossbeam_channel::Sender;
-use ringbuf::{traits::Consumer, HeapCons};
+use ringbuf::HeapCons;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
     Arc,
@@ -9,7 +9,10 @@ use tracing::{debug, error, info};
 use wasapi::{BufferFlags, Direction, SampleType, StreamMode};
 
 use crate::{
-    audio::resample::{ResamplerImpl, ResamplerMode},
+    audio::{
+        playout::{device_sample, PlayoutFeed},
+        resample::ResamplerMode,
+    },
     ui::{
         model::{AudioBackend, AudioDeviceId, AudioDeviceInfo, AudioDirection},
         UiEvent,
@@ -120,6 +123,10 @@ impl WasapiPlayout {
     pub fn is_healthy(&self) -> bool {
         !self.unhealthy.load(Ordering::Relaxed)
     }
+
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
 }
 
 impl Drop for WasapiPlayout {
@@ -238,21 +245,17 @@ fn run_playout_thread(
         .context("start WASAPI render stream")?;
 
     let resampler_mode = ResamplerMode::from_env();
+    let mut feed = PlayoutFeed::new(sample_rate, device_rate, channels, resampler_mode);
     tracing::info!(
-        "[audio] wasapi playout resampler={} in_rate={} out_rate={} channels=1",
+        "[audio] wasapi playout resampler={} in_rate={} out_rate={} channels={}",
         resampler_mode.as_str(),
         sample_rate,
-        device_rate
+        device_rate,
+        feed.source_channels()
     );
-    let mut resampler = ResamplerImpl::new(sample_rate, device_rate, 1, resampler_mode);
-    let source_channels = channels.max(1) as usize;
     let mut consecutive_timeouts = 0u32;
-    let mut source_mono = Vec::<f32>::new();
-    let mut scratch_resampled = Vec::<f32>::new();
-    let mut render_mono = Vec::<f32>::new();
+    let mut render_frames = Vec::<(f32, f32)>::new();
     let mut bytes = Vec::<u8>::new();
-    let mut out_fifo = Vec::<f32>::new();
-    let mut out_off = 0usize;
     let mut padded_out_frames_total = 0usize;
     let mut last_write_instant = std::time::Instant::now();
     let mut last_stats_log = std::time::Instant::now();
@@ -288,38 +291,12 @@ fn run_playout_thread(
 
         consecutive_timeouts = 0;
 
-        let mut refill_loops = 0usize;
-        while output_fifo_len(&out_fifo, out_off) < avail {
-            let missing_out_frames = avail - output_fifo_len(&out_fifo, out_off);
-            let need_in =
-                needed_input_frames_for_output(sample_rate, device_rate, missing_out_frames, 10);
-
-            fill_source_mono(&mut cons, &mut source_mono, need_in, source_channels);
-            scratch_resampled.clear();
-            resampler.process_mono(&source_mono, &mut scratch_resampled);
-            out_fifo.extend_from_slice(&scratch_resampled);
-
-            refill_loops += 1;
-            if refill_loops >= 16 {
-                break;
-            }
-        }
-
-        render_mono.clear();
-        let available = output_fifo_len(&out_fifo, out_off);
-        let to_copy = available.min(avail);
-        render_mono.extend_from_slice(&out_fifo[out_off..out_off + to_copy]);
-        out_off += to_copy;
-        if to_copy < avail {
-            let pad = avail - to_copy;
-            render_mono.resize(avail, 0.0);
-            padded_out_frames_total = padded_out_frames_total.saturating_add(pad);
-        }
-
-        if out_off > 8192 {
-            out_fifo.drain(..out_off);
-            out_off = 0;
+        let queued = feed.fill(&mut cons, avail);
+        if queued < avail {
+            padded_out_frames_total = padded_out_frames_total.saturating_add(avail - queued);
         }
+        render_frames.clear();
+        render_frames.extend((0..avail).map(|_| feed.next_frame()));
 
         bytes.resize(avail * block_align, 0);
         let flags = write_render_bytes(
@@ -328,7 +305,7 @@ fn run_playout_thread(
             block_align,
             device_channels,
             sample_type,
-            &render_mono,
+            &render_frames,
             effective_valid_bits,
         )?;
 
@@ -354,54 +331,16 @@ fn run_playout_thread(
     Ok(())
 }
 
-fn output_fifo_len(fifo: &[f32], out_off: usize) -> usize {
-    fifo.len().saturating_sub(out_off)
-}
-
-fn needed_input_frames_for_output(
-    src_rate: u32,
-    dst_rate: u32,
-    missing_out_frames: usize,
-    slack: usize,
-) -> usize {
-    if missing_out_frames == 0 {
-        return 0;
-    }
-
-    let ratio = src_rate as f64 / dst_rate as f64;
-    ((missing_out_frames as f64) * ratio).ceil() as usize + slack
-}
-
-fn fill_source_mono(
-    cons: &mut HeapCons<i16>,
-    out: &mut Vec<f32>,
-    frames: usize,
-    source_channels: usize,
-) {
-    out.clear();
-    out.resize(frames, 0.0);
-
-    for sample in out.iter_mut().take(frames) {
-        *sample = cons
-            .try_pop()
-            .map(|s| s as f32 / i16::MAX as f32)
-            .unwrap_or(0.0);
-        for _ in 1..source_channels {
-            let _ = cons.try_pop();
-        }
-    }
-}
-
 fn write_render_bytes(
     dst: &mut [u8],
     frames: usize,
     block_align: usize,
     channels: usize,
     sample_type: SampleType,
-    mono: &[f32],
+    render: &[(f32, f32)],
     valid_bits: u16,
 ) -> Result<BufferFlags> {
-    if mono.is_empty() {
+    if render.is_empty() {
         return Ok(BufferFlags {
             silent: true,
             ..BufferFlags::none()
@@ -416,6 +355,10 @@ fn write_render_bytes(
 
     let bytes_per_sample = block_align / channels;
     let scale = int_scale(valid_bits);
+    let sample_at = |frame_idx: usize, ch: usize| {
+        let frame = render.get(frame_idx).copied().unwrap_or((0.0, 0.0));
+        device_sample(frame, ch, channels)
+    };
 
     match sample_type {
         SampleType::Float => {
@@ -426,11 +369,10 @@ fn write_render_bytes(
             }
 
             for (frame_idx, frame) in dst.chunks_exact_mut(block_align).take(frames).enumerate() {
-                let sample = mono.get(frame_idx).copied().unwrap_or(0.0).clamp(-1.0, 1.0);
-                let encoded = sample.to_le_bytes();
                 for ch in 0..channels {
+                    let sample = sample_at(frame_idx, ch).clamp(-1.0, 1.0);
                     let base = ch * bytes_per_sample;
-                    frame[base..base + 4].copy_from_slice(&encoded);
+                    frame[base..base + 4].copy_from_slice(&sample.to_le_bytes());
                 }
             }
         }
@@ -438,21 +380,18 @@ fn write_render_bytes(
             2 => {
                 for (frame_idx, frame) in dst.chunks_exact_mut(block_align).take(frames).enumerate()
                 {
-                    let sample =
-                        scale_to_i32(mono.get(frame_idx).copied().unwrap_or(0.0), scale) as i16;
-                    let encoded = sample.to_le_bytes();
                     for ch in 0..channels {
+                        let sample = scale_to_i32(sample_at(frame_idx, ch), scale) as i16;
                         let base = ch * bytes_per_sample;
-                        frame[base..base + 2].copy_from_slice(&encoded);
+                        frame[base..base + 2].copy_from_slice(&sample.to_le_bytes());
                     }
                 }
             }
             3 => {
                 for (frame_idx, frame) in dst.chunks_exact_mut(block_align).take(frames).enumerate()
                 {
-                    let sample = scale_to_i32(mono.get(frame_idx).copied().unwrap_or(0.0), scale);
-                    let encoded = sample.to_le_bytes();
                     for ch in 0..channels {
+                        let encoded = scale_to_i32(sample_at(frame_idx, ch), scale).to_le_bytes();
                         let base = ch * bytes_per_sample;
                         frame[base] = encoded[0];
                         frame[base + 1] = encoded[1];
@@ -463,16 +402,14 @@ fn write_render_bytes(
             4 => {
                 for (frame_idx, frame) in dst.chunks_exact_mut(block_align).take(frames).enumerate()
                 {
-                    let mut sample =
-                        scale_to_i32(mono.get(frame_idx).copied().unwrap_or(0.0), scale);
-                    if valid_bits > 0 && valid_bits < 32 {
-                        let shift = 32 - valid_bits;
-                        sample <<= shift;
-                    }
-                    let encoded = sample.to_le_bytes();
                     for ch in 0..channels {
+                        let mut sample = scale_to_i32(sample_at(frame_idx, ch), scale);
+                        if valid_bits > 0 && valid_bits < 32 {
+                            let shift = 32 - valid_bits;
+                            sample <<= shift;
+                        }
                         let base = ch * bytes_per_sample;
-                        frame[base..base + 4].copy_from_slice(&encoded);
+                        frame[base..base + 4].copy_from_slice(&sample.to_le_bytes());
                     }
                 }
             }
@@ -503,9 +440,9 @@ mod wasapi_format_tests {
     #[test]
     fn packs_24bit_in_32bit_container_with_high_bit_alignment() {
         let mut dst = vec![0u8; 4];
-        let mono = [1.0f32];
+        let render = [(1.0f32, 1.0f32)];
 
-        let _ = write_render_bytes(&mut dst, 1, 4, 1, SampleType::Int, &mono, 24)
+        let _ = write_render_bytes(&mut dst, 1, 4, 1, SampleType::Int, &render, 24)
             .expect("write should succeed");
 
         let sample = i32::from_le_bytes([dst[0], dst[1], dst[2], dst[3]]);
@@ -516,21 +453,3 @@ mod wasapi_format_tests {
 fn scale_to_i32(sample: f32, scale: f32) -> i32 {
     (sample.clamp(-1.0, 1.0) * scale).round() as i32
 }
-
-#[cfg(test)]
-mod tests {
-    use super::needed_input_frames_for_output;
-
-    #[test]
-    fn needed_input_frames_covers_rate_ratio_with_slack() {
-        let src = 48_000;
-        let dst = 44_100;
-        let missing_out = 441usize;
-        let slack = 10usize;
-        let need_in = needed_input_frames_for_output(src, dst, missing_out, slack);
-        let min_needed = ((missing_out as f64) * (src as f64 / dst as f64)).ceil() as usize;
-
-        assert!(need_in >= min_needed);
-        assert!(need_in >= 480 + slack);
-    }
-}
@@ -42,9 +42,15 @@ pub struct Config {
     #[arg(long)]
     pub no_agc: bool,
 
-    /// VAD threshold (0.0 = very sensitive, 1.0 = very strict).
-    #[arg(long, default_value_t = 0.5)]
-    pub vad_threshold: f32,
+    /// VAD threshold (0.0 = very sensitive, 1.0 = very strict); overrides
+    /// the saved setting for this run.
+    #[arg(long)]
+    pub vad_threshold: Option<f32>,
+
+    /// DEBUG ONLY: artificially impair voice datagrams, e.g.
+    /// "loss=5,delay=40,jitter=20,reorder=2,dir=both". Off when unset.
+    #[arg(long, env = "VP_DEBUG_VOICE_IMPAIRMENT")]
+    pub debug_voice_impairment: Option<String>,
 }
 
 impl Config {
@@ -78,6 +78,20 @@ impl DeviceIdentity {
         })
     }
 
+    /// A throwaway identity that never touches the identity file.
+    #[cfg(test)]
+    pub fn ephemeral() -> Result<Self> {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+            .map_err(|_| anyhow!("failed to generate device keypair"))?;
+        let key = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref())
+            .map_err(|_| anyhow!("failed to decode generated keypair"))?;
+        Ok(Self {
+            device_id: uuid::Uuid::new_v4().to_string(),
+            public_key: key.public_key().as_ref().to_vec(),
+            pkcs8: pkcs8.as_ref().to_vec(),
+        })
+    }
+
     pub fn sign_challenge(&self, challenge: &[u8], session_id: &str) -> Result<Vec<u8>> {
         let key = Ed25519KeyPair::from_pkcs8(&self.pkcs8)
             .map_err(|_| anyhow!("invalid device key material"))?;
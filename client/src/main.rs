@@ -26,28 +26,32 @@ mod updater;
 
 use activity::ActivityRuntimeSettings;
 use anyhow::{anyhow, Context, Result};
+use audio::opus::OpusBandwidth;
 use bytes::Bytes;
 use config::Config;
 use crossbeam_channel::{bounded, Receiver, Sender, TrySendError};
 use identity::DeviceIdentity;
 use media_codec::DecodeMetadata;
-use net::dispatcher::{ControlDispatcher, PushEvent};
+use net::dispatcher::{AuthInfo, ControlDispatcher, PushEvent, ServerError};
 use net::egress::EgressScheduler;
+use net::impairment::{
+    Direction as ImpairDirection, ImpairmentSpec, Verdict as ImpairVerdict, VoiceImpairment,
+};
 use net::overwrite_queue::{pop_voice_realtime, OverwriteQueue, StampedBytes};
 use net::video_datagram::VideoHeader;
 use net::video_transport::{VideoReceiver, VideoStreamProfile};
 use net::voice_datagram::{
-    make_voice_datagram, VOICE_FORWARDED_HDR_LEN, VOICE_HDR_LEN, VOICE_VERSION,
+    make_multi_frame_voice_datagram, make_voice_datagram, with_whisper_targets, VOICE_HDR_LEN,
 };
 use proto::voiceplatform::v1 as pb;
 use screen_share::policy::layer_selection::{
     select_active_share_layer, ViewerLayerSelectionPolicy, ViewerLayerSignals,
 };
 use screen_share::policy::recovery::{RecoveryPolicyConfig, ViewerRecoveryPolicy};
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::path::{Path, PathBuf};
 use std::sync::{
-    atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicU8, Ordering},
+    atomic::{AtomicBool, AtomicI32, AtomicU32, AtomicU64, AtomicU8, Ordering},
     Arc,
 };
 #[cfg(debug_assertions)]
@@ -56,8 +60,11 @@ use tokio::sync::{mpsc, watch, Mutex, RwLock};
 use tokio::time::{sleep, Duration, Instant, MissedTickBehavior};
 use tracing::{debug, info, warn, Level};
 use tracing_subscriber::EnvFilter;
-use ui::model::AudioDeviceId;
-use ui::model::{AttachmentAsset, DspMethod, FecMode, PerUserAudioSettings, ShareSourceSelection};
+use ui::model::{
+    AttachmentAsset, DspMethod, FecMode, NetworkRobustness, PerUserAudioSettings,
+    ShareSourceSelection,
+};
+use ui::model::{AudioDeviceId, AudioDeviceInfo};
 use ui::{UiEvent, UiIntent, VpApp};
 
 #[cfg(debug_assertions)]
@@ -69,6 +76,15 @@ pub const BUILD_VERSION: &str = env!("VP_CLIENT_BUILD_VERSION");
 const VOICE_INGRESS_CAP: usize = 16; // Do not increase without justification; latency risk.
 const VOICE_MAX_AGE: Duration = Duration::from_millis(250);
 const VOICE_DRAIN_KEEP_LATEST: usize = 4;
+/// Budget for pausing streams and draining audio tasks on quit.
+const AUDIO_SHUTDOWN_TIMEOUT: Duration = Duration::from_millis(500);
+/// How long the GUI thread waits for the backend to finish tearing down
+/// before the process exits regardless.
+const BACKEND_SHUTDOWN_GRACE: Duration = Duration::from_secs(2);
+/// Messages per chat history page; the server caps it at 100.
+const CHAT_HISTORY_PAGE_SIZE: u32 = 50;
+/// How often the telemetry panel is refreshed.
+const TELEMETRY_INTERVAL: Duration = Duration::from_millis(500);
 
 #[derive(Debug, Clone)]
 struct PttState {
@@ -112,14 +128,38 @@ fn map_share_selection(selection: ShareSourceSelection) -> ShareSource {
 struct AudioRuntimeSettings {
     output_auto_level: Arc<AtomicBool>,
     mono_expansion: Arc<AtomicBool>,
+    spatial_audio: Arc<AtomicBool>,
     comfort_noise: Arc<AtomicBool>,
     comfort_noise_level: Arc<AtomicU32>,
     ducking_enabled: Arc<AtomicBool>,
     ducking_attenuation_db: Arc<AtomicU32>,
     typing_attenuation: Arc<AtomicBool>,
     denoise_attenuation_db: Arc<AtomicU32>,
+    vad_hold_ms: Arc<AtomicU32>,
     fec_mode: Arc<AtomicU32>,
     fec_strength: Arc<AtomicU32>,
+    network_robustness: Arc<AtomicU32>,
+    voice_frames_per_datagram: Arc<AtomicU8>,
+    frame_ms: Arc<AtomicU32>,
+    low_bandwidth: Arc<AtomicBool>,
+    /// `OpusBandwidth::ctl_value` of the user's band ceiling.
+    opus_max_bandwidth: Arc<AtomicI32>,
+    opus_complexity: Arc<AtomicU8>,
+    opus_dtx: Arc<AtomicBool>,
+    /// The user's voice bitrate limit in bps; 0 follows the channel.
+    voice_bitrate_limit_bps: Arc<AtomicU32>,
+    /// `ServerHint::max_voice_bitrate_bps` from the connected server; 0 when
+    /// it has not capped voice. Per connection, not a setting.
+    server_voice_bitrate_cap_bps: Arc<AtomicU32>,
+    /// Whether the connected server advertises `VoiceLoudness`, i.e. accepts
+    /// the loudness byte in voice headers. Set per connection, not a setting.
+    voice_loudness: Arc<AtomicBool>,
+    /// Whether the connected server advertises `Whisper`. Per connection,
+    /// like `voice_loudness`.
+    voice_whisper: Arc<AtomicBool>,
+    /// `vp_voice::whisper_target_hash` of each user our voice is whispered
+    /// to; empty for ordinary channel voice.
+    whisper_targets: Arc<StdMutex<Vec<u32>>>,
 }
 
 impl AudioRuntimeSettings {
@@ -127,6 +167,7 @@ impl AudioRuntimeSettings {
         Self {
             output_auto_level: Arc::new(AtomicBool::new(settings.output_auto_level)),
             mono_expansion: Arc::new(AtomicBool::new(settings.mono_expansion)),
+            spatial_audio: Arc::new(AtomicBool::new(settings.spatial_audio)),
             comfort_noise: Arc::new(AtomicBool::new(settings.comfort_noise)),
             comfort_noise_level: Arc::new(AtomicU32::new(f32_to_u32(settings.comfort_noise_level))),
             ducking_enabled: Arc::new(AtomicBool::new(settings.ducking_enabled)),
@@ -137,8 +178,23 @@ impl AudioRuntimeSettings {
             denoise_attenuation_db: Arc::new(AtomicU32::new(f32_to_u32(
                 settings.denoise_attenuation_db as f32,
             ))),
+            vad_hold_ms: Arc::new(AtomicU32::new(settings.vad_hold_ms)),
             fec_mode: Arc::new(AtomicU32::new(settings.fec_mode as u32)),
             fec_strength: Arc::new(AtomicU32::new(settings.fec_strength as u32)),
+            network_robustness: Arc::new(AtomicU32::new(settings.network_robustness as u32)),
+            voice_frames_per_datagram: Arc::new(AtomicU8::new(settings.voice_frames_per_datagram)),
+            frame_ms: Arc::new(AtomicU32::new(audio::normalize_frame_ms(settings.frame_ms))),
+            low_bandwidth: Arc::new(AtomicBool::new(settings.low_bandwidth_mode)),
+            opus_max_bandwidth: Arc::new(AtomicI32::new(settings.opus_max_bandwidth.ctl_value())),
+            opus_complexity: Arc::new(AtomicU8::new(settings.opus_complexity)),
+            opus_dtx: Arc::new(AtomicBool::new(settings.opus_dtx)),
+            voice_bitrate_limit_bps: Arc::new(AtomicU32::new(
+                settings.voice_bitrate_limit_kbps.saturating_mul(1000),
+            )),
+            server_voice_bitrate_cap_bps: Arc::new(AtomicU32::new(0)),
+            voice_loudness: Arc::new(AtomicBool::new(false)),
+            voice_whisper: Arc::new(AtomicBool::new(false)),
+            whisper_targets: Arc::new(StdMutex::new(Vec::new())),
         }
     }
 
@@ -147,6 +203,8 @@ impl AudioRuntimeSettings {
             .store(settings.output_auto_level, Ordering::Relaxed);
         self.mono_expansion
             .store(settings.mono_expansion, Ordering::Relaxed);
+        self.spatial_audio
+            .store(settings.spatial_audio, Ordering::Relaxed);
         self.comfort_noise
             .store(settings.comfort_noise, Ordering::Relaxed);
         self.comfort_noise_level
@@ -163,10 +221,106 @@ impl AudioRuntimeSettings {
             f32_to_u32(settings.denoise_attenuation_db as f32),
             Ordering::Relaxed,
         );
+        self.vad_hold_ms
+            .store(settings.vad_hold_ms, Ordering::Relaxed);
         self.fec_mode
             .store(settings.fec_mode as u32, Ordering::Relaxed);
         self.fec_strength
             .store(settings.fec_strength as u32, Ordering::Relaxed);
+        self.network_robustness
+            .store(settings.network_robustness as u32, Ordering::Relaxed);
+        self.voice_frames_per_datagram
+            .store(settings.voice_frames_per_datagram, Ordering::Relaxed);
+        self.frame_ms.store(
+            audio::normalize_frame_ms(settings.frame_ms),
+            Ordering::Relaxed,
+        );
+        self.low_bandwidth
+            .store(settings.low_bandwidth_mode, Ordering::Relaxed);
+        self.opus_max_bandwidth
+            .store(settings.opus_max_bandwidth.ctl_value(), Ordering::Relaxed);
+        self.opus_complexity
+            .store(settings.opus_complexity, Ordering::Relaxed);
+        self.opus_dtx.store(settings.opus_dtx, Ordering::Relaxed);
+        self.voice_bitrate_limit_bps.store(
+            settings.voice_bitrate_limit_kbps.saturating_mul(1000),
+            Ordering::Relaxed,
+        );
+    }
+
+    fn opus_complexity(&self) -> i32 {
+        i32::from(self.opus_complexity.load(Ordering::Relaxed).min(10))
+    }
+
+    /// The tighter of the user's limit and the server's cap, if either is set.
+    fn voice_bitrate_cap_bps(&self) -> Option<i32> {
+        [
+            self.voice_bitrate_limit_bps.load(Ordering::Relaxed),
+            self.server_voice_bitrate_cap_bps.load(Ordering::Relaxed),
+        ]
+        .into_iter()
+        .filter(|&bps| bps != 0)
+        .min()
+        .map(|bps| bps.min(i32::MAX as u32) as i32)
+    }
+
+    fn capped_voice_bitrate(&self, bps: i32) -> i32 {
+        self.voice_bitrate_cap_bps().map_or(bps, |cap| bps.min(cap))
+    }
+
+    fn network_robustness(&self) -> NetworkRobustness {
+        match self.network_robustness.load(Ordering::Relaxed) {
+            0 => NetworkRobustness::Low,
+            2 => NetworkRobustness::High,
+            _ => NetworkRobustness::Medium,
+        }
+    }
+
+    fn voice_frames_per_datagram(&self) -> usize {
+        (self.voice_frames_per_datagram.load(Ordering::Relaxed) as usize)
+            .clamp(1, vp_voice::MAX_COALESCED_FRAMES)
+    }
+
+    /// The single source of truth for the session's frame size; the send and
+    /// receive loops pick up changes on their next tick.
+    fn session_audio(&self) -> audio::SessionAudioConfig {
+        let mut frame_ms = self.frame_ms.load(Ordering::Relaxed);
+        if self.low_bandwidth() {
+            frame_ms = frame_ms.max(audio::LOW_BANDWIDTH_MIN_FRAME_MS);
+        }
+        audio::SessionAudioConfig::with_frame_ms(frame_ms)
+    }
+
+    fn low_bandwidth(&self) -> bool {
+        self.low_bandwidth.load(Ordering::Relaxed)
+    }
+
+    /// Trailer targets for outgoing voice, `None` when not whispering.
+    fn whisper_targets(&self) -> Option<Vec<u32>> {
+        let targets = self.whisper_targets.lock().ok()?;
+        (!targets.is_empty()).then(|| targets.clone())
+    }
+
+    /// Replace the whisper targets; returns whether they changed.
+    fn set_whisper_targets(&self, targets: Vec<u32>) -> bool {
+        let Ok(mut current) = self.whisper_targets.lock() else {
+            return false;
+        };
+        let changed = *current != targets;
+        *current = targets;
+        changed
+    }
+
+    /// Narrowest of the user's ceiling, the robustness profile's and, in
+    /// low-bandwidth mode, [`audio::LOW_BANDWIDTH_MAX_BANDWIDTH`].
+    fn max_bandwidth(&self) -> OpusBandwidth {
+        let user = OpusBandwidth::from_ctl_value(self.opus_max_bandwidth.load(Ordering::Relaxed))
+            .unwrap_or_default();
+        let mut cap = user.min(robustness_params(self.network_robustness()).max_bandwidth);
+        if self.low_bandwidth() {
+            cap = cap.min(audio::LOW_BANDWIDTH_MAX_BANDWIDTH);
+        }
+        cap
     }
 }
 
@@ -177,10 +331,16 @@ struct VoiceTelemetryCounters {
     rx_packets: AtomicU64,
     rx_bytes: AtomicU64,
     late_packets: AtomicU64,
+    reordered_packets: AtomicU64,
     lost_packets: AtomicU64,
     concealment_frames: AtomicU64,
     tx_oversized_payload_drops: AtomicU64,
+    encode_errors: AtomicU64,
+    decode_errors: AtomicU64,
+    codec_resets: AtomicU64,
     jitter_buffer_depth: AtomicU64,
+    /// Worst inter-arrival jitter across inbound streams, in ms.
+    jitter_ms: AtomicU32,
     peak_stream_level_bits: AtomicU32,
     playout_delay_ms: AtomicU32,
 }
@@ -188,8 +348,13 @@ struct VoiceTelemetryCounters {
 #[derive(Default)]
 struct SharedNetworkTelemetry {
     rtt_ms: AtomicU32,
+    /// Loss on inbound voice, as the jitter buffer sees it.
     loss_ppm: AtomicU32,
     jitter_ms: AtomicU32,
+    /// Share of our own QUIC packets the transport declared lost. Unlike
+    /// `loss_ppm` this covers the uplink, and it is measured even while
+    /// nobody else talks.
+    transport_loss_ppm: AtomicU32,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -222,6 +387,58 @@ impl NetworkClass {
     }
 }
 
+/// Encoder bundle behind the user-facing network robustness setting.
+///
+/// `inband_fec: None` defers to the FEC mode / network-class decision; the
+/// loss floor only applies while FEC is on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct RobustnessParams {
+    inband_fec: Option<bool>,
+    min_packet_loss_perc: i32,
+    bitrate_scale: f32,
+    max_bandwidth: OpusBandwidth,
+}
+
+fn robustness_params(profile: NetworkRobustness) -> RobustnessParams {
+    match profile {
+        NetworkRobustness::Low => RobustnessParams {
+            inband_fec: Some(false),
+            min_packet_loss_perc: 0,
+            bitrate_scale: 1.0,
+            max_bandwidth: OpusBandwidth::Fullband,
+        },
+        NetworkRobustness::Medium => RobustnessParams {
+            inband_fec: None,
+            min_packet_loss_perc: 0,
+            bitrate_scale: 1.0,
+            max_bandwidth: OpusBandwidth::Fullband,
+        },
+        // FEC and the lower bitrate leave fewer bits for the signal itself;
+        // dropping the top band keeps speech from sounding smeared.
+        NetworkRobustness::High => RobustnessParams {
+            inband_fec: Some(true),
+            min_packet_loss_perc: 25,
+            bitrate_scale: 0.8,
+            max_bandwidth: OpusBandwidth::SuperWideband,
+        },
+    }
+}
+
+impl RobustnessParams {
+    fn resolve_fec(self, enable_fec: bool, packet_loss_perc: i32) -> (bool, i32) {
+        let enable_fec = self.inband_fec.unwrap_or(enable_fec);
+        if enable_fec {
+            (true, packet_loss_perc.max(self.min_packet_loss_perc))
+        } else {
+            (false, 0)
+        }
+    }
+
+    fn scale_bitrate(self, bitrate_bps: i32) -> i32 {
+        ((bitrate_bps as f32 * self.bitrate_scale) as i32).max(16_000)
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 struct NetworkSample {
     rtt_ms: u32,
@@ -638,11 +855,17 @@ fn is_music_channel(mode: ChannelAudioMode) -> bool {
     ) || mode.bitrate_bps >= 160_000
 }
 
+/// Adaptive hold time for a missing frame before it is concealed.
+///
+/// Tracks EWMAs of inter-arrival jitter and lateness per stream and steers
+/// the wait toward `MIN_WAIT_MS + 2 * jitter + late`, clamped to the
+/// current cap, so stable links play out quickly and jittery ones buffer more.
 #[derive(Debug)]
 struct MissingWaitController {
     ewma_late_ms: f32,
     ewma_jitter_ms: f32,
     missing_wait_ms: f32,
+    max_wait_ms: f32,
     last_adjust_log_ms: u64,
     last_logged_wait_ms: f32,
     last_arrival_ms: Option<u64>,
@@ -652,6 +875,9 @@ struct MissingWaitController {
 impl MissingWaitController {
     const MIN_WAIT_MS: f32 = 40.0;
     const MAX_WAIT_MS: f32 = 200.0;
+    /// Cap in low-bandwidth mode: conceal a late frame rather than let
+    /// playout latency grow on a link that is already slow.
+    const LOW_BANDWIDTH_MAX_WAIT_MS: f32 = 80.0;
     const ADJUST_ALPHA: f32 = 0.05;
 
     fn new() -> Self {
@@ -659,6 +885,7 @@ impl MissingWaitController {
             ewma_late_ms: 0.0,
             ewma_jitter_ms: 0.0,
             missing_wait_ms: Self::MIN_WAIT_MS,
+            max_wait_ms: Self::MAX_WAIT_MS,
             last_adjust_log_ms: 0,
             last_logged_wait_ms: Self::MIN_WAIT_MS,
             last_arrival_ms: None,
@@ -689,9 +916,18 @@ impl MissingWaitController {
         self.update_missing_wait(now_ms);
     }
 
+    fn set_low_bandwidth(&mut self, low_bandwidth: bool) {
+        self.max_wait_ms = if low_bandwidth {
+            Self::LOW_BANDWIDTH_MAX_WAIT_MS
+        } else {
+            Self::MAX_WAIT_MS
+        };
+        self.missing_wait_ms = self.missing_wait_ms.min(self.max_wait_ms);
+    }
+
     fn update_missing_wait(&mut self, now_ms: u64) {
         let target = (Self::MIN_WAIT_MS + 2.0 * self.ewma_jitter_ms + self.ewma_late_ms)
-            .clamp(Self::MIN_WAIT_MS, Self::MAX_WAIT_MS);
+            .clamp(Self::MIN_WAIT_MS, self.max_wait_ms);
         let prev = self.missing_wait_ms;
         self.missing_wait_ms = prev + (target - prev) * Self::ADJUST_ALPHA;
         if (self.missing_wait_ms - self.last_logged_wait_ms).abs() >= 20.0
@@ -711,7 +947,43 @@ impl MissingWaitController {
     fn missing_wait_ms(&self) -> u64 {
         self.missing_wait_ms.round() as u64
     }
+
+    fn jitter_ms(&self) -> u32 {
+        self.ewma_jitter_ms.round() as u32
+    }
+}
+/// Consecutive encode/decode failures on one codec before its state is reset.
+const CODEC_ERROR_RESET_THRESHOLD: u32 = 25;
+
+/// Tracks a run of failures from one Opus encoder or decoder so a codec stuck
+/// in a bad state is reset instead of dropping every frame in silence.
+#[derive(Default)]
+struct CodecErrorTracker {
+    consecutive: u32,
+}
+
+impl CodecErrorTracker {
+    /// Record a failure. Returns true when the run reaches
+    /// [`CODEC_ERROR_RESET_THRESHOLD`]; the run then starts over.
+    fn record_error(&mut self) -> bool {
+        self.consecutive += 1;
+        if self.consecutive >= CODEC_ERROR_RESET_THRESHOLD {
+            self.consecutive = 0;
+            return true;
+        }
+        false
+    }
+
+    fn record_ok(&mut self) {
+        self.consecutive = 0;
+    }
+
+    /// The last recorded failure started a new run; worth logging.
+    fn run_started(&self) -> bool {
+        self.consecutive == 1
+    }
 }
+
 impl VoiceTelemetryCounters {
     fn observe_peak_stream_level(&self, level: f32) {
         let mut current = self.peak_stream_level_bits.load(Ordering::Relaxed);
@@ -743,17 +1015,24 @@ fn apply_fec_encoder_settings(
         _ => FecMode::Auto,
     };
     let fec_strength = audio_runtime.fec_strength.load(Ordering::Relaxed).min(100) as i32;
-    let enable_fec = fec_mode != FecMode::Off;
     let packet_loss = match fec_mode {
         FecMode::Off => 0,
         FecMode::Auto => fec_strength.clamp(10, 40),
         FecMode::On => fec_strength,
     };
+    let robustness = audio_runtime.network_robustness();
+    let (enable_fec, packet_loss) =
+        robustness_params(robustness).resolve_fec(fec_mode != FecMode::Off, packet_loss);
+    let max_bandwidth = audio_runtime.max_bandwidth();
     encoder.set_inband_fec(enable_fec)?;
     encoder.set_packet_loss_perc(packet_loss)?;
+    // Also runs right after an encoder is rebuilt, which resets every CTL.
+    encoder.set_max_bandwidth(max_bandwidth)?;
+    encoder.set_complexity(audio_runtime.opus_complexity())?;
+    encoder.set_dtx(audio_runtime.opus_dtx.load(Ordering::Relaxed))?;
     info!(
-        "[audio] set fec={:?} strength={} encoder_inband_fec={} packet_loss_perc={}",
-        fec_mode, fec_strength, enable_fec, packet_loss
+        "[audio] set fec={:?} strength={} robustness={:?} encoder_inband_fec={} packet_loss_perc={} max_bandwidth={:?}",
+        fec_mode, fec_strength, robustness, enable_fec, packet_loss, max_bandwidth
     );
     Ok(())
 }
@@ -762,25 +1041,63 @@ fn apply_network_class_encoder_settings(
     encoder: &mut audio::opus::OpusEncoder,
     class: NetworkClass,
     channel_bitrate_bps: u32,
+    robustness: NetworkRobustness,
+    low_bandwidth: bool,
+    max_bandwidth: OpusBandwidth,
+    bitrate_cap_bps: Option<i32>,
 ) -> Result<()> {
-    let bitrate = class.opus_target_bitrate_bps(channel_bitrate_bps);
-    let (enable_fec, loss_perc) = class.encoder_fec_params();
+    let params = robustness_params(robustness);
+    let mut bitrate = params.scale_bitrate(class.opus_target_bitrate_bps(channel_bitrate_bps));
+    if low_bandwidth {
+        bitrate = bitrate.min(audio::LOW_BANDWIDTH_BITRATE_BPS as i32);
+    }
+    if let Some(cap) = bitrate_cap_bps {
+        bitrate = bitrate.min(cap);
+    }
+    let (class_fec, class_loss_perc) = class.encoder_fec_params();
+    let (enable_fec, loss_perc) = params.resolve_fec(class_fec, class_loss_perc);
     encoder.set_bitrate(bitrate)?;
     encoder.set_inband_fec(enable_fec)?;
     encoder.set_packet_loss_perc(loss_perc)?;
+    encoder.set_max_bandwidth(max_bandwidth)?;
     info!(
-        "[audio] network_class={class:?} channel_bitrate={} apply opus bitrate={} fec={} packet_loss_perc={}",
+        "[audio] network_class={class:?} robustness={robustness:?} low_bandwidth={low_bandwidth} channel_bitrate={} bitrate_cap={bitrate_cap_bps:?} apply opus bitrate={} fec={} packet_loss_perc={} max_bandwidth={max_bandwidth:?}",
         channel_bitrate_bps, bitrate, enable_fec, loss_perc
     );
     Ok(())
 }
 
+/// Loss over one sampling interval in parts per million; 0 when nothing was
+/// sent.
+fn transport_loss_ppm(sent_packets: u64, lost_packets: u64) -> u32 {
+    if sent_packets == 0 {
+        return 0;
+    }
+    (lost_packets.min(sent_packets) * 1_000_000 / sent_packets) as u32
+}
+
+/// Scale a counter delta taken over `elapsed` to a per-second rate.
+fn per_second(delta: u64, elapsed: Duration) -> u32 {
+    (delta as f64 / elapsed.as_secs_f64()).round() as u32
+}
+
 fn persist_settings(tx_event: &Sender<UiEvent>, settings: &ui::model::AppSettings) {
     if let Err(e) = settings_io::save_settings(settings) {
         let _ = tx_event.send(UiEvent::AppendLog(format!("[settings] save failed: {e:#}")));
     }
 }
 
+/// Save settings changed by an intent and hand them to the activity runtime,
+/// which only wakes its scanner when a sharing flag actually changed.
+fn commit_settings(
+    tx_event: &Sender<UiEvent>,
+    activity_runtime: &ActivityRuntimeSettings,
+    settings: &ui::model::AppSettings,
+) {
+    persist_settings(tx_event, settings);
+    activity_runtime.apply(settings);
+}
+
 fn send_ui_realtime_event(tx_event: &Sender<UiEvent>, event: UiEvent) {
     match tx_event.try_send(event) {
         Ok(()) | Err(TrySendError::Full(_)) | Err(TrySendError::Disconnected(_)) => {}
@@ -865,6 +1182,7 @@ fn main() -> Result<()> {
     let backend_running = running.clone();
     let backend_tx_event = tx_event.clone();
     let backend_ptt = ptt_active.clone();
+    let (backend_done_tx, backend_done_rx) = bounded::<()>(1);
 
     let backend_thread = std::thread::spawn(move || {
         let rt = tokio::runtime::Builder::new_multi_thread()
@@ -886,6 +1204,10 @@ fn main() -> Result<()> {
                 warn!("backend error: {e:#}");
             }
         });
+        // Let spawned tasks drop their audio handles instead of the runtime
+        // vanishing underneath them at process exit.
+        rt.shutdown_timeout(AUDIO_SHUTDOWN_TIMEOUT);
+        let _ = backend_done_tx.send(());
     });
 
     // Run the eframe GUI on the main thread
@@ -911,9 +1233,13 @@ fn main() -> Result<()> {
     running.store(false, Ordering::Relaxed);
     let _ = shutdown_tx.send(true);
 
-    // Do not block UI shutdown waiting for backend/network teardown.
-    // Once the main thread returns, the process exits immediately.
-    let _ = backend_thread;
+    // Give the backend a bounded window to stop the audio streams; a stuck
+    // network teardown must not keep the window-less process alive.
+    if backend_done_rx.recv_timeout(BACKEND_SHUTDOWN_GRACE).is_ok() {
+        let _ = backend_thread.join();
+    } else {
+        warn!("backend did not shut down within {BACKEND_SHUTDOWN_GRACE:?}; exiting");
+    }
 
     gui_result.map_err(|e| anyhow!("eframe error: {e}"))
 }
@@ -1002,16 +1328,7 @@ async fn app_task(
     }
 
     // Enumerate and report audio devices to the UI
-    let input_devices = audio::capture::enumerate_input_devices();
-    let output_devices = audio::playout::enumerate_output_devices();
-    let capture_modes = audio::capture::enumerate_capture_modes();
-    let playback_modes = audio::playout::enumerate_playback_modes();
-    let _ = tx_event.send(UiEvent::SetAudioDevices {
-        input_devices: input_devices.clone(),
-        output_devices: output_devices.clone(),
-        capture_modes,
-        playback_modes,
-    });
+    let (input_devices, output_devices) = report_audio_devices(&tx_event).await;
 
     // Load persisted settings and send to UI
     let mut saved_settings = settings_io::load_settings();
@@ -1066,10 +1383,13 @@ async fn app_task(
     ));
     apply_resampler_mode(saved_settings.dsp_method);
 
-    // Audio constants
-    let sample_rate = 48_000u32;
-    let channels = 1u16;
-    let frame_ms = 20u32;
+    // Audio format; capture and codec are opened for the saved frame size and
+    // the voice loops follow later changes through `audio_runtime`.
+    let audio::SessionAudioConfig {
+        sample_rate,
+        channels,
+        frame_ms,
+    } = audio_runtime.session_audio();
 
     let selected_audio = Arc::new(Mutex::new(AudioSelection {
         input_device: saved_settings.capture_device.clone(),
@@ -1100,7 +1420,7 @@ async fn app_task(
     )?)));
     let playout = Arc::new(RwLock::new(Arc::new(start_playout_with_fallback(
         sample_rate,
-        channels,
+        audio::PLAYOUT_CHANNELS,
         preferred_device_id(&initial_selection.output_device),
         initial_selection.playback_mode.as_deref(),
         &tx_event,
@@ -1117,12 +1437,13 @@ async fn app_task(
 
     if let Some(ref dsp) = capture_dsp {
         let mut d = dsp.lock().await;
-        d.set_vad_threshold(cfg.vad_threshold);
+        d.set_vad_threshold(cfg.vad_threshold.unwrap_or(saved_settings.vad_threshold));
         d.set_noise_suppression(saved_settings.noise_suppression);
-        d.set_agc(saved_settings.agc_enabled);
+        d.set_agc(saved_settings.agc_enabled && !cfg.no_agc);
         d.set_agc_preset(saved_settings.agc_preset);
         d.set_agc_target(saved_settings.agc_target_db);
         d.set_echo_cancellation(saved_settings.echo_cancellation);
+        d.set_echo_reference_delay_samples(saved_settings.echo_reference_delay_samples);
         d.set_echo_reference_enabled(should_enable_aec_reference(&saved_settings.playback_device));
     }
 
@@ -1130,6 +1451,8 @@ async fn app_task(
     let self_muted = Arc::new(AtomicBool::new(false));
     let self_deafened = Arc::new(AtomicBool::new(false));
     let server_deafened = Arc::new(AtomicBool::new(false));
+    // Local "mute all": playout is silenced but decoding keeps running.
+    let local_mute_all = Arc::new(AtomicBool::new(false));
 
     // Shared gain values (stored as u32 bits of f32)
     let input_gain = Arc::new(std::sync::atomic::AtomicU32::new(f32_to_u32(1.0)));
@@ -1146,19 +1469,21 @@ async fn app_task(
     let voice_counters = Arc::new(VoiceTelemetryCounters::default());
     let send_queue_drop_count = Arc::new(AtomicU32::new(0));
     let network_telemetry = Arc::new(SharedNetworkTelemetry::default());
+    let voice_impairment = voice_impairment_from_config(&cfg, &tx_event);
 
-    let _telemetry = tokio::spawn(emit_telemetry_loop(
+    let telemetry = tokio::spawn(emit_telemetry_loop(
         tx_event.clone(),
         capture_dsp.clone(),
         dsp_enabled.clone(),
         voice_counters.clone(),
         network_telemetry.clone(),
         send_queue_drop_count.clone(),
+        voice_impairment.clone(),
         running.clone(),
         shutdown_rx.clone(),
     ));
 
-    let _mic_test = tokio::spawn(mic_test_loop(
+    let mic_test = tokio::spawn(mic_test_loop(
         capture.clone(),
         playout.clone(),
         tx_event.clone(),
@@ -1171,8 +1496,11 @@ async fn app_task(
 
     let mut backoff = Backoff::new(Duration::from_millis(250), Duration::from_secs(10));
     let mut pending_away_message: Option<String> = None;
+    let mut chat_subscriptions: HashSet<String> = HashSet::new();
+    let mut resume_session_id: Option<String> = None;
+    let mut datagram_warning_shown = false;
 
-    while running.load(Ordering::Relaxed) && !*shutdown_rx.borrow() {
+    'session: while running.load(Ordering::Relaxed) && !*shutdown_rx.borrow() {
         match connect_and_run_session(
             &mut cfg,
             &tx_event,
@@ -1191,6 +1519,7 @@ async fn app_task(
             self_muted.clone(),
             self_deafened.clone(),
             server_deafened.clone(),
+            local_mute_all.clone(),
             input_gain.clone(),
             output_gain.clone(),
             per_user_audio.clone(),
@@ -1199,6 +1528,7 @@ async fn app_task(
             voice_counters.clone(),
             network_telemetry.clone(),
             send_queue_drop_count.clone(),
+            voice_impairment.clone(),
             audio_runtime.clone(),
             activity_runtime.clone(),
             sample_rate,
@@ -1207,6 +1537,8 @@ async fn app_task(
             &mut shutdown_rx,
             &mut saved_settings,
             &mut pending_away_message,
+            &mut chat_subscriptions,
+            &mut resume_session_id,
         )
         .await
         {
@@ -1214,22 +1546,47 @@ async fn app_task(
                 backoff.reset();
             }
             Err(e) => {
-                set_connection_stage(
-                    &tx_event,
-                    ui::model::ConnectionStage::Failed,
-                    format!("Connection failed: {e:#}"),
-                );
+                let going_away = e.downcast_ref::<ServerGoingAway>().is_some();
+                if going_away {
+                    set_connection_stage(
+                        &tx_event,
+                        ui::model::ConnectionStage::Reconnecting,
+                        format!("{e:#}; reconnecting"),
+                    );
+                } else {
+                    set_connection_stage(
+                        &tx_event,
+                        ui::model::ConnectionStage::Failed,
+                        format!("Connection failed: {e:#}"),
+                    );
+                }
                 let _ = tx_event.send(UiEvent::AppendLog(format!("[net] disconnected: {e:#}")));
+                let incompatible = e.downcast_ref::<IncompatibleVersion>().is_some();
+                if incompatible {
+                    // Backoff can't fix a version mismatch; wait for the user
+                    // to pick another server instead of retrying forever.
+                    let _ = tx_event.send(UiEvent::Notify {
+                        text: IncompatibleVersion.to_string(),
+                        kind: ui::model::NotificationKind::Error,
+                    });
+                }
+                if e.downcast_ref::<DatagramsUnsupported>().is_some() {
+                    // Only tell the user once; the backoff stays saturated.
+                    if !std::mem::replace(&mut datagram_warning_shown, true) {
+                        let _ = tx_event.send(UiEvent::Notify {
+                            text: DatagramsUnsupported.to_string(),
+                            kind: ui::model::NotificationKind::Error,
+                        });
+                    }
+                }
 
-                let jitter = rand::random::<u64>() % 150;
-                let wait_for = backoff.cur + Duration::from_millis(jitter);
-                backoff.cur = (backoff.cur * 2).min(backoff.max);
+                let wait_for = reconnect_delay(&mut backoff, &e);
 
                 let deadline = tokio::time::Instant::now() + wait_for;
-                'retry_wait: while tokio::time::Instant::now() < deadline {
+                'retry_wait: while incompatible || tokio::time::Instant::now() < deadline {
                     while let Ok(intent) = rx_intent.try_recv() {
                         match intent {
-                            UiIntent::Quit => return Ok(()),
+                            UiIntent::Quit => break 'session,
                             UiIntent::ToggleLoopback => {
                                 let new = !loopback_active.load(Ordering::Relaxed);
                                 loopback_active.store(new, Ordering::Relaxed);
@@ -1240,8 +1597,7 @@ async fn app_task(
                             UiIntent::SetInputGain(gain) => {
                                 saved_settings.input_gain = gain;
                                 input_gain.store(f32_to_u32(gain), Ordering::Relaxed);
-                                persist_settings(&tx_event, &saved_settings);
-                                activity_runtime.apply(&saved_settings);
+                                commit_settings(&tx_event, &activity_runtime, &saved_settings);
                             }
                             UiIntent::SetEchoCancellation(enabled) => {
                                 saved_settings.echo_cancellation = enabled;
@@ -1249,8 +1605,15 @@ async fn app_task(
                                     let mut d = dsp.lock().await;
                                     d.set_echo_cancellation(enabled);
                                 }
+                                commit_settings(&tx_event, &activity_runtime, &saved_settings);
+                            }
+                            UiIntent::SetEchoReferenceDelay(samples) => {
+                                saved_settings.echo_reference_delay_samples = samples;
+                                if let Some(ref dsp) = capture_dsp {
+                                    let mut d = dsp.lock().await;
+                                    d.set_echo_reference_delay_samples(samples);
+                                }
                                 persist_settings(&tx_event, &saved_settings);
-                                activity_runtime.apply(&saved_settings);
                             }
                             UiIntent::SetVoiceProcessingMode(mode) => {
                                 saved_settings.voice_processing_mode = mode;
@@ -1283,7 +1646,9 @@ async fn app_task(
                                     encoder_profile_for_mode(saved_settings.voice_processing_mode),
                                 ) {
                                     Ok(mut new_encoder) => {
-                                        let _ = new_encoder.set_bitrate(bitrate as i32);
+                                        let _ = new_encoder.set_bitrate(
+                                            audio_runtime.capped_voice_bitrate(bitrate as i32),
+                                        );
                                         let _ = apply_fec_encoder_settings(
                                             &mut new_encoder,
                                             &audio_runtime,
@@ -1296,15 +1661,13 @@ async fn app_task(
                                         )));
                                     }
                                 }
-                                persist_settings(&tx_event, &saved_settings);
-                                activity_runtime.apply(&saved_settings);
+                                commit_settings(&tx_event, &activity_runtime, &saved_settings);
                             }
                             UiIntent::SetDspEnabled(enabled) => {
                                 saved_settings.dsp_enabled = enabled;
                                 dsp_enabled
                                     .store(enabled && !cfg.no_noise_suppression, Ordering::Relaxed);
-                                persist_settings(&tx_event, &saved_settings);
-                                activity_runtime.apply(&saved_settings);
+                                commit_settings(&tx_event, &activity_runtime, &saved_settings);
                             }
                             UiIntent::SetDspMethod(method) => {
                                 saved_settings.dsp_method = method;
@@ -1324,8 +1687,7 @@ async fn app_task(
                                         "[audio] failed to switch DSP method: {e:#}"
                                     )));
                                 }
-                                persist_settings(&tx_event, &saved_settings);
-                                activity_runtime.apply(&saved_settings);
+                                commit_settings(&tx_event, &activity_runtime, &saved_settings);
                             }
                             UiIntent::SetNoiseSuppression(enabled) => {
                                 saved_settings.noise_suppression = enabled;
@@ -1333,8 +1695,7 @@ async fn app_task(
                                     let mut d = dsp.lock().await;
                                     d.set_noise_suppression(enabled);
                                 }
-                                persist_settings(&tx_event, &saved_settings);
-                                activity_runtime.apply(&saved_settings);
+                                commit_settings(&tx_event, &activity_runtime, &saved_settings);
                             }
                             UiIntent::SetAgcEnabled(enabled) => {
                                 saved_settings.agc_enabled = enabled;
@@ -1342,8 +1703,7 @@ async fn app_task(
                                     let mut d = dsp.lock().await;
                                     d.set_agc(enabled);
                                 }
-                                persist_settings(&tx_event, &saved_settings);
-                                activity_runtime.apply(&saved_settings);
+                                commit_settings(&tx_event, &activity_runtime, &saved_settings);
                             }
                             UiIntent::SetAgcPreset(preset) => {
                                 saved_settings.agc_preset = preset;
@@ -1352,8 +1712,7 @@ async fn app_task(
                                     let mut d = dsp.lock().await;
                                     d.set_agc_preset(preset);
                                 }
-                                persist_settings(&tx_event, &saved_settings);
-                                activity_runtime.apply(&saved_settings);
+                                commit_settings(&tx_event, &activity_runtime, &saved_settings);
                             }
                             UiIntent::SetAgcTargetDb(target_db) => {
                                 saved_settings.agc_target_db = target_db;
@@ -1361,24 +1720,26 @@ async fn app_task(
                                     let mut d = dsp.lock().await;
                                     d.set_agc_target(target_db);
                                 }
-                                persist_settings(&tx_event, &saved_settings);
-                                activity_runtime.apply(&saved_settings);
+                                commit_settings(&tx_event, &activity_runtime, &saved_settings);
+                            }
+                            UiIntent::SetVadHoldMs(ms) => {
+                                saved_settings.vad_hold_ms = ms;
+                                audio_runtime.vad_hold_ms.store(ms, Ordering::Relaxed);
+                                commit_settings(&tx_event, &activity_runtime, &saved_settings);
                             }
                             UiIntent::SetTypingAttenuation(enabled) => {
                                 saved_settings.typing_attenuation = enabled;
                                 audio_runtime
                                     .typing_attenuation
                                     .store(enabled, Ordering::Relaxed);
-                                persist_settings(&tx_event, &saved_settings);
-                                activity_runtime.apply(&saved_settings);
+                                commit_settings(&tx_event, &activity_runtime, &saved_settings);
                             }
                             UiIntent::SetFecMode(mode) => {
                                 saved_settings.fec_mode = mode;
                                 audio_runtime.fec_mode.store(mode as u32, Ordering::Relaxed);
                                 let mut enc = encoder.lock().await;
                                 let _ = apply_fec_encoder_settings(&mut enc, &audio_runtime);
-                                persist_settings(&tx_event, &saved_settings);
-                                activity_runtime.apply(&saved_settings);
+                                commit_settings(&tx_event, &activity_runtime, &saved_settings);
                             }
                             UiIntent::SetFecStrength(strength) => {
                                 saved_settings.fec_strength = strength.min(100);
@@ -1387,8 +1748,65 @@ async fn app_task(
                                     .store(saved_settings.fec_strength as u32, Ordering::Relaxed);
                                 let mut enc = encoder.lock().await;
                                 let _ = apply_fec_encoder_settings(&mut enc, &audio_runtime);
-                                persist_settings(&tx_event, &saved_settings);
-                                activity_runtime.apply(&saved_settings);
+                                commit_settings(&tx_event, &activity_runtime, &saved_settings);
+                            }
+                            UiIntent::SetNetworkRobustness(profile) => {
+                                saved_settings.network_robustness = profile;
+                                audio_runtime
+                                    .network_robustness
+                                    .store(profile as u32, Ordering::Relaxed);
+                                let mut enc = encoder.lock().await;
+                                let _ = apply_fec_encoder_settings(&mut enc, &audio_runtime);
+                                commit_settings(&tx_event, &activity_runtime, &saved_settings);
+                            }
+                            UiIntent::SetVoiceFramesPerDatagram(frames) => {
+                                saved_settings.voice_frames_per_datagram = frames;
+                                audio_runtime
+                                    .voice_frames_per_datagram
+                                    .store(frames, Ordering::Relaxed);
+                                commit_settings(&tx_event, &activity_runtime, &saved_settings);
+                            }
+                            UiIntent::SetFrameMs(frame_ms) => {
+                                saved_settings.frame_ms = audio::normalize_frame_ms(frame_ms);
+                                audio_runtime
+                                    .frame_ms
+                                    .store(saved_settings.frame_ms, Ordering::Relaxed);
+                                commit_settings(&tx_event, &activity_runtime, &saved_settings);
+                            }
+                            UiIntent::SetOpusMaxBandwidth(bandwidth) => {
+                                saved_settings.opus_max_bandwidth = bandwidth;
+                                audio_runtime
+                                    .opus_max_bandwidth
+                                    .store(bandwidth.ctl_value(), Ordering::Relaxed);
+                                let mut enc = encoder.lock().await;
+                                let _ = apply_fec_encoder_settings(&mut enc, &audio_runtime);
+                                commit_settings(&tx_event, &activity_runtime, &saved_settings);
+                            }
+                            UiIntent::SetVoiceBitrate(kbps) => {
+                                saved_settings.voice_bitrate_limit_kbps = kbps;
+                                audio_runtime
+                                    .voice_bitrate_limit_bps
+                                    .store(kbps.saturating_mul(1000), Ordering::Relaxed);
+                                commit_settings(&tx_event, &activity_runtime, &saved_settings);
+                            }
+                            UiIntent::SetOpusComplexity(complexity) => {
+                                saved_settings.opus_complexity = complexity;
+                                audio_runtime
+                                    .opus_complexity
+                                    .store(complexity, Ordering::Relaxed);
+                                commit_settings(&tx_event, &activity_runtime, &saved_settings);
+                            }
+                            UiIntent::SetDtx(enabled) => {
+                                saved_settings.opus_dtx = enabled;
+                                audio_runtime.opus_dtx.store(enabled, Ordering::Relaxed);
+                                commit_settings(&tx_event, &activity_runtime, &saved_settings);
+                            }
+                            UiIntent::SetLowBandwidthMode(enabled) => {
+                                saved_settings.low_bandwidth_mode = enabled;
+                                audio_runtime
+                                    .low_bandwidth
+                                    .store(enabled, Ordering::Relaxed);
+                                commit_settings(&tx_event, &activity_runtime, &saved_settings);
                             }
                             UiIntent::SetVadThreshold(threshold) => {
                                 saved_settings.vad_threshold = threshold;
@@ -1396,38 +1814,40 @@ async fn app_task(
                                     let mut d = dsp.lock().await;
                                     d.set_vad_threshold(threshold);
                                 }
-                                persist_settings(&tx_event, &saved_settings);
-                                activity_runtime.apply(&saved_settings);
+                                commit_settings(&tx_event, &activity_runtime, &saved_settings);
                             }
                             UiIntent::SetOutputGain(gain) => {
                                 saved_settings.output_gain = gain;
                                 output_gain.store(f32_to_u32(gain), Ordering::Relaxed);
-                                persist_settings(&tx_event, &saved_settings);
-                                activity_runtime.apply(&saved_settings);
+                                commit_settings(&tx_event, &activity_runtime, &saved_settings);
                             }
                             UiIntent::SetOutputAutoLevel(enabled) => {
                                 saved_settings.output_auto_level = enabled;
                                 audio_runtime
                                     .output_auto_level
                                     .store(enabled, Ordering::Relaxed);
-                                persist_settings(&tx_event, &saved_settings);
-                                activity_runtime.apply(&saved_settings);
+                                commit_settings(&tx_event, &activity_runtime, &saved_settings);
                             }
                             UiIntent::SetMonoExpansion(enabled) => {
                                 saved_settings.mono_expansion = enabled;
                                 audio_runtime
                                     .mono_expansion
                                     .store(enabled, Ordering::Relaxed);
-                                persist_settings(&tx_event, &saved_settings);
-                                activity_runtime.apply(&saved_settings);
+                                commit_settings(&tx_event, &activity_runtime, &saved_settings);
+                            }
+                            UiIntent::SetSpatialAudio(enabled) => {
+                                saved_settings.spatial_audio = enabled;
+                                audio_runtime
+                                    .spatial_audio
+                                    .store(enabled, Ordering::Relaxed);
+                                commit_settings(&tx_event, &activity_runtime, &saved_settings);
                             }
                             UiIntent::SetComfortNoise(enabled) => {
                                 saved_settings.comfort_noise = enabled;
                                 audio_runtime
                                     .comfort_noise
                                     .store(enabled, Ordering::Relaxed);
-                                persist_settings(&tx_event, &saved_settings);
-                                activity_runtime.apply(&saved_settings);
+                                commit_settings(&tx_event, &activity_runtime, &saved_settings);
                             }
                             UiIntent::SetComfortNoiseLevel(level) => {
                                 saved_settings.comfort_noise_level = level.clamp(0.0, 0.1);
@@ -1435,16 +1855,14 @@ async fn app_task(
                                     f32_to_u32(saved_settings.comfort_noise_level),
                                     Ordering::Relaxed,
                                 );
-                                persist_settings(&tx_event, &saved_settings);
-                                activity_runtime.apply(&saved_settings);
+                                commit_settings(&tx_event, &activity_runtime, &saved_settings);
                             }
                             UiIntent::SetDuckingEnabled(enabled) => {
                                 saved_settings.ducking_enabled = enabled;
                                 audio_runtime
                                     .ducking_enabled
                                     .store(enabled, Ordering::Relaxed);
-                                persist_settings(&tx_event, &saved_settings);
-                                activity_runtime.apply(&saved_settings);
+                                commit_settings(&tx_event, &activity_runtime, &saved_settings);
                             }
                             UiIntent::SetDuckingAttenuationDb(db) => {
                                 saved_settings.ducking_attenuation_db = db.clamp(-40, 0);
@@ -1452,8 +1870,7 @@ async fn app_task(
                                     f32_to_u32(saved_settings.ducking_attenuation_db as f32),
                                     Ordering::Relaxed,
                                 );
-                                persist_settings(&tx_event, &saved_settings);
-                                activity_runtime.apply(&saved_settings);
+                                commit_settings(&tx_event, &activity_runtime, &saved_settings);
                             }
                             UiIntent::SetInputDevice(dev) => {
                                 {
@@ -1503,6 +1920,12 @@ async fn app_task(
                                     )));
                                 }
                             }
+                            UiIntent::RefreshAudioDevices => {
+                                let tx_event = tx_event.clone();
+                                tokio::spawn(async move {
+                                    report_audio_devices(&tx_event).await;
+                                });
+                            }
                             UiIntent::SetCaptureMode(mode) => {
                                 {
                                     let mut state = selected_audio.lock().await;
@@ -1527,8 +1950,7 @@ async fn app_task(
                             }
                             UiIntent::SaveSettings(ref settings) => {
                                 saved_settings = (**settings).clone();
-                                persist_settings(&tx_event, &saved_settings);
-                                activity_runtime.apply(&saved_settings);
+                                commit_settings(&tx_event, &activity_runtime, &saved_settings);
                             }
                             UiIntent::ConnectToServer {
                                 host,
@@ -1538,6 +1960,7 @@ async fn app_task(
                                 cfg.server = format!("{host}:{port}");
                                 cfg.server_name = host.clone();
                                 cfg.display_name = nickname.clone();
+                                resume_session_id = None;
                                 let _ = tx_event.send(UiEvent::SetNick(nickname.clone()));
                                 let _ = tx_event.send(UiEvent::SetServerAddress { host, port });
                                 let _ = tx_event.send(UiEvent::AppendLog(format!(
@@ -1593,12 +2016,32 @@ async fn app_task(
                             UiIntent::InstallUpdate => {
                                 spawn_update_install_task(tx_event.clone());
                             }
+                            UiIntent::LocalMuteAll | UiIntent::LocalUnmuteAll => {
+                                let muted = matches!(intent, UiIntent::LocalMuteAll);
+                                local_mute_all.store(muted, Ordering::Relaxed);
+                                let _ = tx_event.send(UiEvent::SetLocalMuteAll(muted));
+                            }
+                            // Applied by the next session's resubscribe pass.
+                            UiIntent::SubscribeChannel { channel_id } => {
+                                chat_subscriptions.insert(channel_id.clone());
+                                let _ = tx_event.send(UiEvent::SetChannelChatSubscribed {
+                                    channel_id,
+                                    subscribed: true,
+                                });
+                            }
+                            UiIntent::UnsubscribeChannel { channel_id } => {
+                                chat_subscriptions.remove(&channel_id);
+                                let _ = tx_event.send(UiEvent::SetChannelChatSubscribed {
+                                    channel_id,
+                                    subscribed: false,
+                                });
+                            }
                             _ => {}
                         }
                     }
 
                     if *shutdown_rx.borrow() {
-                        return Ok(());
+                        break 'session;
                     }
                     tokio::time::sleep(Duration::from_millis(25)).await;
                 }
@@ -1607,9 +2050,48 @@ async fn app_task(
     }
 
     let _ = tx_event.send(UiEvent::AppendLog("[sys] shutting down".into()));
+    shutdown_audio(
+        &capture,
+        &playout,
+        &encoder,
+        capture_dsp.as_ref(),
+        [telemetry, mic_test],
+    )
+    .await;
     Ok(())
 }
 
+/// Quiesce audio before the runtime goes away: pause both device streams so
+/// no callback outlives the ring buffers, stop the helper tasks, and wait for
+/// any in-flight DSP/codec work to release its lock. Bounded so a wedged
+/// driver can't hold up exit.
+async fn shutdown_audio(
+    capture: &Arc<RwLock<Arc<audio::capture::Capture>>>,
+    playout: &Arc<RwLock<Arc<audio::playout::Playout>>>,
+    encoder: &Arc<Mutex<audio::opus::OpusEncoder>>,
+    capture_dsp: Option<&Arc<Mutex<audio::dsp::CaptureDsp>>>,
+    tasks: [tokio::task::JoinHandle<()>; 2],
+) {
+    let quiesce = async {
+        capture.read().await.stop();
+        playout.read().await.stop();
+        for task in tasks {
+            task.abort();
+            let _ = task.await;
+        }
+        drop(encoder.lock().await);
+        if let Some(dsp) = capture_dsp {
+            drop(dsp.lock().await);
+        }
+    };
+    if tokio::time::timeout(AUDIO_SHUTDOWN_TIMEOUT, quiesce)
+        .await
+        .is_err()
+    {
+        warn!("[audio] shutdown timed out; dropping streams anyway");
+    }
+}
+
 fn maybe_note_event_gap(_tx_event: &Sender<UiEvent>, _event_seq: u64) {
     // event_seq == 0 means the server did not stamp this push with a sequence
     // number; it is treated as unordered and always applied. No user-visible
@@ -1682,11 +2164,220 @@ fn encoder_profile_for_mode(
     }
 }
 
+fn read_marker_from_pb(marker: &pb::ReadMarker) -> Option<(String, String)> {
+    let user_id = marker.user_id.as_ref()?.value.clone();
+    let message_id = marker.last_read_message_id.as_ref()?.value.clone();
+    Some((user_id, message_id))
+}
+
+/// Toast shown when a moderator kicks or bans the local user, or `None` when
+/// the event targets someone else.
+fn self_moderation_notice(
+    kind: &pb::moderation_event::Kind,
+    local_user_id: &str,
+) -> Option<String> {
+    let targets_self = |target: &Option<pb::UserId>| {
+        target.as_ref().map(|u| u.value.as_str()) == Some(local_user_id)
+    };
+    let with_reason = |text: String, reason: &str| {
+        if reason.trim().is_empty() {
+            text
+        } else {
+            format!("{text}: {}", reason.trim())
+        }
+    };
+    match kind {
+        pb::moderation_event::Kind::UserKicked(ev) if targets_self(&ev.target_user_id) => Some(
+            with_reason("You were kicked from the channel".to_string(), &ev.reason),
+        ),
+        pb::moderation_event::Kind::UserBanned(ev) if targets_self(&ev.target_user_id) => {
+            let text = if ev.duration_seconds == 0 {
+                "You were banned from the server".to_string()
+            } else {
+                format!(
+                    "You were banned from the server for {}s",
+                    ev.duration_seconds
+                )
+            };
+            Some(with_reason(text, &ev.reason))
+        }
+        _ => None,
+    }
+}
+
+/// Message attachments as the server describes them. Local copies are
+/// fetched afterwards by `spawn_attachment_prefetch`.
+fn chat_attachments_from_pb(refs: Vec<pb::AttachmentRef>) -> Vec<ui::model::AttachmentData> {
+    refs.into_iter()
+        .map(|a| ui::model::AttachmentData {
+            asset: AttachmentAsset::UploadedAssetId(
+                a.asset_id.map(|x| x.value).unwrap_or_default(),
+            ),
+            filename: a.filename,
+            mime_type: a.mime_type,
+            size_bytes: a.size_bytes,
+            download_url: String::new(),
+            thumbnail_url: None,
+        })
+        .collect()
+}
+
+/// Resolve the uploaded attachments of `messages` (message id, attachments)
+/// to cached local files in the background, reporting each as it lands so
+/// neither history pages nor live messages wait on asset downloads.
+fn spawn_attachment_prefetch(
+    conn: &quinn::Connection,
+    channel_id: &str,
+    messages: Vec<(String, Vec<ui::model::AttachmentData>)>,
+    tx_event: &Sender<UiEvent>,
+) {
+    let pending: Vec<(String, ui::model::AttachmentData)> = messages
+        .into_iter()
+        .flat_map(|(message_id, attachments)| {
+            attachments
+                .into_iter()
+                .map(move |attachment| (message_id.clone(), attachment))
+        })
+        .filter(|(_, attachment)| {
+            matches!(
+                &attachment.asset,
+                AttachmentAsset::UploadedAssetId(asset_id) if !asset_id.is_empty()
+            )
+        })
+        .collect();
+    if pending.is_empty() {
+        return;
+    }
+    let conn = conn.clone();
+    let channel_id = channel_id.to_string();
+    let tx_event = tx_event.clone();
+    tokio::spawn(async move {
+        for (message_id, attachment) in pending {
+            let AttachmentAsset::UploadedAssetId(asset_id) = &attachment.asset else {
+                continue;
+            };
+            match resolve_attachment_local_path(&conn, &attachment).await {
+                Ok(path) => {
+                    let _ = tx_event.send(UiEvent::AttachmentCached {
+                        channel_id: channel_id.clone(),
+                        message_id,
+                        asset_id: asset_id.clone(),
+                        download_url: format!("file://{}", path.display()),
+                    });
+                }
+                Err(e) => debug!(%asset_id, "attachment prefetch failed: {e:#}"),
+            }
+        }
+    });
+}
+
+/// Fetch the page of `channel_id` history before `before` (or the newest
+/// page) and hand it to the UI; attachments are fetched in the background.
+/// A failed fetch is reported so the panel stops asking until the user
+/// retries.
+async fn load_chat_history(
+    dispatcher: &ControlDispatcher,
+    conn: &quinn::Connection,
+    channel_id: &str,
+    before: Option<&str>,
+    tx_event: &Sender<UiEvent>,
+) {
+    let page = match dispatcher
+        .fetch_history(channel_id, before, CHAT_HISTORY_PAGE_SIZE)
+        .await
+    {
+        Ok(page) => page,
+        Err(e) => {
+            let _ = tx_event.send(UiEvent::AppendLog(format!(
+                "[chat] fetch_history failed: {e:#}"
+            )));
+            let _ = tx_event.send(UiEvent::HistoryLoadFailed {
+                channel_id: channel_id.to_string(),
+            });
+            return;
+        }
+    };
+    let exhausted = page.len() < CHAT_HISTORY_PAGE_SIZE as usize;
+    let mut messages = Vec::with_capacity(page.len());
+    let mut prefetch = Vec::new();
+    for mp in page {
+        let author_id = mp
+            .author_user_id
+            .as_ref()
+            .map(|u| u.value.clone())
+            .unwrap_or_default();
+        let system = mp.kind() == pb::MessageKind::System;
+        let message_id = mp.message_id.map(|m| m.value).unwrap_or_default();
+        let attachments = chat_attachments_from_pb(mp.attachments);
+        if !attachments.is_empty() {
+            prefetch.push((message_id.clone(), attachments.clone()));
+        }
+        messages.push(ui::model::ChatMessage {
+            message_id,
+            channel_id: channel_id.to_string(),
+            author_name: author_id.clone(),
+            author_name_color: None,
+            author_id,
+            author_avatar_url: None,
+            text: mp.text,
+            timestamp: mp.created_at.map(|t| t.unix_millis).unwrap_or_default(),
+            attachments,
+            reply_to: mp.reply_to_message_id.map(|r| r.value),
+            reactions: mp
+                .reactions
+                .into_iter()
+                .map(|r| ui::model::ReactionData {
+                    emoji: r.emoji,
+                    count: r.count,
+                    me: r.me,
+                })
+                .collect(),
+            pinned: mp.pinned,
+            edited: mp.edited_at.is_some(),
+            system,
+        });
+    }
+    let _ = tx_event.send(UiEvent::HistoryLoaded {
+        channel_id: channel_id.to_string(),
+        messages,
+        exhausted,
+    });
+    spawn_attachment_prefetch(conn, channel_id, prefetch, tx_event);
+}
+
+async fn refresh_read_markers(
+    dispatcher: &ControlDispatcher,
+    channel_id: &str,
+    tx_event: &Sender<UiEvent>,
+) {
+    match dispatcher.get_read_state(channel_id).await {
+        Ok(markers) => {
+            let _ = tx_event.send(UiEvent::SetReadMarkers {
+                channel_id: channel_id.to_string(),
+                markers: markers.iter().filter_map(read_marker_from_pb).collect(),
+            });
+        }
+        Err(e) => {
+            let _ = tx_event.send(UiEvent::AppendLog(format!(
+                "[chat] get_read_state failed: {e:#}"
+            )));
+        }
+    }
+}
+
 fn apply_authoritative_snapshot(
     snapshot: &pb::InitialStateSnapshot,
     tx_event: &Sender<UiEvent>,
     requested_channel_id: Option<&str>,
 ) {
+    let member_counts: HashMap<&str, u32> = snapshot
+        .channel_members
+        .iter()
+        .filter_map(|scope| {
+            let channel_id = scope.channel_id.as_ref()?;
+            Some((channel_id.value.as_str(), scope.members.len() as u32))
+        })
+        .collect();
     let channels = snapshot
         .channels
         .iter()
@@ -1701,11 +2392,18 @@ fn apply_authoritative_snapshot(
             channel_type: pb_channel_type_to_ui(info.channel_type),
             parent_id: info.parent_channel_id.as_ref().map(|pid| pid.value.clone()),
             position: info.position,
-            member_count: 0,
+            member_count: info
+                .channel_id
+                .as_ref()
+                .and_then(|id| member_counts.get(id.value.as_str()).copied())
+                .unwrap_or(0),
             user_limit: info.user_limit,
             description: info.description.clone(),
             bitrate_bps: info.bitrate,
             opus_profile: info.opus_profile,
+            max_message_length: info.max_message_length,
+            slow_mode_secs: info.slow_mode_secs,
+            announce_presence: info.announce_presence,
         })
         .collect::<Vec<_>>();
 
@@ -1717,6 +2415,9 @@ fn apply_authoritative_snapshot(
             .map(|channel_id| channel_id.value.clone()),
     ));
     let _ = tx_event.send(UiEvent::SetLastEventSeq(snapshot.snapshot_version));
+    let _ = tx_event.send(UiEvent::SetReadReceiptsEnabled(
+        snapshot.read_receipts_enabled,
+    ));
 
     for scope in &snapshot.channel_members {
         let channel_id = scope
@@ -1767,13 +2468,59 @@ fn apply_authoritative_snapshot(
     )));
 }
 
-fn choose_initial_selected_channel(
-    snapshot: &pb::InitialStateSnapshot,
-    requested_channel_id: Option<&str>,
-) -> Option<String> {
-    if let Some(requested) = requested_channel_id {
-        if snapshot.channels.iter().any(|channel| {
-            channel
+/// Self mute/deafen flags to push for a channel.
+#[derive(Clone)]
+struct SelfVoiceState {
+    channel_id: String,
+    muted: bool,
+    deafened: bool,
+}
+
+/// Queue the local self mute/deafen flags so other members' indicators match.
+/// Only the newest state is kept, so the intent loop never waits on the RPC.
+fn sync_self_voice_state(
+    pending: &watch::Sender<Option<SelfVoiceState>>,
+    channel_id: &str,
+    self_muted: &AtomicBool,
+    self_deafened: &AtomicBool,
+) {
+    pending.send_replace(Some(SelfVoiceState {
+        channel_id: channel_id.to_string(),
+        muted: self_muted.load(Ordering::Relaxed),
+        deafened: self_deafened.load(Ordering::Relaxed),
+    }));
+}
+
+/// Send queued self voice states one at a time, skipping any superseded while
+/// a request was in flight. Failures are logged; the flags still apply
+/// locally either way.
+async fn self_voice_state_sync_loop(
+    dispatcher: ControlDispatcher,
+    mut pending: watch::Receiver<Option<SelfVoiceState>>,
+    tx_event: Sender<UiEvent>,
+) {
+    while pending.changed().await.is_ok() {
+        let Some(state) = pending.borrow_and_update().clone() else {
+            continue;
+        };
+        if let Err(e) = dispatcher
+            .set_self_voice_state(&state.channel_id, state.muted, state.deafened)
+            .await
+        {
+            let _ = tx_event.send(UiEvent::AppendLog(format!(
+                "[voice] self mute/deafen sync failed: {e:#}"
+            )));
+        }
+    }
+}
+
+fn choose_initial_selected_channel(
+    snapshot: &pb::InitialStateSnapshot,
+    requested_channel_id: Option<&str>,
+) -> Option<String> {
+    if let Some(requested) = requested_channel_id {
+        if snapshot.channels.iter().any(|channel| {
+            channel
                 .info
                 .as_ref()
                 .and_then(|info| info.channel_id.as_ref())
@@ -1975,7 +2722,7 @@ async fn restart_audio_streams(
     .context("restart capture")?;
     let new_playout = start_playout_with_fallback(
         sample_rate,
-        channels,
+        audio::PLAYOUT_CHANNELS,
         preferred_output,
         preferred_mode,
         tx_event,
@@ -2023,6 +2770,60 @@ fn resolve_device_label(device: &AudioDeviceId, input: bool) -> String {
         .unwrap_or_else(|| "Unknown device".to_string())
 }
 
+/// Enumerate and probe audio devices and send them to the UI. Host APIs can
+/// block for a while (device wake-up, driver probing), so this runs on a
+/// blocking task.
+async fn report_audio_devices(
+    tx_event: &Sender<UiEvent>,
+) -> (Vec<AudioDeviceInfo>, Vec<AudioDeviceInfo>) {
+    let scan = tokio::task::spawn_blocking(|| {
+        let input_devices = audio::capture::enumerate_input_devices();
+        let output_devices = audio::playout::enumerate_output_devices();
+        let device_capabilities = probe_audio_devices(&input_devices, &output_devices);
+        let modes = (
+            audio::capture::enumerate_capture_modes(),
+            audio::playout::enumerate_playback_modes(),
+        );
+        (input_devices, output_devices, modes, device_capabilities)
+    })
+    .await;
+    let (input_devices, output_devices, (capture_modes, playback_modes), device_capabilities) =
+        match scan {
+            Ok(scan) => scan,
+            Err(e) => {
+                let _ = tx_event.send(UiEvent::AppendLog(format!(
+                    "[audio] device enumeration failed: {e:#}"
+                )));
+                return (Vec::new(), Vec::new());
+            }
+        };
+    let _ = tx_event.send(UiEvent::SetAudioDevices {
+        input_devices: input_devices.clone(),
+        output_devices: output_devices.clone(),
+        capture_modes,
+        playback_modes,
+        device_capabilities,
+    });
+    (input_devices, output_devices)
+}
+
+/// Probe every enumerated device so the settings panel can show formats
+/// without touching cpal on the UI thread. The enumerations already list the
+/// system defaults first, so each device is probed once.
+fn probe_audio_devices(
+    input_devices: &[AudioDeviceInfo],
+    output_devices: &[AudioDeviceInfo],
+) -> HashMap<AudioDeviceId, audio::probe::DeviceCapabilities> {
+    input_devices
+        .iter()
+        .chain(output_devices)
+        .filter_map(|device| {
+            let caps = audio::probe::probe_device(&device.key)?;
+            Some((device.key.clone(), caps))
+        })
+        .collect()
+}
+
 fn split_server_host_port(server: &str) -> (String, u16) {
     if let Some((host, port_text)) = server.rsplit_once(':') {
         if let Ok(port) = port_text.parse::<u16>() {
@@ -2032,45 +2833,19 @@ fn split_server_host_port(server: &str) -> (String, u16) {
     (server.to_string(), 4433)
 }
 
-async fn connect_and_run_session(
-    cfg: &mut Config,
+/// Connects to `cfg.server` and runs hello/auth on a fresh control stream.
+/// After a dropped connection, asks the gateway to resume the session named
+/// by `resume_session_id`; on success that then names the new session, so
+/// the next reconnect resumes this one.
+async fn open_control_session(
+    cfg: &Config,
+    device_identity: &DeviceIdentity,
+    audio_runtime: &AudioRuntimeSettings,
     tx_event: &Sender<UiEvent>,
-    rx_intent: &Receiver<UiIntent>,
-    encoder: Arc<Mutex<audio::opus::OpusEncoder>>,
-    capture: Arc<RwLock<Arc<audio::capture::Capture>>>,
-    playout: Arc<RwLock<Arc<audio::playout::Playout>>>,
-    capture_dsp: Option<Arc<Mutex<audio::dsp::CaptureDsp>>>,
-    dsp_enabled: Arc<AtomicBool>,
-    active_voice_channel_route: Arc<AtomicU32>,
-    active_channel_audio_mode: Arc<std::sync::RwLock<ChannelAudioMode>>,
-    selected_audio: Arc<Mutex<AudioSelection>>,
-    ptt_active: Arc<AtomicBool>,
-    ptt_state: &mut PttState,
-    capture_mode: Arc<AtomicU8>,
-    self_muted: Arc<AtomicBool>,
-    self_deafened: Arc<AtomicBool>,
-    server_deafened: Arc<AtomicBool>,
-    input_gain: Arc<std::sync::atomic::AtomicU32>,
-    output_gain: Arc<std::sync::atomic::AtomicU32>,
-    per_user_audio: Arc<std::sync::RwLock<HashMap<String, PerUserAudioSettings>>>,
-    loopback_active: Arc<AtomicBool>,
-    session_voice_active: Arc<AtomicBool>,
-    voice_counters: Arc<VoiceTelemetryCounters>,
-    network_telemetry: Arc<SharedNetworkTelemetry>,
-    send_queue_drop_count: Arc<AtomicU32>,
-    audio_runtime: AudioRuntimeSettings,
-    activity_runtime: ActivityRuntimeSettings,
-    sample_rate: u32,
-    channels: u16,
-    frame_ms: u32,
-    shutdown_rx: &mut watch::Receiver<bool>,
-    saved_settings: &mut ui::model::AppSettings,
-    pending_away_message: &mut Option<String>,
-) -> Result<()> {
-    let _ = tx_event.send(UiEvent::SetConnected(false));
-    let _ = tx_event.send(UiEvent::SetAuthed(false));
-    server_deafened.store(false, Ordering::Relaxed);
-
+    shutdown_rx: &watch::Receiver<bool>,
+    ui_log_tx: net::UiLogTx,
+    resume_session_id: &mut Option<String>,
+) -> Result<(quinn::Connection, ControlDispatcher, AuthInfo)> {
     set_connection_stage(
         tx_event,
         ui::model::ConnectionStage::Resolving,
@@ -2092,13 +2867,24 @@ async fn connect_and_run_session(
         format!("Establishing QUIC/TLS to {}", cfg.server_name),
     );
     let handshake_started = Instant::now();
-    let conn = endpoint
+    let conn = match endpoint
         .connect(addr, &cfg.server_name)
         .context("connect start")?
         .await
-        .context("connect await")?;
+    {
+        Ok(conn) => conn,
+        Err(err) if is_alpn_mismatch(&err) => return Err(IncompatibleVersion.into()),
+        Err(err) => return Err(anyhow::Error::new(err).context("connect await")),
+    };
     let handshake_elapsed = handshake_started.elapsed();
 
+    // Voice is datagram-only; without DATAGRAM support every send would fail
+    // and the voice loop would die on its first frame.
+    if conn.max_datagram_size().is_none() {
+        conn.close(0u32.into(), b"datagrams unsupported");
+        return Err(DatagramsUnsupported.into());
+    }
+
     let _ = tx_event.send(UiEvent::SetConnected(true));
     set_connection_stage(
         tx_event,
@@ -2109,28 +2895,29 @@ async fn connect_and_run_session(
         ),
     );
 
-    let (ui_log_tx, mut ui_log_rx) = mpsc::unbounded_channel::<String>();
-    let tx_event_log = tx_event.clone();
-    tokio::spawn(async move {
-        while let Some(line) = ui_log_rx.recv().await {
-            let _ = tx_event_log.send(UiEvent::AppendLog(line));
-        }
-    });
-
-    let (send, recv) = conn.open_bi().await.context("open control stream")?;
-    let dispatcher = ControlDispatcher::start(send, recv, shutdown_rx.clone(), ui_log_tx.clone());
+    let (send, recv) = conn
+        .open_bi()
+        .await
+        .map_err(|e| version_error_or(&conn, e.into()))
+        .context("open control stream")?;
+    let dispatcher = ControlDispatcher::start(send, recv, shutdown_rx.clone(), ui_log_tx);
 
     set_connection_stage(
         tx_event,
         ui::model::ConnectionStage::Authenticating,
         "Authenticating with gateway",
     );
-    let device_identity =
-        DeviceIdentity::load_or_create().context("load/create device identity")?;
     let auth_started = Instant::now();
     let auth_info = dispatcher
-        .hello_auth(&cfg.alpn, &device_identity, &cfg.display_name)
+        .hello_auth(
+            &cfg.alpn,
+            device_identity,
+            &cfg.display_name,
+            audio_runtime.session_audio(),
+            audio_runtime.low_bandwidth(),
+        )
         .await
+        .map_err(|e| version_error_or(&conn, e))
         .context("hello/auth")?;
     let auth_elapsed = auth_started.elapsed();
     set_connection_stage(
@@ -2151,6 +2938,141 @@ async fn connect_and_run_session(
     if !auth_info.user_id.is_empty() {
         let _ = tx_event.send(UiEvent::SetUserId(auth_info.user_id.clone()));
     }
+    // Back from a dropped connection: have the gateway hand the old session's
+    // channel membership to this one, so others don't see us leave and rejoin.
+    if let Some(prev_session_id) = resume_session_id.take() {
+        let line = match dispatcher.resume(&prev_session_id).await {
+            Ok(true) => "[net] resumed previous session".to_string(),
+            Ok(false) => "[net] previous session expired; starting fresh".to_string(),
+            Err(e) => format!("[net] session resume failed: {e:#}"),
+        };
+        let _ = tx_event.send(UiEvent::AppendLog(line));
+    }
+    if !auth_info.session_id.is_empty() {
+        *resume_session_id = Some(auth_info.session_id.clone());
+    }
+    Ok((conn, dispatcher, auth_info))
+}
+
+async fn connect_and_run_session(
+    cfg: &mut Config,
+    tx_event: &Sender<UiEvent>,
+    rx_intent: &Receiver<UiIntent>,
+    encoder: Arc<Mutex<audio::opus::OpusEncoder>>,
+    capture: Arc<RwLock<Arc<audio::capture::Capture>>>,
+    playout: Arc<RwLock<Arc<audio::playout::Playout>>>,
+    capture_dsp: Option<Arc<Mutex<audio::dsp::CaptureDsp>>>,
+    dsp_enabled: Arc<AtomicBool>,
+    active_voice_channel_route: Arc<AtomicU32>,
+    active_channel_audio_mode: Arc<std::sync::RwLock<ChannelAudioMode>>,
+    selected_audio: Arc<Mutex<AudioSelection>>,
+    ptt_active: Arc<AtomicBool>,
+    ptt_state: &mut PttState,
+    capture_mode: Arc<AtomicU8>,
+    self_muted: Arc<AtomicBool>,
+    self_deafened: Arc<AtomicBool>,
+    server_deafened: Arc<AtomicBool>,
+    local_mute_all: Arc<AtomicBool>,
+    input_gain: Arc<std::sync::atomic::AtomicU32>,
+    output_gain: Arc<std::sync::atomic::AtomicU32>,
+    per_user_audio: Arc<std::sync::RwLock<HashMap<String, PerUserAudioSettings>>>,
+    loopback_active: Arc<AtomicBool>,
+    session_voice_active: Arc<AtomicBool>,
+    voice_counters: Arc<VoiceTelemetryCounters>,
+    network_telemetry: Arc<SharedNetworkTelemetry>,
+    send_queue_drop_count: Arc<AtomicU32>,
+    voice_impairment: Option<Arc<VoiceImpairment>>,
+    audio_runtime: AudioRuntimeSettings,
+    activity_runtime: ActivityRuntimeSettings,
+    sample_rate: u32,
+    channels: u16,
+    frame_ms: u32,
+    shutdown_rx: &mut watch::Receiver<bool>,
+    saved_settings: &mut ui::model::AppSettings,
+    pending_away_message: &mut Option<String>,
+    chat_subscriptions: &mut HashSet<String>,
+    resume_session_id: &mut Option<String>,
+) -> Result<()> {
+    let _ = tx_event.send(UiEvent::SetConnected(false));
+    let _ = tx_event.send(UiEvent::SetAuthed(false));
+    server_deafened.store(false, Ordering::Relaxed);
+
+    let device_identity =
+        DeviceIdentity::load_or_create().context("load/create device identity")?;
+    let (ui_log_tx, mut ui_log_rx) = mpsc::unbounded_channel::<String>();
+    let tx_event_log = tx_event.clone();
+    tokio::spawn(async move {
+        while let Some(line) = ui_log_rx.recv().await {
+            let _ = tx_event_log.send(UiEvent::AppendLog(line));
+        }
+    });
+
+    let (conn, dispatcher, auth_info) = open_control_session(
+        cfg,
+        &device_identity,
+        &audio_runtime,
+        tx_event,
+        shutdown_rx,
+        ui_log_tx.clone(),
+        resume_session_id,
+    )
+    .await?;
+
+    match &auth_info.server_info {
+        Some(info) => info!(
+            server_version = %info.server_version,
+            feature_bits = format_args!("{:#x}", info.feature_bits),
+            "server info"
+        ),
+        None => info!("server sent no ServerInfo; assuming all features"),
+    }
+    let _ = tx_event.send(UiEvent::SetServerInfo {
+        version: auth_info
+            .server_info
+            .as_ref()
+            .map(|info| info.server_version.clone()),
+        feature_bits: auth_info.server_info.as_ref().map(|info| info.feature_bits),
+        screen_share_codecs: auth_info
+            .server_info
+            .as_ref()
+            .map(|info| {
+                info.screen_share_codecs()
+                    .filter_map(net::dispatcher::screen_share_codec_label)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        max_voice_bitrate_bps: auth_info
+            .server_info
+            .as_ref()
+            .map_or(0, |info| info.max_voice_bitrate_bps),
+        voice_mixing: auth_info
+            .server_info
+            .as_ref()
+            .is_some_and(|info| info.voice_mixing),
+    });
+    // Unlike UI features, a server without ServerInfo predates the loudness
+    // byte and would drop datagrams carrying it, so only trust the bit.
+    audio_runtime.voice_loudness.store(
+        auth_info.server_info.as_ref().is_some_and(|info| {
+            info.feature_bits & (1u64 << pb::ServerFeature::VoiceLoudness as u32) != 0
+        }),
+        Ordering::Relaxed,
+    );
+    // Same for whispers: an older server would forward them to everyone.
+    let whisper = auth_info
+        .server_info
+        .as_ref()
+        .is_some_and(|info| info.feature_bits & (1u64 << pb::ServerFeature::Whisper as u32) != 0);
+    audio_runtime
+        .voice_whisper
+        .store(whisper, Ordering::Relaxed);
+    if !whisper && audio_runtime.set_whisper_targets(Vec::new()) {
+        let _ = tx_event.send(UiEvent::WhisperTargetsChanged(Vec::new()));
+    }
+    // A previous server's ServerHint cap doesn't carry over.
+    audio_runtime
+        .server_voice_bitrate_cap_bps
+        .store(0, Ordering::Relaxed);
 
     #[cfg(debug_assertions)]
     if !auth_info.user_id.trim().is_empty() {
@@ -2208,6 +3130,7 @@ async fn connect_and_run_session(
     });
     server_deafened.store(initially_server_deafened, Ordering::Relaxed);
     apply_authoritative_snapshot(&snapshot, tx_event, initial_active_channel.as_deref());
+    let read_receipts_enabled = snapshot.read_receipts_enabled;
 
     // Prime the self profile immediately after initial sync so the user panel
     // avatar/status render without requiring the Edit Profile modal to be opened.
@@ -2258,10 +3181,25 @@ async fn connect_and_run_session(
         }
     }
 
+    // Chat subscriptions are per session on the gateway; restore them.
+    for channel_id in chat_subscriptions.clone() {
+        if let Err(e) = dispatcher.subscribe_channel(&channel_id).await {
+            chat_subscriptions.remove(&channel_id);
+            let _ = tx_event.send(UiEvent::SetChannelChatSubscribed {
+                channel_id: channel_id.clone(),
+                subscribed: false,
+            });
+            let _ = tx_event.send(UiEvent::AppendLog(format!(
+                "[ctl] resubscribe to {channel_id} failed: {e:#}"
+            )));
+        }
+    }
+
     let active_share_session = Arc::new(ActiveShareSession::default());
 
     // Server push consumer
     let mut push_rx = dispatcher.take_push_receiver().await;
+    let (going_away_tx, mut going_away_rx) = mpsc::channel::<String>(1);
     {
         let tx_event = tx_event.clone();
         let mut last_event_seq = snapshot.snapshot_version;
@@ -2272,6 +3210,7 @@ async fn connect_and_run_session(
         let stream_state = stream_state.clone();
         let dispatcher = dispatcher.clone();
         let active_share_session = active_share_session.clone();
+        let audio_runtime = audio_runtime.clone();
         tokio::spawn(async move {
             let mut prefetched_profile_user_ids = HashSet::new();
             while let Some(ev) = push_rx.recv().await {
@@ -2315,6 +3254,7 @@ async fn connect_and_run_session(
                                         .as_ref()
                                         .map(|m| m.value.clone())
                                         .unwrap_or_default();
+                                    let system_message = mp.kind() == pb::MessageKind::System;
                                     debug!(
                                         message_id = %message_id,
                                         author_user_id = %author_id,
@@ -2322,35 +3262,13 @@ async fn connect_and_run_session(
                                         "received message_posted push event"
                                     );
 
-                                    let mut attachments: Vec<ui::model::AttachmentData> = mp
-                                        .attachments
-                                        .into_iter()
-                                        .map(|a| ui::model::AttachmentData {
-                                            asset: AttachmentAsset::UploadedAssetId(
-                                                a.asset_id.map(|x| x.value).unwrap_or_default(),
-                                            ),
-                                            filename: a.filename,
-                                            mime_type: a.mime_type,
-                                            size_bytes: a.size_bytes,
-                                            download_url: String::new(),
-                                            thumbnail_url: None,
-                                        })
-                                        .collect();
-
-                                    for attachment in &mut attachments {
-                                        if !matches!(
-                                            &attachment.asset,
-                                            AttachmentAsset::UploadedAssetId(ref asset_id) if !asset_id.is_empty()
-                                        ) {
-                                            continue;
-                                        }
-                                        if let Ok(path) =
-                                            resolve_attachment_local_path(&conn, attachment).await
-                                        {
-                                            attachment.download_url =
-                                                format!("file://{}", path.display());
-                                        }
-                                    }
+                                    let attachments = chat_attachments_from_pb(mp.attachments);
+                                    spawn_attachment_prefetch(
+                                        &conn,
+                                        &channel_id,
+                                        vec![(message_id.clone(), attachments.clone())],
+                                        &tx_event,
+                                    );
 
                                     let _ = tx_event.send(UiEvent::MessageReceived(
                                         ui::model::ChatMessage {
@@ -2367,6 +3285,7 @@ async fn connect_and_run_session(
                                             reactions: Vec::new(),
                                             pinned: mp.pinned,
                                             edited: mp.edited_at.is_some(),
+                                            system: system_message,
                                         },
                                     ));
                                     if !author_id.is_empty()
@@ -2394,7 +3313,7 @@ async fn connect_and_run_session(
                                             }
                                         });
                                     }
-                                    if author_id != local_user_id {
+                                    if author_id != local_user_id && !system_message {
                                         let _ = tx_event.send(UiEvent::PlayChatMessageSfx);
                                     }
                                 }
@@ -2471,6 +3390,38 @@ async fn connect_and_run_session(
                                         user_id,
                                     });
                                 }
+                                pb::chat_event::Kind::ReactionChanged(rc) => {
+                                    let _ = tx_event.send(UiEvent::ReactionChanged {
+                                        channel_id: rc
+                                            .channel_id
+                                            .map(|c| c.value)
+                                            .unwrap_or_default(),
+                                        message_id: rc
+                                            .message_id
+                                            .map(|m| m.value)
+                                            .unwrap_or_default(),
+                                        emoji: rc.emoji,
+                                        user_id: rc.user_id.map(|u| u.value).unwrap_or_default(),
+                                        added: rc.added,
+                                        count: rc.count,
+                                    });
+                                }
+                                pb::chat_event::Kind::ReadMarkerUpdated(ru) => {
+                                    let channel_id = ru
+                                        .channel_id
+                                        .as_ref()
+                                        .map(|c| c.value.clone())
+                                        .unwrap_or_default();
+                                    if let Some((user_id, message_id)) =
+                                        ru.marker.as_ref().and_then(read_marker_from_pb)
+                                    {
+                                        let _ = tx_event.send(UiEvent::ReadMarkerUpdated {
+                                            channel_id,
+                                            user_id,
+                                            message_id,
+                                        });
+                                    }
+                                }
                                 pb::chat_event::Kind::TypingStarted(ts) => {
                                     let channel_id = ts
                                         .channel_id
@@ -2653,6 +3604,44 @@ async fn connect_and_run_session(
                                         custom_status_expires_ms: status.custom_status_expires.map(|ts| ts.unix_millis),
                                     });
                                 }
+                                pb::presence_event::Kind::ChannelStateChanged(changed) => {
+                                    let Some(state) = changed.state else { continue };
+                                    let channel_id =
+                                        state.channel_id.map(|c| c.value).unwrap_or_default();
+                                    let members = state
+                                        .members
+                                        .into_iter()
+                                        .map(|m| ui::model::MemberEntry {
+                                            user_id: m.user_id.map(|u| u.value).unwrap_or_default(),
+                                            display_name: m.display_name,
+                                            away_message: m.away_message,
+                                            custom_status_emoji: m.custom_status_emoji,
+                                            muted: m.muted,
+                                            deafened: m.deafened,
+                                            self_muted: m.self_muted,
+                                            self_deafened: m.self_deafened,
+                                            streaming: m.streaming,
+                                            speaking: false,
+                                            avatar_url: None,
+                                            accent_color: (m.accent_color != 0)
+                                                .then_some(m.accent_color),
+                                        })
+                                        .collect::<Vec<_>>();
+                                    if let Some(me) =
+                                        members.iter().find(|m| m.user_id == local_user_id)
+                                    {
+                                        server_deafened.store(me.deafened, Ordering::Relaxed);
+                                    }
+                                    debug!(channel_id = %channel_id, members = members.len(), "received channel state refresh");
+                                    let _ = tx_event.send(UiEvent::SetChannelUserLimit {
+                                        channel_id: channel_id.clone(),
+                                        user_limit: state.max_members,
+                                    });
+                                    let _ = tx_event.send(UiEvent::UpdateChannelMembers {
+                                        channel_id,
+                                        members,
+                                    });
+                                }
                             }
                         }
                     }
@@ -2664,10 +3653,44 @@ async fn connect_and_run_session(
                         if !should_apply_event_seq(&tx_event, &mut last_event_seq, event_seq) {
                             continue;
                         }
-                        if let Some(pb::moderation_event::Kind::UserKicked(ev)) = m.kind.clone() {
+                        let removed = match m.kind.clone() {
+                            Some(pb::moderation_event::Kind::UserKicked(ev)) => {
+                                Some((ev.channel_id, ev.target_user_id))
+                            }
+                            Some(pb::moderation_event::Kind::UserBanned(ev)) => {
+                                Some((ev.channel_id, ev.target_user_id))
+                            }
+                            _ => None,
+                        };
+                        if let Some((channel_id, target_user_id)) = removed {
                             let _ = tx_event.send(UiEvent::MemberLeft {
-                                channel_id: ev.channel_id.map(|c| c.value).unwrap_or_default(),
-                                user_id: ev.target_user_id.map(|u| u.value).unwrap_or_default(),
+                                channel_id: channel_id.map(|c| c.value).unwrap_or_default(),
+                                user_id: target_user_id.map(|u| u.value).unwrap_or_default(),
+                            });
+                        }
+                        if let Some(pb::moderation_event::Kind::UserMoved(mv)) = m.kind.as_ref() {
+                            let target = mv.target_user_id.as_ref().map(|u| u.value.as_str());
+                            if target == Some(local_user_id.as_str()) {
+                                if let Some(to) = mv.to_channel_id.as_ref() {
+                                    let _ = tx_event.send(UiEvent::MovedByModerator {
+                                        channel_id: to.value.clone(),
+                                    });
+                                    let _ = tx_event.send(UiEvent::Notify {
+                                        text: "A moderator moved you to another channel"
+                                            .to_string(),
+                                        kind: ui::model::NotificationKind::Info,
+                                    });
+                                }
+                            }
+                        }
+                        if let Some(text) = m
+                            .kind
+                            .as_ref()
+                            .and_then(|kind| self_moderation_notice(kind, &local_user_id))
+                        {
+                            let _ = tx_event.send(UiEvent::Notify {
+                                text,
+                                kind: ui::model::NotificationKind::Error,
                             });
                         }
                         let _ = tx_event.send(UiEvent::AppendLog(format!("[moderation] {:?}", m)));
@@ -2709,6 +3732,9 @@ async fn connect_and_run_session(
                                         description: channel.description,
                                         bitrate_bps: channel.bitrate,
                                         opus_profile: channel.opus_profile,
+                                        max_message_length: channel.max_message_length,
+                                        slow_mode_secs: channel.slow_mode_secs,
+                                        announce_presence: channel.announce_presence,
                                     },
                                 ));
                             }
@@ -2737,6 +3763,9 @@ async fn connect_and_run_session(
                                         description: channel.description,
                                         bitrate_bps: channel.bitrate,
                                         opus_profile: channel.opus_profile,
+                                        max_message_length: channel.max_message_length,
+                                        slow_mode_secs: channel.slow_mode_secs,
+                                        announce_presence: channel.announce_presence,
                                     },
                                 ));
                             }
@@ -2773,20 +3802,59 @@ async fn connect_and_run_session(
                             });
                         }
                     }
-                    PushEvent::ServerHint { hint: h, event_seq } => {
+                    PushEvent::VoiceLoudness { event, event_seq } => {
                         maybe_note_event_gap(&tx_event, event_seq);
                         if !should_apply_event_seq(&tx_event, &mut last_event_seq, event_seq) {
                             continue;
                         }
-                        let mut parts = vec![];
-                        if h.receiver_report_interval_ms != 0 {
-                            parts.push(format!("rr={}ms", h.receiver_report_interval_ms));
+                        if let Some(channel_id) = event.channel_id {
+                            let _ = tx_event.send(UiEvent::SetTalkerLoudness {
+                                channel_id: channel_id.value,
+                                talkers: event
+                                    .talkers
+                                    .into_iter()
+                                    .filter_map(|t| {
+                                        let loudness = t.loudness.min(u8::MAX as u32) as u8;
+                                        Some((t.user_id?.value, loudness))
+                                    })
+                                    .collect(),
+                            });
                         }
-                        if h.max_stream_bitrate_bps != 0 {
-                            parts.push(format!("stream_cap={}bps", h.max_stream_bitrate_bps));
+                    }
+                    PushEvent::TalkerPreempted { event, event_seq } => {
+                        maybe_note_event_gap(&tx_event, event_seq);
+                        if !should_apply_event_seq(&tx_event, &mut last_event_seq, event_seq) {
+                            continue;
+                        }
+                        if let (Some(channel_id), Some(preempted), Some(by)) =
+                            (event.channel_id, event.preempted_user_id, event.by_user_id)
+                        {
+                            let _ = tx_event.send(UiEvent::TalkerPreempted {
+                                channel_id: channel_id.value,
+                                preempted_user_id: preempted.value,
+                                by_user_id: by.value,
+                            });
+                        }
+                    }
+                    PushEvent::ServerHint { hint: h, event_seq } => {
+                        maybe_note_event_gap(&tx_event, event_seq);
+                        if !should_apply_event_seq(&tx_event, &mut last_event_seq, event_seq) {
+                            continue;
+                        }
+                        let mut parts = vec![];
+                        if h.receiver_report_interval_ms != 0 {
+                            parts.push(format!("rr={}ms", h.receiver_report_interval_ms));
+                        }
+                        if h.max_stream_bitrate_bps != 0 {
+                            parts.push(format!("stream_cap={}bps", h.max_stream_bitrate_bps));
                         }
                         if h.max_voice_bitrate_bps != 0 {
                             parts.push(format!("voice_cap={}bps", h.max_voice_bitrate_bps));
+                            // The send loop re-applies the encoder bitrate on
+                            // its next frame.
+                            audio_runtime
+                                .server_voice_bitrate_cap_bps
+                                .store(h.max_voice_bitrate_bps, Ordering::Relaxed);
                         }
                         let msg = if parts.is_empty() {
                             "server_hint".into()
@@ -3128,6 +4196,13 @@ async fn connect_and_run_session(
                             }
                         }
                     }
+                    PushEvent::ServerGoingAway { event } => {
+                        let _ = tx_event.send(UiEvent::AppendLog(format!(
+                            "[net] server going away ({}); reconnecting",
+                            event.reason
+                        )));
+                        let _ = going_away_tx.try_send(event.reason);
+                    }
                     PushEvent::Unknown(_) => {}
                 }
             }
@@ -3136,6 +4211,14 @@ async fn connect_and_run_session(
 
     let selected_after_sync =
         choose_initial_selected_channel(&snapshot, initial_active_channel.as_deref());
+    set_connection_stage(
+        tx_event,
+        ui::model::ConnectionStage::Joining,
+        match selected_after_sync.as_deref() {
+            Some(channel_id) => format!("Joining channel {channel_id}"),
+            None => "No channel to join".to_string(),
+        },
+    );
 
     if let Some(channel_id) = selected_after_sync.as_ref() {
         let route = uuid::Uuid::parse_str(&channel_id)
@@ -3157,6 +4240,9 @@ async fn connect_and_run_session(
                 };
             }
         }
+        if read_receipts_enabled {
+            refresh_read_markers(&dispatcher, channel_id, tx_event).await;
+        }
     } else {
         active_voice_channel_route.store(0, Ordering::Relaxed);
     }
@@ -3189,7 +4275,7 @@ async fn connect_and_run_session(
 
     let voice_max_inbound = mtu.saturating_sub(vp_voice::FORWARDER_ADDED_HEADER_BYTES);
     let max_opus_payload_runtime =
-        voice_max_inbound.saturating_sub(vp_voice::CLIENT_VOICE_HEADER_BYTES);
+        voice_max_inbound.saturating_sub(vp_voice::CLIENT_VOICE_HEADER_WITH_LOUDNESS_BYTES);
     let _ = tx_event.send(UiEvent::AppendLog(format!(
         "[net] mtu={} voice_max_inbound={} max_opus_payload={}",
         mtu, voice_max_inbound, max_opus_payload_runtime
@@ -3218,6 +4304,7 @@ async fn connect_and_run_session(
         voice_counters.clone(),
         network_telemetry.clone(),
         send_queue_drop_count.clone(),
+        voice_impairment.clone(),
         local_user_id.clone(),
         voice_die_tx.clone(),
     ));
@@ -3247,6 +4334,7 @@ async fn connect_and_run_session(
         local_user_id.clone(),
         self_deafened.clone(),
         server_deafened.clone(),
+        local_mute_all.clone(),
         output_gain.clone(),
         per_user_audio.clone(),
         audio_runtime.clone(),
@@ -3255,6 +4343,7 @@ async fn connect_and_run_session(
         voice_counters.clone(),
         voice_stale_drops_total.clone(),
         voice_drain_drops_total.clone(),
+        voice_impairment.clone(),
         voice_die_tx.clone(),
     ));
 
@@ -3267,6 +4356,13 @@ async fn connect_and_run_session(
     let disp_keepalive = dispatcher.clone();
     let disp_health = dispatcher.clone();
     let disp_voice_rr = dispatcher.clone();
+    let (self_voice_tx, self_voice_rx) = watch::channel(None);
+    tokio::spawn(self_voice_state_sync_loop(
+        dispatcher.clone(),
+        self_voice_rx,
+        tx_event.clone(),
+    ));
+
     let ctl_keepalive = tokio::spawn(async move {
         let mut interval = tokio::time::interval(Duration::from_secs(10));
         loop {
@@ -3353,6 +4449,11 @@ async fn connect_and_run_session(
     let mut layer_selection_policy = ViewerLayerSelectionPolicy::new(1);
     let mut last_decode_sample = (0_u64, 0_u64);
     let mut consecutive_audio_stalls = 0_u32;
+    // (sent, lost) QUIC packet totals at the previous health tick.
+    let mut last_transport_sample = (0_u64, 0_u64);
+    network_telemetry
+        .transport_loss_ppm
+        .store(0, Ordering::Relaxed);
     let mut last_stall_recovery_notice = Instant::now() - Duration::from_secs(30);
     loop {
         tokio::select! {
@@ -3661,6 +4762,15 @@ async fn connect_and_run_session(
                 network_telemetry
                     .rtt_ms
                     .store(ping_rtt_ms, Ordering::Relaxed);
+                let path = conn.stats().path;
+                network_telemetry.transport_loss_ppm.store(
+                    transport_loss_ppm(
+                        path.sent_packets.saturating_sub(last_transport_sample.0),
+                        path.lost_packets.saturating_sub(last_transport_sample.1),
+                    ),
+                    Ordering::Relaxed,
+                );
+                last_transport_sample = (path.sent_packets, path.lost_packets);
 
                 let capture_healthy = {
                     let cap = capture.read().await;
@@ -3738,6 +4848,7 @@ async fn connect_and_run_session(
                             let new_server = format!("{host}:{port}");
                             cfg.server = new_server.clone();
                             cfg.server_name = host.clone();
+                            *resume_session_id = None;
                             let _ = tx_event.send(UiEvent::SetServerAddress { host, port });
                             set_connection_stage(
                                 tx_event,
@@ -3777,13 +4888,34 @@ async fn connect_and_run_session(
                                 let new = !self_muted.load(Ordering::Relaxed);
                                 self_muted.store(new, Ordering::Relaxed);
                                 let _ = tx_event.send(UiEvent::SetSelfMuted(new));
+                                if let Some(ref ch) = active_channel {
+                                    sync_self_voice_state(
+                                        &self_voice_tx,
+                                        ch,
+                                        &self_muted,
+                                        &self_deafened,
+                                    );
+                                }
                             }
                         }
+                        UiIntent::LocalMuteAll | UiIntent::LocalUnmuteAll => {
+                            let muted = matches!(intent, UiIntent::LocalMuteAll);
+                            local_mute_all.store(muted, Ordering::Relaxed);
+                            let _ = tx_event.send(UiEvent::SetLocalMuteAll(muted));
+                        }
                         UiIntent::ToggleSelfDeafen => {
                             if active_voice_channel_route.load(Ordering::Relaxed) != 0 {
                                 let new = !self_deafened.load(Ordering::Relaxed);
                                 self_deafened.store(new, Ordering::Relaxed);
                                 let _ = tx_event.send(UiEvent::SetSelfDeafened(new));
+                                if let Some(ref ch) = active_channel {
+                                    sync_self_voice_state(
+                                        &self_voice_tx,
+                                        ch,
+                                        &self_muted,
+                                        &self_deafened,
+                                    );
+                                }
                             }
                         }
                         UiIntent::SendChat { text, attachments } => {
@@ -3830,7 +4962,7 @@ async fn connect_and_run_session(
 
                                 let _ = tx_event.send(UiEvent::MessageReceived(
                                     ui::model::ChatMessage {
-                                        message_id: local_message_id,
+                                        message_id: local_message_id.clone(),
                                         channel_id: ch.clone(),
                                         author_id: local_user_id.clone(),
                                         author_name: cfg.display_name.clone(),
@@ -3843,6 +4975,7 @@ async fn connect_and_run_session(
                                         reactions: Vec::new(),
                                         pinned: false,
                                         edited: false,
+                                        system: false,
                                     },
                                 ));
                                 let _ = tx_event.send(UiEvent::PlayChatMessageSfx);
@@ -3868,6 +5001,28 @@ async fn connect_and_run_session(
                                     })
                                     .collect();
                                 if let Err(e) = dispatcher.send_chat(ch, &text, pb_attachments).await {
+                                    // A definite rejection (slow mode, too long, ...) means the
+                                    // message was never stored: drop the local echo.
+                                    if let Some(server_err) = e.downcast_ref::<ServerError>() {
+                                        let _ = tx_event.send(UiEvent::MessageDeleted {
+                                            channel_id: ch.clone(),
+                                            message_id: local_message_id,
+                                        });
+                                        let text = match server_err.slow_mode_wait() {
+                                            Some(wait) => {
+                                                let _ = tx_event.send(UiEvent::ChatCooldown {
+                                                    channel_id: ch.clone(),
+                                                    retry_after: wait,
+                                                });
+                                                format!("Slow mode: wait {}s", wait.as_secs_f32().ceil() as u64)
+                                            }
+                                            None => format!("Message not sent: {}", server_err.message),
+                                        };
+                                        let _ = tx_event.send(UiEvent::Notify {
+                                            text,
+                                            kind: ui::model::NotificationKind::Error,
+                                        });
+                                    }
                                     let _ = tx_event.send(UiEvent::AppendLog(format!(
                                         "[ctl] send_chat failed: {e:#}",
                                     )));
@@ -3900,11 +5055,54 @@ async fn connect_and_run_session(
                                 }
                             }
                         }
+                        UiIntent::LoadOlderMessages { channel_id, before } => {
+                            load_chat_history(
+                                &dispatcher,
+                                &conn,
+                                &channel_id,
+                                (!before.is_empty()).then_some(before.as_str()),
+                                &tx_event,
+                            )
+                            .await;
+                        }
+                        UiIntent::EditMessage {
+                            message_id,
+                            new_text,
+                        } => {
+                            if let Some(ref ch) = active_channel {
+                                if let Err(e) =
+                                    dispatcher.edit_message(ch, &message_id, &new_text).await
+                                {
+                                    let _ = tx_event.send(UiEvent::AppendLog(format!(
+                                        "[ctl] edit_message failed: {e:#}",
+                                    )));
+                                }
+                            }
+                        }
+                        UiIntent::DeleteMessage { message_id } => {
+                            if let Some(ref ch) = active_channel {
+                                if let Err(e) = dispatcher.delete_message(ch, &message_id).await {
+                                    let _ = tx_event.send(UiEvent::AppendLog(format!(
+                                        "[ctl] delete_message failed: {e:#}",
+                                    )));
+                                }
+                            }
+                        }
                         UiIntent::SendTyping => {
                             if let Some(ref ch) = active_channel {
                                 let _ = dispatcher.send_typing(ch).await;
                             }
                         }
+                        UiIntent::MarkRead {
+                            channel_id,
+                            message_id,
+                        } => {
+                            if let Err(e) = dispatcher.mark_read(&channel_id, &message_id).await {
+                                let _ = tx_event.send(UiEvent::AppendLog(format!(
+                                    "[chat] mark_read failed: {e:#}"
+                                )));
+                            }
+                        }
                         UiIntent::OpenAttachment { attachment } => {
                             match resolve_attachment_local_path(&conn, &attachment).await {
                                 Ok(path) => {
@@ -3932,7 +5130,7 @@ async fn connect_and_run_session(
                                             encoder_profile_for_mode(saved_settings.voice_processing_mode),
                                         ) {
                                             Ok(mut new_encoder) => {
-                                                let _ = new_encoder.set_bitrate(info.bitrate as i32);
+                                                let _ = new_encoder.set_bitrate(audio_runtime.capped_voice_bitrate(info.bitrate as i32));
                                                 let _ = apply_fec_encoder_settings(&mut new_encoder, &audio_runtime);
                                                 *enc = new_encoder;
                                             }
@@ -3952,6 +5150,8 @@ async fn connect_and_run_session(
                                         );
                                     }
                                     active_channel = Some(channel_id.clone());
+                                    // Reconnects re-join from here.
+                                    cfg.channel_id = Some(channel_id.clone());
                                     *active_channel_for_reports.write().await = active_channel.clone();
                                     if let Ok(mut mode) = active_channel_audio_mode.write() {
                                         *mode = ChannelAudioMode {
@@ -3978,7 +5178,23 @@ async fn connect_and_run_session(
                                         .unwrap_or(0);
                                     active_voice_channel_route.store(route, Ordering::Relaxed);
                                     let _ = tx_event.send(UiEvent::SetActiveVoiceRoute(route));
+                                    // Whisper targets are members of the channel we left.
+                                    if audio_runtime.set_whisper_targets(Vec::new()) {
+                                        let _ = tx_event.send(UiEvent::WhisperTargetsChanged(Vec::new()));
+                                    }
                                     let _ = tx_event.send(UiEvent::SetChannelName(channel_id.clone()));
+                                    let _ = tx_event.send(UiEvent::SetChannelUserLimit {
+                                        channel_id: channel_id.clone(),
+                                        user_limit: state.user_limit(),
+                                    });
+                                    // The server forgets self mute/deafen when we leave (or
+                                    // drop), but the UI keeps them across reconnects.
+                                    sync_self_voice_state(
+                                        &self_voice_tx,
+                                        &channel_id,
+                                        &self_muted,
+                                        &self_deafened,
+                                    );
                                                                         let mut members = Vec::with_capacity(state.members.len());
                                     for m in state.members {
                                         let avatar_url = if m.avatar_asset_url.trim().is_empty() {
@@ -4015,11 +5231,24 @@ async fn connect_and_run_session(
                                         channel_id: channel_id.clone(),
                                         members,
                                     });
+                                    load_chat_history(&dispatcher, &conn, &channel_id, None, &tx_event)
+                                        .await;
+                                    if read_receipts_enabled {
+                                        refresh_read_markers(&dispatcher, &channel_id, &tx_event).await;
+                                    }
                                     let _ = tx_event.send(UiEvent::AppendLog(
                                         format!("[ctl] joined channel {channel_id}"),
                                     ));
                                 }
                                 Err(e) => {
+                                    // A full channel is a normal answer, not a
+                                    // session problem: stay where we are.
+                                    if e.downcast_ref::<ServerError>().is_some_and(ServerError::is_channel_full) {
+                                        let _ = tx_event.send(UiEvent::Notify {
+                                            text: "Channel is full".to_string(),
+                                            kind: ui::model::NotificationKind::Error,
+                                        });
+                                    }
                                     let _ = tx_event.send(UiEvent::AppendLog(
                                         format!("[ctl] join failed: {e:#}"),
                                     ));
@@ -4035,6 +5264,7 @@ async fn connect_and_run_session(
                                 }
                             }
                             active_channel = None;
+                            cfg.channel_id = None;
                             *active_channel_for_reports.write().await = None;
                             if let Ok(mut mode) = active_channel_audio_mode.write() {
                                 *mode = ChannelAudioMode::default();
@@ -4043,6 +5273,34 @@ async fn connect_and_run_session(
                             active_voice_channel_route.store(0, Ordering::Relaxed);
                             let _ = tx_event.send(UiEvent::SetActiveVoiceRoute(0));
                         }
+                        UiIntent::SubscribeChannel { channel_id } => {
+                            match dispatcher.subscribe_channel(&channel_id).await {
+                                Ok(()) => {
+                                    chat_subscriptions.insert(channel_id.clone());
+                                    let _ = tx_event.send(UiEvent::SetChannelChatSubscribed {
+                                        channel_id,
+                                        subscribed: true,
+                                    });
+                                }
+                                Err(e) => {
+                                    let _ = tx_event.send(UiEvent::AppendLog(
+                                        format!("[ctl] subscribe failed: {e:#}"),
+                                    ));
+                                }
+                            }
+                        }
+                        UiIntent::UnsubscribeChannel { channel_id } => {
+                            if let Err(e) = dispatcher.unsubscribe_channel(&channel_id).await {
+                                let _ = tx_event.send(UiEvent::AppendLog(
+                                    format!("[ctl] unsubscribe failed: {e:#}"),
+                                ));
+                            }
+                            chat_subscriptions.remove(&channel_id);
+                            let _ = tx_event.send(UiEvent::SetChannelChatSubscribed {
+                                channel_id,
+                                subscribed: false,
+                            });
+                        }
                         UiIntent::CreateChannel { name, description, channel_type, codec, quality, user_limit, parent_channel_id } => {
                             match dispatcher.create_channel(&name, &description, channel_type, codec, quality * 1000, user_limit, parent_channel_id.as_deref()).await {
                                 Ok(ch_id) => {
@@ -4074,6 +5332,26 @@ async fn connect_and_run_session(
                                 }
                             }
                         }
+                        UiIntent::SetChannelChatLimits {
+                            channel_id,
+                            max_message_length,
+                            slow_mode_secs,
+                            announce_presence,
+                        } => {
+                            if let Err(e) = dispatcher
+                                .set_channel_chat_limits(
+                                    &channel_id,
+                                    max_message_length,
+                                    slow_mode_secs,
+                                    announce_presence,
+                                )
+                                .await
+                            {
+                                let _ = tx_event.send(UiEvent::AppendLog(format!(
+                                    "[ctl] set_channel_chat_limits failed: {e:#}"
+                                )));
+                            }
+                        }
                         UiIntent::DeleteChannel { channel_id } => {
                             match dispatcher.delete_channel(&channel_id).await {
                                 Ok(()) => {
@@ -4120,7 +5398,9 @@ async fn connect_and_run_session(
                                     encoder_profile_for_mode(saved_settings.voice_processing_mode),
                                 ) {
                                     Ok(mut new_encoder) => {
-                                        let _ = new_encoder.set_bitrate(bitrate as i32);
+                                        let _ = new_encoder.set_bitrate(
+                                            audio_runtime.capped_voice_bitrate(bitrate as i32),
+                                        );
                                         let _ = apply_fec_encoder_settings(&mut new_encoder, &audio_runtime);
                                         *enc = new_encoder;
                                     }
@@ -4130,8 +5410,7 @@ async fn connect_and_run_session(
                                         )));
                                     }
                                 }
-                                persist_settings(&tx_event, &saved_settings);
-                                activity_runtime.apply(&saved_settings);
+                                commit_settings(&tx_event, &activity_runtime, &saved_settings);
                             }
                             UiIntent::SetDspEnabled(enabled) => {
                             saved_settings.dsp_enabled = enabled;
@@ -4206,6 +5485,21 @@ async fn connect_and_run_session(
                             info!("[audio] set echo_cancellation={enabled}");
                             persist_settings(tx_event, &saved_settings);
                         }
+                        UiIntent::SetEchoReferenceDelay(samples) => {
+                            saved_settings.echo_reference_delay_samples = samples;
+                            if let Some(ref dsp) = capture_dsp {
+                                let mut d = dsp.lock().await;
+                                d.set_echo_reference_delay_samples(samples);
+                            }
+                            info!("[audio] set echo_reference_delay_samples={samples}");
+                            persist_settings(tx_event, &saved_settings);
+                        }
+                        UiIntent::SetVadHoldMs(ms) => {
+                            saved_settings.vad_hold_ms = ms;
+                            audio_runtime.vad_hold_ms.store(ms, Ordering::Relaxed);
+                            info!("[audio] set vad_hold_ms={ms}");
+                            persist_settings(tx_event, &saved_settings);
+                        }
                         UiIntent::SetTypingAttenuation(enabled) => {
                             saved_settings.typing_attenuation = enabled;
                             audio_runtime
@@ -4238,6 +5532,83 @@ async fn connect_and_run_session(
                             }
                             persist_settings(tx_event, &saved_settings);
                         }
+                        UiIntent::SetNetworkRobustness(profile) => {
+                            saved_settings.network_robustness = profile;
+                            audio_runtime
+                                .network_robustness
+                                .store(profile as u32, Ordering::Relaxed);
+                            let mut enc = encoder.lock().await;
+                            if let Err(e) = apply_fec_encoder_settings(&mut enc, &audio_runtime) {
+                                let _ = tx_event.send(UiEvent::AppendLog(format!(
+                                    "[audio] failed to apply network robustness: {e:#}"
+                                )));
+                            }
+                            info!("[audio] set network_robustness={profile:?}");
+                            persist_settings(tx_event, &saved_settings);
+                        }
+                        UiIntent::SetVoiceFramesPerDatagram(frames) => {
+                            saved_settings.voice_frames_per_datagram = frames;
+                            audio_runtime
+                                .voice_frames_per_datagram
+                                .store(frames, Ordering::Relaxed);
+                            info!("[audio] set voice_frames_per_datagram={frames}");
+                            persist_settings(tx_event, &saved_settings);
+                        }
+                        UiIntent::SetFrameMs(frame_ms) => {
+                            saved_settings.frame_ms = audio::normalize_frame_ms(frame_ms);
+                            audio_runtime
+                                .frame_ms
+                                .store(saved_settings.frame_ms, Ordering::Relaxed);
+                            info!("[audio] set frame_ms={}", saved_settings.frame_ms);
+                            persist_settings(tx_event, &saved_settings);
+                        }
+                        UiIntent::SetOpusMaxBandwidth(bandwidth) => {
+                            saved_settings.opus_max_bandwidth = bandwidth;
+                            audio_runtime
+                                .opus_max_bandwidth
+                                .store(bandwidth.ctl_value(), Ordering::Relaxed);
+                            let mut enc = encoder.lock().await;
+                            if let Err(e) = apply_fec_encoder_settings(&mut enc, &audio_runtime) {
+                                let _ = tx_event.send(UiEvent::AppendLog(format!(
+                                    "[audio] failed to apply max bandwidth: {e:#}"
+                                )));
+                            }
+                            info!("[audio] set opus_max_bandwidth={bandwidth:?}");
+                            persist_settings(tx_event, &saved_settings);
+                        }
+                        UiIntent::SetVoiceBitrate(kbps) => {
+                            saved_settings.voice_bitrate_limit_kbps = kbps;
+                            // The send loop re-applies the bitrate on its next frame.
+                            audio_runtime
+                                .voice_bitrate_limit_bps
+                                .store(kbps.saturating_mul(1000), Ordering::Relaxed);
+                            info!("[audio] set voice_bitrate_limit_kbps={kbps}");
+                            persist_settings(tx_event, &saved_settings);
+                        }
+                        UiIntent::SetOpusComplexity(complexity) => {
+                            saved_settings.opus_complexity = complexity;
+                            audio_runtime
+                                .opus_complexity
+                                .store(complexity, Ordering::Relaxed);
+                            info!("[audio] set opus_complexity={complexity}");
+                            persist_settings(tx_event, &saved_settings);
+                        }
+                        UiIntent::SetDtx(enabled) => {
+                            saved_settings.opus_dtx = enabled;
+                            audio_runtime.opus_dtx.store(enabled, Ordering::Relaxed);
+                            info!("[audio] set opus_dtx={enabled}");
+                            persist_settings(tx_event, &saved_settings);
+                        }
+                        UiIntent::SetLowBandwidthMode(enabled) => {
+                            saved_settings.low_bandwidth_mode = enabled;
+                            audio_runtime.low_bandwidth.store(enabled, Ordering::Relaxed);
+                            info!("[audio] set low_bandwidth_mode={enabled}");
+                            // Capabilities only travel in Hello.
+                            let _ = tx_event.send(UiEvent::AppendLog(
+                                "[audio] low-bandwidth mode: voice settings applied; streaming/video capabilities update on next connect".into(),
+                            ));
+                            persist_settings(tx_event, &saved_settings);
+                        }
                         UiIntent::SetVadThreshold(threshold) => {
                             saved_settings.vad_threshold = threshold;
                             if let Some(ref dsp) = capture_dsp {
@@ -4271,6 +5642,12 @@ async fn connect_and_run_session(
                             info!("[audio] set mono_expansion={enabled}");
                             persist_settings(tx_event, &saved_settings);
                         }
+                        UiIntent::SetSpatialAudio(enabled) => {
+                            saved_settings.spatial_audio = enabled;
+                            audio_runtime.spatial_audio.store(enabled, Ordering::Relaxed);
+                            info!("[audio] set spatial_audio={enabled}");
+                            persist_settings(tx_event, &saved_settings);
+                        }
                         UiIntent::SetComfortNoise(enabled) => {
                             saved_settings.comfort_noise = enabled;
                             audio_runtime.comfort_noise.store(enabled, Ordering::Relaxed);
@@ -4706,6 +6083,12 @@ async fn connect_and_run_session(
                                 )));
                             }
                         }
+                        UiIntent::RefreshAudioDevices => {
+                            let tx_event = tx_event.clone();
+                            tokio::spawn(async move {
+                                report_audio_devices(&tx_event).await;
+                            });
+                        }
                         UiIntent::SetCaptureMode(mode) => {
                             {
                                 let mut state = selected_audio.lock().await;
@@ -5036,6 +6419,7 @@ async fn connect_and_run_session(
                                 d.set_agc_preset(settings.agc_preset);
                                 d.set_agc_target(settings.agc_target_db);
                                 d.set_echo_cancellation(settings.echo_cancellation);
+                                d.set_echo_reference_delay_samples(settings.echo_reference_delay_samples);
                                 d.set_echo_reference_enabled(should_enable_aec_reference(&settings.playback_device));
                             }
                             input_gain.store(f32_to_u32(settings.input_gain), Ordering::Relaxed);
@@ -5342,11 +6726,35 @@ async fn connect_and_run_session(
                         }
                         UiIntent::PokeUser { user_id, message } => {
                             if let Err(e) = dispatcher.poke_user(&user_id, &message).await {
+                                if let Some(text) = e.downcast_ref::<ServerError>().and_then(ServerError::poke_refusal) {
+                                    let _ = tx_event.send(UiEvent::Notify {
+                                        text,
+                                        kind: ui::model::NotificationKind::Error,
+                                    });
+                                }
                                 let _ = tx_event.send(UiEvent::AppendLog(format!("[moderation] poke failed: {e:#}")));
                             } else {
                                 let _ = tx_event.send(UiEvent::AppendLog(format!("[moderation] poked {user_id}")));
                             }
                         }
+                        UiIntent::SetWhisperTargets { mut targets } => {
+                            if !targets.is_empty() && !audio_runtime.voice_whisper.load(Ordering::Relaxed) {
+                                let _ = tx_event.send(UiEvent::Notify {
+                                    text: "This server does not support whispering".into(),
+                                    kind: ui::model::NotificationKind::Error,
+                                });
+                            } else {
+                                targets.retain(|id| uuid::Uuid::parse_str(id).is_ok());
+                                targets.truncate(vp_voice::MAX_WHISPER_TARGETS);
+                                let hashes = targets
+                                    .iter()
+                                    .filter_map(|id| uuid::Uuid::parse_str(id).ok())
+                                    .map(|id| vp_voice::whisper_target_hash(id.as_bytes()))
+                                    .collect();
+                                audio_runtime.set_whisper_targets(hashes);
+                                let _ = tx_event.send(UiEvent::WhisperTargetsChanged(targets));
+                            }
+                        }
                         UiIntent::FetchUserProfile { user_id } => {
                             let request = pb::GetUserProfileRequest {
                                 user_id: Some(pb::UserId { value: user_id.clone() }),
@@ -5511,6 +6919,34 @@ async fn connect_and_run_session(
                                 }
                             }
                         }
+                        UiIntent::BanUser { user_id, reason, duration } => {
+                            if let Some(ref ch) = active_channel {
+                                let action = pb::moderation_action_request::Action::Ban(pb::BanUser { reason, duration_seconds: duration });
+                                if let Err(e) = dispatcher.moderate_user(ch, &user_id, action).await {
+                                    let _ = tx_event.send(UiEvent::AppendLog(format!("[moderation] ban failed: {e:#}")));
+                                }
+                            }
+                        }
+                        UiIntent::MoveUser { user_id, target_channel_id } => {
+                            if let Some(ref ch) = active_channel {
+                                let action = pb::moderation_action_request::Action::Move(pb::MoveUser {
+                                    target_channel_id: Some(pb::ChannelId { value: target_channel_id }),
+                                });
+                                if let Err(e) = dispatcher.moderate_user(ch, &user_id, action).await {
+                                    let _ = tx_event.send(UiEvent::AppendLog(format!("[moderation] move failed: {e:#}")));
+                                }
+                            }
+                        }
+                        UiIntent::DisconnectUser { user_id } => {
+                            match dispatcher.disconnect_session(&user_id, "").await {
+                                Ok(closed) => {
+                                    let _ = tx_event.send(UiEvent::AppendLog(format!("[moderation] disconnected {closed} session(s) of {user_id}")));
+                                }
+                                Err(e) => {
+                                    let _ = tx_event.send(UiEvent::AppendLog(format!("[moderation] disconnect failed: {e:#}")));
+                                }
+                            }
+                        }
                         UiIntent::PermsSaveRoleEdits {
                             role_id,
                             name,
@@ -5723,6 +7159,13 @@ async fn connect_and_run_session(
                 let _ = tx_event.send(UiEvent::VoiceSessionHealth(false));
                 return Err(anyhow!("control keepalive ended: {:?}", r));
             }
+
+            Some(reason) = going_away_rx.recv() => {
+                let _ = tx_event.send(UiEvent::VoiceSessionHealth(false));
+                // A draining gateway doesn't park sessions for resume.
+                *resume_session_id = None;
+                return Err(ServerGoingAway(reason).into());
+            }
         }
     }
 }
@@ -6042,6 +7485,29 @@ fn cached_attachment_filename(attachment: &ui::model::AttachmentData) -> String
     format!("{}-{}", cache_asset_key(attachment), safe_name)
 }
 
+fn voice_impairment_from_config(
+    cfg: &Config,
+    tx_event: &Sender<UiEvent>,
+) -> Option<Arc<VoiceImpairment>> {
+    let raw = cfg.debug_voice_impairment.as_deref()?;
+    match ImpairmentSpec::parse(raw) {
+        Ok(spec) => {
+            let impairment = VoiceImpairment::new(spec)?;
+            warn!(spec = ?impairment.spec(), "DEBUG voice impairment enabled");
+            let _ = tx_event.send(UiEvent::AppendLog(format!(
+                "[debug] simulated voice impairment ACTIVE: {raw}"
+            )));
+            Some(Arc::new(impairment))
+        }
+        Err(e) => {
+            let _ = tx_event.send(UiEvent::AppendLog(format!(
+                "[debug] ignoring invalid --debug-voice-impairment '{raw}': {e}"
+            )));
+            None
+        }
+    }
+}
+
 async fn emit_telemetry_loop(
     tx_event: Sender<UiEvent>,
     capture_dsp: Option<Arc<Mutex<audio::dsp::CaptureDsp>>>,
@@ -6049,18 +7515,23 @@ async fn emit_telemetry_loop(
     counters: Arc<VoiceTelemetryCounters>,
     network_telemetry: Arc<SharedNetworkTelemetry>,
     send_queue_drop_count: Arc<AtomicU32>,
+    voice_impairment: Option<Arc<VoiceImpairment>>,
     running: Arc<AtomicBool>,
     mut shutdown_rx: watch::Receiver<bool>,
 ) {
-    let mut tick = tokio::time::interval(Duration::from_secs(1));
+    let mut tick = tokio::time::interval(TELEMETRY_INTERVAL);
+    let mut last_sample = Instant::now();
 
     let mut prev_tx_packets = 0u64;
     let mut prev_tx_bytes = 0u64;
     let mut prev_rx_packets = 0u64;
     let mut prev_rx_bytes = 0u64;
     let mut prev_late = 0u64;
+    let mut prev_reordered = 0u64;
     let mut prev_lost = 0u64;
     let mut prev_conceal = 0u64;
+    let mut prev_sim_dropped = 0u64;
+    let mut prev_sim_delayed = 0u64;
 
     while running.load(Ordering::Relaxed) && !*shutdown_rx.borrow() {
         tokio::select! {
@@ -6071,12 +7542,17 @@ async fn emit_telemetry_loop(
             }
             _ = tick.tick() => {}
         }
+        // The first tick fires immediately; rate it as a full interval.
+        let now = Instant::now();
+        let elapsed = now.duration_since(last_sample).max(TELEMETRY_INTERVAL);
+        last_sample = now;
 
         let tx_packets = counters.tx_packets.load(Ordering::Relaxed);
         let tx_bytes = counters.tx_bytes.load(Ordering::Relaxed);
         let rx_packets = counters.rx_packets.load(Ordering::Relaxed);
         let rx_bytes = counters.rx_bytes.load(Ordering::Relaxed);
         let late = counters.late_packets.load(Ordering::Relaxed);
+        let reordered = counters.reordered_packets.load(Ordering::Relaxed);
         let lost = counters.lost_packets.load(Ordering::Relaxed);
         let conceal = counters.concealment_frames.load(Ordering::Relaxed);
         let jitter_buffer_depth = counters.jitter_buffer_depth.load(Ordering::Relaxed) as u32;
@@ -6086,10 +7562,11 @@ async fn emit_telemetry_loop(
                 .swap(0.0f32.to_bits(), Ordering::Relaxed),
         );
 
-        let tx_pps = tx_packets.saturating_sub(prev_tx_packets) as u32;
-        let rx_pps = rx_packets.saturating_sub(prev_rx_packets) as u32;
-        let tx_bitrate_bps = (tx_bytes.saturating_sub(prev_tx_bytes) * 8) as u32;
-        let rx_bitrate_bps = (rx_bytes.saturating_sub(prev_rx_bytes) * 8) as u32;
+        let rx_delta = rx_packets.saturating_sub(prev_rx_packets);
+        let tx_pps = per_second(tx_packets.saturating_sub(prev_tx_packets), elapsed);
+        let rx_pps = per_second(rx_delta, elapsed);
+        let tx_bitrate_bps = per_second(tx_bytes.saturating_sub(prev_tx_bytes) * 8, elapsed);
+        let rx_bitrate_bps = per_second(rx_bytes.saturating_sub(prev_rx_bytes) * 8, elapsed);
 
         prev_tx_packets = tx_packets;
         prev_tx_bytes = tx_bytes;
@@ -6097,17 +7574,21 @@ async fn emit_telemetry_loop(
         prev_rx_bytes = rx_bytes;
 
         let late_delta = late.saturating_sub(prev_late) as u32;
+        let reordered_delta = reordered.saturating_sub(prev_reordered) as u32;
         let lost_delta = lost.saturating_sub(prev_lost) as u32;
         let conceal_delta = conceal.saturating_sub(prev_conceal) as u32;
 
         prev_late = late;
+        prev_reordered = reordered;
         prev_lost = lost;
         prev_conceal = conceal;
 
-        let observed_packets = rx_pps.saturating_add(lost_delta).max(1);
+        let observed_packets = (rx_delta as u32).saturating_add(lost_delta).max(1);
         let loss_rate = (lost_delta as f32 / observed_packets as f32).clamp(0.0, 1.0);
         let rtt_ms = network_telemetry.rtt_ms.load(Ordering::Relaxed);
-        let jitter_ms = (jitter_buffer_depth.saturating_mul(4)).clamp(0, 250);
+        let transport_loss_rate =
+            network_telemetry.transport_loss_ppm.load(Ordering::Relaxed) as f32 / 1_000_000.0;
+        let jitter_ms = counters.jitter_ms.load(Ordering::Relaxed).min(250);
         network_telemetry
             .loss_ppm
             .store((loss_rate * 1_000_000.0) as u32, Ordering::Relaxed);
@@ -6115,20 +7596,41 @@ async fn emit_telemetry_loop(
             .jitter_ms
             .store(jitter_ms, Ordering::Relaxed);
 
-        let (agc_gain_db, vad_probability) = if dsp_enabled.load(Ordering::Relaxed) {
-            if let Some(ref dsp) = capture_dsp {
-                let d = dsp.lock().await;
-                (d.agc_gain_db(), d.last_vad_probability())
-            } else {
-                (0.0, 0.0)
+        let (simulated_drops, simulated_delays) = match voice_impairment.as_deref() {
+            Some(imp) => {
+                let dropped = imp.counters.dropped.load(Ordering::Relaxed);
+                let delayed = imp.counters.delayed.load(Ordering::Relaxed);
+                let deltas = (
+                    dropped.saturating_sub(prev_sim_dropped) as u32,
+                    delayed.saturating_sub(prev_sim_delayed) as u32,
+                );
+                prev_sim_dropped = dropped;
+                prev_sim_delayed = delayed;
+                deltas
             }
-        } else {
-            (0.0, 0.0)
+            None => (0, 0),
         };
 
+        let (agc_gain_db, vad_probability, echo_return_loss_db) =
+            if dsp_enabled.load(Ordering::Relaxed) {
+                if let Some(ref dsp) = capture_dsp {
+                    let d = dsp.lock().await;
+                    (
+                        d.agc_gain_db(),
+                        d.last_vad_probability(),
+                        d.echo_return_loss_db(),
+                    )
+                } else {
+                    (0.0, 0.0, None)
+                }
+            } else {
+                (0.0, 0.0, None)
+            };
+
         let _ = tx_event.send(UiEvent::TelemetryUpdate(ui::model::TelemetryData {
             rtt_ms,
             loss_rate,
+            transport_loss_rate,
             jitter_ms,
             tx_bitrate_bps,
             rx_bitrate_bps,
@@ -6136,13 +7638,21 @@ async fn emit_telemetry_loop(
             rx_pps,
             jitter_buffer_depth,
             late_packets: late_delta,
+            reordered_packets: reordered_delta,
             lost_packets: lost_delta,
             concealment_frames: conceal_delta,
             peak_stream_level,
             send_queue_drop_count: send_queue_drop_count.load(Ordering::Relaxed),
             playout_delay_ms: counters.playout_delay_ms.load(Ordering::Relaxed),
+            encode_errors: counters.encode_errors.load(Ordering::Relaxed) as u32,
+            decode_errors: counters.decode_errors.load(Ordering::Relaxed) as u32,
+            codec_resets: counters.codec_resets.load(Ordering::Relaxed) as u32,
             agc_gain_db,
             vad_probability,
+            echo_return_loss_db,
+            simulated_impairment: voice_impairment.is_some(),
+            simulated_drops,
+            simulated_delays,
         }));
     }
 }
@@ -6157,12 +7667,7 @@ async fn mic_test_loop(
     running: Arc<AtomicBool>,
     shutdown_rx: watch::Receiver<bool>,
 ) {
-    let sample_rate = 48_000u32;
-    let channels = 1usize;
-    let frame_ms = 20u32;
-    let frame_samples = (sample_rate as usize * frame_ms as usize / 1000) * channels;
-
-    let mut pcm = vec![0i16; frame_samples];
+    let mut pcm = vec![0i16; audio::SessionAudioConfig::default().frame_samples()];
     let mut tick = tokio::time::interval(Duration::from_millis(10));
 
     loop {
@@ -6189,12 +7694,18 @@ async fn mic_test_loop(
         }
 
         let playout_stream = playout.read().await.clone();
-        playout_stream.push_pcm(&pcm);
+        playout_stream.push_mono(&pcm);
         let waveform = build_mic_test_waveform(&pcm, 96);
         let _ = tx_event.send(UiEvent::MicTestWaveform(waveform));
     }
 }
 
+/// Voiced frames needed before the VAD gate opens.
+const VAD_ATTACK_MS: u32 = 60;
+/// Audio from before the gate opened that is sent with the first voiced
+/// frame; covers the attack time plus one frame of lead-in.
+const VAD_PRE_ROLL_MS: u32 = 80;
+
 async fn voice_send_loop(
     egress: Arc<EgressScheduler>,
     mtu: usize,
@@ -6218,46 +7729,112 @@ async fn voice_send_loop(
     voice_counters: Arc<VoiceTelemetryCounters>,
     network_telemetry: Arc<SharedNetworkTelemetry>,
     send_queue_drop_count: Arc<AtomicU32>,
+    voice_impairment: Option<Arc<VoiceImpairment>>,
     local_user_id: String,
     _voice_die_tx: watch::Sender<bool>,
 ) {
     let mut seq: u32 = 0;
     let ssrc: u32 = rand::random();
 
-    let sample_rate = 48_000u32;
-    let channels = 1usize;
-    let frame_ms = 20u32;
-    let frame_samples = (sample_rate as usize * frame_ms as usize / 1000) * channels;
+    let mut audio_cfg = audio_runtime.session_audio();
+    let mut frame_ms = audio_cfg.frame_ms;
 
-    let mut pcm = vec![0i16; frame_samples];
+    let mut pcm = vec![0i16; audio_cfg.frame_samples()];
     let mut enc_out = vec![0u8; 4000];
 
-    let mut tick = tokio::time::interval(Duration::from_millis(frame_ms as u64));
+    let mut tick = tokio::time::interval(audio_cfg.frame_duration());
     tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
     let mut vad_report_counter = 0u32;
     let mut stream_ts_ms = 0u32;
     let mut last_local_speaking = false;
     let mut last_oversize_warn = Instant::now();
     let voice_max_inbound = mtu.saturating_sub(vp_voice::FORWARDER_ADDED_HEADER_BYTES);
+    // Budget for the loudness byte whether or not the server takes it, so a
+    // payload never fits one header layout and not the other.
     let max_opus_payload_runtime =
-        voice_max_inbound.saturating_sub(vp_voice::CLIENT_VOICE_HEADER_BYTES);
-    let mut vad_hysteresis =
-        audio::dsp::vad::VadHysteresis::from_timing(0.6, 0.45, 60, 300, frame_ms);
+        voice_max_inbound.saturating_sub(vp_voice::CLIENT_VOICE_HEADER_WITH_LOUDNESS_BYTES);
+    let mut vad_hold_ms = audio_runtime.vad_hold_ms.load(Ordering::Relaxed);
+    let mut vad_hysteresis = audio::dsp::vad::VadHysteresis::from_timing(
+        0.6,
+        0.45,
+        VAD_ATTACK_MS,
+        vad_hold_ms,
+        frame_ms,
+    );
+    let mut pre_roll = audio::dsp::vad::PreRoll::from_timing(VAD_PRE_ROLL_MS, frame_ms);
     let mut adaptation = OpusAdaptationController::default();
+    let mut applied_robustness = audio_runtime.network_robustness();
+    let mut applied_low_bandwidth = audio_runtime.low_bandwidth();
+    let mut applied_max_bandwidth = audio_runtime.max_bandwidth();
+    let mut applied_bitrate_cap = audio_runtime.voice_bitrate_cap_bps();
+    let mut applied_complexity = audio_runtime.opus_complexity();
+    let mut applied_dtx = audio_runtime.opus_dtx.load(Ordering::Relaxed);
+    let mut coalescer = VoiceFrameCoalescer::default();
+    let mut encode_errors = CodecErrorTracker::default();
     {
         let init_bitrate = active_channel_audio_mode
             .read()
             .map(|m| m.bitrate_bps)
             .unwrap_or(64_000);
         if let Ok(mut enc) = encoder.try_lock() {
-            let _ =
-                apply_network_class_encoder_settings(&mut enc, NetworkClass::Good, init_bitrate);
+            let _ = apply_network_class_encoder_settings(
+                &mut enc,
+                NetworkClass::Good,
+                init_bitrate,
+                applied_robustness,
+                applied_low_bandwidth,
+                applied_max_bandwidth,
+                applied_bitrate_cap,
+            );
+            let _ = enc.set_complexity(applied_complexity);
+            let _ = enc.set_dtx(applied_dtx);
         }
     }
 
     loop {
         tick.tick().await;
 
+        let wanted_cfg = audio_runtime.session_audio();
+        if wanted_cfg != audio_cfg {
+            // Frames already coalesced were encoded at the old size; send them
+            // before the frame length changes under them.
+            let route = active_voice_channel_route.load(Ordering::Relaxed);
+            if let Some(d) = coalescer.take_datagram(route, ssrc, frame_ms) {
+                if route != 0 {
+                    enqueue_voice_datagram(
+                        &egress,
+                        d,
+                        &voice_counters,
+                        &send_queue_drop_count,
+                        &tx_event,
+                        voice_impairment.as_deref(),
+                    );
+                }
+            }
+            info!(
+                "[audio] voice send frame size {} -> {} ms",
+                frame_ms, wanted_cfg.frame_ms
+            );
+            audio_cfg = wanted_cfg;
+            frame_ms = audio_cfg.frame_ms;
+            pcm = vec![0i16; audio_cfg.frame_samples()];
+            tick = tokio::time::interval(audio_cfg.frame_duration());
+            tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            vad_hysteresis = audio::dsp::vad::VadHysteresis::from_timing(
+                0.6,
+                0.45,
+                VAD_ATTACK_MS,
+                vad_hold_ms,
+                frame_ms,
+            );
+            pre_roll = audio::dsp::vad::PreRoll::from_timing(VAD_PRE_ROLL_MS, frame_ms);
+        }
+        let wanted_hold_ms = audio_runtime.vad_hold_ms.load(Ordering::Relaxed);
+        if wanted_hold_ms != vad_hold_ms {
+            vad_hold_ms = wanted_hold_ms;
+            vad_hysteresis.set_hangover_ms(vad_hold_ms, frame_ms);
+        }
+
         loop {
             let capture_stream = capture.read().await.clone();
             if capture_stream.read_frame(&mut pcm) {
@@ -6285,7 +7862,7 @@ async fn voice_send_loop(
         // Loopback: feed capture directly to playout for mic testing
         if loopback_active.load(Ordering::Relaxed) {
             let playout_stream = playout.read().await.clone();
-            playout_stream.push_pcm(&pcm);
+            playout_stream.push_mono(&pcm);
             let waveform = build_mic_test_waveform(&pcm, 96);
             let _ = tx_event.send(UiEvent::MicTestWaveform(waveform));
         }
@@ -6298,6 +7875,21 @@ async fn voice_send_loop(
                 != ui::model::CaptureMode::PushToTalk
                 || ptt_active.load(Ordering::Relaxed));
         if !can_send {
+            // Flush a partially coalesced bundle (e.g. PTT release); drop it if
+            // we've left the channel.
+            let route = active_voice_channel_route.load(Ordering::Relaxed);
+            if let Some(d) = coalescer.take_datagram(route, ssrc, frame_ms) {
+                if route != 0 {
+                    enqueue_voice_datagram(
+                        &egress,
+                        d,
+                        &voice_counters,
+                        &send_queue_drop_count,
+                        &tx_event,
+                        voice_impairment.as_deref(),
+                    );
+                }
+            }
             if last_local_speaking {
                 last_local_speaking = false;
                 send_ui_realtime_event(
@@ -6320,7 +7912,14 @@ async fn voice_send_loop(
 
         let sample = NetworkSample {
             rtt_ms: network_telemetry.rtt_ms.load(Ordering::Relaxed),
-            loss_rate: network_telemetry.loss_ppm.load(Ordering::Relaxed) as f32 / 1_000_000.0,
+            // Our own packets' loss is what FEC protects; inbound voice
+            // loss stands in for it while the transport has none to report.
+            loss_rate: network_telemetry
+                .loss_ppm
+                .load(Ordering::Relaxed)
+                .max(network_telemetry.transport_loss_ppm.load(Ordering::Relaxed))
+                as f32
+                / 1_000_000.0,
             jitter_ms: network_telemetry.jitter_ms.load(Ordering::Relaxed),
             jitter_buffer_depth: voice_counters.jitter_buffer_depth.load(Ordering::Relaxed) as u32,
         };
@@ -6328,14 +7927,48 @@ async fn voice_send_loop(
             .read()
             .map(|mode| *mode)
             .unwrap_or_default();
-        if let Some(new_class) = adaptation.update(sample) {
+        let robustness = audio_runtime.network_robustness();
+        let low_bandwidth = audio_runtime.low_bandwidth();
+        let max_bandwidth = audio_runtime.max_bandwidth();
+        let bitrate_cap = audio_runtime.voice_bitrate_cap_bps();
+        let class_change = adaptation.update(sample);
+        if class_change.is_some()
+            || robustness != applied_robustness
+            || low_bandwidth != applied_low_bandwidth
+            || max_bandwidth != applied_max_bandwidth
+            || bitrate_cap != applied_bitrate_cap
+        {
+            applied_robustness = robustness;
+            applied_low_bandwidth = low_bandwidth;
+            applied_max_bandwidth = max_bandwidth;
+            applied_bitrate_cap = bitrate_cap;
             let mut enc = encoder.lock().await;
-            if let Err(e) =
-                apply_network_class_encoder_settings(&mut enc, new_class, channel_mode.bitrate_bps)
-            {
+            if let Err(e) = apply_network_class_encoder_settings(
+                &mut enc,
+                adaptation.class,
+                channel_mode.bitrate_bps,
+                robustness,
+                low_bandwidth,
+                max_bandwidth,
+                bitrate_cap,
+            ) {
                 warn!("[audio] failed to apply network-class opus settings: {e:#}");
             }
         }
+        let complexity = audio_runtime.opus_complexity();
+        if complexity != applied_complexity {
+            applied_complexity = complexity;
+            if let Err(e) = encoder.lock().await.set_complexity(complexity) {
+                warn!("[audio] failed to set opus complexity: {e:#}");
+            }
+        }
+        let dtx = audio_runtime.opus_dtx.load(Ordering::Relaxed);
+        if dtx != applied_dtx {
+            applied_dtx = dtx;
+            if let Err(e) = encoder.lock().await.set_dtx(dtx) {
+                warn!("[audio] failed to set opus dtx: {e:#}");
+            }
+        }
         let music_channel = is_music_channel(channel_mode);
 
         // Apply DSP pipeline (noise suppression + AGC + VAD)
@@ -6364,9 +7997,14 @@ async fn voice_send_loop(
             },
         );
 
-        let gated_on = match capture_mode_from_u8(capture_mode.load(Ordering::Relaxed)) {
+        let capture_mode_now = capture_mode_from_u8(capture_mode.load(Ordering::Relaxed));
+        let gated_on = match capture_mode_now {
             ui::model::CaptureMode::PushToTalk => ptt_active.load(Ordering::Relaxed),
-            ui::model::CaptureMode::Continuous => true,
+            // Low-bandwidth mode suppresses silent frames even in continuous
+            // mode: not sending silence at all saves more than Opus DTX,
+            // which still emits a keepalive frame every 400 ms.
+            ui::model::CaptureMode::Continuous if !low_bandwidth || music_channel => true,
+            ui::model::CaptureMode::Continuous => vad_hysteresis.update(vad_score),
             ui::model::CaptureMode::VoiceActivation => {
                 if music_channel {
                     true
@@ -6384,6 +8022,20 @@ async fn voice_send_loop(
             }
         }
 
+        // Keep the frames the VAD is still deciding about so the start of the
+        // word goes out with it. Push-to-talk starts exactly at the key press.
+        let pre_rolled = if capture_mode_now == ui::model::CaptureMode::PushToTalk {
+            pre_roll.clear();
+            Vec::new()
+        } else if !gated_on {
+            pre_roll.push(&pcm);
+            Vec::new()
+        } else if !last_local_speaking {
+            pre_roll.take()
+        } else {
+            Vec::new()
+        };
+
         if !gated_on {
             let mut attenuation_db =
                 u32_to_f32(audio_runtime.denoise_attenuation_db.load(Ordering::Relaxed));
@@ -6411,70 +8063,369 @@ async fn voice_send_loop(
         }
 
         if !speaking_now {
-            continue;
-        }
-
-        let n = match encoder.lock().await.encode(&pcm, &mut enc_out) {
-            Ok(n) => n,
-            Err(_) => continue,
-        };
-
-        if n > max_opus_payload_runtime {
-            voice_counters
-                .tx_oversized_payload_drops
-                .fetch_add(1, Ordering::Relaxed);
-            if last_oversize_warn.elapsed() >= Duration::from_secs(5) {
-                last_oversize_warn = Instant::now();
-                let _ = tx_event.send(UiEvent::AppendLog(format!(
-                    "[voice] dropping oversized opus payload: {} > {} bytes",
-                    n, max_opus_payload_runtime
-                )));
+            // Don't hold the tail of a talk spurt hostage to the coalescing target.
+            if let Some(d) = coalescer.take_datagram(
+                active_voice_channel_route.load(Ordering::Relaxed),
+                ssrc,
+                frame_ms,
+            ) {
+                enqueue_voice_datagram(
+                    &egress,
+                    d,
+                    &voice_counters,
+                    &send_queue_drop_count,
+                    &tx_event,
+                    voice_impairment.as_deref(),
+                );
             }
             continue;
         }
 
-        let d = make_voice_datagram(
-            active_voice_channel_route.load(Ordering::Relaxed),
-            ssrc,
-            seq,
-            stream_ts_ms,
-            gated_on,
-            &enc_out[..n],
-        );
-        seq = seq.wrapping_add(1);
-        stream_ts_ms = stream_ts_ms.wrapping_add(frame_ms);
-
-        debug_assert!(d.len() <= voice_max_inbound);
-
-        voice_counters.tx_packets.fetch_add(1, Ordering::Relaxed);
-        voice_counters
-            .tx_bytes
-            .fetch_add(d.len() as u64, Ordering::Relaxed);
-
-        match egress.enqueue_voice(d) {
-            Ok(report) => {
-                if let Some(dropped) = report.dropped {
-                    send_queue_drop_count.fetch_add(dropped.count, Ordering::Relaxed);
+        for frame in pre_rolled.iter().map(Vec::as_slice).chain([pcm.as_slice()]) {
+            let mut enc = encoder.lock().await;
+            let n = match enc.encode(frame, &mut enc_out) {
+                Ok(n) => {
+                    encode_errors.record_ok();
+                    n
                 }
-            }
-            Err(reason) => {
-                send_queue_drop_count.fetch_add(1, Ordering::Relaxed);
-                let _ = tx_event.send(UiEvent::AppendLog(format!(
-                    "[voice] egress enqueue rejected: {:?}",
-                    reason
-                )));
-            }
-        }
-    }
-}
-
-async fn voice_recv_loop(
-    voice_ingress_q: Arc<OverwriteQueue<StampedBytes>>,
+                Err(e) => {
+                    voice_counters.encode_errors.fetch_add(1, Ordering::Relaxed);
+                    let reset = encode_errors.record_error();
+                    if encode_errors.run_started() {
+                        warn!("[voice] opus encode failed: {e:#}");
+                        let _ = tx_event.send(UiEvent::AppendLog(format!(
+                            "[voice] opus encode failed: {e:#}"
+                        )));
+                    }
+                    if reset {
+                        voice_counters.codec_resets.fetch_add(1, Ordering::Relaxed);
+                        let outcome = match enc.reset() {
+                            Ok(()) => "encoder reset".to_string(),
+                            Err(e) => format!("encoder reset failed: {e:#}"),
+                        };
+                        warn!(
+                            "[voice] {CODEC_ERROR_RESET_THRESHOLD} consecutive encode errors; {outcome}"
+                        );
+                        let _ = tx_event.send(UiEvent::AppendLog(format!(
+                            "[voice] {CODEC_ERROR_RESET_THRESHOLD} consecutive encode errors; {outcome}"
+                        )));
+                    }
+                    continue;
+                }
+            };
+            let in_dtx = applied_dtx && enc.in_dtx();
+            drop(enc);
+
+            if in_dtx && n <= audio::opus::DTX_SKIPPABLE_FRAME_BYTES {
+                // Silence under DTX. Like a VAD-gated pause, seq and timestamp
+                // don't advance, so receivers conceal the gap without
+                // counting it as loss.
+                if let Some(d) = coalescer.take_datagram(
+                    active_voice_channel_route.load(Ordering::Relaxed),
+                    ssrc,
+                    frame_ms,
+                ) {
+                    enqueue_voice_datagram(
+                        &egress,
+                        d,
+                        &voice_counters,
+                        &send_queue_drop_count,
+                        &tx_event,
+                        voice_impairment.as_deref(),
+                    );
+                }
+                continue;
+            }
+            // Comfort-noise updates aren't speech; keep them from holding a
+            // talker slot on the forwarder.
+            let vad = gated_on && !in_dtx;
+
+            if n > max_opus_payload_runtime {
+                voice_counters
+                    .tx_oversized_payload_drops
+                    .fetch_add(1, Ordering::Relaxed);
+                if last_oversize_warn.elapsed() >= Duration::from_secs(5) {
+                    last_oversize_warn = Instant::now();
+                    let _ = tx_event.send(UiEvent::AppendLog(format!(
+                        "[voice] dropping oversized opus payload: {} > {} bytes",
+                        n, max_opus_payload_runtime
+                    )));
+                }
+                continue;
+            }
+
+            let route = active_voice_channel_route.load(Ordering::Relaxed);
+            let loudness = audio_runtime
+                .voice_loudness
+                .load(Ordering::Relaxed)
+                .then(|| vp_voice::loudness_from_dbfs(audio::pcm_rms_dbfs(frame)));
+            if let Some(targets) = audio_runtime.whisper_targets() {
+                // Whispers go out one frame per datagram; frames buffered before
+                // whispering started still go to the channel.
+                if let Some(d) = coalescer.take_datagram(route, ssrc, frame_ms) {
+                    enqueue_voice_datagram(
+                        &egress,
+                        d,
+                        &voice_counters,
+                        &send_queue_drop_count,
+                        &tx_event,
+                        voice_impairment.as_deref(),
+                    );
+                }
+                let d = make_voice_datagram(
+                    route,
+                    ssrc,
+                    seq,
+                    stream_ts_ms,
+                    vad,
+                    loudness,
+                    &enc_out[..n],
+                );
+                seq = seq.wrapping_add(1);
+                stream_ts_ms = stream_ts_ms.wrapping_add(frame_ms);
+                // Never fall back to channel voice: a whisper that can't be sent
+                // as one is dropped.
+                let fits =
+                    n + vp_voice::whisper_trailer_len(targets.len()) <= max_opus_payload_runtime;
+                let whisper = (fits && audio_runtime.voice_whisper.load(Ordering::Relaxed))
+                    .then(|| with_whisper_targets(d, &targets))
+                    .flatten();
+                let Some(d) = whisper else {
+                    voice_counters
+                        .tx_oversized_payload_drops
+                        .fetch_add(1, Ordering::Relaxed);
+                    continue;
+                };
+                debug_assert!(d.len() <= voice_max_inbound);
+                enqueue_voice_datagram(
+                    &egress,
+                    d,
+                    &voice_counters,
+                    &send_queue_drop_count,
+                    &tx_event,
+                    voice_impairment.as_deref(),
+                );
+                continue;
+            }
+            let frames_per_datagram = audio_runtime.voice_frames_per_datagram();
+            if frames_per_datagram <= 1 && coalescer.is_empty() {
+                let d = make_voice_datagram(
+                    route,
+                    ssrc,
+                    seq,
+                    stream_ts_ms,
+                    vad,
+                    loudness,
+                    &enc_out[..n],
+                );
+                seq = seq.wrapping_add(1);
+                stream_ts_ms = stream_ts_ms.wrapping_add(frame_ms);
+                debug_assert!(d.len() <= voice_max_inbound);
+                enqueue_voice_datagram(
+                    &egress,
+                    d,
+                    &voice_counters,
+                    &send_queue_drop_count,
+                    &tx_event,
+                    voice_impairment.as_deref(),
+                );
+                continue;
+            }
+
+            if coalescer.payload_len_with(n) > max_opus_payload_runtime {
+                if let Some(d) = coalescer.take_datagram(route, ssrc, frame_ms) {
+                    enqueue_voice_datagram(
+                        &egress,
+                        d,
+                        &voice_counters,
+                        &send_queue_drop_count,
+                        &tx_event,
+                        voice_impairment.as_deref(),
+                    );
+                }
+            }
+            coalescer.push(seq, stream_ts_ms, vad, loudness, &enc_out[..n]);
+            seq = seq.wrapping_add(1);
+            stream_ts_ms = stream_ts_ms.wrapping_add(frame_ms);
+            if coalescer.len() >= frames_per_datagram {
+                if let Some(d) = coalescer.take_datagram(route, ssrc, frame_ms) {
+                    debug_assert!(d.len() <= voice_max_inbound);
+                    enqueue_voice_datagram(
+                        &egress,
+                        d,
+                        &voice_counters,
+                        &send_queue_drop_count,
+                        &tx_event,
+                        voice_impairment.as_deref(),
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn enqueue_voice_datagram(
+    egress: &Arc<EgressScheduler>,
+    d: Bytes,
+    voice_counters: &VoiceTelemetryCounters,
+    send_queue_drop_count: &AtomicU32,
+    tx_event: &Sender<UiEvent>,
+    impairment: Option<&VoiceImpairment>,
+) {
+    voice_counters.tx_packets.fetch_add(1, Ordering::Relaxed);
+    voice_counters
+        .tx_bytes
+        .fetch_add(d.len() as u64, Ordering::Relaxed);
+
+    match impairment.map(|imp| imp.verdict(ImpairDirection::Outbound)) {
+        None | Some(ImpairVerdict::Pass) => {}
+        Some(ImpairVerdict::Drop) => return,
+        Some(ImpairVerdict::Delay(delay)) => {
+            let egress = egress.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(delay).await;
+                let _ = egress.enqueue_voice(d);
+            });
+            return;
+        }
+    }
+
+    match egress.enqueue_voice(d) {
+        Ok(report) => {
+            if let Some(dropped) = report.dropped {
+                send_queue_drop_count.fetch_add(dropped.count, Ordering::Relaxed);
+            }
+        }
+        Err(reason) => {
+            send_queue_drop_count.fetch_add(1, Ordering::Relaxed);
+            let _ = tx_event.send(UiEvent::AppendLog(format!(
+                "[voice] egress enqueue rejected: {:?}",
+                reason
+            )));
+        }
+    }
+}
+
+/// Buffers encoded Opus frames so several can share one datagram header.
+#[derive(Default)]
+struct VoiceFrameCoalescer {
+    frames: Vec<Vec<u8>>,
+    first_seq: u32,
+    first_ts_ms: u32,
+    vad: bool,
+    /// Loudest frame in the bundle.
+    loudness: Option<u8>,
+}
+
+impl VoiceFrameCoalescer {
+    fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Wire payload size if a frame of `next_len` bytes were added.
+    fn payload_len_with(&self, next_len: usize) -> usize {
+        if self.frames.is_empty() {
+            return next_len;
+        }
+        vp_voice::multi_frame_payload_len(&self.frames)
+            + vp_voice::MULTI_FRAME_LEN_PREFIX_BYTES
+            + next_len
+    }
+
+    fn push(&mut self, seq: u32, ts_ms: u32, vad: bool, loudness: Option<u8>, frame: &[u8]) {
+        if self.frames.is_empty() {
+            self.first_seq = seq;
+            self.first_ts_ms = ts_ms;
+            self.vad = false;
+            self.loudness = None;
+        }
+        self.vad |= vad;
+        self.loudness = self.loudness.max(loudness);
+        self.frames.push(frame.to_vec());
+    }
+
+    fn take_datagram(&mut self, route: u32, ssrc: u32, frame_ms: u32) -> Option<Bytes> {
+        let frames = std::mem::take(&mut self.frames);
+        match frames.len() {
+            0 => None,
+            1 => Some(make_voice_datagram(
+                route,
+                ssrc,
+                self.first_seq,
+                self.first_ts_ms,
+                self.vad,
+                self.loudness,
+                &frames[0],
+            )),
+            _ => make_multi_frame_voice_datagram(
+                route,
+                ssrc,
+                self.first_seq,
+                self.first_ts_ms,
+                self.vad,
+                self.loudness,
+                frame_ms as u8,
+                &frames,
+            ),
+        }
+    }
+}
+
+/// Next inbound voice datagram. With debug impairment enabled, dropped
+/// datagrams are skipped and delayed ones re-enter through `delayed_rx` once
+/// their timer fires.
+async fn next_inbound_voice(
+    queue: &OverwriteQueue<StampedBytes>,
+    stale_drops_total: &AtomicU64,
+    drain_drops_total: &AtomicU64,
+    impairment: Option<&VoiceImpairment>,
+    delayed_tx: &mpsc::UnboundedSender<Bytes>,
+    delayed_rx: &mut mpsc::UnboundedReceiver<Bytes>,
+) -> Option<Bytes> {
+    let pop = || {
+        pop_voice_realtime(
+            queue,
+            VOICE_MAX_AGE,
+            VOICE_DRAIN_KEEP_LATEST,
+            VOICE_MAX_AGE / 2,
+            stale_drops_total,
+            drain_drops_total,
+        )
+    };
+    let Some(impairment) = impairment else {
+        return pop().await;
+    };
+    loop {
+        let d = tokio::select! {
+            Some(d) = delayed_rx.recv() => return Some(d),
+            d = pop() => d?,
+        };
+        match impairment.verdict(ImpairDirection::Inbound) {
+            ImpairVerdict::Pass => return Some(d),
+            ImpairVerdict::Drop => {}
+            ImpairVerdict::Delay(delay) => {
+                let delayed_tx = delayed_tx.clone();
+                tokio::spawn(async move {
+                    sleep(delay).await;
+                    let _ = delayed_tx.send(d);
+                });
+            }
+        }
+    }
+}
+
+async fn voice_recv_loop(
+    voice_ingress_q: Arc<OverwriteQueue<StampedBytes>>,
     playout: Arc<RwLock<Arc<audio::playout::Playout>>>,
     capture_dsp: Option<Arc<Mutex<audio::dsp::CaptureDsp>>>,
     local_user_id: String,
     self_deafened: Arc<AtomicBool>,
     server_deafened: Arc<AtomicBool>,
+    local_mute_all: Arc<AtomicBool>,
     output_gain: Arc<std::sync::atomic::AtomicU32>,
     per_user_audio: Arc<std::sync::RwLock<HashMap<String, PerUserAudioSettings>>>,
     audio_runtime: AudioRuntimeSettings,
@@ -6483,6 +8434,7 @@ async fn voice_recv_loop(
     voice_counters: Arc<VoiceTelemetryCounters>,
     voice_stale_drops_total: Arc<AtomicU64>,
     voice_drain_drops_total: Arc<AtomicU64>,
+    voice_impairment: Option<Arc<VoiceImpairment>>,
     voice_die_tx: watch::Sender<bool>,
 ) {
     const SPEAKING_HANGOVER_MS: u64 = 350;
@@ -6490,29 +8442,35 @@ async fn voice_recv_loop(
     const PLC_MAX_FRAMES: usize = 5;
     const PLC_TO_NOISE_CROSSFADE_FRAMES: usize = 3;
     const RECOVERY_FADE_IN_FRAMES: usize = 2;
-    let sample_rate = 48_000u32;
-    let channels = 1usize;
-    let frame_ms = 20u32;
-    let frame_samples = (sample_rate as usize * frame_ms as usize / 1000) * channels;
+    /// Bound on packets decoded for one stream in one tick (e.g. 10 ms
+    /// frames mixed at 40 ms need four).
+    const MAX_DECODES_PER_TICK: usize = 8;
+    let mut audio_cfg = audio_runtime.session_audio();
+    let mut frame_ms = audio_cfg.frame_ms;
+    let mut frame_samples = audio_cfg.frame_samples();
 
     let mut streams = HashMap::<StreamKey, InboundStreamState>::new();
-    let mut tick = tokio::time::interval(Duration::from_millis(frame_ms as u64));
+    let mut tick = tokio::time::interval(audio_cfg.frame_duration());
     // Prevent long scheduler pauses from triggering a catch-up burst of immediate
     // ticks, which can drain the jitter buffer and inflate apparent packet loss.
     tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
     let mut mix_out = vec![0f32; frame_samples];
     let mut mixed_pcm = vec![0i16; frame_samples];
+    let mut spatial_mixer = audio::dsp::spatial::SpatialMixer::new();
+    let mut stream_frame = Vec::<f32>::with_capacity(frame_samples);
+    let mut spatial_pcm = Vec::<i16>::with_capacity(frame_samples * 2);
     let mut last_logged_fec_mode = None::<FecMode>;
+    let (delayed_tx, mut delayed_rx) = mpsc::unbounded_channel::<Bytes>();
 
     loop {
         tokio::select! {
-            maybe_d = pop_voice_realtime(
+            maybe_d = next_inbound_voice(
                 &voice_ingress_q,
-                VOICE_MAX_AGE,
-                VOICE_DRAIN_KEEP_LATEST,
-                VOICE_MAX_AGE / 2,
                 &voice_stale_drops_total,
                 &voice_drain_drops_total,
+                voice_impairment.as_deref(),
+                &delayed_tx,
+                &mut delayed_rx,
             ) => {
                 let d = match maybe_d {
                     Some(d) => d,
@@ -6530,6 +8488,17 @@ async fn voice_recv_loop(
                     Some(v) => v,
                     None => continue,
                 };
+                let (bundle_frame_ms, frames) = if packet.is_multi_frame() {
+                    match vp_voice::split_multi_frame_payload(packet.payload) {
+                        Some((ms, frames)) => (u32::from(ms), frames),
+                        None => continue,
+                    }
+                } else {
+                    (frame_ms, vec![packet.payload])
+                };
+                let last_frame_ts_ms = packet
+                    .ts_ms
+                    .wrapping_add((frames.len() as u32 - 1) * bundle_frame_ms);
 
                 voice_counters.rx_packets.fetch_add(1, Ordering::Relaxed);
                 voice_counters.rx_bytes.fetch_add(d.len() as u64, Ordering::Relaxed);
@@ -6537,31 +8506,68 @@ async fn voice_recv_loop(
                 let now_ms = unix_ms();
                 let stream = streams
                     .entry(packet.stream_key())
-                    .or_insert_with(|| InboundStreamState::new(sample_rate, channels as u8, 64));
+                    .or_insert_with(|| InboundStreamState::new(audio_cfg, 64));
                 if stream.last_packet_ts_ms != 0 {
                     let gap = packet.ts_ms.wrapping_sub(stream.last_packet_ts_ms);
                     if gap > 10_000 {
                         stream.jitter.set_expected(packet.seq);
                     }
-                    if packet.seq < stream.jitter.expected_seq() {
-                        voice_counters.late_packets.fetch_add(1, Ordering::Relaxed);
-                    }
                 }
-                stream.last_packet_ts_ms = packet.ts_ms;
+                stream.last_packet_ts_ms = last_frame_ts_ms;
                 stream.last_packet_wall_ms = now_ms;
                 if let Some(user_id) = packet.sender_user_id {
                     stream.user_id = Some(user_id.to_string());
                 }
-                stream.jitter.push(packet.seq, packet.payload.to_vec());
-                stream.missing_wait.observe_packet(now_ms, packet.ts_ms, frame_ms);
+                if stream.ssrc != Some(packet.ssrc) {
+                    if let Some(old) = stream.ssrc.replace(packet.ssrc) {
+                        spatial_mixer.clear_position(old);
+                    }
+                    spatial_mixer.set_position(
+                        packet.ssrc,
+                        audio::dsp::spatial::default_azimuth(packet.ssrc),
+                        1.0,
+                    );
+                }
+                stream.missing_wait.set_low_bandwidth(audio_runtime.low_bandwidth());
+                stream.jitter.set_frame_ms(bundle_frame_ms);
+                // Coalesced bundles expand to consecutive seq/ts, one jitter slot per frame.
+                for (i, frame) in frames.into_iter().enumerate() {
+                    let i = i as u32;
+                    stream.jitter.push(packet.seq.wrapping_add(i), frame.to_vec());
+                    stream.missing_wait.observe_packet(
+                        now_ms,
+                        packet.ts_ms.wrapping_add(i * bundle_frame_ms),
+                        frame_ms,
+                    );
+                }
+                stream.report_jitter_stats(&voice_counters);
             }
             _ = tick.tick() => {
+                let wanted_cfg = audio_runtime.session_audio();
+                if wanted_cfg != audio_cfg {
+                    info!(
+                        "[audio] voice mix frame size {} -> {} ms",
+                        frame_ms, wanted_cfg.frame_ms
+                    );
+                    audio_cfg = wanted_cfg;
+                    frame_ms = audio_cfg.frame_ms;
+                    frame_samples = audio_cfg.frame_samples();
+                    mix_out = vec![0f32; frame_samples];
+                    mixed_pcm = vec![0i16; frame_samples];
+                    tick = tokio::time::interval(audio_cfg.frame_duration());
+                    tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+                }
+
                 if self_deafened.load(Ordering::Relaxed) || server_deafened.load(Ordering::Relaxed) {
                     continue;
                 }
 
                 let now_ms = unix_ms();
                 mix_out.fill(0.0);
+                let spatial = audio_runtime.spatial_audio.load(Ordering::Relaxed);
+                if spatial {
+                    spatial_mixer.begin_frame(frame_samples);
+                }
                 let mut mixed_streams = 0usize;
                 let fec_mode = match audio_runtime.fec_mode.load(Ordering::Relaxed) {
                     0 => FecMode::Off,
@@ -6575,72 +8581,59 @@ async fn voice_recv_loop(
                 let opus_use_inband_fec = fec_mode != FecMode::Off;
 
                 let mut jitter_depth_max = 0u64;
+                let mut jitter_ms_max = 0u32;
+                let mut playout_delay_ms = 0u32;
                 for stream in streams.values_mut() {
                     let mut frame_present = false;
                     jitter_depth_max = jitter_depth_max.max(stream.jitter.depth() as u64);
+                    playout_delay_ms = playout_delay_ms.max(stream.jitter.current_delay_ms());
+                    jitter_ms_max = jitter_ms_max.max(stream.missing_wait.jitter_ms());
                     let mut frame_level = 0.0_f32;
 
-                    let ready = stream
-                        .jitter
-                        .pop_ready(now_ms, stream.missing_wait.missing_wait_ms());
-
-                    match ready {
-                        audio::jitter::PopResult::Frame(frame) => {
-                            let n = match stream.decoder.decode(&frame, &mut stream.pcm_out) {
-                                Ok(n) => n,
-                                Err(_) => 0,
-                            };
-                            if n > 0 {
-                                frame_present = true;
-                                stream.plc_frames = 0;
-                                stream.consecutive_misses = 0;
-                                if stream.in_comfort_noise {
-                                    stream.recovery_fade_in_remaining = RECOVERY_FADE_IN_FRAMES;
-                                    stream.in_comfort_noise = false;
-                                }
-                                let recovery_gain = stream.take_recovery_gain(RECOVERY_FADE_IN_FRAMES);
-                                for (acc, sample) in mix_out[..n].iter_mut().zip(stream.pcm_out[..n].iter()) {
-                                    let scaled = *sample as f32
-                                        * recovery_gain
-                                        * stream.effective_gain(&per_user_audio);
-                                    frame_level = frame_level.max((scaled.abs() / 32768.0).min(1.0));
-                                    *acc += scaled;
-                                }
-                                mixed_streams += 1;
-                            }
+                    // Senders choose their own frame size, so decode (or
+                    // conceal) until one mixer frame is buffered instead of
+                    // assuming one packet per tick.
+                    for _ in 0..MAX_DECODES_PER_TICK {
+                        if stream.pending.len() >= frame_samples {
+                            break;
                         }
-                        audio::jitter::PopResult::Missing
-                            if stream.last_packet_wall_ms != 0 && stream.plc_frames < PLC_MAX_FRAMES =>
-                        {
-                            voice_counters.lost_packets.fetch_add(1, Ordering::Relaxed);
-                            stream.consecutive_misses += 1;
-                            let n = stream.render_concealment_frame(
-                                opus_use_inband_fec,
-                                audio_runtime.comfort_noise.load(Ordering::Relaxed),
-                                u32_to_f32(audio_runtime.comfort_noise_level.load(Ordering::Relaxed)),
-                                PLC_MAX_FRAMES,
-                                PLC_TO_NOISE_CROSSFADE_FRAMES,
-                            );
-                            if n > 0 {
-                                stream.plc_frames += 1;
-                                voice_counters.concealment_frames.fetch_add(1, Ordering::Relaxed);
-                                frame_present = true;
-                                for (acc, sample) in mix_out[..n].iter_mut().zip(stream.pcm_out[..n].iter()) {
-                                    let scaled = *sample as f32 * stream.effective_gain(&per_user_audio);
-                                    frame_level = frame_level.max((scaled.abs() / 32768.0).min(1.0));
-                                    *acc += scaled;
+                        let ready = stream
+                            .jitter
+                            .pop_ready(now_ms, stream.missing_wait.missing_wait_ms());
+
+                        let produced = match ready {
+                            audio::jitter::PopResult::Frame(frame) => {
+                                let n = match stream.decoder.decode(&frame, &mut stream.pcm_out) {
+                                    Ok(n) => {
+                                        stream.decode_errors.record_ok();
+                                        n
+                                    }
+                                    Err(e) => {
+                                        voice_counters.decode_errors.fetch_add(1, Ordering::Relaxed);
+                                        stream.note_decode_error(&e, &voice_counters, &tx_event);
+                                        0
+                                    }
+                                };
+                                if n > 0 {
+                                    stream.plc_frames = 0;
+                                    stream.consecutive_misses = 0;
+                                    stream.last_frame_samples = n;
+                                    if stream.in_comfort_noise {
+                                        stream.recovery_fade_in_remaining = RECOVERY_FADE_IN_FRAMES;
+                                        stream.in_comfort_noise = false;
+                                    }
+                                    let recovery_gain = stream.take_recovery_gain(RECOVERY_FADE_IN_FRAMES);
+                                    stream.buffer_decoded(n, recovery_gain);
                                 }
-                                mixed_streams += 1;
+                                n
                             }
-                        }
-                        audio::jitter::PopResult::Waiting
-                            if stream.plc_frames < PLC_MAX_FRAMES && stream.last_packet_wall_ms != 0 =>
-                        {
-                            let since_packet = now_ms.saturating_sub(stream.last_packet_wall_ms);
-                            if since_packet <= (PLC_MAX_FRAMES as u64 * frame_ms as u64) {
+                            audio::jitter::PopResult::Missing
+                                if stream.last_packet_wall_ms != 0 && stream.plc_frames < PLC_MAX_FRAMES =>
+                            {
+                                voice_counters.lost_packets.fetch_add(1, Ordering::Relaxed);
                                 stream.consecutive_misses += 1;
                                 let n = stream.render_concealment_frame(
-                                    false,
+                                    opus_use_inband_fec,
                                     audio_runtime.comfort_noise.load(Ordering::Relaxed),
                                     u32_to_f32(audio_runtime.comfort_noise_level.load(Ordering::Relaxed)),
                                     PLC_MAX_FRAMES,
@@ -6649,17 +8642,58 @@ async fn voice_recv_loop(
                                 if n > 0 {
                                     stream.plc_frames += 1;
                                     voice_counters.concealment_frames.fetch_add(1, Ordering::Relaxed);
-                                    frame_present = true;
-                                    for (acc, sample) in mix_out[..n].iter_mut().zip(stream.pcm_out[..n].iter()) {
-                                        let scaled = *sample as f32 * stream.effective_gain(&per_user_audio);
-                                        frame_level = frame_level.max((scaled.abs() / 32768.0).min(1.0));
-                                        *acc += scaled;
+                                    stream.buffer_decoded(n, 1.0);
+                                }
+                                n
+                            }
+                            // Only conceal a late packet when there is nothing
+                            // left to play; a partial frame is better than PLC
+                            // ahead of audio that is about to arrive.
+                            audio::jitter::PopResult::Waiting
+                                if stream.pending.is_empty()
+                                    && stream.plc_frames < PLC_MAX_FRAMES
+                                    && stream.last_packet_wall_ms != 0 =>
+                            {
+                                let since_packet = now_ms.saturating_sub(stream.last_packet_wall_ms);
+                                if since_packet <= (PLC_MAX_FRAMES as u64 * frame_ms as u64) {
+                                    stream.consecutive_misses += 1;
+                                    let n = stream.render_concealment_frame(
+                                        false,
+                                        audio_runtime.comfort_noise.load(Ordering::Relaxed),
+                                        u32_to_f32(audio_runtime.comfort_noise_level.load(Ordering::Relaxed)),
+                                        PLC_MAX_FRAMES,
+                                        PLC_TO_NOISE_CROSSFADE_FRAMES,
+                                    );
+                                    if n > 0 {
+                                        stream.plc_frames += 1;
+                                        voice_counters.concealment_frames.fetch_add(1, Ordering::Relaxed);
+                                        stream.buffer_decoded(n, 1.0);
                                     }
-                                    mixed_streams += 1;
+                                    n
+                                } else {
+                                    0
                                 }
                             }
+                            _ => 0,
+                        };
+                        if produced == 0 {
+                            break;
                         }
-                        _ => {}
+                    }
+
+                    let take = stream.pending.len().min(frame_samples);
+                    if take > 0 {
+                        frame_present = true;
+                        let gain = stream.effective_gain(&per_user_audio);
+                        stream.drain_with_gain(take, gain, &mut stream_frame);
+                        for (acc, scaled) in mix_out.iter_mut().zip(&stream_frame) {
+                            frame_level = frame_level.max((scaled.abs() / 32768.0).min(1.0));
+                            *acc += scaled;
+                        }
+                        if let Some(ssrc) = stream.ssrc.filter(|_| spatial) {
+                            spatial_mixer.add(ssrc, stream_frame.iter().copied());
+                        }
+                        mixed_streams += 1;
                     }
 
                     if frame_present {
@@ -6702,6 +8736,18 @@ async fn voice_recv_loop(
                 streams.retain(|_, stream| {
                     let idle = now_ms.saturating_sub(stream.last_packet_wall_ms);
                     if idle >= STREAM_IDLE_DROP_MS {
+                        let stats = stream.jitter.stats();
+                        debug!(
+                            sender = stream.user_id.as_deref().unwrap_or("unknown"),
+                            received = stats.received,
+                            reordered = stats.reordered,
+                            late_dropped = stats.late_dropped,
+                            concealed = stats.concealed,
+                            "[voice] inbound stream idle; dropping"
+                        );
+                        if let Some(ssrc) = stream.ssrc {
+                            spatial_mixer.clear_position(ssrc);
+                        }
                         if stream.last_emitted_speaking {
                             if let Some(user_id) = stream.user_id.as_ref() {
                                 if user_id != &local_user_id {
@@ -6723,20 +8769,17 @@ async fn voice_recv_loop(
                 voice_counters
                     .jitter_buffer_depth
                     .store(jitter_depth_max, Ordering::Relaxed);
-                let playout_delay_ms = jitter_depth_max
-                    .saturating_mul(frame_ms.into());
+                voice_counters.jitter_ms.store(jitter_ms_max, Ordering::Relaxed);
                 voice_counters
                     .playout_delay_ms
-                    .store(playout_delay_ms as u32, Ordering::Relaxed);
+                    .store(playout_delay_ms, Ordering::Relaxed);
 
                 let speaking_streams = streams.values().filter(|s| s.speaking).count();
                 mixed_pcm.fill(0);
 
                 if mixed_streams > 0 {
                     for (dst, sample) in mixed_pcm.iter_mut().zip(mix_out.iter()) {
-                        let x = *sample / 32768.0;
-                        let soft = (x / (1.0 + x.abs())).clamp(-1.0, 1.0);
-                        *dst = (soft * 32768.0) as i16;
+                        *dst = (soft_clip(*sample) * 32768.0) as i16;
                     }
                     if audio_runtime.mono_expansion.load(Ordering::Relaxed) {
                         let mut prev = 0.0_f32;
@@ -6787,6 +8830,10 @@ async fn voice_recv_loop(
                 if mixed_streams == 0 && !audio_runtime.comfort_noise.load(Ordering::Relaxed) {
                     continue;
                 }
+                // Streams above are still decoded so unmuting picks up mid-word.
+                if local_mute_all.load(Ordering::Relaxed) {
+                    continue;
+                }
 
                 if (output_mul - 1.0).abs() > 0.001 {
                     for s in mixed_pcm.iter_mut() {
@@ -6800,17 +8847,38 @@ async fn voice_recv_loop(
                 }
 
                 let playout_stream = playout.read().await.clone();
-                playout_stream.push_pcm(&mixed_pcm);
+                if spatial && mixed_streams > 0 && playout_stream.channels() == 2 {
+                    // Placement already spreads talkers, so mono expansion is
+                    // skipped; clip and gain match the mono path.
+                    spatial_pcm.clear();
+                    spatial_pcm.extend(spatial_mixer.output().iter().map(|s| {
+                        (soft_clip(*s) * 32768.0 * output_mul).clamp(-32768.0, 32767.0) as i16
+                    }));
+                    playout_stream.push_pcm(&spatial_pcm);
+                } else {
+                    playout_stream.push_mono(&mixed_pcm);
+                }
             }
         }
     }
 }
 
 struct InboundStreamState {
+    /// Sender's voice ssrc; keys its place in the spatial mix.
+    ssrc: Option<u32>,
     jitter: audio::jitter::JitterBuffer,
     decoder: audio::opus::OpusDecoder,
+    /// Decode scratch, large enough for the longest Opus packet.
     pcm_out: Vec<i16>,
+    /// Length of the sender's last frame; concealment renders this much.
+    last_frame_samples: usize,
+    /// Decoded audio not yet mixed, carried across ticks when the sender's
+    /// frame size differs from ours.
+    pending: VecDeque<i16>,
     user_id: Option<String>,
+    /// Per-user gain the last mixed frame ended on; a new gain ramps from
+    /// here across one frame so slider moves don't click.
+    applied_gain: Option<f32>,
     level: f32,
     last_packet_ts_ms: u32,
     last_packet_wall_ms: u64,
@@ -6821,20 +8889,28 @@ struct InboundStreamState {
     recovery_fade_in_remaining: usize,
     noise_rng_state: u32,
     missing_wait: MissingWaitController,
+    /// Jitter buffer totals already added to the session counters.
+    reported_jitter: audio::jitter::JitterStats,
     speaking: bool,
     last_emitted_speaking: bool,
+    decode_errors: CodecErrorTracker,
 }
 
 impl InboundStreamState {
-    fn new(sample_rate: u32, channels: u8, max_frames: usize) -> Self {
-        let channel_count = channels as usize;
-        let frame_samples = (sample_rate as usize * 20 / 1000) * channel_count;
+    fn new(audio_cfg: audio::SessionAudioConfig, max_frames: usize) -> Self {
+        // Opus packets carry at most 120 ms.
+        let max_packet_samples =
+            (audio_cfg.sample_rate as usize * 120 / 1000) * audio_cfg.channels as usize;
         Self {
+            ssrc: None,
             jitter: audio::jitter::JitterBuffer::new(max_frames),
-            decoder: audio::opus::OpusDecoder::new(sample_rate, channels)
+            decoder: audio::opus::OpusDecoder::new(audio_cfg.sample_rate, audio_cfg.channels as u8)
                 .expect("inbound opus decoder init"),
-            pcm_out: vec![0i16; frame_samples],
+            pcm_out: vec![0i16; max_packet_samples],
+            last_frame_samples: audio_cfg.frame_samples(),
+            pending: VecDeque::with_capacity(max_packet_samples),
             user_id: None,
+            applied_gain: None,
             level: 0.0,
             last_packet_ts_ms: 0,
             last_packet_wall_ms: 0,
@@ -6845,8 +8921,65 @@ impl InboundStreamState {
             recovery_fade_in_remaining: 0,
             noise_rng_state: 0xA5A5_1F3Du32,
             missing_wait: MissingWaitController::new(),
+            reported_jitter: audio::jitter::JitterStats::default(),
             speaking: false,
             last_emitted_speaking: false,
+            decode_errors: CodecErrorTracker::default(),
+        }
+    }
+
+    /// Add late and reordered frames seen by the jitter buffer since the
+    /// last call to the session counters.
+    fn report_jitter_stats(&mut self, voice_counters: &VoiceTelemetryCounters) {
+        let stats = self.jitter.stats();
+        let prev = std::mem::replace(&mut self.reported_jitter, stats);
+        voice_counters
+            .late_packets
+            .fetch_add(stats.late_dropped - prev.late_dropped, Ordering::Relaxed);
+        voice_counters
+            .reordered_packets
+            .fetch_add(stats.reordered - prev.reordered, Ordering::Relaxed);
+    }
+
+    /// Log the first failure of a run and reset the decoder once the run
+    /// reaches [`CODEC_ERROR_RESET_THRESHOLD`].
+    fn note_decode_error(
+        &mut self,
+        err: &anyhow::Error,
+        voice_counters: &VoiceTelemetryCounters,
+        tx_event: &Sender<UiEvent>,
+    ) {
+        let reset = self.decode_errors.record_error();
+        let sender = self.user_id.as_deref().unwrap_or("unknown");
+        if self.decode_errors.run_started() {
+            warn!(sender, "[voice] opus decode failed: {err:#}");
+            let _ = tx_event.send(UiEvent::AppendLog(format!(
+                "[voice] opus decode failed for {sender}: {err:#}"
+            )));
+        }
+        if reset {
+            voice_counters.codec_resets.fetch_add(1, Ordering::Relaxed);
+            let outcome = match self.decoder.reset() {
+                Ok(()) => "decoder reset".to_string(),
+                Err(e) => format!("decoder reset failed: {e:#}"),
+            };
+            warn!(
+                sender,
+                "[voice] {CODEC_ERROR_RESET_THRESHOLD} consecutive decode errors; {outcome}"
+            );
+            let _ = tx_event.send(UiEvent::AppendLog(format!(
+                "[voice] {CODEC_ERROR_RESET_THRESHOLD} consecutive decode errors from {sender}; {outcome}"
+            )));
+        }
+    }
+
+    fn buffer_decoded(&mut self, n: usize, gain: f32) {
+        let decoded = &self.pcm_out[..n.min(self.pcm_out.len())];
+        if (gain - 1.0).abs() <= f32::EPSILON {
+            self.pending.extend(decoded.iter().copied());
+        } else {
+            self.pending
+                .extend(decoded.iter().map(|s| (*s as f32 * gain) as i16));
         }
     }
 
@@ -6867,19 +9000,21 @@ impl InboundStreamState {
         plc_max_frames: usize,
         crossfade_frames: usize,
     ) -> usize {
+        let len = self.last_frame_samples.min(self.pcm_out.len());
+        let pcm = &mut self.pcm_out[..len];
         if self.consecutive_misses <= plc_max_frames {
             self.in_comfort_noise = false;
             return if use_fec {
                 match self.jitter.peek_expected() {
                     Some(next_frame) => self
                         .decoder
-                        .decode_fec(next_frame, &mut self.pcm_out)
-                        .or_else(|_| self.decoder.decode_plc(&mut self.pcm_out))
+                        .decode_fec(next_frame, pcm)
+                        .or_else(|_| self.decoder.decode_plc(pcm))
                         .unwrap_or(0),
-                    None => self.decoder.decode_plc(&mut self.pcm_out).unwrap_or(0),
+                    None => self.decoder.decode_plc(pcm).unwrap_or(0),
                 }
             } else {
-                self.decoder.decode_plc(&mut self.pcm_out).unwrap_or(0)
+                self.decoder.decode_plc(pcm).unwrap_or(0)
             };
         }
 
@@ -6902,12 +9037,12 @@ impl InboundStreamState {
         let plc_gain = 1.0 - noise_gain;
 
         if plc_gain > 0.0 {
-            let _ = self.decoder.decode_plc(&mut self.pcm_out).unwrap_or(0);
+            let _ = self.decoder.decode_plc(pcm).unwrap_or(0);
         } else {
-            self.pcm_out.fill(0);
+            pcm.fill(0);
         }
 
-        for sample in &mut self.pcm_out {
+        for sample in pcm {
             self.noise_rng_state = self
                 .noise_rng_state
                 .wrapping_mul(1_664_525)
@@ -6919,7 +9054,7 @@ impl InboundStreamState {
         }
 
         self.in_comfort_noise = true;
-        self.pcm_out.len()
+        len
     }
 }
 
@@ -6945,6 +9080,20 @@ impl InboundStreamState {
             })
             .unwrap_or(1.0)
     }
+
+    /// Replace `out` with the next `take` pending samples scaled towards
+    /// `gain`, ramping linearly from the previous frame's gain.
+    fn drain_with_gain(&mut self, take: usize, gain: f32, out: &mut Vec<f32>) {
+        let start = self.applied_gain.replace(gain).unwrap_or(gain);
+        let step = (gain - start) / take.max(1) as f32;
+        out.clear();
+        out.extend(
+            self.pending
+                .drain(..take)
+                .enumerate()
+                .map(|(i, s)| s as f32 * (start + step * (i + 1) as f32)),
+        );
+    }
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
@@ -6956,6 +9105,7 @@ enum StreamKey {
 struct InboundVoice<'a> {
     sender_user_id: Option<uuid::Uuid>,
     channel_id: Option<uuid::Uuid>,
+    flags: u8,
     ssrc: u32,
     seq: u32,
     ts_ms: u32,
@@ -6968,46 +9118,30 @@ impl InboundVoice<'_> {
             .map(StreamKey::Sender)
             .unwrap_or(StreamKey::Ssrc(self.ssrc))
     }
+
+    fn is_multi_frame(&self) -> bool {
+        self.flags & vp_voice::VOICE_FLAG_MULTI_FRAME != 0
+    }
 }
 
 fn parse_voice_payload(d: &Bytes) -> Option<InboundVoice<'_>> {
-    if d.len() < VOICE_HDR_LEN {
-        return None;
-    }
-    if d[0] != VOICE_VERSION {
-        return None;
-    }
-    let hdr_len = u16::from_be_bytes([d[2], d[3]]) as usize;
-    if d.len() <= hdr_len {
-        return None;
-    }
-    let ssrc = u32::from_be_bytes([d[8], d[9], d[10], d[11]]);
-    let seq = u32::from_be_bytes([d[12], d[13], d[14], d[15]]);
-    let ts_ms = u32::from_be_bytes([d[16], d[17], d[18], d[19]]);
-
-    match hdr_len {
-        VOICE_HDR_LEN => Some(InboundVoice {
-            sender_user_id: None,
-            channel_id: None,
-            ssrc,
-            seq,
-            ts_ms,
-            payload: &d[hdr_len..],
-        }),
-        VOICE_FORWARDED_HDR_LEN => {
-            let sender_user_id = uuid::Uuid::from_slice(&d[20..36]).ok();
-            let channel_id = uuid::Uuid::from_slice(&d[36..52]).ok();
-            Some(InboundVoice {
-                sender_user_id,
-                channel_id,
-                ssrc,
-                seq,
-                ts_ms,
-                payload: &d[hdr_len..],
-            })
-        }
-        _ => None,
-    }
+    let (hdr, payload) = vp_voice::parse_voice_header(d)?;
+    let (sender_user_id, channel_id) = match hdr.forwarded_ids {
+        Some((sender, channel)) => (
+            Some(uuid::Uuid::from_bytes(sender)),
+            Some(uuid::Uuid::from_bytes(channel)),
+        ),
+        None => (None, None),
+    };
+    Some(InboundVoice {
+        sender_user_id,
+        channel_id,
+        flags: hdr.flags,
+        ssrc: hdr.ssrc,
+        seq: hdr.seq,
+        ts_ms: hdr.ts_ms,
+        payload,
+    })
 }
 
 fn build_mic_test_waveform(pcm: &[i16], points: usize) -> Vec<f32> {
@@ -7043,6 +9177,12 @@ fn u32_to_f32(u: u32) -> f32 {
     f32::from_bits(u)
 }
 
+/// Soft-clip a summed mix sample (i16 scale) into -1.0..=1.0.
+fn soft_clip(sample: f32) -> f32 {
+    let x = sample / 32768.0;
+    (x / (1.0 + x.abs())).clamp(-1.0, 1.0)
+}
+
 fn unix_ms() -> u64 {
     use std::time::{SystemTime, UNIX_EPOCH};
     SystemTime::now()
@@ -7079,10 +9219,95 @@ impl Backoff {
     fn reset(&mut self) {
         self.cur = self.min;
     }
-    async fn sleep(&mut self) {
+    /// Wait before the next attempt: the current step plus up to 150 ms of
+    /// jitter. Each call doubles the step up to `max`.
+    fn next_delay(&mut self) -> Duration {
         let jitter = rand::random::<u64>() % 150;
-        sleep(self.cur + Duration::from_millis(jitter)).await;
+        let delay = self.cur + Duration::from_millis(jitter);
         self.cur = (self.cur * 2).min(self.max);
+        delay
+    }
+    /// Jump to the longest step; retrying sooner can't help.
+    fn saturate(&mut self) {
+        self.cur = self.max;
+    }
+}
+
+/// How long the session loop waits before reconnecting after `err`.
+fn reconnect_delay(backoff: &mut Backoff, err: &anyhow::Error) -> Duration {
+    if err.downcast_ref::<ServerGoingAway>().is_some() {
+        // Nothing failed; reconnect on the shortest step.
+        backoff.reset();
+    }
+    if err.downcast_ref::<DatagramsUnsupported>().is_some() {
+        // Retrying quickly won't change the path; back off fully.
+        backoff.saturate();
+    }
+    backoff.next_delay()
+}
+
+/// The peer or path negotiated QUIC without DATAGRAM frames, so voice
+/// cannot flow until a relay transport exists.
+#[derive(Debug)]
+struct DatagramsUnsupported;
+
+impl std::fmt::Display for DatagramsUnsupported {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("This network doesn't support voice datagrams — relay mode required")
+    }
+}
+
+impl std::error::Error for DatagramsUnsupported {}
+
+/// The gateway announced it is shutting down, so the session ends now
+/// instead of on a read error once the connection is closed.
+#[derive(Debug)]
+struct ServerGoingAway(String);
+
+impl std::fmt::Display for ServerGoingAway {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Server is going away: {}", self.0)
+    }
+}
+
+impl std::error::Error for ServerGoingAway {}
+
+/// The gateway turned down our ALPN, so it speaks a different protocol
+/// version; retrying with the same build will keep failing.
+#[derive(Debug)]
+struct IncompatibleVersion;
+
+impl std::fmt::Display for IncompatibleVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Incompatible client/server version")
+    }
+}
+
+impl std::error::Error for IncompatibleVersion {}
+
+/// TLS `no_application_protocol` alert, sent when rustls finds no common ALPN.
+const TLS_ALERT_NO_APPLICATION_PROTOCOL: u8 = 120;
+
+/// True when the gateway rejected our ALPN, either during the TLS handshake
+/// or with [`vp_voice::CLOSE_CODE_ALPN_MISMATCH`] right after it.
+fn is_alpn_mismatch(err: &quinn::ConnectionError) -> bool {
+    let alert = quinn::TransportErrorCode::crypto(TLS_ALERT_NO_APPLICATION_PROTOCOL);
+    match err {
+        quinn::ConnectionError::ApplicationClosed(close) => {
+            close.error_code == quinn::VarInt::from_u32(vp_voice::CLOSE_CODE_ALPN_MISMATCH)
+        }
+        quinn::ConnectionError::ConnectionClosed(close) => close.error_code == alert,
+        quinn::ConnectionError::TransportError(err) => err.code == alert,
+        _ => false,
+    }
+}
+
+/// Replace `err` with [`IncompatibleVersion`] when the connection was closed
+/// for an ALPN mismatch; stream errors alone don't say why the peer left.
+fn version_error_or(conn: &quinn::Connection, err: anyhow::Error) -> anyhow::Error {
+    match conn.close_reason() {
+        Some(reason) if is_alpn_mismatch(&reason) => IncompatibleVersion.into(),
+        _ => err,
     }
 }
 
@@ -7253,13 +9478,403 @@ async fn upload_profile_image(
 
 #[cfg(test)]
 mod tests {
-    use super::{apply_authoritative_snapshot, choose_initial_selected_channel};
+    use super::{
+        apply_authoritative_snapshot, choose_initial_selected_channel, is_alpn_mismatch,
+        open_control_session, reconnect_delay, self_moderation_notice, AudioRuntimeSettings,
+        Backoff, CodecErrorTracker, DatagramsUnsupported, InboundStreamState, ServerGoingAway,
+        CODEC_ERROR_RESET_THRESHOLD, TLS_ALERT_NO_APPLICATION_PROTOCOL,
+    };
     use crate::{
+        identity::DeviceIdentity,
+        net::frame::{read_delimited, write_delimited},
         proto::voiceplatform::v1 as pb,
         screen_share::policy::layer_selection::select_active_share_layer,
-        ui::{model::ChannelType, UiEvent},
+        ui::{
+            model::{AppSettings, ChannelType},
+            UiEvent,
+        },
     };
     use crossbeam_channel::bounded;
+    use std::{net::SocketAddr, path::Path, sync::Arc};
+    use tokio::{
+        sync::{mpsc, watch},
+        time::{sleep, Duration, Instant},
+    };
+
+    #[test]
+    fn backoff_doubles_to_max_saturates_and_resets() {
+        use std::time::Duration;
+
+        let ms = Duration::from_millis;
+        let jitter = ms(150);
+        let mut backoff = Backoff::new(ms(10), ms(40));
+        for step in [10, 20, 40, 40] {
+            let delay = backoff.next_delay();
+            assert!(delay >= ms(step) && delay < ms(step) + jitter, "{delay:?}");
+        }
+
+        backoff.reset();
+        assert!(backoff.next_delay() < ms(10) + jitter);
+        backoff.saturate();
+        assert!(backoff.next_delay() >= ms(40));
+    }
+
+    #[test]
+    fn reconnect_delay_resets_after_going_away_and_saturates_without_datagrams() {
+        let ms = Duration::from_millis;
+        let jitter = ms(150);
+        let mut backoff = Backoff::new(ms(10), ms(40));
+        for _ in 0..3 {
+            backoff.next_delay();
+        }
+
+        let going_away = anyhow::Error::new(ServerGoingAway("restart".into()));
+        assert!(reconnect_delay(&mut backoff, &going_away) < ms(10) + jitter);
+        let no_datagrams = anyhow::Error::new(DatagramsUnsupported);
+        assert!(reconnect_delay(&mut backoff, &no_datagrams) >= ms(40));
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum GatewayEvent {
+        Authed(String),
+        Resumed(String),
+        Joined(String),
+    }
+
+    /// A loopback stand-in for the gateway's control plane. Each connection
+    /// gets a fresh session id; auths, resumes and joins are reported on
+    /// `events`, and the server side of each connection goes to `conns` so
+    /// the test can drop it. The CA for the self-signed cert is written to
+    /// `ca_cert_path`.
+    fn spawn_mock_gateway(
+        alpn: &str,
+        ca_cert_path: &Path,
+        events: mpsc::UnboundedSender<GatewayEvent>,
+        conns: mpsc::UnboundedSender<quinn::Connection>,
+    ) -> anyhow::Result<SocketAddr> {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()])?;
+        std::fs::write(ca_cert_path, cert.cert.pem())?;
+        let key = rustls::pki_types::PrivatePkcs8KeyDer::from(cert.signing_key.serialize_der());
+
+        let mut crypto = rustls::ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(vec![cert.cert.der().clone()], key.into())?;
+        crypto.alpn_protocols = vec![alpn.as_bytes().to_vec()];
+        let server_config = quinn::ServerConfig::with_crypto(Arc::new(
+            quinn::crypto::rustls::QuicServerConfig::try_from(crypto)?,
+        ));
+        let endpoint = quinn::Endpoint::server(server_config, "127.0.0.1:0".parse()?)?;
+        let addr = endpoint.local_addr()?;
+
+        tokio::spawn(async move {
+            let mut sessions = 0;
+            while let Some(incoming) = endpoint.accept().await {
+                let Ok(conn) = incoming.await else {
+                    continue;
+                };
+                sessions += 1;
+                let _ = conns.send(conn.clone());
+                tokio::spawn(serve_mock_session(
+                    conn,
+                    format!("session-{sessions}"),
+                    events.clone(),
+                ));
+            }
+        });
+        Ok(addr)
+    }
+
+    async fn serve_mock_session(
+        conn: quinn::Connection,
+        session_id: String,
+        events: mpsc::UnboundedSender<GatewayEvent>,
+    ) {
+        use pb::client_to_server::Payload as Request;
+        use pb::server_to_client::Payload as Response;
+
+        let channel = |id: &str| pb::ChannelSnapshot {
+            info: Some(pb::ChannelInfo {
+                channel_id: Some(pb::ChannelId { value: id.into() }),
+                ..Default::default()
+            }),
+        };
+        let Ok((mut send, mut recv)) = conn.accept_bi().await else {
+            return;
+        };
+        while let Ok(msg) = read_delimited::<pb::ClientToServer>(&mut recv, 256 * 1024).await {
+            let payload = match msg.payload {
+                Some(Request::Hello(_)) => Some(Response::HelloAck(pb::HelloAck {
+                    session_id: Some(pb::SessionId {
+                        value: session_id.clone(),
+                    }),
+                    auth_challenge: vec![7; 32],
+                    ..Default::default()
+                })),
+                Some(Request::AuthRequest(_)) => {
+                    let _ = events.send(GatewayEvent::Authed(session_id.clone()));
+                    Some(Response::AuthResponse(pb::AuthResponse {
+                        user_id: Some(pb::UserId {
+                            value: "user-1".into(),
+                        }),
+                        ..Default::default()
+                    }))
+                }
+                Some(Request::ResumeSessionRequest(req)) => {
+                    let prev = req.session_id.map(|id| id.value).unwrap_or_default();
+                    let _ = events.send(GatewayEvent::Resumed(prev));
+                    Some(Response::ResumeSessionResponse(pb::ResumeSessionResponse {
+                        resumed: true,
+                        ..Default::default()
+                    }))
+                }
+                Some(Request::JoinChannelRequest(req)) => {
+                    if let Some(id) = &req.channel_id {
+                        let _ = events.send(GatewayEvent::Joined(id.value.clone()));
+                    }
+                    Some(Response::JoinChannelResponse(pb::JoinChannelResponse {
+                        state: Some(pb::ChannelState {
+                            channel_id: req.channel_id,
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    }))
+                }
+                Some(Request::GetInitialStateSnapshotRequest(_)) => {
+                    Some(Response::InitialStateSnapshot(pb::InitialStateSnapshot {
+                        channels: vec![channel("lobby"), channel("raid")],
+                        default_channel_id: Some(pb::ChannelId {
+                            value: "lobby".into(),
+                        }),
+                        ..Default::default()
+                    }))
+                }
+                // Capability updates and the like only need an answer.
+                _ => None,
+            };
+            let reply = pb::ServerToClient {
+                request_id: msg.request_id,
+                payload,
+                ..Default::default()
+            };
+            if write_delimited(&mut send, &reply).await.is_err() {
+                return;
+            }
+        }
+    }
+
+    /// The steps of `app_task`'s session loop against a loopback gateway: a
+    /// session in a channel loses its connection, and the client reconnects
+    /// after one short backoff step, authenticates again, resumes the old
+    /// session and selects the channel it was in.
+    #[tokio::test]
+    async fn reconnect_reauths_resumes_and_rejoins_after_the_gateway_drops_us() {
+        use clap::Parser as _;
+
+        let (events_tx, mut events) = mpsc::unbounded_channel();
+        let (conns_tx, mut conns) = mpsc::unbounded_channel();
+        let mut cfg = crate::config::Config::parse_from(["vp-client"]);
+        let ca_cert =
+            std::env::temp_dir().join(format!("vp-client-mock-gateway-{}.pem", std::process::id()));
+        let addr = spawn_mock_gateway(&cfg.alpn, &ca_cert, events_tx, conns_tx).unwrap();
+        cfg.server = addr.to_string();
+        cfg.ca_cert_pem = ca_cert.display().to_string();
+
+        let identity = DeviceIdentity::ephemeral().unwrap();
+        let audio_runtime = AudioRuntimeSettings::from_app_settings(&AppSettings::default());
+        let (tx_event, _rx_event) = crossbeam_channel::unbounded::<UiEvent>();
+        let (_shutdown_tx, shutdown_rx) = watch::channel(false);
+        let (ui_log_tx, _ui_log_rx) = mpsc::unbounded_channel();
+        let mut resume_session_id = None;
+
+        let (conn, dispatcher, auth) = open_control_session(
+            &cfg,
+            &identity,
+            &audio_runtime,
+            &tx_event,
+            &shutdown_rx,
+            ui_log_tx.clone(),
+            &mut resume_session_id,
+        )
+        .await
+        .unwrap();
+        assert_eq!(auth.session_id, "session-1");
+        dispatcher.join_channel("raid").await.unwrap();
+        // What the JoinChannel handler records for the next session.
+        cfg.channel_id = Some("raid".into());
+        assert_eq!(
+            events.recv().await,
+            Some(GatewayEvent::Authed("session-1".into()))
+        );
+        assert_eq!(
+            events.recv().await,
+            Some(GatewayEvent::Joined("raid".into()))
+        );
+
+        conns.recv().await.unwrap().close(0u32.into(), b"dropped");
+        let err = anyhow::Error::new(conn.closed().await);
+        let dropped_at = Instant::now();
+        let mut backoff = Backoff::new(Duration::from_millis(10), Duration::from_millis(40));
+        let wait_for = reconnect_delay(&mut backoff, &err);
+        assert!(wait_for < Duration::from_millis(10 + 150), "{wait_for:?}");
+        sleep(wait_for).await;
+
+        let (_conn, dispatcher, auth) = open_control_session(
+            &cfg,
+            &identity,
+            &audio_runtime,
+            &tx_event,
+            &shutdown_rx,
+            ui_log_tx,
+            &mut resume_session_id,
+        )
+        .await
+        .unwrap();
+        let reconnected_in = dropped_at.elapsed();
+        assert!(
+            reconnected_in >= wait_for && reconnected_in < wait_for + Duration::from_secs(2),
+            "{reconnected_in:?}"
+        );
+        assert_eq!(auth.session_id, "session-2");
+        assert_eq!(
+            events.recv().await,
+            Some(GatewayEvent::Authed("session-2".into()))
+        );
+        assert_eq!(
+            events.recv().await,
+            Some(GatewayEvent::Resumed("session-1".into()))
+        );
+        assert_eq!(resume_session_id.as_deref(), Some("session-2"));
+
+        let snapshot = dispatcher.get_initial_state_snapshot().await.unwrap();
+        assert_eq!(
+            choose_initial_selected_channel(&snapshot, cfg.channel_id.as_deref()),
+            Some("raid".to_string())
+        );
+        let _ = std::fs::remove_file(ca_cert);
+    }
+
+    #[test]
+    fn codec_error_tracker_resets_after_a_consecutive_run() {
+        let mut tracker = CodecErrorTracker::default();
+        assert!(!tracker.record_error());
+        assert!(tracker.run_started());
+        tracker.record_ok();
+
+        for _ in 1..CODEC_ERROR_RESET_THRESHOLD {
+            assert!(!tracker.record_error());
+        }
+        assert!(!tracker.run_started());
+        assert!(tracker.record_error());
+        assert!(!tracker.record_error());
+        assert!(tracker.run_started());
+    }
+
+    #[test]
+    fn per_user_gain_changes_ramp_across_one_frame() {
+        let cfg = crate::audio::SessionAudioConfig::with_frame_ms(20);
+        let mut stream = InboundStreamState::new(cfg, 4);
+        let mut out = Vec::new();
+
+        stream.pending.extend([1000i16; 8]);
+        stream.drain_with_gain(4, 0.5, &mut out);
+        assert_eq!(out, vec![500.0; 4], "first frame starts at its gain");
+
+        stream.drain_with_gain(4, 1.5, &mut out);
+        assert_eq!(out, vec![750.0, 1000.0, 1250.0, 1500.0]);
+        assert!(stream.pending.is_empty());
+    }
+
+    #[test]
+    fn moderation_notice_only_fires_for_the_local_user() {
+        let user = |id: &str| {
+            Some(pb::UserId {
+                value: id.to_string(),
+            })
+        };
+        let kicked = pb::moderation_event::Kind::UserKicked(pb::UserKicked {
+            target_user_id: user("me"),
+            reason: " spam ".to_string(),
+            ..Default::default()
+        });
+        assert_eq!(
+            self_moderation_notice(&kicked, "me").as_deref(),
+            Some("You were kicked from the channel: spam")
+        );
+        assert_eq!(self_moderation_notice(&kicked, "someone-else"), None);
+
+        let banned = |duration_seconds| {
+            pb::moderation_event::Kind::UserBanned(pb::UserBanned {
+                target_user_id: user("me"),
+                duration_seconds,
+                ..Default::default()
+            })
+        };
+        assert_eq!(
+            self_moderation_notice(&banned(0), "me").as_deref(),
+            Some("You were banned from the server")
+        );
+        assert_eq!(
+            self_moderation_notice(&banned(600), "me").as_deref(),
+            Some("You were banned from the server for 600s")
+        );
+    }
+
+    #[test]
+    fn alpn_mismatch_is_recognized_from_either_close_path() {
+        let app_close = |code: u32| {
+            quinn::ConnectionError::ApplicationClosed(quinn::ApplicationClose {
+                error_code: quinn::VarInt::from_u32(code),
+                reason: bytes::Bytes::new(),
+            })
+        };
+        assert!(is_alpn_mismatch(&app_close(
+            vp_voice::CLOSE_CODE_ALPN_MISMATCH
+        )));
+        assert!(!is_alpn_mismatch(&app_close(0)));
+
+        let tls_close = quinn::ConnectionError::ConnectionClosed(quinn::ConnectionClose {
+            error_code: quinn::TransportErrorCode::crypto(TLS_ALERT_NO_APPLICATION_PROTOCOL),
+            frame_type: None,
+            reason: bytes::Bytes::new(),
+        });
+        assert!(is_alpn_mismatch(&tls_close));
+        assert!(!is_alpn_mismatch(&quinn::ConnectionError::TimedOut));
+    }
+
+    #[test]
+    fn reconnect_rejoins_last_joined_channel_while_it_exists() {
+        use clap::Parser as _;
+
+        let channel = |id: &str| pb::ChannelSnapshot {
+            info: Some(pb::ChannelInfo {
+                channel_id: Some(pb::ChannelId { value: id.into() }),
+                ..Default::default()
+            }),
+        };
+        let mut cfg = crate::config::Config::parse_from(["vp-client"]);
+        let snapshot = pb::InitialStateSnapshot {
+            channels: vec![channel("lobby"), channel("raid")],
+            default_channel_id: Some(pb::ChannelId {
+                value: "lobby".into(),
+            }),
+            ..Default::default()
+        };
+
+        // What the JoinChannel handler records before the connection drops.
+        cfg.channel_id = Some("raid".into());
+        assert_eq!(
+            choose_initial_selected_channel(&snapshot, cfg.channel_id.as_deref()),
+            Some("raid".to_string())
+        );
+
+        // Deleted while we were away: fall back to the default.
+        cfg.channel_id = Some("gone".into());
+        assert_eq!(
+            choose_initial_selected_channel(&snapshot, cfg.channel_id.as_deref()),
+            Some("lobby".to_string())
+        );
+    }
 
     #[test]
     fn choose_initial_selected_channel_preserves_requested_when_present() {
@@ -7315,6 +9930,134 @@ mod tests {
         assert_eq!(order, vec![pb::VideoCodec::Av1, pb::VideoCodec::Vp9]);
     }
 
+    #[test]
+    fn voice_frame_coalescer_bundles_frames_from_first_seq() {
+        let mut c = super::VoiceFrameCoalescer::default();
+        assert!(c.take_datagram(1, 2, 20).is_none());
+
+        c.push(10, 200, false, Some(20), &[1u8; 30]);
+        assert_eq!(c.payload_len_with(40), 2 + (2 + 30) + (2 + 40));
+        c.push(11, 220, true, Some(70), &[2u8; 40]);
+        let d = c.take_datagram(1, 2, 20).unwrap();
+        assert!(c.is_empty());
+        assert_ne!(d[1] & vp_voice::VOICE_FLAG_MULTI_FRAME, 0);
+        assert_ne!(d[1] & vp_voice::VOICE_FLAG_VAD, 0);
+        assert_eq!(u32::from_be_bytes([d[12], d[13], d[14], d[15]]), 10);
+        assert_eq!(u32::from_be_bytes([d[16], d[17], d[18], d[19]]), 200);
+        assert_eq!(
+            vp_voice::parse_voice_header(&d).unwrap().0.loudness,
+            Some(70)
+        );
+
+        // A lone buffered frame goes out as a plain datagram.
+        c.push(12, 240, true, None, &[3u8; 30]);
+        let d = c.take_datagram(1, 2, 20).unwrap();
+        assert_eq!(d[1] & vp_voice::VOICE_FLAG_MULTI_FRAME, 0);
+        assert_eq!(d.len(), super::VOICE_HDR_LEN + 30);
+    }
+
+    #[test]
+    fn network_robustness_profiles_map_to_encoder_bundle() {
+        use crate::ui::model::NetworkRobustness;
+
+        let low = super::robustness_params(NetworkRobustness::Low);
+        assert_eq!(low.resolve_fec(true, 18), (false, 0));
+        assert_eq!(low.scale_bitrate(64_000), 64_000);
+
+        let medium = super::robustness_params(NetworkRobustness::Medium);
+        assert_eq!(medium.resolve_fec(true, 18), (true, 18));
+        assert_eq!(medium.resolve_fec(false, 8), (false, 0));
+
+        let high = super::robustness_params(NetworkRobustness::High);
+        assert_eq!(high.resolve_fec(false, 8), (true, 25));
+        assert!(high.scale_bitrate(64_000) < 64_000);
+    }
+
+    #[test]
+    fn max_bandwidth_takes_the_narrowest_of_user_robustness_and_low_bandwidth() {
+        use crate::audio::opus::OpusBandwidth;
+        use crate::ui::model::NetworkRobustness;
+
+        let mut settings = crate::ui::model::AppSettings::default();
+        let runtime = super::AudioRuntimeSettings::from_app_settings(&settings);
+        assert_eq!(runtime.max_bandwidth(), OpusBandwidth::Fullband);
+
+        settings.network_robustness = NetworkRobustness::High;
+        runtime.apply(&settings);
+        assert_eq!(runtime.max_bandwidth(), OpusBandwidth::SuperWideband);
+
+        settings.low_bandwidth_mode = true;
+        runtime.apply(&settings);
+        assert_eq!(runtime.max_bandwidth(), OpusBandwidth::Wideband);
+
+        settings.opus_max_bandwidth = OpusBandwidth::Narrowband;
+        runtime.apply(&settings);
+        assert_eq!(runtime.max_bandwidth(), OpusBandwidth::Narrowband);
+
+        // An out-of-range stored value falls back to no user cap.
+        runtime
+            .opus_max_bandwidth
+            .store(-1000, std::sync::atomic::Ordering::Relaxed);
+        assert_eq!(runtime.max_bandwidth(), OpusBandwidth::Wideband);
+    }
+
+    #[test]
+    fn voice_bitrate_cap_takes_the_lower_of_user_limit_and_server_hint() {
+        use std::sync::atomic::Ordering;
+
+        let mut settings = crate::ui::model::AppSettings::default();
+        let runtime = super::AudioRuntimeSettings::from_app_settings(&settings);
+        assert_eq!(runtime.voice_bitrate_cap_bps(), None);
+        assert_eq!(runtime.capped_voice_bitrate(64_000), 64_000);
+
+        settings.voice_bitrate_limit_kbps = 48;
+        runtime.apply(&settings);
+        assert_eq!(runtime.capped_voice_bitrate(64_000), 48_000);
+
+        // The server's cap wins even when the user asks for more.
+        runtime
+            .server_voice_bitrate_cap_bps
+            .store(24_000, Ordering::Relaxed);
+        assert_eq!(runtime.capped_voice_bitrate(64_000), 24_000);
+        settings.voice_bitrate_limit_kbps = 0;
+        runtime.apply(&settings);
+        assert_eq!(runtime.voice_bitrate_cap_bps(), Some(24_000));
+
+        settings.voice_bitrate_limit_kbps = 16;
+        runtime.apply(&settings);
+        assert_eq!(runtime.capped_voice_bitrate(64_000), 16_000);
+    }
+
+    #[test]
+    fn low_bandwidth_mode_raises_frame_size_and_caps_playout_wait() {
+        let mut settings = crate::ui::model::AppSettings {
+            frame_ms: 10,
+            ..Default::default()
+        };
+        let runtime = super::AudioRuntimeSettings::from_app_settings(&settings);
+        assert_eq!(runtime.session_audio().frame_ms, 10);
+        settings.low_bandwidth_mode = true;
+        runtime.apply(&settings);
+        assert_eq!(runtime.session_audio().frame_ms, 20);
+        settings.frame_ms = 40;
+        runtime.apply(&settings);
+        assert_eq!(runtime.session_audio().frame_ms, 40);
+
+        let mut wait = super::MissingWaitController::new();
+        for i in 0..400u64 {
+            // Every packet arrives 150 ms late.
+            wait.observe_packet(i * 170, (i * 20) as u32, 20);
+        }
+        assert!(wait.missing_wait_ms() > 150);
+        wait.set_low_bandwidth(true);
+        assert!(wait.missing_wait_ms() <= 80);
+        wait.observe_packet(400 * 170, 400 * 20, 20);
+        assert!(wait.missing_wait_ms() <= 80);
+        wait.set_low_bandwidth(false);
+        wait.observe_packet(401 * 170, 401 * 20, 20);
+        assert!(wait.missing_wait_ms() > 80);
+    }
+
     #[test]
     fn demux_predicate_routes_video_by_version_and_kind() {
         let video = bytes::Bytes::from_static(&[
@@ -7421,6 +10164,7 @@ mod tests {
                 if channels.len() == 1
                     && channels[0].id == "channel-a"
                     && channels[0].name == "General"
+                    && channels[0].member_count == 1
                     && matches!(channels[0].channel_type, ChannelType::Voice)
         )));
         assert!(events.iter().any(|ev| matches!(
@@ -7432,6 +10176,16 @@ mod tests {
                     && members[0].display_name == "Alice"
         )));
     }
+
+    #[test]
+    fn transport_loss_is_per_interval_and_bounded() {
+        assert_eq!(super::transport_loss_ppm(0, 0), 0);
+        assert_eq!(super::transport_loss_ppm(200, 0), 0);
+        assert_eq!(super::transport_loss_ppm(200, 5), 25_000);
+        // Losses declared late can outnumber this interval's sends.
+        assert_eq!(super::transport_loss_ppm(2, 7), 1_000_000);
+    }
+
     #[test]
     fn voice_ingress_cap_guardrail() {
         // Do not increase without justification; latency risk.
@@ -176,6 +176,8 @@ fn default_caps(alpn: &str) -> pb::ClientCaps {
             supports_noise_suppression: true,
             supports_echo_cancellation: cfg!(feature = "aec"),
             supports_agc: true,
+            supports_voice_multi_frame: true,
+            supports_session_resume: false,
         }),
         voice_audio: Some(pb::AudioCaps {
             codec: pb::audio_caps::Codec::Opus as i32,
@@ -2,7 +2,7 @@ use anyhow::{anyhow, Context, Result};
 use std::{
     collections::HashMap,
     sync::{
-        atomic::{AtomicU32, Ordering},
+        atomic::{AtomicU32, AtomicU64, Ordering},
         Arc, OnceLock,
     },
     time::{Duration, Instant},
@@ -14,6 +14,7 @@ use tokio::{
 use tracing::info;
 
 use crate::{
+    audio::SessionAudioConfig,
     identity::DeviceIdentity,
     net::{
         frame::{read_delimited, write_delimited},
@@ -29,6 +30,9 @@ const FPS_SCALE: f32 = 100.0;
 
 static MEDIA_CAPS_CACHE: OnceLock<MeasuredMediaCaps> = OnceLock::new();
 static RUNTIME_HEADROOM_FPS_X100: AtomicU32 = AtomicU32::new(0);
+/// Bumped for every dispatcher (i.e. every control connection) so request ids
+/// from different connections can never collide.
+static CONNECTION_EPOCH: AtomicU64 = AtomicU64::new(0);
 
 #[derive(Clone, Debug)]
 struct MeasuredMediaCaps {
@@ -76,6 +80,14 @@ pub enum PushEvent {
         event: pb::VoiceTelemetryPush,
         event_seq: u64,
     },
+    VoiceLoudness {
+        event: pb::VoiceLoudnessPush,
+        event_seq: u64,
+    },
+    TalkerPreempted {
+        event: pb::VoiceTalkerPreemptedPush,
+        event_seq: u64,
+    },
     Poke {
         event: pb::PokeEvent,
         event_seq: u64,
@@ -108,6 +120,11 @@ pub enum PushEvent {
         event: pb::ScreenShareEvent,
         event_seq: u64,
     },
+    /// The gateway is shutting down; not sequenced, since the session is
+    /// ending anyway.
+    ServerGoingAway {
+        event: pb::ServerGoingAway,
+    },
     Unknown(pb::ServerToClient),
 }
 
@@ -116,14 +133,90 @@ pub struct AuthInfo {
     pub user_id: String,
     pub session_id: String,
     pub server_id: String,
+    /// `None` from servers that predate `ServerInfo`.
+    pub server_info: Option<pb::ServerInfo>,
 }
 
 #[derive(Clone, Debug)]
 pub struct JoinChannelState {
     pub members: Vec<pb::ChannelMember>,
     pub info: Option<pb::ChannelInfo>,
+    /// 0 = unlimited.
+    pub max_members: u32,
+    /// 0 = unlimited.
+    pub max_talkers: u32,
 }
 
+impl JoinChannelState {
+    /// Member limit to show for the channel. Older servers only fill
+    /// `info.user_limit`, so fall back to it; 0 means unlimited.
+    pub fn user_limit(&self) -> u32 {
+        if self.max_members != 0 {
+            self.max_members
+        } else {
+            self.info.as_ref().map(|i| i.user_limit).unwrap_or(0)
+        }
+    }
+}
+
+/// A request the server answered with an error. Wrapped in `anyhow::Error`
+/// so callers can `downcast_ref` to react to specific codes.
+#[derive(Clone, Debug)]
+pub struct ServerError {
+    pub code: i32,
+    pub message: String,
+    /// Only set for `RATE_LIMITED`; 0 otherwise.
+    pub retry_after_ms: u32,
+    /// `pb::error::Reason`; unspecified unless it's one we react to.
+    pub reason: i32,
+}
+
+impl ServerError {
+    pub fn is_channel_full(&self) -> bool {
+        self.reason == pb::error::Reason::ChannelFull as i32
+    }
+
+    /// How long a channel's slow mode wants us to wait before posting again.
+    pub fn slow_mode_wait(&self) -> Option<Duration> {
+        (self.reason == pb::error::Reason::SlowMode as i32)
+            .then(|| Duration::from_millis(u64::from(self.retry_after_ms)))
+    }
+
+    /// Why a poke was refused, phrased for the sender.
+    pub fn poke_refusal(&self) -> Option<String> {
+        match pb::error::Reason::try_from(self.reason).ok()? {
+            pb::error::Reason::UserOffline => Some("That user is offline".to_string()),
+            pb::error::Reason::PokeThrottled => Some(format!(
+                "Wait {}s before poking them again",
+                self.retry_after_ms.div_ceil(1000).max(1)
+            )),
+            _ => None,
+        }
+    }
+}
+
+impl From<pb::Error> for ServerError {
+    fn from(err: pb::Error) -> Self {
+        Self {
+            code: err.code,
+            message: err.message,
+            retry_after_ms: err.retry_after_ms,
+            reason: err.reason,
+        }
+    }
+}
+
+impl std::fmt::Display for ServerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let code = pb::error::Code::try_from(self.code)
+            .map(|c| c.as_str_name())
+            .unwrap_or("UNKNOWN");
+        write!(f, "server error {code}: {}", self.message)
+    }
+}
+
+impl std::error::Error for ServerError {}
+
 /// Commands into the dispatcher (outgoing requests).
 #[derive(Debug)]
 enum Command {
@@ -199,9 +292,15 @@ impl ControlDispatcher {
         alpn: &str,
         device_identity: &DeviceIdentity,
         preferred_display_name: &str,
+        audio: SessionAudioConfig,
+        low_bandwidth: bool,
     ) -> Result<AuthInfo> {
+        let mut caps = default_caps(alpn, audio);
+        if low_bandwidth {
+            restrict_caps_for_low_bandwidth(&mut caps);
+        }
         let hello = pb::Hello {
-            caps: Some(default_caps(alpn)),
+            caps: Some(caps),
             device_id: Some(pb::DeviceId {
                 value: device_identity.device_id.clone(),
             }),
@@ -213,7 +312,7 @@ impl ControlDispatcher {
             )
             .await??;
 
-        let (session_id, challenge) = match resp.payload {
+        let (session_id, challenge, server_info) = match resp.payload {
             Some(pb::server_to_client::Payload::HelloAck(ack)) => {
                 let sid = ack
                     .session_id
@@ -223,10 +322,16 @@ impl ControlDispatcher {
                 if let Some(sid_msg) = ack.session_id {
                     *self.inner.session_id.write().await = Some(sid_msg);
                 }
-                (sid, ack.auth_challenge)
+                (sid, ack.auth_challenge, ack.server_info)
             }
             _ => return Err(anyhow!("expected HelloAck")),
         };
+        if server_info
+            .as_ref()
+            .is_some_and(|info| !accepts_device_auth(info))
+        {
+            return Err(anyhow!("server does not accept device sign-in"));
+        }
 
         let signature = device_identity
             .sign_challenge(&challenge, &session_id)
@@ -283,12 +388,39 @@ impl ControlDispatcher {
                     user_id: a.user_id.map(|u| u.value).unwrap_or_default(),
                     session_id,
                     server_id: a.server_id.map(|sid| sid.value).unwrap_or_default(),
+                    server_info,
                 })
             }
             _ => Err(anyhow!("expected AuthResponse")),
         }
     }
 
+    /// Ask the gateway to carry `prev_session_id`'s channel membership over
+    /// to this (already authenticated) session. `false` means it expired or
+    /// belonged to someone else, and the caller should sync from scratch.
+    pub async fn resume(&self, prev_session_id: &str) -> Result<bool> {
+        let req = pb::ResumeSessionRequest {
+            session_id: Some(pb::SessionId {
+                value: prev_session_id.into(),
+            }),
+            last_event_seq: 0,
+        };
+        let resp = self
+            .send_request(
+                pb::client_to_server::Payload::ResumeSessionRequest(req),
+                Duration::from_secs(1),
+            )
+            .await??;
+
+        if let Some(err) = resp.error {
+            return Err(ServerError::from(err).into());
+        }
+        match resp.payload {
+            Some(pb::server_to_client::Payload::ResumeSessionResponse(r)) => Ok(r.resumed),
+            _ => Err(anyhow!("expected ResumeSessionResponse")),
+        }
+    }
+
     pub async fn join_channel(&self, channel_id: &str) -> Result<JoinChannelState> {
         let req = pb::JoinChannelRequest {
             channel_id: Some(pb::ChannelId {
@@ -303,22 +435,67 @@ impl ControlDispatcher {
             .await??;
 
         if let Some(err) = resp.error {
-            return Err(anyhow!("join error: {:?}", err));
+            return Err(ServerError::from(err).into());
         }
         match resp.payload {
             Some(pb::server_to_client::Payload::JoinChannelResponse(jr)) => {
                 let state = jr
                     .state
                     .ok_or_else(|| anyhow!("join response missing channel state"))?;
+                // Crowded channels only carry the first page inline; callers
+                // still get the whole roster (ourselves included, joined last).
+                let mut members = state.members;
+                let mut cursor = jr.members_cursor;
+                while jr.has_more_members && !cursor.is_empty() {
+                    let (page, next) = self.list_channel_members(channel_id, &cursor).await?;
+                    if page.is_empty() {
+                        break;
+                    }
+                    members.extend(page);
+                    cursor = next;
+                }
                 Ok(JoinChannelState {
-                    members: state.members,
+                    members,
                     info: state.info,
+                    max_members: state.max_members,
+                    max_talkers: state.max_talkers,
                 })
             }
             _ => Err(anyhow!("expected JoinChannelResponse")),
         }
     }
 
+    /// One page of `channel_id`'s members after `after_cursor` (empty = from
+    /// the start), plus the cursor for the next page (empty on the last).
+    pub async fn list_channel_members(
+        &self,
+        channel_id: &str,
+        after_cursor: &str,
+    ) -> Result<(Vec<pb::ChannelMember>, String)> {
+        let req = pb::ListChannelMembersRequest {
+            channel_id: Some(pb::ChannelId {
+                value: channel_id.into(),
+            }),
+            limit: 0,
+            after_cursor: after_cursor.into(),
+        };
+        let resp = self
+            .send_request(
+                pb::client_to_server::Payload::ListChannelMembers(req),
+                Duration::from_secs(2),
+            )
+            .await??;
+        if let Some(err) = resp.error {
+            return Err(ServerError::from(err).into());
+        }
+        match resp.payload {
+            Some(pb::server_to_client::Payload::ListChannelMembers(r)) => {
+                Ok((r.members, r.next_cursor))
+            }
+            _ => Err(anyhow!("expected ListChannelMembersResponse")),
+        }
+    }
+
     pub async fn get_initial_state_snapshot(&self) -> Result<pb::InitialStateSnapshot> {
         let req = pb::GetInitialStateSnapshotRequest {};
         let resp = self
@@ -375,6 +552,50 @@ impl ControlDispatcher {
         Ok(())
     }
 
+    /// Receive `channel_id`'s chat pushes without joining it; the voice
+    /// channel is unaffected.
+    pub async fn subscribe_channel(&self, channel_id: &str) -> Result<()> {
+        let req = pb::SubscribeChannelRequest {
+            channel_id: Some(pb::ChannelId {
+                value: channel_id.into(),
+            }),
+        };
+        let resp = self
+            .send_request(
+                pb::client_to_server::Payload::SubscribeChannel(req),
+                Duration::from_secs(2),
+            )
+            .await??;
+        if let Some(err) = resp.error {
+            return Err(ServerError::from(err).into());
+        }
+        match resp.payload {
+            Some(pb::server_to_client::Payload::SubscribeChannel(_)) => Ok(()),
+            _ => Err(anyhow!("expected SubscribeChannelResponse")),
+        }
+    }
+
+    pub async fn unsubscribe_channel(&self, channel_id: &str) -> Result<()> {
+        let req = pb::UnsubscribeChannelRequest {
+            channel_id: Some(pb::ChannelId {
+                value: channel_id.into(),
+            }),
+        };
+        let resp = self
+            .send_request(
+                pb::client_to_server::Payload::UnsubscribeChannel(req),
+                Duration::from_secs(2),
+            )
+            .await??;
+        if let Some(err) = resp.error {
+            return Err(ServerError::from(err).into());
+        }
+        match resp.payload {
+            Some(pb::server_to_client::Payload::UnsubscribeChannel(_)) => Ok(()),
+            _ => Err(anyhow!("expected UnsubscribeChannelResponse")),
+        }
+    }
+
     pub async fn create_channel(
         &self,
         name: &str,
@@ -461,6 +682,34 @@ impl ControlDispatcher {
         Ok(())
     }
 
+    /// 0 for either limit means server default length / slow mode off.
+    pub async fn set_channel_chat_limits(
+        &self,
+        channel_id: &str,
+        max_message_length: u32,
+        slow_mode_secs: u32,
+        announce_presence: bool,
+    ) -> Result<()> {
+        let req = pb::SetChannelChatLimitsRequest {
+            channel_id: Some(pb::ChannelId {
+                value: channel_id.into(),
+            }),
+            max_message_length,
+            slow_mode_secs,
+            announce_presence,
+        };
+        let resp = self
+            .send_request(
+                pb::client_to_server::Payload::SetChannelChatLimits(req),
+                Duration::from_secs(1),
+            )
+            .await??;
+        if let Some(err) = resp.error {
+            return Err(ServerError::from(err).into());
+        }
+        Ok(())
+    }
+
     pub async fn delete_channel(&self, channel_id: &str) -> Result<()> {
         let req = pb::DeleteChannelRequest {
             channel_id: Some(pb::ChannelId {
@@ -502,7 +751,7 @@ impl ControlDispatcher {
             .await??;
 
         if let Some(err) = resp.error {
-            return Err(anyhow!("send_chat error: {:?}", err));
+            return Err(ServerError::from(err).into());
         }
         Ok(())
     }
@@ -534,6 +783,113 @@ impl ControlDispatcher {
         Ok(())
     }
 
+    pub async fn disconnect_session(&self, target_user_id: &str, reason: &str) -> Result<u32> {
+        let req = pb::DisconnectSessionRequest {
+            user_id: Some(pb::UserId {
+                value: target_user_id.into(),
+            }),
+            reason: reason.into(),
+        };
+        let resp = self
+            .send_request(
+                pb::client_to_server::Payload::DisconnectSession(req),
+                Duration::from_secs(2),
+            )
+            .await??;
+        if let Some(err) = resp.error {
+            return Err(anyhow!("disconnect_session error: {:?}", err));
+        }
+        match resp.payload {
+            Some(pb::server_to_client::Payload::DisconnectSession(r)) => Ok(r.sessions_closed),
+            _ => Err(anyhow!("unexpected response to disconnect_session")),
+        }
+    }
+
+    pub async fn edit_message(
+        &self,
+        channel_id: &str,
+        message_id: &str,
+        new_text: &str,
+    ) -> Result<()> {
+        let req = pb::EditMessageRequest {
+            message_id: Some(pb::MessageId {
+                value: message_id.into(),
+            }),
+            channel_id: Some(pb::ChannelId {
+                value: channel_id.into(),
+            }),
+            new_text: new_text.into(),
+        };
+        let resp = self
+            .send_request(
+                pb::client_to_server::Payload::EditMessageRequest(req),
+                Duration::from_secs(1),
+            )
+            .await??;
+        if let Some(err) = resp.error {
+            return Err(ServerError::from(err).into());
+        }
+        Ok(())
+    }
+
+    /// A page of `channel_id`'s history, oldest first, ending just before
+    /// `before` (or at the newest message). `limit` 0 takes the server default.
+    pub async fn fetch_history(
+        &self,
+        channel_id: &str,
+        before: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<pb::MessagePosted>> {
+        let req = pb::GetMessageHistoryRequest {
+            channel_id: Some(pb::ChannelId {
+                value: channel_id.into(),
+            }),
+            limit,
+            before_message_id: before.unwrap_or_default().into(),
+        };
+        let resp = self
+            .send_request(
+                pb::client_to_server::Payload::GetMessageHistoryRequest(req),
+                Duration::from_secs(2),
+            )
+            .await??;
+        if let Some(err) = resp.error {
+            return Err(ServerError::from(err).into());
+        }
+        match resp.payload {
+            Some(pb::server_to_client::Payload::GetMessageHistoryResponse(r)) => r
+                .messages
+                .iter()
+                .map(|raw| {
+                    <pb::MessagePosted as prost::Message>::decode(raw.as_slice())
+                        .context("decode history message")
+                })
+                .collect(),
+            _ => Err(anyhow!("expected GetMessageHistoryResponse")),
+        }
+    }
+
+    pub async fn delete_message(&self, channel_id: &str, message_id: &str) -> Result<()> {
+        let req = pb::DeleteMessageRequest {
+            message_id: Some(pb::MessageId {
+                value: message_id.into(),
+            }),
+            channel_id: Some(pb::ChannelId {
+                value: channel_id.into(),
+            }),
+        };
+        let resp = self
+            .send_request(
+                pb::client_to_server::Payload::DeleteMessageRequest(req),
+                Duration::from_secs(1),
+            )
+            .await??;
+        if let Some(err) = resp.error {
+            return Err(ServerError::from(err).into());
+        }
+        Ok(())
+    }
+
     pub async fn add_reaction(
         &self,
         channel_id: &str,
@@ -606,6 +962,73 @@ impl ControlDispatcher {
         Ok(())
     }
 
+    pub async fn mark_read(&self, channel_id: &str, up_to_message_id: &str) -> Result<()> {
+        let req = pb::MarkReadRequest {
+            channel_id: Some(pb::ChannelId {
+                value: channel_id.into(),
+            }),
+            up_to_message_id: Some(pb::MessageId {
+                value: up_to_message_id.into(),
+            }),
+        };
+        let resp = self
+            .send_request(
+                pb::client_to_server::Payload::MarkReadRequest(req),
+                Duration::from_secs(1),
+            )
+            .await??;
+        if let Some(err) = resp.error {
+            return Err(ServerError::from(err).into());
+        }
+        Ok(())
+    }
+
+    pub async fn get_read_state(&self, channel_id: &str) -> Result<Vec<pb::ReadMarker>> {
+        let req = pb::GetReadStateRequest {
+            channel_id: Some(pb::ChannelId {
+                value: channel_id.into(),
+            }),
+        };
+        let resp = self
+            .send_request(
+                pb::client_to_server::Payload::GetReadStateRequest(req),
+                Duration::from_secs(2),
+            )
+            .await??;
+        if let Some(err) = resp.error {
+            return Err(ServerError::from(err).into());
+        }
+        match resp.payload {
+            Some(pb::server_to_client::Payload::GetReadStateResponse(r)) => Ok(r.markers),
+            _ => Err(anyhow!("expected GetReadStateResponse")),
+        }
+    }
+
+    pub async fn set_self_voice_state(
+        &self,
+        channel_id: &str,
+        self_muted: bool,
+        self_deafened: bool,
+    ) -> Result<()> {
+        let req = pb::SetSelfVoiceStateRequest {
+            channel_id: Some(pb::ChannelId {
+                value: channel_id.into(),
+            }),
+            self_muted,
+            self_deafened,
+        };
+        let resp = self
+            .send_request(
+                pb::client_to_server::Payload::SetSelfVoiceStateRequest(req),
+                Duration::from_secs(1),
+            )
+            .await??;
+        if let Some(err) = resp.error {
+            return Err(anyhow!("set_self_voice_state error: {:?}", err));
+        }
+        Ok(())
+    }
+
     pub async fn poke_user(&self, target_user_id: &str, message: &str) -> Result<()> {
         let req = pb::PokeRequest {
             target_user_id: Some(pb::UserId {
@@ -620,7 +1043,7 @@ impl ControlDispatcher {
             )
             .await??;
         if let Some(err) = resp.error {
-            return Err(anyhow!("poke_user error: {:?}", err));
+            return Err(ServerError::from(err).into());
         }
         Ok(())
     }
@@ -953,9 +1376,8 @@ async fn dispatcher_task(
     mut shutdown_rx: watch::Receiver<bool>,
     ui_log_tx: UiLogTx,
 ) {
-    let pending: Arc<Mutex<HashMap<u64, oneshot::Sender<Result<pb::ServerToClient>>>>> =
-        Arc::new(Mutex::new(HashMap::new()));
-    let next_req: Arc<Mutex<u64>> = Arc::new(Mutex::new(1));
+    let epoch = CONNECTION_EPOCH.fetch_add(1, Ordering::Relaxed) + 1;
+    let pending = Arc::new(Mutex::new(PendingRequests::new(epoch)));
 
     // Spawn reader task
     let reader_pending = pending.clone();
@@ -971,12 +1393,16 @@ async fn dispatcher_task(
                 }
             };
 
-            if let Some(rid) = msg.request_id.as_ref().map(|x| x.value) {
-                if let Some(tx) = reader_pending.lock().await.remove(&rid) {
-                    let _ = tx.send(Ok(msg));
+            let msg = match reader_pending.lock().await.resolve(msg) {
+                Resolved::Delivered => continue,
+                Resolved::Stale(rid) => {
+                    let _ = reader_ui_log_tx.send(format!(
+                        "[dispatcher] dropping response for request {rid:#x} from a previous connection"
+                    ));
                     continue;
                 }
-            }
+                Resolved::Unmatched(msg) => msg,
+            };
 
             let ev = classify_push(msg);
             if reader_inner.push_tx.try_send(ev).is_err() {
@@ -1006,14 +1432,7 @@ async fn dispatcher_task(
                         break;
                     }
                     Some(Command::Send { payload, resp_tx, timeout: _ }) => {
-                        let rid = {
-                            let mut g = next_req.lock().await;
-                            let v = *g;
-                            *g += 1;
-                            v
-                        };
-
-                        pending.lock().await.insert(rid, resp_tx);
+                        let rid = pending.lock().await.register(resp_tx);
 
                         let session_id = inner.session_id.read().await.clone();
                         let msg = pb::ClientToServer {
@@ -1064,16 +1483,76 @@ async fn dispatcher_task(
         }
     }
 
+    // Stop reading the old stream so nothing from this connection can be
+    // delivered after a reconnect has started a new dispatcher.
+    reader.abort();
     fail_all_pending(&pending).await;
 }
 
-async fn fail_all_pending(
-    pending: &Arc<Mutex<HashMap<u64, oneshot::Sender<Result<pb::ServerToClient>>>>>,
-) {
-    let mut map = pending.lock().await;
-    for (_, tx) in map.drain() {
-        let _ = tx.send(Err(anyhow!("dispatcher shutdown")));
+async fn fail_all_pending(pending: &Mutex<PendingRequests>) {
+    pending.lock().await.fail_all("dispatcher shutdown");
+}
+
+/// In-flight requests for one control connection. Request ids carry the
+/// connection epoch in the high 32 bits, so a response that straggles in from
+/// an earlier connection is never matched to a newer request.
+struct PendingRequests {
+    epoch: u64,
+    next_seq: u32,
+    waiters: HashMap<u64, oneshot::Sender<Result<pb::ServerToClient>>>,
+}
+
+impl PendingRequests {
+    fn new(epoch: u64) -> Self {
+        Self {
+            epoch,
+            next_seq: 1,
+            waiters: HashMap::new(),
+        }
+    }
+
+    fn register(&mut self, resp_tx: oneshot::Sender<Result<pb::ServerToClient>>) -> u64 {
+        let rid = ((self.epoch & 0xFFFF_FFFF) << 32) | u64::from(self.next_seq);
+        self.next_seq = self.next_seq.wrapping_add(1).max(1);
+        self.waiters.insert(rid, resp_tx);
+        rid
+    }
+
+    /// Hand a response to its waiter, if it belongs to this connection.
+    fn resolve(&mut self, msg: pb::ServerToClient) -> Resolved {
+        let Some(rid) = msg
+            .request_id
+            .as_ref()
+            .map(|x| x.value)
+            .filter(|rid| *rid != 0)
+        else {
+            return Resolved::Unmatched(msg);
+        };
+        if rid >> 32 != self.epoch & 0xFFFF_FFFF {
+            return Resolved::Stale(rid);
+        }
+        match self.waiters.remove(&rid) {
+            Some(tx) => {
+                let _ = tx.send(Ok(msg));
+                Resolved::Delivered
+            }
+            None => Resolved::Unmatched(msg),
+        }
     }
+
+    fn fail_all(&mut self, reason: &str) {
+        for (_, tx) in self.waiters.drain() {
+            let _ = tx.send(Err(anyhow!("{reason}")));
+        }
+    }
+}
+
+enum Resolved {
+    Delivered,
+    /// Reply to a request issued on an earlier connection; never delivered.
+    Stale(u64),
+    /// Push, or a reply nobody is waiting for (e.g. already timed out).
+    Unmatched(pb::ServerToClient),
 }
 
 /// Convert a protobuf UserProfile to the UI model type.
@@ -1190,6 +1669,16 @@ fn classify_push(msg: pb::ServerToClient) -> PushEvent {
                 event_seq: msg.event_seq,
             }
         }
+        Some(pb::server_to_client::Payload::VoiceLoudnessPush(event)) => PushEvent::VoiceLoudness {
+            event,
+            event_seq: msg.event_seq,
+        },
+        Some(pb::server_to_client::Payload::VoiceTalkerPreemptedPush(event)) => {
+            PushEvent::TalkerPreempted {
+                event,
+                event_seq: msg.event_seq,
+            }
+        }
         Some(pb::server_to_client::Payload::PokeEvent(e)) => PushEvent::Poke {
             event: e,
             event_seq: msg.event_seq,
@@ -1229,6 +1718,9 @@ fn classify_push(msg: pb::ServerToClient) -> PushEvent {
             event,
             event_seq: msg.event_seq,
         },
+        Some(pb::server_to_client::Payload::ServerGoingAway(event)) => {
+            PushEvent::ServerGoingAway { event }
+        }
         _ => PushEvent::Unknown(msg),
     }
 }
@@ -1248,6 +1740,16 @@ pub fn available_screen_share_codecs() -> Vec<&'static str> {
     screen_share_codecs_for(&measured_media_caps().runtime_caps)
 }
 
+/// Name of a codec a server advertises in `ServerInfo`, in the same terms as
+/// [`available_screen_share_codecs`]; `None` for codecs we never send.
+pub fn screen_share_codec_label(codec: pb::video_caps::Codec) -> Option<&'static str> {
+    match codec {
+        pb::video_caps::Codec::Vp9 => Some("VP9"),
+        pb::video_caps::Codec::Av1 => Some("AV1"),
+        _ => None,
+    }
+}
+
 fn screen_share_codecs_for(caps: &MediaRuntimeCaps) -> Vec<&'static str> {
     if !screen_share_support_for(caps) {
         return Vec::new();
@@ -1290,7 +1792,11 @@ pub fn screen_share_supported() -> bool {
 }
 
 pub fn validate_screen_share_capability_consistency() {
-    let advertised = default_caps("screen-share-consistency-check")
+    let caps = default_caps(
+        "screen-share-consistency-check",
+        SessionAudioConfig::default(),
+    );
+    let advertised = caps
         .features
         .as_ref()
         .map(|f| f.supports_screen_share)
@@ -1532,7 +2038,7 @@ fn platform_has_capture_backend() -> bool {
     ))
 }
 
-fn default_caps(alpn: &str) -> pb::ClientCaps {
+fn default_caps(alpn: &str, audio: SessionAudioConfig) -> pb::ClientCaps {
     let measured = measured_media_caps();
     let media_caps = measured.caps;
     let supports_1440p60 = measured.runtime_caps.supports_1440p60;
@@ -1568,12 +2074,14 @@ fn default_caps(alpn: &str) -> pb::ClientCaps {
             supports_noise_suppression: true,
             supports_echo_cancellation: cfg!(feature = "aec"),
             supports_agc: true,
+            supports_voice_multi_frame: true,
+            supports_session_resume: true,
         }),
         voice_audio: Some(pb::AudioCaps {
             codec: pb::audio_caps::Codec::Opus as i32,
-            sample_rate_hz: 48_000,
-            stereo: false,
-            frame_ms_preference: vec![20, 10],
+            sample_rate_hz: audio.sample_rate,
+            stereo: audio.channels == 2,
+            frame_ms_preference: audio.frame_ms_preference(),
             max_bitrate_bps: 64_000,
             max_simultaneous_decodes: 8,
         }),
@@ -1593,15 +2101,42 @@ fn default_caps(alpn: &str) -> pb::ClientCaps {
     }
 }
 
+/// Low-bandwidth mode: advertise no streaming or video so the server never
+/// routes any to us, and cap the voice bitrate we ask senders for.
+fn restrict_caps_for_low_bandwidth(caps: &mut pb::ClientCaps) {
+    if let Some(features) = caps.features.as_mut() {
+        features.supports_streaming = false;
+        features.supports_screen_share = false;
+        features.supports_video_call = false;
+    }
+    if let Some(audio) = caps.voice_audio.as_mut() {
+        audio.max_bitrate_bps = audio
+            .max_bitrate_bps
+            .min(crate::audio::LOW_BANDWIDTH_BITRATE_BPS);
+    }
+    caps.screen_video = None;
+    caps.screen_share = None;
+    caps.camera_video = None;
+}
+
+/// Whether a server takes device-key auth. Servers that predate
+/// `ServerInfo.auth_methods` send an empty list and only had device auth.
+fn accepts_device_auth(info: &pb::ServerInfo) -> bool {
+    info.auth_methods.is_empty() || info.auth_methods().any(|m| m == pb::AuthMethod::Device)
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
-        classify_push, screen_share_codecs_for, screen_share_profiles_for,
-        screen_share_supported_for_runtime, PushEvent,
+        accepts_device_auth, classify_push, default_caps, restrict_caps_for_low_bandwidth,
+        screen_share_codecs_for, screen_share_profiles_for, screen_share_supported_for_runtime,
+        PendingRequests, PushEvent, Resolved, ServerError,
     };
     use crate::proto::voiceplatform::v1 as pb;
     use crate::screen_share::runtime_probe::MediaRuntimeCaps;
     use std::collections::HashMap;
+    use std::time::Duration;
+    use tokio::sync::oneshot;
 
     fn runtime_caps_with_codecs() -> MediaRuntimeCaps {
         let mut encode = HashMap::new();
@@ -1621,6 +2156,49 @@ mod tests {
         }
     }
 
+    fn reply_to(rid: u64) -> pb::ServerToClient {
+        pb::ServerToClient {
+            request_id: Some(pb::RequestId { value: rid }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn reconnect_mid_request_fails_old_request_and_rejects_its_reply() {
+        let mut old_conn = PendingRequests::new(1);
+        let (old_tx, mut old_rx) = oneshot::channel();
+        let old_rid = old_conn.register(old_tx);
+
+        // Connection drops before the reply arrives; the dispatcher fails
+        // everything in flight and a new connection starts.
+        old_conn.fail_all("dispatcher shutdown");
+        assert!(old_rx.try_recv().unwrap().is_err());
+
+        let mut new_conn = PendingRequests::new(2);
+        let (new_tx, mut new_rx) = oneshot::channel();
+        let new_rid = new_conn.register(new_tx);
+        assert_ne!(old_rid, new_rid);
+
+        // The stale reply must not be matched to the new request.
+        assert!(
+            matches!(new_conn.resolve(reply_to(old_rid)), Resolved::Stale(rid) if rid == old_rid)
+        );
+        assert!(new_rx.try_recv().is_err());
+
+        assert!(matches!(
+            new_conn.resolve(reply_to(new_rid)),
+            Resolved::Delivered
+        ));
+        assert!(new_rx.try_recv().unwrap().is_ok());
+    }
+
+    #[test]
+    fn pushes_without_request_id_are_unmatched() {
+        let mut conn = PendingRequests::new(3);
+        let push = pb::ServerToClient::default();
+        assert!(matches!(conn.resolve(push), Resolved::Unmatched(_)));
+    }
+
     #[test]
     fn screen_share_profiles_runtime_downshift_hides_1440() {
         // During a live share, if runtime FPS drops below 55, hide 1440p60.
@@ -1724,6 +2302,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn classify_push_server_going_away() {
+        let msg = pb::ServerToClient {
+            payload: Some(pb::server_to_client::Payload::ServerGoingAway(
+                pb::ServerGoingAway {
+                    reason: "server shutting down".into(),
+                    grace_ms: 10_000,
+                },
+            )),
+            ..Default::default()
+        };
+
+        match classify_push(msg) {
+            PushEvent::ServerGoingAway { event } => assert_eq!(event.grace_ms, 10_000),
+            other => panic!("wrong PushEvent variant: {:?}", other),
+        }
+    }
+
     #[test]
     fn classify_push_screen_share_stopped_event() {
         let msg = pb::ServerToClient {
@@ -1796,4 +2392,66 @@ mod tests {
             other => panic!("wrong variant: {:?}", other),
         }
     }
+
+    #[test]
+    fn low_bandwidth_caps_drop_video_and_cap_voice_bitrate() {
+        let mut caps = default_caps("test", crate::audio::SessionAudioConfig::default());
+        restrict_caps_for_low_bandwidth(&mut caps);
+        let features = caps.features.as_ref().unwrap();
+        assert!(!features.supports_streaming);
+        assert!(!features.supports_screen_share);
+        assert!(!features.supports_video_call);
+        assert!(features.supports_quic_datagrams);
+        assert_eq!(
+            caps.voice_audio.as_ref().unwrap().max_bitrate_bps,
+            crate::audio::LOW_BANDWIDTH_BITRATE_BPS
+        );
+        assert!(caps.screen_video.is_none());
+        assert!(caps.screen_share.is_none());
+    }
+
+    #[test]
+    fn device_auth_is_assumed_unless_the_server_lists_other_methods() {
+        let mut info = pb::ServerInfo::default();
+        assert!(accepts_device_auth(&info));
+        info.auth_methods = vec![pb::AuthMethod::OidcToken as i32];
+        assert!(!accepts_device_auth(&info));
+        info.auth_methods.push(pb::AuthMethod::Device as i32);
+        assert!(accepts_device_auth(&info));
+    }
+
+    #[test]
+    fn server_errors_are_classified_by_reason_not_message() {
+        let err = |reason: pb::error::Reason, message: &str| {
+            ServerError::from(pb::Error {
+                code: pb::error::Code::RateLimited as i32,
+                message: message.to_string(),
+                retry_after_ms: 2_500,
+                reason: reason as i32,
+                ..Default::default()
+            })
+        };
+
+        let slow = err(pb::error::Reason::SlowMode, "reworded by the server");
+        assert_eq!(slow.slow_mode_wait(), Some(Duration::from_millis(2_500)));
+        assert!(slow.poke_refusal().is_none());
+
+        let poke = err(pb::error::Reason::PokeThrottled, "");
+        assert_eq!(
+            poke.poke_refusal().as_deref(),
+            Some("Wait 3s before poking them again")
+        );
+        assert!(poke.slow_mode_wait().is_none());
+
+        assert!(err(pb::error::Reason::ChannelFull, "").is_channel_full());
+        let offline = err(pb::error::Reason::UserOffline, "");
+        assert_eq!(
+            offline.poke_refusal().as_deref(),
+            Some("That user is offline")
+        );
+
+        let legacy = err(pb::error::Reason::Unspecified, "slow mode");
+        assert!(legacy.slow_mode_wait().is_none());
+        assert!(!legacy.is_channel_full());
+    }
 }
@@ -0,0 +1,200 @@
+//! Debug-only voice datagram impairment (loss / delay / jitter / reorder).
+//!
+//! A cross-platform stand-in for `tools/netem` so the jitter buffer, PLC and
+//! FEC can be exercised locally. Disabled unless `--debug-voice-impairment`
+//! (or `VP_DEBUG_VOICE_IMPAIRMENT`) is set; everything it does is counted so
+//! simulated loss is never mistaken for a real network problem.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Extra hold applied to a reordered datagram: long enough for the next few
+/// 20 ms frames to overtake it.
+const REORDER_HOLD: Duration = Duration::from_millis(60);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Inbound,
+    Outbound,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImpairmentSpec {
+    pub loss_pct: f32,
+    pub delay_ms: u32,
+    pub jitter_ms: u32,
+    pub reorder_pct: f32,
+    pub inbound: bool,
+    pub outbound: bool,
+}
+
+impl Default for ImpairmentSpec {
+    fn default() -> Self {
+        Self {
+            loss_pct: 0.0,
+            delay_ms: 0,
+            jitter_ms: 0,
+            reorder_pct: 0.0,
+            inbound: true,
+            outbound: true,
+        }
+    }
+}
+
+impl ImpairmentSpec {
+    /// Parse `loss=5,delay=40,jitter=20,reorder=2,dir=in|out|both`.
+    /// Percentages are 0..=100; unknown keys are rejected.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut spec = Self::default();
+        for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("expected key=value, got '{part}'"))?;
+            let pct = |v: &str| -> Result<f32, String> {
+                let p: f32 = v.parse().map_err(|_| format!("invalid {key} '{v}'"))?;
+                if (0.0..=100.0).contains(&p) {
+                    Ok(p)
+                } else {
+                    Err(format!("{key} must be within 0..=100"))
+                }
+            };
+            let ms = |v: &str| -> Result<u32, String> {
+                v.parse().map_err(|_| format!("invalid {key} '{v}'"))
+            };
+            match key.trim() {
+                "loss" => spec.loss_pct = pct(value)?,
+                "delay" => spec.delay_ms = ms(value)?,
+                "jitter" => spec.jitter_ms = ms(value)?,
+                "reorder" => spec.reorder_pct = pct(value)?,
+                "dir" => {
+                    (spec.inbound, spec.outbound) = match value.trim() {
+                        "in" => (true, false),
+                        "out" => (false, true),
+                        "both" => (true, true),
+                        other => return Err(format!("invalid dir '{other}'")),
+                    }
+                }
+                other => return Err(format!("unknown impairment key '{other}'")),
+            }
+        }
+        Ok(spec)
+    }
+
+    pub fn is_noop(&self) -> bool {
+        self.loss_pct == 0.0 && self.delay_ms == 0 && self.jitter_ms == 0 && self.reorder_pct == 0.0
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    Pass,
+    Drop,
+    Delay(Duration),
+}
+
+#[derive(Default)]
+pub struct ImpairmentCounters {
+    pub dropped: AtomicU64,
+    pub delayed: AtomicU64,
+    pub reordered: AtomicU64,
+}
+
+pub struct VoiceImpairment {
+    spec: ImpairmentSpec,
+    pub counters: ImpairmentCounters,
+}
+
+impl VoiceImpairment {
+    /// Returns `None` for a spec that would not change anything, so callers
+    /// can keep the fast path free of impairment checks.
+    pub fn new(spec: ImpairmentSpec) -> Option<Self> {
+        (!spec.is_noop()).then(|| Self {
+            spec,
+            counters: ImpairmentCounters::default(),
+        })
+    }
+
+    pub fn spec(&self) -> &ImpairmentSpec {
+        &self.spec
+    }
+
+    pub fn verdict(&self, dir: Direction) -> Verdict {
+        self.verdict_with(dir, rand::random(), rand::random(), rand::random())
+    }
+
+    /// Deterministic core of [`Self::verdict`]; the `r_*` inputs are uniform
+    /// samples in `0.0..1.0`.
+    fn verdict_with(&self, dir: Direction, r_loss: f32, r_jitter: f32, r_reorder: f32) -> Verdict {
+        let applies = match dir {
+            Direction::Inbound => self.spec.inbound,
+            Direction::Outbound => self.spec.outbound,
+        };
+        if !applies {
+            return Verdict::Pass;
+        }
+        if r_loss * 100.0 < self.spec.loss_pct {
+            self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+            return Verdict::Drop;
+        }
+        let jitter = (r_jitter * (self.spec.jitter_ms as f32 + 1.0)) as u64;
+        let mut delay = Duration::from_millis(u64::from(self.spec.delay_ms) + jitter);
+        if r_reorder * 100.0 < self.spec.reorder_pct {
+            self.counters.reordered.fetch_add(1, Ordering::Relaxed);
+            delay += REORDER_HOLD;
+        }
+        if delay.is_zero() {
+            return Verdict::Pass;
+        }
+        self.counters.delayed.fetch_add(1, Ordering::Relaxed);
+        Verdict::Delay(delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_spec_and_rejects_bad_input() {
+        let spec = ImpairmentSpec::parse("loss=5, delay=40,jitter=20,reorder=2.5,dir=in").unwrap();
+        assert_eq!(spec.loss_pct, 5.0);
+        assert_eq!(spec.delay_ms, 40);
+        assert_eq!(spec.jitter_ms, 20);
+        assert_eq!(spec.reorder_pct, 2.5);
+        assert!(spec.inbound && !spec.outbound);
+
+        assert!(ImpairmentSpec::parse("loss=101").is_err());
+        assert!(ImpairmentSpec::parse("latency=5").is_err());
+        assert!(ImpairmentSpec::parse("loss").is_err());
+        assert!(VoiceImpairment::new(ImpairmentSpec::parse("").unwrap()).is_none());
+    }
+
+    #[test]
+    fn verdicts_follow_spec_and_are_counted() {
+        let imp = VoiceImpairment::new(
+            ImpairmentSpec::parse("loss=10,delay=30,jitter=10,reorder=50,dir=out").unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            imp.verdict_with(Direction::Inbound, 0.0, 0.0, 0.0),
+            Verdict::Pass
+        );
+        assert_eq!(
+            imp.verdict_with(Direction::Outbound, 0.05, 0.0, 0.9),
+            Verdict::Drop
+        );
+        assert_eq!(
+            imp.verdict_with(Direction::Outbound, 0.5, 0.99, 0.9),
+            Verdict::Delay(Duration::from_millis(40))
+        );
+        assert_eq!(
+            imp.verdict_with(Direction::Outbound, 0.5, 0.0, 0.1),
+            Verdict::Delay(Duration::from_millis(30) + REORDER_HOLD)
+        );
+
+        assert_eq!(imp.counters.dropped.load(Ordering::Relaxed), 1);
+        assert_eq!(imp.counters.delayed.load(Ordering::Relaxed), 2);
+        assert_eq!(imp.counters.reordered.load(Ordering::Relaxed), 1);
+    }
+}
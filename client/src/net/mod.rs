@@ -2,6 +2,7 @@ pub mod control;
 pub mod dispatcher;
 pub mod egress;
 pub mod frame;
+pub mod impairment;
 pub mod overwrite_queue;
 pub mod quic;
 pub mod video_datagram;
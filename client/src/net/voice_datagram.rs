@@ -2,40 +2,183 @@ use bytes::{BufMut, Bytes, BytesMut};
 
 pub const VOICE_VERSION: u8 = 1;
 pub const VOICE_HDR_LEN: usize = vp_voice::CLIENT_VOICE_HEADER_BYTES;
-pub const VOICE_FORWARDED_HDR_LEN: usize = vp_voice::FORWARDED_VOICE_HEADER_BYTES;
+pub const VOICE_HDR_WITH_LOUDNESS_LEN: usize = vp_voice::CLIENT_VOICE_HEADER_WITH_LOUDNESS_BYTES;
 
 pub fn outbound_payload_fits(payload_len: usize) -> bool {
     vp_voice::outbound_payload_fits(payload_len)
 }
 
+/// `loudness` adds the optional header byte (see [`vp_voice::loudness_from_dbfs`]);
+/// only send it to servers advertising `SERVER_FEATURE_VOICE_LOUDNESS`.
 pub fn make_voice_datagram(
     channel_route_hash: u32,
     ssrc: u32,
     seq: u32,
     ts_ms: u32,
     vad: bool,
+    loudness: Option<u8>,
     payload: &[u8],
 ) -> Bytes {
-    let mut b = BytesMut::with_capacity(VOICE_HDR_LEN + payload.len());
+    let flags = if vad { vp_voice::VOICE_FLAG_VAD } else { 0x00 };
+    let mut b = BytesMut::with_capacity(header_len(loudness) + payload.len());
+    put_header(
+        &mut b,
+        flags,
+        channel_route_hash,
+        ssrc,
+        seq,
+        ts_ms,
+        loudness,
+    );
+    b.extend_from_slice(payload);
+    b.freeze()
+}
+
+/// Coalesce several Opus frames into one datagram. `seq`/`ts_ms` describe the
+/// first frame; callers advance their counters by `frames.len()` afterwards.
+#[allow(clippy::too_many_arguments)]
+pub fn make_multi_frame_voice_datagram(
+    channel_route_hash: u32,
+    ssrc: u32,
+    seq: u32,
+    ts_ms: u32,
+    vad: bool,
+    loudness: Option<u8>,
+    frame_ms: u8,
+    frames: &[Vec<u8>],
+) -> Option<Bytes> {
+    let mut payload = Vec::with_capacity(vp_voice::multi_frame_payload_len(frames));
+    if !vp_voice::encode_multi_frame_payload(frame_ms, frames, &mut payload) {
+        return None;
+    }
+    let mut flags = vp_voice::VOICE_FLAG_MULTI_FRAME;
+    if vad {
+        flags |= vp_voice::VOICE_FLAG_VAD;
+    }
+    let mut b = BytesMut::with_capacity(header_len(loudness) + payload.len());
+    put_header(
+        &mut b,
+        flags,
+        channel_route_hash,
+        ssrc,
+        seq,
+        ts_ms,
+        loudness,
+    );
+    b.extend_from_slice(&payload);
+    Some(b.freeze())
+}
+
+/// Turn a finished datagram into a whisper to `targets` (hashed with
+/// [`vp_voice::whisper_target_hash`]); `None` for an empty or oversized
+/// target list. Only send to servers advertising `SERVER_FEATURE_WHISPER`,
+/// which would otherwise forward it to the whole channel.
+pub fn with_whisper_targets(datagram: Bytes, targets: &[u32]) -> Option<Bytes> {
+    let mut b = Vec::with_capacity(datagram.len() + vp_voice::whisper_trailer_len(targets.len()));
+    b.extend_from_slice(&datagram);
+    if !vp_voice::encode_whisper_trailer(targets, &mut b) {
+        return None;
+    }
+    b[1] |= vp_voice::VOICE_FLAG_WHISPER;
+    Some(b.into())
+}
+
+fn header_len(loudness: Option<u8>) -> usize {
+    if loudness.is_some() {
+        VOICE_HDR_WITH_LOUDNESS_LEN
+    } else {
+        VOICE_HDR_LEN
+    }
+}
+
+fn put_header(
+    b: &mut BytesMut,
+    mut flags: u8,
+    channel_route_hash: u32,
+    ssrc: u32,
+    seq: u32,
+    ts_ms: u32,
+    loudness: Option<u8>,
+) {
+    if loudness.is_some() {
+        flags |= vp_voice::VOICE_FLAG_LOUDNESS;
+    }
     b.put_u8(VOICE_VERSION);
-    let flags = if vad { 0x01 } else { 0x00 };
     b.put_u8(flags);
-    b.put_u16(VOICE_HDR_LEN as u16); // header_len
+    b.put_u16(header_len(loudness) as u16);
     b.put_u32(channel_route_hash);
     b.put_u32(ssrc);
     b.put_u32(seq);
     b.put_u32(ts_ms);
-    b.extend_from_slice(payload);
-    b.freeze()
+    if let Some(loudness) = loudness {
+        b.put_u8(loudness.min(vp_voice::MAX_VOICE_LOUDNESS));
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::outbound_payload_fits;
+    use super::{
+        make_multi_frame_voice_datagram, make_voice_datagram, outbound_payload_fits,
+        with_whisper_targets, VOICE_HDR_LEN, VOICE_HDR_WITH_LOUDNESS_LEN,
+    };
 
     #[test]
     fn oversized_payloads_are_rejected() {
         assert!(outbound_payload_fits(vp_voice::MAX_OPUS_PAYLOAD_BYTES));
         assert!(!outbound_payload_fits(vp_voice::MAX_OPUS_PAYLOAD_BYTES + 1));
     }
+
+    #[test]
+    fn multi_frame_datagram_sets_flag_and_round_trips() {
+        let frames = vec![vec![1u8; 30], vec![2u8; 40]];
+        let d = make_multi_frame_voice_datagram(7, 1, 10, 200, true, None, 20, &frames).unwrap();
+        assert_ne!(d[1] & vp_voice::VOICE_FLAG_MULTI_FRAME, 0);
+        assert_ne!(d[1] & vp_voice::VOICE_FLAG_VAD, 0);
+
+        let (frame_ms, split) = vp_voice::split_multi_frame_payload(&d[VOICE_HDR_LEN..]).unwrap();
+        assert_eq!(frame_ms, 20);
+        assert_eq!(split, vec![&frames[0][..], &frames[1][..]]);
+    }
+
+    #[test]
+    fn loudness_byte_extends_header_and_sets_flag() {
+        let plain = make_voice_datagram(7, 1, 10, 200, true, None, &[9; 12]);
+        assert_eq!(plain[1] & vp_voice::VOICE_FLAG_LOUDNESS, 0);
+        assert_eq!(plain.len(), VOICE_HDR_LEN + 12);
+
+        let loud = make_voice_datagram(7, 1, 10, 200, true, Some(200), &[9; 12]);
+        assert_ne!(loud[1] & vp_voice::VOICE_FLAG_LOUDNESS, 0);
+        let (hdr, payload) = vp_voice::parse_voice_header(&loud).unwrap();
+        assert_eq!(hdr.header_len, VOICE_HDR_WITH_LOUDNESS_LEN);
+        assert_eq!(hdr.loudness, Some(vp_voice::MAX_VOICE_LOUDNESS));
+        assert_eq!(payload, &[9; 12]);
+
+        let frames = vec![vec![1u8; 30], vec![2u8; 40]];
+        let d =
+            make_multi_frame_voice_datagram(7, 1, 10, 200, true, Some(64), 20, &frames).unwrap();
+        let (hdr, payload) = vp_voice::parse_voice_header(&d).unwrap();
+        assert_eq!(hdr.loudness, Some(64));
+        assert_eq!(
+            vp_voice::split_multi_frame_payload(payload)
+                .unwrap()
+                .1
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn whisper_trailer_follows_payload_and_sets_flag() {
+        let plain = make_voice_datagram(7, 1, 10, 200, true, Some(40), &[9; 12]);
+        let targets = [vp_voice::whisper_target_hash(&[3; 16])];
+        let d = with_whisper_targets(plain.clone(), &targets).unwrap();
+        assert_ne!(d[1] & vp_voice::VOICE_FLAG_WHISPER, 0);
+        let (hdr, body) = vp_voice::parse_voice_header(&d).unwrap();
+        assert_eq!(hdr.loudness, Some(40));
+        let (payload, split) = vp_voice::split_whisper_trailer(body).unwrap();
+        assert_eq!(payload, &[9; 12]);
+        assert_eq!(split, targets);
+
+        assert!(with_whisper_targets(plain, &[]).is_none());
+    }
 }
@@ -601,8 +601,15 @@ pub async fn start_local_share(params: StartLocalShareParams) {
                                     continue;
                                 }
                                 let ts_ms = session_zero.elapsed().as_millis() as u32;
-                                let d =
-                                    make_voice_datagram(route, ssrc, seq, ts_ms, true, &out[..n]);
+                                let d = make_voice_datagram(
+                                    route,
+                                    ssrc,
+                                    seq,
+                                    ts_ms,
+                                    true,
+                                    None,
+                                    &out[..n],
+                                );
                                 if let Err(reason) = egress.enqueue_voice(d) {
                                     warn!(
                                         ?reason,
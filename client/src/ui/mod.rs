@@ -141,6 +141,11 @@ impl VpApp {
     /// Drain all pending backend events and apply them to the model.
     fn drain_events(&mut self) {
         while let Ok(ev) = self.rx_event.try_recv() {
+            if let UiEvent::MovedByModerator { channel_id } = &ev {
+                let _ = self.tx_intent.try_send(UiIntent::JoinChannel {
+                    channel_id: channel_id.clone(),
+                });
+            }
             self.model.apply_event(ev);
         }
     }
@@ -240,11 +245,11 @@ impl VpApp {
         }
 
         ctx.copy_text(lines.join("\n"));
-        self.model.notifications.push_back(model::Notification {
-            text: "Connection details copied".to_string(),
-            created: std::time::Instant::now(),
-            kind: model::NotificationKind::Info,
-        });
+        self.model.push_notification(
+            model::NotificationKind::Info,
+            "Connection details copied".to_string(),
+            None,
+        );
     }
 }
 
@@ -276,6 +281,10 @@ impl eframe::App for VpApp {
         // Apply theme
         theme::apply_theme(ctx, &self.model.settings.theme);
 
+        // Set again by the chat panel if its composer is shown and focused;
+        // a stale flag would otherwise swallow hotkeys on other views.
+        self.model.chat_input_focused = false;
+
         // Top menu bar
         egui::TopBottomPanel::top("top_bar").show(ctx, |ui| {
             egui::MenuBar::new().ui(ui, |ui| {
@@ -287,26 +296,19 @@ impl eframe::App for VpApp {
                 );
                 ui.add_sized([6.0, 18.0], egui::Separator::default().vertical());
 
-                let conn_text = if self.model.connected {
-                    "Connected"
-                } else {
-                    "Disconnected"
-                };
-                let conn_color = if self.model.connected {
-                    theme::COLOR_ONLINE
+                let stage = self.model.connection_stage;
+                let (conn_text, conn_color) = if stage.is_in_progress() {
+                    // Name the phase so a stalled connect shows where it stalls.
+                    (stage.label(), theme::COLOR_MENTION)
+                } else if self.model.connected {
+                    ("Connected", theme::COLOR_ONLINE)
                 } else {
-                    theme::COLOR_OFFLINE
+                    ("Disconnected", theme::COLOR_OFFLINE)
                 };
                 ui.colored_label(conn_color, conn_text);
 
-                if self.model.connection_stage.is_in_progress() {
-                    ui.separator();
+                if stage.is_in_progress() {
                     ui.label(egui::RichText::new("⏳").small());
-                    ui.label(
-                        egui::RichText::new(self.model.connection_stage.label())
-                            .small()
-                            .color(theme::COLOR_MENTION),
-                    );
                     if ui.small_button("Cancel").clicked() {
                         let _ = self.tx_intent.send(UiIntent::CancelConnect);
                     }
@@ -337,6 +339,15 @@ impl eframe::App for VpApp {
                     if ui.button("Telemetry").clicked() {
                         self.model.show_telemetry = !self.model.show_telemetry;
                     }
+                    let pending = self.model.notifications.len();
+                    let label = if pending == 0 {
+                        "Notifications".to_string()
+                    } else {
+                        format!("Notifications ({pending})")
+                    };
+                    if ui.button(label).clicked() {
+                        self.model.show_notification_center = !self.model.show_notification_center;
+                    }
                 });
             });
         });
@@ -944,6 +955,7 @@ impl eframe::App for VpApp {
         panels::server_tree::show_create_channel_dialog(ctx, &mut self.model, &self.tx_intent);
         panels::server_tree::show_channel_dialogs(ctx, &mut self.model, &self.tx_intent);
         panels::permissions_center::show_permissions_center(ctx, &mut self.model, &self.tx_intent);
+        panels::notifications::show_notification_center(ctx, &mut self.model, &self.tx_intent);
 
         // Central panel: connection status + chat messages + input
         egui::CentralPanel::default()
@@ -1072,8 +1084,11 @@ impl VpApp {
                 let _ = self.tx_intent.send(UiIntent::PttDown);
                 self.model.ptt_active = true;
             }
-            if ptt_released && !self.model.chat_input_focused {
+            // Always forward the release of a press we acted on, even if the
+            // composer took focus in between, so transmit never sticks on.
+            if ptt_released && (self.model.ptt_active || !self.model.chat_input_focused) {
                 let _ = self.tx_intent.send(UiIntent::PttUp);
+                self.model.ptt_active = false;
             }
         }
 
@@ -2,12 +2,16 @@
 
 use serde::Deserialize;
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::ops::RangeInclusive;
 use std::path::PathBuf;
 use tracing::debug;
 use uuid::Uuid;
 use vp_route_hash::channel_route_hash;
 
 use crate::audio::dsp::agc::AgcPreset;
+use crate::audio::opus::OpusBandwidth;
+use crate::audio::probe::DeviceCapabilities;
+use crate::proto::voiceplatform::v1::ServerFeature;
 use crate::ui::sfx;
 use crate::ui::widgets::cosmic_chat_composer::ChatComposer;
 use eframe::egui;
@@ -27,6 +31,15 @@ const PROFILE_CACHE_MAX_ENTRIES: usize = 100;
 /// Minimum delay between automatic profile fetch retries for the same user.
 const PROFILE_FETCH_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(10);
 
+/// EMA weight of each VAD report (one every ~10 frames) in the level meter.
+const VAD_METER_SMOOTHING: f32 = 0.35;
+
+/// How long the level meter keeps its peak marker before it falls back.
+const VAD_METER_PEAK_HOLD: std::time::Duration = std::time::Duration::from_millis(800);
+
+/// Fade-out of the speaking ring once its hold has elapsed.
+pub const SPEAKING_FADE: std::time::Duration = std::time::Duration::from_millis(200);
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum ProfileFetchOrigin {
     AutomaticPrefetch,
@@ -213,6 +226,29 @@ pub enum UiEvent {
     // Chat
     PlayChatMessageSfx,
     MessageReceived(ChatMessage),
+    /// A page of a channel's history, oldest first, fetched on join or when
+    /// scrolling up. `exhausted` means nothing older exists.
+    HistoryLoaded {
+        channel_id: String,
+        messages: Vec<ChatMessage>,
+        exhausted: bool,
+    },
+    /// A history page request failed.
+    HistoryLoadFailed {
+        channel_id: String,
+    },
+    /// An uploaded attachment of a message is now cached locally.
+    AttachmentCached {
+        channel_id: String,
+        message_id: String,
+        asset_id: String,
+        download_url: String,
+    },
+    /// Chat pushes for a channel we haven't joined started or stopped.
+    SetChannelChatSubscribed {
+        channel_id: String,
+        subscribed: bool,
+    },
     MessageEdited {
         channel_id: String,
         message_id: String,
@@ -222,6 +258,11 @@ pub enum UiEvent {
         channel_id: String,
         message_id: String,
     },
+    /// Server-reported slow-mode wait for a channel.
+    ChatCooldown {
+        channel_id: String,
+        retry_after: std::time::Duration,
+    },
     ReactionAdded {
         channel_id: String,
         message_id: String,
@@ -236,6 +277,15 @@ pub enum UiEvent {
         user_id: String,
         me: bool,
     },
+    /// Authoritative reaction count after `user_id` added or removed `emoji`.
+    ReactionChanged {
+        channel_id: String,
+        message_id: String,
+        emoji: String,
+        user_id: String,
+        added: bool,
+        count: u32,
+    },
     ClearPendingAttachments,
     AttachmentUploadError {
         path: String,
@@ -256,6 +306,28 @@ pub enum UiEvent {
         channel_id: String,
         user_id: String,
     },
+    /// Server version and `ServerFeature` bits from `HelloAck`; both `None`
+    /// when the server predates `ServerInfo`. The rest are empty/zero/false
+    /// when the server doesn't say.
+    SetServerInfo {
+        version: Option<String>,
+        feature_bits: Option<u64>,
+        screen_share_codecs: Vec<&'static str>,
+        max_voice_bitrate_bps: u32,
+        voice_mixing: bool,
+    },
+    /// Whether the server records read markers (from the state snapshot).
+    SetReadReceiptsEnabled(bool),
+    /// Replace a channel's read markers as `(user_id, message_id)` pairs.
+    SetReadMarkers {
+        channel_id: String,
+        markers: Vec<(String, String)>,
+    },
+    ReadMarkerUpdated {
+        channel_id: String,
+        user_id: String,
+        message_id: String,
+    },
 
     // Remote screen-share lifecycle events (from server push)
     RemoteScreenShareStarted {
@@ -287,6 +359,11 @@ pub enum UiEvent {
         channel_id: String,
         user_id: String,
     },
+    /// A moderator moved the local user; the app re-joins `channel_id` so
+    /// voice and chat follow.
+    MovedByModerator {
+        channel_id: String,
+    },
     MemberAwayMessageUpdated {
         user_id: String,
         away_message: String,
@@ -318,6 +395,19 @@ pub enum UiEvent {
         user_id: String,
         telemetry: TelemetryData,
     },
+    /// Loudest talkers in `channel_id` as `(user_id, loudness)`, loudest
+    /// first; empty once nobody is talking.
+    SetTalkerLoudness {
+        channel_id: String,
+        talkers: Vec<(String, u8)>,
+    },
+    /// The server handed `preempted_user_id`'s talker slot in `channel_id`
+    /// to `by_user_id`, who started speaking while the channel was full.
+    TalkerPreempted {
+        channel_id: String,
+        preempted_user_id: String,
+        by_user_id: String,
+    },
 
     // Poke
     PokeReceived {
@@ -353,6 +443,9 @@ pub enum UiEvent {
     // Self state
     SetSelfMuted(bool),
     SetSelfDeafened(bool),
+    SetLocalMuteAll(bool),
+    /// User ids our voice is whispered to; empty for ordinary channel voice.
+    WhisperTargetsChanged(Vec<String>),
 
     // Audio devices
     SetAudioDevices {
@@ -360,11 +453,17 @@ pub enum UiEvent {
         output_devices: Vec<AudioDeviceInfo>,
         capture_modes: Vec<String>,
         playback_modes: Vec<String>,
+        device_capabilities: HashMap<AudioDeviceId, DeviceCapabilities>,
     },
 
     // Channel management
     ChannelCreated(ChannelEntry),
     ChannelRenamed(ChannelEntry),
+    /// Authoritative member limit from a join response; 0 = unlimited.
+    SetChannelUserLimit {
+        channel_id: String,
+        user_limit: u32,
+    },
     ChannelDeleted {
         channel_id: String,
     },
@@ -422,6 +521,13 @@ pub enum UiIntent {
         channel_id: String,
     },
     LeaveChannel,
+    /// Follow a channel's chat without joining it; voice stays where it is.
+    SubscribeChannel {
+        channel_id: String,
+    },
+    UnsubscribeChannel {
+        channel_id: String,
+    },
     CreateChannel {
         name: String,
         description: String,
@@ -437,6 +543,13 @@ pub enum UiIntent {
         codec: u8,
         quality: u32,
     },
+    /// 0 means server default length / slow mode off.
+    SetChannelChatLimits {
+        channel_id: String,
+        max_message_length: u32,
+        slow_mode_secs: u32,
+        announce_presence: bool,
+    },
     DeleteChannel {
         channel_id: String,
     },
@@ -445,6 +558,10 @@ pub enum UiIntent {
     PttUp,
     ToggleSelfMute,
     ToggleSelfDeafen,
+    /// Silence every speaker locally without telling the server; unlike
+    /// deafen, decoding keeps running so unmuting is instant.
+    LocalMuteAll,
+    LocalUnmuteAll,
     Help,
     SetAwayMessage {
         message: String,
@@ -457,6 +574,12 @@ pub enum UiIntent {
     CancelConnect,
 
     // Chat
+    /// Fetch the page of `channel_id` history just before message `before`,
+    /// or the newest page when `before` is empty.
+    LoadOlderMessages {
+        channel_id: String,
+        before: String,
+    },
     EditMessage {
         message_id: String,
         new_text: String,
@@ -473,12 +596,20 @@ pub enum UiIntent {
         emoji: String,
     },
     SendTyping,
+    MarkRead {
+        channel_id: String,
+        message_id: String,
+    },
 
     // Moderation
     KickUser {
         user_id: String,
         reason: String,
     },
+    /// Close the user's connection without kicking or banning them.
+    DisconnectUser {
+        user_id: String,
+    },
     BanUser {
         user_id: String,
         reason: String,
@@ -557,18 +688,32 @@ pub enum UiIntent {
     SetAgcTargetDb(f32),
     SetAgcPreset(AgcPreset),
     SetEchoCancellation(bool),
+    SetEchoReferenceDelay(u32),
     SetTypingAttenuation(bool),
     SetFecMode(FecMode),
     SetFecStrength(u8),
+    SetNetworkRobustness(NetworkRobustness),
+    SetVoiceFramesPerDatagram(u8),
+    SetFrameMs(u32),
+    SetLowBandwidthMode(bool),
+    SetOpusMaxBandwidth(OpusBandwidth),
+    /// Voice bitrate limit in kbps; 0 follows the channel.
+    SetVoiceBitrate(u32),
+    SetOpusComplexity(u8),
+    SetDtx(bool),
     SetVadThreshold(f32),
+    SetVadHoldMs(u32),
     SetInputDevice(AudioDeviceId),
     SetOutputDevice(AudioDeviceId),
+    /// Re-enumerate audio devices (hot-plug); answered with `SetAudioDevices`.
+    RefreshAudioDevices,
     SetCaptureMode(String),
     SetPlaybackMode(String),
     SetInputGain(f32),
     SetOutputGain(f32),
     SetOutputAutoLevel(bool),
     SetMonoExpansion(bool),
+    SetSpatialAudio(bool),
     SetComfortNoise(bool),
     SetComfortNoiseLevel(f32),
     SetDuckingEnabled(bool),
@@ -706,6 +851,8 @@ pub struct AppSettings {
     pub capture_mode: CaptureMode,
     pub ptt_delay_ms: u32,
     pub vad_threshold: f32,
+    /// How long voice activation keeps transmitting after speech stops.
+    pub vad_hold_ms: u32,
     pub input_gain: f32,
     pub dsp_enabled: bool,
     pub dsp_method: DspMethod,
@@ -717,10 +864,34 @@ pub struct AppSettings {
     #[serde(default)]
     pub agc_preset: AgcPreset,
     pub echo_cancellation: bool,
+    /// Extra delay, in 48 kHz samples, applied to the speaker signal the
+    /// echo canceller compares the mic against.
+    pub echo_reference_delay_samples: u32,
     pub denoise_attenuation_db: i32,
     pub typing_attenuation: bool,
     pub fec_mode: FecMode,
     pub fec_strength: u8,
+    #[serde(default)]
+    pub network_robustness: NetworkRobustness,
+    /// Opus frames coalesced into one voice datagram (1 = no coalescing).
+    #[serde(default = "default_voice_frames_per_datagram")]
+    pub voice_frames_per_datagram: u8,
+    /// Opus frame duration in ms (10, 20 or 40).
+    #[serde(default = "default_frame_ms")]
+    pub frame_ms: u32,
+    /// Overrides bitrate, frame size, silence suppression and playout delay
+    /// for constrained links and stops advertising streaming/video support.
+    pub low_bandwidth_mode: bool,
+    /// Widest audio band the Opus encoder may use; robustness and
+    /// low-bandwidth mode can narrow it further.
+    pub opus_max_bandwidth: OpusBandwidth,
+    /// Upper bound on the voice bitrate in kbps; 0 follows the channel. A
+    /// server's `ServerHint` cap applies on top of it.
+    pub voice_bitrate_limit_kbps: u32,
+    /// Opus encoder complexity, 0 (cheapest) to 10 (best quality per bit).
+    pub opus_complexity: u8,
+    /// Opus discontinuous transmission: near-nothing is sent during silence.
+    pub opus_dtx: bool,
 
     // ─── Playback ───
     #[serde(
@@ -733,6 +904,8 @@ pub struct AppSettings {
     pub per_user_audio: HashMap<String, PerUserAudioSettings>,
     pub output_auto_level: bool,
     pub mono_expansion: bool,
+    /// Pan each remote talker to its own spot in the stereo field.
+    pub spatial_audio: bool,
     pub comfort_noise: bool,
     pub comfort_noise_level: f32,
     pub ducking_enabled: bool,
@@ -781,6 +954,9 @@ pub struct AppSettings {
     pub theme: String,
     pub ui_scale: f32,
     pub chat_show_avatars: bool,
+    /// Keeps the speaking ring lit through short pauses; display only,
+    /// independent of the transmit VAD.
+    pub speaking_hold_ms: u32,
 
     // ─── Screen Share (modern) ───
     pub screen_share_fps: u32,
@@ -816,6 +992,7 @@ impl Default for AppSettings {
             capture_mode: CaptureMode::PushToTalk,
             ptt_delay_ms: 300,
             vad_threshold: 0.5,
+            vad_hold_ms: 300,
             input_gain: 1.0,
             dsp_enabled: true,
             dsp_method: DspMethod::Rubato,
@@ -825,10 +1002,19 @@ impl Default for AppSettings {
             agc_target_db: -18.0,
             agc_preset: AgcPreset::Balanced,
             echo_cancellation: false,
+            echo_reference_delay_samples: 0,
             denoise_attenuation_db: -30,
             typing_attenuation: true,
             fec_mode: FecMode::Auto,
             fec_strength: 50,
+            network_robustness: NetworkRobustness::Medium,
+            voice_frames_per_datagram: default_voice_frames_per_datagram(),
+            frame_ms: default_frame_ms(),
+            low_bandwidth_mode: false,
+            opus_max_bandwidth: OpusBandwidth::Fullband,
+            voice_bitrate_limit_kbps: 0,
+            opus_complexity: 10,
+            opus_dtx: true,
 
             // Playback
             playback_device: AudioDeviceId::default_output(),
@@ -837,6 +1023,7 @@ impl Default for AppSettings {
             per_user_audio: HashMap::new(),
             output_auto_level: false,
             mono_expansion: false,
+            spatial_audio: false,
             comfort_noise: false,
             comfort_noise_level: 0.02,
             ducking_enabled: false,
@@ -884,6 +1071,7 @@ impl Default for AppSettings {
             theme: "Dark".into(),
             ui_scale: 1.0,
             chat_show_avatars: true,
+            speaking_hold_ms: 300,
 
             // Screen Share
             screen_share_fps: 30,
@@ -912,6 +1100,14 @@ fn default_screen_share_sender_policy() -> String {
     "auto_low_latency".to_string()
 }
 
+fn default_voice_frames_per_datagram() -> u8 {
+    1
+}
+
+fn default_frame_ms() -> u32 {
+    crate::audio::SessionAudioConfig::default().frame_ms
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum DspMethod {
     Rubato,
@@ -1024,6 +1220,40 @@ impl FecMode {
     }
 }
 
+/// Single user-facing knob that bundles the Opus loss-resilience settings
+/// (in-band FEC, expected packet loss and bitrate headroom).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, Default)]
+pub enum NetworkRobustness {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+impl NetworkRobustness {
+    pub const ALL: [NetworkRobustness; 3] = [
+        NetworkRobustness::Low,
+        NetworkRobustness::Medium,
+        NetworkRobustness::High,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            NetworkRobustness::Low => "Low (minimum bandwidth)",
+            NetworkRobustness::Medium => "Medium (recommended)",
+            NetworkRobustness::High => "High (lossy networks)",
+        }
+    }
+
+    pub fn short_label(self) -> &'static str {
+        match self {
+            NetworkRobustness::Low => "Low",
+            NetworkRobustness::Medium => "Medium",
+            NetworkRobustness::High => "High",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum CaptureMode {
     PushToTalk,
@@ -1413,11 +1643,18 @@ pub struct ChannelEntry {
     pub channel_type: ChannelType,
     pub parent_id: Option<String>,
     pub position: u32,
+    /// Members as of the last snapshot; `UiModel::members` is kept live.
     pub member_count: u32,
     pub user_limit: u32,
     pub description: String,
     pub bitrate_bps: u32,
     pub opus_profile: i32,
+    /// Chat text limit in bytes; 0 = server default.
+    pub max_message_length: u32,
+    /// Minimum seconds between our messages; 0 = slow mode off.
+    pub slow_mode_secs: u32,
+    /// Joins and leaves are posted to the channel's chat.
+    pub announce_presence: bool,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -1459,6 +1696,9 @@ pub struct ChatMessage {
     pub reactions: Vec<ReactionData>,
     pub pinned: bool,
     pub edited: bool,
+    /// Server-written announcement (e.g. a voice join/leave); `text` is
+    /// already complete and `author_id` is who it is about.
+    pub system: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -1484,10 +1724,28 @@ pub struct ReactionData {
     pub me: bool,
 }
 
+/// Backfill progress for one channel's chat history.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChatHistoryState {
+    /// A page request is in flight.
+    pub loading: bool,
+    /// Nothing older is left to fetch, or the local buffer is full.
+    pub exhausted: bool,
+    /// The last page request failed; nothing more is requested until the
+    /// user retries.
+    pub failed: bool,
+    /// Chat scroll content height when the pending page was requested, so
+    /// the panel can keep the view still once older rows land above it.
+    pub anchor_height: Option<f32>,
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct TelemetryData {
     pub rtt_ms: u32,
+    /// Inbound voice loss.
     pub loss_rate: f32,
+    /// Loss on our own QUIC packets, from the transport's stats.
+    pub transport_loss_rate: f32,
     pub jitter_ms: u32,
     pub rx_bitrate_bps: u32,
     pub tx_bitrate_bps: u32,
@@ -1495,13 +1753,25 @@ pub struct TelemetryData {
     pub tx_pps: u32,
     pub jitter_buffer_depth: u32,
     pub late_packets: u32,
+    pub reordered_packets: u32,
     pub lost_packets: u32,
     pub concealment_frames: u32,
     pub peak_stream_level: f32,
     pub send_queue_drop_count: u32,
     pub playout_delay_ms: u32,
+    /// Opus encode/decode failures and codec resets this session.
+    pub encode_errors: u32,
+    pub decode_errors: u32,
+    pub codec_resets: u32,
     pub agc_gain_db: f32,
     pub vad_probability: f32,
+    /// Echo removed by the canceller while the far end plays; `None` when
+    /// echo cancellation is off or has nothing to measure yet.
+    pub echo_return_loss_db: Option<f32>,
+    /// Debug voice impairment is active; loss/jitter above include it.
+    pub simulated_impairment: bool,
+    pub simulated_drops: u32,
+    pub simulated_delays: u32,
 }
 
 #[derive(Debug, Clone)]
@@ -1882,6 +2152,8 @@ pub struct UiModel {
     // Members (keyed by channel_id, with each channel list deduped by user_id)
     pub members: HashMap<String, Vec<MemberEntry>>,
     pub speaking_users: HashMap<String, bool>,
+    /// When each user last stopped speaking, for the indicator hold/fade.
+    pub speaking_released_at: HashMap<String, std::time::Instant>,
     pub voice_levels: HashMap<String, f32>,
 
     // Chat (keyed by channel_id)
@@ -1893,6 +2165,26 @@ pub struct UiModel {
     pub max_upload_bytes: u64,
     pub typing_users: HashMap<String, Vec<(String, std::time::Instant)>>,
     pub last_typing_sent_at: HashMap<String, std::time::Instant>,
+    /// Slow mode: channel_id -> earliest time we may post again.
+    pub chat_cooldown_until: HashMap<String, std::time::Instant>,
+    pub server_version: Option<String>,
+    pub server_feature_bits: Option<u64>,
+    /// Screen-share codecs the server negotiates; empty = no restriction.
+    pub server_screen_share_codecs: Vec<&'static str>,
+    /// Channel bitrate ceiling; 0 = unknown.
+    pub server_max_voice_bitrate_bps: u32,
+    /// Voice arrives as one server mix, so per-user gain and mute can't apply.
+    pub server_voice_mixing: bool,
+    pub read_receipts_enabled: bool,
+    /// channel_id -> user_id -> last read message_id
+    pub read_markers: HashMap<String, HashMap<String, String>>,
+    /// Channels whose chat is pushed to us without being joined.
+    pub chat_subscriptions: HashSet<String>,
+    pub chat_history: HashMap<String, ChatHistoryState>,
+    /// Messages from others since our read marker, for channels not selected.
+    pub unread_counts: HashMap<String, u32>,
+    /// The subset of `unread_counts` that mention us.
+    pub unread_mentions: HashMap<String, u32>,
 
     // Per-channel drafts (text + attachments preserved on channel switch)
     pub drafts: HashMap<String, DraftState>,
@@ -1906,7 +2198,15 @@ pub struct UiModel {
     pub ptt_active: bool,
     pub self_muted: bool,
     pub self_deafened: bool,
+    /// Everyone is muted locally; the server and other users don't see it.
+    pub local_mute_all: bool,
+    /// Members our voice is whispered to instead of the whole channel.
+    pub whisper_targets: Vec<String>,
+    /// Last raw VAD probability; the meter draws the smoothed fields below.
     pub vad_level: Option<f32>,
+    pub vad_level_smoothed: f32,
+    pub vad_peak: f32,
+    pub vad_peak_at: Option<std::time::Instant>,
     pub active_voice_channel_route: u32,
     pub voice_session_healthy: bool,
     pub connection_established_at: Option<std::time::Instant>,
@@ -1918,7 +2218,12 @@ pub struct UiModel {
 
     // Telemetry
     pub telemetry: TelemetryData,
+    /// A sample has arrived since the current connection came up; until then
+    /// `telemetry` holds defaults and the panel shows "no data".
+    pub telemetry_received: bool,
     pub member_telemetry: HashMap<String, TelemetryData>,
+    /// Server-reported loudest talkers per channel, loudest first.
+    pub talker_loudness: HashMap<String, Vec<(String, u8)>>,
 
     // UI toggles
     pub show_settings: bool,
@@ -1944,6 +2249,8 @@ pub struct UiModel {
     pub output_devices: Vec<AudioDeviceInfo>,
     pub capture_modes: Vec<String>,
     pub playback_modes: Vec<String>,
+    /// cpal-reported formats per device; missing for devices cpal can't open.
+    pub device_capabilities: HashMap<AudioDeviceId, DeviceCapabilities>,
     pub pipewire_pulse_fallback_suggested: bool,
 
     // Mic test loopback (runtime)
@@ -1964,6 +2271,9 @@ pub struct UiModel {
     pub rename_channel_name: String,
     pub rename_channel_codec: usize,
     pub rename_channel_quality: u32,
+    pub rename_channel_max_message_length: u32,
+    pub rename_channel_slow_mode_secs: u32,
+    pub rename_channel_announce_presence: bool,
     pub show_rename_channel: bool,
     pub delete_channel_target_id: Option<String>,
     pub show_delete_channel_confirm: bool,
@@ -2016,6 +2326,12 @@ pub struct UiModel {
     pub poke_target_user_id: String,
     pub poke_target_display_name: String,
     pub poke_message_draft: String,
+    pub show_ban_dialog: bool,
+    pub ban_target_user_id: String,
+    pub ban_target_display_name: String,
+    pub ban_reason_draft: String,
+    /// Seconds; 0 bans permanently.
+    pub ban_duration_secs: u32,
     pub avatar_url: Option<String>,
     pub away_message: String,
     pub away_message_draft: String,
@@ -2023,6 +2339,8 @@ pub struct UiModel {
 
     // Notifications
     pub notifications: VecDeque<Notification>,
+    pub next_notification_id: u64,
+    pub show_notification_center: bool,
 
     // ── Settings system ──
     pub settings: AppSettings,
@@ -2073,11 +2391,20 @@ pub struct DraftState {
     pub attachments: Vec<PendingAttachment>,
 }
 
+/// Upper bound on stored notifications; Info is evicted before anything
+/// that persists until dismissed.
+pub const MAX_NOTIFICATIONS: usize = 50;
+/// How long a notification shows as a toast (and how long Info is kept).
+pub const NOTIFICATION_TOAST_DURATION: std::time::Duration = std::time::Duration::from_secs(5);
+
 #[derive(Debug, Clone)]
 pub struct Notification {
+    pub id: u64,
     pub text: String,
     pub created: std::time::Instant,
     pub kind: NotificationKind,
+    /// Channel the notification center jumps to, if any.
+    pub channel_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -2088,7 +2415,10 @@ pub enum ConnectionStage {
     Handshaking,
     Authenticating,
     Syncing,
+    Joining,
     Connected,
+    /// The server is shutting down; a new connection starts right away.
+    Reconnecting,
     Failed,
 }
 
@@ -2100,6 +2430,8 @@ impl ConnectionStage {
                 | ConnectionStage::Handshaking
                 | ConnectionStage::Authenticating
                 | ConnectionStage::Syncing
+                | ConnectionStage::Joining
+                | ConnectionStage::Reconnecting
         )
     }
 
@@ -2110,13 +2442,15 @@ impl ConnectionStage {
             ConnectionStage::Handshaking => "Establishing QUIC/TLS",
             ConnectionStage::Authenticating => "Authenticating",
             ConnectionStage::Syncing => "Syncing initial state",
+            ConnectionStage::Joining => "Joining channel",
             ConnectionStage::Connected => "Connected",
+            ConnectionStage::Reconnecting => "Reconnecting",
             ConnectionStage::Failed => "Failed",
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum NotificationKind {
     Info,
     Poke,
@@ -2124,6 +2458,16 @@ pub enum NotificationKind {
     Error,
 }
 
+impl NotificationKind {
+    /// How long the notification is kept; `None` means until dismissed.
+    pub fn retention(self) -> Option<std::time::Duration> {
+        match self {
+            NotificationKind::Info => Some(NOTIFICATION_TOAST_DURATION),
+            NotificationKind::Poke | NotificationKind::Mention | NotificationKind::Error => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PermissionsTab {
     Roles,
@@ -2245,6 +2589,7 @@ impl Default for UiModel {
             selected_channel_name: String::new(),
             members: HashMap::new(),
             speaking_users: HashMap::new(),
+            speaking_released_at: HashMap::new(),
             voice_levels: HashMap::new(),
             messages: HashMap::new(),
             chat_composer: ChatComposer::new(),
@@ -2254,6 +2599,18 @@ impl Default for UiModel {
             max_upload_bytes: 25 * 1024 * 1024,
             typing_users: HashMap::new(),
             last_typing_sent_at: HashMap::new(),
+            chat_cooldown_until: HashMap::new(),
+            server_version: None,
+            server_feature_bits: None,
+            server_screen_share_codecs: Vec::new(),
+            server_max_voice_bitrate_bps: 0,
+            server_voice_mixing: false,
+            read_receipts_enabled: false,
+            read_markers: HashMap::new(),
+            chat_subscriptions: HashSet::new(),
+            chat_history: HashMap::new(),
+            unread_counts: HashMap::new(),
+            unread_mentions: HashMap::new(),
             drafts: HashMap::new(),
             drag_hovering: false,
             drag_overlay_until: None,
@@ -2261,7 +2618,12 @@ impl Default for UiModel {
             ptt_active: false,
             self_muted: false,
             self_deafened: false,
+            local_mute_all: false,
+            whisper_targets: Vec::new(),
             vad_level: None,
+            vad_level_smoothed: 0.0,
+            vad_peak: 0.0,
+            vad_peak_at: None,
             active_voice_channel_route: 0,
             voice_session_healthy: false,
             connection_established_at: None,
@@ -2269,7 +2631,9 @@ impl Default for UiModel {
             member_last_active_at: HashMap::new(),
             log: VecDeque::new(),
             telemetry: TelemetryData::default(),
+            telemetry_received: false,
             member_telemetry: HashMap::new(),
+            talker_loudness: HashMap::new(),
             show_settings: false,
             show_about: false,
             about_tab: 0,
@@ -2291,6 +2655,7 @@ impl Default for UiModel {
             output_devices: Vec::new(),
             capture_modes: Vec::new(),
             playback_modes: Vec::new(),
+            device_capabilities: HashMap::new(),
             pipewire_pulse_fallback_suggested: false,
             loopback_active: false,
             mic_test_waveform: Vec::new(),
@@ -2307,6 +2672,9 @@ impl Default for UiModel {
             rename_channel_name: String::new(),
             rename_channel_codec: 0,
             rename_channel_quality: 64,
+            rename_channel_max_message_length: 0,
+            rename_channel_slow_mode_secs: 0,
+            rename_channel_announce_presence: false,
             show_rename_channel: false,
             delete_channel_target_id: None,
             show_delete_channel_confirm: false,
@@ -2354,6 +2722,11 @@ impl Default for UiModel {
             poke_target_user_id: String::new(),
             poke_target_display_name: String::new(),
             poke_message_draft: "Poke".into(),
+            show_ban_dialog: false,
+            ban_target_user_id: String::new(),
+            ban_target_display_name: String::new(),
+            ban_reason_draft: String::new(),
+            ban_duration_secs: 0,
             avatar_url: None,
             away_message: String::new(),
             away_message_draft: String::new(),
@@ -2363,6 +2736,8 @@ impl Default for UiModel {
                 "In a meeting".into(),
             ],
             notifications: VecDeque::new(),
+            next_notification_id: 0,
+            show_notification_center: false,
             settings,
             settings_draft,
             settings_page: SettingsPage::Capture,
@@ -2417,10 +2792,48 @@ impl UiModel {
         }
     }
 
+    /// Whether the connected server advertises `feature`. Servers that send
+    /// no `ServerInfo` are assumed to support everything, as before it existed.
+    pub fn server_supports(&self, feature: ServerFeature) -> bool {
+        self.server_feature_bits
+            .is_none_or(|bits| bits & (1u64 << feature as u32) != 0)
+    }
+
     pub fn can_start_screen_share(&self) -> bool {
         !self.start_share_in_flight
             && !self.sharing_active
-            && !crate::net::dispatcher::available_screen_share_codecs().is_empty()
+            && !self.settings.low_bandwidth_mode
+            && self.server_supports(ServerFeature::ScreenShare)
+            && self
+                .shares_screen_share_codec(&crate::net::dispatcher::available_screen_share_codecs())
+    }
+
+    /// Whether we can encode a codec the server will negotiate.
+    fn shares_screen_share_codec(&self, ours: &[&'static str]) -> bool {
+        ours.iter().any(|codec| {
+            self.server_screen_share_codecs.is_empty()
+                || self.server_screen_share_codecs.contains(codec)
+        })
+    }
+
+    /// Slider bounds in kbps for a channel quality preset (0 = voice,
+    /// 1 = music), capped at the server's channel bitrate ceiling.
+    pub fn channel_quality_range_kbps(&self, codec: usize) -> RangeInclusive<i32> {
+        let (min, max) = match codec {
+            0 => (8, 128),
+            1 => (32, 510),
+            _ => (8, 510),
+        };
+        let max = match self.server_max_voice_bitrate_bps {
+            0 => max,
+            bps => max.min((bps / 1000) as i32).max(min),
+        };
+        min..=max
+    }
+
+    /// Per-user volume and "Mute for me" only work on per-talker streams.
+    pub fn per_user_audio_available(&self) -> bool {
+        !self.server_voice_mixing
     }
 
     /// Open the profile popup for a given user, anchored near `click_pos`.
@@ -2550,11 +2963,35 @@ impl UiModel {
             });
     }
 
+    /// Ask for confirmation, a reason and a duration before banning a user.
+    pub fn open_ban_dialog(&mut self, user_id: String, display_name: String) {
+        self.show_ban_dialog = true;
+        self.ban_target_user_id = user_id;
+        self.ban_target_display_name = display_name;
+        self.ban_reason_draft.clear();
+        self.ban_duration_secs = 0;
+    }
+
+    /// Drop every telemetry sample, ours and other members', so nothing from a
+    /// previous connection is shown.
+    pub fn reset_telemetry(&mut self) {
+        self.telemetry = TelemetryData::default();
+        self.telemetry_received = false;
+        self.member_telemetry.clear();
+        self.talker_loudness.clear();
+        for window in &mut self.member_connection_info_windows {
+            window.telemetry = TelemetryData::default();
+        }
+    }
+
     pub fn apply_event(&mut self, ev: UiEvent) {
         match ev {
             UiEvent::SetConnected(c) => {
                 self.connected = c;
                 self.connection_established_at = c.then(std::time::Instant::now);
+                // A new QUIC connection and jitter buffer start from scratch;
+                // stats from the old one would only mislead.
+                self.reset_telemetry();
             }
             UiEvent::SetAuthed(a) => self.authed = a,
             UiEvent::SetChannelName(n) => {
@@ -2581,6 +3018,8 @@ impl UiModel {
                     self.chat_composer.clear();
                     self.pending_attachments.clear();
                 }
+                self.unread_counts.remove(&n);
+                self.unread_mentions.remove(&n);
                 self.selected_channel = Some(n.clone());
                 self.selected_channel_name =
                     self.channel_name_for_id(&n).map(str::to_owned).unwrap_or(n);
@@ -2677,11 +3116,7 @@ impl UiModel {
                 }
             }
             UiEvent::Notify { text, kind } => {
-                self.notifications.push_back(Notification {
-                    text,
-                    created: std::time::Instant::now(),
-                    kind,
-                });
+                self.push_notification(kind, text, None);
             }
             UiEvent::SetChannels(chs) => {
                 self.channels = chs;
@@ -2695,7 +3130,7 @@ impl UiModel {
             }
             UiEvent::UpdateChannelMembers {
                 channel_id,
-                members,
+                mut members,
             } => {
                 let now = std::time::Instant::now();
                 for member in &members {
@@ -2703,6 +3138,23 @@ impl UiModel {
                         .entry(member.user_id.clone())
                         .or_insert(now);
                 }
+                // The server list is authoritative for who is here and their
+                // voice state, but it never carries speaking/streaming and
+                // refresh pushes omit profile visuals; keep what we know.
+                if let Some(previous) = self.members.get(&channel_id) {
+                    for member in &mut members {
+                        if let Some(old) = previous.iter().find(|m| m.user_id == member.user_id) {
+                            member.speaking = old.speaking;
+                            member.streaming |= old.streaming;
+                            if member.avatar_url.is_none() {
+                                member.avatar_url = old.avatar_url.clone();
+                            }
+                            if member.accent_color.is_none() {
+                                member.accent_color = old.accent_color;
+                            }
+                        }
+                    }
+                }
                 self.members.insert(channel_id, members);
                 self.refresh_message_author_metadata();
             }
@@ -2758,10 +3210,116 @@ impl UiModel {
                     channel_id = %msg.channel_id,
                     "chat dedupe miss (appending message)"
                 );
+                let mention = (!msg.system
+                    && msg.author_id != local_user_id
+                    && mentions_name(&msg.text, &self.nick))
+                .then(|| {
+                    (
+                        format!("{} mentioned you", msg.author_name),
+                        msg.channel_id.clone(),
+                    )
+                });
+                let unread = (!msg.system
+                    && msg.author_id != local_user_id
+                    && self.selected_channel.as_deref() != Some(msg.channel_id.as_str()))
+                .then(|| msg.channel_id.clone());
                 msgs.push_back(msg);
                 if msgs.len() > MAX_MESSAGES_PER_CHANNEL {
                     msgs.pop_front();
                 }
+                if let Some(channel_id) = unread {
+                    if mention.is_some() {
+                        *self.unread_mentions.entry(channel_id.clone()).or_default() += 1;
+                    }
+                    *self.unread_counts.entry(channel_id).or_default() += 1;
+                }
+                if let Some((text, channel_id)) = mention {
+                    self.push_notification(NotificationKind::Mention, text, Some(channel_id));
+                }
+            }
+            UiEvent::HistoryLoaded {
+                channel_id,
+                messages,
+                exhausted,
+            } => {
+                let page = messages
+                    .into_iter()
+                    .map(|mut msg| {
+                        msg.author_name = self.resolve_message_author_name(
+                            &msg.channel_id,
+                            &msg.author_id,
+                            &msg.author_name,
+                        );
+                        msg.author_name_color = self.resolve_message_author_name_color(
+                            &msg.author_id,
+                            msg.author_name_color,
+                        );
+                        msg.author_avatar_url = self.resolve_message_author_avatar_url(
+                            &msg.channel_id,
+                            &msg.author_id,
+                            msg.author_avatar_url.as_deref(),
+                        );
+                        msg
+                    })
+                    .collect::<Vec<_>>();
+                let history = self.chat_history.entry(channel_id.clone()).or_default();
+                history.loading = false;
+                history.failed = false;
+                history.exhausted |= exhausted;
+                // History is old news: no unread counts or mention toasts.
+                let msgs = self.messages.entry(channel_id).or_default();
+                let known = msgs
+                    .iter()
+                    .map(|msg| msg.message_id.clone())
+                    .collect::<HashSet<_>>();
+                msgs.extend(
+                    page.into_iter()
+                        .filter(|msg| !known.contains(&msg.message_id)),
+                );
+                // A rejoin's first page can straddle messages we already hold.
+                msgs.make_contiguous().sort_by_key(|msg| msg.timestamp);
+                while msgs.len() > MAX_MESSAGES_PER_CHANNEL {
+                    msgs.pop_front();
+                    history.exhausted = true;
+                }
+            }
+            UiEvent::HistoryLoadFailed { channel_id } => {
+                let history = self.chat_history.entry(channel_id).or_default();
+                history.loading = false;
+                history.failed = true;
+                history.anchor_height = None;
+            }
+            UiEvent::AttachmentCached {
+                channel_id,
+                message_id,
+                asset_id,
+                download_url,
+            } => {
+                let attachment = self
+                    .messages
+                    .get_mut(&channel_id)
+                    .and_then(|msgs| msgs.iter_mut().find(|m| m.message_id == message_id))
+                    .and_then(|msg| {
+                        msg.attachments.iter_mut().find(|a| {
+                            matches!(
+                                &a.asset,
+                                AttachmentAsset::UploadedAssetId(id) if *id == asset_id
+                            )
+                        })
+                    });
+                if let Some(attachment) = attachment {
+                    attachment.download_url = download_url;
+                }
+            }
+            UiEvent::SetChannelChatSubscribed {
+                channel_id,
+                subscribed,
+            } => {
+                if subscribed {
+                    self.chat_subscriptions.insert(channel_id);
+                } else {
+                    self.chat_subscriptions.remove(&channel_id);
+                }
             }
             UiEvent::PlayChatMessageSfx => {
                 if self.settings.notify_chat_message {
@@ -2788,6 +3346,14 @@ impl UiModel {
                     msgs.retain(|m| m.message_id != message_id);
                 }
             }
+            UiEvent::ChatCooldown {
+                channel_id,
+                retry_after,
+            } => {
+                let until = std::time::Instant::now() + retry_after;
+                let entry = self.chat_cooldown_until.entry(channel_id).or_insert(until);
+                *entry = (*entry).max(until);
+            }
             UiEvent::ReactionAdded {
                 channel_id,
                 message_id,
@@ -2835,6 +3401,38 @@ impl UiModel {
                     }
                 }
             }
+            UiEvent::ReactionChanged {
+                channel_id,
+                message_id,
+                emoji,
+                user_id,
+                added,
+                count,
+            } => {
+                let Some(msg) = self
+                    .messages
+                    .get_mut(&channel_id)
+                    .and_then(|msgs| msgs.iter_mut().find(|m| m.message_id == message_id))
+                else {
+                    return;
+                };
+                let mine = user_id == self.user_id;
+                match msg.reactions.iter().position(|r| r.emoji == emoji) {
+                    _ if count == 0 => msg.reactions.retain(|r| r.emoji != emoji),
+                    Some(idx) => {
+                        let reaction = &mut msg.reactions[idx];
+                        reaction.count = count;
+                        if mine {
+                            reaction.me = added;
+                        }
+                    }
+                    None => msg.reactions.push(ReactionData {
+                        emoji,
+                        count,
+                        me: mine && added,
+                    }),
+                }
+            }
             UiEvent::ClearPendingAttachments => {
                 self.pending_attachments.clear();
                 if let Some(ref ch) = self.selected_channel {
@@ -2847,11 +3445,11 @@ impl UiModel {
                 if let Some(att) = self.pending_attachments.iter_mut().find(|a| a.path == path) {
                     att.error = Some(error.clone());
                 }
-                self.notifications.push_back(Notification {
-                    text: format!("Upload failed: {error}"),
-                    created: std::time::Instant::now(),
-                    kind: NotificationKind::Error,
-                });
+                self.push_notification(
+                    NotificationKind::Error,
+                    format!("Upload failed: {error}"),
+                    None,
+                );
             }
             UiEvent::TypingIndicator {
                 channel_id,
@@ -2867,6 +3465,47 @@ impl UiModel {
                 typers.retain(|(name, _)| name != &user_name);
                 typers.push((user_name, std::time::Instant::now()));
             }
+            UiEvent::SetServerInfo {
+                version,
+                feature_bits,
+                screen_share_codecs,
+                max_voice_bitrate_bps,
+                voice_mixing,
+            } => {
+                self.server_version = version;
+                self.server_feature_bits = feature_bits;
+                self.server_screen_share_codecs = screen_share_codecs;
+                self.server_max_voice_bitrate_bps = max_voice_bitrate_bps;
+                self.server_voice_mixing = voice_mixing;
+            }
+            UiEvent::SetReadReceiptsEnabled(enabled) => {
+                self.read_receipts_enabled = enabled;
+                if !enabled {
+                    self.read_markers.clear();
+                }
+            }
+            UiEvent::SetReadMarkers {
+                channel_id,
+                markers,
+            } => {
+                self.read_markers
+                    .insert(channel_id.clone(), markers.into_iter().collect());
+                self.recount_unread(&channel_id);
+            }
+            UiEvent::ReadMarkerUpdated {
+                channel_id,
+                user_id,
+                message_id,
+            } => {
+                let own = user_id == self.user_id;
+                self.read_markers
+                    .entry(channel_id.clone())
+                    .or_default()
+                    .insert(user_id, message_id);
+                if own {
+                    self.recount_unread(&channel_id);
+                }
+            }
             UiEvent::MemberJoined { channel_id, member } => {
                 let joined_user_id = member.user_id.clone();
                 let joined_channel_route = Uuid::parse_str(&channel_id)
@@ -2927,6 +3566,9 @@ impl UiModel {
                     self.refresh_selected_channel_name();
                 }
             }
+            // Turned into a JoinChannel intent by the app; the join itself
+            // updates the selection.
+            UiEvent::MovedByModerator { .. } => {}
             UiEvent::MemberAwayMessageUpdated {
                 user_id,
                 away_message,
@@ -2971,12 +3613,15 @@ impl UiModel {
             }
             UiEvent::SetActiveVoiceRoute(route) => self.active_voice_channel_route = route,
             UiEvent::VoiceSessionHealth(healthy) => self.voice_session_healthy = healthy,
-            UiEvent::VadLevel(v) => self.vad_level = Some(v),
+            UiEvent::VadLevel(v) => self.update_vad_meter(v, std::time::Instant::now()),
             UiEvent::MicTestWaveform(samples) => self.mic_test_waveform = samples,
             UiEvent::VoiceActivity { user_id, speaking } => {
+                let now = std::time::Instant::now();
                 if speaking {
-                    self.member_last_active_at
-                        .insert(user_id.clone(), std::time::Instant::now());
+                    self.member_last_active_at.insert(user_id.clone(), now);
+                    self.speaking_released_at.remove(&user_id);
+                } else if self.speaking_users.get(&user_id) == Some(&true) {
+                    self.speaking_released_at.insert(user_id.clone(), now);
                 }
                 self.speaking_users.insert(user_id, speaking);
             }
@@ -2985,6 +3630,44 @@ impl UiModel {
             }
             UiEvent::TelemetryUpdate(t) => {
                 self.telemetry = t;
+                self.telemetry_received = true;
+            }
+            UiEvent::SetTalkerLoudness {
+                channel_id,
+                talkers,
+            } => {
+                if talkers.is_empty() {
+                    self.talker_loudness.remove(&channel_id);
+                } else {
+                    self.talker_loudness.insert(channel_id, talkers);
+                }
+            }
+            UiEvent::TalkerPreempted {
+                channel_id,
+                preempted_user_id,
+                by_user_id,
+            } => {
+                let preempted = self.member_name_in(&channel_id, &preempted_user_id);
+                let by = self.member_name_in(&channel_id, &by_user_id);
+                if self.user_id == preempted_user_id {
+                    self.push_notification(
+                        NotificationKind::Info,
+                        format!("{by} took over your talker slot"),
+                        Some(channel_id),
+                    );
+                } else if self.user_id == by_user_id {
+                    self.push_notification(
+                        NotificationKind::Info,
+                        format!("You took over {preempted}'s talker slot"),
+                        Some(channel_id),
+                    );
+                } else {
+                    self.log
+                        .push_back(format!("[voice] {by} took over {preempted}'s talker slot"));
+                    if self.log.len() > MAX_LOG_LINES {
+                        self.log.pop_front();
+                    }
+                }
             }
             UiEvent::MemberTelemetryUpdate { user_id, telemetry } => {
                 self.member_telemetry
@@ -3001,11 +3684,7 @@ impl UiModel {
                 } else {
                     format!("{from_name} poked you: {message}")
                 };
-                self.notifications.push_back(Notification {
-                    text,
-                    created: std::time::Instant::now(),
-                    kind: NotificationKind::Poke,
-                });
+                self.push_notification(NotificationKind::Poke, text, None);
             }
             UiEvent::UserProfileLoaded(mut profile) => {
                 self.profile_fetch_in_flight.remove(&profile.user_id);
@@ -3148,16 +3827,20 @@ impl UiModel {
             }
             UiEvent::SetSelfMuted(m) => self.self_muted = m,
             UiEvent::SetSelfDeafened(d) => self.self_deafened = d,
+            UiEvent::SetLocalMuteAll(m) => self.local_mute_all = m,
+            UiEvent::WhisperTargetsChanged(targets) => self.whisper_targets = targets,
             UiEvent::SetAudioDevices {
                 input_devices,
                 output_devices,
                 capture_modes,
                 playback_modes,
+                device_capabilities,
             } => {
                 self.input_devices = input_devices;
                 self.output_devices = output_devices;
                 self.capture_modes = capture_modes;
                 self.playback_modes = playback_modes;
+                self.device_capabilities = device_capabilities;
             }
             UiEvent::ChannelCreated(entry) => {
                 if let Some(existing) = self.channels.iter_mut().find(|ch| ch.id == entry.id) {
@@ -3181,6 +3864,14 @@ impl UiModel {
                 }
                 self.refresh_selected_channel_name();
             }
+            UiEvent::SetChannelUserLimit {
+                channel_id,
+                user_limit,
+            } => {
+                if let Some(existing) = self.channels.iter_mut().find(|ch| ch.id == channel_id) {
+                    existing.user_limit = user_limit;
+                }
+            }
             UiEvent::ChannelDeleted { channel_id } => {
                 let mut removed = HashSet::new();
                 removed.insert(channel_id.clone());
@@ -3203,6 +3894,10 @@ impl UiModel {
                     self.members.remove(removed_id);
                     self.messages.remove(removed_id);
                     self.typing_users.remove(removed_id);
+                    self.unread_counts.remove(removed_id);
+                    self.unread_mentions.remove(removed_id);
+                    self.chat_subscriptions.remove(removed_id);
+                    self.chat_history.remove(removed_id);
                     self.channel_collapsed.remove(removed_id);
                 }
 
@@ -3307,15 +4002,64 @@ impl UiModel {
             typers.retain(|(_, t)| *t > cutoff);
         }
 
-        // Expire old notifications (>5s)
-        let notif_cutoff = std::time::Instant::now() - std::time::Duration::from_secs(5);
-        while self
-            .notifications
-            .front()
-            .is_some_and(|n| n.created < notif_cutoff)
-        {
-            self.notifications.pop_front();
+        self.expire_notifications(std::time::Instant::now());
+    }
+
+    pub fn push_notification(
+        &mut self,
+        kind: NotificationKind,
+        text: String,
+        channel_id: Option<String>,
+    ) {
+        self.expire_notifications(std::time::Instant::now());
+        if self.notifications.len() >= MAX_NOTIFICATIONS {
+            let victim = self
+                .notifications
+                .iter()
+                .position(|n| n.kind == NotificationKind::Info)
+                .unwrap_or(0);
+            self.notifications.remove(victim);
         }
+        self.next_notification_id += 1;
+        self.notifications.push_back(Notification {
+            id: self.next_notification_id,
+            text,
+            created: std::time::Instant::now(),
+            kind,
+            channel_id,
+        });
+    }
+
+    pub fn dismiss_notification(&mut self, id: u64) {
+        self.notifications.retain(|n| n.id != id);
+    }
+
+    fn expire_notifications(&mut self, now: std::time::Instant) {
+        self.notifications.retain(|n| {
+            n.kind
+                .retention()
+                .is_none_or(|keep| now.saturating_duration_since(n.created) < keep)
+        });
+    }
+
+    /// Notifications young enough to still show as toasts, newest first.
+    pub fn toast_notifications(
+        &self,
+        now: std::time::Instant,
+    ) -> impl Iterator<Item = &Notification> {
+        self.notifications.iter().rev().take_while(move |n| {
+            now.saturating_duration_since(n.created) < NOTIFICATION_TOAST_DURATION
+        })
+    }
+
+    /// Display name of `user_id` among `channel_id`'s members, falling back
+    /// to the id itself.
+    fn member_name_in(&self, channel_id: &str, user_id: &str) -> String {
+        self.members
+            .get(channel_id)
+            .and_then(|members| members.iter().find(|m| m.user_id == user_id))
+            .map(|m| m.display_name.clone())
+            .unwrap_or_else(|| user_id.to_string())
     }
 
     fn channel_name_for_id(&self, channel_id: &str) -> Option<&str> {
@@ -3548,6 +4292,51 @@ impl UiModel {
             .unwrap_or(false)
     }
 
+    /// Member count and user limit for a capped channel; `None` when the
+    /// channel has no limit.
+    pub fn channel_occupancy(&self, ch: &ChannelEntry) -> Option<(usize, u32)> {
+        if ch.user_limit == 0 {
+            return None;
+        }
+        let members = self.members.get(&ch.id).map_or(0, Vec::len);
+        Some((members, ch.user_limit))
+    }
+
+    /// Chat text limit for the selected channel, when it sets one.
+    pub fn current_max_message_length(&self) -> Option<u32> {
+        let selected = self.selected_channel.as_deref()?;
+        self.channels
+            .iter()
+            .find(|channel| channel.id == selected)
+            .map(|channel| channel.max_message_length)
+            .filter(|&max| max > 0)
+    }
+
+    /// Time left before the selected channel's slow mode lets us post again.
+    pub fn chat_cooldown_remaining(&self, now: std::time::Instant) -> Option<std::time::Duration> {
+        let selected = self.selected_channel.as_deref()?;
+        let until = *self.chat_cooldown_until.get(selected)?;
+        (until > now).then(|| until - now)
+    }
+
+    /// Start the local slow-mode countdown after sending to `channel_id`.
+    /// The server stays authoritative and corrects it via `ChatCooldown`.
+    pub fn start_chat_cooldown(&mut self, channel_id: &str, now: std::time::Instant) {
+        let Some(secs) = self
+            .channels
+            .iter()
+            .find(|channel| channel.id == channel_id)
+            .map(|channel| channel.slow_mode_secs)
+            .filter(|&secs| secs > 0)
+        else {
+            return;
+        };
+        self.chat_cooldown_until.insert(
+            channel_id.to_string(),
+            now + std::time::Duration::from_secs(u64::from(secs)),
+        );
+    }
+
     pub fn current_channel_type(&self) -> Option<ChannelType> {
         self.selected_channel.as_ref().and_then(|selected| {
             self.channels
@@ -3573,39 +4362,307 @@ impl UiModel {
             .unwrap_or(&[])
     }
 
-    /// Get typing users for the currently selected channel.
-    pub fn current_typing_users(&self) -> Vec<&str> {
-        self.selected_channel
+    /// [`Self::current_members`] with the server's loudest talkers moved to
+    /// the top, loudest first; everyone else keeps their usual order.
+    pub fn current_members_by_loudness(&self) -> Vec<MemberEntry> {
+        let mut members = self.current_members().to_vec();
+        let Some(talkers) = self
+            .selected_channel
             .as_ref()
-            .and_then(|ch| self.typing_users.get(ch))
-            .map(|typers| typers.iter().map(|(name, _)| name.as_str()).collect())
-            .unwrap_or_default()
+            .and_then(|ch| self.talker_loudness.get(ch))
+        else {
+            return members;
+        };
+        members.sort_by_key(|m| {
+            talkers
+                .iter()
+                .position(|(user_id, _)| *user_id == m.user_id)
+                .unwrap_or(usize::MAX)
+        });
+        members
     }
-}
-
-fn should_prefer_fallback_name(name: &str) -> bool {
-    let trimmed = name.trim();
-    trimmed.is_empty() || trimmed.starts_with("guest-") || trimmed.starts_with("user-")
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
-    #[test]
-    fn can_start_screen_share_is_debounced() {
-        let mut model = UiModel::default();
-        let has_codecs = !crate::net::dispatcher::available_screen_share_codecs().is_empty();
-        assert_eq!(model.can_start_screen_share(), has_codecs);
-        model.start_share_in_flight = true;
-        assert!(!model.can_start_screen_share());
-        model.start_share_in_flight = false;
-        model.sharing_active = true;
-        assert!(!model.can_start_screen_share());
+    /// The newest server-acknowledged message in the selected channel if our
+    /// own marker hasn't reached it yet. Records it locally so the caller
+    /// sends at most one `MarkRead` per new message.
+    pub fn take_pending_mark_read(&mut self) -> Option<(String, String)> {
+        if !self.read_receipts_enabled || self.user_id.is_empty() {
+            return None;
+        }
+        let channel_id = self.selected_channel.clone()?;
+        let latest = self
+            .messages
+            .get(&channel_id)?
+            .iter()
+            .rev()
+            .find(|msg| !msg.message_id.starts_with("local-"))?
+            .message_id
+            .clone();
+        let markers = self.read_markers.entry(channel_id.clone()).or_default();
+        if markers.get(&self.user_id) == Some(&latest) {
+            return None;
+        }
+        markers.insert(self.user_id.clone(), latest.clone());
+        self.unread_counts.remove(&channel_id);
+        self.unread_mentions.remove(&channel_id);
+        Some((channel_id, latest))
     }
 
-    #[test]
-    fn sync_settings_updates_nick_and_connection_nickname() {
+    /// A request for the page before the oldest message in the selected
+    /// channel, unless one is already in flight or history is exhausted.
+    /// Marks the page as loading and remembers `content_height` for
+    /// [`ChatHistoryState::anchor_height`].
+    pub fn take_older_messages_request(&mut self, content_height: f32) -> Option<UiIntent> {
+        let channel_id = self.selected_channel.clone()?;
+        let history = self.chat_history.entry(channel_id.clone()).or_default();
+        if history.loading || history.exhausted || history.failed {
+            return None;
+        }
+        // The first page comes with the join; until it lands there's no cursor.
+        let before = self
+            .messages
+            .get(&channel_id)?
+            .iter()
+            .find(|msg| !msg.message_id.starts_with("local-"))?
+            .message_id
+            .clone();
+        history.loading = true;
+        history.anchor_height = Some(content_height);
+        Some(UiIntent::LoadOlderMessages { channel_id, before })
+    }
+
+    /// Clear a failed history request for the selected channel and ask again:
+    /// for the page before our oldest message, or the newest page if the
+    /// first one never arrived.
+    pub fn retry_chat_history(&mut self) -> Option<UiIntent> {
+        let channel_id = self.selected_channel.clone()?;
+        let history = self.chat_history.get_mut(&channel_id)?;
+        if !history.failed || history.loading {
+            return None;
+        }
+        history.failed = false;
+        history.loading = true;
+        let before = self
+            .messages
+            .get(&channel_id)
+            .and_then(|msgs| {
+                msgs.iter()
+                    .find(|msg| !msg.message_id.starts_with("local-"))
+            })
+            .map(|msg| msg.message_id.clone())
+            .unwrap_or_default();
+        Some(UiIntent::LoadOlderMessages { channel_id, before })
+    }
+
+    /// Rebuild a background channel's unread and mention counts from our read
+    /// marker, e.g. after reading it on another device. Left alone when the
+    /// marker has scrolled out of the local message buffer.
+    fn recount_unread(&mut self, channel_id: &str) {
+        if self.selected_channel.as_deref() == Some(channel_id) {
+            return;
+        }
+        let Some(marker) = self
+            .read_markers
+            .get(channel_id)
+            .and_then(|markers| markers.get(&self.user_id))
+        else {
+            return;
+        };
+        let Some(msgs) = self.messages.get(channel_id) else {
+            return;
+        };
+        let Some(read_idx) = msgs.iter().position(|msg| &msg.message_id == marker) else {
+            return;
+        };
+        let (mut unread, mut mentions) = (0, 0);
+        for msg in msgs
+            .iter()
+            .skip(read_idx + 1)
+            .filter(|msg| !msg.system && msg.author_id != self.user_id)
+        {
+            unread += 1;
+            if mentions_name(&msg.text, &self.nick) {
+                mentions += 1;
+            }
+        }
+        for (counts, n) in [
+            (&mut self.unread_counts, unread),
+            (&mut self.unread_mentions, mentions),
+        ] {
+            if n > 0 {
+                counts.insert(channel_id.to_string(), n);
+            } else {
+                counts.remove(channel_id);
+            }
+        }
+    }
+
+    /// Display names of other members whose read marker sits on `message_id`.
+    pub fn read_by(&self, channel_id: &str, message_id: &str) -> Vec<&str> {
+        let Some(markers) = self.read_markers.get(channel_id) else {
+            return Vec::new();
+        };
+        let members = self.members.get(channel_id);
+        markers
+            .iter()
+            .filter(|(user_id, read)| *read == message_id && **user_id != self.user_id)
+            .filter_map(|(user_id, _)| {
+                members?
+                    .iter()
+                    .find(|m| &m.user_id == user_id)
+                    .map(|m| m.display_name.as_str())
+            })
+            .collect()
+    }
+
+    fn update_vad_meter(&mut self, raw: f32, now: std::time::Instant) {
+        let raw = raw.clamp(0.0, 1.0);
+        self.vad_level_smoothed = match self.vad_level {
+            Some(_) => {
+                self.vad_level_smoothed + VAD_METER_SMOOTHING * (raw - self.vad_level_smoothed)
+            }
+            None => raw,
+        };
+        self.vad_level = Some(raw);
+        let held = self
+            .vad_peak_at
+            .is_some_and(|at| now.saturating_duration_since(at) < VAD_METER_PEAK_HOLD);
+        if raw >= self.vad_peak || !held {
+            self.vad_peak = raw;
+            self.vad_peak_at = Some(now);
+        }
+    }
+
+    /// `(smoothed level, held peak)` for the VAD meter, once any level arrived.
+    pub fn vad_meter(&self) -> Option<(f32, f32)> {
+        self.vad_level
+            .map(|_| (self.vad_level_smoothed, self.vad_peak))
+    }
+
+    /// Opacity of `user_id`'s speaking ring: 1.0 while speaking and for
+    /// `speaking_hold_ms` after, then fading to 0.0 over [`SPEAKING_FADE`].
+    pub fn speaking_intensity(&self, user_id: &str, now: std::time::Instant) -> f32 {
+        if self.speaking_users.get(user_id) == Some(&true) {
+            return 1.0;
+        }
+        let Some(released) = self.speaking_released_at.get(user_id) else {
+            return 0.0;
+        };
+        let hold = std::time::Duration::from_millis(u64::from(self.settings.speaking_hold_ms));
+        let since = now.saturating_duration_since(*released);
+        if since <= hold {
+            return 1.0;
+        }
+        let faded = (since - hold).as_secs_f32() / SPEAKING_FADE.as_secs_f32();
+        (1.0 - faded).max(0.0)
+    }
+
+    /// Get typing users for the currently selected channel.
+    pub fn current_typing_users(&self) -> Vec<&str> {
+        self.selected_channel
+            .as_ref()
+            .and_then(|ch| self.typing_users.get(ch))
+            .map(|typers| typers.iter().map(|(name, _)| name.as_str()).collect())
+            .unwrap_or_default()
+    }
+}
+
+fn should_prefer_fallback_name(name: &str) -> bool {
+    let trimmed = name.trim();
+    trimmed.is_empty() || trimmed.starts_with("guest-") || trimmed.starts_with("user-")
+}
+
+/// True when `text` contains `@name` (case-insensitive) not followed by
+/// another word character, so `@ann` does not match `@anna`.
+fn mentions_name(text: &str, name: &str) -> bool {
+    let name = name.trim();
+    if name.is_empty() {
+        return false;
+    }
+    let needle = format!("@{}", name.to_lowercase());
+    let haystack = text.to_lowercase();
+    haystack.match_indices(&needle).any(|(at, _)| {
+        haystack[at + needle.len()..]
+            .chars()
+            .next()
+            .is_none_or(|c| !c.is_alphanumeric() && c != '_')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_start_screen_share_is_debounced() {
+        let mut model = UiModel::default();
+        let has_codecs = !crate::net::dispatcher::available_screen_share_codecs().is_empty();
+        assert_eq!(model.can_start_screen_share(), has_codecs);
+        model.start_share_in_flight = true;
+        assert!(!model.can_start_screen_share());
+        model.start_share_in_flight = false;
+        model.sharing_active = true;
+        assert!(!model.can_start_screen_share());
+        model.sharing_active = false;
+        model.settings.low_bandwidth_mode = true;
+        assert!(!model.can_start_screen_share());
+    }
+
+    #[test]
+    fn server_features_gate_ui_and_unknown_bits_are_ignored() {
+        use crate::proto::voiceplatform::v1::ServerFeature;
+
+        let mut model = UiModel::default();
+        assert!(model.server_supports(ServerFeature::Reactions));
+
+        let bit = |f: ServerFeature| 1u64 << f as u32;
+        model.apply_event(UiEvent::SetServerInfo {
+            version: Some("9.9.9".into()),
+            // Bit 63 stands in for a feature this client doesn't know.
+            feature_bits: Some(bit(ServerFeature::Poke) | 1 << 63),
+            screen_share_codecs: vec![],
+            max_voice_bitrate_bps: 0,
+            voice_mixing: false,
+        });
+        assert_eq!(model.server_version.as_deref(), Some("9.9.9"));
+        assert!(model.server_supports(ServerFeature::Poke));
+        assert!(!model.server_supports(ServerFeature::Reactions));
+        assert!(!model.can_start_screen_share());
+
+        model.apply_event(UiEvent::SetServerInfo {
+            version: None,
+            feature_bits: None,
+            screen_share_codecs: vec![],
+            max_voice_bitrate_bps: 0,
+            voice_mixing: false,
+        });
+        assert!(model.server_supports(ServerFeature::Reactions));
+    }
+
+    #[test]
+    fn server_caps_limit_channel_quality_codecs_and_per_user_audio() {
+        let mut model = UiModel::default();
+        assert_eq!(model.channel_quality_range_kbps(1), 32..=510);
+        assert!(model.shares_screen_share_codec(&["VP9"]));
+        assert!(model.per_user_audio_available());
+
+        model.apply_event(UiEvent::SetServerInfo {
+            version: Some("9.9.9".into()),
+            feature_bits: None,
+            screen_share_codecs: vec!["AV1"],
+            max_voice_bitrate_bps: 96_000,
+            voice_mixing: true,
+        });
+        assert_eq!(model.channel_quality_range_kbps(0), 8..=96);
+        assert_eq!(model.channel_quality_range_kbps(1), 32..=96);
+        assert!(!model.shares_screen_share_codec(&["VP9"]));
+        assert!(model.shares_screen_share_codec(&["VP9", "AV1"]));
+        assert!(!model.shares_screen_share_codec(&[]));
+        assert!(!model.per_user_audio_available());
+    }
+
+    #[test]
+    fn sync_settings_updates_nick_and_connection_nickname() {
         let mut model = UiModel::new();
         model.settings.identity_nickname = "Overdose".into();
         model.settings.last_server_host = "192.168.1.120".into();
@@ -3665,6 +4722,128 @@ mod tests {
         );
     }
 
+    #[test]
+    fn connection_changes_reset_telemetry_until_fresh_samples_arrive() {
+        let mut model = UiModel::new();
+        model.apply_event(UiEvent::TelemetryUpdate(TelemetryData {
+            rtt_ms: 42,
+            loss_rate: 0.2,
+            ..TelemetryData::default()
+        }));
+        model.apply_event(UiEvent::MemberTelemetryUpdate {
+            user_id: "user-a".into(),
+            telemetry: TelemetryData {
+                rtt_ms: 77,
+                ..TelemetryData::default()
+            },
+        });
+        assert!(model.telemetry_received);
+
+        model.apply_event(UiEvent::SetConnected(false));
+        assert!(!model.telemetry_received);
+        assert_eq!(model.telemetry.rtt_ms, 0);
+        assert_eq!(model.telemetry.loss_rate, 0.0);
+        assert!(model.member_telemetry.is_empty());
+        assert!(model.talker_loudness.is_empty());
+
+        model.apply_event(UiEvent::SetConnected(true));
+        assert!(!model.telemetry_received);
+        model.apply_event(UiEvent::TelemetryUpdate(TelemetryData {
+            rtt_ms: 30,
+            ..TelemetryData::default()
+        }));
+        assert!(model.telemetry_received);
+        assert_eq!(model.telemetry.rtt_ms, 30);
+    }
+
+    #[test]
+    fn talker_preemption_notifies_only_the_people_involved() {
+        let mut model = UiModel::new();
+        model.apply_event(UiEvent::SetUserId("me".into()));
+        let member = |user_id: &str, name: &str| MemberEntry {
+            user_id: user_id.into(),
+            display_name: name.into(),
+            away_message: String::new(),
+            custom_status_emoji: String::new(),
+            muted: false,
+            deafened: false,
+            self_muted: false,
+            self_deafened: false,
+            streaming: false,
+            speaking: false,
+            avatar_url: None,
+            accent_color: None,
+        };
+        model.members.insert(
+            "lounge".into(),
+            vec![member("me", "Me"), member("a", "Alice"), member("b", "Bob")],
+        );
+
+        model.apply_event(UiEvent::TalkerPreempted {
+            channel_id: "lounge".into(),
+            preempted_user_id: "a".into(),
+            by_user_id: "b".into(),
+        });
+        assert!(model.notifications.is_empty());
+        assert!(model
+            .log
+            .back()
+            .is_some_and(|line| line.contains("Bob took over Alice's talker slot")));
+
+        model.apply_event(UiEvent::TalkerPreempted {
+            channel_id: "lounge".into(),
+            preempted_user_id: "me".into(),
+            by_user_id: "a".into(),
+        });
+        let note = model.notifications.back().expect("notification");
+        assert_eq!(note.kind, NotificationKind::Info);
+        assert_eq!(note.text, "Alice took over your talker slot");
+    }
+
+    #[test]
+    fn loudest_talkers_lead_the_member_list_until_cleared() {
+        let mut model = UiModel::new();
+        let member = |user_id: &str| MemberEntry {
+            user_id: user_id.into(),
+            display_name: user_id.into(),
+            away_message: String::new(),
+            custom_status_emoji: String::new(),
+            muted: false,
+            deafened: false,
+            self_muted: false,
+            self_deafened: false,
+            streaming: false,
+            speaking: false,
+            avatar_url: None,
+            accent_color: None,
+        };
+        model.selected_channel = Some("lounge".into());
+        model.members.insert(
+            "lounge".into(),
+            vec![member("a"), member("b"), member("c"), member("d")],
+        );
+        let order = |model: &UiModel| {
+            model
+                .current_members_by_loudness()
+                .into_iter()
+                .map(|m| m.user_id)
+                .collect::<Vec<_>>()
+        };
+
+        model.apply_event(UiEvent::SetTalkerLoudness {
+            channel_id: "lounge".into(),
+            talkers: vec![("c".into(), 90), ("b".into(), 40)],
+        });
+        assert_eq!(order(&model), ["c", "b", "a", "d"]);
+
+        model.apply_event(UiEvent::SetTalkerLoudness {
+            channel_id: "lounge".into(),
+            talkers: Vec::new(),
+        });
+        assert!(model.talker_loudness.is_empty());
+        assert_eq!(order(&model), ["a", "b", "c", "d"]);
+    }
+
     #[test]
     fn member_telemetry_update_refreshes_open_member_connection_info_windows() {
         let mut model = UiModel::new();
@@ -3695,6 +4874,106 @@ mod tests {
         );
     }
 
+    #[test]
+    fn background_channel_messages_count_as_unread_until_selected() {
+        let mut model = UiModel::new();
+        model.user_id = "local-user".into();
+        model.apply_event(UiEvent::SetChannelName("voice-1".into()));
+        model.apply_event(UiEvent::SetChannelChatSubscribed {
+            channel_id: "text-1".into(),
+            subscribed: true,
+        });
+        assert!(model.chat_subscriptions.contains("text-1"));
+
+        for (id, channel, author) in [
+            ("m1", "text-1", "user-1"),
+            ("m2", "text-1", "local-user"),
+            ("m3", "voice-1", "user-1"),
+            ("m4", "text-1", "user-2"),
+        ] {
+            model.apply_event(UiEvent::MessageReceived(ChatMessage {
+                message_id: id.into(),
+                channel_id: channel.into(),
+                author_id: author.into(),
+                author_name: author.into(),
+                author_name_color: None,
+                author_avatar_url: None,
+                text: "hi".into(),
+                timestamp: 1_710_000_000_000,
+                attachments: vec![],
+                reply_to: None,
+                reactions: vec![],
+                pinned: false,
+                edited: false,
+                system: false,
+            }));
+        }
+        assert_eq!(model.unread_counts.get("text-1"), Some(&2));
+        assert!(!model.unread_counts.contains_key("voice-1"));
+
+        model.apply_event(UiEvent::SetChannelName("text-1".into()));
+        assert!(!model.unread_counts.contains_key("text-1"));
+
+        model.apply_event(UiEvent::SetChannelChatSubscribed {
+            channel_id: "text-1".into(),
+            subscribed: false,
+        });
+        assert!(model.chat_subscriptions.is_empty());
+    }
+
+    #[test]
+    fn unread_counts_follow_own_read_marker_and_track_mentions() {
+        let mut model = UiModel::new();
+        model.user_id = "local-user".into();
+        model.nick = "Me".into();
+        model.apply_event(UiEvent::SetChannelName("voice-1".into()));
+
+        for (id, text) in [("m1", "hi"), ("m2", "hey @me"), ("m3", "anyone?")] {
+            model.apply_event(UiEvent::MessageReceived(ChatMessage {
+                message_id: id.into(),
+                channel_id: "text-1".into(),
+                author_id: "user-1".into(),
+                author_name: "user-1".into(),
+                author_name_color: None,
+                author_avatar_url: None,
+                text: text.into(),
+                timestamp: 1_710_000_000_000,
+                attachments: vec![],
+                reply_to: None,
+                reactions: vec![],
+                pinned: false,
+                edited: false,
+                system: false,
+            }));
+        }
+        assert_eq!(model.unread_counts.get("text-1"), Some(&3));
+        assert_eq!(model.unread_mentions.get("text-1"), Some(&1));
+
+        // Read up to m1 elsewhere: the mention is still unread.
+        model.apply_event(UiEvent::ReadMarkerUpdated {
+            channel_id: "text-1".into(),
+            user_id: "local-user".into(),
+            message_id: "m1".into(),
+        });
+        assert_eq!(model.unread_counts.get("text-1"), Some(&2));
+        assert_eq!(model.unread_mentions.get("text-1"), Some(&1));
+
+        // Someone else's marker doesn't touch our counts.
+        model.apply_event(UiEvent::ReadMarkerUpdated {
+            channel_id: "text-1".into(),
+            user_id: "user-1".into(),
+            message_id: "m3".into(),
+        });
+        assert_eq!(model.unread_counts.get("text-1"), Some(&2));
+
+        model.apply_event(UiEvent::SetReadMarkers {
+            channel_id: "text-1".into(),
+            markers: vec![("local-user".into(), "m3".into())],
+        });
+        assert!(!model.unread_counts.contains_key("text-1"));
+        assert!(!model.unread_mentions.contains_key("text-1"));
+    }
+
     #[test]
     fn resolves_author_name_from_channel_member_then_fallback() {
         let mut model = UiModel::new();
@@ -3732,6 +5011,7 @@ mod tests {
             reactions: vec![],
             pinned: false,
             edited: false,
+            system: false,
         }));
 
         let name = model
@@ -3756,6 +5036,7 @@ mod tests {
             reactions: vec![],
             pinned: false,
             edited: false,
+            system: false,
         }));
 
         let fallback = model
@@ -4058,6 +5339,209 @@ mod tests {
         assert_eq!(members[0].display_name, "New");
     }
 
+    #[test]
+    fn channel_occupancy_counts_members_against_limit() {
+        let mut model = UiModel::new();
+        let mut ch = ChannelEntry {
+            id: "c1".into(),
+            name: "Small".into(),
+            channel_type: ChannelType::Voice,
+            parent_id: None,
+            position: 0,
+            member_count: 0,
+            user_limit: 0,
+            description: String::new(),
+            bitrate_bps: 64_000,
+            opus_profile: 1,
+            max_message_length: 0,
+            slow_mode_secs: 0,
+            announce_presence: false,
+        };
+        assert_eq!(model.channel_occupancy(&ch), None);
+
+        ch.user_limit = 1;
+        assert_eq!(model.channel_occupancy(&ch), Some((0, 1)));
+        model.apply_event(UiEvent::MemberJoined {
+            channel_id: "c1".into(),
+            member: MemberEntry {
+                user_id: "u1".into(),
+                display_name: "One".into(),
+                away_message: String::new(),
+                custom_status_emoji: String::new(),
+                muted: false,
+                deafened: false,
+                self_muted: false,
+                self_deafened: false,
+                streaming: false,
+                speaking: false,
+                avatar_url: None,
+                accent_color: None,
+            },
+        });
+        assert_eq!(model.channel_occupancy(&ch), Some((1, 1)));
+    }
+
+    #[test]
+    fn speaking_indicator_holds_then_fades() {
+        let mut model = UiModel::new();
+        model.settings.speaking_hold_ms = 300;
+        model.apply_event(UiEvent::VoiceActivity {
+            user_id: "u1".into(),
+            speaking: true,
+        });
+        let now = std::time::Instant::now();
+        assert_eq!(model.speaking_intensity("u1", now), 1.0);
+
+        model.apply_event(UiEvent::VoiceActivity {
+            user_id: "u1".into(),
+            speaking: false,
+        });
+        let released = model.speaking_released_at["u1"];
+        let ms = std::time::Duration::from_millis;
+        assert_eq!(model.speaking_intensity("u1", released + ms(300)), 1.0);
+        let mid = model.speaking_intensity("u1", released + ms(300) + SPEAKING_FADE / 2);
+        assert!(mid > 0.0 && mid < 1.0);
+        assert_eq!(
+            model.speaking_intensity("u1", released + ms(300) + SPEAKING_FADE),
+            0.0
+        );
+
+        // A repeated "not speaking" doesn't restart the hold.
+        model.apply_event(UiEvent::VoiceActivity {
+            user_id: "u1".into(),
+            speaking: false,
+        });
+        assert_eq!(model.speaking_released_at["u1"], released);
+        assert_eq!(model.speaking_intensity("u2", released), 0.0);
+    }
+
+    #[test]
+    fn vad_meter_smooths_level_and_holds_peak() {
+        let mut model = UiModel::new();
+        assert_eq!(model.vad_meter(), None);
+
+        let t0 = std::time::Instant::now();
+        model.update_vad_meter(0.0, t0);
+        model.update_vad_meter(1.0, t0 + std::time::Duration::from_millis(200));
+        let (level, peak) = model.vad_meter().unwrap();
+        assert!((level - VAD_METER_SMOOTHING).abs() < 1e-6);
+        assert_eq!(peak, 1.0);
+        assert_eq!(model.vad_level, Some(1.0));
+
+        // Quiet reports keep the peak until the hold runs out.
+        let t1 = t0 + std::time::Duration::from_millis(400);
+        model.update_vad_meter(0.1, t1);
+        assert_eq!(model.vad_meter().unwrap().1, 1.0);
+        model.update_vad_meter(0.1, t1 + VAD_METER_PEAK_HOLD);
+        assert_eq!(model.vad_meter().unwrap().1, 0.1);
+    }
+
+    #[test]
+    fn read_markers_mark_latest_once_and_report_readers() {
+        let mut model = UiModel::new();
+        model.user_id = "me".into();
+        model.selected_channel = Some("c1".into());
+        for id in ["m1", "local-2"] {
+            model.apply_event(UiEvent::MessageReceived(ChatMessage {
+                message_id: id.into(),
+                channel_id: "c1".into(),
+                author_id: "me".into(),
+                author_name: "Me".into(),
+                author_name_color: None,
+                author_avatar_url: None,
+                text: "hi".into(),
+                timestamp: 1_710_000_000_000,
+                attachments: vec![],
+                reply_to: None,
+                reactions: vec![],
+                pinned: false,
+                edited: false,
+                system: false,
+            }));
+        }
+        assert_eq!(model.take_pending_mark_read(), None);
+
+        model.apply_event(UiEvent::SetReadReceiptsEnabled(true));
+        assert_eq!(
+            model.take_pending_mark_read(),
+            Some(("c1".to_string(), "m1".to_string()))
+        );
+        assert_eq!(model.take_pending_mark_read(), None);
+
+        model.members.insert(
+            "c1".into(),
+            vec![MemberEntry {
+                user_id: "u2".into(),
+                display_name: "Bob".into(),
+                away_message: String::new(),
+                custom_status_emoji: String::new(),
+                muted: false,
+                deafened: false,
+                self_muted: false,
+                self_deafened: false,
+                streaming: false,
+                speaking: false,
+                avatar_url: None,
+                accent_color: None,
+            }],
+        );
+        model.apply_event(UiEvent::ReadMarkerUpdated {
+            channel_id: "c1".into(),
+            user_id: "u2".into(),
+            message_id: "m1".into(),
+        });
+        assert_eq!(model.read_by("c1", "m1"), vec!["Bob"]);
+
+        model.apply_event(UiEvent::SetReadReceiptsEnabled(false));
+        assert!(model.read_by("c1", "m1").is_empty());
+    }
+
+    #[test]
+    fn slow_mode_cooldown_starts_locally_and_follows_server_hint() {
+        let mut model = UiModel::new();
+        model.apply_event(UiEvent::SetChannels(vec![ChannelEntry {
+            id: "c1".into(),
+            name: "slow".into(),
+            channel_type: ChannelType::Text,
+            parent_id: None,
+            position: 0,
+            member_count: 0,
+            user_limit: 0,
+            description: String::new(),
+            bitrate_bps: 64_000,
+            opus_profile: 1,
+            max_message_length: 280,
+            slow_mode_secs: 10,
+            announce_presence: false,
+        }]));
+        model.selected_channel = Some("c1".into());
+        let now = std::time::Instant::now();
+        assert_eq!(model.current_max_message_length(), Some(280));
+        assert_eq!(model.chat_cooldown_remaining(now), None);
+
+        model.start_chat_cooldown("c1", now);
+        assert_eq!(
+            model.chat_cooldown_remaining(now),
+            Some(std::time::Duration::from_secs(10))
+        );
+        assert_eq!(
+            model.chat_cooldown_remaining(now + std::time::Duration::from_secs(10)),
+            None
+        );
+
+        // A longer wait reported by the server wins over the local guess.
+        model.apply_event(UiEvent::ChatCooldown {
+            channel_id: "c1".into(),
+            retry_after: std::time::Duration::from_secs(30),
+        });
+        assert!(model
+            .chat_cooldown_remaining(now + std::time::Duration::from_secs(20))
+            .is_some());
+
+        model.start_chat_cooldown("unknown", now);
+        assert!(!model.chat_cooldown_until.contains_key("unknown"));
+    }
+
     #[test]
     fn channel_created_updates_existing_channel_instead_of_dup() {
         let mut model = UiModel::new();
@@ -4072,6 +5556,9 @@ mod tests {
             description: String::new(),
             bitrate_bps: 64_000,
             opus_profile: 1,
+            max_message_length: 0,
+            slow_mode_secs: 0,
+            announce_presence: false,
         }));
         model.apply_event(UiEvent::ChannelCreated(ChannelEntry {
             id: "c1".into(),
@@ -4084,6 +5571,9 @@ mod tests {
             description: String::new(),
             bitrate_bps: 64_000,
             opus_profile: 1,
+            max_message_length: 0,
+            slow_mode_secs: 0,
+            announce_presence: false,
         }));
 
         assert_eq!(model.channels.iter().filter(|c| c.id == "c1").count(), 1);
@@ -4107,6 +5597,9 @@ mod tests {
             description: String::new(),
             bitrate_bps: 64_000,
             opus_profile: 1,
+            max_message_length: 0,
+            slow_mode_secs: 0,
+            announce_presence: false,
         }]));
 
         model.apply_event(UiEvent::ChannelRenamed(ChannelEntry {
@@ -4120,12 +5613,158 @@ mod tests {
             description: String::new(),
             bitrate_bps: 64_000,
             opus_profile: 1,
+            max_message_length: 0,
+            slow_mode_secs: 0,
+            announce_presence: false,
         }));
 
         assert_eq!(model.channels.len(), 1);
         assert_eq!(model.channels[0].name, "Lobby");
     }
 
+    #[test]
+    fn system_messages_never_count_as_mentions() {
+        let mut model = UiModel::new();
+        model.nick = "Ann".into();
+        model.user_id = "me".into();
+        model.apply_event(UiEvent::MessageReceived(ChatMessage {
+            message_id: "m1".into(),
+            channel_id: "c1".into(),
+            author_id: "u2".into(),
+            author_name: "u2".into(),
+            author_name_color: None,
+            author_avatar_url: None,
+            text: "@ann joined the channel".into(),
+            timestamp: 1_710_000_000_000,
+            attachments: vec![],
+            reply_to: None,
+            reactions: vec![],
+            pinned: false,
+            edited: false,
+            system: true,
+        }));
+        assert_eq!(model.messages.get("c1").map(|m| m.len()), Some(1));
+        assert!(model.notifications.is_empty());
+    }
+
+    #[test]
+    fn notifications_keep_mentions_and_evict_info_first() {
+        let mut model = UiModel::new();
+        model.nick = "Ann".into();
+        model.user_id = "me".into();
+        model.apply_event(UiEvent::MessageReceived(ChatMessage {
+            message_id: "m1".into(),
+            channel_id: "c1".into(),
+            author_id: "u2".into(),
+            author_name: "Bob".into(),
+            author_name_color: None,
+            author_avatar_url: None,
+            text: "hey @ann, and not @annabel".into(),
+            timestamp: 1_710_000_000_000,
+            attachments: vec![],
+            reply_to: None,
+            reactions: vec![],
+            pinned: false,
+            edited: false,
+            system: false,
+        }));
+        assert_eq!(model.notifications.len(), 1);
+        assert_eq!(model.notifications[0].kind, NotificationKind::Mention);
+        assert_eq!(model.notifications[0].channel_id.as_deref(), Some("c1"));
+        assert!(!mentions_name("ping @annabel", "Ann"));
+
+        for i in 0..MAX_NOTIFICATIONS + 5 {
+            model.push_notification(NotificationKind::Info, format!("info {i}"), None);
+        }
+        assert_eq!(model.notifications.len(), MAX_NOTIFICATIONS);
+        assert_eq!(model.notifications[0].kind, NotificationKind::Mention);
+
+        // Stale Info expires; the mention stays until dismissed.
+        let later = std::time::Instant::now() + NOTIFICATION_TOAST_DURATION * 2;
+        model.expire_notifications(later);
+        assert_eq!(model.notifications.len(), 1);
+        assert_eq!(model.toast_notifications(later).count(), 0);
+        let id = model.notifications[0].id;
+        model.dismiss_notification(id);
+        assert!(model.notifications.is_empty());
+    }
+
+    #[test]
+    fn join_response_limit_overrides_stale_channel_list() {
+        let mut model = UiModel::new();
+        model.apply_event(UiEvent::SetChannels(vec![ChannelEntry {
+            id: "c1".into(),
+            name: "General".into(),
+            channel_type: ChannelType::Voice,
+            parent_id: None,
+            position: 0,
+            member_count: 0,
+            user_limit: 0,
+            description: String::new(),
+            bitrate_bps: 64_000,
+            opus_profile: 1,
+            max_message_length: 0,
+            slow_mode_secs: 0,
+            announce_presence: false,
+        }]));
+
+        model.apply_event(UiEvent::SetChannelUserLimit {
+            channel_id: "c1".into(),
+            user_limit: 8,
+        });
+        assert_eq!(model.channels[0].user_limit, 8);
+
+        model.apply_event(UiEvent::SetChannelUserLimit {
+            channel_id: "c1".into(),
+            user_limit: 0,
+        });
+        assert_eq!(model.channels[0].user_limit, 0);
+
+        model.apply_event(UiEvent::SetChannelUserLimit {
+            channel_id: "missing".into(),
+            user_limit: 4,
+        });
+        assert_eq!(model.channels.len(), 1);
+    }
+
+    #[test]
+    fn channel_member_refresh_replaces_roster_but_keeps_client_side_state() {
+        let member = |user_id: &str, muted: bool| MemberEntry {
+            user_id: user_id.into(),
+            display_name: user_id.into(),
+            away_message: String::new(),
+            custom_status_emoji: String::new(),
+            muted,
+            deafened: false,
+            self_muted: false,
+            self_deafened: false,
+            streaming: false,
+            speaking: false,
+            avatar_url: None,
+            accent_color: None,
+        };
+        let mut model = UiModel::new();
+        let mut alice = member("alice", false);
+        alice.speaking = true;
+        alice.streaming = true;
+        alice.avatar_url = Some("file:///alice.png".into());
+        model
+            .members
+            .insert("c1".into(), vec![alice, member("ghost", false)]);
+
+        model.apply_event(UiEvent::UpdateChannelMembers {
+            channel_id: "c1".into(),
+            members: vec![member("alice", true), member("bob", false)],
+        });
+
+        let roster = &model.members["c1"];
+        let ids: Vec<&str> = roster.iter().map(|m| m.user_id.as_str()).collect();
+        assert_eq!(ids, ["alice", "bob"]);
+        assert!(roster[0].muted && roster[0].speaking && roster[0].streaming);
+        assert_eq!(roster[0].avatar_url.as_deref(), Some("file:///alice.png"));
+        assert!(!roster[1].speaking && roster[1].avatar_url.is_none());
+    }
+
     #[test]
     fn channel_delete_removes_and_falls_back_selection() {
         let mut model = UiModel::new();
@@ -4141,6 +5780,9 @@ mod tests {
                 description: String::new(),
                 bitrate_bps: 64_000,
                 opus_profile: 1,
+                max_message_length: 0,
+                slow_mode_secs: 0,
+                announce_presence: false,
             },
             ChannelEntry {
                 id: "c1".into(),
@@ -4153,6 +5795,9 @@ mod tests {
                 description: String::new(),
                 bitrate_bps: 64_000,
                 opus_profile: 1,
+                max_message_length: 0,
+                slow_mode_secs: 0,
+                announce_presence: false,
             },
             ChannelEntry {
                 id: "c1-child".into(),
@@ -4165,6 +5810,9 @@ mod tests {
                 description: String::new(),
                 bitrate_bps: 64_000,
                 opus_profile: 1,
+                max_message_length: 0,
+                slow_mode_secs: 0,
+                announce_presence: false,
             },
         ]));
         model.apply_event(UiEvent::SetDefaultChannelId(Some("default".into())));
@@ -4193,6 +5841,9 @@ mod tests {
             description: String::new(),
             bitrate_bps: 64_000,
             opus_profile: 1,
+            max_message_length: 0,
+            slow_mode_secs: 0,
+            announce_presence: false,
         }]));
         model.channel_collapsed.insert("parent".into(), true);
 
@@ -4207,6 +5858,9 @@ mod tests {
             description: String::new(),
             bitrate_bps: 64_000,
             opus_profile: 1,
+            max_message_length: 0,
+            slow_mode_secs: 0,
+            announce_presence: false,
         }));
 
         assert_eq!(
@@ -4235,6 +5889,9 @@ mod tests {
                 description: String::new(),
                 bitrate_bps: 64_000,
                 opus_profile: 1,
+                max_message_length: 0,
+                slow_mode_secs: 0,
+                announce_presence: false,
             },
             ChannelEntry {
                 id: "c2".into(),
@@ -4247,6 +5904,9 @@ mod tests {
                 description: String::new(),
                 bitrate_bps: 64_000,
                 opus_profile: 1,
+                max_message_length: 0,
+                slow_mode_secs: 0,
+                announce_presence: false,
             },
         ]));
 
@@ -4261,6 +5921,9 @@ mod tests {
             description: String::new(),
             bitrate_bps: 64_000,
             opus_profile: 1,
+            max_message_length: 0,
+            slow_mode_secs: 0,
+            announce_presence: false,
         }));
         model.apply_event(UiEvent::ChannelDeleted {
             channel_id: "c2".into(),
@@ -4290,6 +5953,7 @@ mod tests {
             reactions: vec![],
             pinned: false,
             edited: false,
+            system: false,
         };
 
         model.apply_event(UiEvent::MessageReceived(message.clone()));
@@ -4298,6 +5962,183 @@ mod tests {
         assert_eq!(model.messages.get("lounge-1").unwrap().len(), 1);
     }
 
+    #[test]
+    fn reaction_changed_sets_count_and_own_flag() {
+        let mut model = UiModel::new();
+        model.user_id = "local-user".into();
+        model.apply_event(UiEvent::MessageReceived(ChatMessage {
+            message_id: "msg-1".into(),
+            channel_id: "lounge-1".into(),
+            author_id: "remote-user".into(),
+            author_name: "Dresk".into(),
+            author_name_color: None,
+            author_avatar_url: None,
+            text: "hello".into(),
+            timestamp: 1_710_000_000_000,
+            attachments: vec![],
+            reply_to: None,
+            reactions: vec![],
+            pinned: false,
+            edited: false,
+            system: false,
+        }));
+        let change = |user_id: &str, added: bool, count: u32| UiEvent::ReactionChanged {
+            channel_id: "lounge-1".into(),
+            message_id: "msg-1".into(),
+            emoji: "👍".into(),
+            user_id: user_id.into(),
+            added,
+            count,
+        };
+        let reactions = |model: &UiModel| {
+            model.messages["lounge-1"][0]
+                .reactions
+                .iter()
+                .map(|r| (r.emoji.clone(), r.count, r.me))
+                .collect::<Vec<_>>()
+        };
+
+        model.apply_event(change("remote-user", true, 1));
+        assert_eq!(reactions(&model), [("👍".to_string(), 1, false)]);
+        model.apply_event(change("local-user", true, 2));
+        assert_eq!(reactions(&model), [("👍".to_string(), 2, true)]);
+        // A replayed push is harmless: the count is absolute.
+        model.apply_event(change("local-user", true, 2));
+        assert_eq!(reactions(&model), [("👍".to_string(), 2, true)]);
+        model.apply_event(change("remote-user", false, 1));
+        assert_eq!(reactions(&model), [("👍".to_string(), 1, true)]);
+        model.apply_event(change("local-user", false, 0));
+        assert!(reactions(&model).is_empty());
+    }
+
+    #[test]
+    fn history_pages_merge_in_order_without_unread_or_dupes() {
+        let mut model = UiModel::new();
+        model.user_id = "local-user".into();
+        let msg = |id: &str, timestamp: i64| ChatMessage {
+            message_id: id.into(),
+            channel_id: "lounge-1".into(),
+            author_id: "remote-user".into(),
+            author_name: "remote-user".into(),
+            author_name_color: None,
+            author_avatar_url: None,
+            text: id.into(),
+            timestamp,
+            attachments: vec![],
+            reply_to: None,
+            reactions: vec![],
+            pinned: false,
+            edited: false,
+            system: false,
+        };
+        let ids = |model: &UiModel| {
+            model.messages["lounge-1"]
+                .iter()
+                .map(|m| m.message_id.clone())
+                .collect::<Vec<_>>()
+        };
+
+        // A push raced ahead of the join's first page.
+        model.apply_event(UiEvent::MessageReceived(msg("m3", 3)));
+        model.apply_event(UiEvent::HistoryLoaded {
+            channel_id: "lounge-1".into(),
+            messages: vec![msg("m2", 2), msg("m3", 3)],
+            exhausted: false,
+        });
+        assert_eq!(ids(&model), ["m2", "m3"]);
+        // Only the live push counts as unread.
+        assert_eq!(model.unread_counts["lounge-1"], 1);
+
+        model.selected_channel = Some("lounge-1".into());
+        let Some(UiIntent::LoadOlderMessages { before, .. }) =
+            model.take_older_messages_request(640.0)
+        else {
+            panic!("expected a history request");
+        };
+        assert_eq!(before, "m2");
+        // Only one page in flight at a time.
+        assert!(model.take_older_messages_request(640.0).is_none());
+
+        model.apply_event(UiEvent::HistoryLoaded {
+            channel_id: "lounge-1".into(),
+            messages: vec![msg("m1", 1)],
+            exhausted: true,
+        });
+        assert_eq!(ids(&model), ["m1", "m2", "m3"]);
+        let history = model.chat_history["lounge-1"];
+        assert!(!history.loading && history.exhausted);
+        assert_eq!(history.anchor_height, Some(640.0));
+        assert!(model.take_older_messages_request(640.0).is_none());
+    }
+
+    #[test]
+    fn failed_history_page_waits_for_a_retry() {
+        let mut model = UiModel::new();
+        model.selected_channel = Some("lounge-1".into());
+
+        // The join's first page failed: nothing to page from yet, so the
+        // retry asks for the newest page.
+        model.apply_event(UiEvent::HistoryLoadFailed {
+            channel_id: "lounge-1".into(),
+        });
+        assert!(model.take_older_messages_request(640.0).is_none());
+        let Some(UiIntent::LoadOlderMessages { before, .. }) = model.retry_chat_history() else {
+            panic!("expected a retry");
+        };
+        assert_eq!(before, "");
+        assert!(model.retry_chat_history().is_none());
+
+        model.apply_event(UiEvent::HistoryLoaded {
+            channel_id: "lounge-1".into(),
+            messages: vec![ChatMessage {
+                message_id: "m1".into(),
+                channel_id: "lounge-1".into(),
+                author_id: "remote-user".into(),
+                author_name: "remote-user".into(),
+                author_name_color: None,
+                author_avatar_url: None,
+                text: "hi".into(),
+                timestamp: 1,
+                attachments: vec![AttachmentData {
+                    asset: AttachmentAsset::UploadedAssetId("asset-1".into()),
+                    filename: "cat.png".into(),
+                    mime_type: "image/png".into(),
+                    size_bytes: 3,
+                    download_url: String::new(),
+                    thumbnail_url: None,
+                }],
+                reply_to: None,
+                reactions: vec![],
+                pinned: false,
+                edited: false,
+                system: false,
+            }],
+            exhausted: false,
+        });
+        assert!(!model.chat_history["lounge-1"].failed);
+        model.apply_event(UiEvent::AttachmentCached {
+            channel_id: "lounge-1".into(),
+            message_id: "m1".into(),
+            asset_id: "asset-1".into(),
+            download_url: "file:///cache/cat.png".into(),
+        });
+        assert_eq!(
+            model.messages["lounge-1"][0].attachments[0].download_url,
+            "file:///cache/cat.png"
+        );
+
+        // An older page failing stops the scroll-driven requests until retried.
+        assert!(model.take_older_messages_request(640.0).is_some());
+        model.apply_event(UiEvent::HistoryLoadFailed {
+            channel_id: "lounge-1".into(),
+        });
+        assert!(model.take_older_messages_request(640.0).is_none());
+        let Some(UiIntent::LoadOlderMessages { before, .. }) = model.retry_chat_history() else {
+            panic!("expected a retry");
+        };
+        assert_eq!(before, "m1");
+    }
+
     #[test]
     fn reconciles_optimistic_local_echo_with_server_message() {
         let mut model = UiModel::new();
@@ -4317,6 +6158,7 @@ mod tests {
             reactions: vec![],
             pinned: false,
             edited: false,
+            system: false,
         }));
 
         model.apply_event(UiEvent::MessageReceived(ChatMessage {
@@ -4333,6 +6175,7 @@ mod tests {
             reactions: vec![],
             pinned: false,
             edited: false,
+            system: false,
         }));
 
         let messages = model.messages.get("lounge-1").unwrap();
@@ -4359,6 +6202,7 @@ mod tests {
             reactions: vec![],
             pinned: false,
             edited: false,
+            system: false,
         }));
         model.apply_event(UiEvent::MessageReceived(ChatMessage {
             message_id: "msg-2".into(),
@@ -4374,6 +6218,7 @@ mod tests {
             reactions: vec![],
             pinned: false,
             edited: false,
+            system: false,
         }));
 
         assert_eq!(model.messages.get("lounge-1").unwrap().len(), 2);
@@ -4393,6 +6238,9 @@ mod tests {
             description: String::new(),
             bitrate_bps: 64_000,
             opus_profile: 1,
+            max_message_length: 0,
+            slow_mode_secs: 0,
+            announce_presence: false,
         });
 
         model.apply_event(UiEvent::SetChannelName(
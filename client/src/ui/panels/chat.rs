@@ -1,5 +1,6 @@
 //! Chat panel: message display, input bar, typing indicators, Discord-like drag overlay.
 
+use crate::proto::voiceplatform::v1::ServerFeature;
 use crate::ui::model::{
     AttachmentAsset, AttachmentData, ChannelType, ChatMessage, PendingAttachment, UiIntent, UiModel,
 };
@@ -29,6 +30,13 @@ const OVERLAY_FADE_MS: u64 = 400;
 
 /// Height of a single attachment preview card in the composer strip.
 const PREVIEW_CARD_HEIGHT: f32 = 86.0;
+/// Typing indicator, separator and margins below the message list, excluding
+/// the (auto-growing) composer itself.
+const BOTTOM_CHROME_HEIGHT: f32 = 38.0;
+/// Separator and spacing around the input bar.
+const INPUT_BAR_PADDING: f32 = 2.0;
+/// How close to the top of the message list scrolling fetches older history.
+const HISTORY_PREFETCH_MARGIN: f32 = 48.0;
 const QUICK_REACTION_EMOJI: &[&str] = &["👍", "❤️", "😂", "😮", "😢", "🔥", "🎉", "👀"];
 
 pub fn show(ui: &mut egui::Ui, model: &mut UiModel, tx_intent: &Sender<UiIntent>) {
@@ -72,13 +80,38 @@ pub fn show(ui: &mut egui::Ui, model: &mut UiModel, tx_intent: &Sender<UiIntent>
     } else {
         0.0
     };
-    let available = ui.available_height() - 78.0 - preview_height - input_toolbar_height;
+    let input_height = model
+        .chat_composer
+        .desired_height(model.chat_input_options_open);
+    let available = ui.available_height()
+        - BOTTOM_CHROME_HEIGHT
+        - input_height
+        - preview_height
+        - input_toolbar_height;
 
     // Messages area
-    egui::ScrollArea::vertical()
+    let mut output = egui::ScrollArea::vertical()
         .max_height(available.max(100.0))
         .stick_to_bottom(true)
         .show(ui, |ui| {
+            let history_failed = model
+                .selected_channel
+                .as_ref()
+                .and_then(|ch| model.chat_history.get(ch))
+                .is_some_and(|history| history.failed);
+            if history_failed {
+                ui.horizontal(|ui| {
+                    ui.label(
+                        egui::RichText::new("Couldn't load message history.")
+                            .color(theme::text_muted()),
+                    );
+                    if ui.small_button("Retry").clicked() {
+                        if let Some(intent) = model.retry_chat_history() {
+                            let _ = tx_intent.send(intent);
+                        }
+                    }
+                });
+            }
             if let Some(messages) = model.current_messages().cloned() {
                 let mut prev_day: Option<NaiveDate> = None;
 
@@ -105,6 +138,36 @@ pub fn show(ui: &mut egui::Ui, model: &mut UiModel, tx_intent: &Sender<UiIntent>
             }
         });
 
+    // Older rows landed above the view: shift by their height so the
+    // messages the user was reading stay put.
+    if let Some(history) = model
+        .selected_channel
+        .as_ref()
+        .and_then(|ch| model.chat_history.get_mut(ch))
+        .filter(|history| !history.loading)
+    {
+        if let Some(old_height) = history.anchor_height.take() {
+            let grown = output.content_size.y - old_height;
+            if grown > 0.0 {
+                output.state.offset.y += grown;
+                output.state.store(ui.ctx(), output.id);
+                ui.ctx().request_repaint();
+            }
+        }
+    }
+    if output.state.offset.y <= HISTORY_PREFETCH_MARGIN {
+        if let Some(intent) = model.take_older_messages_request(output.content_size.y) {
+            let _ = tx_intent.send(intent);
+        }
+    }
+
+    if let Some((channel_id, message_id)) = model.take_pending_mark_read() {
+        let _ = tx_intent.send(UiIntent::MarkRead {
+            channel_id,
+            message_id,
+        });
+    }
+
     // Typing indicator
     let typing = model.current_typing_users();
     if !typing.is_empty() {
@@ -125,8 +188,12 @@ pub fn show(ui: &mut egui::Ui, model: &mut UiModel, tx_intent: &Sender<UiIntent>
         );
     }
 
-    let lower_input_spacer =
-        (ui.available_height() - 42.0 - preview_height - input_toolbar_height).max(6.0);
+    let lower_input_spacer = (ui.available_height()
+        - INPUT_BAR_PADDING
+        - input_height
+        - preview_height
+        - input_toolbar_height)
+        .max(6.0);
     ui.add_space(lower_input_spacer);
     ui.separator();
 
@@ -140,10 +207,18 @@ pub fn show(ui: &mut egui::Ui, model: &mut UiModel, tx_intent: &Sender<UiIntent>
 
     // Input bar
     ui.horizontal(|ui| {
-        let hint = if !model.pending_attachments.is_empty() {
-            "Add a comment..."
+        let cooldown = model.chat_cooldown_remaining(std::time::Instant::now());
+        let over_limit = model
+            .current_max_message_length()
+            .filter(|&max| model.chat_composer.text().trim().len() > max as usize);
+        let hint = if let Some(wait) = cooldown {
+            ui.ctx()
+                .request_repaint_after(std::time::Duration::from_millis(250));
+            format!("Slow mode: wait {}s", wait.as_secs_f32().ceil() as u64)
+        } else if !model.pending_attachments.is_empty() {
+            "Add a comment...".to_string()
         } else {
-            "Type a message..."
+            "Type a message...".to_string()
         };
 
         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
@@ -165,12 +240,31 @@ pub fn show(ui: &mut egui::Ui, model: &mut UiModel, tx_intent: &Sender<UiIntent>
                 "Show formatting"
             });
 
-            let send_clicked = ui.button("Send").clicked();
+            // While slow mode holds us back the button counts down instead.
+            let send_label = cooldown.map_or_else(
+                || "Send".to_string(),
+                |wait| format!("{}s", wait.as_secs_f32().ceil() as u64),
+            );
+            let send_btn = ui.add_enabled(
+                cooldown.is_none() && over_limit.is_none(),
+                egui::Button::new(send_label),
+            );
+            let send_btn = match (cooldown, over_limit) {
+                (Some(wait), _) => send_btn.on_disabled_hover_text(format!(
+                    "Slow mode is on in this channel: wait {}s",
+                    wait.as_secs_f32().ceil() as u64
+                )),
+                (None, Some(max)) => send_btn.on_disabled_hover_text(format!(
+                    "Message is over this channel's {max}-byte limit"
+                )),
+                (None, None) => send_btn,
+            };
+            let send_clicked = send_btn.clicked();
 
             // Composer fills remaining space to the left of the buttons
             let composer_result = model.chat_composer.ui(
                 ui,
-                hint,
+                &hint,
                 ui.available_width().max(120.0),
                 model.chat_input_options_open,
             );
@@ -191,8 +285,11 @@ pub fn show(ui: &mut egui::Ui, model: &mut UiModel, tx_intent: &Sender<UiIntent>
                 }
             }
 
-            if composer_result.send_requested || send_clicked {
+            if (composer_result.send_requested && cooldown.is_none() && over_limit.is_none())
+                || send_clicked
+            {
                 send_chat_from_input(model, tx_intent);
+                model.chat_composer.request_focus();
             }
         });
     });
@@ -736,6 +833,9 @@ fn send_chat_from_input(model: &mut UiModel, tx_intent: &Sender<UiIntent>) {
         .collect::<Vec<_>>();
 
     let _ = tx_intent.send(UiIntent::SendChat { text, attachments });
+    if let Some(channel_id) = model.selected_channel.clone() {
+        model.start_chat_cooldown(&channel_id, std::time::Instant::now());
+    }
     model.chat_composer.clear();
     model.pending_attachments.clear();
     model.clear_current_draft();
@@ -773,6 +873,11 @@ fn show_message(
     msg: &ChatMessage,
     tx_intent: &Sender<UiIntent>,
 ) {
+    if msg.system {
+        show_system_message(ui, msg);
+        return;
+    }
+
     let row_response = ui
         .horizontal(|ui| {
             if model.settings.chat_show_avatars {
@@ -810,11 +915,19 @@ fn show_message(
                     }
                 });
                 show_message_content(ui, msg, tx_intent);
+                let readers = model.read_by(&msg.channel_id, &msg.message_id);
+                if !readers.is_empty() {
+                    ui.label(
+                        egui::RichText::new(format!("Seen by {}", readers.join(", ")))
+                            .small()
+                            .color(theme::text_muted()),
+                    );
+                }
             });
         })
         .response;
 
-    if row_response.hovered() {
+    if row_response.hovered() && model.server_supports(ServerFeature::Reactions) {
         let picker_pos = egui::pos2(
             row_response.rect.right() - 28.0,
             row_response.rect.top() + 4.0,
@@ -847,6 +960,22 @@ fn show_message(
     }
 }
 
+/// Join/leave announcements: one muted line, no avatar, author or reactions.
+fn show_system_message(ui: &mut egui::Ui, msg: &ChatMessage) {
+    ui.horizontal(|ui| {
+        ui.label(
+            egui::RichText::new(&msg.text)
+                .italics()
+                .color(theme::text_muted()),
+        );
+        ui.label(
+            egui::RichText::new(format_timestamp(msg.timestamp))
+                .small()
+                .color(theme::text_muted()),
+        );
+    });
+}
+
 fn author_name_color(color: Option<u32>) -> egui::Color32 {
     let Some(color) = color else {
         return theme::text_color();
@@ -1151,15 +1280,12 @@ fn trim_url_suffix(url: &str) -> &str {
 }
 
 fn show_notifications(ui: &mut egui::Ui, model: &UiModel) {
-    if model.notifications.is_empty() {
-        return;
-    }
-
-    // Overlay notifications in the top-right area
+    // Overlay recent notifications in the top-right area; older ones stay
+    // in the notification center until dismissed or expired.
     let rect = ui.max_rect();
     let mut y = rect.top() + 8.0;
 
-    for notif in model.notifications.iter().rev().take(3) {
+    for notif in model.toast_notifications(std::time::Instant::now()).take(3) {
         let color = match notif.kind {
             crate::ui::model::NotificationKind::Poke => theme::COLOR_MENTION,
             crate::ui::model::NotificationKind::Mention => theme::COLOR_MENTION,
@@ -1,11 +1,28 @@
 //! Member list panel (right sidebar).
 
-use crate::ui::model::{UiIntent, UiModel};
+use crate::proto::voiceplatform::v1::ServerFeature;
+use crate::ui::model::{ChannelType, UiIntent, UiModel};
 use crate::ui::panels::telemetry;
 use crate::ui::theme;
 use crossbeam_channel::Sender;
 use eframe::egui;
 
+/// Ban lengths offered by the ban dialog, in seconds; 0 is permanent.
+const BAN_DURATIONS: &[(u32, &str)] = &[
+    (0, "Permanent"),
+    (60 * 60, "1 hour"),
+    (24 * 60 * 60, "1 day"),
+    (7 * 24 * 60 * 60, "7 days"),
+    (30 * 24 * 60 * 60, "30 days"),
+];
+
+fn ban_duration_label(secs: u32) -> &'static str {
+    BAN_DURATIONS
+        .iter()
+        .find(|(s, _)| *s == secs)
+        .map_or("Custom", |(_, label)| label)
+}
+
 fn member_name_color(model: &UiModel, member: &crate::ui::model::MemberEntry) -> egui::Color32 {
     let user_id = member.user_id.as_str();
     if let Some(color) = member.accent_color.filter(|color| *color != 0) {
@@ -53,7 +70,7 @@ pub fn show(ui: &mut egui::Ui, model: &mut UiModel, tx_intent: &Sender<UiIntent>
 
     ui.separator();
 
-    let members = model.current_members().to_vec();
+    let members = model.current_members_by_loudness();
     if members.is_empty() {
         ui.label(
             egui::RichText::new("No members")
@@ -70,14 +87,19 @@ pub fn show(ui: &mut egui::Ui, model: &mut UiModel, tx_intent: &Sender<UiIntent>
             .color(theme::text_muted()),
     );
 
+    let now = std::time::Instant::now();
     egui::ScrollArea::vertical().show(ui, |ui| {
         for member in members {
-            let is_speaking = model
-                .speaking_users
-                .get(&member.user_id)
-                .copied()
-                .unwrap_or(false)
-                || member.speaking;
+            let speaking_intensity = if member.speaking {
+                1.0
+            } else {
+                model.speaking_intensity(&member.user_id, now)
+            };
+            // Held or fading: keep animating until the ring is gone.
+            if speaking_intensity > 0.0 && model.speaking_released_at.contains_key(&member.user_id)
+            {
+                ui.ctx().request_repaint();
+            }
             let voice_level = model
                 .voice_levels
                 .get(&member.user_id)
@@ -194,11 +216,14 @@ pub fn show(ui: &mut egui::Ui, model: &mut UiModel, tx_intent: &Sender<UiIntent>
                 );
             }
 
-            if is_speaking {
+            if speaking_intensity > 0.0 {
                 ui.painter().circle_stroke(
                     center,
                     radius + 2.0,
-                    egui::Stroke::new(2.0, theme::COLOR_VOICE_ACTIVE),
+                    egui::Stroke::new(
+                        2.0,
+                        theme::COLOR_VOICE_ACTIVE.gamma_multiply(speaking_intensity),
+                    ),
                 );
             }
 
@@ -263,6 +288,9 @@ pub fn show(ui: &mut egui::Ui, model: &mut UiModel, tx_intent: &Sender<UiIntent>
             if member.streaming {
                 status_parts.push("📺 streaming".into());
             }
+            if model.whisper_targets.contains(&member.user_id) {
+                status_parts.push("🤫 whispering to".into());
+            }
             if !member.away_message.trim().is_empty() {
                 status_parts.push(format!("🌙 Away: {}", member.away_message.trim()));
             }
@@ -283,57 +311,78 @@ pub fn show(ui: &mut egui::Ui, model: &mut UiModel, tx_intent: &Sender<UiIntent>
                     ui.close();
                 }
                 ui.separator();
-                let current_gain = model.user_output_gain(&member.user_id);
-                let mut draft_gain = current_gain;
-                let mut local_muted = model.user_locally_muted(&member.user_id);
-                ui.label("Local audio controls");
-                if ui.checkbox(&mut local_muted, "Mute for me").changed() {
-                    model
-                        .settings
-                        .per_user_audio
-                        .entry(member.user_id.clone())
-                        .or_default()
-                        .muted = local_muted;
-                    model.settings_draft = model.settings.clone();
-                    model.settings_dirty = false;
-                    let _ = tx_intent.send(UiIntent::SetUserLocalMute {
-                        user_id: member.user_id.clone(),
-                        muted: local_muted,
-                    });
-                    let _ =
-                        tx_intent.send(UiIntent::SaveSettings(Box::new(model.settings.clone())));
-                }
-                if ui
-                    .add(
-                        egui::Slider::new(&mut draft_gain, 0.0..=2.0)
-                            .text("Volume")
-                            .show_value(true),
-                    )
-                    .changed()
-                {
-                    model
-                        .settings
-                        .per_user_audio
-                        .entry(member.user_id.clone())
-                        .or_default()
-                        .gain = draft_gain;
-                    model.settings_draft = model.settings.clone();
-                    model.settings_dirty = false;
-                    let _ = tx_intent.send(UiIntent::SetUserOutputGain {
-                        user_id: member.user_id.clone(),
-                        gain: draft_gain,
-                    });
-                    let _ =
-                        tx_intent.send(UiIntent::SaveSettings(Box::new(model.settings.clone())));
+                if model.per_user_audio_available() {
+                    let current_gain = model.user_output_gain(&member.user_id);
+                    let mut draft_gain = current_gain;
+                    let mut local_muted = model.user_locally_muted(&member.user_id);
+                    ui.label("Local audio controls");
+                    if ui.checkbox(&mut local_muted, "Mute for me").changed() {
+                        model
+                            .settings
+                            .per_user_audio
+                            .entry(member.user_id.clone())
+                            .or_default()
+                            .muted = local_muted;
+                        model.settings_draft = model.settings.clone();
+                        model.settings_dirty = false;
+                        let _ = tx_intent.send(UiIntent::SetUserLocalMute {
+                            user_id: member.user_id.clone(),
+                            muted: local_muted,
+                        });
+                        let _ = tx_intent
+                            .send(UiIntent::SaveSettings(Box::new(model.settings.clone())));
+                    }
+                    if ui
+                        .add(
+                            egui::Slider::new(&mut draft_gain, 0.0..=2.0)
+                                .text("Volume")
+                                .show_value(true),
+                        )
+                        .changed()
+                    {
+                        model
+                            .settings
+                            .per_user_audio
+                            .entry(member.user_id.clone())
+                            .or_default()
+                            .gain = draft_gain;
+                        model.settings_draft = model.settings.clone();
+                        model.settings_dirty = false;
+                        let _ = tx_intent.send(UiIntent::SetUserOutputGain {
+                            user_id: member.user_id.clone(),
+                            gain: draft_gain,
+                        });
+                        let _ = tx_intent
+                            .send(UiIntent::SaveSettings(Box::new(model.settings.clone())));
+                    }
+                    ui.separator();
                 }
-                ui.separator();
-                if ui.button("Poke").clicked() {
+                if model.server_supports(ServerFeature::Poke) && ui.button("Poke").clicked() {
                     model.show_poke_dialog = true;
                     model.poke_target_user_id = member.user_id.clone();
                     model.poke_target_display_name = member.display_name.clone();
                     model.poke_message_draft = "Poke".into();
                     ui.close();
                 }
+                let is_self = member.user_id == model.user_id;
+                if model.server_supports(ServerFeature::Whisper) && !is_self {
+                    let mut whispering = model.whisper_targets.contains(&member.user_id);
+                    let full =
+                        !whispering && model.whisper_targets.len() >= vp_voice::MAX_WHISPER_TARGETS;
+                    if ui
+                        .add_enabled(!full, egui::Checkbox::new(&mut whispering, "Whisper to"))
+                        .on_disabled_hover_text("Whisper target limit reached")
+                        .changed()
+                    {
+                        let mut targets = model.whisper_targets.clone();
+                        if whispering {
+                            targets.push(member.user_id.clone());
+                        } else {
+                            targets.retain(|id| id != &member.user_id);
+                        }
+                        let _ = tx_intent.send(UiIntent::SetWhisperTargets { targets });
+                    }
+                }
                 ui.separator();
                 if ui.button("Roles…").clicked() {
                     model.show_permissions_center = true;
@@ -353,8 +402,28 @@ pub fn show(ui: &mut egui::Ui, model: &mut UiModel, tx_intent: &Sender<UiIntent>
                 };
                 ui.add_enabled(false, egui::Button::new(deafen_label))
                     .on_disabled_hover_text(tooltip);
-                ui.add_enabled(false, egui::Button::new("Move…"))
-                    .on_disabled_hover_text("Missing permission: Move Members");
+                let move_targets: Vec<(String, String)> = model
+                    .channels
+                    .iter()
+                    .filter(|ch| {
+                        ch.channel_type == ChannelType::Voice
+                            && model.selected_channel.as_deref() != Some(ch.id.as_str())
+                    })
+                    .map(|ch| (ch.id.clone(), ch.name.clone()))
+                    .collect();
+                ui.add_enabled_ui(!move_targets.is_empty(), |ui| {
+                    ui.menu_button("Move…", |ui| {
+                        for (channel_id, name) in &move_targets {
+                            if ui.button(name).clicked() {
+                                let _ = tx_intent.send(UiIntent::MoveUser {
+                                    user_id: member.user_id.clone(),
+                                    target_channel_id: channel_id.clone(),
+                                });
+                                ui.close();
+                            }
+                        }
+                    });
+                });
                 ui.separator();
                 if ui.button("Get Connection Info").clicked() {
                     model.open_member_connection_info_window(
@@ -364,6 +433,12 @@ pub fn show(ui: &mut egui::Ui, model: &mut UiModel, tx_intent: &Sender<UiIntent>
                     ui.close();
                 }
                 ui.separator();
+                if ui.button("Disconnect").clicked() {
+                    let _ = tx_intent.send(UiIntent::DisconnectUser {
+                        user_id: member.user_id.clone(),
+                    });
+                    ui.close();
+                }
                 if ui.button("Kick").clicked() {
                     let _ = tx_intent.send(UiIntent::KickUser {
                         user_id: member.user_id.clone(),
@@ -371,10 +446,13 @@ pub fn show(ui: &mut egui::Ui, model: &mut UiModel, tx_intent: &Sender<UiIntent>
                     });
                     ui.close();
                 }
-                ui.add_enabled(
-                    false,
-                    egui::Button::new(egui::RichText::new("Ban").color(theme::COLOR_DANGER)),
-                );
+                if ui
+                    .button(egui::RichText::new("Ban").color(theme::COLOR_DANGER))
+                    .clicked()
+                {
+                    model.open_ban_dialog(member.user_id.clone(), member.display_name.clone());
+                    ui.close();
+                }
             });
         }
     });
@@ -401,6 +479,49 @@ pub fn show(ui: &mut egui::Ui, model: &mut UiModel, tx_intent: &Sender<UiIntent>
             });
     }
 
+    if model.show_ban_dialog {
+        egui::Window::new("Ban user")
+            .collapsible(false)
+            .resizable(false)
+            .show(ui.ctx(), |ui| {
+                ui.label(format!(
+                    "Ban {} from the server?",
+                    model.ban_target_display_name
+                ));
+                egui::ComboBox::from_label("Duration")
+                    .selected_text(ban_duration_label(model.ban_duration_secs))
+                    .show_ui(ui, |ui| {
+                        for (secs, _) in BAN_DURATIONS {
+                            ui.selectable_value(
+                                &mut model.ban_duration_secs,
+                                *secs,
+                                ban_duration_label(*secs),
+                            );
+                        }
+                    });
+                ui.add(
+                    egui::TextEdit::singleline(&mut model.ban_reason_draft)
+                        .hint_text("Reason (optional)"),
+                );
+                ui.horizontal(|ui| {
+                    if ui
+                        .button(egui::RichText::new("Ban").color(theme::COLOR_DANGER))
+                        .clicked()
+                    {
+                        let _ = tx_intent.send(UiIntent::BanUser {
+                            user_id: model.ban_target_user_id.clone(),
+                            reason: model.ban_reason_draft.trim().to_string(),
+                            duration: model.ban_duration_secs,
+                        });
+                        model.show_ban_dialog = false;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        model.show_ban_dialog = false;
+                    }
+                });
+            });
+    }
+
     let now = std::time::Instant::now();
     let mut close_window_indices = Vec::new();
     for (index, connection_info) in model.member_connection_info_windows.iter().enumerate() {
@@ -1,5 +1,6 @@
 pub mod chat;
 pub mod members;
+pub mod notifications;
 pub mod permissions_center;
 pub mod profile_edit;
 pub mod profile_popup;
@@ -0,0 +1,98 @@
+//! Notification center: recent mentions, pokes and errors until dismissed.
+
+use crate::ui::model::{NotificationKind, UiIntent, UiModel};
+use crate::ui::theme;
+use crossbeam_channel::Sender;
+use eframe::egui;
+
+pub fn show_notification_center(
+    ctx: &egui::Context,
+    model: &mut UiModel,
+    tx_intent: &Sender<UiIntent>,
+) {
+    if !model.show_notification_center {
+        return;
+    }
+
+    let now = std::time::Instant::now();
+    let mut open = true;
+    let mut dismiss = Vec::new();
+    let mut clear_all = false;
+    egui::Window::new("Notifications")
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(true)
+        .default_width(360.0)
+        .default_height(320.0)
+        .show(ctx, |ui| {
+            if model.notifications.is_empty() {
+                ui.colored_label(theme::text_dim(), "Nothing new.");
+                return;
+            }
+            if ui.button("Dismiss all").clicked() {
+                clear_all = true;
+            }
+            ui.separator();
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for notif in model.notifications.iter().rev() {
+                    let (label, color) = match notif.kind {
+                        NotificationKind::Mention => ("Mention", theme::COLOR_MENTION),
+                        NotificationKind::Poke => ("Poke", theme::COLOR_MENTION),
+                        NotificationKind::Error => ("Error", theme::COLOR_DANGER),
+                        NotificationKind::Info => ("Info", theme::COLOR_ACCENT),
+                    };
+                    ui.horizontal(|ui| {
+                        ui.colored_label(color, label);
+                        ui.colored_label(
+                            theme::text_muted(),
+                            format_age(now.saturating_duration_since(notif.created)),
+                        );
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            if ui.small_button("✕").on_hover_text("Dismiss").clicked() {
+                                dismiss.push(notif.id);
+                            }
+                            if let Some(channel_id) = notif.channel_id.as_deref() {
+                                let name = model
+                                    .channels
+                                    .iter()
+                                    .find(|ch| ch.id == channel_id)
+                                    .map(|ch| ch.name.as_str())
+                                    .unwrap_or("channel");
+                                if ui.small_button(format!("Go to #{name}")).clicked() {
+                                    if model.selected_channel.as_deref() != Some(channel_id) {
+                                        let _ = tx_intent.send(UiIntent::JoinChannel {
+                                            channel_id: channel_id.to_string(),
+                                        });
+                                    }
+                                    dismiss.push(notif.id);
+                                }
+                            }
+                        });
+                    });
+                    ui.label(&notif.text);
+                    ui.separator();
+                }
+            });
+        });
+
+    if clear_all {
+        model.notifications.clear();
+    }
+    for id in dismiss {
+        model.dismiss_notification(id);
+    }
+    if !open {
+        model.show_notification_center = false;
+    }
+}
+
+fn format_age(age: std::time::Duration) -> String {
+    let secs = age.as_secs();
+    if secs < 60 {
+        "just now".to_string()
+    } else if secs < 3600 {
+        format!("{}m ago", secs / 60)
+    } else {
+        format!("{}h ago", secs / 3600)
+    }
+}
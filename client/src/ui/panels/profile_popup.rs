@@ -1,3 +1,4 @@
+use crate::proto::voiceplatform::v1::ServerFeature;
 use crate::ui::markdown;
 use crate::ui::model::{OnlineStatus, UiIntent, UiModel, UserProfileData};
 use crate::ui::theme;
@@ -450,32 +451,40 @@ fn render_content_area(
                     ui.close();
                 }
                 ui.separator();
-                let mut local_muted = model.user_locally_muted(&profile.user_id);
-                if ui.checkbox(&mut local_muted, "Mute for me").changed() {
-                    model
-                        .settings
-                        .per_user_audio
-                        .entry(profile.user_id.clone())
-                        .or_default()
-                        .muted = local_muted;
+                if model.per_user_audio_available() {
+                    let mut local_muted = model.user_locally_muted(&profile.user_id);
+                    if ui.checkbox(&mut local_muted, "Mute for me").changed() {
+                        model
+                            .settings
+                            .per_user_audio
+                            .entry(profile.user_id.clone())
+                            .or_default()
+                            .muted = local_muted;
+                    }
+                    let mut gain = model.user_output_gain(&profile.user_id);
+                    if ui
+                        .add(egui::Slider::new(&mut gain, 0.0..=2.0).text("Volume"))
+                        .changed()
+                    {
+                        model
+                            .settings
+                            .per_user_audio
+                            .entry(profile.user_id.clone())
+                            .or_default()
+                            .gain = gain;
+                        let _ = tx_intent.send(UiIntent::SetUserOutputGain {
+                            user_id: profile.user_id.clone(),
+                            gain,
+                        });
+                    }
+                    ui.separator();
                 }
-                let mut gain = model.user_output_gain(&profile.user_id);
-                if ui
-                    .add(egui::Slider::new(&mut gain, 0.0..=2.0).text("Volume"))
-                    .changed()
-                {
-                    model
-                        .settings
-                        .per_user_audio
-                        .entry(profile.user_id.clone())
-                        .or_default()
-                        .gain = gain;
-                    let _ = tx_intent.send(UiIntent::SetUserOutputGain {
+                if ui.button("Disconnect").clicked() {
+                    let _ = tx_intent.send(UiIntent::DisconnectUser {
                         user_id: profile.user_id.clone(),
-                        gain,
                     });
+                    ui.close();
                 }
-                ui.separator();
                 if ui.button("Kick").clicked() {
                     let _ = tx_intent.send(UiIntent::KickUser {
                         user_id: profile.user_id.clone(),
@@ -483,11 +492,13 @@ fn render_content_area(
                     });
                     ui.close();
                 }
-                ui.add_enabled(
-                    false,
-                    egui::Button::new(egui::RichText::new("Ban").color(theme::COLOR_DANGER)),
-                )
-                .on_disabled_hover_text("Ban is currently disabled from this menu");
+                if ui
+                    .button(egui::RichText::new("Ban").color(theme::COLOR_DANGER))
+                    .clicked()
+                {
+                    model.open_ban_dialog(profile.user_id.clone(), profile.display_name.clone());
+                    ui.close();
+                }
                 ui.separator();
                 ui.menu_button("Grant badge", |ui| {
                     for (badge_id, path) in BADGE_DEFS {
@@ -522,13 +533,14 @@ fn render_content_area(
             });
 
             // Poke button
-            if ui
-                .add(
-                    egui::Button::new(egui::RichText::new("Poke").size(13.0))
-                        .fill(egui::Color32::from_rgb(55, 58, 75))
-                        .corner_radius(6.0),
-                )
-                .clicked()
+            if model.server_supports(ServerFeature::Poke)
+                && ui
+                    .add(
+                        egui::Button::new(egui::RichText::new("Poke").size(13.0))
+                            .fill(egui::Color32::from_rgb(55, 58, 75))
+                            .corner_radius(6.0),
+                    )
+                    .clicked()
             {
                 model.show_poke_dialog = true;
                 model.poke_target_user_id = profile.user_id.clone();
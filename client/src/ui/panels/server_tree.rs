@@ -3,6 +3,7 @@
 use crate::proto::voiceplatform::v1 as pb;
 use crate::ui::model::{ChannelType, UiIntent, UiModel};
 use crate::ui::theme;
+use crate::ui::widgets::badge;
 use crossbeam_channel::Sender;
 use eframe::egui;
 
@@ -106,6 +107,19 @@ fn codec_index_from_profile(opus_profile: i32) -> usize {
     }
 }
 
+/// Server-side cap on chat text; channels can only lower it.
+const MAX_MESSAGE_LENGTH: u32 = 2000;
+const SLOW_MODE_PRESETS_SECS: [u32; 9] = [0, 5, 10, 30, 60, 300, 900, 3600, 21600];
+
+fn slow_mode_label(secs: u32) -> String {
+    match secs {
+        0 => "Off".to_string(),
+        s if s % 3600 == 0 => format!("{}h", s / 3600),
+        s if s % 60 == 0 => format!("{}m", s / 60),
+        s => format!("{s}s"),
+    }
+}
+
 pub fn show_create_channel_dialog(
     ctx: &egui::Context,
     model: &mut UiModel,
@@ -226,11 +240,7 @@ pub fn show_channel_dialogs(
 
                 ui.horizontal(|ui| {
                     ui.label("Quality:");
-                    let range = match model.rename_channel_codec {
-                        0 => 8..=128,
-                        1 => 32..=510,
-                        _ => 8..=510,
-                    };
+                    let range = model.channel_quality_range_kbps(model.rename_channel_codec);
                     let mut quality = model.rename_channel_quality as i32;
                     if ui
                         .add(
@@ -244,11 +254,67 @@ pub fn show_channel_dialogs(
                     }
                 });
 
+                ui.add_space(8.0);
+                ui.label("Chat");
+                ui.horizontal(|ui| {
+                    ui.label("Max message length:");
+                    ui.add(
+                        egui::DragValue::new(&mut model.rename_channel_max_message_length)
+                            .range(0..=MAX_MESSAGE_LENGTH)
+                            .custom_formatter(|v, _| {
+                                if v == 0.0 {
+                                    "Default".to_string()
+                                } else {
+                                    format!("{v:.0} bytes")
+                                }
+                            }),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Slow mode:");
+                    egui::ComboBox::from_id_salt("edit_ch_slow_mode")
+                        .selected_text(slow_mode_label(model.rename_channel_slow_mode_secs))
+                        .width(120.0)
+                        .show_ui(ui, |ui| {
+                            for secs in SLOW_MODE_PRESETS_SECS {
+                                ui.selectable_value(
+                                    &mut model.rename_channel_slow_mode_secs,
+                                    secs,
+                                    slow_mode_label(secs),
+                                );
+                            }
+                        });
+                });
+                ui.checkbox(
+                    &mut model.rename_channel_announce_presence,
+                    "Announce joins and leaves in chat",
+                );
+
                 ui.horizontal(|ui| {
                     if ui.button("Save").clicked() {
                         let new_name = model.rename_channel_name.trim().to_string();
                         if !new_name.is_empty() && new_name.len() <= 64 {
                             if let Some(channel_id) = model.rename_channel_target_id.clone() {
+                                let limits_changed = model
+                                    .channels
+                                    .iter()
+                                    .find(|ch| ch.id == channel_id)
+                                    .is_some_and(|ch| {
+                                        ch.max_message_length
+                                            != model.rename_channel_max_message_length
+                                            || ch.slow_mode_secs
+                                                != model.rename_channel_slow_mode_secs
+                                            || ch.announce_presence
+                                                != model.rename_channel_announce_presence
+                                    });
+                                if limits_changed {
+                                    let _ = tx_intent.send(UiIntent::SetChannelChatLimits {
+                                        channel_id: channel_id.clone(),
+                                        max_message_length: model.rename_channel_max_message_length,
+                                        slow_mode_secs: model.rename_channel_slow_mode_secs,
+                                        announce_presence: model.rename_channel_announce_presence,
+                                    });
+                                }
                                 let _ = tx_intent.send(UiIntent::RenameChannel {
                                     channel_id,
                                     new_name,
@@ -402,7 +468,7 @@ fn show_channel(
         indent + 2.0
     };
 
-    ui.painter().text(
+    let name_rect = ui.painter().text(
         row_rect.left_center() + egui::vec2(text_x, 0.0),
         egui::Align2::LEFT_CENTER,
         &ch.name,
@@ -410,6 +476,43 @@ fn show_channel(
         text_color,
     );
 
+    // Unread count, then a red mention badge after it.
+    let mut badge_x = name_rect.right() + 6.0;
+    let unread = model.unread_counts.get(&ch.id).copied().unwrap_or(0);
+    if unread > 0 {
+        let rect = badge::paint_count_badge(
+            ui.painter(),
+            egui::pos2(badge_x, row_rect.center().y),
+            badge::count_text(unread),
+            theme::text_muted(),
+        );
+        badge_x = rect.right() + 4.0;
+    }
+    let mentions = model.unread_mentions.get(&ch.id).copied().unwrap_or(0);
+    if mentions > 0 {
+        badge::paint_count_badge(
+            ui.painter(),
+            egui::pos2(badge_x, row_rect.center().y),
+            format!("@{}", badge::count_text(mentions)),
+            theme::COLOR_DND,
+        );
+    }
+
+    if let Some((members, limit)) = model.channel_occupancy(ch) {
+        let color = if members >= limit as usize {
+            theme::COLOR_DANGER
+        } else {
+            theme::text_muted()
+        };
+        ui.painter().text(
+            row_rect.right_center() - egui::vec2(6.0, 0.0),
+            egui::Align2::RIGHT_CENTER,
+            format!("{members}/{limit}"),
+            egui::FontId::proportional(11.0),
+            color,
+        );
+    }
+
     if row_response.clicked_by(egui::PointerButton::Primary) {
         let clicked_triangle = has_children
             && row_response
@@ -442,6 +545,26 @@ fn show_channel(
             });
             ui.close();
         }
+        if !is_selected {
+            let subscribed = model.chat_subscriptions.contains(&ch.id);
+            let label = if subscribed {
+                "Stop following chat"
+            } else {
+                "Follow chat"
+            };
+            let button = ui.button(label).on_hover_text(
+                "Receive this channel's messages without joining it; voice stays where it is",
+            );
+            if button.clicked() {
+                let channel_id = ch.id.clone();
+                let _ = tx_intent.send(if subscribed {
+                    UiIntent::UnsubscribeChannel { channel_id }
+                } else {
+                    UiIntent::SubscribeChannel { channel_id }
+                });
+                ui.close();
+            }
+        }
         if ui.button("Channel info…").clicked() {
             model.channel_info_target_id = Some(ch.id.clone());
             model.show_channel_info = true;
@@ -452,6 +575,9 @@ fn show_channel(
             model.rename_channel_name = ch.name.clone();
             model.rename_channel_codec = codec_index_from_profile(ch.opus_profile);
             model.rename_channel_quality = (ch.bitrate_bps / 1000).max(8);
+            model.rename_channel_max_message_length = ch.max_message_length;
+            model.rename_channel_slow_mode_secs = ch.slow_mode_secs;
+            model.rename_channel_announce_presence = ch.announce_presence;
             model.show_rename_channel = true;
             ui.close();
         }
@@ -632,11 +758,7 @@ fn show_create_tab_audio(ui: &mut egui::Ui, model: &mut UiModel) {
     // Quality / Bitrate slider
     ui.horizontal(|ui| {
         ui.label("Quality:");
-        let range = match model.create_channel_codec {
-            0 => 8..=128,  // voice range
-            1 => 32..=510, // music range
-            _ => 8..=510,
-        };
+        let range = model.channel_quality_range_kbps(model.create_channel_codec);
         let mut quality = model.create_channel_quality as i32;
         if ui
             .add(
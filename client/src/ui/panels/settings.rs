@@ -3,15 +3,19 @@
 //! Categories: Application, Capture, Playback, Hotkeys, Chat, Downloads,
 //!             Notifications, Whisper, Screen Share, Video Call, Security
 
-use crate::audio::dsp::agc::AgcPreset;
+use crate::audio::dsp::{agc::AgcPreset, MAX_ECHO_REFERENCE_DELAY_SAMPLES};
+use crate::audio::opus::OpusBandwidth;
+use crate::audio::probe::{DeviceCapabilities, NATIVE_SAMPLE_RATE};
 use crate::settings_io;
 use crate::ui::model::{
-    keybind_to_string, parse_keybind, AppSettings, AudioDeviceInfo, CaptureMode, DspMethod,
-    FecMode, Keybind, SettingsPage, UiEvent, UiIntent, UiModel, VoiceProcessingMode,
+    keybind_to_string, parse_keybind, AppSettings, AudioDeviceId, AudioDeviceInfo, CaptureMode,
+    DspMethod, FecMode, Keybind, NetworkRobustness, SettingsPage, UiEvent, UiIntent, UiModel,
+    VoiceProcessingMode,
 };
 use crate::ui::theme;
 use crossbeam_channel::Sender;
 use eframe::egui;
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 /// Main entry point: renders the full settings window content.
@@ -108,9 +112,10 @@ pub fn show(ui: &mut egui::Ui, model: &mut UiModel, tx_intent: &Sender<UiIntent>
                                 ui,
                                 &mut model.settings_draft,
                                 &model.input_devices,
+                                &model.device_capabilities,
                                 &model.capture_modes,
                                 model.loopback_active,
-                                model.vad_level,
+                                model.vad_meter(),
                                 &model.mic_test_waveform,
                                 model.pipewire_pulse_fallback_suggested,
                                 tx_intent,
@@ -119,6 +124,7 @@ pub fn show(ui: &mut egui::Ui, model: &mut UiModel, tx_intent: &Sender<UiIntent>
                                 ui,
                                 &mut model.settings_draft,
                                 &model.output_devices,
+                                &model.device_capabilities,
                                 &model.playback_modes,
                                 model.connected,
                                 tx_intent,
@@ -394,6 +400,45 @@ fn hint(ui: &mut egui::Ui, text: &str) {
     ui.label(egui::RichText::new(text).small().color(theme::text_muted()));
 }
 
+/// Device count with a button to re-enumerate, for hot-plugged devices.
+fn device_count_hint(ui: &mut egui::Ui, text: &str, tx_intent: &Sender<UiIntent>) {
+    ui.horizontal(|ui: &mut egui::Ui| {
+        hint(ui, text);
+        if ui
+            .small_button("Refresh")
+            .on_hover_text("Look for newly connected audio devices")
+            .clicked()
+        {
+            let _ = tx_intent.send(UiIntent::RefreshAudioDevices);
+        }
+    });
+}
+
+fn capabilities_text(caps: Option<&DeviceCapabilities>) -> String {
+    match caps {
+        Some(caps) => format!("Supports {}", caps.summary()),
+        None => "Supported formats unknown".to_string(),
+    }
+}
+
+/// Formats the selected device reports, with a warning when voice at 48 kHz
+/// has to go through the resampler.
+fn device_capabilities_hint(ui: &mut egui::Ui, caps: Option<&DeviceCapabilities>) {
+    hint(ui, &capabilities_text(caps));
+    let needs_resampling =
+        caps.is_some_and(|caps| !caps.sample_rates.is_empty() && !caps.supports_native_rate());
+    if needs_resampling {
+        ui.label(
+            egui::RichText::new(format!(
+                "This device can't run at {} kHz natively; audio will be resampled.",
+                NATIVE_SAMPLE_RATE / 1000
+            ))
+            .small()
+            .color(theme::COLOR_IDLE),
+        );
+    }
+}
+
 fn keybind_capture_edit(
     ui: &mut egui::Ui,
     id_source: impl std::hash::Hash,
@@ -539,6 +584,20 @@ fn page_application(ui: &mut egui::Ui, model: &mut UiModel) -> bool {
         dirty = true;
     }
 
+    ui.horizontal(|ui: &mut egui::Ui| {
+        ui.label("Speaking indicator hold:");
+        if ui
+            .add(egui::Slider::new(&mut s.speaking_hold_ms, 0..=1000).suffix(" ms"))
+            .changed()
+        {
+            dirty = true;
+        }
+    });
+    hint(
+        ui,
+        "Keeps the speaking ring lit through short pauses. Does not affect when your microphone transmits.",
+    );
+
     section(ui, "Debug");
 
     egui::CollapsingHeader::new("Debug Log")
@@ -605,15 +664,33 @@ fn page_capture(
     ui: &mut egui::Ui,
     s: &mut AppSettings,
     input_devices: &[AudioDeviceInfo],
+    device_capabilities: &HashMap<AudioDeviceId, DeviceCapabilities>,
     capture_modes: &[String],
     loopback_active: bool,
-    vad_level: Option<f32>,
+    vad_meter: Option<(f32, f32)>,
     mic_test_waveform: &[f32],
     pipewire_pulse_fallback_suggested: bool,
     tx_intent: &Sender<UiIntent>,
 ) -> bool {
     let mut dirty = false;
 
+    section(ui, "Low Bandwidth");
+
+    if ui
+        .checkbox(
+            &mut s.low_bandwidth_mode,
+            egui::RichText::new("Low Bandwidth Mode").strong(),
+        )
+        .changed()
+    {
+        dirty = true;
+        let _ = tx_intent.send(UiIntent::SetLowBandwidthMode(s.low_bandwidth_mode));
+    }
+    hint(
+        ui,
+        "For mobile or tethered links: ~12 kbps voice, 20 ms or longer frames, nothing sent during silence and a short playout buffer. Screen share and video are turned off from the next connect.",
+    );
+
     section(ui, "Capture Device");
 
     ui.horizontal(|ui: &mut egui::Ui| {
@@ -652,6 +729,7 @@ fn page_capture(
                             dev.key.clone(),
                             dev.display_label.as_str(),
                         )
+                        .on_hover_text(capabilities_text(device_capabilities.get(&dev.key)))
                         .changed()
                     {
                         dirty = true;
@@ -661,10 +739,12 @@ fn page_capture(
             });
     });
 
-    hint(
+    device_count_hint(
         ui,
         &format!("{} input device(s) detected", input_devices.len()),
+        tx_intent,
     );
+    device_capabilities_hint(ui, device_capabilities.get(&s.capture_device));
 
     ui.horizontal(|ui: &mut egui::Ui| {
         ui.label("Capture Mode:");
@@ -769,9 +849,19 @@ fn page_capture(
                 ui,
                 "Lower = more sensitive. Higher = stricter, ignores background noise.",
             );
+            ui.horizontal(|ui: &mut egui::Ui| {
+                ui.label("Hold Time:");
+                let prev = s.vad_hold_ms;
+                ui.add(egui::Slider::new(&mut s.vad_hold_ms, 100..=1500).suffix(" ms"));
+                if s.vad_hold_ms != prev {
+                    dirty = true;
+                    let _ = tx_intent.send(UiIntent::SetVadHoldMs(s.vad_hold_ms));
+                }
+            });
+            hint(ui, "Keeps transmitting this long after you stop talking so word endings aren't cut off.");
 
             // Live VAD meter with threshold marker
-            if let Some(vad) = vad_level {
+            if let Some((vad, peak)) = vad_meter {
                 ui.add_space(4.0);
                 ui.horizontal(|ui: &mut egui::Ui| {
                     ui.label("Level:");
@@ -797,6 +887,13 @@ fn page_capture(
                         theme::COLOR_IDLE
                     };
                     ui.painter().rect_filled(filled, 3.0, color);
+
+                    // Held peak
+                    ui.painter().vline(
+                        rect.left() + bar_width * peak,
+                        rect.y_range(),
+                        egui::Stroke::new(1.5, theme::text_muted()),
+                    );
                 });
             }
         }
@@ -812,18 +909,23 @@ fn page_capture(
 
     ui.horizontal(|ui: &mut egui::Ui| {
         let pct = (s.input_gain * 100.0).round() as i32;
-        ui.label(format!("Mic Gain: {pct}%"));
+        let db = 20.0 * s.input_gain.max(0.01).log10();
+        ui.label(format!("Mic Gain: {pct}% ({db:+.1} dB)"));
     });
     let prev_gain = s.input_gain;
     ui.add(
-        egui::Slider::new(&mut s.input_gain, 0.0..=2.0)
-            .step_by(0.01)
+        egui::Slider::new(&mut s.input_gain, 0.0..=10.0)
+            .logarithmic(true)
             .show_value(false),
     );
     if (s.input_gain - prev_gain).abs() > 0.001 {
         dirty = true;
         let _ = tx_intent.send(UiIntent::SetInputGain(s.input_gain));
     }
+    hint(
+        ui,
+        "Fixed boost or cut applied before noise suppression and AGC, and heard in the mic test. Use it when AGC alone can't bring a quiet microphone up.",
+    );
 
     section(ui, "Signal Processing");
 
@@ -934,6 +1036,29 @@ fn page_capture(
         ui,
         "Removes speaker bleed-through. Useful without headphones.",
     );
+    if s.echo_cancellation {
+        ui.horizontal(|ui: &mut egui::Ui| {
+            ui.label("Reference Delay:");
+            let prev = s.echo_reference_delay_samples;
+            ui.add(
+                egui::Slider::new(
+                    &mut s.echo_reference_delay_samples,
+                    0..=MAX_ECHO_REFERENCE_DELAY_SAMPLES,
+                )
+                .suffix(" samples"),
+            );
+            if s.echo_reference_delay_samples != prev {
+                dirty = true;
+                let _ = tx_intent.send(UiIntent::SetEchoReferenceDelay(
+                    s.echo_reference_delay_samples,
+                ));
+            }
+        });
+        hint(
+            ui,
+            "48 samples = 1 ms. Raise only if echo remains on high-latency speakers (e.g. Bluetooth).",
+        );
+    }
 
     if ui
         .checkbox(&mut s.typing_attenuation, "Typing Attenuation")
@@ -944,6 +1069,151 @@ fn page_capture(
     }
     hint(ui, "Reduces keyboard click noise while you type.");
 
+    ui.horizontal(|ui: &mut egui::Ui| {
+        ui.label("Network Robustness:");
+        let prev = s.network_robustness;
+        egui::ComboBox::from_id_salt("cap_network_robustness")
+            .selected_text(s.network_robustness.label())
+            .width(220.0)
+            .show_ui(ui, |ui: &mut egui::Ui| {
+                for profile in NetworkRobustness::ALL {
+                    ui.selectable_value(&mut s.network_robustness, profile, profile.label());
+                }
+            });
+        if s.network_robustness != prev {
+            dirty = true;
+            let _ = tx_intent.send(UiIntent::SetNetworkRobustness(s.network_robustness));
+        }
+    });
+    hint(
+        ui,
+        "High adds error correction and lowers bitrate for lossy links; Low saves bandwidth.",
+    );
+
+    ui.horizontal(|ui: &mut egui::Ui| {
+        ui.label("Frame Size:");
+        let prev = s.frame_ms;
+        egui::ComboBox::from_id_salt("cap_frame_ms")
+            .selected_text(format!("{} ms", s.frame_ms))
+            .width(220.0)
+            .show_ui(ui, |ui: &mut egui::Ui| {
+                for ms in [10, 20, 40] {
+                    ui.selectable_value(&mut s.frame_ms, ms, format!("{ms} ms"));
+                }
+            });
+        if s.frame_ms != prev {
+            dirty = true;
+            let _ = tx_intent.send(UiIntent::SetFrameMs(s.frame_ms));
+        }
+    });
+    hint(
+        ui,
+        "Shorter frames lower latency; longer frames save bandwidth and ride out jitter better.",
+    );
+    if s.low_bandwidth_mode && s.frame_ms < crate::audio::LOW_BANDWIDTH_MIN_FRAME_MS {
+        hint(ui, "Low Bandwidth Mode sends 20 ms frames instead.");
+    }
+
+    ui.horizontal(|ui: &mut egui::Ui| {
+        ui.label("Max Audio Bandwidth:");
+        let prev = s.opus_max_bandwidth;
+        egui::ComboBox::from_id_salt("cap_opus_max_bandwidth")
+            .selected_text(s.opus_max_bandwidth.label())
+            .width(220.0)
+            .show_ui(ui, |ui: &mut egui::Ui| {
+                for bandwidth in OpusBandwidth::ALL {
+                    ui.selectable_value(&mut s.opus_max_bandwidth, bandwidth, bandwidth.label());
+                }
+            });
+        if s.opus_max_bandwidth != prev {
+            dirty = true;
+            let _ = tx_intent.send(UiIntent::SetOpusMaxBandwidth(s.opus_max_bandwidth));
+        }
+    });
+    hint(
+        ui,
+        "Caps the audio band the encoder may use. Wideband is plenty for speech and leaves more bits for clarity on slow links. High robustness limits this to super-wideband, Low Bandwidth Mode to wideband.",
+    );
+
+    let mut limit_bitrate = s.voice_bitrate_limit_kbps != 0;
+    if ui
+        .checkbox(&mut limit_bitrate, "Limit Voice Bitrate")
+        .changed()
+    {
+        s.voice_bitrate_limit_kbps = if limit_bitrate { 32 } else { 0 };
+        dirty = true;
+        let _ = tx_intent.send(UiIntent::SetVoiceBitrate(s.voice_bitrate_limit_kbps));
+    }
+    if limit_bitrate {
+        ui.horizontal(|ui: &mut egui::Ui| {
+            ui.label("Target Bitrate:");
+            let prev = s.voice_bitrate_limit_kbps;
+            ui.add(egui::Slider::new(&mut s.voice_bitrate_limit_kbps, 16..=64).suffix(" kbps"));
+            if s.voice_bitrate_limit_kbps != prev {
+                dirty = true;
+                let _ = tx_intent.send(UiIntent::SetVoiceBitrate(s.voice_bitrate_limit_kbps));
+            }
+        });
+    }
+    hint(
+        ui,
+        "Trades voice quality for data usage. Off follows the channel's bitrate. A server limit always applies.",
+    );
+
+    ui.horizontal(|ui: &mut egui::Ui| {
+        ui.label("Encoder Complexity:");
+        let prev = s.opus_complexity;
+        ui.add(egui::Slider::new(&mut s.opus_complexity, 0..=10));
+        if s.opus_complexity != prev {
+            dirty = true;
+            let _ = tx_intent.send(UiIntent::SetOpusComplexity(s.opus_complexity));
+        }
+    });
+    hint(
+        ui,
+        "Lower values save CPU on slow machines at some cost in quality.",
+    );
+
+    if ui
+        .checkbox(&mut s.opus_dtx, "Discontinuous Transmission (DTX)")
+        .changed()
+    {
+        dirty = true;
+        let _ = tx_intent.send(UiIntent::SetDtx(s.opus_dtx));
+    }
+    hint(
+        ui,
+        "Sends almost nothing while you're silent, even with an open mic. Listeners hear comfort noise in the gaps.",
+    );
+
+    ui.horizontal(|ui: &mut egui::Ui| {
+        ui.label("Packet Coalescing:");
+        let prev = s.voice_frames_per_datagram;
+        let frame_ms = s.frame_ms;
+        let label = |frames: u8| match frames {
+            0 | 1 => "Off".to_string(),
+            n => format!("{n} frames per packet (+{} ms)", (n as u32 - 1) * frame_ms),
+        };
+        egui::ComboBox::from_id_salt("cap_voice_frames_per_datagram")
+            .selected_text(label(s.voice_frames_per_datagram))
+            .width(220.0)
+            .show_ui(ui, |ui: &mut egui::Ui| {
+                for frames in 1..=vp_voice::MAX_COALESCED_FRAMES as u8 {
+                    ui.selectable_value(&mut s.voice_frames_per_datagram, frames, label(frames));
+                }
+            });
+        if s.voice_frames_per_datagram != prev {
+            dirty = true;
+            let _ = tx_intent.send(UiIntent::SetVoiceFramesPerDatagram(
+                s.voice_frames_per_datagram,
+            ));
+        }
+    });
+    hint(
+        ui,
+        "Bundles several audio frames per packet to cut overhead on mobile links, at the cost of latency.",
+    );
+
     ui.horizontal(|ui: &mut egui::Ui| {
         ui.label("Forward Error Correction:");
         let prev = s.fec_mode;
@@ -1012,7 +1282,7 @@ fn page_capture(
     );
 
     if loopback_active {
-        if let Some(vad) = vad_level {
+        if let Some((vad, _)) = vad_meter {
             let bar_width = ui.available_width().min(300.0);
             let (rect, _) =
                 ui.allocate_exact_size(egui::vec2(bar_width, 10.0), egui::Sense::hover());
@@ -1090,6 +1360,7 @@ fn page_playback(
     ui: &mut egui::Ui,
     s: &mut AppSettings,
     output_devices: &[AudioDeviceInfo],
+    device_capabilities: &HashMap<AudioDeviceId, DeviceCapabilities>,
     playback_modes: &[String],
     connected: bool,
     tx_intent: &Sender<UiIntent>,
@@ -1134,6 +1405,7 @@ fn page_playback(
                             dev.key.clone(),
                             dev.display_label.as_str(),
                         )
+                        .on_hover_text(capabilities_text(device_capabilities.get(&dev.key)))
                         .changed()
                     {
                         dirty = true;
@@ -1144,10 +1416,12 @@ fn page_playback(
             });
     });
 
-    hint(
+    device_count_hint(
         ui,
         &format!("{} output device(s) detected", output_devices.len()),
+        tx_intent,
     );
+    device_capabilities_hint(ui, device_capabilities.get(&s.playback_device));
 
     ui.horizontal(|ui: &mut egui::Ui| {
         ui.label("Playback Mode:");
@@ -1227,6 +1501,18 @@ fn page_playback(
         "Expands mono voice audio to fill both headphone channels.",
     );
 
+    if ui
+        .checkbox(&mut s.spatial_audio, "Spatial voice positioning")
+        .changed()
+    {
+        dirty = true;
+        let _ = tx_intent.send(UiIntent::SetSpatialAudio(s.spatial_audio));
+    }
+    hint(
+        ui,
+        "Places each speaker at a different spot left to right so overlapping voices are easier to follow.",
+    );
+
     section(ui, "Comfort Noise");
 
     if ui
@@ -5,6 +5,13 @@ use crate::ui::theme;
 use eframe::egui;
 
 pub fn show(ui: &mut egui::Ui, model: &UiModel) {
+    if !model.telemetry_received {
+        ui.label(
+            egui::RichText::new("No data yet — waiting for samples from this connection.")
+                .color(theme::text_muted()),
+        );
+        return;
+    }
     let t = &model.telemetry;
 
     egui::Grid::new("telemetry_grid")
@@ -26,6 +33,10 @@ pub fn show(ui: &mut egui::Ui, model: &UiModel) {
             ui.colored_label(loss_color, format!("{:.1}%", t.loss_rate * 100.0));
             ui.end_row();
 
+            ui.label("Transport Loss:");
+            ui.label(format!("{:.1}%", t.transport_loss_rate * 100.0));
+            ui.end_row();
+
             ui.label("Jitter:");
             ui.label(format!("{} ms", t.jitter_ms));
             ui.end_row();
@@ -54,6 +65,10 @@ pub fn show(ui: &mut egui::Ui, model: &UiModel) {
             ui.label(format!("{}/{}", t.late_packets, t.lost_packets));
             ui.end_row();
 
+            ui.label("Reordered:");
+            ui.label(t.reordered_packets.to_string());
+            ui.end_row();
+
             ui.label("Concealment:");
             ui.label(format!("{} frames", t.concealment_frames));
             ui.end_row();
@@ -70,10 +85,32 @@ pub fn show(ui: &mut egui::Ui, model: &UiModel) {
             ui.label(format!("{} ms", t.playout_delay_ms));
             ui.end_row();
 
+            ui.label("Codec Errors:");
+            let codec_text = format!(
+                "{} enc / {} dec ({} resets)",
+                t.encode_errors, t.decode_errors, t.codec_resets
+            );
+            if t.encode_errors + t.decode_errors > 0 {
+                ui.colored_label(theme::COLOR_IDLE, codec_text);
+            } else {
+                ui.label(codec_text);
+            }
+            ui.end_row();
+
+            ui.label("Robustness:");
+            ui.label(model.settings.network_robustness.short_label());
+            ui.end_row();
+
             ui.label("AGC Gain:");
             ui.label(format!("{:.1} dB", t.agc_gain_db));
             ui.end_row();
 
+            if let Some(erl) = t.echo_return_loss_db {
+                ui.label("Echo Removed:");
+                ui.label(format!("{erl:.1} dB"));
+                ui.end_row();
+            }
+
             ui.label("VAD Probability:");
             let vad_color = if t.vad_probability > 0.5 {
                 theme::COLOR_ONLINE
@@ -82,6 +119,18 @@ pub fn show(ui: &mut egui::Ui, model: &UiModel) {
             };
             ui.colored_label(vad_color, format!("{:.0}%", t.vad_probability * 100.0));
             ui.end_row();
+
+            if t.simulated_impairment {
+                ui.colored_label(theme::COLOR_IDLE, "Simulated Impairment:");
+                ui.colored_label(
+                    theme::COLOR_IDLE,
+                    format!(
+                        "{} dropped / {} delayed per s",
+                        t.simulated_drops, t.simulated_delays
+                    ),
+                );
+                ui.end_row();
+            }
         });
 
     ui.separator();
@@ -89,7 +138,8 @@ pub fn show(ui: &mut egui::Ui, model: &UiModel) {
     // Visual RTT / loss graph (simple bar)
     ui.label(egui::RichText::new("Network Quality").strong().size(13.0));
 
-    let quality = compute_quality_score(t.rtt_ms, t.loss_rate, t.jitter_ms);
+    let loss_rate = t.loss_rate.max(t.transport_loss_rate);
+    let quality = compute_quality_score(t.rtt_ms, loss_rate, t.jitter_ms);
     let (quality_text, quality_color) = match quality {
         80..=100 => ("Excellent", theme::COLOR_ONLINE),
         60..=79 => ("Good", theme::COLOR_ONLINE),
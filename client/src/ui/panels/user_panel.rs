@@ -323,6 +323,41 @@ pub fn show(ui: &mut egui::Ui, model: &mut UiModel, tx_intent: &Sender<UiIntent>
                     "Join a voice channel to use deafen"
                 });
 
+                // Mute all (local only): amber rather than red so it doesn't
+                // read as deafen, which also stops decoding and is server-visible.
+                let mute_all_color = if model.local_mute_all {
+                    theme::COLOR_IDLE
+                } else {
+                    egui::Color32::from_rgb(234, 238, 244)
+                };
+                let mute_all_btn = ui.add_enabled_ui(in_voice_channel, |ui| {
+                    circle_icon_button(
+                        ui,
+                        "🔇",
+                        mute_all_color,
+                        control_bg,
+                        control_hover,
+                        control_active,
+                        btn_size,
+                    )
+                });
+                let mute_all_btn = mute_all_btn.inner;
+                if mute_all_btn.clicked() {
+                    model.local_mute_all = !model.local_mute_all;
+                    let _ = tx_intent.send(if model.local_mute_all {
+                        UiIntent::LocalMuteAll
+                    } else {
+                        UiIntent::LocalUnmuteAll
+                    });
+                }
+                mute_all_btn.on_hover_text(if !in_voice_channel {
+                    "Join a voice channel to mute everyone locally"
+                } else if model.local_mute_all {
+                    "Unmute everyone (local only)"
+                } else {
+                    "Mute everyone for you only — others aren't told and your mic stays live"
+                });
+
                 // Screen share
                 let share_text_color = if model.sharing_active {
                     egui::Color32::from_rgb(140, 196, 255)
@@ -0,0 +1,56 @@
+use eframe::egui;
+
+const FONT_SIZE: f32 = 10.0;
+const HEIGHT: f32 = 14.0;
+const RADIUS: u8 = 7;
+const PADDING_X: f32 = 4.0;
+
+/// Counts past this render as `99+` so the pill stays narrow.
+pub const MAX_SHOWN: u32 = 99;
+
+pub fn count_text(count: u32) -> String {
+    if count > MAX_SHOWN {
+        format!("{MAX_SHOWN}+")
+    } else {
+        count.to_string()
+    }
+}
+
+/// Paint a pill-shaped count badge whose left edge sits at `left_center`.
+/// Returns the painted rect so callers can place the next badge after it.
+pub fn paint_count_badge(
+    painter: &egui::Painter,
+    left_center: egui::Pos2,
+    text: String,
+    fill: egui::Color32,
+) -> egui::Rect {
+    let galley = painter.layout_no_wrap(
+        text,
+        egui::FontId::proportional(FONT_SIZE),
+        egui::Color32::WHITE,
+    );
+    let width = (galley.size().x + PADDING_X * 2.0).max(HEIGHT);
+    let rect = egui::Rect::from_min_size(
+        left_center - egui::vec2(0.0, HEIGHT * 0.5),
+        egui::vec2(width, HEIGHT),
+    );
+    painter.rect_filled(rect, egui::CornerRadius::same(RADIUS), fill);
+    painter.galley(
+        rect.center() - galley.size() * 0.5,
+        galley,
+        egui::Color32::WHITE,
+    );
+    rect
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn large_counts_are_capped() {
+        assert_eq!(count_text(7), "7");
+        assert_eq!(count_text(99), "99");
+        assert_eq!(count_text(250), "99+");
+    }
+}
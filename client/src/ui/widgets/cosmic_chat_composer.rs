@@ -42,6 +42,8 @@ pub struct ChatComposer {
     texture: Option<egui::TextureHandle>,
     texture_size: [usize; 2],
     dirty: bool,
+    /// Take focus on the next frame (e.g. after the Send button stole it).
+    refocus: bool,
 }
 
 impl ChatComposer {
@@ -58,6 +60,7 @@ impl ChatComposer {
             texture: None,
             texture_size: [0, 0],
             dirty: true,
+            refocus: false,
         }
     }
 
@@ -94,6 +97,26 @@ impl ChatComposer {
         self.set_text("");
     }
 
+    pub fn request_focus(&mut self) {
+        self.refocus = true;
+    }
+
+    /// Height the input will take this frame: grows one line at a time with
+    /// the wrapped text, up to a few lines, then scrolls.
+    pub fn desired_height(&self, expanded: bool) -> f32 {
+        let max_h = if expanded {
+            MAX_HEIGHT_EXPANDED
+        } else {
+            MAX_HEIGHT
+        };
+        let lines = self.editor.with_buffer(|buffer| {
+            // Layout runs include soft wraps but lag one frame behind edits;
+            // hard newlines are always known.
+            buffer.layout_runs().count().max(buffer.lines.len())
+        });
+        (lines as f32 * LINE_HEIGHT + PADDING_Y * 2.0).clamp(MIN_HEIGHT, max_h)
+    }
+
     fn select_all(&mut self) {
         let end_cursor = self.editor.with_buffer(|buffer| {
             let last_line = buffer.lines.len().saturating_sub(1);
@@ -148,11 +171,7 @@ impl ChatComposer {
         let mut result = ChatComposerUiResult::default();
 
         let desired_width = desired_width.max(120.0);
-        let max_h = if expanded { MAX_HEIGHT_EXPANDED } else { MAX_HEIGHT };
-        let content_height = self
-            .editor
-            .with_buffer(|buffer| (buffer.lines.len() as f32) * LINE_HEIGHT + (PADDING_Y * 2.0));
-        let height = content_height.clamp(MIN_HEIGHT, max_h);
+        let height = self.desired_height(expanded);
 
         let (rect, response) = ui.allocate_exact_size(
             egui::vec2(desired_width, height),
@@ -161,7 +180,7 @@ impl ChatComposer {
         let frame_rect = rect;
         ui.painter().rect_filled(frame_rect, 8.0, theme::bg_input());
 
-        if response.clicked() {
+        if response.clicked() || std::mem::take(&mut self.refocus) {
             response.request_focus();
         }
         response.context_menu(|ui| {
@@ -191,6 +210,20 @@ impl ChatComposer {
 
         let has_focus = response.has_focus();
         result.has_focus = has_focus;
+        if has_focus {
+            // Keep arrow keys for cursor movement instead of letting egui
+            // move focus to a neighbouring widget mid-sentence.
+            ui.memory_mut(|mem| {
+                mem.set_focus_lock_filter(
+                    response.id,
+                    egui::EventFilter {
+                        horizontal_arrows: true,
+                        vertical_arrows: true,
+                        ..Default::default()
+                    },
+                )
+            });
+        }
 
         let content_rect = egui::Rect::from_min_max(
             egui::pos2(frame_rect.left() + PADDING_X, frame_rect.top() + PADDING_Y),
@@ -226,6 +259,7 @@ impl ChatComposer {
                     egui::Event::Key {
                         key,
                         pressed: true,
+                        repeat,
                         modifiers,
                         ..
                     } => {
@@ -271,7 +305,9 @@ impl ChatComposer {
                                 if shift {
                                     Some(Action::Enter)
                                 } else {
-                                    result.send_requested = true;
+                                    // A held Enter must not send the next
+                                    // message the moment it is typed.
+                                    result.send_requested |= !repeat;
                                     None
                                 }
                             }
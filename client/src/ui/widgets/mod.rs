@@ -3,9 +3,9 @@
 //! Placeholder module for future widgets:
 //! - markdown.rs: Markdown text rendering
 //! - avatar.rs: User avatar with status ring
-//! - badge.rs: Badge display
 //! - emoji.rs: Emoji picker
 //! - file_preview.rs: File/image previews
 //! - toast.rs: Toast notifications
 
+pub mod badge;
 pub mod cosmic_chat_composer;
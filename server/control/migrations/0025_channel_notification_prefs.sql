@@ -0,0 +1,14 @@
+-- Per-user channel notification preferences.
+-- 'subscribed' receives chat pushes without joining; 'muted' suppresses them while joined.
+-- Absence of a row means the default (push only for joined channels).
+CREATE TABLE IF NOT EXISTS channel_notification_prefs (
+  server_id   UUID NOT NULL,
+  user_id     UUID NOT NULL,
+  channel_id  UUID NOT NULL REFERENCES channels(id) ON DELETE CASCADE,
+  pref        TEXT NOT NULL CHECK (pref IN ('subscribed', 'muted')),
+  updated_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+  PRIMARY KEY (user_id, channel_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_channel_notification_prefs_server
+    ON channel_notification_prefs (server_id);
@@ -0,0 +1,7 @@
+-- Opt-in ephemeral channels: removed by the gateway's idle sweeper once empty
+-- for the configured period. For these rows updated_at is also bumped on every
+-- leave, so it tracks "last time someone was here".
+ALTER TABLE channels ADD COLUMN IF NOT EXISTS ephemeral BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE INDEX IF NOT EXISTS idx_channels_ephemeral_updated
+    ON channels (server_id, updated_at) WHERE ephemeral;
@@ -0,0 +1,6 @@
+-- Record who created each channel so per-user creation limits can be enforced.
+-- Existing rows stay NULL and never count against anyone.
+ALTER TABLE channels ADD COLUMN IF NOT EXISTS created_by UUID;
+
+CREATE INDEX IF NOT EXISTS idx_channels_server_created_by
+    ON channels (server_id, created_by);
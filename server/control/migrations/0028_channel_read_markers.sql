@@ -0,0 +1,15 @@
+-- Per-user read position in a channel (read receipts; opt-in per server).
+-- message_at mirrors chat_messages.created_at so markers only move forward
+-- without a join on every update.
+CREATE TABLE IF NOT EXISTS channel_read_markers (
+  server_id   UUID NOT NULL,
+  user_id     UUID NOT NULL,
+  channel_id  UUID NOT NULL REFERENCES channels(id) ON DELETE CASCADE,
+  message_id  UUID NOT NULL,
+  message_at  TIMESTAMPTZ NOT NULL,
+  updated_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+  PRIMARY KEY (user_id, channel_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_channel_read_markers_channel
+    ON channel_read_markers (channel_id);
@@ -0,0 +1,8 @@
+-- Per-channel chat policy. max_message_length NULL means the server default;
+-- slow_mode_secs is the minimum gap between one user's messages (0 = off).
+ALTER TABLE channels ADD COLUMN IF NOT EXISTS max_message_length INT;
+ALTER TABLE channels ADD COLUMN IF NOT EXISTS slow_mode_secs INT NOT NULL DEFAULT 0;
+
+-- Slow mode looks up a user's latest message in a channel on every send.
+CREATE INDEX IF NOT EXISTS idx_chat_messages_channel_author_time
+    ON chat_messages (channel_id, author_user_id, created_at DESC);
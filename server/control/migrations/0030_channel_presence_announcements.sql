@@ -0,0 +1,7 @@
+-- Opt-in per channel: post a system chat message when someone joins or leaves.
+ALTER TABLE channels ADD COLUMN IF NOT EXISTS announce_presence BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- 'system' messages are written by the server about author_user_id rather
+-- than by them, and never count toward slow mode.
+ALTER TABLE chat_messages ADD COLUMN IF NOT EXISTS kind TEXT NOT NULL DEFAULT 'user'
+    CHECK (kind IN ('user', 'system'));
@@ -0,0 +1,4 @@
+-- Set when the author (or a moderator) edits a message. Deleted messages keep
+-- their row so audit and read markers still resolve, but are never served.
+ALTER TABLE chat_messages ADD COLUMN IF NOT EXISTS edited_at TIMESTAMPTZ;
+ALTER TABLE chat_messages ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
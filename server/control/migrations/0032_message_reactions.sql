@@ -0,0 +1,12 @@
+-- One row per (message, user, emoji); the primary key is what stops a user
+-- adding the same emoji twice.
+CREATE TABLE IF NOT EXISTS message_reactions (
+  message_id UUID NOT NULL REFERENCES chat_messages(id) ON DELETE CASCADE,
+  user_id    UUID NOT NULL,
+  emoji      TEXT NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  PRIMARY KEY (message_id, user_id, emoji)
+);
+
+CREATE INDEX IF NOT EXISTS idx_message_reactions_message_emoji
+  ON message_reactions (message_id, emoji);
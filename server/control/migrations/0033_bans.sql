@@ -0,0 +1,11 @@
+-- Server-wide bans. Re-banning replaces the row; expires_at NULL is
+-- permanent, and expired rows are simply ignored by the join check.
+CREATE TABLE IF NOT EXISTS bans (
+  server_id     UUID NOT NULL,
+  user_id       UUID NOT NULL,
+  actor_user_id UUID NOT NULL,
+  reason        TEXT NOT NULL DEFAULT '',
+  created_at    TIMESTAMPTZ NOT NULL DEFAULT now(),
+  expires_at    TIMESTAMPTZ,
+  PRIMARY KEY (server_id, user_id)
+);
@@ -0,0 +1,9 @@
+-- Users signing in with an OIDC id token, one row per (issuer, subject).
+CREATE TABLE IF NOT EXISTS auth_oidc_identities (
+  issuer       TEXT NOT NULL,
+  subject      TEXT NOT NULL,
+  user_id      UUID NOT NULL REFERENCES auth_users(user_id) ON DELETE CASCADE,
+  created_at   TIMESTAMPTZ NOT NULL DEFAULT now(),
+  last_seen    TIMESTAMPTZ NOT NULL DEFAULT now(),
+  PRIMARY KEY (issuer, subject)
+);
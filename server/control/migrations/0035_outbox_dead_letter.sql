@@ -0,0 +1,12 @@
+-- Failed delivery attempts per outbox record. Once a record has failed too
+-- often the dispatcher sets failed_at and it is never claimed again;
+-- last_error keeps the reason for whoever looks at it.
+ALTER TABLE outbox_events
+  ADD COLUMN IF NOT EXISTS attempts INT NOT NULL DEFAULT 0,
+  ADD COLUMN IF NOT EXISTS last_error TEXT NULL,
+  ADD COLUMN IF NOT EXISTS failed_at TIMESTAMPTZ NULL;
+
+DROP INDEX IF EXISTS idx_outbox_unpublished_claimable;
+CREATE INDEX IF NOT EXISTS idx_outbox_unpublished_claimable
+  ON outbox_events (server_id, created_at)
+  WHERE published_at IS NULL AND failed_at IS NULL;
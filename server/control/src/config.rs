@@ -1,5 +1,13 @@
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct ControlConfig {
     pub max_members_default: Option<i32>,
     pub max_talkers_default: Option<i32>,
+    /// Total channels a server may hold; `None` = unlimited.
+    pub max_channels_per_server: Option<u32>,
+    /// Channels a single non-admin user may have created; `None` = unlimited.
+    pub max_channels_per_user: Option<u32>,
+    /// Reject a channel name already used by a sibling (case-insensitive).
+    pub unique_channel_names: bool,
+    /// Record per-user read markers and share them with channel members.
+    pub read_receipts: bool,
 }
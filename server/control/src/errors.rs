@@ -2,6 +2,13 @@ use thiserror::Error;
 
 pub type ControlResult<T> = Result<T, ControlError>;
 
+// Messages for the errors clients react to. The gateway maps each to a
+// structured reason, so keep producers and that mapping on these constants.
+pub const CHANNEL_FULL: &str = "channel full";
+pub const SLOW_MODE: &str = "slow mode";
+pub const USER_OFFLINE: &str = "user offline";
+pub const POKE_THROTTLED: &str = "poke";
+
 #[derive(Error, Debug)]
 pub enum ControlError {
     #[error("not found: {0}")]
@@ -22,9 +29,43 @@ pub enum ControlError {
     #[error("failed precondition: {0}")]
     FailedPrecondition(&'static str),
 
+    /// Retryable once `retry_after_secs` have passed (e.g. channel slow mode).
+    #[error("rate limited: {reason}, retry in {retry_after_secs}s")]
+    RateLimited {
+        reason: &'static str,
+        retry_after_secs: u32,
+    },
+
     #[error("db error")]
     Db(#[from] sqlx::Error),
     
     #[error("internal error: {0}")]
     Anyhow(#[from] anyhow::Error),
 }
+
+impl ControlError {
+    /// True when the failure came from losing the database (pool exhausted,
+    /// socket dropped, TLS reset) rather than from the request itself, so the
+    /// caller can safely retry once connectivity returns.
+    pub fn is_unavailable(&self) -> bool {
+        match self {
+            ControlError::Db(e) => is_connectivity_error(e),
+            ControlError::Anyhow(e) => e
+                .chain()
+                .filter_map(|cause| cause.downcast_ref::<sqlx::Error>())
+                .any(is_connectivity_error),
+            _ => false,
+        }
+    }
+}
+
+pub fn is_connectivity_error(err: &sqlx::Error) -> bool {
+    matches!(
+        err,
+        sqlx::Error::Io(_)
+            | sqlx::Error::Tls(_)
+            | sqlx::Error::PoolTimedOut
+            | sqlx::Error::PoolClosed
+            | sqlx::Error::WorkerCrashed
+    )
+}
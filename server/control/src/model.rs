@@ -20,6 +20,16 @@ pub struct Channel {
     pub description: String,
     pub bitrate_bps: i32,
     pub opus_profile: i32,
+    /// Deleted by the idle sweeper once it has been empty long enough.
+    pub ephemeral: bool,
+    /// `None` for channels created before creators were recorded.
+    pub created_by: Option<UserId>,
+    /// Chat text limit in bytes; `None` uses the server default.
+    pub max_message_length: Option<i32>,
+    /// Minimum seconds between one user's messages; 0 disables slow mode.
+    pub slow_mode_secs: i32,
+    /// Post a system chat message when someone joins or leaves.
+    pub announce_presence: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -36,6 +46,9 @@ pub struct ChannelListItem {
     pub description: String,
     pub bitrate_bps: i32,
     pub opus_profile: i32,
+    pub max_message_length: Option<i32>,
+    pub slow_mode_secs: i32,
+    pub announce_presence: bool,
 }
 
 /// Create channel input
@@ -49,6 +62,7 @@ pub struct ChannelCreate {
     pub description: String,
     pub bitrate_bps: i32,
     pub opus_profile: i32,
+    pub ephemeral: bool,
 }
 
 /// Join channel input
@@ -71,6 +85,34 @@ pub struct Member {
     pub custom_status_emoji: String,
 }
 
+/// Keyset position in a channel's member list, which is ordered by
+/// `(joined_at, user_id)`. Clients only ever see the encoded form.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemberCursor {
+    pub joined_at: DateTime<Utc>,
+    pub user_id: UserId,
+}
+
+impl MemberCursor {
+    /// Cursor for the page that starts right after `member`.
+    pub fn after(member: &Member) -> Self {
+        Self {
+            joined_at: member.joined_at,
+            user_id: member.user_id,
+        }
+    }
+    pub fn encode(&self) -> String {
+        format!("{}:{}", self.joined_at.timestamp_micros(), self.user_id.0)
+    }
+    pub fn parse(s: &str) -> Option<Self> {
+        let (micros, user_id) = s.split_once(':')?;
+        Some(Self {
+            joined_at: DateTime::from_timestamp_micros(micros.parse().ok()?)?,
+            user_id: UserId(uuid::Uuid::parse_str(user_id).ok()?),
+        })
+    }
+}
+
 /// Chat message (NO Default; uses author_user_id + attachments + created_at)
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ChatMessage {
@@ -80,7 +122,34 @@ pub struct ChatMessage {
     pub author_user_id: UserId,
     pub text: String,
     pub attachments: Json,
+    pub kind: ChatMessageKind,
     pub created_at: DateTime<Utc>,
+    pub edited_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChatMessageKind {
+    /// Written by `author_user_id`.
+    User,
+    /// Written by the server about `author_user_id` (join/leave announcements).
+    System,
+}
+
+impl ChatMessageKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChatMessageKind::User => "user",
+            ChatMessageKind::System => "system",
+        }
+    }
+    pub fn from_str(s: &str) -> Option<Self> {
+        Some(match s {
+            "user" => ChatMessageKind::User,
+            "system" => ChatMessageKind::System,
+            _ => return None,
+        })
+    }
 }
 
 /// Send message input
@@ -92,6 +161,30 @@ pub struct SendMessage {
     pub attachments: Option<Json>,
 }
 
+/// Edit message input
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EditMessage {
+    pub channel_id: ChannelId,
+    pub message_id: MessageId,
+    pub text: String,
+}
+
+/// Aggregated reactions for one emoji on a message, from the viewer's side.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReactionSummary {
+    pub emoji: String,
+    pub count: i64,
+    /// Whether the viewing user is one of the reactors.
+    pub me: bool,
+}
+
+/// One message of channel history with its current reactions.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChatHistoryEntry {
+    pub message: ChatMessage,
+    pub reactions: Vec<ReactionSummary>,
+}
+
 /// Canonical attachment row loaded from storage.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Attachment {
@@ -122,6 +215,7 @@ pub struct OutboxEventRow {
     pub server_id: ServerId,
     pub topic: String,
     pub payload_json: Json,
+    pub created_at: DateTime<Utc>,
 }
 
 /// Audit entry (insert-only)
@@ -256,6 +350,61 @@ pub struct AssetUploadSession {
     pub expires_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// Per-user push preference for a channel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChannelNotificationPref {
+    /// Chat pushes only while joined (no row stored).
+    Default,
+    /// Chat pushes even when not joined.
+    Subscribed,
+    /// No chat pushes, even while joined.
+    Muted,
+}
+
+impl ChannelNotificationPref {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChannelNotificationPref::Default => "default",
+            ChannelNotificationPref::Subscribed => "subscribed",
+            ChannelNotificationPref::Muted => "muted",
+        }
+    }
+    pub fn from_str(s: &str) -> Option<Self> {
+        Some(match s {
+            "default" => ChannelNotificationPref::Default,
+            "subscribed" => ChannelNotificationPref::Subscribed,
+            "muted" => ChannelNotificationPref::Muted,
+            _ => return None,
+        })
+    }
+}
+
+/// A server ban that is permanent (`expires_at` None) or still running.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ActiveBan {
+    pub user_id: UserId,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChannelNotificationPrefRecord {
+    pub user_id: UserId,
+    pub channel_id: ChannelId,
+    pub pref: ChannelNotificationPref,
+}
+
+/// How far a user has read in a channel. Only stored when the server has
+/// read receipts enabled.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReadMarker {
+    pub user_id: UserId,
+    pub channel_id: ChannelId,
+    pub message_id: MessageId,
+    /// `created_at` of `message_id`; markers only advance along it.
+    pub message_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
 /// Permission check request (repo decides allow/deny)
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PermissionRequest {
@@ -15,3 +15,60 @@ pub struct OutboxRecord {
 pub trait OutboxPublisher: Send + Sync {
     async fn publish(&self, rec: OutboxRecord) -> anyhow::Result<()>;
 }
+
+/// Every topic [`crate::service::ControlService`] writes to `outbox_events`.
+/// The gateway must translate each of these into a push; its dispatcher tests
+/// walk this list so a new topic without a handler fails CI instead of being
+/// silently dropped.
+pub const OUTBOX_TOPICS: &[&str] = &[
+    "channel.created",
+    "channel.renamed",
+    "channel.deleted",
+    "channel.state_refresh",
+    "presence.member_joined",
+    "presence.member_left",
+    "presence.voice_state_changed",
+    "presence.user_online_status_changed",
+    "chat.message_posted",
+    "chat.message_edited",
+    "chat.message_deleted",
+    "chat.reaction_changed",
+    "moderation.user_muted",
+    "moderation.user_deafened",
+    "moderation.user_kicked",
+    "moderation.user_banned",
+    "moderation.user_moved",
+    "perm.role.upserted",
+    "perm.role.deleted",
+    "perm.role.order_changed",
+    "perm.role.caps_changed",
+    "perm.user.roles_changed",
+    "perm.channel.overrides_changed",
+    "perm.audit.appended",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::OUTBOX_TOPICS;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn outbox_topics_match_what_the_service_emits() {
+        const MARKER: &str = "topic: \"";
+        let src = include_str!("service.rs");
+        let emitted: BTreeSet<&str> = src
+            .match_indices(MARKER)
+            .filter_map(|(at, _)| {
+                let rest = &src[at + MARKER.len()..];
+                rest.find('"').map(|end| &rest[..end])
+            })
+            .collect();
+        let listed: BTreeSet<&str> = OUTBOX_TOPICS.iter().copied().collect();
+
+        assert!(!emitted.is_empty(), "no topics found in service.rs");
+        assert_eq!(
+            emitted, listed,
+            "OUTBOX_TOPICS is out of sync with the topics service.rs emits"
+        );
+    }
+}
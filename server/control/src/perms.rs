@@ -81,3 +81,246 @@ pub enum PermissionDecision {
     Allow,
     Deny,
 }
+
+/// A `role_caps` row for the requested capability on a role the requester
+/// holds (or on @everyone).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RoleCapEntry {
+    pub is_everyone: bool,
+    pub position: i32,
+    pub allowed: bool,
+}
+
+/// Resolve a non-admin permission check from the rows that apply to the
+/// requester. Precedence, highest first:
+///
+/// 1. channel overwrites (@everyone, the requester's roles, the requester):
+///    any deny wins, otherwise any grant wins;
+/// 2. the requester's highest-positioned role with an entry (deny wins ties);
+/// 3. the @everyone entry;
+/// 4. deny.
+///
+/// A deny therefore always beats a grant at the same or a lower level.
+pub fn resolve_decision(role_caps: &[RoleCapEntry], channel_overwrites: &[Effect]) -> Decision {
+    if channel_overwrites.contains(&Effect::Deny) {
+        return Decision::Deny;
+    }
+    if channel_overwrites.contains(&Effect::Grant) {
+        return Decision::Allow;
+    }
+    let top_position = role_caps
+        .iter()
+        .filter(|e| !e.is_everyone)
+        .map(|e| e.position)
+        .max();
+    let deciding: Vec<&RoleCapEntry> = match top_position {
+        Some(pos) => role_caps
+            .iter()
+            .filter(|e| !e.is_everyone && e.position == pos)
+            .collect(),
+        None => role_caps.iter().filter(|e| e.is_everyone).collect(),
+    };
+    if !deciding.is_empty() && deciding.iter().all(|e| e.allowed) {
+        Decision::Allow
+    } else {
+        Decision::Deny
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// xorshift64*: deterministic generator so failures reproduce.
+    struct Rng(u64);
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 >> 12;
+            self.0 ^= self.0 << 25;
+            self.0 ^= self.0 >> 27;
+            self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+        }
+        fn below(&mut self, n: u64) -> u64 {
+            self.next() % n
+        }
+        fn effect(&mut self) -> Option<Effect> {
+            match self.below(3) {
+                0 => None,
+                1 => Some(Effect::Grant),
+                _ => Some(Effect::Deny),
+            }
+        }
+    }
+
+    struct Role {
+        position: i32,
+        held: bool,
+        cap: Option<Effect>,
+        channel_overwrite: Option<Effect>,
+    }
+
+    /// A random server: @everyone, a few roles (positions collide on
+    /// purpose), and optional channel overwrites at every layer.
+    struct Config {
+        everyone_cap: Option<Effect>,
+        everyone_overwrite: Option<Effect>,
+        user_overwrite: Option<Effect>,
+        roles: Vec<Role>,
+    }
+
+    impl Config {
+        fn random(rng: &mut Rng) -> Self {
+            let roles = (0..rng.below(5))
+                .map(|_| Role {
+                    position: rng.below(4) as i32 + 1,
+                    held: rng.below(3) != 0,
+                    cap: rng.effect(),
+                    channel_overwrite: rng.effect(),
+                })
+                .collect();
+            Self {
+                everyone_cap: rng.effect(),
+                everyone_overwrite: rng.effect(),
+                user_overwrite: rng.effect(),
+                roles,
+            }
+        }
+
+        /// The rows `decide_permission` selects: only roles the user holds
+        /// contribute, plus @everyone.
+        fn rows(&self) -> (Vec<RoleCapEntry>, Vec<Effect>) {
+            let mut caps = Vec::new();
+            if let Some(e) = self.everyone_cap {
+                caps.push(RoleCapEntry {
+                    is_everyone: true,
+                    position: 0,
+                    allowed: e == Effect::Grant,
+                });
+            }
+            let mut overwrites: Vec<Effect> = self.everyone_overwrite.into_iter().collect();
+            for r in self.roles.iter().filter(|r| r.held) {
+                if let Some(e) = r.cap {
+                    caps.push(RoleCapEntry {
+                        is_everyone: false,
+                        position: r.position,
+                        allowed: e == Effect::Grant,
+                    });
+                }
+                overwrites.extend(r.channel_overwrite);
+            }
+            overwrites.extend(self.user_overwrite);
+            (caps, overwrites)
+        }
+
+        /// Reference model: levels from most to least specific; the first
+        /// level with any entry decides, deny winning within a level.
+        fn expected(&self) -> Decision {
+            let held: Vec<&Role> = self.roles.iter().filter(|r| r.held).collect();
+            let mut levels: Vec<Vec<Effect>> = Vec::new();
+            levels.push(
+                self.everyone_overwrite
+                    .into_iter()
+                    .chain(held.iter().filter_map(|r| r.channel_overwrite))
+                    .chain(self.user_overwrite)
+                    .collect(),
+            );
+            let mut positions: Vec<i32> = held.iter().map(|r| r.position).collect();
+            positions.sort_unstable_by(|a, b| b.cmp(a));
+            positions.dedup();
+            for pos in positions {
+                levels.push(
+                    held.iter()
+                        .filter(|r| r.position == pos)
+                        .filter_map(|r| r.cap)
+                        .collect(),
+                );
+            }
+            levels.push(self.everyone_cap.into_iter().collect());
+            for level in levels {
+                if level.contains(&Effect::Deny) {
+                    return Decision::Deny;
+                }
+                if level.contains(&Effect::Grant) {
+                    return Decision::Allow;
+                }
+            }
+            Decision::Deny
+        }
+    }
+
+    #[test]
+    fn resolve_decision_matches_layered_model_on_random_configs() {
+        let mut rng = Rng(0xD1B5_4A32_D192_ED03);
+        for _ in 0..20_000 {
+            let cfg = Config::random(&mut rng);
+            let (mut caps, mut overwrites) = cfg.rows();
+            let decision = resolve_decision(&caps, &overwrites);
+            assert_eq!(decision, cfg.expected());
+
+            // Row order from the database must not matter.
+            caps.reverse();
+            overwrites.reverse();
+            assert_eq!(resolve_decision(&caps, &overwrites), decision);
+
+            // A channel deny beats every grant beneath it.
+            overwrites.push(Effect::Deny);
+            assert_eq!(resolve_decision(&caps, &overwrites), Decision::Deny);
+            overwrites.pop();
+
+            // Without a channel deny, a channel grant beats every role deny.
+            if !overwrites.contains(&Effect::Deny) {
+                overwrites.push(Effect::Grant);
+                assert_eq!(resolve_decision(&caps, &overwrites), Decision::Allow);
+                overwrites.pop();
+            }
+
+            // A deny on the highest held role beats grants on that role and below.
+            if overwrites.is_empty() {
+                let top = caps
+                    .iter()
+                    .map(|e| if e.is_everyone { 0 } else { e.position })
+                    .max()
+                    .unwrap_or(0);
+                caps.push(RoleCapEntry {
+                    is_everyone: top == 0,
+                    position: top,
+                    allowed: false,
+                });
+                assert_eq!(resolve_decision(&caps, &overwrites), Decision::Deny);
+            }
+        }
+    }
+
+    #[test]
+    fn higher_role_grant_overrides_everyone_deny() {
+        let caps = [
+            RoleCapEntry {
+                is_everyone: true,
+                position: 0,
+                allowed: false,
+            },
+            RoleCapEntry {
+                is_everyone: false,
+                position: 2,
+                allowed: true,
+            },
+        ];
+        assert_eq!(resolve_decision(&caps, &[]), Decision::Allow);
+        assert_eq!(resolve_decision(&[], &[]), Decision::Deny);
+    }
+
+    #[test]
+    fn channel_grant_overrides_everyone_deny() {
+        let caps = [RoleCapEntry {
+            is_everyone: true,
+            position: 0,
+            allowed: false,
+        }];
+        assert_eq!(resolve_decision(&caps, &[]), Decision::Deny);
+        assert_eq!(resolve_decision(&caps, &[Effect::Grant]), Decision::Allow);
+        assert_eq!(
+            resolve_decision(&caps, &[Effect::Grant, Effect::Deny]),
+            Decision::Deny
+        );
+    }
+}
@@ -9,11 +9,12 @@ use crate::{
     errors::{ControlError, ControlResult},
     ids::{ChannelId, MessageId, OutboxId, ServerId, UserId},
     model::{
-        Attachment, AuditEntry, Channel, ChannelListItem, ChatMessage, Member, OutboxEvent,
-        OutboxEventRow, PermAuditRow, PermChannelOverrideRecord, PermRoleRecord,
-        PermUserSummaryRecord, PermissionRequest,
+        ActiveBan, Attachment, AuditEntry, Channel, ChannelListItem, ChannelNotificationPref,
+        ChannelNotificationPrefRecord, ChatMessage, ChatMessageKind, Member, MemberCursor,
+        OutboxEvent, OutboxEventRow, PermAuditRow, PermChannelOverrideRecord, PermRoleRecord,
+        PermUserSummaryRecord, PermissionRequest, ReactionSummary, ReadMarker,
     },
-    perms::Decision,
+    perms::{resolve_decision, Decision, Effect, RoleCapEntry},
 };
 
 pub async fn is_user_in_channel(
@@ -77,6 +78,15 @@ pub trait ControlRepo: Send + Sync {
         bitrate_bps: i32,
         opus_profile: i32,
     ) -> ControlResult<Option<Channel>>;
+    async fn set_channel_chat_limits(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        id: ChannelId,
+        max_message_length: Option<i32>,
+        slow_mode_secs: i32,
+        announce_presence: bool,
+    ) -> ControlResult<Option<Channel>>;
     async fn delete_channel(
         &self,
         tx: &mut Transaction<'_, Postgres>,
@@ -89,6 +99,79 @@ pub trait ControlRepo: Send + Sync {
         server: ServerId,
         id: ChannelId,
     ) -> ControlResult<Vec<ChannelId>>;
+    /// Serialize channel creation per server until `tx` ends so limit checks
+    /// cannot be raced.
+    async fn lock_channel_creation(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+    ) -> ControlResult<()>;
+    /// Returns (channels on the server, channels created by `creator`).
+    async fn count_channels(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        creator: UserId,
+    ) -> ControlResult<(i64, i64)>;
+    /// Whether a sibling under `parent` already uses `name` (case-insensitive),
+    /// ignoring `except` so a channel can keep its own name.
+    async fn channel_name_taken(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        parent: Option<ChannelId>,
+        name: &str,
+        except: Option<ChannelId>,
+    ) -> ControlResult<bool>;
+    /// Bump `updated_at` on an ephemeral channel so the idle sweeper measures
+    /// emptiness from the last leave. No-op for regular channels.
+    async fn touch_ephemeral_channel(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        id: ChannelId,
+    ) -> ControlResult<()>;
+    /// Ephemeral channels untouched since `before`, oldest first.
+    async fn list_stale_ephemeral_channels(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        before: DateTime<Utc>,
+    ) -> ControlResult<Vec<ChannelId>>;
+
+    // Channel notification prefs
+    async fn set_channel_notification_pref(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        user: UserId,
+        channel: ChannelId,
+        pref: ChannelNotificationPref,
+    ) -> ControlResult<()>;
+    async fn list_channel_notification_prefs(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+    ) -> ControlResult<Vec<ChannelNotificationPrefRecord>>;
+
+    // Read markers
+    /// Move `user`'s marker in `channel` to `message` unless it already points
+    /// at a later one. Returns the stored marker and whether it advanced, or
+    /// `None` when `message` is not in `channel`.
+    async fn mark_read(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        channel: ChannelId,
+        user: UserId,
+        message: MessageId,
+    ) -> ControlResult<Option<(ReadMarker, bool)>>;
+    async fn list_read_markers(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        channel: ChannelId,
+    ) -> ControlResult<Vec<ReadMarker>>;
 
     // Members (Member has NO server_id)
     async fn upsert_member(
@@ -97,6 +180,15 @@ pub trait ControlRepo: Send + Sync {
         server: ServerId,
         m: &Member,
     ) -> ControlResult<()>;
+    /// Inserts the member unless the row already exists; returns whether it
+    /// was inserted. Unlike `upsert_member` this leaves an existing row
+    /// (including its server mute/deafen) untouched.
+    async fn insert_member(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        m: &Member,
+    ) -> ControlResult<bool>;
     async fn delete_member(
         &self,
         tx: &mut Transaction<'_, Postgres>,
@@ -117,18 +209,61 @@ pub trait ControlRepo: Send + Sync {
         server: ServerId,
         channel: ChannelId,
     ) -> ControlResult<Vec<Member>>;
+    /// Up to `limit` members after `after`, in the same order as `list_members`.
+    async fn list_members_page(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        channel: ChannelId,
+        after: Option<&MemberCursor>,
+        limit: i64,
+    ) -> ControlResult<Vec<Member>>;
     async fn count_members(
         &self,
         tx: &mut Transaction<'_, Postgres>,
         server: ServerId,
         channel: ChannelId,
     ) -> ControlResult<i64>;
+    /// Take a row lock on the channel until `tx` ends, serializing
+    /// capacity-checked joins. Returns false if the channel is gone.
+    async fn lock_channel(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        channel: ChannelId,
+    ) -> ControlResult<bool>;
     async fn list_member_channels_for_user(
         &self,
         tx: &mut Transaction<'_, Postgres>,
         server: ServerId,
         user: UserId,
     ) -> ControlResult<Vec<ChannelId>>;
+    /// Ban `user` from the server, replacing any earlier ban. `expires_at`
+    /// None is permanent.
+    async fn upsert_ban(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        user: UserId,
+        actor: UserId,
+        reason: &str,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> ControlResult<()>;
+    /// Whether `user` has a ban that is permanent or still running at `now`.
+    async fn is_banned(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        user: UserId,
+        now: DateTime<Utc>,
+    ) -> ControlResult<bool>;
+    /// Bans on `server` that are permanent or still running at `now`.
+    async fn list_active_bans(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        now: DateTime<Utc>,
+    ) -> ControlResult<Vec<ActiveBan>>;
 
     async fn perm_list_roles(
         &self,
@@ -252,6 +387,70 @@ pub trait ControlRepo: Send + Sync {
         server: ServerId,
         id: MessageId,
     ) -> ControlResult<Option<ChatMessage>>;
+    /// Replace a message's text and stamp `edited_at`. Returns false if the
+    /// message is missing or already deleted.
+    async fn edit_chat_message(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        id: MessageId,
+        text: &str,
+        at: DateTime<Utc>,
+    ) -> ControlResult<bool>;
+    /// Soft-delete a message. Returns false if it is missing or already deleted.
+    async fn delete_chat_message(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        id: MessageId,
+        at: DateTime<Utc>,
+    ) -> ControlResult<bool>;
+    /// Up to `limit` live messages in `channel`, newest first, strictly older
+    /// than `before` when given.
+    async fn list_messages(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        channel: ChannelId,
+        before: Option<MessageId>,
+        limit: i64,
+    ) -> ControlResult<Vec<ChatMessage>>;
+    /// Per-emoji reaction counts for `messages`, flagging the ones `viewer` added.
+    async fn list_message_reactions(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        messages: &[MessageId],
+        viewer: UserId,
+    ) -> ControlResult<Vec<(MessageId, ReactionSummary)>>;
+    /// Record `user`'s `emoji` on a message. Returns false if it was already there.
+    async fn add_message_reaction(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        message: MessageId,
+        user: UserId,
+        emoji: &str,
+    ) -> ControlResult<bool>;
+    /// Returns false if `user` had not reacted with `emoji`.
+    async fn remove_message_reaction(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        message: MessageId,
+        user: UserId,
+        emoji: &str,
+    ) -> ControlResult<bool>;
+    async fn count_message_reactions(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        message: MessageId,
+        emoji: &str,
+    ) -> ControlResult<i64>;
+    /// When `author` last posted in `channel`, for slow mode.
+    async fn last_chat_message_at(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        channel: ChannelId,
+        author: UserId,
+    ) -> ControlResult<Option<DateTime<Utc>>>;
 
     async fn get_attachment(
         &self,
@@ -278,6 +477,17 @@ pub trait ControlRepo: Send + Sync {
         ids: &[OutboxId],
         claim_token: Uuid,
     ) -> ControlResult<()>;
+    /// Count a failed delivery of a claimed record. On the `max_attempts`th
+    /// failure the record is dead-lettered and no longer claimed; returns
+    /// whether that happened.
+    async fn record_outbox_failure(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: OutboxId,
+        claim_token: Uuid,
+        error: &str,
+        max_attempts: i32,
+    ) -> ControlResult<bool>;
 
     // Audit
     async fn insert_audit(
@@ -452,8 +662,8 @@ impl ControlRepo for PgControlRepo {
     ) -> ControlResult<()> {
         sqlx::query(
             r#"
-            INSERT INTO channels (id, server_id, name, parent_id, max_members, max_talkers, channel_type, description, bitrate_bps, opus_profile, created_at, updated_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, NOW(), NOW())
+            INSERT INTO channels (id, server_id, name, parent_id, max_members, max_talkers, channel_type, description, bitrate_bps, opus_profile, ephemeral, created_by, max_message_length, slow_mode_secs, announce_presence, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, NOW(), NOW())
             "#,
         )
         .bind(ch.id.0)
@@ -466,6 +676,11 @@ impl ControlRepo for PgControlRepo {
         .bind(&ch.description)
         .bind(ch.bitrate_bps)
         .bind(ch.opus_profile)
+        .bind(ch.ephemeral)
+        .bind(ch.created_by.map(|u| u.0))
+        .bind(ch.max_message_length)
+        .bind(ch.slow_mode_secs)
+        .bind(ch.announce_presence)
         .execute(&mut **tx)
         .await
         .context("insert channels")?;
@@ -480,7 +695,7 @@ impl ControlRepo for PgControlRepo {
     ) -> ControlResult<Option<Channel>> {
         let row = sqlx::query(
             r#"
-            SELECT id, server_id, name, parent_id, max_members, max_talkers, channel_type, description, bitrate_bps, opus_profile, created_at, updated_at
+            SELECT id, server_id, name, parent_id, max_members, max_talkers, channel_type, description, bitrate_bps, opus_profile, ephemeral, created_by, max_message_length, slow_mode_secs, announce_presence, created_at, updated_at
             FROM channels
             WHERE server_id = $1 AND id = $2
             "#,
@@ -491,20 +706,7 @@ impl ControlRepo for PgControlRepo {
         .await
         .context("get channel")?;
 
-        Ok(row.map(|r| Channel {
-            id: ChannelId(r.get::<Uuid, _>("id")),
-            server_id: ServerId(r.get::<Uuid, _>("server_id")),
-            name: r.get::<String, _>("name"),
-            parent_id: r.get::<Option<Uuid>, _>("parent_id").map(ChannelId),
-            max_members: r.get::<Option<i32>, _>("max_members"),
-            max_talkers: r.get::<Option<i32>, _>("max_talkers"),
-            channel_type: r.get::<i32, _>("channel_type"),
-            description: r.get::<String, _>("description"),
-            bitrate_bps: r.get::<i32, _>("bitrate_bps"),
-            opus_profile: r.get::<i32, _>("opus_profile"),
-            created_at: r.get::<DateTime<Utc>, _>("created_at"),
-            updated_at: r.get::<DateTime<Utc>, _>("updated_at"),
-        }))
+        Ok(row.as_ref().map(channel_from_row))
     }
 
     async fn list_channels(
@@ -514,7 +716,7 @@ impl ControlRepo for PgControlRepo {
     ) -> ControlResult<Vec<ChannelListItem>> {
         let rows = sqlx::query(
             r#"
-            SELECT id, name, parent_id, max_members, max_talkers, channel_type, description, bitrate_bps, opus_profile
+            SELECT id, name, parent_id, max_members, max_talkers, channel_type, description, bitrate_bps, opus_profile, max_message_length, slow_mode_secs, announce_presence
             FROM channels
             WHERE server_id = $1
             ORDER BY name ASC
@@ -537,6 +739,9 @@ impl ControlRepo for PgControlRepo {
                 description: r.get::<String, _>("description"),
                 bitrate_bps: r.get::<i32, _>("bitrate_bps"),
                 opus_profile: r.get::<i32, _>("opus_profile"),
+                max_message_length: r.get::<Option<i32>, _>("max_message_length"),
+                slow_mode_secs: r.get::<i32, _>("slow_mode_secs"),
+                announce_presence: r.get::<bool, _>("announce_presence"),
             });
         }
         Ok(out)
@@ -554,7 +759,7 @@ impl ControlRepo for PgControlRepo {
             UPDATE channels
             SET name = $3, updated_at = NOW()
             WHERE server_id = $1 AND id = $2
-            RETURNING id, server_id, name, parent_id, max_members, max_talkers, channel_type, description, bitrate_bps, opus_profile, created_at, updated_at
+            RETURNING id, server_id, name, parent_id, max_members, max_talkers, channel_type, description, bitrate_bps, opus_profile, ephemeral, created_by, max_message_length, slow_mode_secs, announce_presence, created_at, updated_at
             "#,
         )
         .bind(server.0)
@@ -564,20 +769,7 @@ impl ControlRepo for PgControlRepo {
         .await
         .context("rename channel")?;
 
-        Ok(row.map(|r| Channel {
-            id: ChannelId(r.get::<Uuid, _>("id")),
-            server_id: ServerId(r.get::<Uuid, _>("server_id")),
-            name: r.get::<String, _>("name"),
-            parent_id: r.get::<Option<Uuid>, _>("parent_id").map(ChannelId),
-            max_members: r.get::<Option<i32>, _>("max_members"),
-            max_talkers: r.get::<Option<i32>, _>("max_talkers"),
-            channel_type: r.get::<i32, _>("channel_type"),
-            description: r.get::<String, _>("description"),
-            bitrate_bps: r.get::<i32, _>("bitrate_bps"),
-            opus_profile: r.get::<i32, _>("opus_profile"),
-            created_at: r.get::<DateTime<Utc>, _>("created_at"),
-            updated_at: r.get::<DateTime<Utc>, _>("updated_at"),
-        }))
+        Ok(row.as_ref().map(channel_from_row))
     }
 
     async fn update_channel(
@@ -594,7 +786,7 @@ impl ControlRepo for PgControlRepo {
             UPDATE channels
             SET name = $3, bitrate_bps = $4, opus_profile = $5, updated_at = NOW()
             WHERE server_id = $1 AND id = $2
-            RETURNING id, server_id, name, parent_id, max_members, max_talkers, channel_type, description, bitrate_bps, opus_profile, created_at, updated_at
+            RETURNING id, server_id, name, parent_id, max_members, max_talkers, channel_type, description, bitrate_bps, opus_profile, ephemeral, created_by, max_message_length, slow_mode_secs, announce_presence, created_at, updated_at
             "#,
         )
         .bind(server.0)
@@ -606,20 +798,36 @@ impl ControlRepo for PgControlRepo {
         .await
         .context("update channel")?;
 
-        Ok(row.map(|r| Channel {
-            id: ChannelId(r.get::<Uuid, _>("id")),
-            server_id: ServerId(r.get::<Uuid, _>("server_id")),
-            name: r.get::<String, _>("name"),
-            parent_id: r.get::<Option<Uuid>, _>("parent_id").map(ChannelId),
-            max_members: r.get::<Option<i32>, _>("max_members"),
-            max_talkers: r.get::<Option<i32>, _>("max_talkers"),
-            channel_type: r.get::<i32, _>("channel_type"),
-            description: r.get::<String, _>("description"),
-            bitrate_bps: r.get::<i32, _>("bitrate_bps"),
-            opus_profile: r.get::<i32, _>("opus_profile"),
-            created_at: r.get::<DateTime<Utc>, _>("created_at"),
-            updated_at: r.get::<DateTime<Utc>, _>("updated_at"),
-        }))
+        Ok(row.as_ref().map(channel_from_row))
+    }
+
+    async fn set_channel_chat_limits(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        id: ChannelId,
+        max_message_length: Option<i32>,
+        slow_mode_secs: i32,
+        announce_presence: bool,
+    ) -> ControlResult<Option<Channel>> {
+        let row = sqlx::query(
+            r#"
+            UPDATE channels
+            SET max_message_length = $3, slow_mode_secs = $4, announce_presence = $5, updated_at = NOW()
+            WHERE server_id = $1 AND id = $2
+            RETURNING id, server_id, name, parent_id, max_members, max_talkers, channel_type, description, bitrate_bps, opus_profile, ephemeral, created_by, max_message_length, slow_mode_secs, announce_presence, created_at, updated_at
+            "#,
+        )
+        .bind(server.0)
+        .bind(id.0)
+        .bind(max_message_length)
+        .bind(slow_mode_secs)
+        .bind(announce_presence)
+        .fetch_optional(&mut **tx)
+        .await
+        .context("set channel chat limits")?;
+
+        Ok(row.as_ref().map(channel_from_row))
     }
 
     async fn delete_channel(
@@ -673,6 +881,286 @@ impl ControlRepo for PgControlRepo {
             .collect())
     }
 
+    async fn lock_channel_creation(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+    ) -> ControlResult<()> {
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtextextended('channels:' || $1::text, 0))")
+            .bind(server.0)
+            .execute(&mut **tx)
+            .await
+            .context("lock channel creation")?;
+        Ok(())
+    }
+
+    async fn count_channels(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        creator: UserId,
+    ) -> ControlResult<(i64, i64)> {
+        let row = sqlx::query(
+            r#"
+            SELECT COUNT(*)::bigint AS total,
+                   COUNT(*) FILTER (WHERE created_by = $2)::bigint AS by_creator
+            FROM channels
+            WHERE server_id = $1
+            "#,
+        )
+        .bind(server.0)
+        .bind(creator.0)
+        .fetch_one(&mut **tx)
+        .await
+        .context("count channels")?;
+
+        Ok((row.get::<i64, _>("total"), row.get::<i64, _>("by_creator")))
+    }
+
+    async fn channel_name_taken(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        parent: Option<ChannelId>,
+        name: &str,
+        except: Option<ChannelId>,
+    ) -> ControlResult<bool> {
+        let taken = sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM channels
+                WHERE server_id = $1
+                  AND parent_id IS NOT DISTINCT FROM $2
+                  AND lower(name) = lower($3)
+                  AND ($4::uuid IS NULL OR id <> $4)
+            )
+            "#,
+        )
+        .bind(server.0)
+        .bind(parent.map(|p| p.0))
+        .bind(name)
+        .bind(except.map(|c| c.0))
+        .fetch_one(&mut **tx)
+        .await
+        .context("check channel name")?;
+
+        Ok(taken)
+    }
+
+    async fn touch_ephemeral_channel(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        id: ChannelId,
+    ) -> ControlResult<()> {
+        sqlx::query(
+            r#"
+            UPDATE channels
+            SET updated_at = NOW()
+            WHERE server_id = $1 AND id = $2 AND ephemeral
+            "#,
+        )
+        .bind(server.0)
+        .bind(id.0)
+        .execute(&mut **tx)
+        .await
+        .context("touch ephemeral channel")?;
+        Ok(())
+    }
+
+    async fn list_stale_ephemeral_channels(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        before: DateTime<Utc>,
+    ) -> ControlResult<Vec<ChannelId>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id
+            FROM channels
+            WHERE server_id = $1 AND ephemeral AND updated_at <= $2
+            ORDER BY updated_at ASC
+            "#,
+        )
+        .bind(server.0)
+        .bind(before)
+        .fetch_all(&mut **tx)
+        .await
+        .context("list stale ephemeral channels")?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| ChannelId(r.get::<Uuid, _>("id")))
+            .collect())
+    }
+
+    // -------------------------
+    // Channel notification prefs
+    // -------------------------
+
+    async fn set_channel_notification_pref(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        user: UserId,
+        channel: ChannelId,
+        pref: ChannelNotificationPref,
+    ) -> ControlResult<()> {
+        if pref == ChannelNotificationPref::Default {
+            sqlx::query(
+                r#"
+                DELETE FROM channel_notification_prefs
+                WHERE server_id = $1 AND user_id = $2 AND channel_id = $3
+                "#,
+            )
+            .bind(server.0)
+            .bind(user.0)
+            .bind(channel.0)
+            .execute(&mut **tx)
+            .await
+            .context("clear channel notification pref")?;
+            return Ok(());
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO channel_notification_prefs (server_id, user_id, channel_id, pref, updated_at)
+            VALUES ($1, $2, $3, $4, NOW())
+            ON CONFLICT (user_id, channel_id) DO UPDATE
+            SET pref = EXCLUDED.pref, updated_at = NOW()
+            "#,
+        )
+        .bind(server.0)
+        .bind(user.0)
+        .bind(channel.0)
+        .bind(pref.as_str())
+        .execute(&mut **tx)
+        .await
+        .context("upsert channel notification pref")?;
+        Ok(())
+    }
+
+    async fn list_channel_notification_prefs(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+    ) -> ControlResult<Vec<ChannelNotificationPrefRecord>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT user_id, channel_id, pref
+            FROM channel_notification_prefs
+            WHERE server_id = $1
+            "#,
+        )
+        .bind(server.0)
+        .fetch_all(&mut **tx)
+        .await
+        .context("list channel notification prefs")?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|r| {
+                let pref = ChannelNotificationPref::from_str(&r.get::<String, _>("pref"))?;
+                Some(ChannelNotificationPrefRecord {
+                    user_id: UserId(r.get("user_id")),
+                    channel_id: ChannelId(r.get("channel_id")),
+                    pref,
+                })
+            })
+            .collect())
+    }
+
+    // -------------------------
+    // Read markers
+    // -------------------------
+
+    async fn mark_read(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        channel: ChannelId,
+        user: UserId,
+        message: MessageId,
+    ) -> ControlResult<Option<(ReadMarker, bool)>> {
+        let message_at = sqlx::query_scalar::<_, DateTime<Utc>>(
+            r#"
+            SELECT created_at
+            FROM chat_messages
+            WHERE server_id = $1 AND channel_id = $2 AND id = $3
+            "#,
+        )
+        .bind(server.0)
+        .bind(channel.0)
+        .bind(message.0)
+        .fetch_optional(&mut **tx)
+        .await
+        .context("lookup read marker message")?;
+        let Some(message_at) = message_at else {
+            return Ok(None);
+        };
+
+        let advanced = sqlx::query(
+            r#"
+            INSERT INTO channel_read_markers
+                (server_id, user_id, channel_id, message_id, message_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, NOW())
+            ON CONFLICT (user_id, channel_id) DO UPDATE
+            SET message_id = EXCLUDED.message_id,
+                message_at = EXCLUDED.message_at,
+                updated_at = NOW()
+            WHERE channel_read_markers.message_at < EXCLUDED.message_at
+            "#,
+        )
+        .bind(server.0)
+        .bind(user.0)
+        .bind(channel.0)
+        .bind(message.0)
+        .bind(message_at)
+        .execute(&mut **tx)
+        .await
+        .context("upsert read marker")?
+        .rows_affected()
+            > 0;
+
+        let row = sqlx::query(
+            r#"
+            SELECT user_id, channel_id, message_id, message_at, updated_at
+            FROM channel_read_markers
+            WHERE user_id = $1 AND channel_id = $2
+            "#,
+        )
+        .bind(user.0)
+        .bind(channel.0)
+        .fetch_one(&mut **tx)
+        .await
+        .context("load read marker")?;
+
+        Ok(Some((read_marker_from_row(&row), advanced)))
+    }
+
+    async fn list_read_markers(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        channel: ChannelId,
+    ) -> ControlResult<Vec<ReadMarker>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT user_id, channel_id, message_id, message_at, updated_at
+            FROM channel_read_markers
+            WHERE server_id = $1 AND channel_id = $2
+            ORDER BY message_at DESC
+            "#,
+        )
+        .bind(server.0)
+        .bind(channel.0)
+        .fetch_all(&mut **tx)
+        .await
+        .context("list read markers")?;
+
+        Ok(rows.iter().map(read_marker_from_row).collect())
+    }
+
     // -------------------------
     // Members
     // -------------------------
@@ -708,6 +1196,32 @@ impl ControlRepo for PgControlRepo {
         Ok(())
     }
 
+    async fn insert_member(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        m: &Member,
+    ) -> ControlResult<bool> {
+        let res = sqlx::query(
+            r#"
+            INSERT INTO members (server_id, channel_id, user_id, display_name, muted, deafened, joined_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, COALESCE($7, NOW()), NOW())
+            ON CONFLICT (server_id, channel_id, user_id) DO NOTHING
+            "#,
+        )
+        .bind(server.0)
+        .bind(m.channel_id.0)
+        .bind(m.user_id.0)
+        .bind(&m.display_name)
+        .bind(m.muted)
+        .bind(m.deafened)
+        .bind(Some(m.joined_at))
+        .execute(&mut **tx)
+        .await
+        .context("insert member")?;
+        Ok(res.rows_affected() == 1)
+    }
+
     async fn delete_member(
         &self,
         tx: &mut Transaction<'_, Postgres>,
@@ -780,29 +1294,49 @@ impl ControlRepo for PgControlRepo {
             FROM members m
             LEFT JOIN user_profiles up ON up.user_id = m.user_id AND up.server_id = m.server_id
             WHERE m.server_id = $1 AND m.channel_id = $2
-            ORDER BY m.joined_at ASC
+            ORDER BY m.joined_at ASC, m.user_id ASC
             "#,
         )
         .bind(server.0)
         .bind(channel.0)
         .fetch_all(&mut **tx)
         .await
-        .context("list members")?;
+        .context("list members")?;
+
+        Ok(rows.iter().map(member_from_row).collect())
+    }
+
+    async fn list_members_page(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        channel: ChannelId,
+        after: Option<&MemberCursor>,
+        limit: i64,
+    ) -> ControlResult<Vec<Member>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT m.channel_id, m.user_id, m.display_name, m.muted, m.deafened, m.joined_at,
+                   COALESCE(up.custom_status_text, '') AS custom_status_text,
+                   COALESCE(up.custom_status_emoji, '') AS custom_status_emoji
+            FROM members m
+            LEFT JOIN user_profiles up ON up.user_id = m.user_id AND up.server_id = m.server_id
+            WHERE m.server_id = $1 AND m.channel_id = $2
+              AND ($3::timestamptz IS NULL OR (m.joined_at, m.user_id) > ($3, $4))
+            ORDER BY m.joined_at ASC, m.user_id ASC
+            LIMIT $5
+            "#,
+        )
+        .bind(server.0)
+        .bind(channel.0)
+        .bind(after.map(|c| c.joined_at))
+        .bind(after.map(|c| c.user_id.0))
+        .bind(limit)
+        .fetch_all(&mut **tx)
+        .await
+        .context("list members page")?;
 
-        let mut out = Vec::with_capacity(rows.len());
-        for r in rows {
-            out.push(Member {
-                channel_id: ChannelId(r.get::<Uuid, _>("channel_id")),
-                user_id: UserId(r.get::<Uuid, _>("user_id")),
-                display_name: r.get::<String, _>("display_name"),
-                muted: r.get::<bool, _>("muted"),
-                deafened: r.get::<bool, _>("deafened"),
-                joined_at: r.get::<DateTime<Utc>, _>("joined_at"),
-                custom_status_text: r.get::<String, _>("custom_status_text"),
-                custom_status_emoji: r.get::<String, _>("custom_status_emoji"),
-            });
-        }
-        Ok(out)
+        Ok(rows.iter().map(member_from_row).collect())
     }
 
     async fn count_members(
@@ -827,6 +1361,29 @@ impl ControlRepo for PgControlRepo {
         Ok(n)
     }
 
+    async fn lock_channel(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        channel: ChannelId,
+    ) -> ControlResult<bool> {
+        let locked: Option<Uuid> = sqlx::query_scalar(
+            r#"
+            SELECT id
+            FROM channels
+            WHERE server_id = $1 AND id = $2
+            FOR UPDATE
+            "#,
+        )
+        .bind(server.0)
+        .bind(channel.0)
+        .fetch_optional(&mut **tx)
+        .await
+        .context("lock channel")?;
+
+        Ok(locked.is_some())
+    }
+
     async fn list_member_channels_for_user(
         &self,
         tx: &mut Transaction<'_, Postgres>,
@@ -852,6 +1409,92 @@ impl ControlRepo for PgControlRepo {
             .collect())
     }
 
+    async fn upsert_ban(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        user: UserId,
+        actor: UserId,
+        reason: &str,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> ControlResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO bans (server_id, user_id, actor_user_id, reason, created_at, expires_at)
+            VALUES ($1, $2, $3, $4, now(), $5)
+            ON CONFLICT (server_id, user_id) DO UPDATE
+            SET actor_user_id = EXCLUDED.actor_user_id,
+                reason = EXCLUDED.reason,
+                created_at = EXCLUDED.created_at,
+                expires_at = EXCLUDED.expires_at
+            "#,
+        )
+        .bind(server.0)
+        .bind(user.0)
+        .bind(actor.0)
+        .bind(reason)
+        .bind(expires_at)
+        .execute(&mut **tx)
+        .await
+        .context("upsert ban")?;
+        Ok(())
+    }
+
+    async fn is_banned(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        user: UserId,
+        now: DateTime<Utc>,
+    ) -> ControlResult<bool> {
+        let banned = sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT EXISTS (
+                SELECT 1
+                FROM bans
+                WHERE server_id = $1 AND user_id = $2
+                  AND (expires_at IS NULL OR expires_at > $3)
+            )
+            "#,
+        )
+        .bind(server.0)
+        .bind(user.0)
+        .bind(now)
+        .fetch_one(&mut **tx)
+        .await
+        .context("check ban")?;
+        Ok(banned)
+    }
+
+    async fn list_active_bans(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        now: DateTime<Utc>,
+    ) -> ControlResult<Vec<ActiveBan>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT user_id, expires_at
+            FROM bans
+            WHERE server_id = $1
+              AND (expires_at IS NULL OR expires_at > $2)
+            "#,
+        )
+        .bind(server.0)
+        .bind(now)
+        .fetch_all(&mut **tx)
+        .await
+        .context("list active bans")?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| ActiveBan {
+                user_id: UserId(r.get("user_id")),
+                expires_at: r.get("expires_at"),
+            })
+            .collect())
+    }
+
     // -------------------------
     // Admin permissions RPC backing ops
     // -------------------------
@@ -1256,12 +1899,11 @@ impl ControlRepo for PgControlRepo {
 
         let cap = req.capability.as_str();
 
-        // Base permission from roles in hierarchy order:
-        // @everyone first, then regular roles by position (low -> high),
-        // where later rows override earlier ones.
-        let base_role_allowed: Option<bool> = sqlx::query_scalar(
+        // Every role_caps row for this cap on @everyone or a role the user
+        // holds; precedence is applied by `resolve_decision`, not row order.
+        let role_caps: Vec<RoleCapEntry> = sqlx::query(
             r#"
-            SELECT rc.allowed
+            SELECT r.is_everyone, r.position, rc.allowed
             FROM role_caps rc
             JOIN roles r ON r.id = rc.role_id
             LEFT JOIN user_roles ur
@@ -1272,117 +1914,60 @@ impl ControlRepo for PgControlRepo {
               AND rc.cap = $3
               AND rc.server_id = $1
               AND (r.is_everyone = TRUE OR ur.role_id IS NOT NULL)
-            ORDER BY r.is_everyone DESC, r.position ASC, r.id ASC
             "#,
         )
         .bind(req.server_id.0)
         .bind(req.user_id.0)
         .bind(cap)
-        .fetch_optional(&mut **tx)
+        .fetch_all(&mut **tx)
         .await
-        .context("decide_permission base role effect")?;
-
-        let base_allowed = base_role_allowed.unwrap_or(false);
+        .context("decide_permission role caps")?
+        .into_iter()
+        .map(|row| RoleCapEntry {
+            is_everyone: row.get("is_everyone"),
+            position: row.get("position"),
+            allowed: row.get("allowed"),
+        })
+        .collect();
 
-        let overwrite_decision = if let Some(channel_id) = req.channel_id {
-            // Discord-like channel overwrite evaluation:
-            // @everyone role overwrite -> role overwrites -> user overwrite,
-            // with deny winning over allow at overwrite layer.
-            let everyone_role_override: Option<String> = sqlx::query_scalar(
+        let mut channel_overwrites: Vec<Effect> = Vec::new();
+        if let Some(channel_id) = req.channel_id {
+            // Discord-like channel overwrites: @everyone role, the user's
+            // roles and the user themselves form a single layer.
+            let effects: Vec<String> = sqlx::query_scalar(
                 r#"
                 SELECT cro.effect
                 FROM channel_role_overrides cro
                 JOIN roles r ON r.id = cro.role_id
-                WHERE cro.server_id = $2
-                  AND cro.channel_id = $1
-                  AND r.server_id = $2
-                  AND r.is_everyone = TRUE
-                  AND cro.cap = $3
-                ORDER BY cro.effect DESC
-                "#,
-            )
-            .bind(channel_id.0)
-            .bind(req.server_id.0)
-            .bind(cap)
-            .fetch_optional(&mut **tx)
-            .await
-            .context("decide_permission everyone overwrite")?;
-
-            let role_overwrite_effects: Vec<String> = sqlx::query_scalar(
-                r#"
-                SELECT cro.effect
-                FROM channel_role_overrides cro
-                JOIN user_roles ur
+                LEFT JOIN user_roles ur
                   ON ur.server_id = $2
                  AND ur.user_id = $3
                  AND ur.role_id = cro.role_id
-                JOIN roles r ON r.id = ur.role_id
                 WHERE cro.server_id = $2
                   AND cro.channel_id = $1
+                  AND r.server_id = $2
                   AND cro.cap = $4
-                ORDER BY r.position ASC, r.id ASC
-                "#,
-            )
-            .bind(channel_id.0)
-            .bind(req.server_id.0)
-            .bind(req.user_id.0)
-            .bind(cap)
-            .fetch_all(&mut **tx)
-            .await
-            .context("decide_permission role overwrites")?;
-
-            let member_override_effect: Option<String> = sqlx::query_scalar(
-                r#"
+                  AND (r.is_everyone = TRUE OR ur.role_id IS NOT NULL)
+                UNION ALL
                 SELECT effect
                 FROM channel_user_overrides
-                WHERE server_id = $4
+                WHERE server_id = $2
                   AND channel_id = $1
-                  AND user_id = $2
-                  AND cap = $3
-                ORDER BY effect DESC
+                  AND user_id = $3
+                  AND cap = $4
                 "#,
             )
             .bind(channel_id.0)
+            .bind(req.server_id.0)
             .bind(req.user_id.0)
             .bind(cap)
-            .bind(req.server_id.0)
-            .fetch_optional(&mut **tx)
+            .fetch_all(&mut **tx)
             .await
-            .context("decide_permission member overwrite")?;
-
-            let mut has_allow = false;
-            let mut has_deny = false;
-
-            for effect in everyone_role_override
-                .iter()
-                .chain(role_overwrite_effects.iter())
-                .chain(member_override_effect.iter())
-            {
-                match effect.as_str() {
-                    "deny" => has_deny = true,
-                    "grant" => has_allow = true,
-                    _ => {}
-                }
-            }
-
-            if has_deny {
-                Some(Decision::Deny)
-            } else if has_allow {
-                Some(Decision::Allow)
-            } else {
-                None
-            }
-        } else {
-            None
-        };
-
-        if let Some(decision) = overwrite_decision {
-            Ok(decision)
-        } else if base_allowed {
-            Ok(Decision::Allow)
-        } else {
-            Ok(Decision::Deny)
+            .context("decide_permission channel overwrites")?;
+            channel_overwrites.extend(effects.iter().filter_map(|e| Effect::from_str(e)));
         }
+
+        Ok(resolve_decision(&role_caps, &channel_overwrites))
     }
 
     // -------------------------
@@ -1396,8 +1981,8 @@ impl ControlRepo for PgControlRepo {
     ) -> ControlResult<()> {
         sqlx::query(
             r#"
-            INSERT INTO chat_messages (id, server_id, channel_id, author_user_id, text, attachments, created_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            INSERT INTO chat_messages (id, server_id, channel_id, author_user_id, text, attachments, kind, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
             "#,
         )
         .bind(msg.id.0)
@@ -1406,6 +1991,7 @@ impl ControlRepo for PgControlRepo {
         .bind(msg.author_user_id.0)
         .bind(&msg.text)
         .bind(&msg.attachments)
+        .bind(msg.kind.as_str())
         .bind(msg.created_at)
         .execute(&mut **tx)
         .await
@@ -1421,7 +2007,8 @@ impl ControlRepo for PgControlRepo {
     ) -> ControlResult<Option<ChatMessage>> {
         let row = sqlx::query(
             r#"
-            SELECT id, server_id, channel_id, author_user_id, text, attachments, created_at
+            SELECT id, server_id, channel_id, author_user_id, text, attachments, kind, created_at,
+                   edited_at, deleted_at
             FROM chat_messages
             WHERE server_id = $1 AND id = $2
             "#,
@@ -1432,15 +2019,220 @@ impl ControlRepo for PgControlRepo {
         .await
         .context("get chat message")?;
 
-        Ok(row.map(|r| ChatMessage {
-            id: MessageId(r.get::<Uuid, _>("id")),
-            server_id: ServerId(r.get::<Uuid, _>("server_id")),
-            channel_id: ChannelId(r.get::<Uuid, _>("channel_id")),
-            author_user_id: UserId(r.get::<Uuid, _>("author_user_id")),
-            text: r.get::<String, _>("text"),
-            attachments: r.get::<Json, _>("attachments"),
-            created_at: r.get::<DateTime<Utc>, _>("created_at"),
-        }))
+        Ok(row.as_ref().map(chat_message_from_row))
+    }
+
+    async fn list_messages(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        channel: ChannelId,
+        before: Option<MessageId>,
+        limit: i64,
+    ) -> ControlResult<Vec<ChatMessage>> {
+        // Keyset on (created_at, id) so messages sharing a timestamp are
+        // neither skipped nor repeated across pages.
+        let rows = sqlx::query(
+            r#"
+            SELECT id, server_id, channel_id, author_user_id, text, attachments, kind, created_at,
+                   edited_at, deleted_at
+            FROM chat_messages
+            WHERE server_id = $1
+              AND channel_id = $2
+              AND deleted_at IS NULL
+              AND ($3::uuid IS NULL
+                   OR (created_at, id) < (SELECT created_at, id FROM chat_messages WHERE id = $3))
+            ORDER BY created_at DESC, id DESC
+            LIMIT $4
+            "#,
+        )
+        .bind(server.0)
+        .bind(channel.0)
+        .bind(before.map(|m| m.0))
+        .bind(limit)
+        .fetch_all(&mut **tx)
+        .await
+        .context("list chat messages")?;
+
+        Ok(rows.iter().map(chat_message_from_row).collect())
+    }
+
+    async fn list_message_reactions(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        messages: &[MessageId],
+        viewer: UserId,
+    ) -> ControlResult<Vec<(MessageId, ReactionSummary)>> {
+        let ids = messages.iter().map(|m| m.0).collect::<Vec<_>>();
+        let rows = sqlx::query(
+            r#"
+            SELECT message_id, emoji, COUNT(*) AS count, BOOL_OR(user_id = $2) AS me
+            FROM message_reactions
+            WHERE message_id = ANY($1)
+            GROUP BY message_id, emoji
+            ORDER BY message_id, MIN(created_at)
+            "#,
+        )
+        .bind(&ids)
+        .bind(viewer.0)
+        .fetch_all(&mut **tx)
+        .await
+        .context("list message reactions")?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| {
+                (
+                    MessageId(r.get::<Uuid, _>("message_id")),
+                    ReactionSummary {
+                        emoji: r.get::<String, _>("emoji"),
+                        count: r.get::<i64, _>("count"),
+                        me: r.get::<bool, _>("me"),
+                    },
+                )
+            })
+            .collect())
+    }
+
+    async fn edit_chat_message(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        id: MessageId,
+        text: &str,
+        at: DateTime<Utc>,
+    ) -> ControlResult<bool> {
+        let updated = sqlx::query(
+            r#"
+            UPDATE chat_messages
+            SET text = $3, edited_at = $4
+            WHERE server_id = $1 AND id = $2 AND deleted_at IS NULL
+            "#,
+        )
+        .bind(server.0)
+        .bind(id.0)
+        .bind(text)
+        .bind(at)
+        .execute(&mut **tx)
+        .await
+        .context("edit chat message")?
+        .rows_affected();
+        Ok(updated > 0)
+    }
+
+    async fn delete_chat_message(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        server: ServerId,
+        id: MessageId,
+        at: DateTime<Utc>,
+    ) -> ControlResult<bool> {
+        let updated = sqlx::query(
+            r#"
+            UPDATE chat_messages
+            SET deleted_at = $3
+            WHERE server_id = $1 AND id = $2 AND deleted_at IS NULL
+            "#,
+        )
+        .bind(server.0)
+        .bind(id.0)
+        .bind(at)
+        .execute(&mut **tx)
+        .await
+        .context("delete chat message")?
+        .rows_affected();
+        Ok(updated > 0)
+    }
+
+    async fn add_message_reaction(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        message: MessageId,
+        user: UserId,
+        emoji: &str,
+    ) -> ControlResult<bool> {
+        let inserted = sqlx::query(
+            r#"
+            INSERT INTO message_reactions (message_id, user_id, emoji)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (message_id, user_id, emoji) DO NOTHING
+            "#,
+        )
+        .bind(message.0)
+        .bind(user.0)
+        .bind(emoji)
+        .execute(&mut **tx)
+        .await
+        .context("insert message reaction")?
+        .rows_affected();
+        Ok(inserted > 0)
+    }
+
+    async fn remove_message_reaction(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        message: MessageId,
+        user: UserId,
+        emoji: &str,
+    ) -> ControlResult<bool> {
+        let removed = sqlx::query(
+            r#"
+            DELETE FROM message_reactions
+            WHERE message_id = $1 AND user_id = $2 AND emoji = $3
+            "#,
+        )
+        .bind(message.0)
+        .bind(user.0)
+        .bind(emoji)
+        .execute(&mut **tx)
+        .await
+        .context("delete message reaction")?
+        .rows_affected();
+        Ok(removed > 0)
+    }
+
+    async fn count_message_reactions(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        message: MessageId,
+        emoji: &str,
+    ) -> ControlResult<i64> {
+        let count = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(*)
+            FROM message_reactions
+            WHERE message_id = $1 AND emoji = $2
+            "#,
+        )
+        .bind(message.0)
+        .bind(emoji)
+        .fetch_one(&mut **tx)
+        .await
+        .context("count message reactions")?;
+        Ok(count)
+    }
+
+    async fn last_chat_message_at(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        channel: ChannelId,
+        author: UserId,
+    ) -> ControlResult<Option<DateTime<Utc>>> {
+        let at = sqlx::query_scalar::<_, DateTime<Utc>>(
+            r#"
+            SELECT created_at
+            FROM chat_messages
+            WHERE channel_id = $1 AND author_user_id = $2 AND kind = 'user'
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(channel.0)
+        .bind(author.0)
+        .fetch_optional(&mut **tx)
+        .await
+        .context("last chat message at")?;
+        Ok(at)
     }
 
     async fn get_attachment(
@@ -1514,6 +2306,7 @@ impl ControlRepo for PgControlRepo {
               FROM outbox_events
               WHERE server_id = $1
                 AND published_at IS NULL
+                AND failed_at IS NULL
                 AND (claim_token IS NULL OR claimed_at < NOW() - INTERVAL '30 seconds')
               ORDER BY created_at ASC
               FOR UPDATE SKIP LOCKED
@@ -1523,7 +2316,7 @@ impl ControlRepo for PgControlRepo {
             SET claim_token = $3, claimed_at = NOW()
             FROM cte
             WHERE o.id = cte.id
-            RETURNING o.id, o.server_id, o.topic, o.payload_json
+            RETURNING o.id, o.server_id, o.topic, o.payload_json, o.created_at
             "#,
         )
         .bind(server.0)
@@ -1547,12 +2340,16 @@ impl ControlRepo for PgControlRepo {
             let payload_json = r
                 .try_get::<Json, _>("payload_json")
                 .context("decode outbox_events.payload_json as jsonb")?;
+            let created_at = r
+                .try_get::<DateTime<Utc>, _>("created_at")
+                .context("decode outbox_events.created_at")?;
 
             out.push(OutboxEventRow {
                 id: OutboxId(id),
                 server_id: ServerId(server_id),
                 topic,
                 payload_json,
+                created_at,
             });
         }
         Ok(out)
@@ -1582,6 +2379,40 @@ impl ControlRepo for PgControlRepo {
         Ok(())
     }
 
+    async fn record_outbox_failure(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: OutboxId,
+        claim_token: Uuid,
+        error: &str,
+        max_attempts: i32,
+    ) -> ControlResult<bool> {
+        let row = sqlx::query(
+            r#"
+            UPDATE outbox_events
+            SET attempts = attempts + 1,
+                last_error = $3,
+                failed_at = CASE WHEN attempts + 1 >= $4 THEN NOW() END
+            WHERE id = $1
+              AND claim_token = $2
+            RETURNING failed_at IS NOT NULL AS dead
+            "#,
+        )
+        .bind(id.0)
+        .bind(claim_token)
+        .bind(error)
+        .bind(max_attempts)
+        .fetch_optional(&mut **tx)
+        .await
+        .context("record outbox failure")?;
+        Ok(match row {
+            Some(row) => row
+                .try_get::<bool, _>("dead")
+                .context("decode outbox failure result")?,
+            None => false,
+        })
+    }
+
     // -------------------------
     // Audit
     // -------------------------
@@ -2074,3 +2905,64 @@ impl ControlRepo for PgControlRepo {
         Ok(exists)
     }
 }
+
+fn channel_from_row(r: &sqlx::postgres::PgRow) -> Channel {
+    Channel {
+        id: ChannelId(r.get::<Uuid, _>("id")),
+        server_id: ServerId(r.get::<Uuid, _>("server_id")),
+        name: r.get::<String, _>("name"),
+        parent_id: r.get::<Option<Uuid>, _>("parent_id").map(ChannelId),
+        max_members: r.get::<Option<i32>, _>("max_members"),
+        max_talkers: r.get::<Option<i32>, _>("max_talkers"),
+        channel_type: r.get::<i32, _>("channel_type"),
+        description: r.get::<String, _>("description"),
+        bitrate_bps: r.get::<i32, _>("bitrate_bps"),
+        opus_profile: r.get::<i32, _>("opus_profile"),
+        ephemeral: r.get::<bool, _>("ephemeral"),
+        created_by: r.get::<Option<Uuid>, _>("created_by").map(UserId),
+        max_message_length: r.get::<Option<i32>, _>("max_message_length"),
+        slow_mode_secs: r.get::<i32, _>("slow_mode_secs"),
+        announce_presence: r.get::<bool, _>("announce_presence"),
+        created_at: r.get::<DateTime<Utc>, _>("created_at"),
+        updated_at: r.get::<DateTime<Utc>, _>("updated_at"),
+    }
+}
+
+fn member_from_row(r: &sqlx::postgres::PgRow) -> Member {
+    Member {
+        channel_id: ChannelId(r.get::<Uuid, _>("channel_id")),
+        user_id: UserId(r.get::<Uuid, _>("user_id")),
+        display_name: r.get::<String, _>("display_name"),
+        muted: r.get::<bool, _>("muted"),
+        deafened: r.get::<bool, _>("deafened"),
+        joined_at: r.get::<DateTime<Utc>, _>("joined_at"),
+        custom_status_text: r.get::<String, _>("custom_status_text"),
+        custom_status_emoji: r.get::<String, _>("custom_status_emoji"),
+    }
+}
+
+fn chat_message_from_row(r: &sqlx::postgres::PgRow) -> ChatMessage {
+    ChatMessage {
+        id: MessageId(r.get::<Uuid, _>("id")),
+        server_id: ServerId(r.get::<Uuid, _>("server_id")),
+        channel_id: ChannelId(r.get::<Uuid, _>("channel_id")),
+        author_user_id: UserId(r.get::<Uuid, _>("author_user_id")),
+        text: r.get::<String, _>("text"),
+        attachments: r.get::<Json, _>("attachments"),
+        kind: ChatMessageKind::from_str(&r.get::<String, _>("kind"))
+            .unwrap_or(ChatMessageKind::User),
+        created_at: r.get::<DateTime<Utc>, _>("created_at"),
+        edited_at: r.get::<Option<DateTime<Utc>>, _>("edited_at"),
+        deleted_at: r.get::<Option<DateTime<Utc>>, _>("deleted_at"),
+    }
+}
+
+fn read_marker_from_row(r: &sqlx::postgres::PgRow) -> ReadMarker {
+    ReadMarker {
+        user_id: UserId(r.get("user_id")),
+        channel_id: ChannelId(r.get("channel_id")),
+        message_id: MessageId(r.get("message_id")),
+        message_at: r.get("message_at"),
+        updated_at: r.get("updated_at"),
+    }
+}
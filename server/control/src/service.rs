@@ -1,20 +1,41 @@
 use chrono::Utc;
 use serde_json::json;
+use std::collections::HashMap;
 use tracing::debug;
 use uuid::Uuid;
 
 use crate::{
-    errors::{ControlError, ControlResult},
+    config::ControlConfig,
+    errors::{ControlError, ControlResult, CHANNEL_FULL, SLOW_MODE},
     ids::{ChannelId, MessageId, OutboxId, ServerId, UserId},
     model::{
-        AssetUploadSession, AuditEntry, Channel, ChannelCreate, ChatMessage, JoinChannel, Member,
-        OutboxEvent, OutboxEventRow, PermAuditRow, PermChannelOverrideRecord, PermRoleRecord,
-        PermUserSummaryRecord, PermissionRequest, SendMessage, UserProfileRow,
+        ActiveBan, AssetUploadSession, AuditEntry, Channel, ChannelCreate, ChannelNotificationPref,
+        ChannelNotificationPrefRecord, ChatHistoryEntry, ChatMessage, ChatMessageKind, EditMessage,
+        JoinChannel, Member, MemberCursor, OutboxEvent, OutboxEventRow, PermAuditRow,
+        PermChannelOverrideRecord, PermRoleRecord, PermUserSummaryRecord, PermissionRequest,
+        ReadMarker, SendMessage, UserProfileRow,
     },
     perms::{Capability, Decision},
     repo::ControlRepo,
 };
 
+/// Server-wide cap on chat text, in bytes. Channels may only lower it.
+pub const MAX_MESSAGE_LENGTH: usize = 2000;
+/// Cap on a reaction's emoji, in bytes; enough for any ZWJ sequence.
+pub const MAX_REACTION_EMOJI_LENGTH: usize = 64;
+/// Longest slow-mode interval an admin can set (6 hours).
+pub const MAX_SLOW_MODE_SECS: i32 = 6 * 60 * 60;
+/// Bounds on a voice channel's Opus bitrate; 510 kbps is Opus's own ceiling.
+pub const MIN_CHANNEL_BITRATE_BPS: i32 = 8_000;
+pub const MAX_CHANNEL_BITRATE_BPS: i32 = 510_000;
+/// Members per page of a channel's member list, and the most a join response
+/// carries inline; the rest are fetched with `list_members_page`.
+pub const MEMBER_PAGE_SIZE: usize = 100;
+/// Messages per page of channel history when the caller doesn't ask for a
+/// size, and the most a single page may hold.
+pub const HISTORY_PAGE_SIZE: usize = 50;
+pub const MAX_HISTORY_PAGE_SIZE: usize = 100;
+
 #[derive(Clone, Copy, Debug)]
 pub struct RequestContext {
     pub server_id: ServerId,
@@ -25,11 +46,20 @@ pub struct RequestContext {
 #[derive(Clone)]
 pub struct ControlService<R: ControlRepo> {
     repo: R,
+    config: ControlConfig,
 }
 
 impl<R: ControlRepo> ControlService<R> {
     pub fn new(repo: R) -> Self {
-        Self { repo }
+        Self {
+            repo,
+            config: ControlConfig::default(),
+        }
+    }
+
+    pub fn with_config(mut self, config: ControlConfig) -> Self {
+        self.config = config;
+        self
     }
 
     #[inline]
@@ -37,6 +67,11 @@ impl<R: ControlRepo> ControlService<R> {
         &self.repo
     }
 
+    #[inline]
+    pub fn read_receipts_enabled(&self) -> bool {
+        self.config.read_receipts
+    }
+
     // -------------------------------------------------------------------------
     // Channels
     // -------------------------------------------------------------------------
@@ -58,8 +93,56 @@ impl<R: ControlRepo> ControlService<R> {
         self.require(&mut tx, ctx, None, None, Capability::CreateChannel)
             .await?;
 
+        <R as ControlRepo>::lock_channel_creation(&self.repo, &mut tx, ctx.server_id).await?;
+        let (server_channels, user_channels) =
+            <R as ControlRepo>::count_channels(&self.repo, &mut tx, ctx.server_id, ctx.user_id)
+                .await?;
+        if let Some(limit) =
+            channel_creation_limit(&self.config, ctx.is_admin, server_channels, user_channels)
+        {
+            // Keep a record of the rejection even though nothing is created.
+            <R as ControlRepo>::insert_audit(
+                &self.repo,
+                &mut tx,
+                &AuditEntry::new(
+                    ctx.server_id,
+                    Some(ctx.user_id),
+                    "channel.create_rejected",
+                    "server",
+                    ctx.server_id.0.to_string(),
+                    json!({
+                        "name": name,
+                        "reason": limit,
+                        "server_channels": server_channels,
+                        "user_channels": user_channels,
+                        "max_channels_per_server": self.config.max_channels_per_server,
+                        "max_channels_per_user": self.config.max_channels_per_user,
+                    }),
+                ),
+            )
+            .await?;
+            tx.commit().await?;
+            return Err(ControlError::ResourceExhausted(limit));
+        }
+
+        if self.config.unique_channel_names
+            && <R as ControlRepo>::channel_name_taken(
+                &self.repo,
+                &mut tx,
+                ctx.server_id,
+                req.parent_id,
+                name,
+                None,
+            )
+            .await?
+        {
+            return Err(ControlError::AlreadyExists("channel name"));
+        }
+
         let now = Utc::now();
-        let bitrate_bps = req.bitrate_bps.clamp(8_000, 510_000);
+        let bitrate_bps = req
+            .bitrate_bps
+            .clamp(MIN_CHANNEL_BITRATE_BPS, MAX_CHANNEL_BITRATE_BPS);
         let opus_profile = match req.opus_profile {
             1 | 2 => req.opus_profile,
             _ => 1,
@@ -75,6 +158,11 @@ impl<R: ControlRepo> ControlService<R> {
             description: req.description,
             bitrate_bps,
             opus_profile,
+            ephemeral: req.ephemeral,
+            created_by: Some(ctx.user_id),
+            max_message_length: None,
+            slow_mode_secs: 0,
+            announce_presence: false,
             created_at: now,
             updated_at: now,
         };
@@ -99,6 +187,7 @@ impl<R: ControlRepo> ControlService<R> {
                     "bitrate_bps": ch.bitrate_bps,
                     "opus_profile": ch.opus_profile,
                     "max_members": ch.max_members,
+                    "ephemeral": ch.ephemeral,
                 }),
             ),
         )
@@ -179,10 +268,29 @@ impl<R: ControlRepo> ControlService<R> {
             ctx,
             Some(channel_id),
             None,
-            Capability::CreateChannel,
+            Capability::ManageChannel,
         )
         .await?;
 
+        if self.config.unique_channel_names {
+            let current =
+                <R as ControlRepo>::get_channel(&self.repo, &mut tx, ctx.server_id, channel_id)
+                    .await?
+                    .ok_or(ControlError::NotFound("channel"))?;
+            if <R as ControlRepo>::channel_name_taken(
+                &self.repo,
+                &mut tx,
+                ctx.server_id,
+                current.parent_id,
+                name,
+                Some(channel_id),
+            )
+            .await?
+            {
+                return Err(ControlError::AlreadyExists("channel name"));
+            }
+        }
+
         let renamed = <R as ControlRepo>::rename_channel(
             &self.repo,
             &mut tx,
@@ -224,6 +332,9 @@ impl<R: ControlRepo> ControlService<R> {
                     "description": renamed.description,
                     "bitrate_bps": renamed.bitrate_bps,
                     "opus_profile": renamed.opus_profile,
+                    "max_message_length": renamed.max_message_length,
+                    "slow_mode_secs": renamed.slow_mode_secs,
+                    "announce_presence": renamed.announce_presence,
                     "updated_at": renamed.updated_at,
                 }),
             },
@@ -250,6 +361,7 @@ impl<R: ControlRepo> ControlService<R> {
         if name.len() > 64 {
             return Err(ControlError::InvalidArgument("channel name too long"));
         }
+        let bitrate_bps = bitrate_bps.clamp(MIN_CHANNEL_BITRATE_BPS, MAX_CHANNEL_BITRATE_BPS);
 
         let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
         self.require(
@@ -304,6 +416,100 @@ impl<R: ControlRepo> ControlService<R> {
                     "description": updated.description,
                     "bitrate_bps": updated.bitrate_bps,
                     "opus_profile": updated.opus_profile,
+                    "max_message_length": updated.max_message_length,
+                    "slow_mode_secs": updated.slow_mode_secs,
+                    "announce_presence": updated.announce_presence,
+                    "updated_at": updated.updated_at,
+                }),
+            },
+        )
+        .await?;
+
+        tx.commit().await?;
+        Ok(updated)
+    }
+
+    /// Set a channel's chat policy. `max_message_length` of `None` falls back
+    /// to [`MAX_MESSAGE_LENGTH`]; `slow_mode_secs` of 0 turns slow mode off;
+    /// `announce_presence` posts a system message on every join and leave.
+    pub async fn set_channel_chat_limits(
+        &self,
+        ctx: &RequestContext,
+        channel_id: ChannelId,
+        max_message_length: Option<i32>,
+        slow_mode_secs: i32,
+        announce_presence: bool,
+    ) -> ControlResult<Channel> {
+        if max_message_length.is_some_and(|max| max < 1 || max as usize > MAX_MESSAGE_LENGTH) {
+            return Err(ControlError::InvalidArgument(
+                "max_message_length out of range",
+            ));
+        }
+        if !(0..=MAX_SLOW_MODE_SECS).contains(&slow_mode_secs) {
+            return Err(ControlError::InvalidArgument("slow_mode_secs out of range"));
+        }
+
+        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
+        self.require(
+            &mut tx,
+            ctx,
+            Some(channel_id),
+            None,
+            Capability::ManageChannel,
+        )
+        .await?;
+
+        let updated = <R as ControlRepo>::set_channel_chat_limits(
+            &self.repo,
+            &mut tx,
+            ctx.server_id,
+            channel_id,
+            max_message_length,
+            slow_mode_secs,
+            announce_presence,
+        )
+        .await?
+        .ok_or(ControlError::NotFound("channel"))?;
+
+        <R as ControlRepo>::insert_audit(
+            &self.repo,
+            &mut tx,
+            &AuditEntry::new(
+                ctx.server_id,
+                Some(ctx.user_id),
+                "channel.chat_limits",
+                "channel",
+                updated.id.0.to_string(),
+                json!({
+                    "max_message_length": updated.max_message_length,
+                    "slow_mode_secs": updated.slow_mode_secs,
+                    "announce_presence": updated.announce_presence,
+                }),
+            ),
+        )
+        .await?;
+
+        // Reuses the rename push: it already carries the full channel info.
+        <R as ControlRepo>::insert_outbox(
+            &self.repo,
+            &mut tx,
+            &OutboxEvent {
+                id: OutboxId(Uuid::new_v4()),
+                server_id: ctx.server_id,
+                topic: "channel.renamed".to_string(),
+                payload_json: json!({
+                    "server_id": ctx.server_id.0,
+                    "channel_id": updated.id.0,
+                    "name": updated.name,
+                    "parent_channel_id": updated.parent_id.map(|p| p.0),
+                    "max_members": updated.max_members,
+                    "channel_type": updated.channel_type,
+                    "description": updated.description,
+                    "bitrate_bps": updated.bitrate_bps,
+                    "opus_profile": updated.opus_profile,
+                    "max_message_length": updated.max_message_length,
+                    "slow_mode_secs": updated.slow_mode_secs,
+                    "announce_presence": updated.announce_presence,
                     "updated_at": updated.updated_at,
                 }),
             },
@@ -384,6 +590,96 @@ impl<R: ControlRepo> ControlService<R> {
         Ok(descendants)
     }
 
+    /// Delete ephemeral channels that have had no members for at least
+    /// `idle_for`. Channels that still have sub-channels are left alone.
+    /// Returns the deleted ids.
+    pub async fn sweep_idle_ephemeral_channels(
+        &self,
+        server_id: ServerId,
+        idle_for: chrono::Duration,
+    ) -> ControlResult<Vec<ChannelId>> {
+        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
+        let now = Utc::now();
+        let candidates = <R as ControlRepo>::list_stale_ephemeral_channels(
+            &self.repo,
+            &mut tx,
+            server_id,
+            now - idle_for,
+        )
+        .await?;
+
+        let mut deleted = Vec::new();
+        for channel_id in candidates {
+            // The row lock keeps a concurrent capacity-checked join from
+            // landing between the count and the delete.
+            if !<R as ControlRepo>::lock_channel(&self.repo, &mut tx, server_id, channel_id).await?
+            {
+                continue;
+            }
+            let Some(ch) =
+                <R as ControlRepo>::get_channel(&self.repo, &mut tx, server_id, channel_id).await?
+            else {
+                continue;
+            };
+            let members =
+                <R as ControlRepo>::count_members(&self.repo, &mut tx, server_id, channel_id)
+                    .await?;
+            if !ephemeral_channel_is_idle(&ch, members, now, idle_for) {
+                continue;
+            }
+            let descendants = <R as ControlRepo>::list_channel_descendants(
+                &self.repo, &mut tx, server_id, channel_id,
+            )
+            .await?;
+            if descendants.len() > 1 {
+                continue;
+            }
+            if !<R as ControlRepo>::delete_channel(&self.repo, &mut tx, server_id, channel_id)
+                .await?
+            {
+                continue;
+            }
+
+            <R as ControlRepo>::insert_audit(
+                &self.repo,
+                &mut tx,
+                &AuditEntry::new(
+                    server_id,
+                    None,
+                    "channel.delete_idle",
+                    "channel",
+                    channel_id.0.to_string(),
+                    json!({
+                        "name": ch.name,
+                        "idle_secs": (now - ch.updated_at).num_seconds(),
+                    }),
+                ),
+            )
+            .await?;
+
+            <R as ControlRepo>::insert_outbox(
+                &self.repo,
+                &mut tx,
+                &OutboxEvent {
+                    id: OutboxId(Uuid::new_v4()),
+                    server_id,
+                    topic: "channel.deleted".to_string(),
+                    payload_json: json!({
+                        "server_id": server_id.0,
+                        "channel_id": channel_id.0,
+                        "updated_at": now,
+                    }),
+                },
+            )
+            .await?;
+            debug!(server_id=%server_id.0, channel_id=%channel_id.0, topic="channel.deleted", "swept idle ephemeral channel");
+            deleted.push(channel_id);
+        }
+
+        tx.commit().await?;
+        Ok(deleted)
+    }
+
     // -------------------------------------------------------------------------
     // Membership
     // -------------------------------------------------------------------------
@@ -410,6 +706,17 @@ impl<R: ControlRepo> ControlService<R> {
             Capability::JoinChannel,
         )
         .await?;
+        if <R as ControlRepo>::is_banned(
+            &self.repo,
+            &mut tx,
+            ctx.server_id,
+            ctx.user_id,
+            Utc::now(),
+        )
+        .await?
+        {
+            return Err(ControlError::PermissionDenied("banned"));
+        }
 
         // Ensure channel exists
         let ch =
@@ -417,17 +724,35 @@ impl<R: ControlRepo> ControlService<R> {
                 .await?
                 .ok_or(ControlError::NotFound("channel"))?;
 
-        // Optional capacity check (if your repo supports count_members)
         if let Some(max) = ch.max_members {
-            let cur = <R as ControlRepo>::count_members(
+            // Count-then-insert is only atomic while we hold the channel row:
+            // without the lock, joins racing through different gateways could
+            // both see the last free slot.
+            if !<R as ControlRepo>::lock_channel(&self.repo, &mut tx, ctx.server_id, req.channel_id)
+                .await?
+            {
+                return Err(ControlError::NotFound("channel"));
+            }
+            let rejoining = <R as ControlRepo>::get_member(
                 &self.repo,
                 &mut tx,
                 ctx.server_id,
                 req.channel_id,
+                ctx.user_id,
             )
-            .await?;
-            if cur >= max as i64 {
-                return Err(ControlError::ResourceExhausted("channel full"));
+            .await?
+            .is_some();
+            if !rejoining {
+                let cur = <R as ControlRepo>::count_members(
+                    &self.repo,
+                    &mut tx,
+                    ctx.server_id,
+                    req.channel_id,
+                )
+                .await?;
+                if cur >= max as i64 {
+                    return Err(ControlError::ResourceExhausted(CHANNEL_FULL));
+                }
             }
         }
 
@@ -447,9 +772,22 @@ impl<R: ControlRepo> ControlService<R> {
             channel_id = %req.channel_id.0,
             user_id = %ctx.user_id.0,
             display_name = %m.display_name,
-            "join_channel member upsert"
+            "join_channel member insert"
         );
-        <R as ControlRepo>::upsert_member(&self.repo, &mut tx, ctx.server_id, &m).await?;
+        if !<R as ControlRepo>::insert_member(&self.repo, &mut tx, ctx.server_id, &m).await? {
+            // Re-join of a channel the user is already in (e.g. a second
+            // click): nothing changed, so no audit row or member_joined event.
+            debug!(server_id=%ctx.server_id.0, channel_id=%req.channel_id.0, user_id=%ctx.user_id.0, "join_channel already a member");
+            let members = <R as ControlRepo>::list_members(
+                &self.repo,
+                &mut tx,
+                ctx.server_id,
+                req.channel_id,
+            )
+            .await?;
+            tx.commit().await?;
+            return Ok(members);
+        }
 
         <R as ControlRepo>::insert_audit(
             &self.repo,
@@ -495,6 +833,8 @@ impl<R: ControlRepo> ControlService<R> {
         .await?;
 
         debug!(server_id=%ctx.server_id.0, channel_id=%req.channel_id.0, user_id=%ctx.user_id.0, topic="presence.member_joined", "produced outbox event");
+        self.insert_presence_announcement(&mut tx, ctx.server_id, &m, true)
+            .await?;
 
         let members =
             <R as ControlRepo>::list_members(&self.repo, &mut tx, ctx.server_id, req.channel_id)
@@ -508,6 +848,8 @@ impl<R: ControlRepo> ControlService<R> {
                 "join_channel member listed"
             );
         }
+        self.insert_channel_state_refresh(&mut tx, ctx.server_id, req.channel_id)
+            .await?;
         tx.commit().await?;
         debug!(server_id=%ctx.server_id.0, channel_id=%req.channel_id.0, user_id=%ctx.user_id.0, "join_channel transaction committed");
         Ok(members)
@@ -529,7 +871,7 @@ impl<R: ControlRepo> ControlService<R> {
             Capability::JoinChannel,
         )
         .await?;
-        let _m = <R as ControlRepo>::get_member(
+        let m = <R as ControlRepo>::get_member(
             &self.repo,
             &mut tx,
             ctx.server_id,
@@ -547,6 +889,8 @@ impl<R: ControlRepo> ControlService<R> {
             ctx.user_id,
         )
         .await?;
+        <R as ControlRepo>::touch_ephemeral_channel(&self.repo, &mut tx, ctx.server_id, channel_id)
+            .await?;
 
         <R as ControlRepo>::insert_audit(
             &self.repo,
@@ -576,6 +920,10 @@ impl<R: ControlRepo> ControlService<R> {
             },
         )
         .await?;
+        self.insert_presence_announcement(&mut tx, ctx.server_id, &m, false)
+            .await?;
+        self.insert_channel_state_refresh(&mut tx, ctx.server_id, channel_id)
+            .await?;
 
         tx.commit().await?;
         Ok(())
@@ -593,6 +941,14 @@ impl<R: ControlRepo> ControlService<R> {
         .await?;
 
         for channel_id in &channels {
+            let member = <R as ControlRepo>::get_member(
+                &self.repo,
+                &mut tx,
+                ctx.server_id,
+                *channel_id,
+                ctx.user_id,
+            )
+            .await?;
             <R as ControlRepo>::delete_member(
                 &self.repo,
                 &mut tx,
@@ -601,6 +957,13 @@ impl<R: ControlRepo> ControlService<R> {
                 ctx.user_id,
             )
             .await?;
+            <R as ControlRepo>::touch_ephemeral_channel(
+                &self.repo,
+                &mut tx,
+                ctx.server_id,
+                *channel_id,
+            )
+            .await?;
 
             <R as ControlRepo>::insert_audit(
                 &self.repo,
@@ -630,6 +993,12 @@ impl<R: ControlRepo> ControlService<R> {
                 },
             )
             .await?;
+            if let Some(member) = &member {
+                self.insert_presence_announcement(&mut tx, ctx.server_id, member, false)
+                    .await?;
+            }
+            self.insert_channel_state_refresh(&mut tx, ctx.server_id, *channel_id)
+                .await?;
         }
 
         tx.commit().await?;
@@ -783,6 +1152,9 @@ impl<R: ControlRepo> ControlService<R> {
         Ok(m)
     }
 
+    /// Force `target_user` out of `channel_id`. Their client learns of it from
+    /// `moderation.user_kicked`; the `presence.member_left` that follows is
+    /// what drops their voice routing on every gateway.
     pub async fn kick_member(
         &self,
         ctx: &RequestContext,
@@ -791,12 +1163,31 @@ impl<R: ControlRepo> ControlService<R> {
         reason: Option<String>,
     ) -> ControlResult<()> {
         let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
-        <R as ControlRepo>::delete_member(
+        self.require(
+            &mut tx,
+            ctx,
+            Some(channel_id),
+            Some(target_user),
+            Capability::ModerateMembers,
+        )
+        .await?;
+        <R as ControlRepo>::get_member(&self.repo, &mut tx, ctx.server_id, channel_id, target_user)
+            .await?
+            .ok_or(ControlError::NotFound("member"))?;
+
+        self.remove_member(&mut tx, ctx.server_id, channel_id, target_user)
+            .await?;
+        <R as ControlRepo>::insert_audit(
             &self.repo,
             &mut tx,
-            ctx.server_id,
-            channel_id,
-            target_user,
+            &AuditEntry::new(
+                ctx.server_id,
+                Some(ctx.user_id),
+                "moderation.kick",
+                "user",
+                target_user.0.to_string(),
+                json!({ "channel_id": channel_id.0, "reason": reason }),
+            ),
         )
         .await?;
         <R as ControlRepo>::insert_outbox(
@@ -815,156 +1206,104 @@ impl<R: ControlRepo> ControlService<R> {
             },
         )
         .await?;
-        <R as ControlRepo>::insert_outbox(
-            &self.repo,
-            &mut tx,
-            &OutboxEvent {
-                id: OutboxId(Uuid::new_v4()),
-                server_id: ctx.server_id,
-                topic: "presence.member_left".to_string(),
-                payload_json: json!({
-                    "channel_id": channel_id.0,
-                    "user_id": target_user.0
-                }),
-            },
-        )
-        .await?;
         tx.commit().await?;
         Ok(())
     }
 
-    pub async fn poke_user(
+    /// Move `target_user` from `from_channel` into `to_channel` in one
+    /// transaction. Server mute/deafen carry over; the destination's member
+    /// limit still applies. Returns the new membership.
+    pub async fn move_member(
         &self,
         ctx: &RequestContext,
+        from_channel: ChannelId,
         target_user: UserId,
-        from_display_name: &str,
-        message: String,
-    ) -> ControlResult<()> {
+        to_channel: ChannelId,
+    ) -> ControlResult<Member> {
+        if from_channel == to_channel {
+            return Err(ControlError::InvalidArgument("already in target channel"));
+        }
+
         let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
-        <R as ControlRepo>::insert_outbox(
+        for channel_id in [from_channel, to_channel] {
+            self.require(
+                &mut tx,
+                ctx,
+                Some(channel_id),
+                Some(target_user),
+                Capability::ModerateMembers,
+            )
+            .await?;
+        }
+        let current = <R as ControlRepo>::get_member(
             &self.repo,
             &mut tx,
-            &OutboxEvent {
-                id: OutboxId(Uuid::new_v4()),
-                server_id: ctx.server_id,
-                topic: "poke.received".to_string(),
-                payload_json: json!({
-                    "target_user_id": target_user.0,
-                    "from_user_id": ctx.user_id.0,
-                    "from_display_name": from_display_name,
-                    "message": message,
-                }),
-            },
-        )
-        .await?;
-        tx.commit().await?;
-        Ok(())
-    }
-
-    // -------------------------------------------------------------------------
-    // Chat
-    // -------------------------------------------------------------------------
-
-    pub async fn send_message(
-        &self,
-        ctx: &RequestContext,
-        msg: SendMessage,
-    ) -> ControlResult<ChatMessage> {
-        let text = msg.text.trim();
-        if text.len() > 2000 {
-            return Err(ControlError::InvalidArgument("message too long"));
-        }
-
-        let requested_attachments = msg
-            .attachments
-            .unwrap_or_else(|| json!([]))
-            .as_array()
-            .cloned()
-            .ok_or(ControlError::InvalidArgument(
-                "attachments must be an array",
-            ))?;
-
-        if text.is_empty() && requested_attachments.is_empty() {
-            return Err(ControlError::InvalidArgument(
-                "message text and attachments empty",
-            ));
-        }
-
-        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
-        self.require(
-            &mut tx,
-            ctx,
-            Some(msg.channel_id),
-            None,
-            Capability::SendMessage,
-        )
-        .await?;
-
-        // Ensure member exists
-        let _m = <R as ControlRepo>::get_member(
-            &self.repo,
-            &mut tx,
-            ctx.server_id,
-            msg.channel_id,
-            ctx.user_id,
+            ctx.server_id,
+            from_channel,
+            target_user,
         )
         .await?
         .ok_or(ControlError::NotFound("member"))?;
+        let ch = <R as ControlRepo>::get_channel(&self.repo, &mut tx, ctx.server_id, to_channel)
+            .await?
+            .ok_or(ControlError::NotFound("channel"))?;
 
-        let mut canonical_attachments = Vec::with_capacity(requested_attachments.len());
-        for requested in requested_attachments {
-            let Some(asset_id) = requested
-                .get("asset_id")
-                .and_then(serde_json::Value::as_str)
-                .filter(|s| !s.is_empty())
-            else {
-                return Err(ControlError::InvalidArgument("attachment asset_id missing"));
-            };
-            let parsed_asset_id = Uuid::parse_str(asset_id)
-                .map_err(|_| ControlError::InvalidArgument("invalid attachment asset_id"))?;
-
-            let attachment =
-                <R as ControlRepo>::get_attachment(&self.repo, &mut tx, parsed_asset_id)
-                    .await?
-                    .ok_or(ControlError::NotFound("attachment"))?;
-
-            if attachment.server_id != ctx.server_id {
-                return Err(ControlError::PermissionDenied("attachment server mismatch"));
-            }
-            if attachment.channel_id != msg.channel_id {
-                return Err(ControlError::PermissionDenied(
-                    "attachment channel mismatch",
-                ));
-            }
-            if attachment.uploader_user_id != ctx.user_id {
-                return Err(ControlError::PermissionDenied(
-                    "attachment uploader mismatch",
-                ));
+        if let Some(max) = ch.max_members {
+            // Same lock as join_channel, so a move can't race a join into
+            // the last free slot.
+            if !<R as ControlRepo>::lock_channel(&self.repo, &mut tx, ctx.server_id, to_channel)
+                .await?
+            {
+                return Err(ControlError::NotFound("channel"));
             }
-            if attachment.quarantined {
-                return Err(ControlError::FailedPrecondition("attachment quarantined"));
+            let cur =
+                <R as ControlRepo>::count_members(&self.repo, &mut tx, ctx.server_id, to_channel)
+                    .await?;
+            if cur >= max as i64 {
+                return Err(ControlError::ResourceExhausted(CHANNEL_FULL));
             }
-
-            canonical_attachments.push(json!({
-                "asset_id": attachment.id,
-                "filename": attachment.filename,
-                "mime_type": attachment.content_type,
-                "size_bytes": attachment.size_bytes,
-                "sha256": attachment.sha256.unwrap_or_default(),
-            }));
         }
 
-        let rec = ChatMessage {
-            id: MessageId(Uuid::new_v4()),
-            server_id: ctx.server_id,
-            channel_id: msg.channel_id,
-            author_user_id: ctx.user_id,
-            text: text.to_string(),
-            attachments: json!(canonical_attachments),
-            created_at: Utc::now(),
-        };
+        self.remove_member(&mut tx, ctx.server_id, from_channel, target_user)
+            .await?;
+        self.insert_presence_announcement(&mut tx, ctx.server_id, &current, false)
+            .await?;
 
-        <R as ControlRepo>::insert_chat_message(&self.repo, &mut tx, &rec).await?;
+        let m = Member {
+            channel_id: to_channel,
+            joined_at: Utc::now(),
+            ..current
+        };
+        if !<R as ControlRepo>::insert_member(&self.repo, &mut tx, ctx.server_id, &m).await? {
+            return Err(ControlError::InvalidArgument("already in target channel"));
+        }
+        let away_message =
+            <R as ControlRepo>::get_user_profile(&self.repo, &mut tx, target_user, ctx.server_id)
+                .await?
+                .map(|profile| profile.custom_status_text)
+                .unwrap_or_default();
+        <R as ControlRepo>::insert_outbox(
+            &self.repo,
+            &mut tx,
+            &OutboxEvent {
+                id: OutboxId(Uuid::new_v4()),
+                server_id: ctx.server_id,
+                topic: "presence.member_joined".to_string(),
+                payload_json: json!({
+                    "channel_id": to_channel.0,
+                    "user_id": target_user.0,
+                    "display_name": m.display_name,
+                    "muted": m.muted,
+                    "deafened": m.deafened,
+                    "away_message": away_message,
+                }),
+            },
+        )
+        .await?;
+        self.insert_presence_announcement(&mut tx, ctx.server_id, &m, true)
+            .await?;
+        self.insert_channel_state_refresh(&mut tx, ctx.server_id, to_channel)
+            .await?;
 
         <R as ControlRepo>::insert_audit(
             &self.repo,
@@ -972,110 +1311,100 @@ impl<R: ControlRepo> ControlService<R> {
             &AuditEntry::new(
                 ctx.server_id,
                 Some(ctx.user_id),
-                "chat.message",
-                "channel",
-                msg.channel_id.0.to_string(),
-                json!({ "message_id": rec.id.0, "text_len": rec.text.len() }),
+                "moderation.move",
+                "user",
+                target_user.0.to_string(),
+                json!({
+                    "from_channel_id": from_channel.0,
+                    "to_channel_id": to_channel.0,
+                }),
             ),
         )
         .await?;
-
         <R as ControlRepo>::insert_outbox(
             &self.repo,
             &mut tx,
             &OutboxEvent {
                 id: OutboxId(Uuid::new_v4()),
                 server_id: ctx.server_id,
-                topic: "chat.message_posted".to_string(),
+                topic: "moderation.user_moved".to_string(),
                 payload_json: json!({
-                    "message_id": rec.id.0,
-                    "channel_id": rec.channel_id.0,
-                    "author_user_id": rec.author_user_id.0,
-                    "text": rec.text,
-                    "attachments": rec.attachments,
-                    "created_at": rec.created_at,
+                    "from_channel_id": from_channel.0,
+                    "to_channel_id": to_channel.0,
+                    "target_user_id": target_user.0,
+                    "actor_user_id": ctx.user_id.0,
                 }),
             },
         )
         .await?;
-
-        tx.commit().await?;
-        Ok(rec)
-    }
-
-    // -------------------------------------------------------------------------
-    // Admin permissions RPCs
-    // -------------------------------------------------------------------------
-
-    pub async fn perm_list_roles(
-        &self,
-        ctx: &RequestContext,
-    ) -> ControlResult<Vec<PermRoleRecord>> {
-        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
-        self.require(&mut tx, ctx, None, None, Capability::ManageRoles)
-            .await?;
-        let roles = <R as ControlRepo>::perm_list_roles(&self.repo, &mut tx, ctx.server_id).await?;
         tx.commit().await?;
-        Ok(roles)
-    }
-
-    pub async fn perm_list_users(
-        &self,
-        ctx: &RequestContext,
-    ) -> ControlResult<(Vec<PermUserSummaryRecord>, i32, bool)> {
-        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
-        self.require(&mut tx, ctx, None, None, Capability::ManageRoles)
-            .await?;
-        let users = <R as ControlRepo>::perm_list_users(&self.repo, &mut tx, ctx.server_id).await?;
-        let editor_highest_role_position = self.actor_max_role_position(&mut tx, ctx).await?;
-        tx.commit().await?;
-        Ok((users, editor_highest_role_position, ctx.is_admin))
+        Ok(m)
     }
 
-    pub async fn perm_upsert_role(
+    /// Ban `target_user` from the server and pull them out of every channel.
+    /// `duration_secs` 0 is permanent. `channel_id` is where the action was
+    /// taken and where the `moderation.user_banned` push is routed.
+    pub async fn ban_member(
         &self,
         ctx: &RequestContext,
-        role_id: Option<&str>,
-        name: &str,
-        color: i32,
-        position: i32,
-    ) -> ControlResult<PermRoleRecord> {
-        if name.trim().is_empty() {
-            return Err(ControlError::InvalidArgument("role name empty"));
-        }
-        if name.trim().eq_ignore_ascii_case("owner") && !ctx.is_admin {
-            return Err(ControlError::PermissionDenied(
-                "owner role editable only by server owner",
-            ));
+        channel_id: ChannelId,
+        target_user: UserId,
+        reason: Option<String>,
+        duration_secs: u32,
+    ) -> ControlResult<()> {
+        if target_user == ctx.user_id {
+            return Err(ControlError::InvalidArgument("cannot ban yourself"));
         }
+        let expires_at = (duration_secs > 0)
+            .then(|| Utc::now() + chrono::Duration::seconds(i64::from(duration_secs)));
+
         let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
-        self.require(&mut tx, ctx, None, None, Capability::ManageRoles)
-            .await?;
-        if let Some(existing_role_id) = role_id {
-            let _ = self
-                .require_manageable_role(&mut tx, ctx, existing_role_id)
-                .await?;
-        }
-        let role = <R as ControlRepo>::perm_upsert_role(
+        // The ban is server-wide, so it is checked at server scope: a
+        // channel-level grant must not let a moderator ban everywhere.
+        self.require(
+            &mut tx,
+            ctx,
+            None,
+            Some(target_user),
+            Capability::ModerateMembers,
+        )
+        .await?;
+        <R as ControlRepo>::upsert_ban(
             &self.repo,
             &mut tx,
             ctx.server_id,
-            role_id,
-            name.trim(),
-            color,
-            position,
+            target_user,
+            ctx.user_id,
+            reason.as_deref().unwrap_or_default(),
+            expires_at,
+        )
+        .await?;
+        let channels = <R as ControlRepo>::list_member_channels_for_user(
+            &self.repo,
+            &mut tx,
+            ctx.server_id,
+            target_user,
         )
         .await?;
+        for member_channel in channels {
+            self.remove_member(&mut tx, ctx.server_id, member_channel, target_user)
+                .await?;
+        }
+
         <R as ControlRepo>::insert_audit(
             &self.repo,
             &mut tx,
             &AuditEntry::new(
                 ctx.server_id,
                 Some(ctx.user_id),
-                "perm.role.upsert",
-                "role",
-                role.role_id.clone(),
-                json!({"name": role.name, "position": role.role_position}),
+                "moderation.ban",
+                "user",
+                target_user.0.to_string(),
+                json!({
+                    "channel_id": channel_id.0,
+                    "reason": reason,
+                    "duration_seconds": duration_secs,
+                }),
             ),
         )
         .await?;
@@ -1085,830 +1414,2902 @@ impl<R: ControlRepo> ControlService<R> {
             &OutboxEvent {
                 id: OutboxId(Uuid::new_v4()),
                 server_id: ctx.server_id,
-                topic: "perm.audit.appended".to_string(),
-                payload_json: json!({"action": "perm.role.upsert", "target_type": "role", "target_id": role.role_id}),
+                topic: "moderation.user_banned".to_string(),
+                payload_json: json!({
+                    "channel_id": channel_id.0,
+                    "target_user_id": target_user.0,
+                    "actor_user_id": ctx.user_id.0,
+                    "reason": reason,
+                    "duration_seconds": duration_secs,
+                }),
             },
         )
         .await?;
-        <R as ControlRepo>::insert_outbox(&self.repo, &mut tx, &OutboxEvent { id: OutboxId(Uuid::new_v4()), server_id: ctx.server_id, topic: "perm.role.upserted".to_string(), payload_json: json!({"role_id": role.role_id, "name": role.name, "position": role.role_position}) }).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Drop a membership on a moderator's behalf: the row, the leave
+    /// presence event and a fresh channel snapshot.
+    async fn remove_member(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        server_id: ServerId,
+        channel_id: ChannelId,
+        user_id: UserId,
+    ) -> ControlResult<()> {
+        <R as ControlRepo>::delete_member(&self.repo, tx, server_id, channel_id, user_id).await?;
+        <R as ControlRepo>::touch_ephemeral_channel(&self.repo, tx, server_id, channel_id).await?;
         <R as ControlRepo>::insert_outbox(
             &self.repo,
-            &mut tx,
+            tx,
             &OutboxEvent {
                 id: OutboxId(Uuid::new_v4()),
-                server_id: ctx.server_id,
-                topic: "perm.role.order_changed".to_string(),
-                payload_json: json!({"role_ids": [role.role_id.clone()]}),
+                server_id,
+                topic: "presence.member_left".to_string(),
+                payload_json: json!({
+                    "channel_id": channel_id.0,
+                    "user_id": user_id.0
+                }),
             },
         )
         .await?;
-        tx.commit().await?;
-        Ok(role)
+        self.insert_channel_state_refresh(tx, server_id, channel_id)
+            .await
     }
 
-    pub async fn perm_delete_role(&self, ctx: &RequestContext, role_id: &str) -> ControlResult<()> {
+    /// Push the authoritative member list and limits for `channel_id` to its
+    /// members, e.g. after a session resumes and may have missed deltas.
+    pub async fn request_channel_state_refresh(
+        &self,
+        ctx: &RequestContext,
+        channel_id: ChannelId,
+    ) -> ControlResult<()> {
         let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
-        self.require(&mut tx, ctx, None, None, Capability::ManageRoles)
-            .await?;
-        let _ = self.require_manageable_role(&mut tx, ctx, role_id).await?;
-        let deleted =
-            <R as ControlRepo>::perm_delete_role(&self.repo, &mut tx, ctx.server_id, role_id)
-                .await?;
-        if !deleted {
-            return Err(ControlError::NotFound("role"));
-        }
-        <R as ControlRepo>::insert_audit(
-            &self.repo,
-            &mut tx,
-            &AuditEntry::new(
-                ctx.server_id,
-                Some(ctx.user_id),
-                "perm.role.delete",
-                "role",
-                role_id.to_string(),
-                json!({}),
-            ),
-        )
-        .await?;
-        <R as ControlRepo>::insert_outbox(
-            &self.repo,
-            &mut tx,
-            &OutboxEvent {
-                id: OutboxId(Uuid::new_v4()),
-                server_id: ctx.server_id,
-                topic: "perm.audit.appended".to_string(),
-                payload_json: json!({"action": "perm.role.delete", "target_type": "role", "target_id": role_id}),
-            },
-        )
-        .await?;
-        <R as ControlRepo>::insert_outbox(
-            &self.repo,
+        self.require(
             &mut tx,
-            &OutboxEvent {
-                id: OutboxId(Uuid::new_v4()),
-                server_id: ctx.server_id,
-                topic: "perm.role.deleted".to_string(),
-                payload_json: json!({"role_id": role_id}),
-            },
+            ctx,
+            Some(channel_id),
+            None,
+            Capability::JoinChannel,
         )
         .await?;
+        self.insert_channel_state_refresh(&mut tx, ctx.server_id, channel_id)
+            .await?;
         tx.commit().await?;
         Ok(())
     }
 
-    pub async fn perm_set_role_caps(
+    /// Queue a `channel.state_refresh` snapshot read inside `tx`, so it
+    /// reflects exactly what the surrounding change commits. The gateway
+    /// throttles these per channel; emitting one per membership change is fine.
+    async fn insert_channel_state_refresh(
         &self,
-        ctx: &RequestContext,
-        role_id: &str,
-        caps: &[(String, String)],
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        server_id: ServerId,
+        channel_id: ChannelId,
     ) -> ControlResult<()> {
-        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
-        self.require(&mut tx, ctx, None, None, Capability::ManageRoles)
-            .await?;
-        let _ = self.require_manageable_role(&mut tx, ctx, role_id).await?;
-        <R as ControlRepo>::perm_replace_role_caps(&self.repo, &mut tx, role_id, caps).await?;
-        <R as ControlRepo>::insert_audit(
-            &self.repo,
-            &mut tx,
-            &AuditEntry::new(
-                ctx.server_id,
-                Some(ctx.user_id),
-                "perm.role.caps",
-                "role",
-                role_id.to_string(),
-                json!({"caps": caps}),
-            ),
-        )
-        .await?;
+        let Some(ch) =
+            <R as ControlRepo>::get_channel(&self.repo, tx, server_id, channel_id).await?
+        else {
+            return Ok(());
+        };
+        let members =
+            <R as ControlRepo>::list_members(&self.repo, tx, server_id, channel_id).await?;
+
         <R as ControlRepo>::insert_outbox(
             &self.repo,
-            &mut tx,
+            tx,
             &OutboxEvent {
                 id: OutboxId(Uuid::new_v4()),
-                server_id: ctx.server_id,
-                topic: "perm.audit.appended".to_string(),
-                payload_json: json!({"action": "perm.role.caps", "target_type": "role", "target_id": role_id}),
+                server_id,
+                topic: "channel.state_refresh".to_string(),
+                payload_json: channel_state_refresh_payload(&ch, &members),
             },
         )
         .await?;
+        Ok(())
+    }
+
+    /// Post a system chat message about `member` joining or leaving when the
+    /// channel has presence announcements on. It is stored and pushed like
+    /// any other message so clients see it in history and read markers.
+    async fn insert_presence_announcement(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        server_id: ServerId,
+        member: &Member,
+        joined: bool,
+    ) -> ControlResult<()> {
+        let Some(ch) =
+            <R as ControlRepo>::get_channel(&self.repo, tx, server_id, member.channel_id).await?
+        else {
+            return Ok(());
+        };
+        if !ch.announce_presence {
+            return Ok(());
+        }
+
+        let rec = ChatMessage {
+            id: MessageId(Uuid::new_v4()),
+            server_id,
+            channel_id: member.channel_id,
+            author_user_id: member.user_id,
+            text: presence_announcement_text(&member.display_name, joined),
+            attachments: json!([]),
+            kind: ChatMessageKind::System,
+            created_at: Utc::now(),
+            edited_at: None,
+            deleted_at: None,
+        };
+        <R as ControlRepo>::insert_chat_message(&self.repo, tx, &rec).await?;
+
         <R as ControlRepo>::insert_outbox(
             &self.repo,
-            &mut tx,
+            tx,
             &OutboxEvent {
                 id: OutboxId(Uuid::new_v4()),
-                server_id: ctx.server_id,
-                topic: "perm.role.caps_changed".to_string(),
-                payload_json: json!({"role_id": role_id, "caps": caps}),
+                server_id,
+                topic: "chat.message_posted".to_string(),
+                payload_json: chat_message_posted_payload(&rec),
             },
         )
         .await?;
-        tx.commit().await?;
         Ok(())
     }
 
-    pub async fn perm_assign_roles(
+    /// Authorize and audit an admin force-disconnect. Closing the connection
+    /// is the gateway's job; membership and ban state are left untouched and
+    /// the regular disconnect cleanup emits the presence events.
+    ///
+    /// `is_connected` says whether the target has a live session. It is only
+    /// asked once the caller is authorized, so the `NotFound` for an offline
+    /// target tells nobody else who is online.
+    pub async fn disconnect_session(
         &self,
         ctx: &RequestContext,
-        user_id: UserId,
-        role_ids: &[String],
+        target_user: UserId,
+        reason: &str,
+        is_connected: impl FnOnce() -> bool,
     ) -> ControlResult<()> {
         let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
-        self.require(&mut tx, ctx, None, Some(user_id), Capability::ManageRoles)
-            .await?;
-        self.require_manageable_target_user(&mut tx, ctx, user_id)
-            .await?;
-        let actor_max = self.actor_max_role_position(&mut tx, ctx).await?;
-        for rid in role_ids {
-            let role = <R as ControlRepo>::perm_get_role(&self.repo, &mut tx, ctx.server_id, rid)
-                .await?
-                .ok_or(ControlError::NotFound("role"))?;
-            if role.role_position >= actor_max {
-                return Err(ControlError::PermissionDenied(
-                    "cannot assign roles above or equal to your highest role",
-                ));
-            }
-            if role.name.eq_ignore_ascii_case("owner") && !ctx.is_admin {
-                return Err(ControlError::PermissionDenied(
-                    "owner role editable only by server owner",
-                ));
-            }
-        }
-        <R as ControlRepo>::perm_replace_user_roles(
-            &self.repo,
+        self.require(
             &mut tx,
-            ctx.server_id,
-            user_id,
-            role_ids,
+            ctx,
+            None,
+            Some(target_user),
+            Capability::ModerateMembers,
         )
         .await?;
+        if !is_connected() {
+            return Err(ControlError::NotFound("session"));
+        }
+
         <R as ControlRepo>::insert_audit(
             &self.repo,
             &mut tx,
             &AuditEntry::new(
                 ctx.server_id,
                 Some(ctx.user_id),
-                "perm.user.roles",
+                "moderation.disconnect",
                 "user",
-                user_id.0.to_string(),
-                json!({"roles": role_ids}),
+                target_user.0.to_string(),
+                json!({ "reason": reason }),
             ),
         )
         .await?;
-        <R as ControlRepo>::insert_outbox(
-            &self.repo,
-            &mut tx,
-            &OutboxEvent {
-                id: OutboxId(Uuid::new_v4()),
-                server_id: ctx.server_id,
-                topic: "perm.audit.appended".to_string(),
-                payload_json: json!({"action": "perm.user.roles", "target_type": "user", "target_id": user_id.0}),
-            },
-        )
-        .await?;
-        <R as ControlRepo>::insert_outbox(
-            &self.repo,
-            &mut tx,
-            &OutboxEvent {
-                id: OutboxId(Uuid::new_v4()),
-                server_id: ctx.server_id,
-                topic: "perm.user.roles_changed".to_string(),
-                payload_json: json!({"user_id": user_id.0, "roles": role_ids}),
-            },
-        )
-        .await?;
+
         tx.commit().await?;
         Ok(())
     }
 
-    pub async fn perm_list_channel_overrides(
+    // -------------------------------------------------------------------------
+    // Chat
+    // -------------------------------------------------------------------------
+
+    pub async fn send_message(
         &self,
         ctx: &RequestContext,
-        channel_id: ChannelId,
-    ) -> ControlResult<Vec<PermChannelOverrideRecord>> {
+        msg: SendMessage,
+    ) -> ControlResult<ChatMessage> {
+        let text = msg.text.trim();
+        if text.len() > MAX_MESSAGE_LENGTH {
+            return Err(ControlError::InvalidArgument("message too long"));
+        }
+
+        let requested_attachments = msg
+            .attachments
+            .unwrap_or_else(|| json!([]))
+            .as_array()
+            .cloned()
+            .ok_or(ControlError::InvalidArgument(
+                "attachments must be an array",
+            ))?;
+
+        if text.is_empty() && requested_attachments.is_empty() {
+            return Err(ControlError::InvalidArgument(
+                "message text and attachments empty",
+            ));
+        }
+
         let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
         self.require(
             &mut tx,
             ctx,
-            Some(channel_id),
+            Some(msg.channel_id),
             None,
-            Capability::ManageRoles,
+            Capability::SendMessage,
         )
         .await?;
-        self.require(
+
+        // Ensure member exists
+        let _m = <R as ControlRepo>::get_member(
+            &self.repo,
             &mut tx,
-            ctx,
-            Some(channel_id),
-            None,
-            Capability::ManageChannel,
+            ctx.server_id,
+            msg.channel_id,
+            ctx.user_id,
         )
-        .await?;
-        let rows = <R as ControlRepo>::perm_list_channel_overrides(&self.repo, &mut tx, channel_id)
+        .await?
+        .ok_or(ControlError::NotFound("member"))?;
+
+        let channel =
+            <R as ControlRepo>::get_channel(&self.repo, &mut tx, ctx.server_id, msg.channel_id)
+                .await?
+                .ok_or(ControlError::NotFound("channel"))?;
+        if channel
+            .max_message_length
+            .is_some_and(|max| text.len() > max.max(0) as usize)
+        {
+            return Err(ControlError::InvalidArgument("message too long"));
+        }
+        if channel.slow_mode_secs > 0 {
+            // Serialises sends in the channel so two quick messages can't
+            // both see the same "last message" and slip through.
+            <R as ControlRepo>::lock_channel(&self.repo, &mut tx, ctx.server_id, msg.channel_id)
+                .await?;
+            let last = <R as ControlRepo>::last_chat_message_at(
+                &self.repo,
+                &mut tx,
+                msg.channel_id,
+                ctx.user_id,
+            )
             .await?;
-        tx.commit().await?;
-        Ok(rows)
-    }
+            if let Some(retry_after_secs) =
+                slow_mode_retry_after(channel.slow_mode_secs, last, Utc::now())
+            {
+                return Err(ControlError::RateLimited {
+                    reason: SLOW_MODE,
+                    retry_after_secs,
+                });
+            }
+        }
 
-    pub async fn perm_set_channel_override(
-        &self,
-        ctx: &RequestContext,
-        rec: &PermChannelOverrideRecord,
-    ) -> ControlResult<()> {
-        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
-        self.require(
-            &mut tx,
-            ctx,
-            Some(rec.channel_id),
-            rec.user_id,
-            Capability::ManageRoles,
+        let mut canonical_attachments = Vec::with_capacity(requested_attachments.len());
+        for requested in requested_attachments {
+            let Some(asset_id) = requested
+                .get("asset_id")
+                .and_then(serde_json::Value::as_str)
+                .filter(|s| !s.is_empty())
+            else {
+                return Err(ControlError::InvalidArgument("attachment asset_id missing"));
+            };
+            let parsed_asset_id = Uuid::parse_str(asset_id)
+                .map_err(|_| ControlError::InvalidArgument("invalid attachment asset_id"))?;
+
+            let attachment =
+                <R as ControlRepo>::get_attachment(&self.repo, &mut tx, parsed_asset_id)
+                    .await?
+                    .ok_or(ControlError::NotFound("attachment"))?;
+
+            if attachment.server_id != ctx.server_id {
+                return Err(ControlError::PermissionDenied("attachment server mismatch"));
+            }
+            if attachment.channel_id != msg.channel_id {
+                return Err(ControlError::PermissionDenied(
+                    "attachment channel mismatch",
+                ));
+            }
+            if attachment.uploader_user_id != ctx.user_id {
+                return Err(ControlError::PermissionDenied(
+                    "attachment uploader mismatch",
+                ));
+            }
+            if attachment.quarantined {
+                return Err(ControlError::FailedPrecondition("attachment quarantined"));
+            }
+
+            canonical_attachments.push(json!({
+                "asset_id": attachment.id,
+                "filename": attachment.filename,
+                "mime_type": attachment.content_type,
+                "size_bytes": attachment.size_bytes,
+                "sha256": attachment.sha256.unwrap_or_default(),
+            }));
+        }
+
+        let rec = ChatMessage {
+            id: MessageId(Uuid::new_v4()),
+            server_id: ctx.server_id,
+            channel_id: msg.channel_id,
+            author_user_id: ctx.user_id,
+            text: text.to_string(),
+            attachments: json!(canonical_attachments),
+            kind: ChatMessageKind::User,
+            created_at: Utc::now(),
+            edited_at: None,
+            deleted_at: None,
+        };
+
+        <R as ControlRepo>::insert_chat_message(&self.repo, &mut tx, &rec).await?;
+
+        <R as ControlRepo>::insert_audit(
+            &self.repo,
+            &mut tx,
+            &AuditEntry::new(
+                ctx.server_id,
+                Some(ctx.user_id),
+                "chat.message",
+                "channel",
+                msg.channel_id.0.to_string(),
+                json!({ "message_id": rec.id.0, "text_len": rec.text.len() }),
+            ),
         )
         .await?;
-        self.require(
+
+        <R as ControlRepo>::insert_outbox(
+            &self.repo,
             &mut tx,
-            ctx,
-            Some(rec.channel_id),
-            rec.user_id,
-            Capability::ManageChannel,
+            &OutboxEvent {
+                id: OutboxId(Uuid::new_v4()),
+                server_id: ctx.server_id,
+                topic: "chat.message_posted".to_string(),
+                payload_json: chat_message_posted_payload(&rec),
+            },
         )
         .await?;
-        if let Some(target_user) = rec.user_id {
-            self.require_manageable_target_user(&mut tx, ctx, target_user)
-                .await?;
+
+        tx.commit().await?;
+        Ok(rec)
+    }
+
+    /// Replace a message's text. Its author may edit while they can still
+    /// send in the channel; anyone else needs to be able to moderate them.
+    pub async fn edit_message(
+        &self,
+        ctx: &RequestContext,
+        edit: EditMessage,
+    ) -> ControlResult<ChatMessage> {
+        let text = edit.text.trim();
+        if text.len() > MAX_MESSAGE_LENGTH {
+            return Err(ControlError::InvalidArgument("message too long"));
         }
-        if let Some(ref role_id) = rec.role_id {
-            let _ = self.require_manageable_role(&mut tx, ctx, role_id).await?;
+
+        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
+        let mut rec = self
+            .require_message_actor(&mut tx, ctx, edit.channel_id, edit.message_id)
+            .await?;
+        if rec.kind == ChatMessageKind::System {
+            return Err(ControlError::FailedPrecondition(
+                "system messages cannot be edited",
+            ));
         }
-        <R as ControlRepo>::perm_set_channel_override(&self.repo, &mut tx, rec).await?;
-        <R as ControlRepo>::insert_audit(&self.repo, &mut tx, &AuditEntry::new(ctx.server_id, Some(ctx.user_id), "perm.channel.override", "channel", rec.channel_id.0.to_string(), json!({"role_id": rec.role_id, "user_id": rec.user_id.map(|u| u.0), "cap": rec.cap, "effect": rec.effect}))).await?;
+        if rec.author_user_id == ctx.user_id {
+            self.require(
+                &mut tx,
+                ctx,
+                Some(edit.channel_id),
+                None,
+                Capability::SendMessage,
+            )
+            .await?;
+        }
+        let has_attachments = rec
+            .attachments
+            .as_array()
+            .is_some_and(|attachments| !attachments.is_empty());
+        if text.is_empty() && !has_attachments {
+            return Err(ControlError::InvalidArgument(
+                "message text and attachments empty",
+            ));
+        }
+
+        let channel =
+            <R as ControlRepo>::get_channel(&self.repo, &mut tx, ctx.server_id, edit.channel_id)
+                .await?
+                .ok_or(ControlError::NotFound("channel"))?;
+        if channel
+            .max_message_length
+            .is_some_and(|max| text.len() > max.max(0) as usize)
+        {
+            return Err(ControlError::InvalidArgument("message too long"));
+        }
+
+        let at = Utc::now();
+        if !<R as ControlRepo>::edit_chat_message(
+            &self.repo,
+            &mut tx,
+            ctx.server_id,
+            rec.id,
+            text,
+            at,
+        )
+        .await?
+        {
+            return Err(ControlError::NotFound("message"));
+        }
+        rec.text = text.to_string();
+        rec.edited_at = Some(at);
+
+        <R as ControlRepo>::insert_audit(
+            &self.repo,
+            &mut tx,
+            &AuditEntry::new(
+                ctx.server_id,
+                Some(ctx.user_id),
+                "chat.message_edit",
+                "channel",
+                edit.channel_id.0.to_string(),
+                json!({
+                    "message_id": rec.id.0,
+                    "author_user_id": rec.author_user_id.0,
+                    "text_len": rec.text.len(),
+                }),
+            ),
+        )
+        .await?;
+
         <R as ControlRepo>::insert_outbox(
             &self.repo,
             &mut tx,
             &OutboxEvent {
                 id: OutboxId(Uuid::new_v4()),
                 server_id: ctx.server_id,
-                topic: "perm.audit.appended".to_string(),
-                payload_json: json!({"action": "perm.channel.override", "target_type": "channel", "target_id": rec.channel_id.0}),
+                topic: "chat.message_edited".to_string(),
+                payload_json: chat_message_edited_payload(&rec, at),
             },
         )
         .await?;
-        <R as ControlRepo>::insert_outbox(&self.repo, &mut tx, &OutboxEvent { id: OutboxId(Uuid::new_v4()), server_id: ctx.server_id, topic: "perm.channel.overrides_changed".to_string(), payload_json: json!({"channel_id": rec.channel_id.0, "role_id": rec.role_id, "user_id": rec.user_id.map(|u| u.0), "cap": rec.cap, "effect": rec.effect}) }).await?;
+
         tx.commit().await?;
-        Ok(())
+        Ok(rec)
     }
 
-    pub async fn perm_audit_query(
+    /// Soft-delete a message. Its author may always delete it; anyone else,
+    /// and anyone removing a system message, needs to be able to moderate the
+    /// author.
+    pub async fn delete_message(
         &self,
         ctx: &RequestContext,
-        limit: i64,
-    ) -> ControlResult<Vec<PermAuditRow>> {
+        channel_id: ChannelId,
+        message_id: MessageId,
+    ) -> ControlResult<()> {
         let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
-        self.require(&mut tx, ctx, None, None, Capability::ManageRoles)
+        let rec = self
+            .require_message_actor(&mut tx, ctx, channel_id, message_id)
             .await?;
-        let rows = <R as ControlRepo>::perm_query_audit(
+
+        let at = Utc::now();
+        if !<R as ControlRepo>::delete_chat_message(&self.repo, &mut tx, ctx.server_id, rec.id, at)
+            .await?
+        {
+            return Err(ControlError::NotFound("message"));
+        }
+
+        <R as ControlRepo>::insert_audit(
             &self.repo,
             &mut tx,
-            ctx.server_id,
-            limit.max(1).min(200),
+            &AuditEntry::new(
+                ctx.server_id,
+                Some(ctx.user_id),
+                "chat.message_delete",
+                "channel",
+                channel_id.0.to_string(),
+                json!({
+                    "message_id": rec.id.0,
+                    "author_user_id": rec.author_user_id.0,
+                }),
+            ),
+        )
+        .await?;
+
+        <R as ControlRepo>::insert_outbox(
+            &self.repo,
+            &mut tx,
+            &OutboxEvent {
+                id: OutboxId(Uuid::new_v4()),
+                server_id: ctx.server_id,
+                topic: "chat.message_deleted".to_string(),
+                payload_json: chat_message_deleted_payload(&rec, at),
+            },
         )
         .await?;
+
         tx.commit().await?;
-        Ok(rows)
+        Ok(())
     }
 
-    pub async fn perm_eval_effective(
+    /// A page of live messages in a channel, oldest first, each with its
+    /// reaction counts as seen by the caller. `before` pages backwards from
+    /// that message; `limit` 0 means the default page size.
+    pub async fn list_messages(
         &self,
         ctx: &RequestContext,
-        user_id: UserId,
-        channel_id: Option<ChannelId>,
-        caps: &[String],
-    ) -> ControlResult<Vec<(String, bool)>> {
+        channel_id: ChannelId,
+        before: Option<MessageId>,
+        limit: usize,
+    ) -> ControlResult<Vec<ChatHistoryEntry>> {
+        let limit = match limit {
+            0 => HISTORY_PAGE_SIZE,
+            n => n.min(MAX_HISTORY_PAGE_SIZE),
+        };
+
         let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
         self.require(
             &mut tx,
             ctx,
+            Some(channel_id),
+            None,
+            Capability::JoinChannel,
+        )
+        .await?;
+        <R as ControlRepo>::get_channel(&self.repo, &mut tx, ctx.server_id, channel_id)
+            .await?
+            .ok_or(ControlError::NotFound("channel"))?;
+        if let Some(before) = before {
+            // Deleted anchors are fine: the client may page from a message
+            // that was removed after it loaded.
+            <R as ControlRepo>::get_chat_message(&self.repo, &mut tx, ctx.server_id, before)
+                .await?
+                .filter(|m| m.channel_id == channel_id)
+                .ok_or(ControlError::NotFound("message"))?;
+        }
+
+        let mut messages = <R as ControlRepo>::list_messages(
+            &self.repo,
+            &mut tx,
+            ctx.server_id,
             channel_id,
-            Some(user_id),
-            Capability::ManageRoles,
+            before,
+            limit as i64,
         )
         .await?;
-        let mut out = Vec::with_capacity(caps.len());
-        for cap in caps {
-            let c = Capability::from_str(cap)
-                .ok_or(ControlError::InvalidArgument("unknown capability"))?;
-            let req = PermissionRequest {
-                server_id: ctx.server_id,
-                user_id,
-                is_admin: false,
-                capability: c,
-                channel_id,
-                target_user_id: None,
-            };
-            let allowed = matches!(
-                <R as ControlRepo>::decide_permission(&self.repo, &mut tx, &req).await?,
-                Decision::Allow
-            );
-            out.push((cap.clone(), allowed));
+        messages.reverse();
+        let ids = messages.iter().map(|m| m.id).collect::<Vec<_>>();
+        let mut reactions = HashMap::<MessageId, Vec<_>>::new();
+        for (id, summary) in
+            <R as ControlRepo>::list_message_reactions(&self.repo, &mut tx, &ids, ctx.user_id)
+                .await?
+        {
+            reactions.entry(id).or_default().push(summary);
         }
         tx.commit().await?;
-        Ok(out)
+
+        Ok(messages
+            .into_iter()
+            .map(|message| ChatHistoryEntry {
+                reactions: reactions.remove(&message.id).unwrap_or_default(),
+                message,
+            })
+            .collect())
     }
 
-    async fn actor_max_role_position(
+    /// React to a message with `emoji`. Only channel members may react, and
+    /// reacting twice with the same emoji is a no-op that emits nothing.
+    /// Returns whether the reaction was new.
+    pub async fn add_reaction(
         &self,
-        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         ctx: &RequestContext,
-    ) -> ControlResult<i32> {
-        <R as ControlRepo>::perm_actor_max_role_position(&self.repo, tx, ctx.server_id, ctx.user_id)
+        channel_id: ChannelId,
+        message_id: MessageId,
+        emoji: &str,
+    ) -> ControlResult<bool> {
+        self.change_reaction(ctx, channel_id, message_id, emoji, true)
             .await
     }
 
-    async fn require_manageable_role(
+    /// Withdraw the caller's `emoji` reaction. Returns whether they had one.
+    pub async fn remove_reaction(
         &self,
-        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         ctx: &RequestContext,
-        role_id: &str,
-    ) -> ControlResult<PermRoleRecord> {
-        let role = <R as ControlRepo>::perm_get_role(&self.repo, tx, ctx.server_id, role_id)
-            .await?
-            .ok_or(ControlError::NotFound("role"))?;
-        if role.is_everyone {
-            return Err(ControlError::FailedPrecondition(
-                "@everyone role is immutable for this action",
-            ));
+        channel_id: ChannelId,
+        message_id: MessageId,
+        emoji: &str,
+    ) -> ControlResult<bool> {
+        self.change_reaction(ctx, channel_id, message_id, emoji, false)
+            .await
+    }
+
+    async fn change_reaction(
+        &self,
+        ctx: &RequestContext,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        emoji: &str,
+        add: bool,
+    ) -> ControlResult<bool> {
+        let emoji = emoji.trim();
+        if emoji.is_empty() {
+            return Err(ControlError::InvalidArgument("emoji missing"));
         }
-        if role.name.eq_ignore_ascii_case("owner") && !ctx.is_admin {
-            return Err(ControlError::PermissionDenied(
-                "owner role editable only by server owner",
-            ));
+        if emoji.len() > MAX_REACTION_EMOJI_LENGTH {
+            return Err(ControlError::InvalidArgument("emoji too long"));
         }
-        let actor_max = self.actor_max_role_position(tx, ctx).await?;
-        if role.role_position >= actor_max {
-            return Err(ControlError::PermissionDenied(
-                "can only manage roles below your highest role",
-            ));
+
+        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
+        <R as ControlRepo>::get_member(&self.repo, &mut tx, ctx.server_id, channel_id, ctx.user_id)
+            .await?
+            .ok_or(ControlError::PermissionDenied("not a channel member"))?;
+        <R as ControlRepo>::get_chat_message(&self.repo, &mut tx, ctx.server_id, message_id)
+            .await?
+            .filter(|m| m.channel_id == channel_id && m.deleted_at.is_none())
+            .ok_or(ControlError::NotFound("message"))?;
+
+        let changed = if add {
+            <R as ControlRepo>::add_message_reaction(
+                &self.repo,
+                &mut tx,
+                message_id,
+                ctx.user_id,
+                emoji,
+            )
+            .await?
+        } else {
+            <R as ControlRepo>::remove_message_reaction(
+                &self.repo,
+                &mut tx,
+                message_id,
+                ctx.user_id,
+                emoji,
+            )
+            .await?
+        };
+        if !changed {
+            return Ok(false);
         }
-        Ok(role)
+        let count =
+            <R as ControlRepo>::count_message_reactions(&self.repo, &mut tx, message_id, emoji)
+                .await?;
+
+        <R as ControlRepo>::insert_outbox(
+            &self.repo,
+            &mut tx,
+            &OutboxEvent {
+                id: OutboxId(Uuid::new_v4()),
+                server_id: ctx.server_id,
+                topic: "chat.reaction_changed".to_string(),
+                payload_json: json!({
+                    "message_id": message_id.0,
+                    "channel_id": channel_id.0,
+                    "user_id": ctx.user_id.0,
+                    "emoji": emoji,
+                    "added": add,
+                    "count": count,
+                }),
+            },
+        )
+        .await?;
+
+        tx.commit().await?;
+        Ok(true)
     }
 
-    async fn require_manageable_target_user(
+    /// Load a live message in `channel_id` that the caller may change: their
+    /// own user message, or any message whose author they can moderate.
+    async fn require_message_actor(
         &self,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         ctx: &RequestContext,
-        target_user_id: UserId,
+        channel_id: ChannelId,
+        message_id: MessageId,
+    ) -> ControlResult<ChatMessage> {
+        let rec = <R as ControlRepo>::get_chat_message(&self.repo, tx, ctx.server_id, message_id)
+            .await?
+            .filter(|m| m.channel_id == channel_id && m.deleted_at.is_none())
+            .ok_or(ControlError::NotFound("message"))?;
+        if rec.author_user_id != ctx.user_id || rec.kind == ChatMessageKind::System {
+            self.require(
+                tx,
+                ctx,
+                Some(channel_id),
+                Some(rec.author_user_id),
+                Capability::ModerateMembers,
+            )
+            .await?;
+        }
+        Ok(rec)
+    }
+
+    /// Store the caller's push preference for a channel. Subscribing requires
+    /// the same access as joining so it can't be used to read hidden channels.
+    pub async fn set_channel_notification_pref(
+        &self,
+        ctx: &RequestContext,
+        channel_id: ChannelId,
+        pref: ChannelNotificationPref,
     ) -> ControlResult<()> {
-        let actor_max = self.actor_max_role_position(tx, ctx).await?;
-        let target_max = <R as ControlRepo>::perm_user_max_role_position(
+        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
+        <R as ControlRepo>::get_channel(&self.repo, &mut tx, ctx.server_id, channel_id)
+            .await?
+            .ok_or(ControlError::NotFound("channel"))?;
+        if pref == ChannelNotificationPref::Subscribed {
+            self.require(
+                &mut tx,
+                ctx,
+                Some(channel_id),
+                None,
+                Capability::JoinChannel,
+            )
+            .await?;
+        }
+
+        <R as ControlRepo>::set_channel_notification_pref(
             &self.repo,
-            tx,
+            &mut tx,
             ctx.server_id,
-            target_user_id,
+            ctx.user_id,
+            channel_id,
+            pref,
         )
         .await?;
-        if target_max >= actor_max {
-            return Err(ControlError::PermissionDenied(
-                "target user is not below your highest role",
-            ));
-        }
+
+        tx.commit().await?;
         Ok(())
     }
 
-    // -------------------------------------------------------------------------
-    // Outbox helpers (optional – if your gateway uses these)
-    // -------------------------------------------------------------------------
-
-    pub async fn claim_outbox_batch(
+    /// All stored notification prefs for a server, used to warm the gateway cache.
+    pub async fn list_channel_notification_prefs(
         &self,
-        server: ServerId,
-        limit: i64,
-    ) -> ControlResult<(Uuid, Vec<OutboxEventRow>)> {
+        server_id: ServerId,
+    ) -> ControlResult<Vec<ChannelNotificationPrefRecord>> {
         let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
-        let token = Uuid::new_v4();
         let rows =
-            <R as ControlRepo>::claim_outbox_batch(&self.repo, &mut tx, server, token, limit)
+            <R as ControlRepo>::list_channel_notification_prefs(&self.repo, &mut tx, server_id)
                 .await?;
         tx.commit().await?;
-        Ok((token, rows))
+        Ok(rows)
     }
 
-    pub async fn ack_outbox_published(&self, token: Uuid, ids: &[OutboxId]) -> ControlResult<()> {
+    /// Bans still in force on a server, used to warm the gateway cache.
+    pub async fn list_active_bans(&self, server_id: ServerId) -> ControlResult<Vec<ActiveBan>> {
         let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
-        <R as ControlRepo>::ack_outbox_published(&self.repo, &mut tx, ids, token).await?;
+        let bans = <R as ControlRepo>::list_active_bans(&self.repo, &mut tx, server_id, Utc::now())
+            .await?;
         tx.commit().await?;
-        Ok(())
+        Ok(bans)
     }
 
     // -------------------------------------------------------------------------
-    // User profiles
+    // Read receipts
     // -------------------------------------------------------------------------
 
-    pub async fn get_user_profile(
+    /// Record that the caller has read `channel_id` up to `message_id`.
+    /// Markers never move backwards; the flag says whether this call advanced
+    /// it, i.e. whether channel members should hear about it.
+    pub async fn mark_read(
         &self,
         ctx: &RequestContext,
-        target_user_id: UserId,
-    ) -> ControlResult<Option<UserProfileRow>> {
-        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
-        let row = <R as ControlRepo>::get_user_profile(
+        channel_id: ChannelId,
+        message_id: MessageId,
+    ) -> ControlResult<(ReadMarker, bool)> {
+        let mut tx = self.read_receipts_tx(ctx, channel_id).await?;
+        let marked = <R as ControlRepo>::mark_read(
             &self.repo,
             &mut tx,
-            target_user_id,
             ctx.server_id,
+            channel_id,
+            ctx.user_id,
+            message_id,
         )
-        .await?;
+        .await?
+        .ok_or(ControlError::NotFound("message"))?;
         tx.commit().await?;
-        Ok(row)
+        Ok(marked)
     }
 
-    pub async fn update_user_profile(
+    /// One page of a channel's members, in join order, plus the cursor for
+    /// the next page (`None` on the last one). `limit` of 0 means
+    /// [`MEMBER_PAGE_SIZE`], which is also the cap.
+    pub async fn list_members_page(
         &self,
         ctx: &RequestContext,
-        display_name: Option<String>,
-        description: Option<String>,
-        accent_color: Option<i32>,
-        links_json: Option<serde_json::Value>,
-    ) -> ControlResult<()> {
-        if let Some(ref dn) = display_name {
-            if dn.len() > 32 {
-                return Err(ControlError::InvalidArgument("display_name too long"));
-            }
-        }
-        if let Some(ref desc) = description {
-            if desc.len() > 190 {
-                return Err(ControlError::InvalidArgument("description too long"));
-            }
-        }
+        channel_id: ChannelId,
+        after: Option<&str>,
+        limit: usize,
+    ) -> ControlResult<(Vec<Member>, Option<String>)> {
+        let after = after
+            .filter(|raw| !raw.is_empty())
+            .map(|raw| {
+                MemberCursor::parse(raw)
+                    .ok_or(ControlError::InvalidArgument("invalid member cursor"))
+            })
+            .transpose()?;
+        let limit = match limit {
+            0 => MEMBER_PAGE_SIZE,
+            n => n.min(MEMBER_PAGE_SIZE),
+        };
+
         let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
-        <R as ControlRepo>::upsert_user_profile(
-            &self.repo,
+        self.require(
             &mut tx,
-            ctx.user_id,
-            ctx.server_id,
-            display_name.as_deref(),
-            description.as_deref(),
-            accent_color,
-            None,
-            None,
+            ctx,
+            Some(channel_id),
             None,
-            links_json,
+            Capability::JoinChannel,
         )
         .await?;
-        tx.commit().await?;
-        Ok(())
-    }
+        <R as ControlRepo>::get_channel(&self.repo, &mut tx, ctx.server_id, channel_id)
+            .await?
+            .ok_or(ControlError::NotFound("channel"))?;
 
-    pub async fn set_avatar(&self, ctx: &RequestContext, avatar_url: &str) -> ControlResult<()> {
-        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
-        <R as ControlRepo>::set_profile_avatar(
+        // One extra row tells us whether another page exists.
+        let members = <R as ControlRepo>::list_members_page(
             &self.repo,
             &mut tx,
-            ctx.user_id,
             ctx.server_id,
-            avatar_url,
+            channel_id,
+            after.as_ref(),
+            limit as i64 + 1,
         )
         .await?;
         tx.commit().await?;
-        Ok(())
+        Ok(split_member_page(members, limit))
     }
 
-    pub async fn set_banner(&self, ctx: &RequestContext, banner_url: &str) -> ControlResult<()> {
-        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
-        <R as ControlRepo>::set_profile_banner(
-            &self.repo,
-            &mut tx,
-            ctx.user_id,
-            ctx.server_id,
-            banner_url,
-        )
-        .await?;
+    /// Every member's read marker in a channel, most recent first.
+    pub async fn list_read_markers(
+        &self,
+        ctx: &RequestContext,
+        channel_id: ChannelId,
+    ) -> ControlResult<Vec<ReadMarker>> {
+        let mut tx = self.read_receipts_tx(ctx, channel_id).await?;
+        let markers =
+            <R as ControlRepo>::list_read_markers(&self.repo, &mut tx, ctx.server_id, channel_id)
+                .await?;
         tx.commit().await?;
-        Ok(())
+        Ok(markers)
     }
 
-    pub async fn begin_profile_asset_upload(
+    /// Shared gate for read-receipt calls: the feature must be on and the
+    /// caller must be able to see the channel.
+    async fn read_receipts_tx(
         &self,
         ctx: &RequestContext,
-        purpose: &str,
-        mime_type: &str,
-        byte_length: i64,
-    ) -> ControlResult<Uuid> {
-        const MAX_AVATAR_BYTES: i64 = 8 * 1024 * 1024;
-        const MAX_BANNER_BYTES: i64 = 10 * 1024 * 1024;
-
-        match purpose {
-            "profile_avatar" | "profile_banner" => {}
-            _ => return Err(ControlError::InvalidArgument("unknown purpose")),
-        }
-        match mime_type {
-            "image/png" | "image/jpeg" | "image/webp" => {}
-            _ => return Err(ControlError::InvalidArgument("unsupported mime_type")),
-        }
-        let limit = if purpose == "profile_avatar" {
-            MAX_AVATAR_BYTES
-        } else {
-            MAX_BANNER_BYTES
-        };
-        if byte_length <= 0 || byte_length > limit {
-            return Err(ControlError::InvalidArgument("byte_length out of range"));
+        channel_id: ChannelId,
+    ) -> ControlResult<sqlx::Transaction<'_, sqlx::Postgres>> {
+        if !self.config.read_receipts {
+            return Err(ControlError::FailedPrecondition("read receipts disabled"));
         }
-
-        let session_id = Uuid::new_v4();
         let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
-        <R as ControlRepo>::create_asset_upload_session(
-            &self.repo,
+        <R as ControlRepo>::get_channel(&self.repo, &mut tx, ctx.server_id, channel_id)
+            .await?
+            .ok_or(ControlError::NotFound("channel"))?;
+        self.require(
             &mut tx,
-            session_id,
-            ctx.user_id,
-            ctx.server_id,
-            purpose,
-            mime_type,
-            byte_length,
+            ctx,
+            Some(channel_id),
+            None,
+            Capability::JoinChannel,
         )
         .await?;
-        tx.commit().await?;
-        Ok(session_id)
+        Ok(tx)
     }
 
-    pub async fn store_verified_asset(
+    // -------------------------------------------------------------------------
+    // Admin permissions RPCs
+    // -------------------------------------------------------------------------
+
+    pub async fn perm_list_roles(
         &self,
-        user_id: UserId,
-        session_id: Uuid,
-        asset_data: &[u8],
-    ) -> ControlResult<()> {
-        // Re-verify ownership.
+        ctx: &RequestContext,
+    ) -> ControlResult<Vec<PermRoleRecord>> {
         let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
-        let session =
-            <R as ControlRepo>::get_asset_upload_session(&self.repo, &mut tx, session_id, user_id)
-                .await?;
-        if session.is_none() {
-            return Err(ControlError::NotFound(
-                "asset upload session not found or expired",
-            ));
-        }
-        <R as ControlRepo>::store_verified_asset(&self.repo, &mut tx, session_id, asset_data)
+        self.require(&mut tx, ctx, None, None, Capability::ManageRoles)
             .await?;
+        let roles = <R as ControlRepo>::perm_list_roles(&self.repo, &mut tx, ctx.server_id).await?;
         tx.commit().await?;
-        Ok(())
+        Ok(roles)
     }
 
-    pub async fn create_default_profile(
+    pub async fn perm_list_users(
         &self,
-        user_id: UserId,
-        server_id: ServerId,
-        display_name: &str,
-    ) -> ControlResult<()> {
+        ctx: &RequestContext,
+    ) -> ControlResult<(Vec<PermUserSummaryRecord>, i32, bool)> {
         let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
-        <R as ControlRepo>::create_default_profile(
-            &self.repo,
-            &mut tx,
-            user_id,
-            server_id,
-            display_name,
-        )
-        .await?;
+        self.require(&mut tx, ctx, None, None, Capability::ManageRoles)
+            .await?;
+        let users = <R as ControlRepo>::perm_list_users(&self.repo, &mut tx, ctx.server_id).await?;
+        let editor_highest_role_position = self.actor_max_role_position(&mut tx, ctx).await?;
         tx.commit().await?;
-        Ok(())
+        Ok((users, editor_highest_role_position, ctx.is_admin))
     }
 
-    pub async fn get_user_badges(
+    pub async fn perm_upsert_role(
         &self,
         ctx: &RequestContext,
-        target_user_id: UserId,
-    ) -> ControlResult<Vec<crate::model::UserBadgeRow>> {
+        role_id: Option<&str>,
+        name: &str,
+        color: i32,
+        position: i32,
+    ) -> ControlResult<PermRoleRecord> {
+        if name.trim().is_empty() {
+            return Err(ControlError::InvalidArgument("role name empty"));
+        }
+        if name.trim().eq_ignore_ascii_case("owner") && !ctx.is_admin {
+            return Err(ControlError::PermissionDenied(
+                "owner role editable only by server owner",
+            ));
+        }
         let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
-        let badges =
-            <R as ControlRepo>::get_user_badges(&self.repo, &mut tx, target_user_id, ctx.server_id)
+        self.require(&mut tx, ctx, None, None, Capability::ManageRoles)
+            .await?;
+        if let Some(existing_role_id) = role_id {
+            let _ = self
+                .require_manageable_role(&mut tx, ctx, existing_role_id)
+                .await?;
+        }
+        let role = <R as ControlRepo>::perm_upsert_role(
+            &self.repo,
+            &mut tx,
+            ctx.server_id,
+            role_id,
+            name.trim(),
+            color,
+            position,
+        )
+        .await?;
+        <R as ControlRepo>::insert_audit(
+            &self.repo,
+            &mut tx,
+            &AuditEntry::new(
+                ctx.server_id,
+                Some(ctx.user_id),
+                "perm.role.upsert",
+                "role",
+                role.role_id.clone(),
+                json!({"name": role.name, "position": role.role_position}),
+            ),
+        )
+        .await?;
+        <R as ControlRepo>::insert_outbox(
+            &self.repo,
+            &mut tx,
+            &OutboxEvent {
+                id: OutboxId(Uuid::new_v4()),
+                server_id: ctx.server_id,
+                topic: "perm.audit.appended".to_string(),
+                payload_json: json!({"action": "perm.role.upsert", "target_type": "role", "target_id": role.role_id}),
+            },
+        )
+        .await?;
+        <R as ControlRepo>::insert_outbox(&self.repo, &mut tx, &OutboxEvent { id: OutboxId(Uuid::new_v4()), server_id: ctx.server_id, topic: "perm.role.upserted".to_string(), payload_json: json!({"role_id": role.role_id, "name": role.name, "position": role.role_position}) }).await?;
+        <R as ControlRepo>::insert_outbox(
+            &self.repo,
+            &mut tx,
+            &OutboxEvent {
+                id: OutboxId(Uuid::new_v4()),
+                server_id: ctx.server_id,
+                topic: "perm.role.order_changed".to_string(),
+                payload_json: json!({"role_ids": [role.role_id.clone()]}),
+            },
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(role)
+    }
+
+    pub async fn perm_delete_role(&self, ctx: &RequestContext, role_id: &str) -> ControlResult<()> {
+        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
+        self.require(&mut tx, ctx, None, None, Capability::ManageRoles)
+            .await?;
+        let _ = self.require_manageable_role(&mut tx, ctx, role_id).await?;
+        let deleted =
+            <R as ControlRepo>::perm_delete_role(&self.repo, &mut tx, ctx.server_id, role_id)
                 .await?;
+        if !deleted {
+            return Err(ControlError::NotFound("role"));
+        }
+        <R as ControlRepo>::insert_audit(
+            &self.repo,
+            &mut tx,
+            &AuditEntry::new(
+                ctx.server_id,
+                Some(ctx.user_id),
+                "perm.role.delete",
+                "role",
+                role_id.to_string(),
+                json!({}),
+            ),
+        )
+        .await?;
+        <R as ControlRepo>::insert_outbox(
+            &self.repo,
+            &mut tx,
+            &OutboxEvent {
+                id: OutboxId(Uuid::new_v4()),
+                server_id: ctx.server_id,
+                topic: "perm.audit.appended".to_string(),
+                payload_json: json!({"action": "perm.role.delete", "target_type": "role", "target_id": role_id}),
+            },
+        )
+        .await?;
+        <R as ControlRepo>::insert_outbox(
+            &self.repo,
+            &mut tx,
+            &OutboxEvent {
+                id: OutboxId(Uuid::new_v4()),
+                server_id: ctx.server_id,
+                topic: "perm.role.deleted".to_string(),
+                payload_json: json!({"role_id": role_id}),
+            },
+        )
+        .await?;
         tx.commit().await?;
-        Ok(badges)
+        Ok(())
     }
 
-    pub async fn create_badge(
+    pub async fn perm_set_role_caps(
         &self,
         ctx: &RequestContext,
-        id: &str,
-        label: &str,
-        icon_url: &str,
-        tooltip: &str,
-    ) -> ControlResult<crate::model::BadgeDefinitionRow> {
+        role_id: &str,
+        caps: &[(String, String)],
+    ) -> ControlResult<()> {
         let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
-        self.require(&mut tx, ctx, None, None, Capability::ManageBadges)
+        self.require(&mut tx, ctx, None, None, Capability::ManageRoles)
+            .await?;
+        let _ = self.require_manageable_role(&mut tx, ctx, role_id).await?;
+        <R as ControlRepo>::perm_replace_role_caps(&self.repo, &mut tx, role_id, caps).await?;
+        <R as ControlRepo>::insert_audit(
+            &self.repo,
+            &mut tx,
+            &AuditEntry::new(
+                ctx.server_id,
+                Some(ctx.user_id),
+                "perm.role.caps",
+                "role",
+                role_id.to_string(),
+                json!({"caps": caps}),
+            ),
+        )
+        .await?;
+        <R as ControlRepo>::insert_outbox(
+            &self.repo,
+            &mut tx,
+            &OutboxEvent {
+                id: OutboxId(Uuid::new_v4()),
+                server_id: ctx.server_id,
+                topic: "perm.audit.appended".to_string(),
+                payload_json: json!({"action": "perm.role.caps", "target_type": "role", "target_id": role_id}),
+            },
+        )
+        .await?;
+        <R as ControlRepo>::insert_outbox(
+            &self.repo,
+            &mut tx,
+            &OutboxEvent {
+                id: OutboxId(Uuid::new_v4()),
+                server_id: ctx.server_id,
+                topic: "perm.role.caps_changed".to_string(),
+                payload_json: json!({"role_id": role_id, "caps": caps}),
+            },
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn perm_assign_roles(
+        &self,
+        ctx: &RequestContext,
+        user_id: UserId,
+        role_ids: &[String],
+    ) -> ControlResult<()> {
+        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
+        self.require(&mut tx, ctx, None, Some(user_id), Capability::ManageRoles)
+            .await?;
+        self.require_manageable_target_user(&mut tx, ctx, user_id)
+            .await?;
+        let actor_max = self.actor_max_role_position(&mut tx, ctx).await?;
+        for rid in role_ids {
+            let role = <R as ControlRepo>::perm_get_role(&self.repo, &mut tx, ctx.server_id, rid)
+                .await?
+                .ok_or(ControlError::NotFound("role"))?;
+            if role.role_position >= actor_max {
+                return Err(ControlError::PermissionDenied(
+                    "cannot assign roles above or equal to your highest role",
+                ));
+            }
+            if role.name.eq_ignore_ascii_case("owner") && !ctx.is_admin {
+                return Err(ControlError::PermissionDenied(
+                    "owner role editable only by server owner",
+                ));
+            }
+        }
+        <R as ControlRepo>::perm_replace_user_roles(
+            &self.repo,
+            &mut tx,
+            ctx.server_id,
+            user_id,
+            role_ids,
+        )
+        .await?;
+        <R as ControlRepo>::insert_audit(
+            &self.repo,
+            &mut tx,
+            &AuditEntry::new(
+                ctx.server_id,
+                Some(ctx.user_id),
+                "perm.user.roles",
+                "user",
+                user_id.0.to_string(),
+                json!({"roles": role_ids}),
+            ),
+        )
+        .await?;
+        <R as ControlRepo>::insert_outbox(
+            &self.repo,
+            &mut tx,
+            &OutboxEvent {
+                id: OutboxId(Uuid::new_v4()),
+                server_id: ctx.server_id,
+                topic: "perm.audit.appended".to_string(),
+                payload_json: json!({"action": "perm.user.roles", "target_type": "user", "target_id": user_id.0}),
+            },
+        )
+        .await?;
+        <R as ControlRepo>::insert_outbox(
+            &self.repo,
+            &mut tx,
+            &OutboxEvent {
+                id: OutboxId(Uuid::new_v4()),
+                server_id: ctx.server_id,
+                topic: "perm.user.roles_changed".to_string(),
+                payload_json: json!({"user_id": user_id.0, "roles": role_ids}),
+            },
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn perm_list_channel_overrides(
+        &self,
+        ctx: &RequestContext,
+        channel_id: ChannelId,
+    ) -> ControlResult<Vec<PermChannelOverrideRecord>> {
+        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
+        self.require(
+            &mut tx,
+            ctx,
+            Some(channel_id),
+            None,
+            Capability::ManageRoles,
+        )
+        .await?;
+        self.require(
+            &mut tx,
+            ctx,
+            Some(channel_id),
+            None,
+            Capability::ManageChannel,
+        )
+        .await?;
+        let rows = <R as ControlRepo>::perm_list_channel_overrides(&self.repo, &mut tx, channel_id)
+            .await?;
+        tx.commit().await?;
+        Ok(rows)
+    }
+
+    pub async fn perm_set_channel_override(
+        &self,
+        ctx: &RequestContext,
+        rec: &PermChannelOverrideRecord,
+    ) -> ControlResult<()> {
+        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
+        self.require(
+            &mut tx,
+            ctx,
+            Some(rec.channel_id),
+            rec.user_id,
+            Capability::ManageRoles,
+        )
+        .await?;
+        self.require(
+            &mut tx,
+            ctx,
+            Some(rec.channel_id),
+            rec.user_id,
+            Capability::ManageChannel,
+        )
+        .await?;
+        if let Some(target_user) = rec.user_id {
+            self.require_manageable_target_user(&mut tx, ctx, target_user)
+                .await?;
+        }
+        if let Some(ref role_id) = rec.role_id {
+            let _ = self.require_manageable_role(&mut tx, ctx, role_id).await?;
+        }
+        <R as ControlRepo>::perm_set_channel_override(&self.repo, &mut tx, rec).await?;
+        <R as ControlRepo>::insert_audit(&self.repo, &mut tx, &AuditEntry::new(ctx.server_id, Some(ctx.user_id), "perm.channel.override", "channel", rec.channel_id.0.to_string(), json!({"role_id": rec.role_id, "user_id": rec.user_id.map(|u| u.0), "cap": rec.cap, "effect": rec.effect}))).await?;
+        <R as ControlRepo>::insert_outbox(
+            &self.repo,
+            &mut tx,
+            &OutboxEvent {
+                id: OutboxId(Uuid::new_v4()),
+                server_id: ctx.server_id,
+                topic: "perm.audit.appended".to_string(),
+                payload_json: json!({"action": "perm.channel.override", "target_type": "channel", "target_id": rec.channel_id.0}),
+            },
+        )
+        .await?;
+        <R as ControlRepo>::insert_outbox(&self.repo, &mut tx, &OutboxEvent { id: OutboxId(Uuid::new_v4()), server_id: ctx.server_id, topic: "perm.channel.overrides_changed".to_string(), payload_json: json!({"channel_id": rec.channel_id.0, "role_id": rec.role_id, "user_id": rec.user_id.map(|u| u.0), "cap": rec.cap, "effect": rec.effect}) }).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn perm_audit_query(
+        &self,
+        ctx: &RequestContext,
+        limit: i64,
+    ) -> ControlResult<Vec<PermAuditRow>> {
+        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
+        self.require(&mut tx, ctx, None, None, Capability::ManageRoles)
+            .await?;
+        let rows = <R as ControlRepo>::perm_query_audit(
+            &self.repo,
+            &mut tx,
+            ctx.server_id,
+            limit.max(1).min(200),
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(rows)
+    }
+
+    pub async fn perm_eval_effective(
+        &self,
+        ctx: &RequestContext,
+        user_id: UserId,
+        channel_id: Option<ChannelId>,
+        caps: &[String],
+    ) -> ControlResult<Vec<(String, bool)>> {
+        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
+        self.require(
+            &mut tx,
+            ctx,
+            channel_id,
+            Some(user_id),
+            Capability::ManageRoles,
+        )
+        .await?;
+        let mut out = Vec::with_capacity(caps.len());
+        for cap in caps {
+            let c = Capability::from_str(cap)
+                .ok_or(ControlError::InvalidArgument("unknown capability"))?;
+            let req = PermissionRequest {
+                server_id: ctx.server_id,
+                user_id,
+                is_admin: false,
+                capability: c,
+                channel_id,
+                target_user_id: None,
+            };
+            let allowed = matches!(
+                <R as ControlRepo>::decide_permission(&self.repo, &mut tx, &req).await?,
+                Decision::Allow
+            );
+            out.push((cap.clone(), allowed));
+        }
+        tx.commit().await?;
+        Ok(out)
+    }
+
+    async fn actor_max_role_position(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        ctx: &RequestContext,
+    ) -> ControlResult<i32> {
+        <R as ControlRepo>::perm_actor_max_role_position(&self.repo, tx, ctx.server_id, ctx.user_id)
+            .await
+    }
+
+    async fn require_manageable_role(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        ctx: &RequestContext,
+        role_id: &str,
+    ) -> ControlResult<PermRoleRecord> {
+        let role = <R as ControlRepo>::perm_get_role(&self.repo, tx, ctx.server_id, role_id)
+            .await?
+            .ok_or(ControlError::NotFound("role"))?;
+        if role.is_everyone {
+            return Err(ControlError::FailedPrecondition(
+                "@everyone role is immutable for this action",
+            ));
+        }
+        if role.name.eq_ignore_ascii_case("owner") && !ctx.is_admin {
+            return Err(ControlError::PermissionDenied(
+                "owner role editable only by server owner",
+            ));
+        }
+        let actor_max = self.actor_max_role_position(tx, ctx).await?;
+        if role.role_position >= actor_max {
+            return Err(ControlError::PermissionDenied(
+                "can only manage roles below your highest role",
+            ));
+        }
+        Ok(role)
+    }
+
+    async fn require_manageable_target_user(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        ctx: &RequestContext,
+        target_user_id: UserId,
+    ) -> ControlResult<()> {
+        let actor_max = self.actor_max_role_position(tx, ctx).await?;
+        let target_max = <R as ControlRepo>::perm_user_max_role_position(
+            &self.repo,
+            tx,
+            ctx.server_id,
+            target_user_id,
+        )
+        .await?;
+        if target_max >= actor_max {
+            return Err(ControlError::PermissionDenied(
+                "target user is not below your highest role",
+            ));
+        }
+        Ok(())
+    }
+
+    // -------------------------------------------------------------------------
+    // Outbox helpers (optional – if your gateway uses these)
+    // -------------------------------------------------------------------------
+
+    pub async fn claim_outbox_batch(
+        &self,
+        server: ServerId,
+        limit: i64,
+    ) -> ControlResult<(Uuid, Vec<OutboxEventRow>)> {
+        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
+        let token = Uuid::new_v4();
+        let rows =
+            <R as ControlRepo>::claim_outbox_batch(&self.repo, &mut tx, server, token, limit)
+                .await?;
+        tx.commit().await?;
+        Ok((token, rows))
+    }
+
+    pub async fn ack_outbox_published(&self, token: Uuid, ids: &[OutboxId]) -> ControlResult<()> {
+        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
+        <R as ControlRepo>::ack_outbox_published(&self.repo, &mut tx, ids, token).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // -------------------------------------------------------------------------
+    // User profiles
+    // -------------------------------------------------------------------------
+
+    pub async fn get_user_profile(
+        &self,
+        ctx: &RequestContext,
+        target_user_id: UserId,
+    ) -> ControlResult<Option<UserProfileRow>> {
+        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
+        let row = <R as ControlRepo>::get_user_profile(
+            &self.repo,
+            &mut tx,
+            target_user_id,
+            ctx.server_id,
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(row)
+    }
+
+    pub async fn update_user_profile(
+        &self,
+        ctx: &RequestContext,
+        display_name: Option<String>,
+        description: Option<String>,
+        accent_color: Option<i32>,
+        links_json: Option<serde_json::Value>,
+    ) -> ControlResult<()> {
+        if let Some(ref dn) = display_name {
+            if dn.len() > 32 {
+                return Err(ControlError::InvalidArgument("display_name too long"));
+            }
+        }
+        if let Some(ref desc) = description {
+            if desc.len() > 190 {
+                return Err(ControlError::InvalidArgument("description too long"));
+            }
+        }
+        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
+        <R as ControlRepo>::upsert_user_profile(
+            &self.repo,
+            &mut tx,
+            ctx.user_id,
+            ctx.server_id,
+            display_name.as_deref(),
+            description.as_deref(),
+            accent_color,
+            None,
+            None,
+            None,
+            links_json,
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn set_avatar(&self, ctx: &RequestContext, avatar_url: &str) -> ControlResult<()> {
+        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
+        <R as ControlRepo>::set_profile_avatar(
+            &self.repo,
+            &mut tx,
+            ctx.user_id,
+            ctx.server_id,
+            avatar_url,
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn set_banner(&self, ctx: &RequestContext, banner_url: &str) -> ControlResult<()> {
+        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
+        <R as ControlRepo>::set_profile_banner(
+            &self.repo,
+            &mut tx,
+            ctx.user_id,
+            ctx.server_id,
+            banner_url,
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn begin_profile_asset_upload(
+        &self,
+        ctx: &RequestContext,
+        purpose: &str,
+        mime_type: &str,
+        byte_length: i64,
+    ) -> ControlResult<Uuid> {
+        const MAX_AVATAR_BYTES: i64 = 8 * 1024 * 1024;
+        const MAX_BANNER_BYTES: i64 = 10 * 1024 * 1024;
+
+        match purpose {
+            "profile_avatar" | "profile_banner" => {}
+            _ => return Err(ControlError::InvalidArgument("unknown purpose")),
+        }
+        match mime_type {
+            "image/png" | "image/jpeg" | "image/webp" => {}
+            _ => return Err(ControlError::InvalidArgument("unsupported mime_type")),
+        }
+        let limit = if purpose == "profile_avatar" {
+            MAX_AVATAR_BYTES
+        } else {
+            MAX_BANNER_BYTES
+        };
+        if byte_length <= 0 || byte_length > limit {
+            return Err(ControlError::InvalidArgument("byte_length out of range"));
+        }
+
+        let session_id = Uuid::new_v4();
+        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
+        <R as ControlRepo>::create_asset_upload_session(
+            &self.repo,
+            &mut tx,
+            session_id,
+            ctx.user_id,
+            ctx.server_id,
+            purpose,
+            mime_type,
+            byte_length,
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(session_id)
+    }
+
+    pub async fn store_verified_asset(
+        &self,
+        user_id: UserId,
+        session_id: Uuid,
+        asset_data: &[u8],
+    ) -> ControlResult<()> {
+        // Re-verify ownership.
+        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
+        let session =
+            <R as ControlRepo>::get_asset_upload_session(&self.repo, &mut tx, session_id, user_id)
+                .await?;
+        if session.is_none() {
+            return Err(ControlError::NotFound(
+                "asset upload session not found or expired",
+            ));
+        }
+        <R as ControlRepo>::store_verified_asset(&self.repo, &mut tx, session_id, asset_data)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn create_default_profile(
+        &self,
+        user_id: UserId,
+        server_id: ServerId,
+        display_name: &str,
+    ) -> ControlResult<()> {
+        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
+        <R as ControlRepo>::create_default_profile(
+            &self.repo,
+            &mut tx,
+            user_id,
+            server_id,
+            display_name,
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn get_user_badges(
+        &self,
+        ctx: &RequestContext,
+        target_user_id: UserId,
+    ) -> ControlResult<Vec<crate::model::UserBadgeRow>> {
+        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
+        let badges =
+            <R as ControlRepo>::get_user_badges(&self.repo, &mut tx, target_user_id, ctx.server_id)
+                .await?;
+        tx.commit().await?;
+        Ok(badges)
+    }
+
+    pub async fn create_badge(
+        &self,
+        ctx: &RequestContext,
+        id: &str,
+        label: &str,
+        icon_url: &str,
+        tooltip: &str,
+    ) -> ControlResult<crate::model::BadgeDefinitionRow> {
+        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
+        self.require(&mut tx, ctx, None, None, Capability::ManageBadges)
+            .await?;
+
+        let badge = crate::model::BadgeDefinitionRow {
+            id: id.to_string(),
+            server_id: ctx.server_id,
+            label: label.to_string(),
+            icon_url: icon_url.to_string(),
+            tooltip: tooltip.to_string(),
+            position: 0,
+        };
+        <R as ControlRepo>::create_badge_definition(&self.repo, &mut tx, &badge).await?;
+        tx.commit().await?;
+        Ok(badge)
+    }
+
+    pub async fn grant_badge(
+        &self,
+        ctx: &RequestContext,
+        user_id: UserId,
+        badge_id: &str,
+    ) -> ControlResult<()> {
+        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
+        self.require(&mut tx, ctx, None, None, Capability::ManageBadges)
+            .await?;
+        <R as ControlRepo>::grant_badge(&self.repo, &mut tx, user_id, badge_id, ctx.server_id)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn revoke_badge(
+        &self,
+        ctx: &RequestContext,
+        user_id: UserId,
+        badge_id: &str,
+    ) -> ControlResult<()> {
+        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
+        self.require(&mut tx, ctx, None, None, Capability::ManageBadges)
+            .await?;
+        <R as ControlRepo>::revoke_badge(&self.repo, &mut tx, user_id, badge_id, ctx.server_id)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn get_user_roles_display(
+        &self,
+        ctx: &RequestContext,
+        target_user_id: UserId,
+    ) -> ControlResult<Vec<crate::model::UserRoleRow>> {
+        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
+        let roles = <R as ControlRepo>::get_user_roles_display(
+            &self.repo,
+            &mut tx,
+            target_user_id,
+            ctx.server_id,
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(roles)
+    }
+
+    pub async fn verify_asset_ownership(
+        &self,
+        asset_id: &str,
+        user_id: UserId,
+    ) -> ControlResult<bool> {
+        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
+        let owned =
+            <R as ControlRepo>::verify_asset_ownership(&self.repo, &mut tx, asset_id, user_id)
+                .await?;
+        tx.commit().await?;
+        Ok(owned)
+    }
+
+    pub async fn get_asset_upload_session(
+        &self,
+        user_id: UserId,
+        session_id: Uuid,
+    ) -> ControlResult<Option<AssetUploadSession>> {
+        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
+        let session =
+            <R as ControlRepo>::get_asset_upload_session(&self.repo, &mut tx, session_id, user_id)
+                .await?;
+        tx.commit().await?;
+        Ok(session)
+    }
+
+    pub async fn set_custom_status(
+        &self,
+        ctx: &RequestContext,
+        status_text: Option<String>,
+        status_emoji: Option<String>,
+        status_expires: Option<Option<chrono::DateTime<chrono::Utc>>>,
+    ) -> ControlResult<()> {
+        if let Some(ref t) = status_text {
+            if t.len() > 128 {
+                return Err(ControlError::InvalidArgument("status_text too long"));
+            }
+        }
+        if let Some(ref e) = status_emoji {
+            if e.len() > 64 {
+                return Err(ControlError::InvalidArgument("status_emoji too long"));
+            }
+        }
+        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
+        <R as ControlRepo>::upsert_user_profile(
+            &self.repo,
+            &mut tx,
+            ctx.user_id,
+            ctx.server_id,
+            None,
+            None,
+            None,
+            status_text.as_deref(),
+            status_emoji.as_deref(),
+            status_expires,
+            None,
+        )
+        .await?;
+
+        let member_channels = <R as ControlRepo>::list_member_channels_for_user(
+            &self.repo,
+            &mut tx,
+            ctx.server_id,
+            ctx.user_id,
+        )
+        .await?;
+        let custom_status_text = status_text.unwrap_or_default();
+        let custom_status_emoji = status_emoji.unwrap_or_default();
+        let custom_status_expires_ms = status_expires
+            .flatten()
+            .map(|dt| dt.timestamp_millis());
+        for channel_id in member_channels {
+            <R as ControlRepo>::insert_outbox(
+                &self.repo,
+                &mut tx,
+                &OutboxEvent {
+                    id: OutboxId(Uuid::new_v4()),
+                    server_id: ctx.server_id,
+                    topic: "presence.user_online_status_changed".to_string(),
+                    payload_json: json!({
+                        "channel_id": channel_id.0,
+                        "user_id": ctx.user_id.0,
+                        "custom_status_text": custom_status_text,
+                        "custom_status_emoji": custom_status_emoji,
+                        "custom_status_expires_ms": custom_status_expires_ms,
+                    }),
+                },
+            )
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Clear expired custom statuses and emit outbox events for each affected user.
+    pub async fn clear_expired_statuses(&self, server_id: ServerId) -> ControlResult<()> {
+        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
+        let cleared = <R as ControlRepo>::clear_expired_custom_statuses(&self.repo, &mut tx).await?;
+
+        for (user_id, _sid) in &cleared {
+            let member_channels = <R as ControlRepo>::list_member_channels_for_user(
+                &self.repo,
+                &mut tx,
+                server_id,
+                *user_id,
+            )
+            .await?;
+            for channel_id in member_channels {
+                <R as ControlRepo>::insert_outbox(
+                    &self.repo,
+                    &mut tx,
+                    &OutboxEvent {
+                        id: OutboxId(Uuid::new_v4()),
+                        server_id,
+                        topic: "presence.user_online_status_changed".to_string(),
+                        payload_json: json!({
+                            "channel_id": channel_id.0,
+                            "user_id": user_id.0,
+                            "custom_status_text": "",
+                            "custom_status_emoji": "",
+                            "custom_status_expires_ms": null,
+                        }),
+                    },
+                )
+                .await?;
+            }
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // -------------------------------------------------------------------------
+    // Permission gate
+    // -------------------------------------------------------------------------
+
+    async fn require(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        ctx: &RequestContext,
+        channel_id: Option<ChannelId>,
+        target_user_id: Option<UserId>,
+        capability: Capability,
+    ) -> ControlResult<()> {
+        let req = PermissionRequest {
+            server_id: ctx.server_id,
+            user_id: ctx.user_id,
+            is_admin: ctx.is_admin,
+            capability,
+            channel_id,
+            target_user_id,
+        };
+
+        match <R as ControlRepo>::decide_permission(&self.repo, tx, &req).await? {
+            Decision::Allow => Ok(()),
+            Decision::Deny => Err(ControlError::PermissionDenied("permission denied")),
+        }
+    }
+}
+
+fn chat_message_posted_payload(rec: &ChatMessage) -> serde_json::Value {
+    json!({
+        "message_id": rec.id.0,
+        "channel_id": rec.channel_id.0,
+        "author_user_id": rec.author_user_id.0,
+        "text": rec.text,
+        "attachments": rec.attachments,
+        "kind": rec.kind.as_str(),
+        "created_at": rec.created_at,
+    })
+}
+
+fn chat_message_edited_payload(
+    rec: &ChatMessage,
+    edited_at: chrono::DateTime<Utc>,
+) -> serde_json::Value {
+    json!({
+        "message_id": rec.id.0,
+        "channel_id": rec.channel_id.0,
+        "author_user_id": rec.author_user_id.0,
+        "text": rec.text,
+        "edited_at": edited_at,
+    })
+}
+
+fn chat_message_deleted_payload(
+    rec: &ChatMessage,
+    deleted_at: chrono::DateTime<Utc>,
+) -> serde_json::Value {
+    json!({
+        "message_id": rec.id.0,
+        "channel_id": rec.channel_id.0,
+        "author_user_id": rec.author_user_id.0,
+        "deleted_at": deleted_at,
+    })
+}
+
+fn presence_announcement_text(display_name: &str, joined: bool) -> String {
+    if joined {
+        format!("{display_name} joined the channel")
+    } else {
+        format!("{display_name} left the channel")
+    }
+}
+
+fn channel_state_refresh_payload(ch: &Channel, members: &[Member]) -> serde_json::Value {
+    json!({
+        "channel_id": ch.id.0,
+        "name": ch.name,
+        "max_members": ch.max_members,
+        "max_talkers": ch.max_talkers,
+        "members": members
+            .iter()
+            .map(|m| {
+                json!({
+                    "user_id": m.user_id.0,
+                    "display_name": m.display_name,
+                    "muted": m.muted,
+                    "deafened": m.deafened,
+                    "away_message": m.custom_status_text,
+                    "custom_status_emoji": m.custom_status_emoji,
+                })
+            })
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// Which creation limit, if any, blocks another channel. The per-user cap
+/// does not apply to admins.
+fn channel_creation_limit(
+    config: &ControlConfig,
+    is_admin: bool,
+    server_channels: i64,
+    user_channels: i64,
+) -> Option<&'static str> {
+    if config
+        .max_channels_per_server
+        .is_some_and(|max| server_channels >= i64::from(max))
+    {
+        return Some("server channel limit reached");
+    }
+    if !is_admin
+        && config
+            .max_channels_per_user
+            .is_some_and(|max| user_channels >= i64::from(max))
+    {
+        return Some("user channel limit reached");
+    }
+    None
+}
+
+/// An ephemeral channel is idle once it is empty and `updated_at` (bumped on
+/// every leave) is at least `idle_for` in the past.
+fn ephemeral_channel_is_idle(
+    ch: &Channel,
+    member_count: i64,
+    now: chrono::DateTime<Utc>,
+    idle_for: chrono::Duration,
+) -> bool {
+    ch.ephemeral && member_count == 0 && now - ch.updated_at >= idle_for
+}
+
+/// Keep the first `limit` of `members` (in list order) and return the
+/// cursor for the rest, or `None` when nothing was cut.
+pub fn split_member_page(mut members: Vec<Member>, limit: usize) -> (Vec<Member>, Option<String>) {
+    if members.len() <= limit {
+        return (members, None);
+    }
+    members.truncate(limit);
+    let next = members
+        .last()
+        .map(|last| MemberCursor::after(last).encode());
+    (members, next)
+}
+
+/// Whole seconds left before a slow-mode channel accepts another message from
+/// a user whose last one was sent at `last`; `None` once they may post.
+fn slow_mode_retry_after(
+    slow_mode_secs: i32,
+    last: Option<chrono::DateTime<Utc>>,
+    now: chrono::DateTime<Utc>,
+) -> Option<u32> {
+    let remaining = last? + chrono::Duration::seconds(i64::from(slow_mode_secs)) - now;
+    let millis = remaining.num_milliseconds();
+    (millis > 0).then(|| (millis as u64).div_ceil(1000) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::perms::Effect;
+    use crate::repo::PgControlRepo;
+    use sqlx::PgPool;
+
+    /// A service over a freshly migrated `VP_DATABASE_URL` database, an admin
+    /// context on a new server, and the pool; `None` when the variable is
+    /// unset, so database tests skip.
+    async fn test_service(
+    ) -> anyhow::Result<Option<(ControlService<PgControlRepo>, RequestContext, PgPool)>> {
+        let Ok(url) = std::env::var("VP_DATABASE_URL") else {
+            return Ok(None);
+        };
+        let pool = PgPool::connect(&url).await?;
+        sqlx::migrate!("./migrations").run(&pool).await?;
+        let admin = RequestContext {
+            server_id: ServerId(Uuid::new_v4()),
+            user_id: UserId(Uuid::new_v4()),
+            is_admin: true,
+        };
+        let svc = ControlService::new(PgControlRepo::new(pool.clone()));
+        Ok(Some((svc, admin, pool)))
+    }
+
+    async fn create_test_channel(
+        svc: &ControlService<PgControlRepo>,
+        ctx: &RequestContext,
+        name: &str,
+        max_members: Option<i32>,
+    ) -> ControlResult<Channel> {
+        svc.create_channel(
+            ctx,
+            ChannelCreate {
+                name: name.into(),
+                parent_id: None,
+                max_members,
+                max_talkers: None,
+                channel_type: 0,
+                description: String::new(),
+                bitrate_bps: 64_000,
+                opus_profile: 1,
+                ephemeral: false,
+            },
+        )
+        .await
+    }
+
+    #[test]
+    fn member_pages_split_with_a_round_trippable_cursor() {
+        let member = |i: i64| Member {
+            channel_id: ChannelId(Uuid::nil()),
+            user_id: UserId(Uuid::new_v4()),
+            display_name: format!("m{i}"),
+            muted: false,
+            deafened: false,
+            joined_at: chrono::DateTime::from_timestamp_micros(1_700_000_000_000_000 + i).unwrap(),
+            custom_status_text: String::new(),
+            custom_status_emoji: String::new(),
+        };
+        let members = (0..5).map(member).collect::<Vec<_>>();
+
+        let (page, next) = split_member_page(members.clone(), 5);
+        assert_eq!(page.len(), 5);
+        assert_eq!(next, None);
+
+        let (page, next) = split_member_page(members.clone(), 3);
+        assert_eq!(page.len(), 3);
+        let cursor = MemberCursor::parse(&next.expect("more members")).expect("valid cursor");
+        assert_eq!(cursor, MemberCursor::after(&members[2]));
+
+        assert_eq!(MemberCursor::parse("not-a-cursor"), None);
+        assert_eq!(MemberCursor::parse("12:not-a-uuid"), None);
+    }
+
+    #[test]
+    fn channel_creation_limits() {
+        let config = ControlConfig {
+            max_channels_per_server: Some(100),
+            max_channels_per_user: Some(5),
+            ..ControlConfig::default()
+        };
+
+        assert_eq!(channel_creation_limit(&config, false, 99, 4), None);
+        assert_eq!(
+            channel_creation_limit(&config, false, 100, 0),
+            Some("server channel limit reached")
+        );
+        assert_eq!(
+            channel_creation_limit(&config, false, 10, 5),
+            Some("user channel limit reached")
+        );
+        assert_eq!(channel_creation_limit(&config, true, 10, 5), None);
+        assert_eq!(
+            channel_creation_limit(&config, true, 100, 0),
+            Some("server channel limit reached")
+        );
+        assert_eq!(
+            channel_creation_limit(&ControlConfig::default(), false, i64::MAX, i64::MAX),
+            None
+        );
+    }
+
+    #[test]
+    fn ephemeral_channel_idle_threshold() {
+        let now = Utc::now();
+        let idle_for = chrono::Duration::minutes(10);
+        let mut ch = Channel {
+            id: ChannelId(Uuid::new_v4()),
+            server_id: ServerId(Uuid::new_v4()),
+            name: "temp".into(),
+            parent_id: None,
+            max_members: None,
+            max_talkers: None,
+            channel_type: 0,
+            description: String::new(),
+            bitrate_bps: 64_000,
+            opus_profile: 1,
+            ephemeral: true,
+            created_by: None,
+            max_message_length: None,
+            slow_mode_secs: 0,
+            announce_presence: false,
+            created_at: now - chrono::Duration::hours(1),
+            updated_at: now - chrono::Duration::minutes(9),
+        };
+
+        assert!(!ephemeral_channel_is_idle(&ch, 0, now, idle_for));
+        ch.updated_at = now - idle_for;
+        assert!(ephemeral_channel_is_idle(&ch, 0, now, idle_for));
+        assert!(!ephemeral_channel_is_idle(&ch, 1, now, idle_for));
+        ch.ephemeral = false;
+        assert!(!ephemeral_channel_is_idle(&ch, 0, now, idle_for));
+    }
+
+    #[test]
+    fn slow_mode_rounds_remaining_wait_up() {
+        let now = Utc::now();
+        assert_eq!(slow_mode_retry_after(10, None, now), None);
+        assert_eq!(
+            slow_mode_retry_after(10, Some(now - chrono::Duration::milliseconds(5_500)), now),
+            Some(5)
+        );
+        assert_eq!(
+            slow_mode_retry_after(10, Some(now - chrono::Duration::milliseconds(9_999)), now),
+            Some(1)
+        );
+        assert_eq!(
+            slow_mode_retry_after(10, Some(now - chrono::Duration::seconds(10)), now),
+            None
+        );
+        assert_eq!(slow_mode_retry_after(0, Some(now), now), None);
+    }
+
+    #[tokio::test]
+    async fn concurrent_joins_cannot_overfill_channel() -> anyhow::Result<()> {
+        let Some((svc, admin, _)) = test_service().await? else {
+            return Ok(());
+        };
+        let ch = create_test_channel(&svc, &admin, "last-slot", Some(1)).await?;
+        let channel_id = ch.id;
+
+        for _ in 0..10 {
+            let joiners: Vec<RequestContext> = (0..2)
+                .map(|_| RequestContext {
+                    server_id: admin.server_id,
+                    user_id: UserId(Uuid::new_v4()),
+                    is_admin: true,
+                })
+                .collect();
+            let join = |ctx: RequestContext| {
+                let svc = svc.clone();
+                tokio::spawn(async move {
+                    svc.join_channel(
+                        &ctx,
+                        JoinChannel {
+                            channel_id,
+                            display_name: "racer".into(),
+                        },
+                    )
+                    .await
+                })
+            };
+            let (a, b) = tokio::join!(join(joiners[0]), join(joiners[1]));
+            let results = [a?, b?];
+            assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 1);
+            assert!(results
+                .iter()
+                .any(|r| matches!(r, Err(ControlError::ResourceExhausted(_)))));
+
+            // The winner can rejoin its own slot; then free it for the next round.
+            let winner = joiners[usize::from(results[0].is_err())];
+            svc.join_channel(
+                &winner,
+                JoinChannel {
+                    channel_id,
+                    display_name: "racer".into(),
+                },
+            )
+            .await?;
+            svc.leave_channel(&winner, channel_id).await?;
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn bans_are_server_scoped_and_block_rejoining() -> anyhow::Result<()> {
+        let Some((svc, admin, _)) = test_service().await? else {
+            return Ok(());
+        };
+        let repo = svc.repo().clone();
+        let target = RequestContext {
+            user_id: UserId(Uuid::new_v4()),
+            ..admin
+        };
+        let moderator = RequestContext {
+            user_id: UserId(Uuid::new_v4()),
+            is_admin: false,
+            ..admin
+        };
+        let ch = create_test_channel(&svc, &admin, "banned-from", None).await?;
+        let channel_id = ch.id;
+        let join = |ctx: RequestContext| {
+            let svc = svc.clone();
+            async move {
+                svc.join_channel(
+                    &ctx,
+                    JoinChannel {
+                        channel_id,
+                        display_name: "member".into(),
+                    },
+                )
+                .await
+            }
+        };
+        join(target).await?;
+
+        // A channel override granting moderation must not escalate to a
+        // server-wide ban.
+        let mut tx = <PgControlRepo as ControlRepo>::tx(&repo).await?;
+        <PgControlRepo as ControlRepo>::perm_set_channel_override(
+            &repo,
+            &mut tx,
+            &PermChannelOverrideRecord {
+                channel_id: ch.id,
+                role_id: None,
+                user_id: Some(moderator.user_id),
+                cap: Capability::ModerateMembers.as_str().into(),
+                effect: Effect::Grant.as_str().into(),
+            },
+        )
+        .await?;
+        let channel_scoped = PermissionRequest {
+            server_id: admin.server_id,
+            user_id: moderator.user_id,
+            is_admin: false,
+            capability: Capability::ModerateMembers,
+            channel_id: Some(ch.id),
+            target_user_id: Some(target.user_id),
+        };
+        assert_eq!(
+            <PgControlRepo as ControlRepo>::decide_permission(&repo, &mut tx, &channel_scoped)
+                .await?,
+            Decision::Allow
+        );
+        tx.commit().await?;
+        assert!(matches!(
+            svc.ban_member(&moderator, ch.id, target.user_id, None, 0)
+                .await,
+            Err(ControlError::PermissionDenied(_))
+        ));
+
+        assert!(matches!(
+            svc.ban_member(&admin, ch.id, admin.user_id, None, 0).await,
+            Err(ControlError::InvalidArgument(_))
+        ));
+        svc.ban_member(&admin, ch.id, target.user_id, Some("spam".into()), 60)
+            .await?;
+
+        let is_banned = |server_id: ServerId, at: chrono::DateTime<Utc>| {
+            let repo = repo.clone();
+            async move {
+                let mut tx = <PgControlRepo as ControlRepo>::tx(&repo).await?;
+                <PgControlRepo as ControlRepo>::is_banned(
+                    &repo,
+                    &mut tx,
+                    server_id,
+                    target.user_id,
+                    at,
+                )
+                .await
+            }
+        };
+        let now = Utc::now();
+        assert!(is_banned(admin.server_id, now).await?);
+        assert!(!is_banned(admin.server_id, now + chrono::Duration::seconds(61)).await?);
+        assert!(!is_banned(ServerId(Uuid::new_v4()), now).await?);
+
+        // What the gateway loads into its ban cache at startup.
+        let bans = svc.list_active_bans(admin.server_id).await?;
+        assert_eq!(bans.len(), 1);
+        assert_eq!(bans[0].user_id, target.user_id);
+        assert!(bans[0].expires_at.is_some_and(|at| at > now));
+        let elsewhere = svc.list_active_bans(ServerId(Uuid::new_v4())).await?;
+        assert!(elsewhere.is_empty());
+
+        let (members, _) = svc.list_members_page(&admin, ch.id, None, 50).await?;
+        assert!(members.iter().all(|m| m.user_id != target.user_id));
+        assert!(matches!(
+            join(target).await,
+            Err(ControlError::PermissionDenied("banned"))
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn decide_permission_applies_channel_and_role_precedence() -> anyhow::Result<()> {
+        let Some((svc, admin, _)) = test_service().await? else {
+            return Ok(());
+        };
+        let repo = svc.repo().clone();
+        let user = UserId(Uuid::new_v4());
+        let ch = create_test_channel(&svc, &admin, "precedence", None).await?;
+
+        let mut tx = <PgControlRepo as ControlRepo>::tx(&repo).await?;
+        let mut role_ids = Vec::new();
+        for (name, position) in [("everyone", 0), ("held", 1), ("other", 2)] {
+            let role = <PgControlRepo as ControlRepo>::perm_upsert_role(
+                &repo,
+                &mut tx,
+                admin.server_id,
+                None,
+                name,
+                0,
+                position,
+            )
+            .await?;
+            role_ids.push(role.role_id);
+        }
+        let [everyone, held, other]: [String; 3] = role_ids.try_into().expect("three roles");
+        sqlx::query("UPDATE roles SET is_everyone = TRUE WHERE id = $1")
+            .bind(&everyone)
+            .execute(&mut *tx)
+            .await?;
+        <PgControlRepo as ControlRepo>::perm_replace_user_roles(
+            &repo,
+            &mut tx,
+            admin.server_id,
+            user,
+            std::slice::from_ref(&held),
+        )
+        .await?;
+        let caps = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+            pairs
+                .iter()
+                .map(|(cap, effect)| (cap.to_string(), effect.to_string()))
+                .collect()
+        };
+        for (role_id, pairs) in [
+            (
+                &everyone,
+                caps(&[
+                    ("speak", "grant"),
+                    ("stream", "grant"),
+                    ("join_channel", "deny"),
+                ]),
+            ),
+            (&held, caps(&[("speak", "deny"), ("upload", "grant")])),
+            (
+                &other,
+                caps(&[("upload", "deny"), ("send_message", "grant")]),
+            ),
+        ] {
+            <PgControlRepo as ControlRepo>::perm_replace_role_caps(&repo, &mut tx, role_id, &pairs)
+                .await?;
+        }
+        for (role_id, user_id, cap, effect) in [
+            // A channel grant beats the held role's deny...
+            (None, Some(user), "speak", "grant"),
+            // ...and a deny on @everyone.
+            (Some(everyone.clone()), None, "join_channel", "grant"),
+            // A channel deny on a held role beats the @everyone grant.
+            (Some(held.clone()), None, "stream", "deny"),
+            // Overwrites on roles the user doesn't hold are ignored.
+            (Some(other.clone()), None, "upload", "deny"),
+            // A channel grant beats the implicit default deny.
+            (Some(everyone.clone()), None, "send_message", "grant"),
+        ] {
+            <PgControlRepo as ControlRepo>::perm_set_channel_override(
+                &repo,
+                &mut tx,
+                &PermChannelOverrideRecord {
+                    channel_id: ch.id,
+                    role_id,
+                    user_id,
+                    cap: cap.into(),
+                    effect: effect.into(),
+                },
+            )
+            .await?;
+        }
+
+        let channel = Some(ch.id);
+        for (capability, channel_id, expected) in [
+            (Capability::Speak, channel, Decision::Allow),
+            (Capability::Speak, None, Decision::Deny),
+            (Capability::JoinChannel, channel, Decision::Allow),
+            (Capability::JoinChannel, None, Decision::Deny),
+            (Capability::Stream, channel, Decision::Deny),
+            (Capability::Stream, None, Decision::Allow),
+            (Capability::Upload, channel, Decision::Allow),
+            (Capability::SendMessage, channel, Decision::Allow),
+            (Capability::SendMessage, None, Decision::Deny),
+            (Capability::ManageRoles, channel, Decision::Deny),
+        ] {
+            let req = PermissionRequest {
+                server_id: admin.server_id,
+                user_id: user,
+                is_admin: false,
+                capability: capability.clone(),
+                channel_id,
+                target_user_id: None,
+            };
+            assert_eq!(
+                <PgControlRepo as ControlRepo>::decide_permission(&repo, &mut tx, &req).await?,
+                expected,
+                "{capability:?} in {channel_id:?}"
+            );
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rejoining_a_channel_is_idempotent() -> anyhow::Result<()> {
+        let Some((svc, ctx, pool)) = test_service().await? else {
+            return Ok(());
+        };
+        let ch = create_test_channel(&svc, &ctx, "double-click", None).await?;
+        let join = || {
+            svc.join_channel(
+                &ctx,
+                JoinChannel {
+                    channel_id: ch.id,
+                    display_name: "clicker".into(),
+                },
+            )
+        };
+        let first = join().await?;
+        let second = join().await?;
+        assert_eq!(first.len(), 1);
+        assert_eq!(second.len(), 1);
+        assert_eq!(second[0].user_id, ctx.user_id);
+
+        let (joined_events,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM outbox_events WHERE server_id = $1 AND topic = 'presence.member_joined'",
+        )
+        .bind(ctx.server_id.0)
+        .fetch_one(&pool)
+        .await?;
+        assert_eq!(joined_events, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_markers_only_move_forward() -> anyhow::Result<()> {
+        let Some((svc, ctx, _)) = test_service().await? else {
+            return Ok(());
+        };
+        let repo = svc.repo().clone();
+        let svc = svc.with_config(ControlConfig {
+            read_receipts: true,
+            ..ControlConfig::default()
+        });
+
+        let ch = create_test_channel(&svc, &ctx, "receipts", None).await?;
+        svc.join_channel(
+            &ctx,
+            JoinChannel {
+                channel_id: ch.id,
+                display_name: "reader".into(),
+            },
+        )
+        .await?;
+        let mut sent = Vec::new();
+        for text in ["first", "second"] {
+            let msg = svc
+                .send_message(
+                    &ctx,
+                    SendMessage {
+                        channel_id: ch.id,
+                        text: text.into(),
+                        attachments: None,
+                    },
+                )
+                .await?;
+            sent.push(msg.id);
+        }
+
+        let (marker, advanced) = svc.mark_read(&ctx, ch.id, sent[1]).await?;
+        assert!(advanced);
+        assert_eq!(marker.message_id, sent[1]);
+        let (marker, advanced) = svc.mark_read(&ctx, ch.id, sent[0]).await?;
+        assert!(!advanced);
+        assert_eq!(marker.message_id, sent[1]);
+        assert!(matches!(
+            svc.mark_read(&ctx, ch.id, MessageId(Uuid::new_v4())).await,
+            Err(ControlError::NotFound("message"))
+        ));
+
+        let markers = svc.list_read_markers(&ctx, ch.id).await?;
+        assert_eq!(markers.len(), 1);
+        assert_eq!(markers[0].user_id, ctx.user_id);
+
+        let disabled = ControlService::new(repo);
+        assert!(matches!(
+            disabled.list_read_markers(&ctx, ch.id).await,
+            Err(ControlError::FailedPrecondition(_))
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn unique_channel_names_reject_sibling_duplicates() -> anyhow::Result<()> {
+        let Some((svc, ctx, _)) = test_service().await? else {
+            return Ok(());
+        };
+        let repo = svc.repo().clone();
+        let svc = svc.with_config(ControlConfig {
+            unique_channel_names: true,
+            ..ControlConfig::default()
+        });
+
+        let lobby = create_test_channel(&svc, &ctx, "Lobby", None).await?;
+        assert!(matches!(
+            create_test_channel(&svc, &ctx, " lobby ", None).await,
+            Err(ControlError::AlreadyExists("channel name"))
+        ));
+        let games = create_test_channel(&svc, &ctx, "Games", None).await?;
+        assert!(matches!(
+            svc.rename_channel(&ctx, games.id, "LOBBY").await,
+            Err(ControlError::AlreadyExists("channel name"))
+        ));
+        // Renaming a channel to its own name in another case is not a clash.
+        assert_eq!(
+            svc.rename_channel(&ctx, lobby.id, "lobby").await?.name,
+            "lobby"
+        );
+
+        // Names only have to be unique among siblings.
+        let nested = svc
+            .create_channel(
+                &ctx,
+                ChannelCreate {
+                    name: "Lobby".into(),
+                    parent_id: Some(games.id),
+                    max_members: None,
+                    max_talkers: None,
+                    channel_type: 0,
+                    description: String::new(),
+                    bitrate_bps: 64_000,
+                    opus_profile: 1,
+                    ephemeral: false,
+                },
+            )
             .await?;
+        assert_eq!(nested.parent_id, Some(games.id));
 
-        let badge = crate::model::BadgeDefinitionRow {
-            id: id.to_string(),
-            server_id: ctx.server_id,
-            label: label.to_string(),
-            icon_url: icon_url.to_string(),
-            tooltip: tooltip.to_string(),
-            position: 0,
-        };
-        <R as ControlRepo>::create_badge_definition(&self.repo, &mut tx, &badge).await?;
-        tx.commit().await?;
-        Ok(badge)
+        let relaxed = ControlService::new(repo);
+        create_test_channel(&relaxed, &ctx, "Lobby", None).await?;
+        relaxed.rename_channel(&ctx, games.id, "lobby").await?;
+        Ok(())
     }
 
-    pub async fn grant_badge(
-        &self,
-        ctx: &RequestContext,
-        user_id: UserId,
-        badge_id: &str,
-    ) -> ControlResult<()> {
-        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
-        self.require(&mut tx, ctx, None, None, Capability::ManageBadges)
-            .await?;
-        <R as ControlRepo>::grant_badge(&self.repo, &mut tx, user_id, badge_id, ctx.server_id)
-            .await?;
-        tx.commit().await?;
+    #[tokio::test]
+    async fn disconnect_session_checks_permission_before_presence() -> anyhow::Result<()> {
+        let Some((svc, ctx, _pool)) = test_service().await? else {
+            return Ok(());
+        };
+        let other = RequestContext {
+            user_id: UserId(Uuid::new_v4()),
+            is_admin: false,
+            ..ctx
+        };
+        let target = UserId(Uuid::new_v4());
+
+        assert!(matches!(
+            svc.disconnect_session(&other, target, "", || panic!("presence probed"))
+                .await,
+            Err(ControlError::PermissionDenied(_))
+        ));
+        assert!(matches!(
+            svc.disconnect_session(&ctx, target, "", || false).await,
+            Err(ControlError::NotFound("session"))
+        ));
+        svc.disconnect_session(&ctx, target, "", || true).await?;
         Ok(())
     }
 
-    pub async fn revoke_badge(
-        &self,
-        ctx: &RequestContext,
-        user_id: UserId,
-        badge_id: &str,
-    ) -> ControlResult<()> {
-        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
-        self.require(&mut tx, ctx, None, None, Capability::ManageBadges)
-            .await?;
-        <R as ControlRepo>::revoke_badge(&self.repo, &mut tx, user_id, badge_id, ctx.server_id)
+    #[tokio::test]
+    async fn only_author_or_moderator_edits_and_deletes_messages() -> anyhow::Result<()> {
+        let Some((svc, ctx, pool)) = test_service().await? else {
+            return Ok(());
+        };
+        let other = RequestContext {
+            user_id: UserId(Uuid::new_v4()),
+            is_admin: false,
+            ..ctx
+        };
+        let ch = create_test_channel(&svc, &ctx, "edits", None).await?;
+        svc.join_channel(
+            &ctx,
+            JoinChannel {
+                channel_id: ch.id,
+                display_name: "author".into(),
+            },
+        )
+        .await?;
+        let msg = svc
+            .send_message(
+                &ctx,
+                SendMessage {
+                    channel_id: ch.id,
+                    text: "helo".into(),
+                    attachments: None,
+                },
+            )
             .await?;
-        tx.commit().await?;
+        let edit = |text: &str| EditMessage {
+            channel_id: ch.id,
+            message_id: msg.id,
+            text: text.into(),
+        };
+
+        assert!(matches!(
+            svc.edit_message(&other, edit("pwned")).await,
+            Err(ControlError::PermissionDenied(_))
+        ));
+        assert!(matches!(
+            svc.delete_message(&other, ch.id, msg.id).await,
+            Err(ControlError::PermissionDenied(_))
+        ));
+
+        let edited = svc.edit_message(&ctx, edit(" hello ")).await?;
+        assert_eq!(edited.text, "hello");
+        let (text, was_edited) = sqlx::query_as::<_, (String, bool)>(
+            "SELECT text, edited_at IS NOT NULL FROM chat_messages WHERE id = $1",
+        )
+        .bind(msg.id.0)
+        .fetch_one(&pool)
+        .await?;
+        assert_eq!(text, "hello");
+        assert!(was_edited);
+
+        svc.delete_message(&ctx, ch.id, msg.id).await?;
+        assert!(matches!(
+            svc.edit_message(&ctx, edit("again")).await,
+            Err(ControlError::NotFound("message"))
+        ));
+        assert!(matches!(
+            svc.delete_message(&ctx, ch.id, msg.id).await,
+            Err(ControlError::NotFound("message"))
+        ));
         Ok(())
     }
 
-    pub async fn get_user_roles_display(
-        &self,
-        ctx: &RequestContext,
-        target_user_id: UserId,
-    ) -> ControlResult<Vec<crate::model::UserRoleRow>> {
-        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
-        let roles = <R as ControlRepo>::get_user_roles_display(
-            &self.repo,
-            &mut tx,
-            target_user_id,
-            ctx.server_id,
+    #[tokio::test]
+    async fn reactions_are_deduped_and_member_only() -> anyhow::Result<()> {
+        let Some((svc, ctx, pool)) = test_service().await? else {
+            return Ok(());
+        };
+        let outsider = RequestContext {
+            user_id: UserId(Uuid::new_v4()),
+            ..ctx
+        };
+        let ch = create_test_channel(&svc, &ctx, "reactions", None).await?;
+        svc.join_channel(
+            &ctx,
+            JoinChannel {
+                channel_id: ch.id,
+                display_name: "reactor".into(),
+            },
         )
         .await?;
-        tx.commit().await?;
-        Ok(roles)
-    }
+        let msg = svc
+            .send_message(
+                &ctx,
+                SendMessage {
+                    channel_id: ch.id,
+                    text: "react to me".into(),
+                    attachments: None,
+                },
+            )
+            .await?;
+        let reaction_counts = || async {
+            sqlx::query_scalar::<_, serde_json::Value>(
+                "SELECT payload_json -> 'count' FROM outbox_events \
+                 WHERE topic = 'chat.reaction_changed' AND payload_json ->> 'message_id' = $1 \
+                 ORDER BY created_at",
+            )
+            .bind(msg.id.0.to_string())
+            .fetch_all(&pool)
+            .await
+        };
 
-    pub async fn verify_asset_ownership(
-        &self,
-        asset_id: &str,
-        user_id: UserId,
-    ) -> ControlResult<bool> {
-        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
-        let owned =
-            <R as ControlRepo>::verify_asset_ownership(&self.repo, &mut tx, asset_id, user_id)
-                .await?;
-        tx.commit().await?;
-        Ok(owned)
+        assert!(svc.add_reaction(&ctx, ch.id, msg.id, "👍").await?);
+        assert!(!svc.add_reaction(&ctx, ch.id, msg.id, " 👍 ").await?);
+        assert!(matches!(
+            svc.add_reaction(&outsider, ch.id, msg.id, "👍").await,
+            Err(ControlError::PermissionDenied(_))
+        ));
+        assert_eq!(reaction_counts().await?, [json!(1)]);
+
+        assert!(svc.remove_reaction(&ctx, ch.id, msg.id, "👍").await?);
+        assert!(!svc.remove_reaction(&ctx, ch.id, msg.id, "👍").await?);
+        assert_eq!(reaction_counts().await?, [json!(1), json!(0)]);
+        Ok(())
     }
 
-    pub async fn get_asset_upload_session(
-        &self,
-        user_id: UserId,
-        session_id: Uuid,
-    ) -> ControlResult<Option<AssetUploadSession>> {
-        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
-        let session =
-            <R as ControlRepo>::get_asset_upload_session(&self.repo, &mut tx, session_id, user_id)
+    #[tokio::test]
+    async fn message_history_pages_backwards_oldest_first() -> anyhow::Result<()> {
+        let Some((svc, ctx, _)) = test_service().await? else {
+            return Ok(());
+        };
+        let ch = create_test_channel(&svc, &ctx, "history", None).await?;
+        svc.join_channel(
+            &ctx,
+            JoinChannel {
+                channel_id: ch.id,
+                display_name: "historian".into(),
+            },
+        )
+        .await?;
+        let mut sent = Vec::new();
+        for i in 0..5 {
+            let msg = svc
+                .send_message(
+                    &ctx,
+                    SendMessage {
+                        channel_id: ch.id,
+                        text: format!("m{i}"),
+                        attachments: None,
+                    },
+                )
                 .await?;
-        tx.commit().await?;
-        Ok(session)
+            sent.push(msg.id);
+        }
+        svc.delete_message(&ctx, ch.id, sent[1]).await?;
+        svc.add_reaction(&ctx, ch.id, sent[3], "👍").await?;
+        let texts = |page: &[ChatHistoryEntry]| {
+            page.iter()
+                .map(|e| e.message.text.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let newest = svc.list_messages(&ctx, ch.id, None, 2).await?;
+        assert_eq!(texts(&newest), ["m3", "m4"]);
+        assert_eq!(newest[0].reactions.len(), 1);
+        assert_eq!(newest[0].reactions[0].count, 1);
+        assert!(newest[0].reactions[0].me);
+        assert!(newest[1].reactions.is_empty());
+
+        let older = svc
+            .list_messages(&ctx, ch.id, Some(newest[0].message.id), 2)
+            .await?;
+        assert_eq!(texts(&older), ["m0", "m2"]);
+        let oldest = svc
+            .list_messages(&ctx, ch.id, Some(older[0].message.id), 0)
+            .await?;
+        assert!(oldest.is_empty());
+        // The deleted message still works as an anchor.
+        let from_deleted = svc.list_messages(&ctx, ch.id, Some(sent[1]), 0).await?;
+        assert_eq!(texts(&from_deleted), ["m0"]);
+        Ok(())
     }
 
-    pub async fn set_custom_status(
-        &self,
-        ctx: &RequestContext,
-        status_text: Option<String>,
-        status_emoji: Option<String>,
-        status_expires: Option<Option<chrono::DateTime<chrono::Utc>>>,
-    ) -> ControlResult<()> {
-        if let Some(ref t) = status_text {
-            if t.len() > 128 {
-                return Err(ControlError::InvalidArgument("status_text too long"));
-            }
-        }
-        if let Some(ref e) = status_emoji {
-            if e.len() > 64 {
-                return Err(ControlError::InvalidArgument("status_emoji too long"));
-            }
-        }
-        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
-        <R as ControlRepo>::upsert_user_profile(
-            &self.repo,
-            &mut tx,
-            ctx.user_id,
-            ctx.server_id,
-            None,
-            None,
-            None,
-            status_text.as_deref(),
-            status_emoji.as_deref(),
-            status_expires,
-            None,
+    #[tokio::test]
+    async fn channel_chat_limits_apply_to_send_message() -> anyhow::Result<()> {
+        let Some((svc, ctx, _)) = test_service().await? else {
+            return Ok(());
+        };
+        let ch = create_test_channel(&svc, &ctx, "slow", None).await?;
+        svc.join_channel(
+            &ctx,
+            JoinChannel {
+                channel_id: ch.id,
+                display_name: "writer".into(),
+            },
         )
         .await?;
 
-        let member_channels = <R as ControlRepo>::list_member_channels_for_user(
-            &self.repo,
-            &mut tx,
-            ctx.server_id,
-            ctx.user_id,
-        )
-        .await?;
-        let custom_status_text = status_text.unwrap_or_default();
-        let custom_status_emoji = status_emoji.unwrap_or_default();
-        let custom_status_expires_ms = status_expires
-            .flatten()
-            .map(|dt| dt.timestamp_millis());
-        for channel_id in member_channels {
-            <R as ControlRepo>::insert_outbox(
-                &self.repo,
-                &mut tx,
-                &OutboxEvent {
-                    id: OutboxId(Uuid::new_v4()),
-                    server_id: ctx.server_id,
-                    topic: "presence.user_online_status_changed".to_string(),
-                    payload_json: json!({
-                        "channel_id": channel_id.0,
-                        "user_id": ctx.user_id.0,
-                        "custom_status_text": custom_status_text,
-                        "custom_status_emoji": custom_status_emoji,
-                        "custom_status_expires_ms": custom_status_expires_ms,
-                    }),
+        assert!(matches!(
+            svc.set_channel_chat_limits(&ctx, ch.id, Some(0), 0, false)
+                .await,
+            Err(ControlError::InvalidArgument(_))
+        ));
+        let updated = svc
+            .set_channel_chat_limits(&ctx, ch.id, Some(5), 30, false)
+            .await?;
+        assert_eq!(
+            (updated.max_message_length, updated.slow_mode_secs),
+            (Some(5), 30)
+        );
+
+        let send = |text: &str| {
+            svc.send_message(
+                &ctx,
+                SendMessage {
+                    channel_id: ch.id,
+                    text: text.into(),
+                    attachments: None,
                 },
             )
-            .await?;
+        };
+        assert!(matches!(
+            send("too long").await,
+            Err(ControlError::InvalidArgument("message too long"))
+        ));
+        send("hi").await?;
+        match send("again").await {
+            Err(ControlError::RateLimited {
+                reason: "slow mode",
+                retry_after_secs,
+            }) => assert!((29..=30).contains(&retry_after_secs)),
+            other => panic!("expected slow mode rejection, got {other:?}"),
         }
 
-        tx.commit().await?;
+        svc.set_channel_chat_limits(&ctx, ch.id, None, 0, false)
+            .await?;
+        send("again").await?;
         Ok(())
     }
 
-    /// Clear expired custom statuses and emit outbox events for each affected user.
-    pub async fn clear_expired_statuses(&self, server_id: ServerId) -> ControlResult<()> {
-        let mut tx = <R as ControlRepo>::tx(&self.repo).await?;
-        let cleared = <R as ControlRepo>::clear_expired_custom_statuses(&self.repo, &mut tx).await?;
-
-        for (user_id, _sid) in &cleared {
-            let member_channels = <R as ControlRepo>::list_member_channels_for_user(
-                &self.repo,
-                &mut tx,
-                server_id,
-                *user_id,
+    #[tokio::test]
+    async fn member_pages_walk_the_whole_channel() -> anyhow::Result<()> {
+        let Some((svc, ctx, _)) = test_service().await? else {
+            return Ok(());
+        };
+        let ch = create_test_channel(&svc, &ctx, "crowded", None).await?;
+        for i in 0..3 {
+            let member = RequestContext {
+                user_id: UserId(Uuid::new_v4()),
+                ..ctx
+            };
+            svc.join_channel(
+                &member,
+                JoinChannel {
+                    channel_id: ch.id,
+                    display_name: format!("member{i}"),
+                },
             )
             .await?;
-            for channel_id in member_channels {
-                <R as ControlRepo>::insert_outbox(
-                    &self.repo,
-                    &mut tx,
-                    &OutboxEvent {
-                        id: OutboxId(Uuid::new_v4()),
-                        server_id,
-                        topic: "presence.user_online_status_changed".to_string(),
-                        payload_json: json!({
-                            "channel_id": channel_id.0,
-                            "user_id": user_id.0,
-                            "custom_status_text": "",
-                            "custom_status_emoji": "",
-                            "custom_status_expires_ms": null,
-                        }),
-                    },
-                )
-                .await?;
-            }
         }
 
-        tx.commit().await?;
+        let (first, next) = svc.list_members_page(&ctx, ch.id, None, 2).await?;
+        assert_eq!(first.len(), 2);
+        let next = next.expect("a second page");
+        let (second, last) = svc.list_members_page(&ctx, ch.id, Some(&next), 2).await?;
+        assert_eq!(second.len(), 1);
+        assert_eq!(last, None);
+        assert!(!first.iter().any(|m| m.user_id == second[0].user_id));
+
+        assert!(matches!(
+            svc.list_members_page(&ctx, ch.id, Some("bogus"), 2).await,
+            Err(ControlError::InvalidArgument(_))
+        ));
         Ok(())
     }
 
-    // -------------------------------------------------------------------------
-    // Permission gate
-    // -------------------------------------------------------------------------
+    #[tokio::test]
+    async fn presence_announcements_post_system_messages() -> anyhow::Result<()> {
+        let Some((svc, ctx, pool)) = test_service().await? else {
+            return Ok(());
+        };
+        let ch = create_test_channel(&svc, &ctx, "lobby", None).await?;
+        let join = || {
+            svc.join_channel(
+                &ctx,
+                JoinChannel {
+                    channel_id: ch.id,
+                    display_name: "greeter".into(),
+                },
+            )
+        };
+        let system_texts = || async {
+            sqlx::query_scalar::<_, String>(
+                "SELECT text FROM chat_messages WHERE channel_id = $1 AND kind = 'system' ORDER BY created_at",
+            )
+            .bind(ch.id.0)
+            .fetch_all(&pool)
+            .await
+        };
 
-    async fn require(
-        &self,
-        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
-        ctx: &RequestContext,
-        channel_id: Option<ChannelId>,
-        target_user_id: Option<UserId>,
-        capability: Capability,
-    ) -> ControlResult<()> {
-        let req = PermissionRequest {
-            server_id: ctx.server_id,
-            user_id: ctx.user_id,
-            is_admin: ctx.is_admin,
-            capability,
-            channel_id,
-            target_user_id,
+        // Off by default.
+        join().await?;
+        svc.leave_channel(&ctx, ch.id).await?;
+        assert!(system_texts().await?.is_empty());
+
+        svc.set_channel_chat_limits(&ctx, ch.id, None, 30, true)
+            .await?;
+        join().await?;
+        svc.leave_channel(&ctx, ch.id).await?;
+        assert_eq!(
+            system_texts().await?,
+            ["greeter joined the channel", "greeter left the channel"]
+        );
+
+        // Announcements don't count toward the user's slow mode.
+        join().await?;
+        svc.send_message(
+            &ctx,
+            SendMessage {
+                channel_id: ch.id,
+                text: "hello".into(),
+                attachments: None,
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn move_member_relocates_and_respects_target_limit() -> anyhow::Result<()> {
+        let Some((svc, admin, _)) = test_service().await? else {
+            return Ok(());
+        };
+        let target = RequestContext {
+            user_id: UserId(Uuid::new_v4()),
+            ..admin
+        };
+        let from = create_test_channel(&svc, &admin, "from", None).await?.id;
+        let to = create_test_channel(&svc, &admin, "to", Some(1)).await?.id;
+        let members = |channel_id| {
+            let svc = svc.clone();
+            async move {
+                svc.list_members_page(&admin, channel_id, None, 100)
+                    .await
+                    .map(|(page, _)| page)
+            }
+        };
+        let join = |ctx: RequestContext, channel_id| {
+            let svc = svc.clone();
+            async move {
+                svc.join_channel(
+                    &ctx,
+                    JoinChannel {
+                        channel_id,
+                        display_name: "mover".into(),
+                    },
+                )
+                .await
+            }
         };
+        join(target, from).await?;
+        svc.set_voice_mute(&admin, from, target.user_id, true, None)
+            .await?;
 
-        match <R as ControlRepo>::decide_permission(&self.repo, tx, &req).await? {
-            Decision::Allow => Ok(()),
-            Decision::Deny => Err(ControlError::PermissionDenied("permission denied")),
-        }
+        // Full destination: nothing changes.
+        join(admin, to).await?;
+        assert!(matches!(
+            svc.move_member(&admin, from, target.user_id, to).await,
+            Err(ControlError::ResourceExhausted(_))
+        ));
+        assert_eq!(members(from).await?.len(), 1);
+
+        svc.leave_channel(&admin, to).await?;
+        let moved = svc.move_member(&admin, from, target.user_id, to).await?;
+        assert_eq!(moved.channel_id, to);
+        assert!(moved.muted);
+        assert!(members(from).await?.is_empty());
+        let in_to = members(to).await?;
+        assert_eq!(in_to.len(), 1);
+        assert_eq!(in_to[0].user_id, target.user_id);
+
+        assert!(matches!(
+            svc.move_member(&admin, from, target.user_id, to).await,
+            Err(ControlError::NotFound("member"))
+        ));
+        Ok(())
     }
 }
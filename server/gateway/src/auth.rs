@@ -1,4 +1,5 @@
 use anyhow::{anyhow, Context, Result};
+use sha2::{Digest, Sha256};
 use sqlx::{Pool, Postgres, Row};
 use tracing::warn;
 
@@ -6,6 +7,7 @@ use crate::bootstrap::{
     ensure_baseline_role_assignment, ensure_core_roles, ensure_owner_exists, BootstrapConfig,
     OwnerBootstrapPolicy,
 };
+use crate::jwt::{Claims, JwtVerifier};
 use crate::proto::voiceplatform::v1 as pb;
 
 #[derive(Debug, Clone)]
@@ -24,6 +26,9 @@ pub trait AuthProvider: Send + Sync + 'static {
         session_id: &str,
         auth_challenge: &[u8],
     ) -> Result<AuthedIdentity>;
+
+    /// Methods this provider accepts, advertised in `ServerInfo.auth_methods`.
+    fn methods(&self) -> Vec<pb::AuthMethod>;
 }
 
 #[derive(Debug, Clone)]
@@ -115,6 +120,149 @@ impl AuthProvider for DeviceAuthProvider {
             _ => Err(anyhow!("unsupported auth method in device provider")),
         }
     }
+
+    fn methods(&self) -> Vec<pb::AuthMethod> {
+        vec![pb::AuthMethod::Device]
+    }
+}
+
+/// Accepts OIDC id tokens (`AuthRequest.oidc_token`) signed by a trusted
+/// issuer. Each `(iss, sub)` pair maps to one local user, created on first
+/// sign-in just like a new device.
+#[derive(Debug, Clone)]
+pub struct JwtAuthProvider {
+    pool: Pool<Postgres>,
+    default_server_id: uuid::Uuid,
+    bootstrap: BootstrapConfig,
+    verifier: JwtVerifier,
+    admin_role: Option<String>,
+}
+
+impl JwtAuthProvider {
+    pub fn new(
+        pool: Pool<Postgres>,
+        default_server_id: uuid::Uuid,
+        bootstrap: BootstrapConfig,
+        verifier: JwtVerifier,
+        admin_role: Option<String>,
+    ) -> Self {
+        Self {
+            pool,
+            default_server_id,
+            bootstrap,
+            verifier,
+            admin_role,
+        }
+    }
+
+    /// Verify `token` as of `now_unix` and check it is meant for this server.
+    fn verify_token(&self, token: &str, now_unix: i64) -> Result<Claims> {
+        let claims = self.verifier.verify(token, now_unix)?;
+        if let Some(server_id) = claims.server_id.as_deref() {
+            if uuid::Uuid::parse_str(server_id).ok() != Some(self.default_server_id) {
+                return Err(anyhow!("token is for another server"));
+            }
+        }
+        Ok(claims)
+    }
+}
+
+#[async_trait::async_trait]
+impl AuthProvider for JwtAuthProvider {
+    async fn authenticate(
+        &self,
+        req: &pb::AuthRequest,
+        _session_id: &str,
+        _auth_challenge: &[u8],
+    ) -> Result<AuthedIdentity> {
+        let Some(pb::auth_request::Method::OidcToken(oidc)) = req.method.as_ref() else {
+            return Err(anyhow!("unsupported auth method in jwt provider"));
+        };
+        let claims = self.verify_token(oidc.id_token.trim(), chrono::Utc::now().timestamp())?;
+
+        let display_name = claims
+            .name
+            .as_deref()
+            .or(claims.preferred_username.as_deref())
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| {
+                let digest = Sha256::digest(claims.sub.as_bytes());
+                format!("user-{}", &hex::encode(digest)[..8])
+            });
+        let user_id = lookup_or_create_user_for_oidc(
+            &self.pool,
+            self.default_server_id,
+            self.bootstrap,
+            &claims.iss,
+            &claims.sub,
+            &display_name,
+        )
+        .await?;
+
+        let role_admin = self
+            .admin_role
+            .as_ref()
+            .is_some_and(|role| claims.roles.contains(role));
+        let is_admin =
+            role_admin || is_user_admin(&self.pool, self.default_server_id, user_id).await?;
+
+        Ok(AuthedIdentity {
+            user_id: user_id.to_string(),
+            server_id: self.default_server_id.to_string(),
+            display_name,
+            is_admin,
+        })
+    }
+
+    fn methods(&self) -> Vec<pb::AuthMethod> {
+        vec![pb::AuthMethod::OidcToken]
+    }
+}
+
+/// Routes each `AuthRequest` to the provider for its method. Device auth is
+/// always available; OIDC tokens only once a JWKS is configured.
+pub struct AuthRouter {
+    device: DeviceAuthProvider,
+    jwt: Option<JwtAuthProvider>,
+}
+
+impl AuthRouter {
+    pub fn new(device: DeviceAuthProvider, jwt: Option<JwtAuthProvider>) -> Self {
+        Self { device, jwt }
+    }
+}
+
+#[async_trait::async_trait]
+impl AuthProvider for AuthRouter {
+    async fn authenticate(
+        &self,
+        req: &pb::AuthRequest,
+        session_id: &str,
+        auth_challenge: &[u8],
+    ) -> Result<AuthedIdentity> {
+        match req.method.as_ref() {
+            Some(pb::auth_request::Method::Device(_)) => {
+                self.device
+                    .authenticate(req, session_id, auth_challenge)
+                    .await
+            }
+            Some(pb::auth_request::Method::OidcToken(_)) => match &self.jwt {
+                Some(jwt) => jwt.authenticate(req, session_id, auth_challenge).await,
+                None => Err(anyhow!("token sign-in is not enabled on this server")),
+            },
+            None => Err(anyhow!("missing auth method")),
+        }
+    }
+
+    fn methods(&self) -> Vec<pb::AuthMethod> {
+        let mut methods = self.device.methods();
+        if let Some(jwt) = &self.jwt {
+            methods.extend(jwt.methods());
+        }
+        methods
+    }
 }
 
 async fn lookup_or_create_user_for_device(
@@ -277,6 +425,84 @@ async fn lookup_or_create_user_for_device(
     Ok(user_id)
 }
 
+async fn lookup_or_create_user_for_oidc(
+    pool: &Pool<Postgres>,
+    server_id: uuid::Uuid,
+    bootstrap_cfg: BootstrapConfig,
+    issuer: &str,
+    subject: &str,
+    display_name: &str,
+) -> Result<uuid::Uuid> {
+    let mut tx = pool.begin().await.context("begin oidc auth tx")?;
+
+    ensure_core_roles(&mut tx, server_id).await?;
+
+    let existing = sqlx::query(
+        r#"
+        UPDATE auth_oidc_identities
+        SET last_seen = now()
+        WHERE issuer = $1 AND subject = $2
+        RETURNING user_id
+        "#,
+    )
+    .bind(issuer)
+    .bind(subject)
+    .fetch_optional(&mut *tx)
+    .await
+    .context("lookup oidc identity")?;
+
+    let user_id = match existing {
+        Some(row) => row.try_get::<uuid::Uuid, _>("user_id")?,
+        None => {
+            let user_id = uuid::Uuid::new_v4();
+            sqlx::query(
+                r#"
+                INSERT INTO auth_users (user_id)
+                VALUES ($1)
+                "#,
+            )
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .context("insert auth user")?;
+
+            let inserted = sqlx::query(
+                r#"
+                INSERT INTO auth_oidc_identities (issuer, subject, user_id)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (issuer, subject) DO NOTHING
+                "#,
+            )
+            .bind(issuer)
+            .bind(subject)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .context("insert oidc identity")?;
+            if inserted.rows_affected() == 0 {
+                // Lost a race with a concurrent first sign-in; the client's
+                // reconnect will find the winner's row.
+                return Err(anyhow!("concurrent first sign-in for this identity"));
+            }
+            create_default_profile(&mut tx, server_id, user_id, display_name).await;
+            user_id
+        }
+    };
+
+    ensure_baseline_role_assignment(&mut tx, server_id, user_id).await?;
+    ensure_owner_exists(
+        &mut tx,
+        server_id,
+        Some(user_id),
+        bootstrap_cfg.bootstrap_owner_user_id,
+        bootstrap_cfg.owner_bootstrap_policy,
+    )
+    .await?;
+
+    tx.commit().await.context("commit oidc auth")?;
+    Ok(user_id)
+}
+
 /// Best-effort auto-creation of a default profile row for a user.
 async fn create_default_profile(
     tx: &mut sqlx::Transaction<'_, Postgres>,
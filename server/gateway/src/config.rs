@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use clap::Parser;
 
 use crate::bootstrap::OwnerBootstrapPolicy;
@@ -21,6 +23,12 @@ pub struct Config {
     #[arg(long)]
     pub tls_key_pem: Option<String>,
 
+    /// How often to check the TLS cert/key files for changes, in seconds.
+    /// New handshakes pick up a renewed certificate without a restart;
+    /// 0 disables reloading. Ignored for self-signed certs.
+    #[arg(long, env = "VP_TLS_RELOAD_INTERVAL_SECS", default_value_t = 60)]
+    pub tls_reload_interval_secs: u64,
+
     /// Extra Subject Alternative Names (SANs) for generated self-signed certs.
     /// Repeat the flag for multiple values (IP or DNS), e.g.:
     /// --tls-self-signed-san 192.168.1.220 --tls-self-signed-san 136.38.142.63
@@ -51,6 +59,44 @@ pub struct Config {
     #[arg(long, default_value = "0.0.0.0:9100")]
     pub metrics_listen: String,
 
+    /// Prefix for every exported metric name.
+    #[arg(long, env = "VP_METRICS_NAMESPACE", default_value = "vp")]
+    pub metrics_namespace: String,
+
+    /// Static labels stamped on every metric, as `key=value,key=value` (max 8).
+    #[arg(long, env = "VP_METRICS_LABELS", default_value = "")]
+    pub metrics_labels: String,
+
+    /// Require `Authorization: Bearer <token>` on the metrics endpoint.
+    #[arg(long, env = "VP_METRICS_BEARER_TOKEN")]
+    pub metrics_bearer_token: Option<String>,
+
+    /// Require HTTP basic auth on the metrics endpoint, as `username:password`.
+    #[arg(
+        long,
+        env = "VP_METRICS_BASIC_AUTH",
+        conflicts_with = "metrics_bearer_token"
+    )]
+    pub metrics_basic_auth: Option<String>,
+
+    /// Record forwarded voice to per-talker Ogg Opus files in this directory.
+    /// Unset disables recording.
+    #[arg(long, env = "VP_VOICE_ARCHIVE_DIR")]
+    pub voice_archive_dir: Option<String>,
+
+    /// How voice reaches listeners. `sfu` forwards each talker's packets to
+    /// every listener as-is. `mix` decodes talkers and sends every listener
+    /// one re-encoded stream, trading server CPU for fanout that no longer
+    /// grows with the number of talkers; see `benches/mix_vs_sfu.rs` in
+    /// vp-media for the cost.
+    #[arg(
+        long,
+        env = "VP_VOICE_FORWARD_MODE",
+        value_enum,
+        default_value_t = VoiceForwardMode::Sfu
+    )]
+    pub voice_forward_mode: VoiceForwardMode,
+
     /// Outbox poll interval in milliseconds
     #[arg(long, default_value_t = 200)]
     pub outbox_poll_ms: u64,
@@ -63,6 +109,32 @@ pub struct Config {
     #[arg(long, default_value_t = 30)]
     pub outbox_claim_ttl_s: i64,
 
+    /// Failed deliveries after which an outbox record is dead-lettered
+    #[arg(long, default_value_t = 5)]
+    pub outbox_max_attempts: i32,
+
+    /// JWKS file holding the public keys that sign accepted OIDC id tokens.
+    /// Unset leaves device-key sign-in as the only auth method.
+    #[arg(
+        long,
+        env = "VP_JWT_JWKS_PATH",
+        requires_all = ["jwt_issuer", "jwt_audience"]
+    )]
+    pub jwt_jwks_path: Option<String>,
+
+    /// Required `iss` claim of accepted id tokens.
+    #[arg(long, env = "VP_JWT_ISSUER")]
+    pub jwt_issuer: Option<String>,
+
+    /// Required `aud` claim of accepted id tokens (usually the client id).
+    #[arg(long, env = "VP_JWT_AUDIENCE")]
+    pub jwt_audience: Option<String>,
+
+    /// Entry in a token's `roles` claim that marks the user as an admin, on
+    /// top of admin/owner roles granted on the server itself.
+    #[arg(long, env = "VP_JWT_ADMIN_ROLE")]
+    pub jwt_admin_role: Option<String>,
+
     /// Dev mode: accept dev token "dev" (NEVER enable in production)
     #[arg(long, default_value_t = default_dev_mode())]
     pub dev_mode: bool,
@@ -71,14 +143,79 @@ pub struct Config {
     #[arg(long, default_value_t = 10_000)]
     pub max_connections: usize,
 
+    /// Seconds to wait on shutdown for clients to disconnect after being told
+    /// the server is going away, before remaining connections are closed
+    #[arg(long, env = "VP_SHUTDOWN_GRACE_SECS", default_value_t = 10)]
+    pub shutdown_grace_secs: u64,
+
     /// Max Postgres pool connections.
     #[arg(long, env = "VP_DB_POOL_MAX_CONNECTIONS", default_value_t = 32)]
     pub db_pool_max_connections: u32,
 
+    /// Interval in seconds between Postgres health probes feeding `vp_gateway_db_up` (0 = disabled)
+    #[arg(long, env = "VP_DB_HEALTH_INTERVAL_SECS", default_value_t = 5)]
+    pub db_health_interval_secs: u64,
+
     /// Interval in seconds between orphan upload file scans (0 = disabled)
     #[arg(long, default_value_t = 3600)]
     pub orphan_scan_interval_secs: u64,
 
+    /// Maximum channels on the server (0 = unlimited)
+    #[arg(long, env = "VP_MAX_CHANNELS_PER_SERVER", default_value_t = 0)]
+    pub max_channels_per_server: u32,
+
+    /// Maximum channels a single non-admin user may create (0 = unlimited)
+    #[arg(long, env = "VP_MAX_CHANNELS_PER_USER", default_value_t = 0)]
+    pub max_channels_per_user: u32,
+
+    /// Reject channel names already used by a sibling channel (case-insensitive)
+    #[arg(long, env = "VP_UNIQUE_CHANNEL_NAMES", default_value_t = false)]
+    pub unique_channel_names: bool,
+
+    /// Record per-user read markers and share them with channel members (opt-in for privacy)
+    #[arg(long, env = "VP_READ_RECEIPTS", default_value_t = false)]
+    pub read_receipts: bool,
+
+    /// Seconds an ephemeral channel may sit empty before it is deleted (0 = disabled)
+    #[arg(long, default_value_t = 300)]
+    pub ephemeral_channel_idle_secs: u64,
+
+    /// Seconds a cached channel with nobody in it is kept before eviction (0 = never evict)
+    #[arg(long, env = "VP_MEMBERSHIP_CACHE_IDLE_SECS", default_value_t = 600)]
+    pub membership_cache_idle_secs: u64,
+
+    /// Cached channels kept before empty ones are evicted early, oldest first (0 = unlimited)
+    #[arg(long, env = "VP_MEMBERSHIP_CACHE_MAX_CHANNELS", default_value_t = 4096)]
+    pub membership_cache_max_channels: usize,
+
+    /// Per-connection bidi streams opened per second before the connection is closed (0 = disabled)
+    #[arg(long, env = "VP_CONN_MAX_STREAMS_PER_SEC", default_value_t = 20)]
+    pub conn_max_streams_per_sec: u32,
+
+    /// Per-connection datagrams per second before the connection is closed (0 = disabled)
+    #[arg(long, env = "VP_CONN_MAX_DATAGRAMS_PER_SEC", default_value_t = 4000)]
+    pub conn_max_datagrams_per_sec: u32,
+
+    /// Per-connection datagram bytes per second before the connection is closed (0 = disabled)
+    #[arg(long, env = "VP_CONN_MAX_DATAGRAM_BYTES_PER_SEC", default_value_t = 4 * 1024 * 1024)]
+    pub conn_max_datagram_bytes_per_sec: u64,
+
+    /// Forward voice only from users joined to a voice or streaming channel;
+    /// senders sitting in a text channel are dropped.
+    #[arg(
+        long,
+        env = "VP_VOICE_REQUIRES_JOIN",
+        default_value_t = true,
+        action = clap::ArgAction::Set
+    )]
+    pub voice_requires_join: bool,
+
+    /// Milliseconds between loudest-talker pushes to voice channel members.
+    /// 0 disables publishing and stops advertising voice loudness, so clients
+    /// leave the loudness byte out of their voice headers.
+    #[arg(long, env = "VP_VOICE_LOUDNESS_INTERVAL_MS", default_value_t = 250)]
+    pub voice_loudness_interval_ms: u64,
+
     /// Quinn per-connection total bytes buffered for received-but-not-yet-consumed datagrams.
     ///
     /// In quinn 0.11 this also influences the peer-advertised max datagram frame size.
@@ -90,6 +227,56 @@ pub struct Config {
         default_value_t = 32 * 1024
     )]
     pub quic_datagram_recv_buffer_bytes: usize,
+
+    /// QUIC congestion controller applied to every connection.
+    ///
+    /// Voice rides unreliable datagrams that are paced by the codec, not by
+    /// the window, but they still share the connection's congestion budget
+    /// with control streams and screen-share video. Cubic (quinn's default)
+    /// backs off hard on random loss; BBR models bandwidth and RTT instead and
+    /// usually keeps more headroom on lossy or high-latency paths, at the cost
+    /// of being less fair to competing loss-based flows. NewReno is the
+    /// conservative reference point for comparisons.
+    #[arg(
+        long,
+        env = "VP_QUIC_CONGESTION_CONTROLLER",
+        value_enum,
+        default_value_t = CongestionController::Cubic
+    )]
+    pub quic_congestion_controller: CongestionController,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, clap::ValueEnum)]
+pub enum VoiceForwardMode {
+    Sfu,
+    Mix,
+}
+
+impl VoiceForwardMode {
+    pub fn forward_mode(self) -> vp_media::voice_forwarder::ForwardMode {
+        match self {
+            VoiceForwardMode::Sfu => vp_media::voice_forwarder::ForwardMode::Sfu,
+            VoiceForwardMode::Mix => vp_media::voice_forwarder::ForwardMode::Mix,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, clap::ValueEnum)]
+pub enum CongestionController {
+    Cubic,
+    Bbr,
+    #[value(name = "newreno")]
+    NewReno,
+}
+
+impl CongestionController {
+    pub fn factory(self) -> Arc<dyn quinn::congestion::ControllerFactory + Send + Sync> {
+        match self {
+            CongestionController::Cubic => Arc::new(quinn::congestion::CubicConfig::default()),
+            CongestionController::Bbr => Arc::new(quinn::congestion::BbrConfig::default()),
+            CongestionController::NewReno => Arc::new(quinn::congestion::NewRenoConfig::default()),
+        }
+    }
 }
 
 fn default_dev_mode() -> bool {
@@ -106,7 +293,7 @@ fn default_owner_bootstrap_policy() -> OwnerBootstrapPolicy {
 
 #[cfg(test)]
 mod tests {
-    use super::Config;
+    use super::{Config, CongestionController, VoiceForwardMode};
     use clap::Parser;
 
     #[test]
@@ -114,4 +301,89 @@ mod tests {
         let cfg = Config::parse_from(["vp-gateway", "--database-url", "postgres://dummy"]);
         assert_eq!(cfg.quic_datagram_recv_buffer_bytes, 32 * 1024);
     }
+
+    #[test]
+    fn voice_forward_mode_defaults_to_sfu() {
+        let cfg = Config::parse_from(["vp-gateway", "--database-url", "postgres://dummy"]);
+        assert_eq!(cfg.voice_forward_mode, VoiceForwardMode::Sfu);
+
+        let cfg = Config::parse_from([
+            "vp-gateway",
+            "--database-url",
+            "postgres://dummy",
+            "--voice-forward-mode",
+            "mix",
+        ]);
+        assert_eq!(cfg.voice_forward_mode, VoiceForwardMode::Mix);
+    }
+
+    #[test]
+    fn congestion_controller_defaults_to_cubic_and_parses_names() {
+        let cfg = Config::parse_from(["vp-gateway", "--database-url", "postgres://dummy"]);
+        assert_eq!(cfg.quic_congestion_controller, CongestionController::Cubic);
+
+        for (name, expected) in [
+            ("bbr", CongestionController::Bbr),
+            ("newreno", CongestionController::NewReno),
+        ] {
+            let cfg = Config::parse_from([
+                "vp-gateway",
+                "--database-url",
+                "postgres://dummy",
+                "--quic-congestion-controller",
+                name,
+            ]);
+            assert_eq!(cfg.quic_congestion_controller, expected);
+        }
+        assert!(Config::try_parse_from([
+            "vp-gateway",
+            "--database-url",
+            "postgres://dummy",
+            "--quic-congestion-controller",
+            "vegas",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn jwt_jwks_requires_issuer_and_audience() {
+        let cfg = Config::parse_from(["vp-gateway", "--database-url", "postgres://dummy"]);
+        assert!(cfg.jwt_jwks_path.is_none());
+
+        let base = [
+            "vp-gateway",
+            "--database-url",
+            "postgres://dummy",
+            "--jwt-jwks-path",
+            "jwks.json",
+        ];
+        assert!(Config::try_parse_from(base).is_err());
+        assert!(Config::try_parse_from(base.into_iter().chain(["--jwt-issuer", "i"])).is_err());
+
+        let cfg = Config::parse_from(base.into_iter().chain([
+            "--jwt-issuer",
+            "https://idp.example",
+            "--jwt-audience",
+            "tsod",
+        ]));
+        assert_eq!(cfg.jwt_issuer.as_deref(), Some("https://idp.example"));
+        assert_eq!(cfg.jwt_audience.as_deref(), Some("tsod"));
+    }
+
+    #[test]
+    fn metrics_auth_flags_are_mutually_exclusive() {
+        let cfg = Config::parse_from(["vp-gateway", "--database-url", "postgres://dummy"]);
+        assert!(cfg.metrics_bearer_token.is_none() && cfg.metrics_basic_auth.is_none());
+
+        let res = Config::try_parse_from([
+            "vp-gateway",
+            "--database-url",
+            "postgres://dummy",
+            "--metrics-bearer-token",
+            "t",
+            "--metrics-basic-auth",
+            "u:p",
+        ]);
+        assert!(res.is_err());
+    }
 }
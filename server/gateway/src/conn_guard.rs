@@ -0,0 +1,169 @@
+//! Connection-level abuse guard.
+//!
+//! Counts streams and datagrams per QUIC connection in fixed one-second
+//! windows. A connection that exceeds any configured threshold is closed with
+//! [`CLOSE_CODE_ABUSE`]; the per-sender voice rate limiter only drops packets,
+//! this closes the door on clients that flood regardless of payload.
+
+use std::time::{Duration, Instant};
+
+/// Application close code sent when a connection trips a threshold.
+pub const CLOSE_CODE_ABUSE: u32 = 0x1a;
+
+const WINDOW: Duration = Duration::from_secs(1);
+
+/// Per-connection thresholds; 0 disables a check.
+#[derive(Clone, Copy, Debug)]
+pub struct ConnLimits {
+    pub max_streams_per_sec: u32,
+    pub max_datagrams_per_sec: u32,
+    pub max_datagram_bytes_per_sec: u64,
+}
+
+impl ConnLimits {
+    #[cfg(test)]
+    pub const UNLIMITED: Self = Self {
+        max_streams_per_sec: 0,
+        max_datagrams_per_sec: 0,
+        max_datagram_bytes_per_sec: 0,
+    };
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Violation {
+    StreamRate,
+    DatagramRate,
+    DatagramBytes,
+}
+
+impl Violation {
+    /// Metric label and close reason.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Violation::StreamRate => "stream_rate",
+            Violation::DatagramRate => "datagram_rate",
+            Violation::DatagramBytes => "datagram_bytes",
+        }
+    }
+}
+
+struct Window {
+    start: Instant,
+    count: u64,
+    bytes: u64,
+}
+
+impl Window {
+    fn new(now: Instant) -> Self {
+        Self {
+            start: now,
+            count: 0,
+            bytes: 0,
+        }
+    }
+
+    fn add(&mut self, bytes: u64, now: Instant) {
+        if now.saturating_duration_since(self.start) >= WINDOW {
+            *self = Self::new(now);
+        }
+        self.count += 1;
+        self.bytes += bytes;
+    }
+}
+
+/// Counters for one connection. Streams and datagrams are accepted on
+/// different tasks, so each task owns its own instance and only feeds the
+/// side it sees.
+pub struct ConnAccounting {
+    limits: ConnLimits,
+    streams: Window,
+    datagrams: Window,
+    pub streams_total: u64,
+    pub datagrams_total: u64,
+    pub datagram_bytes_total: u64,
+}
+
+impl ConnAccounting {
+    pub fn new(limits: ConnLimits, now: Instant) -> Self {
+        Self {
+            limits,
+            streams: Window::new(now),
+            datagrams: Window::new(now),
+            streams_total: 0,
+            datagrams_total: 0,
+            datagram_bytes_total: 0,
+        }
+    }
+
+    pub fn on_stream(&mut self, now: Instant) -> Result<(), Violation> {
+        self.streams_total += 1;
+        self.streams.add(0, now);
+        let max = u64::from(self.limits.max_streams_per_sec);
+        if max > 0 && self.streams.count > max {
+            return Err(Violation::StreamRate);
+        }
+        Ok(())
+    }
+
+    pub fn on_datagram(&mut self, len: usize, now: Instant) -> Result<(), Violation> {
+        self.datagrams_total += 1;
+        self.datagram_bytes_total += len as u64;
+        self.datagrams.add(len as u64, now);
+        let max = u64::from(self.limits.max_datagrams_per_sec);
+        if max > 0 && self.datagrams.count > max {
+            return Err(Violation::DatagramRate);
+        }
+        let max_bytes = self.limits.max_datagram_bytes_per_sec;
+        if max_bytes > 0 && self.datagrams.bytes > max_bytes {
+            return Err(Violation::DatagramBytes);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thresholds_trip_within_a_window_and_reset_after() {
+        let t0 = Instant::now();
+        let mut acct = ConnAccounting::new(
+            ConnLimits {
+                max_streams_per_sec: 3,
+                max_datagrams_per_sec: 10,
+                max_datagram_bytes_per_sec: 5_000,
+            },
+            t0,
+        );
+
+        for _ in 0..3 {
+            assert_eq!(acct.on_stream(t0), Ok(()));
+        }
+        assert_eq!(acct.on_stream(t0), Err(Violation::StreamRate));
+        assert_eq!(acct.on_stream(t0 + WINDOW), Ok(()));
+
+        for _ in 0..4 {
+            assert_eq!(acct.on_datagram(1_000, t0), Ok(()));
+        }
+        assert_eq!(acct.on_datagram(1_200, t0), Err(Violation::DatagramBytes));
+
+        let t1 = t0 + WINDOW;
+        for _ in 0..10 {
+            assert_eq!(acct.on_datagram(10, t1), Ok(()));
+        }
+        assert_eq!(acct.on_datagram(10, t1), Err(Violation::DatagramRate));
+        assert_eq!(acct.datagrams_total, 16);
+        assert_eq!(acct.streams_total, 5);
+    }
+
+    #[test]
+    fn zero_limits_never_trip() {
+        let t0 = Instant::now();
+        let mut acct = ConnAccounting::new(ConnLimits::UNLIMITED, t0);
+        for _ in 0..10_000 {
+            assert_eq!(acct.on_stream(t0), Ok(()));
+            assert_eq!(acct.on_datagram(1_200, t0), Ok(()));
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use std::time::Duration;
+
+use metrics::gauge;
+use sqlx::PgPool;
+use tokio::time::MissedTickBehavior;
+use tracing::{info, warn};
+use vp_metrics::metric_name;
+
+use crate::readiness::Readiness;
+
+/// Probes are bounded so a hung pool reports "down" instead of stalling the loop.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Periodically ping Postgres and publish `vp_gateway_db_up` (1 = reachable),
+/// mirroring it into `readiness`.
+///
+/// Voice forwarding runs entirely off the in-memory membership cache, so a
+/// DB outage only degrades control-plane requests; this gauge is what lets
+/// operators tell the two apart.
+pub async fn run_db_health_monitor(pool: PgPool, interval: Duration, readiness: Readiness) {
+    let mut tick = tokio::time::interval(interval);
+    tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    let mut was_up = true;
+
+    loop {
+        tick.tick().await;
+        let up = probe(&pool).await;
+        readiness.set_db_up(up);
+        gauge!(metric_name("gateway_db_up")).set(if up { 1.0 } else { 0.0 });
+        gauge!(metric_name("gateway_db_pool_idle")).set(pool.num_idle() as f64);
+
+        if up != was_up {
+            if up {
+                info!("database connectivity restored");
+            } else {
+                warn!("database unreachable; control-plane requests will return unavailable");
+            }
+            was_up = up;
+        }
+    }
+}
+
+async fn probe(pool: &PgPool) -> bool {
+    matches!(
+        tokio::time::timeout(PROBE_TIMEOUT, sqlx::query("SELECT 1").execute(pool)).await,
+        Ok(Ok(_))
+    )
+}
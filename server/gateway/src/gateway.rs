@@ -1,5 +1,6 @@
 use anyhow::{anyhow, Context, Result};
 use dashmap::DashMap;
+use metrics::counter;
 use ring::rand::SecureRandom;
 use scopeguard::defer;
 use std::{
@@ -10,34 +11,44 @@ use std::{
     },
 };
 use tokio::{
-    sync::{mpsc, RwLock, Semaphore},
+    sync::{mpsc, Semaphore},
     time::{timeout, Duration, Instant},
 };
 use tracing::{debug, info, warn};
 
 use crate::{
     auth::{AuthProvider, AuthedIdentity},
+    conn_guard::{ConnAccounting, ConnLimits, Violation, CLOSE_CODE_ABUSE},
     frame::{read_delimited, write_delimited},
     media::MediaService,
     overwrite_queue::{pop_voice_realtime, OverwriteQueue, StampedBytes},
     proto::voiceplatform::v1 as pb,
+    readiness::Readiness,
     screenshare::{
         select_and_persist_layer, should_request_keyframe_on_layer_change,
         validate_owner_action, validate_start_share_authorization, validate_viewer_access,
     },
     screenshare_policy::ScreenSharePolicy,
     state::{
-        MembershipCache, PushHub, Sessions, StreamSessionOwnership, StreamSessionRegistry,
-        VoiceTelemetryCache, VoiceTelemetrySample,
+        MembershipCache, ParkedSessions, PokeThrottle, PushHub, Sessions, StreamSessionOwnership,
+        StreamSessionRegistry, TypingThrottle, VoiceTelemetryCache, VoiceTelemetrySample,
     },
 };
 
-use vp_control::ids::{ChannelId, ServerId, UserId};
-use vp_control::model::{ChannelCreate, JoinChannel, SendMessage};
+use vp_control::errors::{CHANNEL_FULL, POKE_THROTTLED, SLOW_MODE, USER_OFFLINE};
+use vp_control::ids::{ChannelId, MessageId, ServerId, UserId};
+use vp_control::model::{
+    ChannelCreate, ChannelNotificationPref, ChatHistoryEntry, ChatMessageKind, EditMessage,
+    JoinChannel, Member, ReadMarker, SendMessage,
+};
+use vp_control::service::{
+    split_member_page, MAX_CHANNEL_BITRATE_BPS, MEMBER_PAGE_SIZE, MIN_CHANNEL_BITRATE_BPS,
+};
 use vp_control::{ControlError, ControlRepo, ControlService, PgControlRepo, RequestContext};
 use vp_media::datagram_send_policy::SessionSendCtx;
 use vp_media::stream_forwarder::StreamForwarder;
-use vp_media::voice_forwarder::VoiceForwarder;
+use vp_media::voice_forwarder::{TalkerTuning, VoiceForwarder};
+use vp_metrics::{gateway::GatewayMetrics, metric_name};
 
 const CONTROL_STREAM_MAX_MSG: usize = 256 * 1024; // 256KB
 const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
@@ -49,6 +60,20 @@ const PROFILE_ASSET_MAX_MSG: usize = 3 * 1024 * 1024; // 3 MB
 const VOICE_INGRESS_CAP: usize = 16; // Do not increase without justification; latency risk.
 const VOICE_MAX_AGE: Duration = Duration::from_millis(250);
 const VOICE_DRAIN_KEEP_LATEST: usize = 4;
+/// Bounds for admin talker-window updates; below ~one frame every talker
+/// would flap, above a few seconds slots are held long after speech ends.
+const TALKER_WINDOW_MS_RANGE: std::ops::RangeInclusive<u32> = 100..=5_000;
+/// Application close code for an admin force-disconnect (distinct from
+/// [`CLOSE_CODE_ABUSE`]) so clients can tell it apart from network loss.
+const CLOSE_CODE_ADMIN_DISCONNECT: u32 = 0x1b;
+/// Application close code for a stale connection whose session was resumed
+/// on a newer one.
+const CLOSE_CODE_SESSION_RESUMED: u32 = 0x1d;
+/// Application close code for connections still open when a graceful
+/// shutdown's grace period runs out.
+const CLOSE_CODE_SERVER_SHUTDOWN: u32 = 0x1e;
+/// How often a draining gateway checks whether its connections are gone.
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
 #[derive(Clone)]
 pub struct Gateway {
@@ -63,8 +88,16 @@ pub struct Gateway {
     video: Arc<StreamForwarder>,
     media: Arc<MediaService>,
     connection_limit: Arc<Semaphore>,
-    reactions: Arc<RwLock<HashMap<(ChannelId, uuid::Uuid), HashMap<String, HashSet<UserId>>>>>,
+    conn_limits: ConnLimits,
     current_activity: Arc<DashMap<UserId, pb::GameActivity>>,
+    typing: TypingThrottle,
+    pokes: PokeThrottle,
+    parked: ParkedSessions,
+    metrics: Arc<GatewayMetrics>,
+    voice_loudness: bool,
+    voice_mixing: bool,
+    voice_recording: bool,
+    readiness: Option<Readiness>,
 }
 
 impl Gateway {
@@ -81,6 +114,7 @@ impl Gateway {
         video: Arc<StreamForwarder>,
         media: Arc<MediaService>,
         max_connections: usize,
+        conn_limits: ConnLimits,
     ) -> Self {
         Self {
             auth,
@@ -94,19 +128,62 @@ impl Gateway {
             video,
             media,
             connection_limit: Arc::new(Semaphore::new(max_connections)),
-            reactions: Arc::new(RwLock::new(HashMap::new())),
+            conn_limits,
             current_activity: Arc::new(DashMap::new()),
+            typing: TypingThrottle::new(),
+            pokes: PokeThrottle::new(),
+            parked: ParkedSessions::new(),
+            metrics: Arc::new(GatewayMetrics::new(vp_metrics::namespace())),
+            voice_loudness: false,
+            voice_mixing: false,
+            voice_recording: false,
+            readiness: None,
         }
     }
 
-    pub async fn serve(self, endpoint: quinn::Endpoint) -> Result<()> {
+    /// Advertise [`pb::ServerFeature::VoiceLoudness`]; set when the loudest
+    /// talker publisher is running.
+    pub fn with_voice_loudness(mut self, enabled: bool) -> Self {
+        self.voice_loudness = enabled;
+        self
+    }
+
+    /// Advertise `ServerInfo.voice_mixing`; set when the forwarder mixes.
+    pub fn with_voice_mixing(mut self, enabled: bool) -> Self {
+        self.voice_mixing = enabled;
+        self
+    }
+
+    /// Advertise [`pb::ServerFeature::Recording`]; set when voice is archived.
+    pub fn with_voice_recording(mut self, enabled: bool) -> Self {
+        self.voice_recording = enabled;
+        self
+    }
+
+    /// Report not-ready on `readiness` as soon as a drain starts.
+    pub fn with_readiness(mut self, readiness: Readiness) -> Self {
+        self.readiness = Some(readiness);
+        self
+    }
+
+    /// Accept connections until `shutdown` resolves, then [drain](Self::drain)
+    /// them for up to `grace`.
+    pub async fn serve(
+        self,
+        endpoint: quinn::Endpoint,
+        shutdown: impl std::future::Future<Output = ()>,
+        grace: Duration,
+    ) -> Result<()> {
         info!(expected_alpn = %String::from_utf8_lossy(&self.alpn), "gateway listening");
+        tokio::pin!(shutdown);
 
         loop {
-            let incoming = endpoint
-                .accept()
-                .await
-                .ok_or_else(|| anyhow!("endpoint closed"))?;
+            let incoming = tokio::select! {
+                incoming = endpoint.accept() => {
+                    incoming.ok_or_else(|| anyhow!("endpoint closed"))?
+                }
+                () = &mut shutdown => break,
+            };
             let Ok(permit) = self.connection_limit.clone().try_acquire_owned() else {
                 warn!("connection soft limit reached; dropping incoming connection");
                 continue;
@@ -120,10 +197,54 @@ impl Gateway {
                 }
             });
         }
+
+        self.drain(&endpoint, grace).await;
+        Ok(())
+    }
+
+    /// Graceful shutdown: refuse new connections, push `ServerGoingAway` so
+    /// clients reconnect elsewhere, and wait up to `grace` for them to leave
+    /// before closing whatever is left. The outbox dispatcher keeps running
+    /// meanwhile, so pushes still reach the sessions that remain.
+    async fn drain(&self, endpoint: &quinn::Endpoint, grace: Duration) {
+        if let Some(readiness) = &self.readiness {
+            readiness.set_draining();
+        }
+        endpoint.set_server_config(None);
+        info!(
+            connections = endpoint.open_connections(),
+            grace_ms = grace.as_millis() as u64,
+            "draining gateway"
+        );
+
+        let going_away = server_going_away_push(grace);
+        for user in self.push.connected_users() {
+            self.push.send(user, going_away.clone()).await;
+        }
+
+        let deadline = Instant::now() + grace;
+        while endpoint.open_connections() > 0 && Instant::now() < deadline {
+            tokio::time::sleep(DRAIN_POLL_INTERVAL).await;
+        }
+        let remaining = endpoint.open_connections();
+        if remaining > 0 {
+            warn!(remaining, "grace period over; closing connections");
+        }
+        endpoint.close(
+            quinn::VarInt::from_u32(CLOSE_CODE_SERVER_SHUTDOWN),
+            b"server shutting down",
+        );
+        // Give the close frames a moment to go out.
+        let _ = timeout(Duration::from_secs(1), endpoint.wait_idle()).await;
     }
 
     async fn handle_conn(&self, incoming: quinn::Incoming) -> Result<()> {
         let conn = incoming.await.context("accept quic connection")?;
+        let accepted_at = Instant::now();
+        self.metrics.conn_accepted();
+        defer! {
+            self.metrics.conn_closed();
+        }
 
         // ALPN check (defense-in-depth).
         let negotiated = conn
@@ -141,6 +262,11 @@ impl Gateway {
         );
 
         if negotiated.as_deref() != Some(&self.alpn[..]) {
+            self.metrics.alpn_mismatch();
+            conn.close(
+                quinn::VarInt::from_u32(vp_voice::CLOSE_CODE_ALPN_MISMATCH),
+                b"alpn mismatch",
+            );
             return Err(anyhow!(
                 "ALPN mismatch: got {:?}, want {:?}",
                 negotiated,
@@ -154,13 +280,22 @@ impl Gateway {
         // Expect client to open the first bi-directional stream as the control stream.
         let (mut send, mut recv) = timeout(HANDSHAKE_TIMEOUT, conn.accept_bi())
             .await
+            .inspect_err(|_| self.metrics.handshake_timeout())
             .context("control accept_bi timeout")?
             .context("accept_bi failed")?;
 
-        let (session_id, _hello_caps, auth_challenge) = self.do_hello(&mut send, &mut recv).await?;
+        let (session_id, hello_caps, auth_challenge) = self.do_hello(&mut send, &mut recv).await?;
+        // What the client negotiated in Hello; absent caps read as all-false.
+        let client_caps = hello_caps.unwrap_or_default();
+        let voice_multi_frame = client_caps
+            .features
+            .as_ref()
+            .is_some_and(|f| f.supports_voice_multi_frame);
         let identity = self
             .do_auth(&mut send, &mut recv, &session_id, &auth_challenge)
             .await?;
+        let handshake = accepted_at.elapsed();
+        self.metrics.handshake_seconds(handshake.as_secs_f64());
 
         let user_id =
             UserId(uuid::Uuid::parse_str(&identity.user_id).context("invalid user_id uuid")?);
@@ -196,21 +331,31 @@ impl Gateway {
         self.sessions.register(
             user_id,
             &session_id,
-            Arc::new(SessionSendCtx::new(
-                user_id,
-                session_id.clone(),
-                conn.clone(),
-            )),
+            Arc::new(
+                SessionSendCtx::new(user_id, session_id.clone(), conn.clone())
+                    .with_voice_multi_frame(voice_multi_frame),
+            ),
         );
+        self.sessions.set_client_caps(&session_id, client_caps);
 
         let mut current_channel: Option<ChannelId> = None;
         let mut stream_registry = StreamSessionRegistry::new();
         let mut screenshare_policy = ScreenSharePolicy::default();
         let video_forwarder = self.video.clone();
+        self.metrics.session_opened();
         defer! {
+            self.metrics.session_closed();
             self.push.unregister(user_id, &session_id);
             self.sessions.unregister(user_id, &session_id);
             self.telemetry.remove(user_id);
+            if !self.sessions.has_user_sessions(user_id) {
+                self.typing.remove_user(user_id);
+                self.pokes.remove_user(user_id);
+                let voice = self.voice.clone();
+                tokio::spawn(async move {
+                    voice.remove_receiver(user_id).await;
+                });
+            }
             let vf = video_forwarder.clone();
             let sid = session_id.clone();
             tokio::spawn(async move {
@@ -223,6 +368,7 @@ impl Gateway {
         let video = self.video.clone();
         let user_for_dg = user_id;
         let conn_dg = conn.clone();
+        let dg_limits = self.conn_limits;
         tokio::spawn(async move {
             const VIDEO_DATAGRAM_QUEUE_CAPACITY: usize = 8192;
             const VIDEO_DATAGRAM_WORKERS: usize = 2;
@@ -276,9 +422,14 @@ impl Gateway {
 
             let mut video_rr = 0usize;
             let mut last_log = Instant::now();
+            let mut accounting = ConnAccounting::new(dg_limits, std::time::Instant::now());
             // Fast-path only: read datagram -> classify -> enqueue/drop.
             // Keep heavy decoding/mixing work in downstream workers.
             while let Ok(d) = conn_dg.read_datagram().await {
+                if let Err(violation) = accounting.on_datagram(d.len(), std::time::Instant::now()) {
+                    close_for_abuse(&conn_dg, user_for_dg, violation, &accounting);
+                    break;
+                }
                 if d.len() > vp_voice::APP_MEDIA_MTU {
                     oversized_drops.fetch_add(1, Ordering::Relaxed);
                     if last_log.elapsed() >= Duration::from_secs(1) {
@@ -387,8 +538,14 @@ impl Gateway {
         let media = self.media.clone();
         let control_svc = self.control.clone();
         let conn_media = conn.clone();
+        let stream_limits = self.conn_limits;
         tokio::spawn(async move {
+            let mut accounting = ConnAccounting::new(stream_limits, std::time::Instant::now());
             while let Ok((send_s, mut recv_s)) = conn_media.accept_bi().await {
+                if let Err(violation) = accounting.on_stream(std::time::Instant::now()) {
+                    close_for_abuse(&conn_media, user_id, violation, &accounting);
+                    break;
+                }
                 let media = media.clone();
                 let control_svc = control_svc.clone();
                 tokio::spawn(async move {
@@ -490,6 +647,7 @@ impl Gateway {
 
                     // Update membership cache
                     let member_ids = members.iter().map(|m| m.user_id).collect::<Vec<_>>();
+                    self.membership.set_channel_kind(ch, chan.channel_type);
                     self.membership.set_channel_state(
                         ch,
                         chan.max_talkers.map(|v| v as usize).unwrap_or(4),
@@ -519,24 +677,18 @@ impl Gateway {
                         );
                     }
 
+                    // The cache keeps every member for fanout; the response
+                    // only carries the first page so a crowded channel can't
+                    // push it past the control-stream frame limit.
+                    let (first_page, members_cursor) = split_member_page(members, MEMBER_PAGE_SIZE);
                     let state = pb::ChannelState {
                         channel_id: Some(pb::ChannelId {
                             value: ch.0.to_string(),
                         }),
                         name: chan.name.clone(),
-                        members: members
+                        members: first_page
                             .into_iter()
-                            .map(|m| pb::ChannelMember {
-                                user_id: Some(pb::UserId {
-                                    value: m.user_id.0.to_string(),
-                                }),
-                                display_name: m.display_name,
-                                muted: m.muted,
-                                deafened: m.deafened,
-                                away_message: m.custom_status_text,
-                                custom_status_emoji: m.custom_status_emoji,
-                                ..Default::default()
-                            })
+                            .map(|m| channel_member_to_pb(&self.membership, ch, m))
                             .collect(),
                         info: Some(pb::ChannelInfo {
                             channel_id: Some(pb::ChannelId {
@@ -548,11 +700,16 @@ impl Gateway {
                             parent_channel_id: chan.parent_id.map(|pid| pb::ChannelId {
                                 value: pid.0.to_string(),
                             }),
-                            user_limit: chan.max_members.unwrap_or_default().max(0) as u32,
+                            user_limit: channel_limit(chan.max_members),
                             bitrate: chan.bitrate_bps.max(0) as u32,
                             opus_profile: chan.opus_profile,
+                            max_message_length: channel_limit(chan.max_message_length),
+                            slow_mode_secs: chan.slow_mode_secs.max(0) as u32,
+                            announce_presence: chan.announce_presence,
                             ..Default::default()
                         }),
+                        max_members: channel_limit(chan.max_members),
+                        max_talkers: channel_limit(chan.max_talkers),
                     };
 
                     let resp = pb::ServerToClient {
@@ -564,7 +721,11 @@ impl Gateway {
                         error: None,
                         event_seq: 0,
                         payload: Some(pb::server_to_client::Payload::JoinChannelResponse(
-                            pb::JoinChannelResponse { state: Some(state) },
+                            pb::JoinChannelResponse {
+                                state: Some(state),
+                                has_more_members: members_cursor.is_some(),
+                                members_cursor: members_cursor.unwrap_or_default(),
+                            },
                         )),
                     };
                     if let Err(e) = write_delimited(&mut send, &resp).await {
@@ -610,16 +771,10 @@ impl Gateway {
                     let ch = parse_channel_id(r.channel_id.as_ref())?;
                     self.control.leave_channel(&ctx, ch).await?;
 
-                    self.membership.remove_user(user_id);
+                    self.membership.leave_channel(user_id, ch);
                     if current_channel == Some(ch) {
                         current_channel = None;
                     }
-                    // best effort update channel member list
-                    if let Some(mut cur) = self.membership.members_of(ch) {
-                        cur.retain(|u| *u != user_id);
-                        let max = self.membership.max_talkers_of(ch).unwrap_or(4);
-                        self.membership.set_channel_state(ch, max, cur);
-                    }
 
                     let resp = pb::ServerToClient {
                         request_id: req_id,
@@ -649,7 +804,8 @@ impl Gateway {
                         .and_then(|pid| uuid::Uuid::parse_str(&pid.value).ok())
                         .map(ChannelId);
                     let user_limit = r.user_limit;
-                    let bitrate_bps = (r.bitrate as i32).clamp(8_000, 510_000);
+                    let bitrate_bps = (r.bitrate as i32)
+                        .clamp(MIN_CHANNEL_BITRATE_BPS, MAX_CHANNEL_BITRATE_BPS);
                     let created = self
                         .control
                         .create_channel(
@@ -667,6 +823,7 @@ impl Gateway {
                                 description: r.description,
                                 bitrate_bps,
                                 opus_profile: r.opus_profile,
+                                ephemeral: r.ephemeral,
                             },
                         )
                         .await?;
@@ -688,11 +845,13 @@ impl Gateway {
                             parent_channel_id: created.parent_id.map(|pid| pb::ChannelId {
                                 value: pid.0.to_string(),
                             }),
-                            user_limit: created.max_members.unwrap_or_default().max(0) as u32,
+                            user_limit: channel_limit(created.max_members),
                             bitrate: created.bitrate_bps.max(0) as u32,
                             opus_profile: created.opus_profile,
                             ..Default::default()
                         }),
+                        max_members: channel_limit(created.max_members),
+                        max_talkers: channel_limit(created.max_talkers),
                     };
 
                     let resp = pb::ServerToClient {
@@ -749,6 +908,62 @@ impl Gateway {
                                         as u32,
                                     bitrate: updated.bitrate_bps.max(0) as u32,
                                     opus_profile: updated.opus_profile,
+                                    max_message_length: channel_limit(updated.max_message_length),
+                                    slow_mode_secs: updated.slow_mode_secs.max(0) as u32,
+                                    announce_presence: updated.announce_presence,
+                                    ..Default::default()
+                                }),
+                            },
+                        )),
+                    };
+                    if let Err(e) = write_delimited(&mut send, &resp).await {
+                        warn!("control write failed: {:#}", e);
+                        break;
+                    }
+                }
+                Some(pb::client_to_server::Payload::SetChannelChatLimits(r)) => {
+                    let ch = parse_channel_id(r.channel_id.as_ref())?;
+                    let max_message_length = match r.max_message_length {
+                        0 => None,
+                        n => Some(i32::try_from(n).unwrap_or(i32::MAX)),
+                    };
+                    let slow_mode_secs = i32::try_from(r.slow_mode_secs).unwrap_or(i32::MAX);
+                    let updated = self
+                        .control
+                        .set_channel_chat_limits(
+                            &ctx,
+                            ch,
+                            max_message_length,
+                            slow_mode_secs,
+                            r.announce_presence,
+                        )
+                        .await?;
+                    let resp = pb::ServerToClient {
+                        request_id: req_id,
+                        session_id: Some(pb::SessionId {
+                            value: session_id.clone(),
+                        }),
+                        sent_at: Some(now_ts()),
+                        error: None,
+                        event_seq: 0,
+                        payload: Some(pb::server_to_client::Payload::SetChannelChatLimits(
+                            pb::SetChannelChatLimitsResponse {
+                                info: Some(pb::ChannelInfo {
+                                    channel_id: Some(pb::ChannelId {
+                                        value: updated.id.0.to_string(),
+                                    }),
+                                    name: updated.name,
+                                    parent_channel_id: updated.parent_id.map(|pid| pb::ChannelId {
+                                        value: pid.0.to_string(),
+                                    }),
+                                    channel_type: updated.channel_type,
+                                    description: updated.description,
+                                    user_limit: channel_limit(updated.max_members),
+                                    bitrate: updated.bitrate_bps.max(0) as u32,
+                                    opus_profile: updated.opus_profile,
+                                    max_message_length: channel_limit(updated.max_message_length),
+                                    slow_mode_secs: updated.slow_mode_secs.max(0) as u32,
+                                    announce_presence: updated.announce_presence,
                                     ..Default::default()
                                 }),
                             },
@@ -759,6 +974,93 @@ impl Gateway {
                         break;
                     }
                 }
+                Some(pb::client_to_server::Payload::ListChannelMembers(r)) => {
+                    let ch = parse_channel_id(r.channel_id.as_ref())?;
+                    let (members, next_cursor) = self
+                        .control
+                        .list_members_page(
+                            &ctx,
+                            ch,
+                            Some(r.after_cursor.as_str()),
+                            r.limit as usize,
+                        )
+                        .await?;
+                    let resp = pb::ServerToClient {
+                        request_id: req_id,
+                        session_id: Some(pb::SessionId {
+                            value: session_id.clone(),
+                        }),
+                        sent_at: Some(now_ts()),
+                        error: None,
+                        event_seq: 0,
+                        payload: Some(pb::server_to_client::Payload::ListChannelMembers(
+                            pb::ListChannelMembersResponse {
+                                channel_id: Some(pb::ChannelId {
+                                    value: ch.0.to_string(),
+                                }),
+                                members: members
+                                    .into_iter()
+                                    .map(|m| channel_member_to_pb(&self.membership, ch, m))
+                                    .collect(),
+                                next_cursor: next_cursor.unwrap_or_default(),
+                            },
+                        )),
+                    };
+                    if let Err(e) = write_delimited(&mut send, &resp).await {
+                        warn!("control write failed: {:#}", e);
+                        break;
+                    }
+                }
+                Some(pb::client_to_server::Payload::SubscribeChannel(r)) => {
+                    let ch = parse_channel_id(r.channel_id.as_ref())?;
+                    // Same gate as viewing the channel; voice membership is untouched.
+                    self.control.get_channel(&ctx, ch).await?;
+                    self.membership.subscribe_chat(user_id, &session_id, ch);
+                    let resp = pb::ServerToClient {
+                        request_id: req_id,
+                        session_id: Some(pb::SessionId {
+                            value: session_id.clone(),
+                        }),
+                        sent_at: Some(now_ts()),
+                        error: None,
+                        event_seq: 0,
+                        payload: Some(pb::server_to_client::Payload::SubscribeChannel(
+                            pb::SubscribeChannelResponse {
+                                channel_id: Some(pb::ChannelId {
+                                    value: ch.0.to_string(),
+                                }),
+                            },
+                        )),
+                    };
+                    if let Err(e) = write_delimited(&mut send, &resp).await {
+                        warn!("control write failed: {:#}", e);
+                        break;
+                    }
+                }
+                Some(pb::client_to_server::Payload::UnsubscribeChannel(r)) => {
+                    let ch = parse_channel_id(r.channel_id.as_ref())?;
+                    self.membership.unsubscribe_chat(user_id, &session_id, ch);
+                    let resp = pb::ServerToClient {
+                        request_id: req_id,
+                        session_id: Some(pb::SessionId {
+                            value: session_id.clone(),
+                        }),
+                        sent_at: Some(now_ts()),
+                        error: None,
+                        event_seq: 0,
+                        payload: Some(pb::server_to_client::Payload::UnsubscribeChannel(
+                            pb::UnsubscribeChannelResponse {
+                                channel_id: Some(pb::ChannelId {
+                                    value: ch.0.to_string(),
+                                }),
+                            },
+                        )),
+                    };
+                    if let Err(e) = write_delimited(&mut send, &resp).await {
+                        warn!("control write failed: {:#}", e);
+                        break;
+                    }
+                }
                 Some(pb::client_to_server::Payload::RenameChannelRequest(r)) => {
                     let ch = parse_channel_id(r.channel_id.as_ref())?;
                     let renamed = self.control.rename_channel(&ctx, ch, &r.new_name).await?;
@@ -786,6 +1088,9 @@ impl Gateway {
                                         as u32,
                                     bitrate: renamed.bitrate_bps.max(0) as u32,
                                     opus_profile: renamed.opus_profile,
+                                    max_message_length: channel_limit(renamed.max_message_length),
+                                    slow_mode_secs: renamed.slow_mode_secs.max(0) as u32,
+                                    announce_presence: renamed.announce_presence,
                                     ..Default::default()
                                 }),
                             },
@@ -820,6 +1125,86 @@ impl Gateway {
                         break;
                     }
                 }
+                Some(pb::client_to_server::Payload::SetChannelNotificationPrefRequest(r)) => {
+                    let ch = parse_channel_id(r.channel_id.as_ref())?;
+                    let pref = match pb::ChannelNotificationPref::try_from(r.pref) {
+                        Ok(pb::ChannelNotificationPref::Default) => ChannelNotificationPref::Default,
+                        Ok(pb::ChannelNotificationPref::Subscribed) => {
+                            ChannelNotificationPref::Subscribed
+                        }
+                        Ok(pb::ChannelNotificationPref::Muted) => ChannelNotificationPref::Muted,
+                        Err(_) => {
+                            return Err(ControlError::InvalidArgument("invalid notification pref").into())
+                        }
+                    };
+                    self.control
+                        .set_channel_notification_pref(&ctx, ch, pref)
+                        .await?;
+                    self.membership.set_notification_pref(user_id, ch, pref);
+                    let resp = pb::ServerToClient {
+                        request_id: req_id,
+                        session_id: Some(pb::SessionId {
+                            value: session_id.clone(),
+                        }),
+                        sent_at: Some(now_ts()),
+                        error: None,
+                        event_seq: 0,
+                        payload: Some(
+                            pb::server_to_client::Payload::SetChannelNotificationPrefResponse(
+                                pb::SetChannelNotificationPrefResponse {
+                                    channel_id: Some(pb::ChannelId {
+                                        value: ch.0.to_string(),
+                                    }),
+                                    pref: r.pref,
+                                },
+                            ),
+                        ),
+                    };
+                    if let Err(e) = write_delimited(&mut send, &resp).await {
+                        warn!("control write failed: {:#}", e);
+                        break;
+                    }
+                }
+                Some(pb::client_to_server::Payload::SetSelfVoiceStateRequest(r)) => {
+                    let ch = parse_channel_id(r.channel_id.as_ref())?;
+                    let (muted, deafened) = self
+                        .membership
+                        .set_self_voice_state(user_id, ch, r.self_muted, r.self_deafened)
+                        .ok_or(ControlError::PermissionDenied("not in channel"))?;
+                    debug!(user_id=%user_id.0, channel_id=%ch.0, self_muted=r.self_muted, self_deafened=r.self_deafened, "self voice state updated");
+                    self.broadcast_presence_event(
+                        ch,
+                        pb::presence_event::Kind::MemberVoiceStateChanged(
+                            pb::MemberVoiceStateChanged {
+                                channel_id: Some(pb::ChannelId { value: ch.0.to_string() }),
+                                user_id: Some(pb::UserId { value: user_id.0.to_string() }),
+                                muted,
+                                deafened,
+                                self_muted: r.self_muted,
+                                self_deafened: r.self_deafened,
+                                ..Default::default()
+                            },
+                        ),
+                    )
+                    .await;
+
+                    let resp = pb::ServerToClient {
+                        request_id: req_id,
+                        session_id: Some(pb::SessionId {
+                            value: session_id.clone(),
+                        }),
+                        sent_at: Some(now_ts()),
+                        error: None,
+                        event_seq: 0,
+                        payload: Some(pb::server_to_client::Payload::SetSelfVoiceStateResponse(
+                            pb::SetSelfVoiceStateResponse {},
+                        )),
+                    };
+                    if let Err(e) = write_delimited(&mut send, &resp).await {
+                        warn!("control write failed: {:#}", e);
+                        break;
+                    }
+                }
                 Some(pb::client_to_server::Payload::SendMessageRequest(r)) => {
                     let ch = parse_channel_id(r.channel_id.as_ref())?;
                     let attachments = serde_json::Value::Array(
@@ -860,40 +1245,108 @@ impl Gateway {
                         break;
                     }
                 }
-                Some(pb::client_to_server::Payload::AddReactionRequest(r)) => {
+                Some(pb::client_to_server::Payload::EditMessageRequest(r)) => {
                     let ch = parse_channel_id(r.channel_id.as_ref())?;
-                    let msg_id = parse_message_uuid(r.message_id.as_ref())?;
-                    let emoji = r.emoji.trim().to_string();
-                    if emoji.is_empty() {
-                        return Err(ControlError::InvalidArgument("emoji missing").into());
-                    }
-                    if !self
-                        .membership
-                        .members_of(ch)
-                        .unwrap_or_default()
-                        .contains(&user_id)
-                    {
-                        return Err(ControlError::PermissionDenied("not a channel member").into());
+                    let msg_id = MessageId(parse_message_uuid(r.message_id.as_ref())?);
+                    self.control
+                        .edit_message(
+                            &ctx,
+                            EditMessage {
+                                channel_id: ch,
+                                message_id: msg_id,
+                                text: r.new_text,
+                            },
+                        )
+                        .await?;
+
+                    // Listeners get the new text via the outbox push.
+                    let resp = pb::ServerToClient {
+                        request_id: req_id,
+                        session_id: Some(pb::SessionId {
+                            value: session_id.clone(),
+                        }),
+                        sent_at: Some(now_ts()),
+                        error: None,
+                        event_seq: 0,
+                        payload: Some(pb::server_to_client::Payload::EditMessageResponse(
+                            pb::EditMessageResponse {
+                                message_id: Some(pb::MessageId {
+                                    value: msg_id.0.to_string(),
+                                }),
+                            },
+                        )),
+                    };
+                    if let Err(e) = write_delimited(&mut send, &resp).await {
+                        warn!("control write failed: {:#}", e);
+                        break;
                     }
+                }
+                Some(pb::client_to_server::Payload::DeleteMessageRequest(r)) => {
+                    let ch = parse_channel_id(r.channel_id.as_ref())?;
+                    let msg_id = MessageId(parse_message_uuid(r.message_id.as_ref())?);
+                    self.control.delete_message(&ctx, ch, msg_id).await?;
 
-                    let inserted = {
-                        let mut reactions = self.reactions.write().await;
-                        let by_msg = reactions.entry((ch, msg_id)).or_default();
-                        by_msg.entry(emoji.clone()).or_default().insert(user_id)
+                    let resp = pb::ServerToClient {
+                        request_id: req_id,
+                        session_id: Some(pb::SessionId {
+                            value: session_id.clone(),
+                        }),
+                        sent_at: Some(now_ts()),
+                        error: None,
+                        event_seq: 0,
+                        payload: Some(pb::server_to_client::Payload::DeleteMessageResponse(
+                            pb::DeleteMessageResponse {},
+                        )),
                     };
+                    if let Err(e) = write_delimited(&mut send, &resp).await {
+                        warn!("control write failed: {:#}", e);
+                        break;
+                    }
+                }
+                Some(pb::client_to_server::Payload::GetMessageHistoryRequest(r)) => {
+                    let ch = parse_channel_id(r.channel_id.as_ref())?;
+                    let before = match r.before_message_id.as_str() {
+                        "" => None,
+                        raw => Some(MessageId(uuid::Uuid::parse_str(raw).map_err(|_| {
+                            ControlError::InvalidArgument("invalid before_message_id")
+                        })?)),
+                    };
+                    let page = self
+                        .control
+                        .list_messages(&ctx, ch, before, r.limit as usize)
+                        .await?;
 
-                    if inserted {
-                        self.broadcast_chat_event(
-                            ch,
-                            pb::chat_event::Kind::ReactionAdded(pb::ReactionAdded {
-                                message_id: Some(pb::MessageId { value: msg_id.to_string() }),
-                                channel_id: Some(pb::ChannelId { value: ch.0.to_string() }),
-                                user_id: Some(pb::UserId { value: user_id.0.to_string() }),
-                                emoji,
-                            }),
-                        )
-                        .await;
+                    let resp = pb::ServerToClient {
+                        request_id: req_id,
+                        session_id: Some(pb::SessionId {
+                            value: session_id.clone(),
+                        }),
+                        sent_at: Some(now_ts()),
+                        error: None,
+                        event_seq: 0,
+                        payload: Some(pb::server_to_client::Payload::GetMessageHistoryResponse(
+                            pb::GetMessageHistoryResponse {
+                                messages: page
+                                    .into_iter()
+                                    .map(|entry| {
+                                        prost::Message::encode_to_vec(&history_entry_to_pb(entry))
+                                    })
+                                    .collect(),
+                            },
+                        )),
+                    };
+                    if let Err(e) = write_delimited(&mut send, &resp).await {
+                        warn!("control write failed: {:#}", e);
+                        break;
                     }
+                }
+                Some(pb::client_to_server::Payload::AddReactionRequest(r)) => {
+                    let ch = parse_channel_id(r.channel_id.as_ref())?;
+                    let msg_id = MessageId(parse_message_uuid(r.message_id.as_ref())?);
+                    // Listeners, including us, learn the new count via the outbox push.
+                    self.control
+                        .add_reaction(&ctx, ch, msg_id, &r.emoji)
+                        .await?;
 
                     let resp = pb::ServerToClient {
                         request_id: req_id,
@@ -914,11 +1367,30 @@ impl Gateway {
                 }
                 Some(pb::client_to_server::Payload::RemoveReactionRequest(r)) => {
                     let ch = parse_channel_id(r.channel_id.as_ref())?;
-                    let msg_id = parse_message_uuid(r.message_id.as_ref())?;
-                    let emoji = r.emoji.trim().to_string();
-                    if emoji.is_empty() {
-                        return Err(ControlError::InvalidArgument("emoji missing").into());
+                    let msg_id = MessageId(parse_message_uuid(r.message_id.as_ref())?);
+                    self.control
+                        .remove_reaction(&ctx, ch, msg_id, &r.emoji)
+                        .await?;
+
+                    let resp = pb::ServerToClient {
+                        request_id: req_id,
+                        session_id: Some(pb::SessionId {
+                            value: session_id.clone(),
+                        }),
+                        sent_at: Some(now_ts()),
+                        error: None,
+                        event_seq: 0,
+                        payload: Some(pb::server_to_client::Payload::RemoveReactionResponse(
+                            pb::RemoveReactionResponse {},
+                        )),
+                    };
+                    if let Err(e) = write_delimited(&mut send, &resp).await {
+                        warn!("control write failed: {:#}", e);
+                        break;
                     }
+                }
+                Some(pb::client_to_server::Payload::SendTypingRequest(r)) => {
+                    let ch = parse_channel_id(r.channel_id.as_ref())?;
                     if !self
                         .membership
                         .members_of(ch)
@@ -927,32 +1399,14 @@ impl Gateway {
                     {
                         return Err(ControlError::PermissionDenied("not a channel member").into());
                     }
-
-                    let removed = {
-                        let mut reactions = self.reactions.write().await;
-                        let mut did_remove = false;
-                        if let Some(by_msg) = reactions.get_mut(&(ch, msg_id)) {
-                            if let Some(users) = by_msg.get_mut(&emoji) {
-                                did_remove = users.remove(&user_id);
-                                if users.is_empty() {
-                                    by_msg.remove(&emoji);
-                                }
-                            }
-                            if by_msg.is_empty() {
-                                reactions.remove(&(ch, msg_id));
-                            }
-                        }
-                        did_remove
-                    };
-
-                    if removed {
+                    // Ephemeral: pushed straight to listeners, never via the
+                    // outbox. Notices inside the interval are acked but dropped.
+                    if self.typing.allow(user_id, ch, Instant::now()) {
                         self.broadcast_chat_event(
                             ch,
-                            pb::chat_event::Kind::ReactionRemoved(pb::ReactionRemoved {
-                                message_id: Some(pb::MessageId { value: msg_id.to_string() }),
+                            pb::chat_event::Kind::TypingStarted(pb::TypingStarted {
                                 channel_id: Some(pb::ChannelId { value: ch.0.to_string() }),
                                 user_id: Some(pb::UserId { value: user_id.0.to_string() }),
-                                emoji,
                             }),
                         )
                         .await;
@@ -966,8 +1420,8 @@ impl Gateway {
                         sent_at: Some(now_ts()),
                         error: None,
                         event_seq: 0,
-                        payload: Some(pb::server_to_client::Payload::RemoveReactionResponse(
-                            pb::RemoveReactionResponse {},
+                        payload: Some(pb::server_to_client::Payload::SendTypingResponse(
+                            pb::SendTypingResponse {},
                         )),
                     };
                     if let Err(e) = write_delimited(&mut send, &resp).await {
@@ -975,24 +1429,43 @@ impl Gateway {
                         break;
                     }
                 }
-                Some(pb::client_to_server::Payload::SendTypingRequest(r)) => {
+                Some(pb::client_to_server::Payload::MarkReadRequest(r)) => {
                     let ch = parse_channel_id(r.channel_id.as_ref())?;
-                    if !self
-                        .membership
-                        .members_of(ch)
-                        .unwrap_or_default()
-                        .contains(&user_id)
-                    {
-                        return Err(ControlError::PermissionDenied("not a channel member").into());
+                    let msg_id = MessageId(parse_message_uuid(r.up_to_message_id.as_ref())?);
+                    let (marker, advanced) = self.control.mark_read(&ctx, ch, msg_id).await?;
+                    if advanced {
+                        self.broadcast_chat_event(
+                            ch,
+                            pb::chat_event::Kind::ReadMarkerUpdated(pb::ReadMarkerUpdated {
+                                channel_id: Some(pb::ChannelId { value: ch.0.to_string() }),
+                                marker: Some(read_marker_to_pb(&marker)),
+                            }),
+                        )
+                        .await;
                     }
-                    self.broadcast_chat_event(
-                        ch,
-                        pb::chat_event::Kind::TypingStarted(pb::TypingStarted {
-                            channel_id: Some(pb::ChannelId { value: ch.0.to_string() }),
-                            user_id: Some(pb::UserId { value: user_id.0.to_string() }),
+
+                    let resp = pb::ServerToClient {
+                        request_id: req_id,
+                        session_id: Some(pb::SessionId {
+                            value: session_id.clone(),
                         }),
-                    )
-                    .await;
+                        sent_at: Some(now_ts()),
+                        error: None,
+                        event_seq: 0,
+                        payload: Some(pb::server_to_client::Payload::MarkReadResponse(
+                            pb::MarkReadResponse {
+                                marker: Some(read_marker_to_pb(&marker)),
+                            },
+                        )),
+                    };
+                    if let Err(e) = write_delimited(&mut send, &resp).await {
+                        warn!("control write failed: {:#}", e);
+                        break;
+                    }
+                }
+                Some(pb::client_to_server::Payload::GetReadStateRequest(r)) => {
+                    let ch = parse_channel_id(r.channel_id.as_ref())?;
+                    let markers = self.control.list_read_markers(&ctx, ch).await?;
 
                     let resp = pb::ServerToClient {
                         request_id: req_id,
@@ -1002,8 +1475,11 @@ impl Gateway {
                         sent_at: Some(now_ts()),
                         error: None,
                         event_seq: 0,
-                        payload: Some(pb::server_to_client::Payload::SendTypingResponse(
-                            pb::SendTypingResponse {},
+                        payload: Some(pb::server_to_client::Payload::GetReadStateResponse(
+                            pb::GetReadStateResponse {
+                                channel_id: Some(pb::ChannelId { value: ch.0.to_string() }),
+                                markers: markers.iter().map(read_marker_to_pb).collect(),
+                            },
                         )),
                     };
                     if let Err(e) = write_delimited(&mut send, &resp).await {
@@ -1011,7 +1487,28 @@ impl Gateway {
                         break;
                     }
                 }
-                Some(pb::client_to_server::Payload::ResumeSessionRequest(_r)) => {
+                Some(pb::client_to_server::Payload::ResumeSessionRequest(r)) => {
+                    let resumed = r.session_id.as_ref().is_some_and(|prev| {
+                        self.do_resume(user_id, &prev.value, &session_id)
+                    });
+                    if resumed {
+                        current_channel = self.membership.channel_of(user_id);
+                        // Deltas pushed while the connection was down never
+                        // arrived; resend the channel's authoritative state.
+                        if let Some(ch) = current_channel {
+                            if let Err(e) =
+                                self.control.request_channel_state_refresh(&ctx, ch).await
+                            {
+                                warn!("channel state refresh after resume failed: {:#}", e);
+                            }
+                        }
+                    }
+                    info!(
+                        session_id = %session_id,
+                        user_id = %user_id.0,
+                        resumed,
+                        "resume session request"
+                    );
                     let resp = pb::ServerToClient {
                         request_id: req_id,
                         session_id: Some(pb::SessionId { value: session_id.clone() }),
@@ -1020,7 +1517,7 @@ impl Gateway {
                         event_seq: 0,
                         payload: Some(pb::server_to_client::Payload::ResumeSessionResponse(
                             pb::ResumeSessionResponse {
-                                resumed: false,
+                                resumed,
                                 current_event_seq: 0,
                             },
                         )),
@@ -1063,6 +1560,32 @@ impl Gateway {
                                 self.control
                                     .kick_member(&ctx, ch, target, Some(k.reason))
                                     .await?;
+                                self.membership.leave_channel(target, ch);
+                            }
+                            pb::moderation_action_request::Action::Ban(b) => {
+                                tracing::info!(actor=%ctx.user_id.0,target=%target.0,channel=%ch.0,duration_seconds=b.duration_seconds,"moderation ban action");
+                                self.control
+                                    .ban_member(&ctx, ch, target, Some(b.reason), b.duration_seconds)
+                                    .await?;
+                                match b.duration_seconds {
+                                    0 => self.membership.set_banned(target, true),
+                                    secs => self
+                                        .membership
+                                        .ban_for(target, Duration::from_secs(u64::from(secs))),
+                                }
+                                self.membership.remove_user(target);
+                            }
+                            pb::moderation_action_request::Action::Move(mv) => {
+                                let to = parse_channel_id(mv.target_channel_id.as_ref())?;
+                                tracing::info!(actor=%ctx.user_id.0,target=%target.0,channel=%ch.0,to_channel=%to.0,"moderation move action");
+                                let member =
+                                    self.control.move_member(&ctx, ch, target, to).await?;
+                                // Reroute now rather than waiting for the
+                                // presence events to come back through the outbox.
+                                self.membership.leave_channel(target, ch);
+                                self.membership
+                                    .set_user(target, to, member.muted, member.deafened);
+                                self.membership.add_channel_member(to, target);
                             }
                             _ => {}
                         }
@@ -1090,10 +1613,41 @@ impl Gateway {
                         .ok_or(ControlError::InvalidArgument("target_user_id missing"))?;
                     let target = UserId(uuid::Uuid::parse_str(&target.value)
                         .map_err(|_| ControlError::InvalidArgument("invalid target_user_id"))?);
+                    if !self.sessions.has_user_sessions(target) {
+                        return Err(ControlError::FailedPrecondition(USER_OFFLINE).into());
+                    }
+                    if let Err(wait) = self.pokes.allow(user_id, target, Instant::now()) {
+                        return Err(ControlError::RateLimited {
+                            reason: POKE_THROTTLED,
+                            retry_after_secs: wait.as_secs_f32().ceil() as u32,
+                        }
+                        .into());
+                    }
                     tracing::info!(actor=%ctx.user_id.0,target=%target.0,"poke request");
-                    self.control
-                        .poke_user(&ctx, target, &identity.display_name, r.message)
-                        .await?;
+                    // Ephemeral: straight to the target's sessions, never via
+                    // the outbox, so an offline user doesn't get stale pokes.
+                    self.push
+                        .send_to(
+                            target,
+                            pb::ServerToClient {
+                                request_id: None,
+                                session_id: None,
+                                sent_at: Some(now_ts()),
+                                error: None,
+                                event_seq: 0,
+                                payload: Some(pb::server_to_client::Payload::PokeEvent(
+                                    pb::PokeEvent {
+                                        at: Some(now_ts()),
+                                        from_user_id: Some(pb::UserId {
+                                            value: user_id.0.to_string(),
+                                        }),
+                                        from_display_name: identity.display_name.clone(),
+                                        message: r.message,
+                                    },
+                                )),
+                            },
+                        )
+                        .await;
                     let resp = pb::ServerToClient {
                         request_id: req_id,
                         session_id: Some(pb::SessionId {
@@ -1311,6 +1865,115 @@ impl Gateway {
                     let resp = pb::ServerToClient { request_id: req_id, session_id: Some(pb::SessionId { value: session_id.clone() }), sent_at: Some(now_ts()), error: None, event_seq: 0, payload: Some(pb::server_to_client::Payload::RevokeBadge(pb::RevokeBadgeResponse {})) };
                     if let Err(e) = write_delimited(&mut send, &resp).await { warn!("control write failed: {:#}", e); break; }
                 }
+                Some(pb::client_to_server::Payload::SetVoiceTalkerTuning(r)) => {
+                    if !ctx.is_admin {
+                        return Err(ControlError::PermissionDenied("admin required").into());
+                    }
+                    let tuning = talker_tuning_from_pb(&r)?;
+                    self.voice.set_talker_tuning(tuning);
+                    info!(
+                        admin = %user_id.0,
+                        window_ms = tuning.activity_window.as_millis() as u64,
+                        vad_required = tuning.vad_required,
+                        "voice talker tuning updated"
+                    );
+                    let resp = pb::ServerToClient {
+                        request_id: req_id,
+                        session_id: Some(pb::SessionId {
+                            value: session_id.clone(),
+                        }),
+                        sent_at: Some(now_ts()),
+                        error: None,
+                        event_seq: 0,
+                        payload: Some(pb::server_to_client::Payload::SetVoiceTalkerTuning(
+                            pb::SetVoiceTalkerTuningResponse {
+                                talker_activity_window_ms: tuning.activity_window.as_millis()
+                                    as u32,
+                                vad_required_for_talker: tuning.vad_required,
+                            },
+                        )),
+                    };
+                    if let Err(e) = write_delimited(&mut send, &resp).await {
+                        warn!("control write failed: {:#}", e);
+                        break;
+                    }
+                }
+                Some(pb::client_to_server::Payload::DisconnectSession(r)) => {
+                    let target = parse_user_id(r.user_id.as_ref())?;
+                    if target == user_id {
+                        return Err(ControlError::InvalidArgument("cannot disconnect own session").into());
+                    }
+                    self.control
+                        .disconnect_session(&ctx, target, &r.reason, || {
+                            self.sessions.has_user_sessions(target)
+                        })
+                        .await?;
+                    let closed = self.sessions.close_user_sessions(
+                        target,
+                        CLOSE_CODE_ADMIN_DISCONNECT,
+                        b"disconnected by admin",
+                    );
+                    info!(
+                        actor = %user_id.0,
+                        target = %target.0,
+                        sessions_closed = closed,
+                        "admin force-disconnect"
+                    );
+                    let resp = pb::ServerToClient {
+                        request_id: req_id,
+                        session_id: Some(pb::SessionId {
+                            value: session_id.clone(),
+                        }),
+                        sent_at: Some(now_ts()),
+                        error: None,
+                        event_seq: 0,
+                        payload: Some(pb::server_to_client::Payload::DisconnectSession(
+                            pb::DisconnectSessionResponse {
+                                sessions_closed: closed as u32,
+                            },
+                        )),
+                    };
+                    if let Err(e) = write_delimited(&mut send, &resp).await {
+                        warn!("control write failed: {:#}", e);
+                        break;
+                    }
+                }
+                Some(pb::client_to_server::Payload::ListSessions(r)) => {
+                    if !ctx.is_admin {
+                        return Err(ControlError::PermissionDenied("admin required").into());
+                    }
+                    let sessions = self
+                        .sessions
+                        .all_sessions()
+                        .iter()
+                        .map(|s| {
+                            active_session_to_pb(
+                                s.user_id,
+                                &s.session_id,
+                                s.conn.remote_address(),
+                                self.membership.channel_of(s.user_id),
+                                s.connected_at,
+                                r.redact_addresses,
+                            )
+                        })
+                        .collect::<Vec<_>>();
+                    let resp = pb::ServerToClient {
+                        request_id: req_id,
+                        session_id: Some(pb::SessionId {
+                            value: session_id.clone(),
+                        }),
+                        sent_at: Some(now_ts()),
+                        error: None,
+                        event_seq: 0,
+                        payload: Some(pb::server_to_client::Payload::ListSessions(
+                            pb::ListSessionsResponse { sessions },
+                        )),
+                    };
+                    if let Err(e) = write_delimited(&mut send, &resp).await {
+                        warn!("control write failed: {:#}", e);
+                        break;
+                    }
+                }
                 Some(pb::client_to_server::Payload::StartScreenShareRequest(r)) => {
                     let ch = parse_channel_id(r.channel_id.as_ref())?;
                     let members = self.membership.members_of(ch);
@@ -1926,6 +2589,17 @@ impl Gateway {
                         error = %err,
                         "permission denied; keeping connection alive"
                     );
+                } else if err
+                    .downcast_ref::<ControlError>()
+                    .is_some_and(ControlError::is_unavailable)
+                {
+                    counter!(metric_name("gateway_requests_db_unavailable_total")).increment(1);
+                    warn!(
+                        session_id = %session_id,
+                        user_id = %user_id.0,
+                        error = %err,
+                        "database unavailable; returning retryable error"
+                    );
                 } else {
                     warn!(
                         session_id = %session_id,
@@ -1957,19 +2631,80 @@ impl Gateway {
         }
         .await;
 
-        match self.control.disconnect_user(&ctx).await {
-            Ok(channels) => {
-                self.membership.remove_user(user_id);
-                for ch in channels {
-                    if let Some(mut cur) = self.membership.members_of(ch) {
-                        cur.retain(|u| *u != user_id);
-                        let max = self.membership.max_talkers_of(ch).unwrap_or(4);
-                        self.membership.set_channel_state(ch, max, cur);
-                    }
+        let chat_channels = self
+            .membership
+            .drop_session_chat_subscriptions(user_id, &session_id);
+        let resumable = self.sessions.client_caps(&session_id).is_some_and(|caps| {
+            caps.features
+                .as_ref()
+                .is_some_and(|f| f.supports_session_resume)
+        });
+        if self.parked.take_superseded(&session_id) {
+            // A newer connection resumed this session and owns its membership.
+        } else if resumable && is_transport_loss(conn.close_reason()) {
+            // Hold membership for a while so a reconnect can resume instead of
+            // flapping leave/join presence for everyone in the channel.
+            self.parked
+                .park(&session_id, user_id, chat_channels, Instant::now());
+            let gw = self.clone();
+            let parked_session_id = session_id.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(ParkedSessions::TTL).await;
+                // A fresh session (say, after a client restart) may have joined
+                // somewhere since; its own disconnect will clean up instead.
+                if gw.parked.expire(&parked_session_id) && !gw.sessions.has_user_sessions(user_id) {
+                    gw.finish_disconnect(&ctx).await;
                 }
+            });
+        } else {
+            self.finish_disconnect(&ctx).await;
+        }
+
+        res
+    }
+
+    /// Adopt the session `prev_session_id` for `user_id`'s new connection
+    /// `session_id`: either parked after a lost connection and still within
+    /// its TTL, or still open on a connection the gateway hasn't noticed is
+    /// dead yet. The channel membership and chat subscriptions it held carry
+    /// over as-is.
+    fn do_resume(&self, user_id: UserId, prev_session_id: &str, session_id: &str) -> bool {
+        if prev_session_id == session_id {
+            return false;
+        }
+        if let Some(chat_channels) = self.parked.claim(prev_session_id, user_id, Instant::now()) {
+            for channel in chat_channels {
+                self.membership.subscribe_chat(user_id, session_id, channel);
+            }
+            return true;
+        }
+        // Mark before closing so the old connection's teardown sees it, and
+        // move its chat subscriptions off before that teardown drops them.
+        self.parked.supersede(prev_session_id);
+        self.membership
+            .move_session_chat_subscriptions(user_id, prev_session_id, session_id);
+        if self.sessions.close_session(
+            user_id,
+            prev_session_id,
+            CLOSE_CODE_SESSION_RESUMED,
+            b"session resumed",
+        ) {
+            return true;
+        }
+        self.parked.take_superseded(prev_session_id);
+        false
+    }
+
+    /// Leave the user's channels and drop their cached membership once a
+    /// session is gone for good.
+    async fn finish_disconnect(&self, ctx: &RequestContext) {
+        let user_id = ctx.user_id;
+        match self.control.disconnect_user(ctx).await {
+            Ok(_) => {
+                self.membership.remove_user(user_id);
                 if !self.sessions.has_user_sessions(user_id) {
                     if self.current_activity.remove(&user_id).is_some() {
-                        if let Ok(Some(row)) = self.control.get_user_profile(&ctx, user_id).await {
+                        if let Ok(Some(row)) = self.control.get_user_profile(ctx, user_id).await {
                             let mut p = profile_row_to_pb(row);
                             self.overlay_current_activity(user_id, &mut p);
                             self.broadcast_profile_updated(user_id, p).await;
@@ -1981,12 +2716,11 @@ impl Gateway {
                 warn!(
                     user_id = %user_id.0,
                     error = %e,
-                    "disconnect cleanup failed"
+                    "disconnect cleanup failed; evicting from membership cache only"
                 );
+                self.membership.remove_user(user_id);
             }
         }
-
-        res
     }
 
     async fn do_hello(
@@ -2018,6 +2752,13 @@ impl Gateway {
             max_upload_size_bytes: 50 * 1024 * 1024,
             ping_interval_ms: 15_000,
             auth_challenge: auth_challenge.to_vec(),
+            server_info: Some(server_info(&ServerInfoOptions {
+                read_receipts: self.control.read_receipts_enabled(),
+                voice_loudness: self.voice_loudness,
+                voice_mixing: self.voice_mixing,
+                voice_recording: self.voice_recording,
+                auth_methods: self.auth.methods(),
+            })),
         };
 
         let resp = pb::ServerToClient {
@@ -2053,11 +2794,37 @@ impl Gateway {
             _ => return Err(anyhow!("expected AuthRequest as second message")),
         };
 
-        let mut identity = self
+        let mut identity = match self
             .auth
             .authenticate(&auth_req, session_id, auth_challenge)
             .await
-            .context("auth failed")?;
+        {
+            Ok(identity) => {
+                self.metrics.auth_success();
+                identity
+            }
+            Err(err) => {
+                self.metrics.auth_failed();
+                // Tell the client why before the connection goes away, so it
+                // can show the reason instead of a bare disconnect.
+                let resp = pb::ServerToClient {
+                    request_id: req.request_id,
+                    session_id: Some(pb::SessionId {
+                        value: session_id.to_string(),
+                    }),
+                    sent_at: Some(now_ts()),
+                    error: Some(auth_error(&err)),
+                    event_seq: 0,
+                    payload: None,
+                };
+                if let Err(e) = write_delimited(send, &resp).await {
+                    warn!("auth error write failed: {:#}", e);
+                }
+                let _ = send.finish();
+                let _ = timeout(Duration::from_secs(1), send.stopped()).await;
+                return Err(err.context("auth failed"));
+            }
+        };
         if let Some(preferred) = normalize_preferred_display_name(&auth_req.preferred_display_name)
         {
             identity.display_name = preferred;
@@ -2124,11 +2891,35 @@ impl Gateway {
         }
     }
 
-    /// Broadcast a `ScreenShareEvent` to all members of `channel_id`.
-    async fn broadcast_screen_share_event(
+    /// Broadcast a `ScreenShareEvent` to all members of `channel_id`.
+    async fn broadcast_screen_share_event(
+        &self,
+        channel_id: ChannelId,
+        kind: pb::screen_share_event::Kind,
+    ) {
+        let recipients = self.membership.members_of(channel_id).unwrap_or_default();
+        let msg = pb::ServerToClient {
+            request_id: None,
+            session_id: None,
+            sent_at: Some(now_ts()),
+            error: None,
+            event_seq: 0,
+            payload: Some(pb::server_to_client::Payload::ScreenShareEvent(
+                pb::ScreenShareEvent {
+                    at: Some(now_ts()),
+                    kind: Some(kind),
+                },
+            )),
+        };
+        for uid in recipients {
+            self.push.send_to(uid, msg.clone()).await;
+        }
+    }
+
+    async fn broadcast_presence_event(
         &self,
         channel_id: ChannelId,
-        kind: pb::screen_share_event::Kind,
+        kind: pb::presence_event::Kind,
     ) {
         let recipients = self.membership.members_of(channel_id).unwrap_or_default();
         let msg = pb::ServerToClient {
@@ -2137,8 +2928,8 @@ impl Gateway {
             sent_at: Some(now_ts()),
             error: None,
             event_seq: 0,
-            payload: Some(pb::server_to_client::Payload::ScreenShareEvent(
-                pb::ScreenShareEvent {
+            payload: Some(pb::server_to_client::Payload::PresenceEvent(
+                pb::PresenceEvent {
                     at: Some(now_ts()),
                     kind: Some(kind),
                 },
@@ -2231,6 +3022,9 @@ impl Gateway {
                     user_limit: channel.max_members.unwrap_or_default().max(0) as u32,
                     bitrate: channel.bitrate_bps.max(0) as u32,
                     opus_profile: channel.opus_profile,
+                    max_message_length: channel_limit(channel.max_message_length),
+                    slow_mode_secs: channel.slow_mode_secs.max(0) as u32,
+                    announce_presence: channel.announce_presence,
                     ..Default::default()
                 }),
             });
@@ -2244,15 +3038,21 @@ impl Gateway {
             .await?;
             let pb_members = members
                 .into_iter()
-                .map(|m| pb::ChannelMember {
-                    user_id: Some(pb::UserId {
-                        value: m.user_id.0.to_string(),
-                    }),
-                    display_name: m.display_name,
-                    muted: m.muted,
-                    deafened: m.deafened,
-                    away_message: m.custom_status_text,
-                    ..Default::default()
+                .map(|m| {
+                    let (self_muted, self_deafened) =
+                        self.membership.self_voice_state(m.user_id, channel.id);
+                    pb::ChannelMember {
+                        user_id: Some(pb::UserId {
+                            value: m.user_id.0.to_string(),
+                        }),
+                        display_name: m.display_name,
+                        muted: m.muted,
+                        deafened: m.deafened,
+                        self_muted,
+                        self_deafened,
+                        away_message: m.custom_status_text,
+                        ..Default::default()
+                    }
                 })
                 .collect::<Vec<_>>();
 
@@ -2298,10 +3098,51 @@ impl Gateway {
             default_channel_id,
             snapshot_version: unix_ms_u64(),
             self_profile,
+            read_receipts_enabled: self.control.read_receipts_enabled(),
         })
     }
 }
 
+fn close_for_abuse(
+    conn: &quinn::Connection,
+    user_id: UserId,
+    violation: Violation,
+    accounting: &ConnAccounting,
+) {
+    warn!(
+        user_id = %user_id.0,
+        remote = %conn.remote_address(),
+        reason = violation.as_str(),
+        streams_total = accounting.streams_total,
+        datagrams_total = accounting.datagrams_total,
+        datagram_bytes_total = accounting.datagram_bytes_total,
+        "closing connection: abuse threshold exceeded"
+    );
+    counter!(
+        metric_name("gateway_conn_abuse_closed_total"),
+        "reason" => violation.as_str()
+    )
+    .increment(1);
+    conn.close(
+        quinn::VarInt::from_u32(CLOSE_CODE_ABUSE),
+        violation.as_str().as_bytes(),
+    );
+}
+
+/// Whether the connection was lost rather than closed on purpose by either
+/// side; only those sessions are worth holding for a resume.
+fn is_transport_loss(reason: Option<quinn::ConnectionError>) -> bool {
+    matches!(
+        reason,
+        Some(
+            quinn::ConnectionError::TimedOut
+                | quinn::ConnectionError::Reset
+                | quinn::ConnectionError::TransportError(_)
+                | quinn::ConnectionError::ConnectionClosed(_)
+        )
+    )
+}
+
 fn normalize_preferred_display_name(value: &str) -> Option<String> {
     let trimmed = value.trim();
     if trimmed.is_empty() {
@@ -2490,7 +3331,152 @@ fn parse_message_uuid(id: Option<&pb::MessageId>) -> Result<uuid::Uuid> {
         .map_err(|_| ControlError::InvalidArgument("invalid message_id").into())
 }
 
+fn read_marker_to_pb(marker: &ReadMarker) -> pb::ReadMarker {
+    pb::ReadMarker {
+        user_id: Some(pb::UserId {
+            value: marker.user_id.0.to_string(),
+        }),
+        last_read_message_id: Some(pb::MessageId {
+            value: marker.message_id.0.to_string(),
+        }),
+        updated_at: Some(pb::Timestamp {
+            unix_millis: marker.updated_at.timestamp_millis(),
+        }),
+    }
+}
+
+fn history_entry_to_pb(entry: ChatHistoryEntry) -> pb::MessagePosted {
+    let msg = entry.message;
+    pb::MessagePosted {
+        message_id: Some(pb::MessageId {
+            value: msg.id.0.to_string(),
+        }),
+        channel_id: Some(pb::ChannelId {
+            value: msg.channel_id.0.to_string(),
+        }),
+        author_user_id: Some(pb::UserId {
+            value: msg.author_user_id.0.to_string(),
+        }),
+        text: msg.text,
+        attachments: crate::outbox_dispatch::json_attachments_to_pb(msg.attachments),
+        edited_at: msg.edited_at.map(|at| pb::Timestamp {
+            unix_millis: at.timestamp_millis(),
+        }),
+        reactions: entry
+            .reactions
+            .into_iter()
+            .map(|r| pb::Reaction {
+                emoji: r.emoji,
+                count: r.count as u32,
+                users: Vec::new(),
+                me: r.me,
+            })
+            .collect(),
+        kind: match msg.kind {
+            ChatMessageKind::System => pb::MessageKind::System,
+            ChatMessageKind::User => pb::MessageKind::User,
+        } as i32,
+        created_at: Some(pb::Timestamp {
+            unix_millis: msg.created_at.timestamp_millis(),
+        }),
+        ..Default::default()
+    }
+}
+
+fn active_session_to_pb(
+    user_id: UserId,
+    session_id: &str,
+    remote: std::net::SocketAddr,
+    channel: Option<ChannelId>,
+    connected_at: std::time::SystemTime,
+    redact_address: bool,
+) -> pb::ActiveSession {
+    pb::ActiveSession {
+        user_id: Some(pb::UserId {
+            value: user_id.0.to_string(),
+        }),
+        session_id: Some(pb::SessionId {
+            value: session_id.to_string(),
+        }),
+        remote_address: if redact_address {
+            String::new()
+        } else {
+            remote.to_string()
+        },
+        channel_id: channel.map(|ch| pb::ChannelId {
+            value: ch.0.to_string(),
+        }),
+        connected_at: Some(pb::Timestamp {
+            unix_millis: connected_at
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as i64,
+        }),
+    }
+}
+
+fn channel_member_to_pb(
+    membership: &MembershipCache,
+    channel: ChannelId,
+    m: Member,
+) -> pb::ChannelMember {
+    let (self_muted, self_deafened) = membership.self_voice_state(m.user_id, channel);
+    pb::ChannelMember {
+        user_id: Some(pb::UserId {
+            value: m.user_id.0.to_string(),
+        }),
+        display_name: m.display_name,
+        muted: m.muted,
+        deafened: m.deafened,
+        self_muted,
+        self_deafened,
+        away_message: m.custom_status_text,
+        custom_status_emoji: m.custom_status_emoji,
+        ..Default::default()
+    }
+}
+
+/// Wire form of an optional channel limit: unset or non-positive means
+/// unlimited, sent as 0.
+fn channel_limit(limit: Option<i32>) -> u32 {
+    limit.unwrap_or_default().max(0) as u32
+}
+
+fn talker_tuning_from_pb(r: &pb::SetVoiceTalkerTuningRequest) -> Result<TalkerTuning> {
+    if !TALKER_WINDOW_MS_RANGE.contains(&r.talker_activity_window_ms) {
+        return Err(ControlError::InvalidArgument("talker_activity_window_ms out of range").into());
+    }
+    Ok(TalkerTuning {
+        activity_window: Duration::from_millis(u64::from(r.talker_activity_window_ms)),
+        vad_required: r.vad_required_for_talker,
+    })
+}
+
+/// `pb::Error` for a rejected `AuthRequest`. Credential problems are
+/// `UNAUTHENTICATED`; a database outage is `UNAVAILABLE` so the client keeps
+/// retrying instead of treating its credentials as bad.
+fn auth_error(err: &anyhow::Error) -> pb::Error {
+    let unavailable = err.chain().any(|cause| cause.is::<sqlx::Error>());
+    let (code, message) = if unavailable {
+        (pb::error::Code::Unavailable, "authentication unavailable")
+    } else {
+        (pb::error::Code::Unauthenticated, "authentication failed")
+    };
+    pb::Error {
+        code: code as i32,
+        message: message.to_string(),
+        detail: if unavailable {
+            String::new()
+        } else {
+            err.to_string()
+        },
+        retry_after_ms: 0,
+        reason: pb::error::Reason::Unspecified as i32,
+    }
+}
+
 fn error_from_anyhow(err: &anyhow::Error) -> pb::Error {
+    let mut retry_after_ms = 0;
     let (code, message) = if let Some(control_err) = err.downcast_ref::<ControlError>() {
         match control_err {
             ControlError::NotFound(msg) => (pb::error::Code::NotFound as i32, *msg),
@@ -2503,17 +3489,49 @@ fn error_from_anyhow(err: &anyhow::Error) -> pb::Error {
             ControlError::FailedPrecondition(msg) => {
                 (pb::error::Code::FailedPrecondition as i32, *msg)
             }
+            ControlError::RateLimited {
+                reason,
+                retry_after_secs,
+            } => {
+                retry_after_ms = retry_after_secs.saturating_mul(1000);
+                (pb::error::Code::RateLimited as i32, *reason)
+            }
             ControlError::Db(_) => (pb::error::Code::Unavailable as i32, "database unavailable"),
+            ControlError::Anyhow(_) if control_err.is_unavailable() => {
+                (pb::error::Code::Unavailable as i32, "database unavailable")
+            }
             ControlError::Anyhow(_) => (pb::error::Code::Internal as i32, "internal error"),
         }
     } else {
         (pb::error::Code::Internal as i32, "internal error")
     };
 
+    let reason = err
+        .downcast_ref::<ControlError>()
+        .map_or(pb::error::Reason::Unspecified, error_reason);
+
     pb::Error {
         code,
         message: message.to_string(),
         detail: format!("{:#}", err),
+        retry_after_ms,
+        reason: reason as i32,
+    }
+}
+
+/// The structured reason for the errors clients react to.
+fn error_reason(err: &ControlError) -> pb::error::Reason {
+    match err {
+        ControlError::ResourceExhausted(CHANNEL_FULL) => pb::error::Reason::ChannelFull,
+        ControlError::RateLimited {
+            reason: SLOW_MODE, ..
+        } => pb::error::Reason::SlowMode,
+        ControlError::FailedPrecondition(USER_OFFLINE) => pb::error::Reason::UserOffline,
+        ControlError::RateLimited {
+            reason: POKE_THROTTLED,
+            ..
+        } => pb::error::Reason::PokeThrottled,
+        _ => pb::error::Reason::Unspecified,
     }
 }
 
@@ -2645,18 +3663,103 @@ fn verify_profile_asset_data(data: &[u8]) -> Result<Vec<u8>> {
     Ok(data.to_vec())
 }
 
+/// The deployment-dependent parts of [`server_info`].
+#[derive(Debug, Clone, Default)]
+struct ServerInfoOptions {
+    read_receipts: bool,
+    voice_loudness: bool,
+    voice_mixing: bool,
+    voice_recording: bool,
+    auth_methods: Vec<pb::AuthMethod>,
+}
+
+fn server_going_away_push(grace: Duration) -> pb::ServerToClient {
+    pb::ServerToClient {
+        request_id: None,
+        session_id: None,
+        sent_at: Some(now_ts()),
+        error: None,
+        event_seq: 0,
+        payload: Some(pb::server_to_client::Payload::ServerGoingAway(
+            pb::ServerGoingAway {
+                reason: "server shutting down".to_string(),
+                grace_ms: grace.as_millis().min(u32::MAX as u128) as u32,
+            },
+        )),
+    }
+}
+
+/// What this gateway build supports, for `HelloAck.server_info`.
+fn server_info(opts: &ServerInfoOptions) -> pb::ServerInfo {
+    let mut features = vec![
+        pb::ServerFeature::VoiceFec,
+        pb::ServerFeature::VoiceMultiFrame,
+        pb::ServerFeature::FileUpload,
+        pb::ServerFeature::Reactions,
+        pb::ServerFeature::ScreenShare,
+        pb::ServerFeature::TextChannels,
+        pb::ServerFeature::CustomStatus,
+        pb::ServerFeature::Poke,
+        pb::ServerFeature::Whisper,
+    ];
+    if opts.read_receipts {
+        features.push(pb::ServerFeature::ReadReceipts);
+    }
+    if opts.voice_loudness {
+        features.push(pb::ServerFeature::VoiceLoudness);
+    }
+    if opts.voice_recording {
+        features.push(pb::ServerFeature::Recording);
+    }
+    pb::ServerInfo {
+        server_version: env!("CARGO_PKG_VERSION").to_string(),
+        feature_bits: features
+            .into_iter()
+            .fold(0u64, |bits, f| bits | 1u64 << (f as u32)),
+        // The codecs negotiate_codecs will pick between, in its preference order.
+        screen_share_codecs: vec![
+            pb::video_caps::Codec::Av1 as i32,
+            pb::video_caps::Codec::Vp9 as i32,
+        ],
+        max_voice_bitrate_bps: MAX_CHANNEL_BITRATE_BPS as u32,
+        voice_mixing: opts.voice_mixing,
+        auth_methods: opts.auth_methods.iter().map(|m| *m as i32).collect(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
-        accepted_layer_ids_for_request, allows_1440p60, error_from_anyhow, is_video_datagram,
-        negotiate_codecs, normalize_preferred_display_name,
+        accepted_layer_ids_for_request, active_session_to_pb, allows_1440p60, auth_error,
+        error_from_anyhow, is_transport_loss, is_video_datagram, negotiate_codecs,
+        normalize_preferred_display_name, server_going_away_push, server_info,
+        talker_tuning_from_pb, Gateway, ServerInfoOptions, CLOSE_CODE_ABUSE,
+        CLOSE_CODE_ADMIN_DISCONNECT, CLOSE_CODE_SERVER_SHUTDOWN, CLOSE_CODE_SESSION_RESUMED,
+        CONTROL_STREAM_MAX_MSG,
     };
+    use crate::auth::{AuthProvider, AuthedIdentity};
+    use crate::conn_guard::ConnLimits;
+    use crate::frame::{read_delimited, write_delimited};
+    use crate::media::MediaService;
+    use crate::metrics_adapter::{stream_metrics, voice_metrics};
     use crate::proto::voiceplatform::v1 as pb;
-    use crate::state::{ShareMetadata, StreamSessionOwnership, StreamSessionRegistry};
+    use crate::state::{
+        MembershipCache, ParkedSessions, PushHub, Sessions, ShareMetadata, StreamSessionOwnership,
+        StreamSessionRegistry, VoiceTelemetryCache,
+    };
+    use crate::tls;
+    use sqlx::PgPool;
     use std::collections::HashMap;
-    use tokio::time::Instant;
-    use vp_control::ids::{ChannelId, UserId};
-    use vp_control::ControlError;
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+    use tokio::sync::mpsc;
+    use tokio::time::{timeout, Duration, Instant};
+    use vp_control::errors::{CHANNEL_FULL, POKE_THROTTLED, SLOW_MODE, USER_OFFLINE};
+    use vp_control::ids::{ChannelId, ServerId, UserId};
+    use vp_control::model::ChannelCreate;
+    use vp_control::{ControlError, ControlService, PgControlRepo, RequestContext};
+    use vp_media::stream_forwarder::{StreamForwarder, StreamForwarderConfig};
+    use vp_media::voice_forwarder::{VoiceForwarder, VoiceForwarderConfig};
 
     fn test_metadata() -> ShareMetadata {
         ShareMetadata { codec: pb::VideoCodec::Vp9 as i32, layers: vec![], has_audio: false }
@@ -2669,6 +3772,107 @@ mod tests {
         assert_eq!(mapped.code, pb::error::Code::PermissionDenied as i32);
     }
 
+    #[test]
+    fn lost_db_connection_maps_to_retryable_unavailable() {
+        let inner = anyhow::Error::new(sqlx::Error::PoolTimedOut).context("insert message");
+        let err = anyhow::Error::new(ControlError::Anyhow(inner));
+        let mapped = error_from_anyhow(&err);
+        assert_eq!(mapped.code, pb::error::Code::Unavailable as i32);
+
+        let err = anyhow::Error::new(ControlError::Anyhow(anyhow::anyhow!("bad row")));
+        assert_eq!(
+            error_from_anyhow(&err).code,
+            pb::error::Code::Internal as i32
+        );
+    }
+
+    #[test]
+    fn auth_failures_map_to_unauthenticated_unless_the_db_is_down() {
+        let err = anyhow::anyhow!("token expired");
+        let mapped = auth_error(&err);
+        assert_eq!(mapped.code, pb::error::Code::Unauthenticated as i32);
+        assert_eq!(mapped.detail, "token expired");
+
+        let err = anyhow::Error::new(sqlx::Error::PoolTimedOut).context("lookup oidc identity");
+        let mapped = auth_error(&err);
+        assert_eq!(mapped.code, pb::error::Code::Unavailable as i32);
+        assert!(mapped.detail.is_empty());
+    }
+
+    #[test]
+    fn slow_mode_maps_to_rate_limited_with_retry_after() {
+        let err = anyhow::Error::new(ControlError::RateLimited {
+            reason: "slow mode",
+            retry_after_secs: 5,
+        });
+        let mapped = error_from_anyhow(&err);
+        assert_eq!(mapped.code, pb::error::Code::RateLimited as i32);
+        assert_eq!(mapped.message, "slow mode");
+        assert_eq!(mapped.retry_after_ms, 5_000);
+
+        let err = anyhow::Error::new(ControlError::InvalidArgument("message too long"));
+        assert_eq!(error_from_anyhow(&err).retry_after_ms, 0);
+    }
+
+    #[test]
+    fn errors_clients_react_to_carry_a_structured_reason() {
+        let cases = [
+            (
+                ControlError::ResourceExhausted(CHANNEL_FULL),
+                pb::error::Reason::ChannelFull,
+            ),
+            (
+                ControlError::RateLimited {
+                    reason: SLOW_MODE,
+                    retry_after_secs: 5,
+                },
+                pb::error::Reason::SlowMode,
+            ),
+            (
+                ControlError::FailedPrecondition(USER_OFFLINE),
+                pb::error::Reason::UserOffline,
+            ),
+            (
+                ControlError::RateLimited {
+                    reason: POKE_THROTTLED,
+                    retry_after_secs: 1,
+                },
+                pb::error::Reason::PokeThrottled,
+            ),
+            (
+                ControlError::ResourceExhausted("channel limit reached"),
+                pb::error::Reason::Unspecified,
+            ),
+        ];
+        for (err, reason) in cases {
+            let mapped = error_from_anyhow(&anyhow::Error::new(err));
+            assert_eq!(mapped.reason, reason as i32, "{}", mapped.message);
+        }
+    }
+
+    #[test]
+    fn talker_tuning_request_is_bounds_checked() {
+        let ok = talker_tuning_from_pb(&pb::SetVoiceTalkerTuningRequest {
+            talker_activity_window_ms: 400,
+            vad_required_for_talker: true,
+        })
+        .unwrap();
+        assert_eq!(ok.activity_window, Duration::from_millis(400));
+        assert!(ok.vad_required);
+
+        for ms in [0, 99, 5_001] {
+            let err = talker_tuning_from_pb(&pb::SetVoiceTalkerTuningRequest {
+                talker_activity_window_ms: ms,
+                vad_required_for_talker: false,
+            })
+            .unwrap_err();
+            assert_eq!(
+                error_from_anyhow(&err).code,
+                pb::error::Code::InvalidArgument as i32
+            );
+        }
+    }
+
     #[test]
     fn voice_flags_0x02_is_not_video_datagram() {
         // Voice packets use byte[1] as flags; 0x02 (e.g., FEC) must not route as video.
@@ -3086,4 +4290,556 @@ mod tests {
         assert_eq!(ownership.metadata.layers.len(), 1);
         assert_eq!(ownership.metadata.layers[0].width, 1920);
     }
+
+    #[test]
+    fn active_session_redacts_address_on_request() {
+        let user = UserId::new();
+        let channel = ChannelId::new();
+        let addr: std::net::SocketAddr = "203.0.113.7:4433".parse().unwrap();
+        let at = std::time::UNIX_EPOCH + std::time::Duration::from_millis(1_700_000_000_000);
+
+        let open = active_session_to_pb(user, "s1", addr, Some(channel), at, false);
+        assert_eq!(open.remote_address, "203.0.113.7:4433");
+        assert_eq!(open.channel_id.unwrap().value, channel.0.to_string());
+        assert_eq!(open.connected_at.unwrap().unix_millis, 1_700_000_000_000);
+
+        let redacted = active_session_to_pb(user, "s1", addr, None, at, true);
+        assert!(redacted.remote_address.is_empty());
+        assert!(redacted.channel_id.is_none());
+    }
+
+    #[test]
+    fn server_info_sets_feature_bits_and_follows_read_receipt_config() {
+        let bit = |f: pb::ServerFeature| 1u64 << (f as u32);
+        let info = server_info(&ServerInfoOptions::default());
+        assert_eq!(info.server_version, env!("CARGO_PKG_VERSION"));
+        assert_ne!(info.feature_bits & bit(pb::ServerFeature::Reactions), 0);
+        assert_ne!(info.feature_bits & bit(pb::ServerFeature::Whisper), 0);
+        assert_eq!(info.feature_bits & bit(pb::ServerFeature::ReadReceipts), 0);
+        assert_eq!(info.feature_bits & bit(pb::ServerFeature::Relay), 0);
+        assert_eq!(info.feature_bits & bit(pb::ServerFeature::VoiceLoudness), 0);
+        assert_eq!(info.feature_bits & bit(pb::ServerFeature::Recording), 0);
+        assert_eq!(info.feature_bits & 1, 0);
+        assert!(!info.voice_mixing);
+
+        let info = server_info(&ServerInfoOptions {
+            read_receipts: true,
+            voice_loudness: true,
+            voice_mixing: true,
+            voice_recording: true,
+            auth_methods: vec![pb::AuthMethod::Device, pb::AuthMethod::OidcToken],
+        });
+        assert_ne!(info.feature_bits & bit(pb::ServerFeature::ReadReceipts), 0);
+        assert_ne!(info.feature_bits & bit(pb::ServerFeature::VoiceLoudness), 0);
+        assert_ne!(info.feature_bits & bit(pb::ServerFeature::Recording), 0);
+        assert!(info.voice_mixing);
+        assert_eq!(
+            info.auth_methods,
+            vec![
+                pb::AuthMethod::Device as i32,
+                pb::AuthMethod::OidcToken as i32
+            ]
+        );
+    }
+
+    #[test]
+    fn server_info_codecs_match_negotiation() {
+        let info = server_info(&ServerInfoOptions::default());
+        let advertised: Vec<pb::VideoCodec> = info
+            .screen_share_codecs()
+            .map(|c| match c {
+                pb::video_caps::Codec::Av1 => pb::VideoCodec::Av1,
+                pb::video_caps::Codec::Vp9 => pb::VideoCodec::Vp9,
+                other => panic!("advertised unnegotiable codec {other:?}"),
+            })
+            .collect();
+        for codec in &advertised {
+            let plan = negotiate_codecs(&[*codec], &HashMap::new()).expect("plan");
+            assert_eq!(plan.primary, *codec);
+        }
+        assert_eq!(info.max_voice_bitrate_bps, 510_000);
+    }
+
+    #[test]
+    fn close_codes_are_distinct() {
+        let codes = [
+            CLOSE_CODE_ABUSE,
+            CLOSE_CODE_ADMIN_DISCONNECT,
+            CLOSE_CODE_SESSION_RESUMED,
+            CLOSE_CODE_SERVER_SHUTDOWN,
+            vp_voice::CLOSE_CODE_ALPN_MISMATCH,
+        ];
+        for (i, a) in codes.iter().enumerate() {
+            assert!(
+                codes[i + 1..].iter().all(|b| a != b),
+                "duplicate close code {a:#x}"
+            );
+        }
+    }
+
+    #[test]
+    fn only_a_lost_connection_is_held_for_resume() {
+        assert!(is_transport_loss(Some(quinn::ConnectionError::TimedOut)));
+        assert!(is_transport_loss(Some(quinn::ConnectionError::Reset)));
+        // Either side hanging up on purpose ends the session for good.
+        let kicked = quinn::ApplicationClose {
+            error_code: quinn::VarInt::from_u32(CLOSE_CODE_ADMIN_DISCONNECT),
+            reason: bytes::Bytes::from_static(b"kicked"),
+        };
+        assert!(!is_transport_loss(Some(
+            quinn::ConnectionError::ApplicationClosed(kicked)
+        )));
+        assert!(!is_transport_loss(Some(
+            quinn::ConnectionError::LocallyClosed
+        )));
+        assert!(!is_transport_loss(None));
+    }
+
+    #[test]
+    fn going_away_push_is_an_unsequenced_push_with_the_grace_period() {
+        let push = server_going_away_push(std::time::Duration::from_secs(15));
+        assert_eq!(push.request_id, None);
+        assert_eq!(push.event_seq, 0);
+        let Some(pb::server_to_client::Payload::ServerGoingAway(away)) = push.payload else {
+            panic!("expected ServerGoingAway, got {:?}", push.payload);
+        };
+        assert_eq!(away.grace_ms, 15_000);
+        assert!(!away.reason.is_empty());
+    }
+
+    /// Lets every connection in as the same user, so a test can reconnect
+    /// without device keys.
+    struct FixedIdentity(AuthedIdentity);
+
+    #[async_trait::async_trait]
+    impl AuthProvider for FixedIdentity {
+        async fn authenticate(
+            &self,
+            _req: &pb::AuthRequest,
+            _session_id: &str,
+            _auth_challenge: &[u8],
+        ) -> anyhow::Result<AuthedIdentity> {
+            Ok(self.0.clone())
+        }
+
+        fn methods(&self) -> Vec<pb::AuthMethod> {
+            Vec::new()
+        }
+    }
+
+    const TEST_ALPN: &str = "vp-test";
+
+    /// A gateway serving on a loopback QUIC endpoint, plus a client config
+    /// that trusts its self-signed certificate. The server drops a silent
+    /// peer after half a second so a lost connection is noticed quickly.
+    async fn spawn_test_gateway(
+        pool: PgPool,
+        identity: AuthedIdentity,
+    ) -> anyhow::Result<(Gateway, SocketAddr, quinn::ClientConfig)> {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let server_id = ServerId(uuid::Uuid::parse_str(&identity.server_id)?);
+
+        let sessions = Sessions::new();
+        let membership = MembershipCache::new();
+        let (prune_wake_tx, _) = mpsc::channel(1);
+        let voice = Arc::new(VoiceForwarder::new(
+            VoiceForwarderConfig::default(),
+            Arc::new(sessions.clone()),
+            Arc::new(membership.clone()),
+            voice_metrics(),
+            prune_wake_tx,
+        ));
+        let video = Arc::new(StreamForwarder::new(
+            StreamForwarderConfig::default(),
+            Arc::new(sessions.clone()),
+            Arc::new(membership.clone()),
+            stream_metrics(),
+        ));
+        let media = Arc::new(
+            MediaService::new(
+                pool.clone(),
+                std::env::temp_dir().join("vp-gateway-test-uploads"),
+                server_id,
+            )
+            .await?,
+        );
+        let gw = Gateway::new(
+            Arc::new(FixedIdentity(identity)),
+            TEST_ALPN.to_string(),
+            Arc::new(ControlService::new(PgControlRepo::new(pool))),
+            sessions,
+            PushHub::new(),
+            membership,
+            VoiceTelemetryCache::new(),
+            voice,
+            video,
+            media,
+            16,
+            ConnLimits::UNLIMITED,
+        );
+
+        let (certs, key) = tls::load_or_generate_tls(None, None, &[])?;
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add(certs[0].clone())?;
+
+        let mut server_crypto = rustls::ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(certs, key)?;
+        server_crypto.alpn_protocols = vec![TEST_ALPN.as_bytes().to_vec()];
+        let mut server_config = quinn::ServerConfig::with_crypto(Arc::new(
+            quinn::crypto::rustls::QuicServerConfig::try_from(server_crypto)?,
+        ));
+        let mut transport = quinn::TransportConfig::default();
+        transport.max_idle_timeout(Some(Duration::from_millis(500).try_into()?));
+        server_config.transport_config(Arc::new(transport));
+        let endpoint = quinn::Endpoint::server(server_config, "127.0.0.1:0".parse()?)?;
+        let addr = endpoint.local_addr()?;
+        tokio::spawn(
+            gw.clone()
+                .serve(endpoint, std::future::pending::<()>(), Duration::ZERO),
+        );
+
+        let mut client_crypto = rustls::ClientConfig::builder()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        client_crypto.alpn_protocols = vec![TEST_ALPN.as_bytes().to_vec()];
+        let mut client_config = quinn::ClientConfig::new(Arc::new(
+            quinn::crypto::rustls::QuicClientConfig::try_from(client_crypto)?,
+        ));
+        let mut transport = quinn::TransportConfig::default();
+        transport.keep_alive_interval(Some(Duration::from_millis(100)));
+        client_config.transport_config(Arc::new(transport));
+
+        Ok((gw, addr, client_config))
+    }
+
+    /// Speaks the control stream the way the desktop client does.
+    struct TestClient {
+        _endpoint: quinn::Endpoint,
+        conn: quinn::Connection,
+        send: quinn::SendStream,
+        recv: quinn::RecvStream,
+        session_id: String,
+        next_request_id: u64,
+    }
+
+    impl TestClient {
+        /// Connect, say Hello advertising session resume, and authenticate.
+        async fn connect(addr: SocketAddr, config: quinn::ClientConfig) -> anyhow::Result<Self> {
+            let mut endpoint = quinn::Endpoint::client("127.0.0.1:0".parse()?)?;
+            endpoint.set_default_client_config(config);
+            let conn = endpoint.connect(addr, "localhost")?.await?;
+            let (send, recv) = conn.open_bi().await?;
+            let mut client = Self {
+                _endpoint: endpoint,
+                conn,
+                send,
+                recv,
+                session_id: String::new(),
+                next_request_id: 0,
+            };
+
+            let hello = pb::Hello {
+                caps: Some(pb::ClientCaps {
+                    features: Some(pb::FeatureCaps {
+                        supports_session_resume: true,
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+                device_id: None,
+            };
+            let Some(pb::server_to_client::Payload::HelloAck(ack)) = client
+                .request(pb::client_to_server::Payload::Hello(hello))
+                .await?
+            else {
+                anyhow::bail!("expected HelloAck");
+            };
+            client.session_id = ack.session_id.map(|s| s.value).unwrap_or_default();
+
+            let auth = pb::AuthRequest::default();
+            let Some(pb::server_to_client::Payload::AuthResponse(_)) = client
+                .request(pb::client_to_server::Payload::AuthRequest(auth))
+                .await?
+            else {
+                anyhow::bail!("expected AuthResponse");
+            };
+            Ok(client)
+        }
+
+        /// Send one request and wait for its response, skipping any pushes.
+        async fn request(
+            &mut self,
+            payload: pb::client_to_server::Payload,
+        ) -> anyhow::Result<Option<pb::server_to_client::Payload>> {
+            self.next_request_id += 1;
+            let request_id = Some(pb::RequestId {
+                value: self.next_request_id,
+            });
+            let req = pb::ClientToServer {
+                request_id,
+                session_id: Some(pb::SessionId {
+                    value: self.session_id.clone(),
+                }),
+                sent_at: None,
+                payload: Some(payload),
+            };
+            write_delimited(&mut self.send, &req).await?;
+            loop {
+                let resp: pb::ServerToClient = timeout(
+                    Duration::from_secs(5),
+                    read_delimited(&mut self.recv, CONTROL_STREAM_MAX_MSG),
+                )
+                .await??;
+                if resp.request_id != request_id {
+                    continue;
+                }
+                if let Some(err) = resp.error {
+                    anyhow::bail!("request failed: {err:?}");
+                }
+                return Ok(resp.payload);
+            }
+        }
+
+        async fn resume(&mut self, prev_session_id: &str) -> anyhow::Result<bool> {
+            let req = pb::ResumeSessionRequest {
+                session_id: Some(pb::SessionId {
+                    value: prev_session_id.to_string(),
+                }),
+                last_event_seq: 0,
+            };
+            match self
+                .request(pb::client_to_server::Payload::ResumeSessionRequest(req))
+                .await?
+            {
+                Some(pb::server_to_client::Payload::ResumeSessionResponse(r)) => Ok(r.resumed),
+                other => anyhow::bail!("expected ResumeSessionResponse, got {other:?}"),
+            }
+        }
+
+        /// User ids the authoritative snapshot lists as members of `channel`.
+        async fn snapshot_members(&mut self, channel: ChannelId) -> anyhow::Result<Vec<String>> {
+            let req = pb::GetInitialStateSnapshotRequest::default();
+            let Some(pb::server_to_client::Payload::InitialStateSnapshot(snapshot)) = self
+                .request(pb::client_to_server::Payload::GetInitialStateSnapshotRequest(req))
+                .await?
+            else {
+                anyhow::bail!("expected InitialStateSnapshot");
+            };
+            let channel = channel.0.to_string();
+            Ok(snapshot
+                .channel_members
+                .into_iter()
+                .filter(|scope| scope.channel_id.as_ref().map(|c| &c.value) == Some(&channel))
+                .flat_map(|scope| scope.members)
+                .filter_map(|m| m.user_id.map(|u| u.value))
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn reconnect_resumes_the_lost_session_with_its_channel() -> anyhow::Result<()> {
+        let Ok(url) = std::env::var("VP_DATABASE_URL") else {
+            return Ok(());
+        };
+        let pool = PgPool::connect(&url).await?;
+        sqlx::migrate!("../control/migrations").run(&pool).await?;
+
+        let ctx = RequestContext {
+            server_id: ServerId(uuid::Uuid::new_v4()),
+            user_id: UserId(uuid::Uuid::new_v4()),
+            is_admin: true,
+        };
+        let identity = AuthedIdentity {
+            user_id: ctx.user_id.0.to_string(),
+            server_id: ctx.server_id.0.to_string(),
+            display_name: "resumer".into(),
+            is_admin: true,
+        };
+        let (gw, addr, client_config) = spawn_test_gateway(pool.clone(), identity).await?;
+        let channel = gw
+            .control
+            .create_channel(
+                &ctx,
+                ChannelCreate {
+                    name: format!("resume-{}", uuid::Uuid::new_v4()),
+                    parent_id: None,
+                    max_members: None,
+                    max_talkers: None,
+                    channel_type: 0,
+                    description: String::new(),
+                    bitrate_bps: 64_000,
+                    opus_profile: 1,
+                    ephemeral: false,
+                },
+            )
+            .await?;
+
+        // The first connection lives on its own runtime; shutting that down
+        // stops it dead without a close frame, like pulling the network cable.
+        let lost_rt = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()?;
+        let config = client_config.clone();
+        let lost = lost_rt
+            .spawn(async move {
+                let mut client = TestClient::connect(addr, config).await?;
+                let join = pb::JoinChannelRequest {
+                    channel_id: Some(pb::ChannelId {
+                        value: channel.id.0.to_string(),
+                    }),
+                };
+                client
+                    .request(pb::client_to_server::Payload::JoinChannelRequest(join))
+                    .await?;
+                anyhow::Ok(client)
+            })
+            .await??;
+        let lost_session_id = lost.session_id.clone();
+        lost_rt.shutdown_background();
+        drop(lost);
+
+        timeout(Duration::from_secs(5), async {
+            while gw.sessions.has_user_sessions(ctx.user_id) {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await?;
+        // Parked, not left: the channel still counts the user.
+        assert_eq!(gw.membership.channel_of(ctx.user_id), Some(channel.id));
+
+        let mut resumed = TestClient::connect(addr, client_config.clone()).await?;
+        assert!(resumed.resume(&lost_session_id).await?);
+        assert_eq!(gw.membership.channel_of(ctx.user_id), Some(channel.id));
+        assert_eq!(
+            resumed.snapshot_members(channel.id).await?,
+            vec![ctx.user_id.0.to_string()]
+        );
+        // The parked session is spent; nobody else can claim it.
+        let mut late = TestClient::connect(addr, client_config.clone()).await?;
+        assert!(!late.resume(&lost_session_id).await?);
+
+        // A reconnect while the old connection still looks alive takes it
+        // over, closing the old one instead of waiting for it to time out.
+        let mut takeover = TestClient::connect(addr, client_config).await?;
+        assert!(takeover.resume(&resumed.session_id).await?);
+        let closed = timeout(Duration::from_secs(5), resumed.conn.closed()).await?;
+        let quinn::ConnectionError::ApplicationClosed(close) = closed else {
+            panic!("expected an application close, got {closed:?}");
+        };
+        assert_eq!(
+            close.error_code,
+            quinn::VarInt::from_u32(CLOSE_CODE_SESSION_RESUMED)
+        );
+        assert_eq!(
+            takeover.snapshot_members(channel.id).await?,
+            vec![ctx.user_id.0.to_string()]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn resume_refuses_expired_foreign_and_unknown_sessions() -> anyhow::Result<()> {
+        let Ok(url) = std::env::var("VP_DATABASE_URL") else {
+            return Ok(());
+        };
+        let pool = PgPool::connect(&url).await?;
+        sqlx::migrate!("../control/migrations").run(&pool).await?;
+
+        let owner = UserId(uuid::Uuid::new_v4());
+        let stranger = UserId(uuid::Uuid::new_v4());
+        let identity = AuthedIdentity {
+            user_id: owner.0.to_string(),
+            server_id: uuid::Uuid::new_v4().to_string(),
+            display_name: "resumer".into(),
+            is_admin: false,
+        };
+        let (gw, addr, client_config) = spawn_test_gateway(pool, identity).await?;
+
+        // Past its TTL a parked session can't be picked up any more.
+        gw.parked.park(
+            "expired",
+            owner,
+            Vec::new(),
+            Instant::now() - ParkedSessions::TTL,
+        );
+        assert!(!gw.do_resume(owner, "expired", "next"));
+
+        // Only the user who lost a session can resume it, and it gets back
+        // the chat subscriptions the session held.
+        let chat = ChannelId(uuid::Uuid::new_v4());
+        gw.parked.park("parked", owner, vec![chat], Instant::now());
+        assert!(!gw.do_resume(stranger, "parked", "next"));
+        assert!(gw.do_resume(owner, "parked", "next"));
+        assert_eq!(
+            gw.membership.drop_session_chat_subscriptions(owner, "next"),
+            vec![chat]
+        );
+
+        // Nor can a stranger take over a live connection, and the refused
+        // takeover leaves no supersede mark that would spare the owner's
+        // membership once that connection really ends.
+        let live = TestClient::connect(addr, client_config).await?;
+        assert!(!gw.do_resume(stranger, &live.session_id, "next"));
+        assert!(live.conn.close_reason().is_none());
+        assert!(!gw.parked.take_superseded(&live.session_id));
+
+        // A connection can't resume itself, and unknown ids resume nothing.
+        assert!(!gw.do_resume(owner, &live.session_id, &live.session_id));
+        assert!(!gw.do_resume(owner, "unknown", "next"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn close_user_sessions_closes_every_connection_of_that_user() -> anyhow::Result<()> {
+        let Ok(url) = std::env::var("VP_DATABASE_URL") else {
+            return Ok(());
+        };
+        let pool = PgPool::connect(&url).await?;
+        sqlx::migrate!("../control/migrations").run(&pool).await?;
+
+        let user = UserId(uuid::Uuid::new_v4());
+        let identity = AuthedIdentity {
+            user_id: user.0.to_string(),
+            server_id: uuid::Uuid::new_v4().to_string(),
+            display_name: "two-devices".into(),
+            is_admin: false,
+        };
+        let (gw, addr, client_config) = spawn_test_gateway(pool, identity).await?;
+        let desktop = TestClient::connect(addr, client_config.clone()).await?;
+        let laptop = TestClient::connect(addr, client_config).await?;
+
+        let stranger = UserId(uuid::Uuid::new_v4());
+        assert_eq!(
+            gw.sessions
+                .close_user_sessions(stranger, CLOSE_CODE_ADMIN_DISCONNECT, b"bye"),
+            0
+        );
+        assert!(desktop.conn.close_reason().is_none());
+
+        assert_eq!(
+            gw.sessions
+                .close_user_sessions(user, CLOSE_CODE_ADMIN_DISCONNECT, b"bye"),
+            2
+        );
+        for client in [&desktop, &laptop] {
+            let closed = timeout(Duration::from_secs(5), client.conn.closed()).await?;
+            let quinn::ConnectionError::ApplicationClosed(close) = closed else {
+                panic!("expected an application close, got {closed:?}");
+            };
+            assert_eq!(
+                close.error_code,
+                quinn::VarInt::from_u32(CLOSE_CODE_ADMIN_DISCONNECT)
+            );
+        }
+        // Each connection's task unregisters itself once it sees the close.
+        timeout(Duration::from_secs(5), async {
+            while gw.sessions.has_user_sessions(user) {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await?;
+        Ok(())
+    }
 }
@@ -0,0 +1,399 @@
+use anyhow::{anyhow, Context, Result};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use ring::signature::{self, RsaPublicKeyComponents, UnparsedPublicKey};
+use serde::Deserialize;
+
+/// Allowance for clock skew between the gateway and the token issuer when
+/// checking `exp`/`nbf`.
+const CLOCK_LEEWAY_SECS: i64 = 60;
+
+/// Verifies compact-serialized JWTs (OIDC id tokens) against a JWKS and the
+/// issuer/audience this gateway trusts.
+///
+/// Only asymmetric algorithms are accepted (RS256, ES256, EdDSA); `none` and
+/// the HMAC family are rejected outright since a public JWKS can't back them.
+#[derive(Debug, Clone)]
+pub struct JwtVerifier {
+    keys: Vec<VerifyKey>,
+    issuer: String,
+    audience: String,
+}
+
+#[derive(Debug, Clone)]
+struct VerifyKey {
+    kid: Option<String>,
+    key: KeyMaterial,
+}
+
+#[derive(Debug, Clone)]
+enum KeyMaterial {
+    Rsa { n: Vec<u8>, e: Vec<u8> },
+    EcP256 { point: Vec<u8> },
+    Ed25519 { x: Vec<u8> },
+}
+
+#[derive(Deserialize)]
+struct Jwks {
+    keys: Vec<Jwk>,
+}
+
+#[derive(Deserialize)]
+struct Jwk {
+    kty: String,
+    kid: Option<String>,
+    #[serde(rename = "use")]
+    key_use: Option<String>,
+    crv: Option<String>,
+    n: Option<String>,
+    e: Option<String>,
+    x: Option<String>,
+    y: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Header {
+    alg: String,
+    kid: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Audience {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// The claims the gateway maps onto an identity; everything else is ignored.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Claims {
+    pub iss: String,
+    pub sub: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub preferred_username: Option<String>,
+    /// Server this token is scoped to, when the issuer serves several.
+    #[serde(default)]
+    pub server_id: Option<String>,
+    #[serde(default)]
+    pub roles: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct TimedClaims {
+    #[serde(flatten)]
+    claims: Claims,
+    aud: Audience,
+    exp: i64,
+    #[serde(default)]
+    nbf: Option<i64>,
+}
+
+impl JwtVerifier {
+    /// Build a verifier from a JWKS document. Keys of unsupported types, or
+    /// marked for encryption only, are skipped.
+    pub fn from_jwks(jwks_json: &str, issuer: &str, audience: &str) -> Result<Self> {
+        let jwks: Jwks = serde_json::from_str(jwks_json).context("parse JWKS")?;
+        let keys = jwks
+            .keys
+            .into_iter()
+            .filter(|jwk| jwk.key_use.as_deref().is_none_or(|u| u == "sig"))
+            .filter_map(|jwk| VerifyKey::from_jwk(jwk).transpose())
+            .collect::<Result<Vec<_>>>()?;
+        if keys.is_empty() {
+            return Err(anyhow!("JWKS has no usable signing keys"));
+        }
+        Ok(Self {
+            keys,
+            issuer: issuer.to_string(),
+            audience: audience.to_string(),
+        })
+    }
+
+    /// Check `token`'s signature and registered claims as of `now_unix`
+    /// (seconds) and return its claims.
+    pub fn verify(&self, token: &str, now_unix: i64) -> Result<Claims> {
+        let mut parts = token.split('.');
+        let (Some(header_b64), Some(payload_b64), Some(sig_b64), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(anyhow!("malformed token"));
+        };
+
+        let header: Header = decode_json(header_b64).context("token header")?;
+        let signature = URL_SAFE_NO_PAD
+            .decode(sig_b64)
+            .map_err(|_| anyhow!("malformed token signature"))?;
+        let signed = &token[..header_b64.len() + 1 + payload_b64.len()];
+
+        let mut candidates = self
+            .keys
+            .iter()
+            .filter(|key| header.kid.is_none() || key.kid.is_none() || key.kid == header.kid)
+            .peekable();
+        if candidates.peek().is_none() {
+            return Err(anyhow!("unknown token key id"));
+        }
+        if !candidates.any(|key| key.verify(&header.alg, signed.as_bytes(), &signature)) {
+            return Err(anyhow!("invalid token signature"));
+        }
+
+        let timed: TimedClaims = decode_json(payload_b64).context("token claims")?;
+        if timed.exp + CLOCK_LEEWAY_SECS <= now_unix {
+            return Err(anyhow!("token expired"));
+        }
+        if timed
+            .nbf
+            .is_some_and(|nbf| nbf - CLOCK_LEEWAY_SECS > now_unix)
+        {
+            return Err(anyhow!("token not yet valid"));
+        }
+        if timed.claims.iss != self.issuer {
+            return Err(anyhow!("token issuer not trusted"));
+        }
+        let audience_ok = match &timed.aud {
+            Audience::One(aud) => *aud == self.audience,
+            Audience::Many(auds) => auds.contains(&self.audience),
+        };
+        if !audience_ok {
+            return Err(anyhow!("token audience mismatch"));
+        }
+        if timed.claims.sub.trim().is_empty() {
+            return Err(anyhow!("token has no subject"));
+        }
+        Ok(timed.claims)
+    }
+}
+
+impl VerifyKey {
+    fn from_jwk(jwk: Jwk) -> Result<Option<Self>> {
+        let field = |name: &str, value: Option<String>| -> Result<Vec<u8>> {
+            let value = value.ok_or_else(|| anyhow!("JWK missing `{name}`"))?;
+            URL_SAFE_NO_PAD
+                .decode(value)
+                .map_err(|_| anyhow!("JWK `{name}` is not base64url"))
+        };
+        let key = match (jwk.kty.as_str(), jwk.crv.as_deref()) {
+            ("RSA", _) => KeyMaterial::Rsa {
+                n: field("n", jwk.n)?,
+                e: field("e", jwk.e)?,
+            },
+            ("EC", Some("P-256")) => {
+                let mut point = vec![0x04];
+                point.extend(field("x", jwk.x)?);
+                point.extend(field("y", jwk.y)?);
+                KeyMaterial::EcP256 { point }
+            }
+            ("OKP", Some("Ed25519")) => KeyMaterial::Ed25519 {
+                x: field("x", jwk.x)?,
+            },
+            _ => return Ok(None),
+        };
+        Ok(Some(Self { kid: jwk.kid, key }))
+    }
+
+    fn verify(&self, alg: &str, message: &[u8], sig: &[u8]) -> bool {
+        match (alg, &self.key) {
+            ("RS256", KeyMaterial::Rsa { n, e }) => RsaPublicKeyComponents { n, e }
+                .verify(&signature::RSA_PKCS1_2048_8192_SHA256, message, sig)
+                .is_ok(),
+            ("ES256", KeyMaterial::EcP256 { point }) => {
+                UnparsedPublicKey::new(&signature::ECDSA_P256_SHA256_FIXED, point)
+                    .verify(message, sig)
+                    .is_ok()
+            }
+            ("EdDSA", KeyMaterial::Ed25519 { x }) => UnparsedPublicKey::new(&signature::ED25519, x)
+                .verify(message, sig)
+                .is_ok(),
+            _ => false,
+        }
+    }
+}
+
+fn decode_json<T: serde::de::DeserializeOwned>(segment: &str) -> Result<T> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(segment)
+        .map_err(|_| anyhow!("not base64url"))?;
+    Ok(serde_json::from_slice(&bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::rand::SystemRandom;
+    use ring::signature::{EcdsaKeyPair, Ed25519KeyPair, KeyPair};
+    use serde_json::json;
+
+    const NOW: i64 = 1_800_000_000;
+
+    fn b64(bytes: &[u8]) -> String {
+        URL_SAFE_NO_PAD.encode(bytes)
+    }
+
+    fn ed25519_key() -> Ed25519KeyPair {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap()
+    }
+
+    fn ed25519_verifier(key: &Ed25519KeyPair) -> JwtVerifier {
+        let jwks = json!({
+            "keys": [{
+                "kty": "OKP",
+                "crv": "Ed25519",
+                "kid": "k1",
+                "x": b64(key.public_key().as_ref()),
+            }]
+        });
+        JwtVerifier::from_jwks(&jwks.to_string(), "https://idp.example", "tsod").unwrap()
+    }
+
+    fn sign_eddsa(key: &Ed25519KeyPair, kid: &str, claims: serde_json::Value) -> String {
+        let header = json!({ "alg": "EdDSA", "typ": "JWT", "kid": kid });
+        let signed = format!(
+            "{}.{}",
+            b64(header.to_string().as_bytes()),
+            b64(claims.to_string().as_bytes())
+        );
+        let sig = key.sign(signed.as_bytes());
+        format!("{signed}.{}", b64(sig.as_ref()))
+    }
+
+    fn claims(exp: i64) -> serde_json::Value {
+        json!({
+            "iss": "https://idp.example",
+            "aud": ["other", "tsod"],
+            "sub": "alice",
+            "name": "Alice",
+            "exp": exp,
+        })
+    }
+
+    #[test]
+    fn accepts_a_valid_eddsa_token() {
+        let key = ed25519_key();
+        let verifier = ed25519_verifier(&key);
+
+        let token = sign_eddsa(&key, "k1", claims(NOW + 300));
+        let claims = verifier.verify(&token, NOW).unwrap();
+        assert_eq!(claims.sub, "alice");
+        assert_eq!(claims.name.as_deref(), Some("Alice"));
+    }
+
+    #[test]
+    fn accepts_a_valid_es256_token() {
+        let rng = SystemRandom::new();
+        let alg = &signature::ECDSA_P256_SHA256_FIXED_SIGNING;
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(alg, &rng).unwrap();
+        let key = EcdsaKeyPair::from_pkcs8(alg, pkcs8.as_ref(), &rng).unwrap();
+        let point = key.public_key().as_ref();
+        let jwks = json!({
+            "keys": [{
+                "kty": "EC",
+                "crv": "P-256",
+                "x": b64(&point[1..33]),
+                "y": b64(&point[33..]),
+            }]
+        });
+        let verifier =
+            JwtVerifier::from_jwks(&jwks.to_string(), "https://idp.example", "tsod").unwrap();
+
+        let header = json!({ "alg": "ES256" });
+        let signed = format!(
+            "{}.{}",
+            b64(header.to_string().as_bytes()),
+            b64(claims(NOW + 300).to_string().as_bytes())
+        );
+        let sig = key.sign(&rng, signed.as_bytes()).unwrap();
+        let token = format!("{signed}.{}", b64(sig.as_ref()));
+        assert_eq!(verifier.verify(&token, NOW).unwrap().sub, "alice");
+    }
+
+    #[test]
+    fn rejects_expired_and_not_yet_valid_tokens() {
+        let key = ed25519_key();
+        let verifier = ed25519_verifier(&key);
+
+        let expired = sign_eddsa(&key, "k1", claims(NOW - CLOCK_LEEWAY_SECS - 1));
+        let err = verifier.verify(&expired, NOW).unwrap_err();
+        assert!(err.to_string().contains("expired"), "{err:#}");
+
+        // Within the skew allowance still passes.
+        let skewed = sign_eddsa(&key, "k1", claims(NOW - 10));
+        assert!(verifier.verify(&skewed, NOW).is_ok());
+
+        let mut early = claims(NOW + 600);
+        early["nbf"] = json!(NOW + 300);
+        let err = verifier
+            .verify(&sign_eddsa(&key, "k1", early), NOW)
+            .unwrap_err();
+        assert!(err.to_string().contains("not yet valid"), "{err:#}");
+    }
+
+    #[test]
+    fn rejects_tampered_or_foreign_signatures() {
+        let key = ed25519_key();
+        let verifier = ed25519_verifier(&key);
+
+        let token = sign_eddsa(&key, "k1", claims(NOW + 300));
+        let (signed, sig) = token.rsplit_once('.').unwrap();
+        let (header, _) = signed.split_once('.').unwrap();
+        let mut forged = claims(NOW + 300);
+        forged["sub"] = json!("mallory");
+        let tampered = format!("{header}.{}.{sig}", b64(forged.to_string().as_bytes()));
+        let err = verifier.verify(&tampered, NOW).unwrap_err();
+        assert!(
+            err.to_string().contains("invalid token signature"),
+            "{err:#}"
+        );
+
+        let stranger = sign_eddsa(&ed25519_key(), "k1", claims(NOW + 300));
+        assert!(verifier.verify(&stranger, NOW).is_err());
+
+        let unknown_kid = sign_eddsa(&key, "k2", claims(NOW + 300));
+        let err = verifier.verify(&unknown_kid, NOW).unwrap_err();
+        assert!(err.to_string().contains("unknown token key id"), "{err:#}");
+
+        assert!(verifier.verify("not-a-jwt", NOW).is_err());
+    }
+
+    #[test]
+    fn rejects_unsigned_and_hmac_tokens() {
+        let key = ed25519_key();
+        let verifier = ed25519_verifier(&key);
+        let payload = b64(claims(NOW + 300).to_string().as_bytes());
+
+        for alg in ["none", "HS256"] {
+            let header = b64(json!({ "alg": alg, "kid": "k1" }).to_string().as_bytes());
+            let token = format!("{header}.{payload}.{}", b64(b"sig"));
+            assert!(verifier.verify(&token, NOW).is_err(), "{alg} accepted");
+        }
+    }
+
+    #[test]
+    fn rejects_wrong_issuer_or_audience() {
+        let key = ed25519_key();
+        let verifier = ed25519_verifier(&key);
+
+        let mut other_iss = claims(NOW + 300);
+        other_iss["iss"] = json!("https://evil.example");
+        let err = verifier
+            .verify(&sign_eddsa(&key, "k1", other_iss), NOW)
+            .unwrap_err();
+        assert!(err.to_string().contains("issuer"), "{err:#}");
+
+        let mut other_aud = claims(NOW + 300);
+        other_aud["aud"] = json!("someone-else");
+        let err = verifier
+            .verify(&sign_eddsa(&key, "k1", other_aud), NOW)
+            .unwrap_err();
+        assert!(err.to_string().contains("audience"), "{err:#}");
+    }
+
+    #[test]
+    fn jwks_without_signing_keys_is_rejected() {
+        let jwks = json!({
+            "keys": [{ "kty": "oct", "k": "c2VjcmV0" }]
+        });
+        assert!(JwtVerifier::from_jwks(&jwks.to_string(), "iss", "aud").is_err());
+    }
+}
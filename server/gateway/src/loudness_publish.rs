@@ -0,0 +1,66 @@
+use std::{sync::Arc, time::Duration};
+
+use tokio::time::MissedTickBehavior;
+use vp_control::ids::ChannelId;
+use vp_media::voice_forwarder::{TalkerLoudness, VoiceForwarder};
+
+use crate::outbox_dispatch::now_ts;
+use crate::proto::voiceplatform::v1 as pb;
+use crate::state::{MembershipCache, PushHub};
+
+/// Talkers listed per channel in each `VoiceLoudnessPush`.
+const MAX_PUBLISHED_TALKERS: usize = 8;
+
+/// Periodically push the loudest talkers of each voice channel to its members.
+///
+/// Levels come from the loudness byte clients put in voice headers; channels
+/// whose levels didn't change since the last tick are skipped, so an idle
+/// server sends nothing.
+pub async fn run_loudness_publisher(
+    voice: Arc<VoiceForwarder>,
+    membership: MembershipCache,
+    push: PushHub,
+    interval: Duration,
+) {
+    let mut tick = tokio::time::interval(interval);
+    tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    loop {
+        tick.tick().await;
+        for (channel, talkers) in voice.take_loudness_updates(MAX_PUBLISHED_TALKERS).await {
+            let Some(members) = membership.members_of(channel) else {
+                continue;
+            };
+            let msg = loudness_push(channel, &talkers);
+            for member in members {
+                push.send_to(member, msg.clone()).await;
+            }
+        }
+    }
+}
+
+fn loudness_push(channel: ChannelId, talkers: &[TalkerLoudness]) -> pb::ServerToClient {
+    pb::ServerToClient {
+        request_id: None,
+        session_id: None,
+        sent_at: Some(now_ts()),
+        error: None,
+        event_seq: 0,
+        payload: Some(pb::server_to_client::Payload::VoiceLoudnessPush(
+            pb::VoiceLoudnessPush {
+                channel_id: Some(pb::ChannelId {
+                    value: channel.0.to_string(),
+                }),
+                talkers: talkers
+                    .iter()
+                    .map(|t| pb::TalkerLoudness {
+                        user_id: Some(pb::UserId {
+                            value: t.user.0.to_string(),
+                        }),
+                        loudness: u32::from(t.loudness),
+                    })
+                    .collect(),
+            },
+        )),
+    }
+}
@@ -1,15 +1,22 @@
 mod auth;
 mod bootstrap;
 mod config;
+mod conn_guard;
+mod db_health;
 mod egress;
 mod frame;
 mod gateway;
+mod jwt;
+mod loudness_publish;
 mod media;
+mod membership_sweep;
 mod metrics_adapter;
 mod orphan_cleaner;
 mod outbox_dispatch;
 mod overwrite_queue;
+mod preemption_publish;
 mod prune;
+mod readiness;
 mod screenshare;
 mod screenshare_policy;
 mod state;
@@ -17,7 +24,7 @@ mod tls;
 
 pub mod proto;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use bootstrap::{ensure_core_state, BootstrapConfig};
 use clap::Parser;
 use config::Config;
@@ -29,11 +36,12 @@ use std::{net::SocketAddr, sync::Arc};
 use tokio::time::{Duration, MissedTickBehavior};
 use tracing::{info, Level};
 use tracing_subscriber::EnvFilter;
-use vp_metrics::{MetricsConfig, MetricsServer};
+use vp_metrics::{MetricsAuth, MetricsConfig, MetricsServer, StaticLabels};
 
-use crate::auth::DeviceAuthProvider;
-use crate::metrics_adapter::{stream_metrics, voice_metrics};
+use crate::auth::{AuthRouter, DeviceAuthProvider, JwtAuthProvider};
+use crate::metrics_adapter::{outbox_metrics, stream_metrics, voice_metrics};
 use crate::outbox_dispatch::{run_outbox_dispatcher, OutboxDispatcherConfig};
+use crate::readiness::Readiness;
 use crate::state::{MembershipCache, PushHub, Sessions, VoiceTelemetryCache};
 
 const QUIC_DATAGRAM_SEND_BUFFER_SIZE: usize = 128 * 1024; // keep explicit latency budget; avoid turning send buffer into hidden queue latency
@@ -52,10 +60,27 @@ async fn main() -> Result<()> {
     let addr: SocketAddr = cfg.listen.parse()?;
 
     // Metrics
+    let metrics_auth = match (&cfg.metrics_bearer_token, &cfg.metrics_basic_auth) {
+        (Some(token), _) => MetricsAuth::Bearer(token.clone()),
+        (None, Some(creds)) => {
+            let (username, password) = creds
+                .split_once(':')
+                .ok_or_else(|| anyhow::anyhow!("--metrics-basic-auth must be username:password"))?;
+            MetricsAuth::Basic {
+                username: username.to_string(),
+                password: password.to_string(),
+            }
+        }
+        (None, None) => MetricsAuth::None,
+    };
+    let readiness = Readiness::new();
     let ms = MetricsServer::install(MetricsConfig {
         listen: cfg.metrics_listen.clone(),
-        namespace: "vp",
-    })?;
+        namespace: cfg.metrics_namespace.clone(),
+        static_labels: StaticLabels::parse(&cfg.metrics_labels)?,
+        auth: metrics_auth,
+    })?
+    .with_readiness(readiness.check());
     tokio::spawn(async move {
         let _ = ms.serve().await;
     });
@@ -79,24 +104,58 @@ async fn main() -> Result<()> {
     );
 
     let repo = vp_control::PgControlRepo::new(pool.clone());
-    let control = Arc::new(vp_control::ControlService::new(repo.clone()));
+    let control_config = vp_control::ControlConfig {
+        max_channels_per_server: (cfg.max_channels_per_server > 0)
+            .then_some(cfg.max_channels_per_server),
+        max_channels_per_user: (cfg.max_channels_per_user > 0).then_some(cfg.max_channels_per_user),
+        unique_channel_names: cfg.unique_channel_names,
+        read_receipts: cfg.read_receipts,
+        ..Default::default()
+    };
+    let control =
+        Arc::new(vp_control::ControlService::new(repo.clone()).with_config(control_config));
 
     // Shared runtime state
     let push = PushHub::new();
     let sessions = Sessions::new();
-    let membership = MembershipCache::new();
+    let membership = MembershipCache::new().with_voice_requires_join(cfg.voice_requires_join);
     let telemetry = VoiceTelemetryCache::new();
 
     let (prune_wake_tx, prune_wake_rx) = tokio::sync::mpsc::channel(1);
 
     // Voice forwarder
-    let forwarder = Arc::new(vp_media::voice_forwarder::VoiceForwarder::new(
-        vp_media::voice_forwarder::VoiceForwarderConfig::default(),
+    let (preemption_tx, preemption_rx) = tokio::sync::mpsc::channel(64);
+    let mut forwarder = vp_media::voice_forwarder::VoiceForwarder::new(
+        vp_media::voice_forwarder::VoiceForwarderConfig {
+            mode: cfg.voice_forward_mode.forward_mode(),
+            ..Default::default()
+        },
         Arc::new(sessions.clone()),
         Arc::new(membership.clone()),
         voice_metrics(),
         prune_wake_tx.clone(),
-    ));
+    )
+    .with_preemption_events(preemption_tx);
+    if let Some(dir) = cfg.voice_archive_dir.as_deref() {
+        let sink = vp_media::voice_archive::OggOpusFileSink::new(dir)?;
+        info!("recording voice to {dir}");
+        forwarder = forwarder.with_voice_sink(Arc::new(sink));
+    }
+    let forwarder = Arc::new(forwarder);
+
+    if cfg.voice_forward_mode == config::VoiceForwardMode::Mix {
+        info!("voice forward mode: mix");
+        let forwarder = forwarder.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_millis(u64::from(
+                vp_media::voice_mixer::MIX_FRAME_MS,
+            )));
+            loop {
+                interval.tick().await;
+                forwarder.mix_tick().await;
+            }
+        });
+    }
 
     // Video/screenshare stream forwarder (SFU)
     let stream_forwarder = Arc::new(vp_media::stream_forwarder::StreamForwarder::new(
@@ -134,17 +193,48 @@ async fn main() -> Result<()> {
         });
     }
 
-    // Outbox dispatcher (push fanout)
     let server_id = vp_control::ids::ServerId(uuid::Uuid::parse_str(&cfg.default_server_id)?);
+
+    // Warm per-user channel notification prefs so chat fanout honors them from boot.
+    match control.list_channel_notification_prefs(server_id).await {
+        Ok(prefs) => {
+            for p in prefs {
+                membership.set_notification_pref(p.user_id, p.channel_id, p.pref);
+            }
+        }
+        Err(e) => tracing::warn!("failed to load channel notification prefs: {:#}", e),
+    }
+
+    // Bans are only cached as they are issued; pick up the ones already in force.
+    match control.list_active_bans(server_id).await {
+        Ok(bans) => {
+            for ban in bans {
+                match ban.expires_at {
+                    None => membership.set_banned(ban.user_id, true),
+                    Some(at) => {
+                        if let Ok(left) = (at - chrono::Utc::now()).to_std() {
+                            membership.ban_for(ban.user_id, left);
+                        }
+                    }
+                }
+            }
+        }
+        Err(e) => tracing::warn!("failed to load active bans: {:#}", e),
+    }
+
+    // Outbox dispatcher (push fanout)
     tokio::spawn(run_outbox_dispatcher(
         repo.clone(),
         push.clone(),
         membership.clone(),
+        outbox_metrics(),
+        readiness.clone(),
         OutboxDispatcherConfig {
             server_id,
             poll_interval: std::time::Duration::from_millis(cfg.outbox_poll_ms),
             batch_size: cfg.outbox_batch,
             claim_ttl_seconds: cfg.outbox_claim_ttl_s,
+            max_attempts: cfg.outbox_max_attempts,
         },
     ));
 
@@ -164,6 +254,71 @@ async fn main() -> Result<()> {
         });
     }
 
+    // Idle ephemeral channel sweeper
+    if cfg.ephemeral_channel_idle_secs > 0 {
+        let control_for_sweep = Arc::clone(&control);
+        let sweep_server_id = server_id;
+        let idle_for = chrono::Duration::seconds(cfg.ephemeral_channel_idle_secs as i64);
+        let period = Duration::from_secs(cfg.ephemeral_channel_idle_secs.clamp(5, 60));
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+            loop {
+                interval.tick().await;
+                match control_for_sweep
+                    .sweep_idle_ephemeral_channels(sweep_server_id, idle_for)
+                    .await
+                {
+                    Ok(deleted) if !deleted.is_empty() => {
+                        tracing::info!(count = deleted.len(), "deleted idle ephemeral channels");
+                    }
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!("ephemeral channel sweep error: {:#}", e),
+                }
+            }
+        });
+    }
+
+    // Postgres health gauge
+    if cfg.db_health_interval_secs > 0 {
+        tokio::spawn(db_health::run_db_health_monitor(
+            pool.clone(),
+            Duration::from_secs(cfg.db_health_interval_secs),
+            readiness.clone(),
+        ));
+    } else {
+        // Nothing probes the pool; readiness then rests on the outbox
+        // dispatcher, whose claims stall while the database is down.
+        readiness.set_db_up(true);
+    }
+
+    // Idle channel eviction for the membership cache
+    if cfg.membership_cache_idle_secs > 0 {
+        tokio::spawn(membership_sweep::run_membership_sweeper(
+            membership.clone(),
+            Duration::from_secs(cfg.membership_cache_idle_secs.clamp(5, 60)),
+            Duration::from_secs(cfg.membership_cache_idle_secs),
+            cfg.membership_cache_max_channels,
+        ));
+    }
+
+    // Loudest-talker pushes for voice channels
+    if cfg.voice_loudness_interval_ms > 0 {
+        tokio::spawn(loudness_publish::run_loudness_publisher(
+            forwarder.clone(),
+            membership.clone(),
+            push.clone(),
+            Duration::from_millis(cfg.voice_loudness_interval_ms),
+        ));
+    }
+
+    // Talker slot hand-off notices
+    tokio::spawn(preemption_publish::run_preemption_publisher(
+        preemption_rx,
+        membership.clone(),
+        push.clone(),
+    ));
+
     // Orphan upload file cleaner
     if cfg.orphan_scan_interval_secs > 0 {
         let orphan_pool = pool.clone();
@@ -183,9 +338,22 @@ async fn main() -> Result<()> {
         &cfg.tls_self_signed_sans,
     )?;
 
+    let cert_resolver = Arc::new(tls::ReloadableCertResolver::new(tls::certified_key(
+        certs, key,
+    )?));
+    if let (Some(cert_path), Some(key_path)) = (&cfg.tls_cert_pem, &cfg.tls_key_pem) {
+        if cfg.tls_reload_interval_secs > 0 {
+            tokio::spawn(tls::run_cert_reloader(
+                cert_resolver.clone(),
+                tls::CertFileWatch::new(cert_path.clone(), key_path.clone()),
+                Duration::from_secs(cfg.tls_reload_interval_secs),
+            ));
+        }
+    }
+
     let mut rustls = RustlsServerConfig::builder()
         .with_no_client_auth()
-        .with_single_cert(certs, key)?;
+        .with_cert_resolver(cert_resolver);
     rustls.alpn_protocols = vec![cfg.alpn.as_bytes().to_vec()];
     info!(
         expected_alpn = %cfg.alpn,
@@ -211,6 +379,11 @@ async fn main() -> Result<()> {
     transport.datagram_receive_buffer_size(Some(dg_recv_buf));
     transport.datagram_send_buffer_size(QUIC_DATAGRAM_SEND_BUFFER_SIZE);
     transport.keep_alive_interval(Some(std::time::Duration::from_secs(10)));
+    transport.congestion_controller_factory(cfg.quic_congestion_controller.factory());
+    info!(
+        congestion_controller = ?cfg.quic_congestion_controller,
+        "configured QUIC congestion controller"
+    );
     info!(
         quic_datagram_recv_buffer_bytes = dg_recv_buf,
         app_media_mtu = vp_voice::APP_MEDIA_MTU,
@@ -240,13 +413,39 @@ async fn main() -> Result<()> {
     )
     .await?;
 
-    let auth_provider: Arc<dyn auth::AuthProvider> = Arc::new(DeviceAuthProvider::new(
+    let device_auth = DeviceAuthProvider::new(
         pool.clone(),
         server_id.0,
         bootstrap_owner_user_id,
         cfg.owner_bootstrap_policy,
         cfg.dev_repair_orphan_user_roles,
-    ));
+    );
+    let jwt_auth = match cfg.jwt_jwks_path.as_deref() {
+        Some(path) => {
+            let jwks =
+                std::fs::read_to_string(path).with_context(|| format!("read JWKS from {path}"))?;
+            let verifier = jwt::JwtVerifier::from_jwks(
+                &jwks,
+                cfg.jwt_issuer.as_deref().unwrap_or_default(),
+                cfg.jwt_audience.as_deref().unwrap_or_default(),
+            )?;
+            info!(jwks = %path, "OIDC token sign-in enabled");
+            Some(JwtAuthProvider::new(
+                pool.clone(),
+                server_id.0,
+                BootstrapConfig {
+                    bootstrap_owner_user_id,
+                    owner_bootstrap_policy: cfg.owner_bootstrap_policy,
+                    dev_repair_orphan_user_roles: cfg.dev_repair_orphan_user_roles,
+                },
+                verifier,
+                cfg.jwt_admin_role.clone(),
+            ))
+        }
+        None => None,
+    };
+    let auth_provider: Arc<dyn auth::AuthProvider> =
+        Arc::new(AuthRouter::new(device_auth, jwt_auth));
 
     let gw = Gateway::new(
         auth_provider,
@@ -260,14 +459,43 @@ async fn main() -> Result<()> {
         stream_forwarder,
         media,
         cfg.max_connections,
-    );
+        conn_guard::ConnLimits {
+            max_streams_per_sec: cfg.conn_max_streams_per_sec,
+            max_datagrams_per_sec: cfg.conn_max_datagrams_per_sec,
+            max_datagram_bytes_per_sec: cfg.conn_max_datagram_bytes_per_sec,
+        },
+    )
+    .with_voice_loudness(cfg.voice_loudness_interval_ms > 0)
+    .with_voice_mixing(cfg.voice_forward_mode == config::VoiceForwardMode::Mix)
+    .with_voice_recording(cfg.voice_archive_dir.is_some())
+    .with_readiness(readiness);
+
+    gw.serve(
+        endpoint,
+        shutdown_signal(),
+        Duration::from_secs(cfg.shutdown_grace_secs),
+    )
+    .await?;
+    info!("shutdown");
 
-    tokio::select! {
-        r = gw.serve(endpoint) => r?,
-        _ = tokio::signal::ctrl_c() => {
-            info!("shutdown");
+    Ok(())
+}
+
+/// Ctrl-C, or SIGTERM (what orchestrators send) on Unix.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut term) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = term.recv() => {}
+                }
+                return;
+            }
+            Err(e) => tracing::warn!("SIGTERM handler unavailable: {e}"),
         }
     }
-
-    Ok(())
+    let _ = tokio::signal::ctrl_c().await;
 }
@@ -0,0 +1,36 @@
+use std::time::Duration;
+
+use metrics::{counter, gauge};
+use tokio::time::{Instant, MissedTickBehavior};
+use tracing::debug;
+use vp_metrics::metric_name;
+
+use crate::state::MembershipCache;
+
+/// Periodically evict membership cache entries for channels nobody is in.
+///
+/// Active channels keep their cached roster for the voice fast path; empty
+/// ones are rebuilt from the control plane on the next join, so dropping them
+/// only costs a DB round trip later. Publishes `vp_gateway_membership_cache_channels`
+/// and `vp_gateway_membership_cache_evictions_total`.
+pub async fn run_membership_sweeper(
+    membership: MembershipCache,
+    interval: Duration,
+    idle: Duration,
+    max_channels: usize,
+) {
+    let mut tick = tokio::time::interval(interval);
+    tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    loop {
+        tick.tick().await;
+        let evicted = membership.evict_idle_channels(max_channels, idle, Instant::now());
+        if evicted > 0 {
+            counter!(metric_name("gateway_membership_cache_evictions_total"))
+                .increment(evicted as u64);
+            debug!(evicted, "evicted idle channels from membership cache");
+        }
+        gauge!(metric_name("gateway_membership_cache_channels"))
+            .set(membership.cached_channel_count() as f64);
+    }
+}
@@ -5,11 +5,16 @@ use vp_media::{
     stream_forwarder::{StreamDropReason, StreamMetrics},
     voice_forwarder::VoiceMetrics,
 };
-use vp_metrics::{labels::LabelPolicy, stream::StreamMetricsImpl, voice::VoiceMetricsImpl};
+use vp_metrics::{
+    control::OutboxMetricsImpl, labels::LabelPolicy, stream::StreamMetricsImpl,
+    voice::VoiceMetricsImpl,
+};
+
+use crate::outbox_dispatch::OutboxMetrics;
 
 pub fn voice_metrics() -> Arc<dyn VoiceMetrics> {
     Arc::new(GatewayVoiceMetrics {
-        inner: VoiceMetricsImpl::new("vp", LabelPolicy::default()),
+        inner: VoiceMetricsImpl::new(vp_metrics::namespace(), LabelPolicy::default()),
     })
 }
 
@@ -30,12 +35,24 @@ impl VoiceMetrics for GatewayVoiceMetrics {
     fn inc_drop_rate_limited(&self) {
         self.inner.drop_reason("rate_limited");
     }
+    fn inc_drop_replay(&self) {
+        self.inner.drop_reason("replay");
+    }
     fn inc_drop_not_member(&self) {
         self.inner.drop_reason("not_member");
     }
+    fn inc_drop_not_in_voice(&self) {
+        self.inner.drop_reason("not_in_voice");
+    }
+    fn inc_drop_probe_penalty(&self) {
+        self.inner.drop_reason("probe_penalty");
+    }
     fn inc_drop_muted(&self) {
         self.inner.drop_reason("muted");
     }
+    fn inc_drop_banned(&self) {
+        self.inner.drop_reason("banned");
+    }
     fn inc_drop_talker_limit(&self) {
         self.inner.drop_reason("talker_limit");
     }
@@ -57,6 +74,9 @@ impl VoiceMetrics for GatewayVoiceMetrics {
     fn observe_handle_incoming_us(&self, micros: u64) {
         self.inner.handle_incoming_us(micros);
     }
+    fn observe_loudness(&self, loudness: u8) {
+        self.inner.loudness(loudness);
+    }
 }
 
 impl DatagramSendPolicyMetrics for GatewayVoiceMetrics {
@@ -81,7 +101,7 @@ impl DatagramSendPolicyMetrics for GatewayVoiceMetrics {
 }
 pub fn stream_metrics() -> Arc<dyn StreamMetrics> {
     Arc::new(GatewayStreamMetrics {
-        inner: StreamMetricsImpl::new("vp", LabelPolicy::default()),
+        inner: StreamMetricsImpl::new(vp_metrics::namespace(), LabelPolicy::default()),
     })
 }
 
@@ -124,3 +144,56 @@ impl StreamMetrics for GatewayStreamMetrics {
         self.inner.recovery_requests();
     }
 }
+
+pub fn outbox_metrics() -> Arc<dyn OutboxMetrics> {
+    Arc::new(GatewayOutboxMetrics {
+        inner: OutboxMetricsImpl::new(vp_metrics::namespace()),
+    })
+}
+
+struct GatewayOutboxMetrics {
+    inner: OutboxMetricsImpl,
+}
+
+impl OutboxMetrics for GatewayOutboxMetrics {
+    fn observe_claim(&self, requested: usize, claimed: usize) {
+        self.inner.claimed(requested, claimed);
+    }
+    fn inc_published(&self, topic: &str, lag_seconds: f64) {
+        let topic = LabelPolicy::outbox_topic(topic);
+        self.inner.published(topic, lag_seconds);
+    }
+    fn observe_fanout(&self, recipients: usize) {
+        self.inner.fanout(recipients);
+    }
+    fn inc_dead_lettered(&self, topic: &str) {
+        self.inner.dead_lettered(LabelPolicy::outbox_topic(topic));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::voice_metrics;
+    use metrics_exporter_prometheus::PrometheusBuilder;
+
+    #[test]
+    fn forwarded_voice_reaches_the_prometheus_series() {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        metrics::with_local_recorder(&recorder, || {
+            let metrics = voice_metrics();
+            metrics.inc_forwarded(3);
+            metrics.inc_drop_muted();
+        });
+
+        let rendered = handle.render();
+        assert!(
+            rendered.contains("vp_voice_forwarded_total 1"),
+            "{rendered}"
+        );
+        assert!(
+            rendered.contains(r#"vp_voice_drops_total{reason="muted"} 1"#),
+            "{rendered}"
+        );
+    }
+}
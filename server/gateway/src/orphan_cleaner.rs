@@ -7,6 +7,7 @@ use tokio::fs;
 use tokio::time::MissedTickBehavior;
 use tracing::{debug, info, warn};
 use uuid::Uuid;
+use vp_metrics::metric_name;
 
 /// Grace period: files younger than this are skipped to avoid racing with
 /// in-flight uploads that haven't inserted their DB row yet.
@@ -96,7 +97,7 @@ async fn scan_and_remove(pool: &PgPool, upload_dir: &PathBuf) -> anyhow::Result<
                     } else {
                         debug!(path = %file_path.display(), "removed orphan upload file");
                         removed += 1;
-                        counter!("vp_orphan_uploads_removed_total").increment(1);
+                        counter!(metric_name("orphan_uploads_removed_total")).increment(1);
                     }
                 }
                 Err(e) => {
@@ -106,6 +107,6 @@ async fn scan_and_remove(pool: &PgPool, upload_dir: &PathBuf) -> anyhow::Result<
         }
     }
 
-    counter!("vp_orphan_uploads_scanned_total").increment(scanned);
+    counter!(metric_name("orphan_uploads_scanned_total")).increment(scanned);
     Ok((scanned, removed))
 }
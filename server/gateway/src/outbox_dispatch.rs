@@ -1,4 +1,6 @@
-use std::time::Duration;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, Context, Result};
 use chrono::{DateTime, Utc};
@@ -7,40 +9,90 @@ use tokio::time::sleep;
 use tracing::{debug, info, warn};
 
 use crate::proto::voiceplatform::v1 as pb;
+use crate::readiness::Readiness;
 use crate::state::{MembershipCache, PushHub};
 
-use vp_control::ids::{ChannelId, MessageId, ServerId, UserId};
+use vp_control::ids::{ChannelId, MessageId, OutboxId, ServerId, UserId};
 use vp_control::model::OutboxEventRow;
 use vp_control::{ControlRepo, PgControlRepo};
 
+const MAX_CLAIM_RETRY_DELAY: Duration = Duration::from_secs(10);
+/// Minimum spacing between `channel.state_refresh` pushes for one channel.
+const STATE_REFRESH_MIN_INTERVAL: Duration = Duration::from_secs(3);
+
+/// A topic this dispatcher has no translation for. Retrying can't help, so
+/// such records are dead-lettered on the first failure.
+#[derive(Debug)]
+struct UnsupportedTopic(String);
+
+impl std::fmt::Display for UnsupportedTopic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unsupported outbox event type: {}", self.0)
+    }
+}
+
+impl std::error::Error for UnsupportedTopic {}
+
+/// Dispatcher instrumentation; implemented over `vp_metrics` in
+/// `metrics_adapter`.
+pub trait OutboxMetrics: Send + Sync {
+    /// A non-empty claim of `claimed` rows out of `requested`.
+    fn observe_claim(&self, requested: usize, claimed: usize);
+    /// A record delivered and acked, `lag_seconds` after it was written.
+    fn inc_published(&self, topic: &str, lag_seconds: f64);
+    /// Recipients a push was sent to.
+    fn observe_fanout(&self, recipients: usize);
+    fn inc_dead_lettered(&self, topic: &str);
+}
+
 pub struct OutboxDispatcherConfig {
     pub server_id: ServerId,
     pub poll_interval: Duration,
     pub batch_size: i64,
     pub claim_ttl_seconds: i64,
+    /// Failed deliveries before a record is dead-lettered.
+    pub max_attempts: i32,
 }
 
 pub async fn run_outbox_dispatcher(
     repo: PgControlRepo,
     hub: PushHub,
     membership: MembershipCache,
+    metrics: Arc<dyn OutboxMetrics>,
+    readiness: Readiness,
     cfg: OutboxDispatcherConfig,
 ) -> Result<()> {
     let token = uuid::Uuid::new_v4();
     info!(claim_token = %token, server_id = %cfg.server_id.0, ttl_s = cfg.claim_ttl_seconds, "outbox dispatcher started");
 
+    let mut retry_delay = cfg.poll_interval;
+    let mut refresh_throttle = StateRefreshThrottle::new(STATE_REFRESH_MIN_INTERVAL);
     loop {
-        let mut tx = repo.tx().await.context("outbox tx")?;
-        let batch = <PgControlRepo as ControlRepo>::claim_outbox_batch(
-            &repo,
-            &mut tx,
-            cfg.server_id,
-            token,
-            cfg.batch_size,
-        )
-        .await
-        .context("claim_outbox_batch")?;
-        tx.commit().await.context("outbox tx commit")?;
+        for (channel_id, mut push) in refresh_throttle.take_due(Instant::now()) {
+            overlay_channel_state_self_flags(&membership, channel_id, &mut push);
+            for uid in membership.members_of(channel_id).unwrap_or_default() {
+                hub.send(uid, push.clone()).await;
+            }
+        }
+
+        // A DB blip must not kill push fanout for the rest of the process;
+        // back off and keep polling until the pool recovers.
+        let batch = match claim_batch(&repo, &cfg, token).await {
+            Ok(batch) => {
+                retry_delay = cfg.poll_interval;
+                readiness.outbox_claimed();
+                batch
+            }
+            Err(e) => {
+                warn!(
+                    retry_in_ms = retry_delay.as_millis() as u64,
+                    "outbox claim failed: {:#}", e
+                );
+                sleep(retry_delay).await;
+                retry_delay = next_retry_delay(retry_delay);
+                continue;
+            }
+        };
 
         if batch.is_empty() {
             sleep(cfg.poll_interval).await;
@@ -48,71 +100,214 @@ pub async fn run_outbox_dispatcher(
         }
 
         debug!(server_id=%cfg.server_id.0, claimed=batch.len(), "claimed outbox rows");
+        metrics.observe_claim(cfg.batch_size.max(0) as usize, batch.len());
 
         for rec in batch {
-            if let Err(e) = handle_record(&repo, &hub, &membership, token, rec).await {
-                warn!("outbox record handling error: {:#}", e);
-                // do not ack; it'll be reclaimed after TTL
+            let (id, topic) = (rec.id, rec.topic.clone());
+            if let Err(e) = handle_record(
+                &repo,
+                &hub,
+                &membership,
+                &*metrics,
+                &mut refresh_throttle,
+                token,
+                rec,
+            )
+            .await
+            {
+                warn!(outbox_id = %id.0, %topic, "outbox record handling error: {:#}", e);
+                // Left claimed, so it is retried after the claim TTL until
+                // it has failed max_attempts times.
+                let max_attempts = attempts_allowed(&e, cfg.max_attempts);
+                match record_failure(&repo, token, id, &format!("{e:#}"), max_attempts).await {
+                    Ok(true) => {
+                        metrics.inc_dead_lettered(&topic);
+                        warn!(outbox_id = %id.0, %topic, "outbox record dead-lettered");
+                    }
+                    Ok(false) => {}
+                    Err(e) => warn!(outbox_id = %id.0, "recording outbox failure failed: {:#}", e),
+                }
             }
         }
     }
 }
 
+async fn claim_batch(
+    repo: &PgControlRepo,
+    cfg: &OutboxDispatcherConfig,
+    token: uuid::Uuid,
+) -> Result<Vec<OutboxEventRow>> {
+    let mut tx = repo.tx().await.context("outbox tx")?;
+    let batch = <PgControlRepo as ControlRepo>::claim_outbox_batch(
+        repo,
+        &mut tx,
+        cfg.server_id,
+        token,
+        cfg.batch_size,
+    )
+    .await
+    .context("claim_outbox_batch")?;
+    tx.commit().await.context("outbox tx commit")?;
+    Ok(batch)
+}
+
+/// Failed deliveries a record may reach before `err` dead-letters it.
+fn attempts_allowed(err: &anyhow::Error, max_attempts: i32) -> i32 {
+    if err.is::<UnsupportedTopic>() {
+        1
+    } else {
+        max_attempts
+    }
+}
+
+fn next_retry_delay(current: Duration) -> Duration {
+    (current * 2).min(MAX_CLAIM_RETRY_DELAY)
+}
+
 async fn handle_record(
     repo: &PgControlRepo,
     hub: &PushHub,
     membership: &MembershipCache,
+    metrics: &dyn OutboxMetrics,
+    refresh_throttle: &mut StateRefreshThrottle,
     token: uuid::Uuid,
     rec: OutboxEventRow,
 ) -> Result<()> {
-    let (channel_id, push) = translate_record(&rec)?;
-
-    // NOTE: For poke.received we resolve a single UserId. This is correct
-    // because PushHub::send fans out to *all* sessions for that user (see
-    // state.rs:send_to), so every connected session receives the notification.
-    let recipients = if rec.topic == "poke.received" {
-        vec![parse_user_id_field(&rec.payload_json, "target_user_id")?]
-    } else if matches!(
-        rec.topic.as_str(),
-        "channel.created"
-            | "channels.created"
-            | "channel.renamed"
-            | "channel.deleted"
-            | "perm.role.upserted"
-            | "perm.role.deleted"
-            | "perm.role.order_changed"
-            | "perm.role.caps_changed"
-            | "perm.user.roles_changed"
-            | "perm.channel.overrides_changed"
-            | "perm.audit.appended"
-    ) {
-        hub.connected_users()
+    let (channel_id, mut push) = translate_record(&rec)?;
+    overlay_self_voice_state(membership, &rec, &mut push)?;
+    // Refreshes inside the throttle window are parked (newest wins) and sent
+    // from the dispatcher loop; the record itself is acked either way.
+    let push = if rec.topic == "channel.state_refresh" {
+        let admitted = refresh_throttle.offer(channel_id, push, Instant::now());
+        admitted.map(|mut push| {
+            overlay_channel_state_self_flags(membership, channel_id, &mut push);
+            push
+        })
     } else {
-        membership.members_of(channel_id).unwrap_or_default()
+        Some(push)
     };
 
+    let recipients = recipient_policy(&rec, channel_id)?.resolve(hub, membership);
+
     debug!(
         outbox_id = %rec.id.0,
         topic = %rec.topic,
         channel_id = %channel_id.0,
         server_id = %rec.server_id.0,
         fanout = recipients.len(),
-        event_seq = push.event_seq,
+        event_seq = push.as_ref().map_or(0, |p| p.event_seq),
+        throttled = push.is_none(),
         "dispatching outbox event"
     );
 
     apply_cache_side_effects(membership, &rec)?;
 
-    for uid in recipients {
-        hub.send(uid, push.clone()).await;
+    if let Some(push) = push {
+        metrics.observe_fanout(recipients.len());
+        for uid in recipients {
+            hub.send(uid, push.clone()).await;
+        }
+    }
+
+    ack_record(repo, token, &rec).await?;
+    let lag = Utc::now() - rec.created_at;
+    metrics.inc_published(&rec.topic, lag.num_milliseconds() as f64 / 1000.0);
+    Ok(())
+}
+
+/// Who an outbox record's push is delivered to. Users are resolved to all of
+/// their sessions by `PushHub::send`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Recipients {
+    /// Current members of the channel.
+    Channel(ChannelId),
+    /// Channel members, honoring per-user chat notification prefs.
+    Chat(ChannelId),
+    /// Channel members plus one user the gateway may already have dropped
+    /// from the membership cache (a kick or ban target, or someone leaving),
+    /// so their own sessions still hear about it.
+    ChannelAndUser(ChannelId, UserId),
+    User(UserId),
+    /// Every authenticated user connected to this gateway, which serves a
+    /// single server; for server-wide changes nobody is a channel member of.
+    ServerWide,
+}
+
+impl Recipients {
+    fn resolve(self, hub: &PushHub, membership: &MembershipCache) -> Vec<UserId> {
+        match self {
+            Recipients::Channel(channel) => membership.members_of(channel).unwrap_or_default(),
+            Recipients::Chat(channel) => membership.chat_push_recipients(channel),
+            Recipients::ChannelAndUser(channel, user) => {
+                let mut users = membership.members_of(channel).unwrap_or_default();
+                if !users.contains(&user) {
+                    users.push(user);
+                }
+                users
+            }
+            Recipients::User(user) => vec![user],
+            Recipients::ServerWide => hub.connected_users(),
+        }
     }
+}
+
+fn recipient_policy(rec: &OutboxEventRow, channel_id: ChannelId) -> Result<Recipients> {
+    Ok(match rec.topic.as_str() {
+        "poke.received" => {
+            Recipients::User(parse_user_id_field(&rec.payload_json, "target_user_id")?)
+        }
+        "presence.member_left" => Recipients::ChannelAndUser(
+            channel_id,
+            parse_user_id_field(&rec.payload_json, "user_id")?,
+        ),
+        "moderation.user_kicked" | "moderation.user_banned" => Recipients::ChannelAndUser(
+            channel_id,
+            parse_user_id_field(&rec.payload_json, "target_user_id")?,
+        ),
+        "channel.created"
+        | "channels.created"
+        | "channel.renamed"
+        | "channel.deleted"
+        | "perm.role.upserted"
+        | "perm.role.deleted"
+        | "perm.role.order_changed"
+        | "perm.role.caps_changed"
+        | "perm.user.roles_changed"
+        | "perm.channel.overrides_changed"
+        | "perm.audit.appended" => Recipients::ServerWide,
+        topic if topic.starts_with("chat.") => Recipients::Chat(channel_id),
+        _ => Recipients::Channel(channel_id),
+    })
+}
 
+async fn ack_record(repo: &PgControlRepo, token: uuid::Uuid, rec: &OutboxEventRow) -> Result<()> {
     let mut tx = repo.tx().await?;
     <PgControlRepo as ControlRepo>::ack_outbox_published(repo, &mut tx, &[rec.id], token).await?;
     tx.commit().await?;
     Ok(())
 }
 
+async fn record_failure(
+    repo: &PgControlRepo,
+    token: uuid::Uuid,
+    id: OutboxId,
+    error: &str,
+    max_attempts: i32,
+) -> Result<bool> {
+    let mut tx = repo.tx().await?;
+    let dead = <PgControlRepo as ControlRepo>::record_outbox_failure(
+        repo,
+        &mut tx,
+        id,
+        token,
+        error,
+        max_attempts,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(dead)
+}
+
 fn translate_record(rec: &OutboxEventRow) -> Result<(ChannelId, pb::ServerToClient)> {
     match rec.topic.as_str() {
         "presence.member_joined" => {
@@ -268,6 +463,10 @@ fn translate_record(rec: &OutboxEventRow) -> Result<(ChannelId, pb::ServerToClie
                 .get("attachments")
                 .cloned()
                 .unwrap_or(Value::Array(vec![]));
+            let kind = match rec.payload_json.get("kind").and_then(Value::as_str) {
+                Some("system") => pb::MessageKind::System,
+                _ => pb::MessageKind::User,
+            };
 
             let event_at = rec
                 .payload_json
@@ -293,6 +492,7 @@ fn translate_record(rec: &OutboxEventRow) -> Result<(ChannelId, pb::ServerToClie
                     }),
                     text,
                     attachments: json_attachments_to_pb(attachments),
+                    kind: kind as i32,
                     ..Default::default()
                 })),
             };
@@ -302,6 +502,118 @@ fn translate_record(rec: &OutboxEventRow) -> Result<(ChannelId, pb::ServerToClie
                 server_push(pb::server_to_client::Payload::ChatEvent(ev)),
             ))
         }
+        "chat.message_edited" => {
+            let channel_id = parse_channel_id_field(&rec.payload_json, "channel_id")?;
+            let message_id = parse_message_id_field(&rec.payload_json, "message_id")?;
+            let new_text = rec
+                .payload_json
+                .get("text")
+                .and_then(Value::as_str)
+                .unwrap_or("")
+                .to_string();
+            let edited_at = rec
+                .payload_json
+                .get("edited_at")
+                .and_then(Value::as_str)
+                .and_then(|raw| DateTime::parse_from_rfc3339(raw).ok())
+                .map(|dt| pb::Timestamp {
+                    unix_millis: dt.with_timezone(&Utc).timestamp_millis(),
+                })
+                .unwrap_or_else(now_ts);
+
+            let ev = pb::ChatEvent {
+                at: Some(edited_at),
+                kind: Some(pb::chat_event::Kind::MessageEdited(pb::MessageEdited {
+                    message_id: Some(pb::MessageId {
+                        value: message_id.0.to_string(),
+                    }),
+                    channel_id: Some(pb::ChannelId {
+                        value: channel_id.0.to_string(),
+                    }),
+                    new_text,
+                    edited_at: Some(edited_at),
+                })),
+            };
+
+            Ok((
+                channel_id,
+                server_push(pb::server_to_client::Payload::ChatEvent(ev)),
+            ))
+        }
+        "chat.message_deleted" => {
+            let channel_id = parse_channel_id_field(&rec.payload_json, "channel_id")?;
+            let message_id = parse_message_id_field(&rec.payload_json, "message_id")?;
+            let deleted_at = rec
+                .payload_json
+                .get("deleted_at")
+                .and_then(Value::as_str)
+                .and_then(|raw| DateTime::parse_from_rfc3339(raw).ok())
+                .map(|dt| pb::Timestamp {
+                    unix_millis: dt.with_timezone(&Utc).timestamp_millis(),
+                })
+                .unwrap_or_else(now_ts);
+
+            let ev = pb::ChatEvent {
+                at: Some(deleted_at),
+                kind: Some(pb::chat_event::Kind::MessageDeleted(pb::MessageDeleted {
+                    message_id: Some(pb::MessageId {
+                        value: message_id.0.to_string(),
+                    }),
+                    channel_id: Some(pb::ChannelId {
+                        value: channel_id.0.to_string(),
+                    }),
+                })),
+            };
+
+            Ok((
+                channel_id,
+                server_push(pb::server_to_client::Payload::ChatEvent(ev)),
+            ))
+        }
+        "chat.reaction_changed" => {
+            let channel_id = parse_channel_id_field(&rec.payload_json, "channel_id")?;
+            let message_id = parse_message_id_field(&rec.payload_json, "message_id")?;
+            let user_id = parse_user_id_field(&rec.payload_json, "user_id")?;
+            let emoji = rec
+                .payload_json
+                .get("emoji")
+                .and_then(Value::as_str)
+                .unwrap_or("")
+                .to_string();
+            let added = rec
+                .payload_json
+                .get("added")
+                .and_then(Value::as_bool)
+                .unwrap_or(false);
+            let count = rec
+                .payload_json
+                .get("count")
+                .and_then(Value::as_u64)
+                .unwrap_or(0) as u32;
+
+            let ev = pb::ChatEvent {
+                at: Some(now_ts()),
+                kind: Some(pb::chat_event::Kind::ReactionChanged(pb::ReactionChanged {
+                    message_id: Some(pb::MessageId {
+                        value: message_id.0.to_string(),
+                    }),
+                    channel_id: Some(pb::ChannelId {
+                        value: channel_id.0.to_string(),
+                    }),
+                    user_id: Some(pb::UserId {
+                        value: user_id.0.to_string(),
+                    }),
+                    emoji,
+                    added,
+                    count,
+                })),
+            };
+
+            Ok((
+                channel_id,
+                server_push(pb::server_to_client::Payload::ChatEvent(ev)),
+            ))
+        }
         "moderation.user_muted" => {
             let channel_id = parse_channel_id_field(&rec.payload_json, "channel_id")?;
             let target_user_id = parse_user_id_field(&rec.payload_json, "target_user_id")?;
@@ -400,6 +712,68 @@ fn translate_record(rec: &OutboxEventRow) -> Result<(ChannelId, pb::ServerToClie
                 server_push(pb::server_to_client::Payload::ModerationEvent(ev)),
             ))
         }
+        "moderation.user_banned" => {
+            let channel_id = parse_channel_id_field(&rec.payload_json, "channel_id")?;
+            let target_user_id = parse_user_id_field(&rec.payload_json, "target_user_id")?;
+            let actor_user_id = parse_user_id_field(&rec.payload_json, "actor_user_id")?;
+            let reason = rec
+                .payload_json
+                .get("reason")
+                .and_then(Value::as_str)
+                .unwrap_or("")
+                .to_string();
+            let duration_seconds =
+                parse_u32_field_default(&rec.payload_json, "duration_seconds", 0);
+            let ev = pb::ModerationEvent {
+                at: Some(now_ts()),
+                kind: Some(pb::moderation_event::Kind::UserBanned(pb::UserBanned {
+                    channel_id: Some(pb::ChannelId {
+                        value: channel_id.0.to_string(),
+                    }),
+                    target_user_id: Some(pb::UserId {
+                        value: target_user_id.0.to_string(),
+                    }),
+                    reason,
+                    duration_seconds,
+                    actor_user_id: Some(pb::UserId {
+                        value: actor_user_id.0.to_string(),
+                    }),
+                })),
+            };
+            Ok((
+                channel_id,
+                server_push(pb::server_to_client::Payload::ModerationEvent(ev)),
+            ))
+        }
+        "moderation.user_moved" => {
+            let from_channel_id = parse_channel_id_field(&rec.payload_json, "from_channel_id")?;
+            let to_channel_id = parse_channel_id_field(&rec.payload_json, "to_channel_id")?;
+            let target_user_id = parse_user_id_field(&rec.payload_json, "target_user_id")?;
+            let actor_user_id = parse_user_id_field(&rec.payload_json, "actor_user_id")?;
+            let ev = pb::ModerationEvent {
+                at: Some(now_ts()),
+                kind: Some(pb::moderation_event::Kind::UserMoved(pb::UserMoved {
+                    from_channel_id: Some(pb::ChannelId {
+                        value: from_channel_id.0.to_string(),
+                    }),
+                    to_channel_id: Some(pb::ChannelId {
+                        value: to_channel_id.0.to_string(),
+                    }),
+                    target_user_id: Some(pb::UserId {
+                        value: target_user_id.0.to_string(),
+                    }),
+                    actor_user_id: Some(pb::UserId {
+                        value: actor_user_id.0.to_string(),
+                    }),
+                })),
+            };
+            Ok((
+                to_channel_id,
+                server_push(pb::server_to_client::Payload::ModerationEvent(ev)),
+            ))
+        }
+        // Pokes are pushed directly by the gateway now; this only drains rows
+        // queued before that change.
         "poke.received" => {
             let _target_user_id = parse_user_id_field(&rec.payload_json, "target_user_id")?;
             let from_user_id = parse_user_id_field(&rec.payload_json, "from_user_id")?;
@@ -502,6 +876,14 @@ fn translate_record(rec: &OutboxEventRow) -> Result<(ChannelId, pb::ServerToClie
             let user_limit = parse_u32_field_default(&rec.payload_json, "max_members", 0);
             let bitrate = parse_u32_field_default(&rec.payload_json, "bitrate_bps", 64_000);
             let opus_profile = parse_i32_field_default(&rec.payload_json, "opus_profile", 1);
+            let max_message_length =
+                parse_u32_field_default(&rec.payload_json, "max_message_length", 0);
+            let slow_mode_secs = parse_u32_field_default(&rec.payload_json, "slow_mode_secs", 0);
+            let announce_presence = rec
+                .payload_json
+                .get("announce_presence")
+                .and_then(Value::as_bool)
+                .unwrap_or(false);
 
             Ok((
                 channel_id,
@@ -518,6 +900,9 @@ fn translate_record(rec: &OutboxEventRow) -> Result<(ChannelId, pb::ServerToClie
                             user_limit,
                             bitrate,
                             opus_profile,
+                            max_message_length,
+                            slow_mode_secs,
+                            announce_presence,
                             ..Default::default()
                         }),
                     },
@@ -691,23 +1076,227 @@ fn translate_record(rec: &OutboxEventRow) -> Result<(ChannelId, pb::ServerToClie
                 )),
             ))
         }
-        other => Err(anyhow!("unsupported outbox event type: {other}")),
+        "channel.state_refresh" => {
+            let channel_id = parse_channel_id_field(&rec.payload_json, "channel_id")?;
+            let members = rec
+                .payload_json
+                .get("members")
+                .and_then(Value::as_array)
+                .map(|members| {
+                    members
+                        .iter()
+                        .filter_map(|m| {
+                            let user_id = parse_user_id_field(m, "user_id").ok()?;
+                            let str_field = |field: &str| {
+                                m.get(field)
+                                    .and_then(Value::as_str)
+                                    .unwrap_or("")
+                                    .to_string()
+                            };
+                            Some(pb::ChannelMember {
+                                user_id: Some(pb::UserId {
+                                    value: user_id.0.to_string(),
+                                }),
+                                display_name: str_field("display_name"),
+                                muted: m.get("muted").and_then(Value::as_bool).unwrap_or(false),
+                                deafened: m
+                                    .get("deafened")
+                                    .and_then(Value::as_bool)
+                                    .unwrap_or(false),
+                                away_message: str_field("away_message"),
+                                custom_status_emoji: str_field("custom_status_emoji"),
+                                ..Default::default()
+                            })
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+
+            let ev = pb::PresenceEvent {
+                at: Some(now_ts()),
+                kind: Some(pb::presence_event::Kind::ChannelStateChanged(
+                    pb::ChannelStateChanged {
+                        state: Some(pb::ChannelState {
+                            channel_id: Some(pb::ChannelId {
+                                value: channel_id.0.to_string(),
+                            }),
+                            name: rec
+                                .payload_json
+                                .get("name")
+                                .and_then(Value::as_str)
+                                .unwrap_or("")
+                                .to_string(),
+                            members,
+                            info: None,
+                            max_members: parse_u32_field_default(
+                                &rec.payload_json,
+                                "max_members",
+                                0,
+                            ),
+                            max_talkers: parse_u32_field_default(
+                                &rec.payload_json,
+                                "max_talkers",
+                                0,
+                            ),
+                        }),
+                    },
+                )),
+            };
+
+            Ok((
+                channel_id,
+                server_push(pb::server_to_client::Payload::PresenceEvent(ev)),
+            ))
+        }
+        other => Err(UnsupportedTopic(other.to_string()).into()),
     }
 }
 
+/// Per-channel leading-edge throttle with a trailing send: the first refresh
+/// in a window goes out immediately, later ones replace a single parked push
+/// that is released once the window has passed. Busy channels therefore get
+/// at most one full member list per interval and still end on the latest.
+struct StateRefreshThrottle {
+    min_interval: Duration,
+    last_sent: HashMap<ChannelId, Instant>,
+    parked: HashMap<ChannelId, pb::ServerToClient>,
+}
+
+impl StateRefreshThrottle {
+    fn new(min_interval: Duration) -> Self {
+        Self {
+            min_interval,
+            last_sent: HashMap::new(),
+            parked: HashMap::new(),
+        }
+    }
+
+    /// Returns the push if it may be sent now; otherwise parks it.
+    fn offer(
+        &mut self,
+        channel_id: ChannelId,
+        push: pb::ServerToClient,
+        now: Instant,
+    ) -> Option<pb::ServerToClient> {
+        let recently_sent = self
+            .last_sent
+            .get(&channel_id)
+            .is_some_and(|at| now.saturating_duration_since(*at) < self.min_interval);
+        if recently_sent {
+            self.parked.insert(channel_id, push);
+            return None;
+        }
+        self.parked.remove(&channel_id);
+        self.last_sent.insert(channel_id, now);
+        Some(push)
+    }
+
+    /// Parked pushes whose window has closed, marked as sent.
+    fn take_due(&mut self, now: Instant) -> Vec<(ChannelId, pb::ServerToClient)> {
+        let min_interval = self.min_interval;
+        let last_sent = &mut self.last_sent;
+        let due = self
+            .parked
+            .keys()
+            .filter(|ch| {
+                last_sent
+                    .get(*ch)
+                    .is_none_or(|at| now.saturating_duration_since(*at) >= min_interval)
+            })
+            .copied()
+            .collect::<Vec<_>>();
+        let out = due
+            .into_iter()
+            .filter_map(|ch| {
+                let push = self.parked.remove(&ch)?;
+                last_sent.insert(ch, now);
+                Some((ch, push))
+            })
+            .collect();
+        let parked = &self.parked;
+        last_sent.retain(|ch, at| {
+            parked.contains_key(ch) || now.saturating_duration_since(*at) < min_interval
+        });
+        out
+    }
+}
+
+/// Refresh payloads come from the control plane, which never sees the
+/// client-reported self flags; fill them in from the cache like
+/// [`overlay_self_voice_state`] does for single-member updates.
+fn overlay_channel_state_self_flags(
+    membership: &MembershipCache,
+    channel_id: ChannelId,
+    push: &mut pb::ServerToClient,
+) {
+    if let Some(pb::server_to_client::Payload::PresenceEvent(pb::PresenceEvent {
+        kind:
+            Some(pb::presence_event::Kind::ChannelStateChanged(pb::ChannelStateChanged {
+                state: Some(state),
+            })),
+        ..
+    })) = push.payload.as_mut()
+    {
+        for member in &mut state.members {
+            let Some(user_id) = member
+                .user_id
+                .as_ref()
+                .and_then(|u| uuid::Uuid::parse_str(&u.value).ok())
+            else {
+                continue;
+            };
+            (member.self_muted, member.self_deafened) =
+                membership.self_voice_state(UserId(user_id), channel_id);
+        }
+    }
+}
+
+/// Moderation voice-state events come from the control plane, which never
+/// sees the client-reported self flags; fill them in from the cache so
+/// receivers don't clear a member's self mute/deafen indicator.
+fn overlay_self_voice_state(
+    membership: &MembershipCache,
+    rec: &OutboxEventRow,
+    push: &mut pb::ServerToClient,
+) -> Result<()> {
+    if rec.topic != "presence.voice_state_changed" {
+        return Ok(());
+    }
+    let channel_id = parse_channel_id_field(&rec.payload_json, "channel_id")?;
+    let user_id = parse_user_id_field(&rec.payload_json, "user_id")?;
+    if let Some(pb::server_to_client::Payload::PresenceEvent(pb::PresenceEvent {
+        kind: Some(pb::presence_event::Kind::MemberVoiceStateChanged(vs)),
+        ..
+    })) = push.payload.as_mut()
+    {
+        (vs.self_muted, vs.self_deafened) = membership.self_voice_state(user_id, channel_id);
+    }
+    Ok(())
+}
+
 fn apply_cache_side_effects(membership: &MembershipCache, rec: &OutboxEventRow) -> Result<()> {
     match rec.topic.as_str() {
         "presence.member_joined" => {
             let channel_id = parse_channel_id_field(&rec.payload_json, "channel_id")?;
             let user_id = parse_user_id_field(&rec.payload_json, "user_id")?;
-            membership.set_user(user_id, channel_id, false, false);
+            // A moderator move carries the member's server mute/deafen along.
+            let muted = rec
+                .payload_json
+                .get("muted")
+                .and_then(Value::as_bool)
+                .unwrap_or(false);
+            let deafened = rec
+                .payload_json
+                .get("deafened")
+                .and_then(Value::as_bool)
+                .unwrap_or(false);
+            membership.set_user(user_id, channel_id, muted, deafened);
             membership.add_channel_member(channel_id, user_id);
         }
         "presence.member_left" => {
             let channel_id = parse_channel_id_field(&rec.payload_json, "channel_id")?;
             let user_id = parse_user_id_field(&rec.payload_json, "user_id")?;
-            membership.remove_user(user_id);
-            membership.remove_channel_member(channel_id, user_id);
+            membership.leave_channel(user_id, channel_id);
         }
         "presence.voice_state_changed" => {
             let channel_id = parse_channel_id_field(&rec.payload_json, "channel_id")?;
@@ -752,10 +1341,20 @@ fn apply_cache_side_effects(membership: &MembershipCache, rec: &OutboxEventRow)
                 .unwrap_or(false);
             membership.update_deafen(user_id, channel_id, deafened);
         }
+        "moderation.user_banned" => {
+            let user_id = parse_user_id_field(&rec.payload_json, "target_user_id")?;
+            match parse_u32_field_default(&rec.payload_json, "duration_seconds", 0) {
+                0 => membership.set_banned(user_id, true),
+                secs => membership.ban_for(user_id, Duration::from_secs(u64::from(secs))),
+            }
+        }
+        "channel.deleted" => {
+            let channel_id = parse_channel_id_field(&rec.payload_json, "channel_id")?;
+            membership.drop_channel_chat_subscriptions(channel_id);
+        }
         "channel.created"
         | "channels.created"
         | "channel.renamed"
-        | "channel.deleted"
         | "perm.role.upserted"
         | "perm.role.deleted"
         | "perm.role.order_changed"
@@ -811,7 +1410,7 @@ fn parse_u32_field_default(v: &Value, field: &str, default: u32) -> u32 {
         .unwrap_or(default)
 }
 
-fn json_attachments_to_pb(v: Value) -> Vec<pb::AttachmentRef> {
+pub(crate) fn json_attachments_to_pb(v: Value) -> Vec<pb::AttachmentRef> {
     let arr = match v {
         Value::Array(a) => a,
         _ => return vec![],
@@ -867,7 +1466,7 @@ fn now_seq() -> u64 {
         .as_millis() as u64
 }
 
-fn now_ts() -> pb::Timestamp {
+pub(crate) fn now_ts() -> pb::Timestamp {
     let ms = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .unwrap_or_default()
@@ -878,26 +1477,564 @@ fn now_ts() -> pb::Timestamp {
 #[cfg(test)]
 mod tests {
 
-    use super::{apply_cache_side_effects, translate_record};
+    use super::{
+        apply_cache_side_effects, attempts_allowed, claim_batch, next_retry_delay,
+        overlay_channel_state_self_flags, overlay_self_voice_state, recipient_policy,
+        record_failure, translate_record, OutboxDispatcherConfig, Recipients, StateRefreshThrottle,
+        UnsupportedTopic, MAX_CLAIM_RETRY_DELAY,
+    };
     use crate::proto::voiceplatform::v1 as pb;
-    use crate::state::MembershipCache;
+    use crate::state::{MembershipCache, PushHub};
     use serde_json::json;
+    use std::time::{Duration, Instant};
     use vp_control::ids::{OutboxId, ServerId};
-    use vp_control::model::OutboxEventRow;
+    use vp_control::model::{OutboxEvent, OutboxEventRow};
+    use vp_control::{ControlRepo, PgControlRepo};
     use vp_media::voice_forwarder::MembershipProvider;
 
+    fn test_row(topic: &str, payload_json: serde_json::Value) -> OutboxEventRow {
+        OutboxEventRow {
+            id: OutboxId(uuid::Uuid::new_v4()),
+            server_id: ServerId(uuid::Uuid::new_v4()),
+            topic: topic.to_string(),
+            payload_json,
+            created_at: chrono::Utc::now(),
+        }
+    }
+
     #[test]
-    fn translate_channel_created_topic_is_supported() {
+    fn claim_retry_delay_doubles_up_to_cap() {
+        assert_eq!(
+            next_retry_delay(Duration::from_millis(250)),
+            Duration::from_millis(500)
+        );
+        assert_eq!(
+            next_retry_delay(Duration::from_secs(8)),
+            MAX_CLAIM_RETRY_DELAY
+        );
+        assert_eq!(
+            next_retry_delay(MAX_CLAIM_RETRY_DELAY),
+            MAX_CLAIM_RETRY_DELAY
+        );
+    }
+
+    #[test]
+    fn state_refresh_translates_to_full_channel_state() {
         let channel_id = uuid::Uuid::new_v4();
+        let alice = uuid::Uuid::new_v4();
+        let bob = uuid::Uuid::new_v4();
+        let rec = test_row(
+            "channel.state_refresh",
+            json!({
+                "channel_id": channel_id.to_string(),
+                "name": "Lobby",
+                "max_members": 8,
+                "max_talkers": null,
+                "members": [
+                    { "user_id": alice.to_string(), "display_name": "alice", "muted": true, "deafened": false },
+                    { "user_id": bob.to_string(), "display_name": "bob", "muted": false, "deafened": false },
+                ],
+            }),
+        );
+
+        let membership = MembershipCache::new();
+        let ch = vp_control::ids::ChannelId(channel_id);
+        membership.set_user(vp_control::ids::UserId(bob), ch, false, false);
+        membership.set_self_voice_state(vp_control::ids::UserId(bob), ch, true, false);
+
+        let (_, mut push) = translate_record(&rec).expect("channel.state_refresh is supported");
+        overlay_channel_state_self_flags(&membership, ch, &mut push);
+        let Some(pb::server_to_client::Payload::PresenceEvent(pb::PresenceEvent {
+            kind: Some(pb::presence_event::Kind::ChannelStateChanged(changed)),
+            ..
+        })) = push.payload
+        else {
+            panic!("expected ChannelStateChanged");
+        };
+        let state = changed.state.expect("state");
+        assert_eq!(state.name, "Lobby");
+        assert_eq!((state.max_members, state.max_talkers), (8, 0));
+        assert_eq!(state.members.len(), 2);
+        assert!(state.members[0].muted && !state.members[0].self_muted);
+        assert!(!state.members[1].muted && state.members[1].self_muted);
+    }
+
+    #[test]
+    fn state_refresh_throttle_sends_first_then_latest_per_window() {
+        let interval = Duration::from_secs(3);
+        let mut throttle = StateRefreshThrottle::new(interval);
+        let busy = vp_control::ids::ChannelId(uuid::Uuid::new_v4());
+        let quiet = vp_control::ids::ChannelId(uuid::Uuid::new_v4());
+        let push = |seq: u64| pb::ServerToClient {
+            event_seq: seq,
+            ..Default::default()
+        };
+        let t0 = Instant::now();
+
+        assert_eq!(
+            throttle.offer(busy, push(1), t0).map(|p| p.event_seq),
+            Some(1)
+        );
+        assert!(throttle
+            .offer(busy, push(2), t0 + Duration::from_millis(100))
+            .is_none());
+        assert!(throttle
+            .offer(busy, push(3), t0 + Duration::from_millis(200))
+            .is_none());
+        assert_eq!(
+            throttle.offer(quiet, push(4), t0).map(|p| p.event_seq),
+            Some(4)
+        );
+
+        assert!(throttle.take_due(t0 + Duration::from_secs(1)).is_empty());
+        let due = throttle.take_due(t0 + interval);
+        assert_eq!(due.len(), 1);
+        assert_eq!((due[0].0, due[0].1.event_seq), (busy, 3));
+
+        // The trailing send opens a new window.
+        assert!(throttle.offer(busy, push(5), t0 + interval).is_none());
+        assert_eq!(throttle.take_due(t0 + interval * 2).len(), 1);
+        assert!(throttle.take_due(t0 + interval * 3).is_empty());
+        assert_eq!(
+            throttle
+                .offer(busy, push(6), t0 + interval * 4)
+                .map(|p| p.event_seq),
+            Some(6)
+        );
+    }
+
+    type PushCheck = fn(&pb::server_to_client::Payload) -> bool;
+
+    /// A payload shaped like the one the control service writes for `topic`,
+    /// and a check that the translated push is the right kind.
+    fn sample_for_topic(
+        topic: &str,
+        channel_id: uuid::Uuid,
+        user_id: uuid::Uuid,
+    ) -> (serde_json::Value, PushCheck) {
+        use pb::presence_event::Kind as Presence;
+        use pb::push_event::Evt as Perm;
+        use pb::server_to_client::Payload as P;
+
+        let actor = uuid::Uuid::new_v4();
+        let channel = json!({
+            "server_id": uuid::Uuid::new_v4(),
+            "channel_id": channel_id,
+            "name": "General",
+            "parent_channel_id": null,
+            "max_members": null,
+            "channel_type": 2,
+            "description": "",
+            "bitrate_bps": 64_000,
+            "opus_profile": 1,
+            "updated_at": "2026-01-01T00:00:00Z",
+        });
+        match topic {
+            "channel.created" => (channel, |p| matches!(p, P::ChannelCreatedPush(_))),
+            "channel.renamed" => (channel, |p| matches!(p, P::ChannelRenamedPush(_))),
+            "channel.deleted" => (
+                json!({ "channel_id": channel_id, "updated_at": "2026-01-01T00:00:00Z" }),
+                |p| matches!(p, P::ChannelDeletedPush(_)),
+            ),
+            "channel.state_refresh" => (
+                json!({
+                    "channel_id": channel_id,
+                    "name": "General",
+                    "max_members": 8,
+                    "max_talkers": null,
+                    "members": [{ "user_id": user_id, "display_name": "Alice" }],
+                }),
+                |p| {
+                    matches!(p, P::PresenceEvent(pb::PresenceEvent {
+                        kind: Some(Presence::ChannelStateChanged(_)),
+                        ..
+                    }))
+                },
+            ),
+            "presence.member_joined" => (
+                json!({
+                    "channel_id": channel_id,
+                    "user_id": user_id,
+                    "display_name": "Alice",
+                    "muted": false,
+                    "deafened": false,
+                    "away_message": "",
+                }),
+                |p| {
+                    matches!(p, P::PresenceEvent(pb::PresenceEvent {
+                        kind: Some(Presence::MemberJoined(_)),
+                        ..
+                    }))
+                },
+            ),
+            "presence.member_left" => (
+                json!({ "channel_id": channel_id, "user_id": user_id }),
+                |p| {
+                    matches!(p, P::PresenceEvent(pb::PresenceEvent {
+                        kind: Some(Presence::MemberLeft(_)),
+                        ..
+                    }))
+                },
+            ),
+            "presence.voice_state_changed" => (
+                json!({
+                    "channel_id": channel_id,
+                    "user_id": user_id,
+                    "muted": true,
+                    "deafened": false,
+                }),
+                |p| {
+                    matches!(p, P::PresenceEvent(pb::PresenceEvent {
+                        kind: Some(Presence::MemberVoiceStateChanged(_)),
+                        ..
+                    }))
+                },
+            ),
+            "presence.user_online_status_changed" => (
+                json!({
+                    "channel_id": channel_id,
+                    "user_id": user_id,
+                    "custom_status_text": "away",
+                    "custom_status_emoji": "",
+                    "custom_status_expires_ms": null,
+                }),
+                |p| {
+                    matches!(p, P::PresenceEvent(pb::PresenceEvent {
+                        kind: Some(Presence::UserOnlineStatusChanged(_)),
+                        ..
+                    }))
+                },
+            ),
+            "chat.message_posted" => (
+                json!({
+                    "message_id": uuid::Uuid::new_v4(),
+                    "channel_id": channel_id,
+                    "author_user_id": user_id,
+                    "text": "hi",
+                    "attachments": [],
+                    "kind": "user",
+                    "created_at": "2026-01-01T00:00:00Z",
+                }),
+                |p| {
+                    matches!(p, P::ChatEvent(pb::ChatEvent {
+                        kind: Some(pb::chat_event::Kind::MessagePosted(_)),
+                        ..
+                    }))
+                },
+            ),
+            "chat.message_edited" => (
+                json!({
+                    "message_id": uuid::Uuid::new_v4(),
+                    "channel_id": channel_id,
+                    "author_user_id": user_id,
+                    "text": "hi again",
+                    "edited_at": "2026-01-01T00:00:05Z",
+                }),
+                |p| {
+                    matches!(p, P::ChatEvent(pb::ChatEvent {
+                        kind: Some(pb::chat_event::Kind::MessageEdited(_)),
+                        ..
+                    }))
+                },
+            ),
+            "chat.message_deleted" => (
+                json!({
+                    "message_id": uuid::Uuid::new_v4(),
+                    "channel_id": channel_id,
+                    "author_user_id": user_id,
+                    "deleted_at": "2026-01-01T00:00:05Z",
+                }),
+                |p| {
+                    matches!(p, P::ChatEvent(pb::ChatEvent {
+                        kind: Some(pb::chat_event::Kind::MessageDeleted(_)),
+                        ..
+                    }))
+                },
+            ),
+            "chat.reaction_changed" => (
+                json!({
+                    "message_id": uuid::Uuid::new_v4(),
+                    "channel_id": channel_id,
+                    "user_id": user_id,
+                    "emoji": "👍",
+                    "added": true,
+                    "count": 2,
+                }),
+                |p| {
+                    matches!(p, P::ChatEvent(pb::ChatEvent {
+                        kind: Some(pb::chat_event::Kind::ReactionChanged(pb::ReactionChanged {
+                            added: true,
+                            count: 2,
+                            ..
+                        })),
+                        ..
+                    }))
+                },
+            ),
+            "moderation.user_muted" => (
+                json!({
+                    "channel_id": channel_id,
+                    "target_user_id": user_id,
+                    "actor_user_id": actor,
+                    "muted": true,
+                    "deafened": false,
+                    "reason": "",
+                }),
+                |p| {
+                    matches!(p, P::ModerationEvent(pb::ModerationEvent {
+                        kind: Some(pb::moderation_event::Kind::UserMuted(_)),
+                        ..
+                    }))
+                },
+            ),
+            "moderation.user_deafened" => (
+                json!({
+                    "channel_id": channel_id,
+                    "target_user_id": user_id,
+                    "actor_user_id": actor,
+                    "deafened": true,
+                    "reason": "",
+                }),
+                |p| {
+                    matches!(p, P::ModerationEvent(pb::ModerationEvent {
+                        kind: Some(pb::moderation_event::Kind::UserDeafened(_)),
+                        ..
+                    }))
+                },
+            ),
+            "moderation.user_kicked" => (
+                json!({
+                    "channel_id": channel_id,
+                    "target_user_id": user_id,
+                    "actor_user_id": actor,
+                    "reason": "",
+                }),
+                |p| {
+                    matches!(p, P::ModerationEvent(pb::ModerationEvent {
+                        kind: Some(pb::moderation_event::Kind::UserKicked(_)),
+                        ..
+                    }))
+                },
+            ),
+            "moderation.user_banned" => (
+                json!({
+                    "channel_id": channel_id,
+                    "target_user_id": user_id,
+                    "actor_user_id": actor,
+                    "reason": "",
+                    "duration_seconds": 3600,
+                }),
+                |p| {
+                    matches!(p, P::ModerationEvent(pb::ModerationEvent {
+                        kind: Some(pb::moderation_event::Kind::UserBanned(_)),
+                        ..
+                    }))
+                },
+            ),
+            "moderation.user_moved" => (
+                json!({
+                    "from_channel_id": channel_id,
+                    "to_channel_id": uuid::Uuid::new_v4(),
+                    "target_user_id": user_id,
+                    "actor_user_id": actor,
+                }),
+                |p| {
+                    matches!(p, P::ModerationEvent(pb::ModerationEvent {
+                        kind: Some(pb::moderation_event::Kind::UserMoved(_)),
+                        ..
+                    }))
+                },
+            ),
+            "perm.role.upserted" => (
+                json!({ "role_id": "mods", "name": "Mods", "position": 2 }),
+                |p| {
+                    matches!(p, P::PermissionsPushEvent(pb::PushEvent {
+                        evt: Some(Perm::RoleUpserted(_)),
+                    }))
+                },
+            ),
+            "perm.role.deleted" => (json!({ "role_id": "mods" }), |p| {
+                matches!(p, P::PermissionsPushEvent(pb::PushEvent {
+                    evt: Some(Perm::RoleDeleted(_)),
+                }))
+            }),
+            "perm.role.order_changed" => (json!({ "role_ids": ["mods", "everyone"] }), |p| {
+                matches!(p, P::PermissionsPushEvent(pb::PushEvent {
+                    evt: Some(Perm::RoleOrder(_)),
+                }))
+            }),
+            "perm.role.caps_changed" => (
+                json!({ "role_id": "mods", "caps": [] }),
+                |p| {
+                    matches!(p, P::PermissionsPushEvent(pb::PushEvent {
+                        evt: Some(Perm::RoleCaps(_)),
+                    }))
+                },
+            ),
+            "perm.user.roles_changed" => (
+                json!({ "user_id": user_id, "roles": ["mods"] }),
+                |p| {
+                    matches!(p, P::PermissionsPushEvent(pb::PushEvent {
+                        evt: Some(Perm::UserRoles(_)),
+                    }))
+                },
+            ),
+            "perm.channel.overrides_changed" => (
+                json!({
+                    "channel_id": channel_id,
+                    "role_id": "mods",
+                    "user_id": null,
+                    "cap": "speak",
+                    "effect": "deny",
+                }),
+                |p| {
+                    matches!(p, P::PermissionsPushEvent(pb::PushEvent {
+                        evt: Some(Perm::ChanOvr(_)),
+                    }))
+                },
+            ),
+            "perm.audit.appended" => (
+                json!({ "action": "role.upsert", "target_type": "role", "target_id": "mods" }),
+                |p| {
+                    matches!(p, P::PermissionsPushEvent(pb::PushEvent {
+                        evt: Some(Perm::AuditAppended(_)),
+                    }))
+                },
+            ),
+            other => panic!(
+                "no sample payload for outbox topic '{other}': add one next to its translate_record arm"
+            ),
+        }
+    }
+
+    #[test]
+    fn every_emitted_outbox_topic_translates_to_a_push() {
+        for &topic in vp_control::outbox::OUTBOX_TOPICS {
+            let channel_id = uuid::Uuid::new_v4();
+            let user_id = uuid::Uuid::new_v4();
+            let (payload_json, is_expected) = sample_for_topic(topic, channel_id, user_id);
+            let scoped = payload_json.get("channel_id").is_some();
+            let rec = test_row(topic, payload_json);
+
+            let (routed_channel, push) = translate_record(&rec)
+                .unwrap_or_else(|e| panic!("{topic} has no working translate arm: {e:#}"));
+            let payload = push
+                .payload
+                .unwrap_or_else(|| panic!("{topic} translated to an empty push"));
+            assert!(is_expected(&payload), "{topic} translated to {payload:?}");
+            if scoped {
+                assert_eq!(
+                    routed_channel.0, channel_id,
+                    "{topic} routed to the wrong channel"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_topic_is_reported_as_unsupported() {
+        let rec = test_row("channel.teleported", json!({}));
+        let err = translate_record(&rec).expect_err("unknown topic");
+        assert!(err.is::<UnsupportedTopic>());
+
+        // Malformed payloads on known topics stay retryable.
         let rec = OutboxEventRow {
-            id: OutboxId(uuid::Uuid::new_v4()),
-            server_id: ServerId(uuid::Uuid::new_v4()),
             topic: "channel.created".to_string(),
-            payload_json: json!({
+            ..rec
+        };
+        let err = translate_record(&rec).expect_err("missing channel_id");
+        assert!(!err.is::<UnsupportedTopic>());
+    }
+
+    #[tokio::test]
+    async fn failed_records_are_dead_lettered_and_never_reclaimed() -> anyhow::Result<()> {
+        let Ok(url) = std::env::var("VP_DATABASE_URL") else {
+            return Ok(());
+        };
+        let pool = sqlx::PgPool::connect(&url).await?;
+        sqlx::migrate!("../control/migrations").run(&pool).await?;
+        let repo = PgControlRepo::new(pool.clone());
+        let cfg = OutboxDispatcherConfig {
+            server_id: ServerId(uuid::Uuid::new_v4()),
+            poll_interval: Duration::from_millis(100),
+            batch_size: 10,
+            claim_ttl_seconds: 30,
+            max_attempts: 3,
+        };
+        let event = |topic: &str| OutboxEvent {
+            id: OutboxId(uuid::Uuid::new_v4()),
+            server_id: cfg.server_id,
+            topic: topic.to_string(),
+            payload_json: json!({}),
+        };
+        let (flaky, unsupported, healthy) = (
+            event("channel.created"),
+            event("channel.teleported"),
+            event("presence.member_joined"),
+        );
+        let mut tx = repo.tx().await?;
+        for ev in [&flaky, &unsupported, &healthy] {
+            <PgControlRepo as ControlRepo>::insert_outbox(&repo, &mut tx, ev).await?;
+        }
+        tx.commit().await?;
+        let state = |id: OutboxId| {
+            sqlx::query_as::<_, (i32, bool, Option<String>)>(
+                "SELECT attempts, failed_at IS NOT NULL, last_error FROM outbox_events WHERE id = $1",
+            )
+            .bind(id.0)
+            .fetch_one(&pool)
+        };
+
+        let token = uuid::Uuid::new_v4();
+        let claimed = claim_batch(&repo, &cfg, token).await?;
+        assert_eq!(claimed.len(), 3);
+
+        for attempt in 1..=2 {
+            assert!(!record_failure(&repo, token, flaky.id, "push failed", 3).await?);
+            assert_eq!(
+                state(flaky.id).await?,
+                (attempt, false, Some("push failed".to_string()))
+            );
+        }
+        assert!(record_failure(&repo, token, flaky.id, "push failed", 3).await?);
+        assert!(state(flaky.id).await?.1);
+
+        let rec = claimed
+            .iter()
+            .find(|rec| rec.id == unsupported.id)
+            .expect("claimed");
+        let err = translate_record(rec).expect_err("unknown topic");
+        let max_attempts = attempts_allowed(&err, cfg.max_attempts);
+        assert_eq!(max_attempts, 1);
+        let error = format!("{err:#}");
+        assert!(record_failure(&repo, token, unsupported.id, &error, max_attempts).await?);
+        assert_eq!(state(unsupported.id).await?.0, 1);
+
+        // Once the claim expires only the record that never failed comes back.
+        sqlx::query(
+            "UPDATE outbox_events SET claimed_at = NOW() - INTERVAL '1 minute' WHERE server_id = $1",
+        )
+        .bind(cfg.server_id.0)
+        .execute(&pool)
+        .await?;
+        let reclaimed = claim_batch(&repo, &cfg, uuid::Uuid::new_v4()).await?;
+        assert_eq!(
+            reclaimed.iter().map(|rec| rec.id).collect::<Vec<_>>(),
+            [healthy.id]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn translate_channel_created_topic_is_supported() {
+        let channel_id = uuid::Uuid::new_v4();
+        let rec = test_row(
+            "channel.created",
+            json!({
                 "channel_id": channel_id,
                 "name": "General"
             }),
-        };
+        );
 
         let (parsed_channel, push) =
             translate_record(&rec).expect("channel.created should be supported");
@@ -914,32 +2051,127 @@ mod tests {
     #[test]
     fn translate_channels_created_alias_is_supported() {
         let channel_id = uuid::Uuid::new_v4();
-        let rec = OutboxEventRow {
-            id: OutboxId(uuid::Uuid::new_v4()),
-            server_id: ServerId(uuid::Uuid::new_v4()),
-            topic: "channels.created".to_string(),
-            payload_json: json!({"channel_id": channel_id}),
-        };
+        let rec = test_row("channels.created", json!({"channel_id": channel_id}));
 
         let (parsed_channel, _push) =
             translate_record(&rec).expect("channels.created alias should be supported");
         assert_eq!(parsed_channel.0, channel_id);
     }
 
+    #[test]
+    fn translate_channel_renamed_carries_chat_limits() {
+        let channel_id = uuid::Uuid::new_v4();
+        let rec = test_row(
+            "channel.renamed",
+            json!({
+                "channel_id": channel_id,
+                "name": "General",
+                "max_message_length": 500,
+                "slow_mode_secs": 10,
+                "announce_presence": true,
+            }),
+        );
+
+        let (_, push) = translate_record(&rec).expect("channel.renamed should be supported");
+        let Some(pb::server_to_client::Payload::ChannelRenamedPush(renamed)) = push.payload else {
+            panic!("unexpected payload: {:?}", push.payload);
+        };
+        let channel = renamed.channel.expect("channel");
+        assert_eq!(
+            (channel.max_message_length, channel.slow_mode_secs),
+            (500, 10)
+        );
+        assert!(channel.announce_presence);
+
+        // Rows written before chat limits existed, or with the default length.
+        let rec = OutboxEventRow {
+            payload_json: json!({ "channel_id": channel_id, "max_message_length": null }),
+            ..rec
+        };
+        let (_, push) = translate_record(&rec).expect("channel.renamed should be supported");
+        let Some(pb::server_to_client::Payload::ChannelRenamedPush(renamed)) = push.payload else {
+            panic!("unexpected payload: {:?}", push.payload);
+        };
+        let channel = renamed.channel.expect("channel");
+        assert_eq!((channel.max_message_length, channel.slow_mode_secs), (0, 0));
+    }
+
+    #[test]
+    fn translate_chat_message_posted_keeps_system_kind() {
+        let rec = test_row(
+            "chat.message_posted",
+            json!({
+                "message_id": uuid::Uuid::new_v4(),
+                "channel_id": uuid::Uuid::new_v4(),
+                "author_user_id": uuid::Uuid::new_v4(),
+                "text": "alice joined the channel",
+                "attachments": [],
+                "kind": "system",
+            }),
+        );
+        let kind_of = |rec: &OutboxEventRow| {
+            let (_, push) = translate_record(rec).expect("chat.message_posted should be supported");
+            let Some(pb::server_to_client::Payload::ChatEvent(pb::ChatEvent {
+                kind: Some(pb::chat_event::Kind::MessagePosted(posted)),
+                ..
+            })) = push.payload
+            else {
+                panic!("unexpected payload: {:?}", push.payload);
+            };
+            posted.kind()
+        };
+        assert_eq!(kind_of(&rec), pb::MessageKind::System);
+
+        // Rows written before message kinds existed are user messages.
+        let mut legacy = rec.clone();
+        legacy.payload_json.as_object_mut().unwrap().remove("kind");
+        assert_eq!(kind_of(&legacy), pb::MessageKind::User);
+    }
+
+    #[test]
+    fn translate_chat_message_edited_carries_text_and_time() {
+        let message_id = uuid::Uuid::new_v4();
+        let rec = test_row(
+            "chat.message_edited",
+            json!({
+                "message_id": message_id,
+                "channel_id": uuid::Uuid::new_v4(),
+                "author_user_id": uuid::Uuid::new_v4(),
+                "text": "fixed typo",
+                "edited_at": "2026-01-01T00:00:05Z",
+            }),
+        );
+        let (_, push) = translate_record(&rec).expect("chat.message_edited should be supported");
+        let Some(pb::server_to_client::Payload::ChatEvent(pb::ChatEvent {
+            kind: Some(pb::chat_event::Kind::MessageEdited(edited)),
+            ..
+        })) = push.payload
+        else {
+            panic!("unexpected payload: {:?}", push.payload);
+        };
+        assert_eq!(
+            edited.message_id.expect("message id").value,
+            message_id.to_string()
+        );
+        assert_eq!(edited.new_text, "fixed typo");
+        assert_eq!(
+            edited.edited_at.expect("edited_at").unix_millis,
+            1_767_225_605_000
+        );
+    }
+
     #[test]
     fn translate_presence_user_online_status_changed_is_supported() {
         let channel_id = uuid::Uuid::new_v4();
         let user_id = uuid::Uuid::new_v4();
-        let rec = OutboxEventRow {
-            id: OutboxId(uuid::Uuid::new_v4()),
-            server_id: ServerId(uuid::Uuid::new_v4()),
-            topic: "presence.user_online_status_changed".to_string(),
-            payload_json: json!({
+        let rec = test_row(
+            "presence.user_online_status_changed",
+            json!({
                 "channel_id": channel_id,
                 "user_id": user_id,
                 "custom_status_text": "Lunch"
             }),
-        };
+        );
 
         let (parsed_channel, push) = translate_record(&rec)
             .expect("presence.user_online_status_changed should be supported");
@@ -955,22 +2187,20 @@ mod tests {
             other => panic!("unexpected payload: {:?}", other),
         }
     }
-    
+
     #[test]
     fn translate_presence_member_joined_includes_away_message() {
         let channel_id = uuid::Uuid::new_v4();
         let user_id = uuid::Uuid::new_v4();
-        let rec = OutboxEventRow {
-            id: OutboxId(uuid::Uuid::new_v4()),
-            server_id: ServerId(uuid::Uuid::new_v4()),
-            topic: "presence.member_joined".to_string(),
-            payload_json: json!({
+        let rec = test_row(
+            "presence.member_joined",
+            json!({
                 "channel_id": channel_id,
                 "user_id": user_id,
                 "display_name": "alice",
                 "away_message": "Out to lunch"
             }),
-        };
+        );
 
         let (parsed_channel, push) =
             translate_record(&rec).expect("presence.member_joined should be supported");
@@ -988,7 +2218,60 @@ mod tests {
             other => panic!("unexpected payload: {:?}", other),
         }
     }
-    
+
+    #[test]
+    fn kicked_user_still_receives_their_kick_after_leaving_the_cache() {
+        let membership = MembershipCache::new();
+        let channel = vp_control::ids::ChannelId(uuid::Uuid::new_v4());
+        let bystander = vp_control::ids::UserId(uuid::Uuid::new_v4());
+        let target = vp_control::ids::UserId(uuid::Uuid::new_v4());
+        // The gateway drops the target from the cache before the record
+        // comes back through the outbox.
+        membership.set_channel(channel, 4, vec![bystander]);
+
+        let rec = test_row(
+            "moderation.user_kicked",
+            json!({
+                "channel_id": channel.0,
+                "target_user_id": target.0,
+                "actor_user_id": bystander.0,
+            }),
+        );
+        let policy = recipient_policy(&rec, channel).expect("policy");
+        assert_eq!(policy, Recipients::ChannelAndUser(channel, target));
+        let recipients = policy.resolve(&PushHub::new(), &membership);
+        assert_eq!(recipients, vec![bystander, target]);
+
+        // A target still in the cache isn't notified twice.
+        membership.set_channel(channel, 4, vec![bystander, target]);
+        assert_eq!(policy.resolve(&PushHub::new(), &membership).len(), 2);
+    }
+
+    #[test]
+    fn recipient_policy_routes_by_topic() {
+        let channel = vp_control::ids::ChannelId(uuid::Uuid::new_v4());
+        let user = vp_control::ids::UserId(uuid::Uuid::new_v4());
+        let policy = |topic: &str| {
+            let rec = test_row(
+                topic,
+                json!({ "user_id": user.0, "target_user_id": user.0 }),
+            );
+            recipient_policy(&rec, channel).expect("policy")
+        };
+        assert_eq!(policy("poke.received"), Recipients::User(user));
+        assert_eq!(
+            policy("presence.member_left"),
+            Recipients::ChannelAndUser(channel, user)
+        );
+        assert_eq!(policy("channel.created"), Recipients::ServerWide);
+        assert_eq!(policy("perm.role.deleted"), Recipients::ServerWide);
+        assert_eq!(policy("chat.message_posted"), Recipients::Chat(channel));
+        assert_eq!(
+            policy("presence.member_joined"),
+            Recipients::Channel(channel)
+        );
+    }
+
     #[test]
     fn member_join_left_side_effects_update_channel_members() {
         let membership = MembershipCache::new();
@@ -997,15 +2280,13 @@ mod tests {
 
         membership.set_channel(vp_control::ids::ChannelId(channel_id), 4, vec![]);
 
-        let joined = OutboxEventRow {
-            id: OutboxId(uuid::Uuid::new_v4()),
-            server_id: ServerId(uuid::Uuid::new_v4()),
-            topic: "presence.member_joined".to_string(),
-            payload_json: json!({
+        let joined = test_row(
+            "presence.member_joined",
+            json!({
                 "channel_id": channel_id,
                 "user_id": user_id
             }),
-        };
+        );
         apply_cache_side_effects(&membership, &joined).expect("join side effects should apply");
 
         let members = membership
@@ -1013,15 +2294,13 @@ mod tests {
             .expect("channel should exist in cache");
         assert_eq!(members, vec![vp_control::ids::UserId(user_id)]);
 
-        let left = OutboxEventRow {
-            id: OutboxId(uuid::Uuid::new_v4()),
-            server_id: ServerId(uuid::Uuid::new_v4()),
-            topic: "presence.member_left".to_string(),
-            payload_json: json!({
+        let left = test_row(
+            "presence.member_left",
+            json!({
                 "channel_id": channel_id,
                 "user_id": user_id
             }),
-        };
+        );
         apply_cache_side_effects(&membership, &left).expect("left side effects should apply");
 
         let members = membership
@@ -1037,54 +2316,142 @@ mod tests {
 
         membership.set_channel(channel, 4, vec![user]);
         membership.set_user(user, channel, false, false);
+        membership.set_self_voice_state(user, channel, true, false);
 
-        let voice_state = OutboxEventRow {
-            id: OutboxId(uuid::Uuid::new_v4()),
-            server_id: ServerId(uuid::Uuid::new_v4()),
-            topic: "presence.voice_state_changed".to_string(),
-            payload_json: json!({
+        let voice_state = test_row(
+            "presence.voice_state_changed",
+            json!({
                 "channel_id": channel.0,
                 "user_id": user.0,
                 "muted": true,
                 "deafened": true
             }),
-        };
+        );
         apply_cache_side_effects(&membership, &voice_state).expect("voice state side effects");
 
         assert!(membership.is_muted(channel, user).await);
         assert!(membership.is_deafened(channel, user).await);
 
-        let moderation = OutboxEventRow {
-            id: OutboxId(uuid::Uuid::new_v4()),
-            server_id: ServerId(uuid::Uuid::new_v4()),
-            topic: "moderation.user_deafened".to_string(),
-            payload_json: json!({
+        let (_, mut push) = translate_record(&voice_state).expect("translate voice state");
+        overlay_self_voice_state(&membership, &voice_state, &mut push).expect("overlay");
+        match push.payload {
+            Some(pb::server_to_client::Payload::PresenceEvent(pb::PresenceEvent {
+                kind: Some(pb::presence_event::Kind::MemberVoiceStateChanged(vs)),
+                ..
+            })) => {
+                assert!(vs.muted && vs.deafened);
+                assert!(vs.self_muted && !vs.self_deafened);
+            }
+            other => panic!("unexpected payload: {other:?}"),
+        }
+
+        let moderation = test_row(
+            "moderation.user_deafened",
+            json!({
                 "channel_id": channel.0,
                 "target_user_id": user.0,
                 "deafened": false
             }),
-        };
+        );
         apply_cache_side_effects(&membership, &moderation).expect("moderation deafen side effects");
 
         assert!(membership.is_muted(channel, user).await);
         assert!(!membership.is_deafened(channel, user).await);
     }
 
+    #[tokio::test]
+    async fn user_banned_translates_and_marks_cache() {
+        let membership = MembershipCache::new();
+        let channel = vp_control::ids::ChannelId(uuid::Uuid::new_v4());
+        let user = vp_control::ids::UserId(uuid::Uuid::new_v4());
+        let rec = test_row(
+            "moderation.user_banned",
+            json!({
+                "channel_id": channel.0,
+                "target_user_id": user.0,
+                "actor_user_id": uuid::Uuid::new_v4(),
+                "reason": "spam"
+            }),
+        );
+
+        let (ch, push) = translate_record(&rec).expect("should translate");
+        assert_eq!(ch, channel);
+        match push.payload {
+            Some(pb::server_to_client::Payload::ModerationEvent(ev)) => match ev.kind {
+                Some(pb::moderation_event::Kind::UserBanned(b)) => {
+                    assert_eq!(b.reason, "spam");
+                    assert_eq!(b.duration_seconds, 0);
+                }
+                other => panic!("unexpected: {:?}", other),
+            },
+            other => panic!("unexpected: {:?}", other),
+        }
+
+        apply_cache_side_effects(&membership, &rec).expect("ban side effects");
+        assert!(membership.is_banned(channel, user).await);
+    }
+
+    #[tokio::test]
+    async fn user_moved_routes_to_destination_and_keeps_server_mute() {
+        use vp_media::voice_forwarder::SenderRoute;
+
+        let membership = MembershipCache::new();
+        let from = vp_control::ids::ChannelId(uuid::Uuid::new_v4());
+        let to = vp_control::ids::ChannelId(uuid::Uuid::new_v4());
+        let user = vp_control::ids::UserId(uuid::Uuid::new_v4());
+        membership.set_user(user, from, true, false);
+
+        let left = test_row(
+            "presence.member_left",
+            json!({ "channel_id": from.0, "user_id": user.0 }),
+        );
+        let joined = test_row(
+            "presence.member_joined",
+            json!({
+                "channel_id": to.0,
+                "user_id": user.0,
+                "display_name": "moved",
+                "muted": true,
+                "deafened": false,
+            }),
+        );
+        let moved = test_row(
+            "moderation.user_moved",
+            json!({
+                "from_channel_id": from.0,
+                "to_channel_id": to.0,
+                "target_user_id": user.0,
+                "actor_user_id": uuid::Uuid::new_v4(),
+            }),
+        );
+        for rec in [&left, &joined, &moved] {
+            apply_cache_side_effects(&membership, rec).expect("move side effects");
+        }
+
+        assert_eq!(
+            membership
+                .resolve_channel_for_sender(user, crate::state::channel_route_key(to))
+                .await,
+            SenderRoute::Channel(to)
+        );
+        assert!(membership.is_muted(to, user).await);
+        let (ch, _) = translate_record(&moved).expect("should translate");
+        assert_eq!(ch, to);
+    }
+
     #[test]
     fn status_changed_propagates_text_and_emoji() {
         let channel_id = uuid::Uuid::new_v4();
         let user_id = uuid::Uuid::new_v4();
-        let rec = OutboxEventRow {
-            id: OutboxId(uuid::Uuid::new_v4()),
-            server_id: ServerId(uuid::Uuid::new_v4()),
-            topic: "presence.user_online_status_changed".to_string(),
-            payload_json: json!({
+        let rec = test_row(
+            "presence.user_online_status_changed",
+            json!({
                 "channel_id": channel_id,
                 "user_id": user_id,
                 "custom_status_text": "In a meeting",
                 "custom_status_emoji": "\u{1F4BC}",
             }),
-        };
+        );
 
         let (_ch, push) = translate_record(&rec).expect("should translate");
         match push.payload {
@@ -1104,17 +2471,15 @@ mod tests {
     fn status_changed_propagates_emoji_only() {
         let channel_id = uuid::Uuid::new_v4();
         let user_id = uuid::Uuid::new_v4();
-        let rec = OutboxEventRow {
-            id: OutboxId(uuid::Uuid::new_v4()),
-            server_id: ServerId(uuid::Uuid::new_v4()),
-            topic: "presence.user_online_status_changed".to_string(),
-            payload_json: json!({
+        let rec = test_row(
+            "presence.user_online_status_changed",
+            json!({
                 "channel_id": channel_id,
                 "user_id": user_id,
                 "custom_status_text": "",
                 "custom_status_emoji": "\u{2615}",
             }),
-        };
+        );
 
         let (_ch, push) = translate_record(&rec).expect("should translate");
         match push.payload {
@@ -1134,18 +2499,16 @@ mod tests {
         let channel_id = uuid::Uuid::new_v4();
         let user_id = uuid::Uuid::new_v4();
         let expires_ms: i64 = 1700000000000;
-        let rec = OutboxEventRow {
-            id: OutboxId(uuid::Uuid::new_v4()),
-            server_id: ServerId(uuid::Uuid::new_v4()),
-            topic: "presence.user_online_status_changed".to_string(),
-            payload_json: json!({
+        let rec = test_row(
+            "presence.user_online_status_changed",
+            json!({
                 "channel_id": channel_id,
                 "user_id": user_id,
                 "custom_status_text": "BRB",
                 "custom_status_emoji": "\u{1F6B6}",
                 "custom_status_expires_ms": expires_ms,
             }),
-        };
+        );
 
         let (_ch, push) = translate_record(&rec).expect("should translate");
         match push.payload {
@@ -1166,18 +2529,16 @@ mod tests {
     fn status_changed_clear_propagates_empty_fields() {
         let channel_id = uuid::Uuid::new_v4();
         let user_id = uuid::Uuid::new_v4();
-        let rec = OutboxEventRow {
-            id: OutboxId(uuid::Uuid::new_v4()),
-            server_id: ServerId(uuid::Uuid::new_v4()),
-            topic: "presence.user_online_status_changed".to_string(),
-            payload_json: json!({
+        let rec = test_row(
+            "presence.user_online_status_changed",
+            json!({
                 "channel_id": channel_id,
                 "user_id": user_id,
                 "custom_status_text": "",
                 "custom_status_emoji": "",
                 "custom_status_expires_ms": null,
             }),
-        };
+        );
 
         let (_ch, push) = translate_record(&rec).expect("should translate");
         match push.payload {
@@ -1198,16 +2559,14 @@ mod tests {
         // Old-format outbox events without emoji/expiry should still work
         let channel_id = uuid::Uuid::new_v4();
         let user_id = uuid::Uuid::new_v4();
-        let rec = OutboxEventRow {
-            id: OutboxId(uuid::Uuid::new_v4()),
-            server_id: ServerId(uuid::Uuid::new_v4()),
-            topic: "presence.user_online_status_changed".to_string(),
-            payload_json: json!({
+        let rec = test_row(
+            "presence.user_online_status_changed",
+            json!({
                 "channel_id": channel_id,
                 "user_id": user_id,
                 "custom_status_text": "Legacy status"
             }),
-        };
+        );
 
         let (_ch, push) = translate_record(&rec).expect("should translate");
         match push.payload {
@@ -0,0 +1,48 @@
+use tokio::sync::mpsc;
+use vp_media::voice_forwarder::TalkerPreemption;
+
+use crate::outbox_dispatch::now_ts;
+use crate::proto::voiceplatform::v1 as pb;
+use crate::state::{MembershipCache, PushHub};
+
+/// Tell every member of a voice channel when one talker's slot was handed to
+/// another, so clients can show who got cut off. Runs until the forwarder
+/// drops its sender.
+pub async fn run_preemption_publisher(
+    mut rx: mpsc::Receiver<TalkerPreemption>,
+    membership: MembershipCache,
+    push: PushHub,
+) {
+    while let Some(event) = rx.recv().await {
+        let Some(members) = membership.members_of(event.channel) else {
+            continue;
+        };
+        let msg = preemption_push(&event);
+        for member in members {
+            push.send_to(member, msg.clone()).await;
+        }
+    }
+}
+
+fn preemption_push(event: &TalkerPreemption) -> pb::ServerToClient {
+    pb::ServerToClient {
+        request_id: None,
+        session_id: None,
+        sent_at: Some(now_ts()),
+        error: None,
+        event_seq: 0,
+        payload: Some(pb::server_to_client::Payload::VoiceTalkerPreemptedPush(
+            pb::VoiceTalkerPreemptedPush {
+                channel_id: Some(pb::ChannelId {
+                    value: event.channel.0.to_string(),
+                }),
+                preempted_user_id: Some(pb::UserId {
+                    value: event.preempted.0.to_string(),
+                }),
+                by_user_id: Some(pb::UserId {
+                    value: event.by.0.to_string(),
+                }),
+            },
+        )),
+    }
+}
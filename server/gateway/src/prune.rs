@@ -8,6 +8,7 @@ use tokio::time::MissedTickBehavior;
 use tracing::debug;
 use vp_media::datagram_send_policy::{now_ms, PruneReason, SessionSendCtx};
 use vp_media::stream_forwarder::StreamForwarder;
+use vp_metrics::metric_name;
 
 use crate::state::Sessions;
 
@@ -39,7 +40,7 @@ pub async fn run_pruner(
         }
 
         if processed > 0 {
-            counter!("vp_gateway_prune_processed_total").increment(processed as u64);
+            counter!(metric_name("gateway_prune_processed_total")).increment(processed as u64);
             tokio::task::yield_now().await;
             if sessions.has_pending() {
                 let _ = wake_tx.try_send(());
@@ -59,7 +60,8 @@ async fn process_one(
     let reason_u8 = ctx.prune.reason.load(Ordering::Relaxed);
     let reason = prune_reason_from_u8(reason_u8);
 
-    counter!("vp_gateway_prune_reason_total", "reason" => reason_label(reason)).increment(1);
+    counter!(metric_name("gateway_prune_reason_total"), "reason" => reason_label(reason))
+        .increment(1);
 
     match reason {
         PruneReason::Backpressure => {
@@ -100,7 +102,7 @@ async fn process_one(
         }
     }
 
-    gauge!("vp_gateway_prune_pending_count").set(sessions.pending_count() as f64);
+    gauge!(metric_name("gateway_prune_pending_count")).set(sessions.pending_count() as f64);
 }
 
 fn prune_reason_from_u8(v: u8) -> PruneReason {
@@ -0,0 +1,117 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use vp_metrics::ReadinessCheck;
+
+/// How long the outbox dispatcher may go without a successful claim before
+/// `/readyz` reports it stalled. Comfortably above its longest claim backoff.
+const OUTBOX_STALL_AFTER: Duration = Duration::from_secs(30);
+
+/// Gateway readiness, fed by the DB health monitor, the outbox dispatcher
+/// and shutdown, and served on `/readyz`.
+#[derive(Clone)]
+pub struct Readiness {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    started: Instant,
+    db_up: AtomicBool,
+    draining: AtomicBool,
+    /// Millis after `started` of the last successful outbox claim, plus one;
+    /// zero until the first claim.
+    last_claim: AtomicU64,
+}
+
+impl Readiness {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                started: Instant::now(),
+                db_up: AtomicBool::new(false),
+                draining: AtomicBool::new(false),
+                last_claim: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    pub fn set_db_up(&self, up: bool) {
+        self.inner.db_up.store(up, Ordering::Relaxed);
+    }
+
+    /// Shutdown has started; stays not-ready so load balancers stop sending
+    /// new clients while sessions drain.
+    pub fn set_draining(&self) {
+        self.inner.draining.store(true, Ordering::Relaxed);
+    }
+
+    /// A claim round-trip succeeded, whether or not it returned rows.
+    pub fn outbox_claimed(&self) {
+        let since_start = self.inner.started.elapsed().as_millis() as u64;
+        self.inner
+            .last_claim
+            .store(since_start + 1, Ordering::Relaxed);
+    }
+
+    pub fn check(&self) -> ReadinessCheck {
+        let this = self.clone();
+        Arc::new(move || this.check_at(Instant::now()))
+    }
+
+    fn check_at(&self, now: Instant) -> Result<(), &'static str> {
+        if self.inner.draining.load(Ordering::Relaxed) {
+            return Err("draining");
+        }
+        if !self.inner.db_up.load(Ordering::Relaxed) {
+            return Err("database unreachable");
+        }
+        let last_claim = match self.inner.last_claim.load(Ordering::Relaxed) {
+            0 => return Err("outbox dispatcher has not claimed yet"),
+            stamp => self.inner.started + Duration::from_millis(stamp - 1),
+        };
+        if now.saturating_duration_since(last_claim) > OUTBOX_STALL_AFTER {
+            return Err("outbox dispatcher stalled");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Readiness, OUTBOX_STALL_AFTER};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn ready_only_with_db_up_and_a_recent_claim() {
+        let readiness = Readiness::new();
+        let now = Instant::now();
+        assert_eq!(readiness.check_at(now), Err("database unreachable"));
+
+        readiness.set_db_up(true);
+        assert_eq!(
+            readiness.check_at(now),
+            Err("outbox dispatcher has not claimed yet")
+        );
+
+        readiness.outbox_claimed();
+        let now = Instant::now();
+        assert_eq!(readiness.check_at(now), Ok(()));
+        assert_eq!(
+            readiness.check_at(now + OUTBOX_STALL_AFTER + Duration::from_secs(1)),
+            Err("outbox dispatcher stalled")
+        );
+
+        readiness.set_db_up(false);
+        assert_eq!(readiness.check_at(now), Err("database unreachable"));
+    }
+
+    #[test]
+    fn draining_is_never_ready() {
+        let readiness = Readiness::new();
+        readiness.set_db_up(true);
+        readiness.outbox_claimed();
+        readiness.set_draining();
+        assert_eq!(readiness.check_at(Instant::now()), Err("draining"));
+    }
+}
@@ -3,16 +3,17 @@ use std::{
     sync::Arc,
 };
 
-use dashmap::DashMap;
+use dashmap::{mapref::entry::Entry, DashMap, DashSet};
 use tokio::sync::mpsc;
 use tokio::time::{Duration, Instant};
 
 use crate::proto::voiceplatform::v1 as pb;
 
 use vp_control::ids::{ChannelId, UserId};
+use vp_control::model::ChannelNotificationPref;
 use vp_media::datagram_send_policy::SessionSendCtx;
 use vp_media::stream_forwarder::ViewerProvider;
-use vp_media::voice_forwarder::{DatagramTx, MembershipProvider, SessionRegistry};
+use vp_media::voice_forwarder::{DatagramTx, MembershipProvider, SenderRoute, SessionRegistry};
 
 #[derive(Clone)]
 pub struct PushHub {
@@ -92,6 +93,141 @@ impl VoiceTelemetryCache {
     }
 }
 
+/// Per-user, per-channel throttle for typing notifications, so a client
+/// that sends one per keystroke still costs listeners one push per interval.
+#[derive(Clone)]
+pub struct TypingThrottle {
+    inner: Arc<DashMap<(UserId, ChannelId), Instant>>,
+}
+
+impl TypingThrottle {
+    pub const INTERVAL: Duration = Duration::from_secs(3);
+
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// Whether a typing notice from `user_id` in `channel_id` should go out
+    /// at `now`; records it if so.
+    pub fn allow(&self, user_id: UserId, channel_id: ChannelId, now: Instant) -> bool {
+        match self.inner.entry((user_id, channel_id)) {
+            Entry::Occupied(mut last) => {
+                if now.duration_since(*last.get()) < Self::INTERVAL {
+                    return false;
+                }
+                last.insert(now);
+            }
+            Entry::Vacant(slot) => {
+                slot.insert(now);
+            }
+        }
+        true
+    }
+
+    pub fn remove_user(&self, user_id: UserId) {
+        self.inner.retain(|(uid, _), _| *uid != user_id);
+    }
+}
+
+/// Per-sender, per-target throttle for pokes. Unlike typing, a throttled
+/// poke is refused so the sender learns when they may try again.
+#[derive(Clone)]
+pub struct PokeThrottle {
+    inner: Arc<DashMap<(UserId, UserId), Instant>>,
+}
+
+impl PokeThrottle {
+    pub const INTERVAL: Duration = Duration::from_secs(10);
+
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// Records a poke from `from` to `to` at `now`, or returns how long
+    /// `from` still has to wait.
+    pub fn allow(&self, from: UserId, to: UserId, now: Instant) -> Result<(), Duration> {
+        match self.inner.entry((from, to)) {
+            Entry::Occupied(mut last) => {
+                let elapsed = now.duration_since(*last.get());
+                if elapsed < Self::INTERVAL {
+                    return Err(Self::INTERVAL - elapsed);
+                }
+                last.insert(now);
+            }
+            Entry::Vacant(slot) => {
+                slot.insert(now);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn remove_user(&self, user_id: UserId) {
+        self.inner.retain(|(from, _), _| *from != user_id);
+    }
+}
+
+/// Sessions whose connection was lost, held for [`Self::TTL`] so a client
+/// back from a brief network blip can resume one instead of leaving and
+/// rejoining its channel. The chat channels a session was subscribed to are
+/// kept with it and handed back on resume.
+#[derive(Clone)]
+pub struct ParkedSessions {
+    parked: Arc<DashMap<String, (UserId, Instant, Vec<ChannelId>)>>,
+    superseded: Arc<DashSet<String>>,
+}
+
+impl ParkedSessions {
+    pub const TTL: Duration = Duration::from_secs(30);
+
+    pub fn new() -> Self {
+        Self {
+            parked: Arc::new(DashMap::new()),
+            superseded: Arc::new(DashSet::new()),
+        }
+    }
+
+    pub fn park(
+        &self,
+        session_id: &str,
+        user: UserId,
+        chat_channels: Vec<ChannelId>,
+        now: Instant,
+    ) {
+        self.parked
+            .insert(session_id.to_string(), (user, now, chat_channels));
+    }
+
+    /// Takes `session_id` over for `user` if it's parked under that user and
+    /// hasn't outlived [`Self::TTL`], returning its chat subscriptions.
+    pub fn claim(&self, session_id: &str, user: UserId, now: Instant) -> Option<Vec<ChannelId>> {
+        self.parked
+            .remove_if(session_id, |_, (owner, parked_at, _)| {
+                *owner == user && now.duration_since(*parked_at) < Self::TTL
+            })
+            .map(|(_, (_, _, chat_channels))| chat_channels)
+    }
+
+    /// Forgets `session_id` once its TTL is up; true if nobody resumed it and
+    /// its disconnect cleanup still has to run.
+    pub fn expire(&self, session_id: &str) -> bool {
+        self.parked.remove(session_id).is_some()
+    }
+
+    /// Marks a session whose old connection is still open as taken over by
+    /// a resume, so tearing that connection down leaves membership alone.
+    pub fn supersede(&self, session_id: &str) {
+        self.superseded.insert(session_id.to_string());
+    }
+
+    pub fn take_superseded(&self, session_id: &str) -> bool {
+        self.superseded.remove(session_id).is_some()
+    }
+}
+
 pub fn channel_route_key(channel_id: ChannelId) -> u32 {
     vp_route_hash::channel_route_hash(channel_id.0)
 }
@@ -100,6 +236,8 @@ pub fn channel_route_key(channel_id: ChannelId) -> u32 {
 pub struct SessionMap {
     inner: Arc<DashMap<(UserId, String), Arc<SessionSendCtx>>>,
     user_index: Arc<DashMap<UserId, HashSet<String>>>,
+    /// What each session's client negotiated in its Hello.
+    client_caps: Arc<DashMap<String, Arc<pb::ClientCaps>>>,
 }
 
 impl SessionMap {
@@ -107,6 +245,7 @@ impl SessionMap {
         Self {
             inner: Arc::new(DashMap::new()),
             user_index: Arc::new(DashMap::new()),
+            client_caps: Arc::new(DashMap::new()),
         }
     }
 
@@ -119,6 +258,7 @@ impl SessionMap {
     pub fn unregister(&self, user: UserId, session_id: &str) {
         self.inner.remove(&(user, session_id.to_string()));
         self.remove_from_user_index(user, session_id);
+        self.client_caps.remove(session_id);
     }
 
     pub fn unregister_by_session_id(&self, session_id: &str) {
@@ -132,6 +272,20 @@ impl SessionMap {
             self.inner.remove(&key);
             self.remove_from_user_index(key.0, &key.1);
         }
+        self.client_caps.remove(session_id);
+    }
+
+    /// Keep the caps `session_id`'s client sent in its Hello until the
+    /// session is unregistered.
+    pub fn set_client_caps(&self, session_id: &str, caps: pb::ClientCaps) {
+        self.client_caps
+            .insert(session_id.to_string(), Arc::new(caps));
+    }
+
+    pub fn client_caps(&self, session_id: &str) -> Option<Arc<pb::ClientCaps>> {
+        self.client_caps
+            .get(session_id)
+            .map(|caps| caps.value().clone())
     }
 
     fn remove_from_user_index(&self, user: UserId, session_id: &str) {
@@ -144,6 +298,53 @@ impl SessionMap {
         }
     }
 
+    /// Close every connection `user` has on this gateway. Each connection's
+    /// own task notices the close and runs the usual disconnect cleanup.
+    pub fn close_user_sessions(&self, user: UserId, code: u32, reason: &[u8]) -> usize {
+        let Some(session_ids) = self
+            .user_index
+            .get(&user)
+            .map(|sessions| sessions.iter().cloned().collect::<Vec<_>>())
+        else {
+            return 0;
+        };
+
+        let mut closed = 0;
+        for session_id in session_ids {
+            if let Some(entry) = self.inner.get(&(user, session_id)) {
+                entry
+                    .value()
+                    .conn
+                    .close(quinn::VarInt::from_u32(code), reason);
+                closed += 1;
+            }
+        }
+        closed
+    }
+
+    /// Close one connection of `user`; false if it isn't registered.
+    pub fn close_session(&self, user: UserId, session_id: &str, code: u32, reason: &[u8]) -> bool {
+        let Some(entry) = self.inner.get(&(user, session_id.to_string())) else {
+            return false;
+        };
+        entry
+            .value()
+            .conn
+            .close(quinn::VarInt::from_u32(code), reason);
+        true
+    }
+
+    /// Every registered session, oldest connection first.
+    pub fn all_sessions(&self) -> Vec<Arc<SessionSendCtx>> {
+        let mut sessions = self
+            .inner
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect::<Vec<_>>();
+        sessions.sort_by_key(|ctx| ctx.connected_at);
+        sessions
+    }
+
     pub fn has_user_sessions(&self, user: UserId) -> bool {
         self.user_index
             .get(&user)
@@ -216,12 +417,17 @@ struct UserPresence {
     route: u32,
     muted: bool,
     deafened: bool,
+    /// Client-reported; informational only, never enforced on the voice path.
+    self_muted: bool,
+    self_deafened: bool,
 }
 
 #[derive(Clone, Debug)]
 struct ChannelRuntime {
     max_talkers: usize,
     members: Vec<UserId>,
+    /// Last roster write; eviction picks the stalest unoccupied entries first.
+    last_active: Instant,
 }
 
 #[derive(Clone)]
@@ -229,6 +435,18 @@ pub struct MembershipCache {
     users: Arc<DashMap<UserId, UserPresence>>,
     channels: Arc<DashMap<ChannelId, ChannelRuntime>>,
     media_caps: Arc<DashMap<UserId, pb::ClientMediaCapabilities>>,
+    /// Users banned from the server this gateway serves, with the expiry of
+    /// temporary bans; refreshed from moderation outbox events so the voice
+    /// fast path never hits the DB.
+    banned: Arc<DashMap<UserId, Option<Instant>>>,
+    /// Non-default chat push preferences, keyed by channel for dispatcher fanout.
+    notification_prefs: Arc<DashMap<ChannelId, HashMap<UserId, ChannelNotificationPref>>>,
+    /// Joined channels that carry no voice (text, category).
+    text_channels: Arc<DashSet<ChannelId>>,
+    /// Chat-only subscriptions, separate from voice membership: the sessions
+    /// of each user that asked for a channel's chat pushes without joining it.
+    chat_subscriptions: Arc<DashMap<ChannelId, HashMap<UserId, HashSet<String>>>>,
+    voice_requires_join: bool,
 }
 
 impl MembershipCache {
@@ -237,6 +455,31 @@ impl MembershipCache {
             users: Arc::new(DashMap::new()),
             channels: Arc::new(DashMap::new()),
             media_caps: Arc::new(DashMap::new()),
+            banned: Arc::new(DashMap::new()),
+            notification_prefs: Arc::new(DashMap::new()),
+            text_channels: Arc::new(DashSet::new()),
+            chat_subscriptions: Arc::new(DashMap::new()),
+            voice_requires_join: true,
+        }
+    }
+
+    /// When disabled, a sender in a text channel is forwarded like any other
+    /// member as long as the route hash matches.
+    pub fn with_voice_requires_join(mut self, enabled: bool) -> Self {
+        self.voice_requires_join = enabled;
+        self
+    }
+
+    /// Record whether `channel` carries voice; unknown channels are assumed to.
+    pub fn set_channel_kind(&self, channel: ChannelId, channel_type: i32) {
+        let voice = !matches!(
+            pb::ChannelType::try_from(channel_type),
+            Ok(pb::ChannelType::Text | pb::ChannelType::Category)
+        );
+        if voice {
+            self.text_channels.remove(&channel);
+        } else {
+            self.text_channels.insert(channel);
         }
     }
 
@@ -246,6 +489,7 @@ impl MembershipCache {
             ChannelRuntime {
                 max_talkers,
                 members,
+                last_active: Instant::now(),
             },
         );
     }
@@ -254,7 +498,16 @@ impl MembershipCache {
         self.set_channel(channel, max_talkers, members);
     }
 
+    /// Joins refresh every member's presence, so self flags are kept while
+    /// the user stays in the same channel. This is the only call that moves a
+    /// user between channels.
     pub fn set_user(&self, user: UserId, channel: ChannelId, muted: bool, deafened: bool) {
+        let (self_muted, self_deafened) = self
+            .users
+            .get(&user)
+            .filter(|entry| entry.channel == channel)
+            .map(|entry| (entry.self_muted, entry.self_deafened))
+            .unwrap_or((false, false));
         self.users.insert(
             user,
             UserPresence {
@@ -262,13 +515,35 @@ impl MembershipCache {
                 route: channel_route_key(channel),
                 muted,
                 deafened,
+                self_muted,
+                self_deafened,
             },
         );
     }
 
+    /// Drop a user's presence, media capabilities and every cached channel
+    /// roster entry. This is the single cleanup for a disconnect, so it does
+    /// not depend on knowing which channels the control plane had them in.
     pub fn remove_user(&self, user: UserId) {
         self.users.remove(&user);
         self.media_caps.remove(&user);
+        for mut runtime in self.channels.iter_mut() {
+            runtime.members.retain(|member| *member != user);
+        }
+    }
+
+    /// Remove `user` from `channel`'s roster, and clear their presence only if
+    /// it still points at `channel`; a leave that lands after the user already
+    /// joined elsewhere must not drop them from the new channel.
+    pub fn leave_channel(&self, user: UserId, channel: ChannelId) {
+        self.remove_channel_member(channel, user);
+        if self
+            .users
+            .remove_if(&user, |_, entry| entry.channel == channel)
+            .is_some()
+        {
+            self.media_caps.remove(&user);
+        }
     }
 
     pub fn add_channel_member(&self, channel: ChannelId, user: UserId) {
@@ -276,15 +551,72 @@ impl MembershipCache {
             if !runtime.members.contains(&user) {
                 runtime.members.push(user);
             }
+            runtime.last_active = Instant::now();
         }
     }
 
     pub fn remove_channel_member(&self, channel: ChannelId, user: UserId) {
         if let Some(mut runtime) = self.channels.get_mut(&channel) {
             runtime.members.retain(|member| *member != user);
+            runtime.last_active = Instant::now();
         }
     }
 
+    pub fn cached_channel_count(&self) -> usize {
+        self.channels.len()
+    }
+
+    /// Drop cached channels nobody is present in: every one idle for at least
+    /// `idle`, plus the stalest others while more than `max_channels` entries
+    /// remain (0 = no cap). Occupied channels are never evicted, and an evicted
+    /// channel is rebuilt from the control plane on the next join. Returns the
+    /// number of entries removed.
+    pub fn evict_idle_channels(&self, max_channels: usize, idle: Duration, now: Instant) -> usize {
+        let occupied: HashSet<ChannelId> = self.users.iter().map(|entry| entry.channel).collect();
+        let mut candidates: Vec<(Instant, ChannelId)> = self
+            .channels
+            .iter()
+            .filter(|entry| !occupied.contains(entry.key()))
+            .map(|entry| (entry.last_active, *entry.key()))
+            .collect();
+        candidates.sort_unstable_by_key(|(last_active, _)| *last_active);
+
+        let mut over_cap = match max_channels {
+            0 => 0,
+            max => self.channels.len().saturating_sub(max),
+        };
+        let mut evicted = 0;
+        for (last_active, channel) in candidates {
+            if over_cap == 0 && now.saturating_duration_since(last_active) < idle {
+                break;
+            }
+            // A join since the scan refreshes `last_active`; leave that entry be.
+            if self
+                .channels
+                .remove_if(&channel, |_, runtime| runtime.last_active == last_active)
+                .is_some()
+            {
+                self.text_channels.remove(&channel);
+                over_cap = over_cap.saturating_sub(1);
+                evicted += 1;
+            }
+        }
+        evicted
+    }
+
+    pub fn set_banned(&self, user: UserId, banned: bool) {
+        if banned {
+            self.banned.insert(user, None);
+        } else {
+            self.banned.remove(&user);
+        }
+    }
+
+    /// Ban `user` until `duration` from now, replacing any earlier ban.
+    pub fn ban_for(&self, user: UserId, duration: Duration) {
+        self.banned.insert(user, Some(Instant::now() + duration));
+    }
+
     pub fn set_media_capabilities(&self, user: UserId, caps: pb::ClientMediaCapabilities) {
         self.media_caps.insert(user, caps);
     }
@@ -330,6 +662,8 @@ impl MembershipCache {
             .collect()
     }
 
+    /// Server mute/deafen for `user` in `channel`. Ignored when the user is
+    /// no longer there, so a late event can't pull them back into it.
     pub fn update_voice_state(
         &self,
         user: UserId,
@@ -337,56 +671,201 @@ impl MembershipCache {
         muted: bool,
         deafened: bool,
     ) {
-        self.users.insert(
-            user,
-            UserPresence {
-                channel,
-                route: channel_route_key(channel),
-                muted,
-                deafened,
-            },
-        );
+        if let Some(mut entry) = self.users.get_mut(&user) {
+            if entry.channel == channel {
+                entry.muted = muted;
+                entry.deafened = deafened;
+            }
+        }
     }
 
-    pub fn update_mute(&self, user: UserId, channel: ChannelId, muted: bool) {
-        let deafened = self
-            .users
+    /// Record the client's own mute/deafen flags. Returns the server-side
+    /// `(muted, deafened)` flags when `user` is present in `channel`, or
+    /// `None` (and records nothing) when they are not.
+    pub fn set_self_voice_state(
+        &self,
+        user: UserId,
+        channel: ChannelId,
+        self_muted: bool,
+        self_deafened: bool,
+    ) -> Option<(bool, bool)> {
+        let mut entry = self.users.get_mut(&user)?;
+        if entry.channel != channel {
+            return None;
+        }
+        entry.self_muted = self_muted;
+        entry.self_deafened = self_deafened;
+        Some((entry.muted, entry.deafened))
+    }
+
+    /// `(self_muted, self_deafened)` for `user` in `channel`; unknown users
+    /// read as neither.
+    pub fn self_voice_state(&self, user: UserId, channel: ChannelId) -> (bool, bool) {
+        self.users
             .get(&user)
-            .map(|entry| entry.deafened)
-            .unwrap_or(false);
-        self.update_voice_state(user, channel, muted, deafened);
+            .filter(|entry| entry.channel == channel)
+            .map(|entry| (entry.self_muted, entry.self_deafened))
+            .unwrap_or((false, false))
+    }
+
+    pub fn update_mute(&self, user: UserId, channel: ChannelId, muted: bool) {
+        if let Some(mut entry) = self.users.get_mut(&user) {
+            if entry.channel == channel {
+                entry.muted = muted;
+            }
+        }
     }
 
     pub fn update_deafen(&self, user: UserId, channel: ChannelId, deafened: bool) {
-        let muted = self
-            .users
-            .get(&user)
-            .map(|entry| entry.muted)
-            .unwrap_or(false);
-        self.update_voice_state(user, channel, muted, deafened);
+        if let Some(mut entry) = self.users.get_mut(&user) {
+            if entry.channel == channel {
+                entry.deafened = deafened;
+            }
+        }
+    }
+
+    pub fn channel_of(&self, user: UserId) -> Option<ChannelId> {
+        self.users.get(&user).map(|entry| entry.channel)
     }
 
     pub fn members_of(&self, channel: ChannelId) -> Option<Vec<UserId>> {
         self.channels.get(&channel).map(|e| e.members.clone())
     }
 
-    pub fn max_talkers_of(&self, channel: ChannelId) -> Option<usize> {
-        self.channels.get(&channel).map(|e| e.max_talkers)
+    pub fn set_notification_pref(
+        &self,
+        user: UserId,
+        channel: ChannelId,
+        pref: ChannelNotificationPref,
+    ) {
+        if pref == ChannelNotificationPref::Default {
+            if let Some(mut prefs) = self.notification_prefs.get_mut(&channel) {
+                prefs.remove(&user);
+            }
+            self.notification_prefs
+                .remove_if(&channel, |_, prefs| prefs.is_empty());
+        } else {
+            self.notification_prefs
+                .entry(channel)
+                .or_default()
+                .insert(user, pref);
+        }
+    }
+
+    #[cfg(test)]
+    pub fn notification_pref(&self, user: UserId, channel: ChannelId) -> ChannelNotificationPref {
+        self.notification_prefs
+            .get(&channel)
+            .and_then(|prefs| prefs.get(&user).copied())
+            .unwrap_or(ChannelNotificationPref::Default)
+    }
+
+    /// Start pushing `channel`'s chat to `session_id`'s user; idempotent.
+    pub fn subscribe_chat(&self, user: UserId, session_id: &str, channel: ChannelId) {
+        self.chat_subscriptions
+            .entry(channel)
+            .or_default()
+            .entry(user)
+            .or_default()
+            .insert(session_id.to_string());
+    }
+
+    pub fn unsubscribe_chat(&self, user: UserId, session_id: &str, channel: ChannelId) {
+        if let Some(mut subs) = self.chat_subscriptions.get_mut(&channel) {
+            if let Some(sessions) = subs.get_mut(&user) {
+                sessions.remove(session_id);
+                if sessions.is_empty() {
+                    subs.remove(&user);
+                }
+            }
+        }
+        self.chat_subscriptions
+            .remove_if(&channel, |_, subs| subs.is_empty());
+    }
+
+    /// Drop every chat subscription held by a session that is going away,
+    /// returning the channels it was subscribed to.
+    pub fn drop_session_chat_subscriptions(
+        &self,
+        user: UserId,
+        session_id: &str,
+    ) -> Vec<ChannelId> {
+        let mut dropped = Vec::new();
+        for mut subs in self.chat_subscriptions.iter_mut() {
+            let channel = *subs.key();
+            if let Some(sessions) = subs.get_mut(&user) {
+                if sessions.remove(session_id) {
+                    dropped.push(channel);
+                }
+                if sessions.is_empty() {
+                    subs.remove(&user);
+                }
+            }
+        }
+        self.chat_subscriptions.retain(|_, subs| !subs.is_empty());
+        dropped
+    }
+
+    /// Hand `from`'s chat subscriptions to `to`, a session resuming it.
+    pub fn move_session_chat_subscriptions(&self, user: UserId, from: &str, to: &str) {
+        for mut subs in self.chat_subscriptions.iter_mut() {
+            if let Some(sessions) = subs.get_mut(&user) {
+                if sessions.remove(from) {
+                    sessions.insert(to.to_string());
+                }
+            }
+        }
+    }
+
+    pub fn drop_channel_chat_subscriptions(&self, channel: ChannelId) {
+        self.chat_subscriptions.remove(&channel);
+    }
+
+    #[cfg(test)]
+    pub fn is_chat_subscribed(&self, user: UserId, channel: ChannelId) -> bool {
+        self.chat_subscriptions
+            .get(&channel)
+            .is_some_and(|subs| subs.contains_key(&user))
+    }
+
+    /// Users that should receive chat pushes for `channel`: joined members that
+    /// haven't muted it, plus anyone subscribed without joining. A live chat
+    /// subscription wins over a mute, since the client is showing the channel.
+    pub fn chat_push_recipients(&self, channel: ChannelId) -> Vec<UserId> {
+        let mut recipients = self.members_of(channel).unwrap_or_default();
+        if let Some(prefs) = self.notification_prefs.get(&channel) {
+            recipients.retain(|u| prefs.get(u) != Some(&ChannelNotificationPref::Muted));
+            for (user, pref) in prefs.iter() {
+                if *pref == ChannelNotificationPref::Subscribed && !recipients.contains(user) {
+                    recipients.push(*user);
+                }
+            }
+        }
+        if let Some(subs) = self.chat_subscriptions.get(&channel) {
+            for user in subs.keys() {
+                if !recipients.contains(user) {
+                    recipients.push(*user);
+                }
+            }
+        }
+        recipients
     }
 }
 
 #[async_trait::async_trait]
 impl MembershipProvider for MembershipCache {
-    async fn resolve_channel_for_sender(
-        &self,
-        sender: UserId,
-        route_key: u32,
-    ) -> Option<ChannelId> {
-        let u = self.users.get(&sender)?;
-        if u.route == route_key {
-            Some(u.channel)
+    async fn resolve_channel_for_sender(&self, sender: UserId, route_key: u32) -> SenderRoute {
+        // Route and channel come from the same entry guard, so a concurrent
+        // join elsewhere is seen entirely or not at all.
+        let Some(u) = self.users.get(&sender) else {
+            return SenderRoute::NotMember;
+        };
+        if self.voice_requires_join && self.text_channels.contains(&u.channel) {
+            SenderRoute::NotInVoice
+        } else if u.route == route_key {
+            SenderRoute::Channel(u.channel)
         } else {
-            None
+            SenderRoute::NotMember
         }
     }
 
@@ -405,6 +884,13 @@ impl MembershipProvider for MembershipCache {
         self.users.get(&user).map(|e| e.deafened).unwrap_or(false)
     }
 
+    async fn is_banned(&self, _channel: ChannelId, user: UserId) -> bool {
+        let now = Instant::now();
+        self.banned
+            .remove_if(&user, |_, expires| expires.is_some_and(|at| at <= now));
+        self.banned.contains_key(&user)
+    }
+
     async fn max_talkers(&self, channel: ChannelId) -> usize {
         self.channels
             .get(&channel)
@@ -431,10 +917,13 @@ impl ViewerProvider for MembershipCache {
 
 #[cfg(test)]
 mod tests {
-    use super::{MembershipCache, PushHub, ShareMetadata, StreamSessionOwnership, StreamSessionRegistry};
+    use super::{
+        MembershipCache, ParkedSessions, PokeThrottle, PushHub, ShareMetadata,
+        StreamSessionOwnership, StreamSessionRegistry, TypingThrottle,
+    };
     use crate::proto::voiceplatform::v1 as pb;
     use tokio::sync::mpsc;
-    use tokio::time::Instant;
+    use tokio::time::{Duration, Instant};
     use vp_control::ids::{ChannelId, UserId};
 
     fn test_metadata() -> ShareMetadata {
@@ -469,6 +958,151 @@ mod tests {
         hub.unregister(user, "s2");
     }
 
+    #[test]
+    fn remove_user_clears_presence_and_rosters() {
+        let membership = MembershipCache::new();
+        let ch = ChannelId(uuid::Uuid::new_v4());
+        let user = UserId(uuid::Uuid::new_v4());
+        let other = UserId(uuid::Uuid::new_v4());
+        membership.set_channel_state(ch, 4, vec![user, other]);
+        membership.set_user(user, ch, false, false);
+
+        membership.remove_user(user);
+
+        assert_eq!(membership.members_of(ch), Some(vec![other]));
+    }
+
+    #[test]
+    fn leave_channel_drops_member_from_roster() {
+        let membership = MembershipCache::new();
+        let ch = ChannelId(uuid::Uuid::new_v4());
+        let alice = UserId(uuid::Uuid::new_v4());
+        let bob = UserId(uuid::Uuid::new_v4());
+        membership.set_channel(ch, 4, vec![]);
+        for user in [alice, bob] {
+            membership.set_user(user, ch, false, false);
+            membership.add_channel_member(ch, user);
+        }
+
+        membership.leave_channel(alice, ch);
+
+        let members = membership
+            .members_of(ch)
+            .expect("channel should exist in cache");
+        assert!(!members.contains(&alice));
+        assert_eq!(members, vec![bob]);
+        assert_eq!(membership.channel_of(alice), None);
+    }
+
+    #[test]
+    fn typing_throttle_allows_one_notice_per_interval_per_channel() {
+        let typing = TypingThrottle::new();
+        let user = UserId(uuid::Uuid::new_v4());
+        let ch = ChannelId(uuid::Uuid::new_v4());
+        let other_ch = ChannelId(uuid::Uuid::new_v4());
+        let t0 = Instant::now();
+
+        assert!(typing.allow(user, ch, t0));
+        assert!(!typing.allow(user, ch, t0));
+        assert!(!typing.allow(user, ch, t0 + Duration::from_millis(2_900)));
+        assert!(typing.allow(user, other_ch, t0 + Duration::from_millis(2_900)));
+        assert!(typing.allow(user, ch, t0 + TypingThrottle::INTERVAL));
+
+        typing.remove_user(user);
+        assert!(typing.allow(user, ch, t0 + TypingThrottle::INTERVAL));
+    }
+
+    #[test]
+    fn parked_session_is_claimed_once_by_its_owner_within_ttl() {
+        let parked = ParkedSessions::new();
+        let owner = UserId(uuid::Uuid::new_v4());
+        let other = UserId(uuid::Uuid::new_v4());
+        let chat = ChannelId(uuid::Uuid::new_v4());
+        let t0 = Instant::now();
+
+        parked.park("s1", owner, vec![chat], t0);
+        assert!(parked.claim("s1", other, t0).is_none());
+        assert!(parked
+            .claim("s1", owner, t0 + ParkedSessions::TTL)
+            .is_none());
+        assert_eq!(
+            parked.claim("s1", owner, t0 + Duration::from_secs(5)),
+            Some(vec![chat])
+        );
+        assert!(parked
+            .claim("s1", owner, t0 + Duration::from_secs(5))
+            .is_none());
+        // Resumed, so the TTL task must not run disconnect cleanup.
+        assert!(!parked.expire("s1"));
+
+        parked.park("s2", owner, Vec::new(), t0);
+        assert!(parked.expire("s2"));
+        assert!(parked.claim("s2", owner, t0).is_none());
+
+        parked.supersede("s3");
+        assert!(parked.take_superseded("s3"));
+        assert!(!parked.take_superseded("s3"));
+    }
+
+    #[test]
+    fn poke_throttle_limits_each_sender_target_pair() {
+        let pokes = PokeThrottle::new();
+        let from = UserId(uuid::Uuid::new_v4());
+        let to = UserId(uuid::Uuid::new_v4());
+        let other = UserId(uuid::Uuid::new_v4());
+        let t0 = Instant::now();
+
+        assert_eq!(pokes.allow(from, to, t0), Ok(()));
+        assert_eq!(
+            pokes.allow(from, to, t0 + Duration::from_secs(4)),
+            Err(Duration::from_secs(6))
+        );
+        assert_eq!(pokes.allow(from, other, t0), Ok(()));
+        assert_eq!(pokes.allow(other, to, t0), Ok(()));
+        assert_eq!(pokes.allow(from, to, t0 + PokeThrottle::INTERVAL), Ok(()));
+
+        pokes.remove_user(from);
+        assert_eq!(pokes.allow(from, to, t0 + PokeThrottle::INTERVAL), Ok(()));
+    }
+
+    #[test]
+    fn eviction_drops_only_unoccupied_channels() {
+        let membership = MembershipCache::new();
+        let occupied = ChannelId(uuid::Uuid::new_v4());
+        let stale = ChannelId(uuid::Uuid::new_v4());
+        let fresh = ChannelId(uuid::Uuid::new_v4());
+        let user = UserId(uuid::Uuid::new_v4());
+        membership.set_channel(occupied, 4, vec![user]);
+        membership.set_user(user, occupied, false, false);
+        membership.set_channel(stale, 4, vec![]);
+        membership.set_channel_kind(stale, pb::ChannelType::Text as i32);
+        std::thread::sleep(Duration::from_millis(5));
+        membership.set_channel(fresh, 4, vec![]);
+
+        let now = Instant::now() + Duration::from_secs(60);
+        assert_eq!(
+            membership.evict_idle_channels(0, Duration::from_secs(600), now),
+            0
+        );
+
+        // Over the cap, the stalest unoccupied entry goes first.
+        assert_eq!(
+            membership.evict_idle_channels(2, Duration::from_secs(600), now),
+            1
+        );
+        assert!(membership.members_of(stale).is_none());
+        assert!(membership.members_of(fresh).is_some());
+        assert!(!membership.text_channels.contains(&stale));
+
+        // Once idle long enough, the rest of the unoccupied entries go too.
+        assert_eq!(
+            membership.evict_idle_channels(0, Duration::from_secs(30), now),
+            1
+        );
+        assert_eq!(membership.cached_channel_count(), 1);
+        assert_eq!(membership.members_of(occupied), Some(vec![user]));
+    }
+
     #[test]
     fn membership_cache_tracks_media_caps() {
         let membership = MembershipCache::new();
@@ -517,6 +1151,247 @@ mod tests {
         assert!(members.is_empty());
     }
 
+    #[test]
+    fn self_voice_state_survives_roster_updates_but_not_leave() {
+        let membership = MembershipCache::new();
+        let channel = ChannelId(uuid::Uuid::new_v4());
+        let other = ChannelId(uuid::Uuid::new_v4());
+        let user = UserId(uuid::Uuid::new_v4());
+
+        assert_eq!(
+            membership.set_self_voice_state(user, channel, true, false),
+            None
+        );
+
+        membership.set_user(user, channel, false, false);
+        assert_eq!(
+            membership.set_self_voice_state(user, other, true, true),
+            None
+        );
+        assert_eq!(
+            membership.set_self_voice_state(user, channel, true, false),
+            Some((false, false))
+        );
+
+        membership.update_deafen(user, channel, true);
+        assert_eq!(membership.self_voice_state(user, channel), (true, false));
+        assert_eq!(
+            membership.set_self_voice_state(user, channel, true, true),
+            Some((false, true))
+        );
+
+        // Another member joining refreshes everyone's presence.
+        membership.set_user(user, channel, false, true);
+        assert_eq!(membership.self_voice_state(user, channel), (true, true));
+
+        // Leaving starts clean; the client re-sends its flags after rejoin.
+        membership.remove_user(user);
+        membership.set_user(user, channel, false, false);
+        assert_eq!(membership.self_voice_state(user, channel), (false, false));
+    }
+
+    #[tokio::test]
+    async fn late_events_for_the_previous_channel_do_not_move_presence_back() {
+        use super::channel_route_key;
+        use vp_media::voice_forwarder::{MembershipProvider, SenderRoute};
+
+        let membership = MembershipCache::new();
+        let old = ChannelId(uuid::Uuid::new_v4());
+        let new = ChannelId(uuid::Uuid::new_v4());
+        let user = UserId(uuid::Uuid::new_v4());
+
+        membership.set_user(user, old, false, false);
+        membership.set_user(user, new, false, false);
+
+        // Moderation and leave events for the old channel arriving after the switch.
+        membership.update_mute(user, old, true);
+        membership.update_voice_state(user, old, true, true);
+        membership.leave_channel(user, old);
+
+        assert_eq!(
+            membership
+                .resolve_channel_for_sender(user, channel_route_key(new))
+                .await,
+            SenderRoute::Channel(new)
+        );
+        assert_eq!(
+            membership
+                .resolve_channel_for_sender(user, channel_route_key(old))
+                .await,
+            SenderRoute::NotMember
+        );
+        assert!(!membership.is_muted(new, user).await);
+
+        membership.leave_channel(user, new);
+        assert_eq!(
+            membership
+                .resolve_channel_for_sender(user, channel_route_key(new))
+                .await,
+            SenderRoute::NotMember
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn route_and_channel_resolve_together_during_rapid_switches() {
+        use super::channel_route_key;
+        use vp_media::voice_forwarder::{MembershipProvider, SenderRoute};
+
+        let membership = MembershipCache::new();
+        let a = ChannelId(uuid::Uuid::new_v4());
+        let b = ChannelId(uuid::Uuid::new_v4());
+        let user = UserId(uuid::Uuid::new_v4());
+        membership.set_user(user, a, false, false);
+
+        let switcher = {
+            let membership = membership.clone();
+            tokio::spawn(async move {
+                for i in 0..10_000 {
+                    let ch = if i % 2 == 0 { b } else { a };
+                    membership.set_user(user, ch, false, false);
+                }
+            })
+        };
+        for _ in 0..10_000 {
+            match membership
+                .resolve_channel_for_sender(user, channel_route_key(a))
+                .await
+            {
+                SenderRoute::Channel(ch) => assert_eq!(ch, a),
+                SenderRoute::NotMember => {}
+                SenderRoute::NotInVoice => panic!("no text channel involved"),
+            }
+        }
+        switcher.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn text_channel_presence_blocks_voice_unless_gate_disabled() {
+        use super::channel_route_key;
+        use vp_media::voice_forwarder::{MembershipProvider, SenderRoute};
+
+        let text = ChannelId(uuid::Uuid::new_v4());
+        let voice = ChannelId(uuid::Uuid::new_v4());
+        let user = UserId(uuid::Uuid::new_v4());
+
+        let membership = MembershipCache::new();
+        membership.set_channel_kind(text, pb::ChannelType::Text as i32);
+        membership.set_channel_kind(voice, pb::ChannelType::Voice as i32);
+        membership.set_user(user, text, false, false);
+        assert_eq!(
+            membership
+                .resolve_channel_for_sender(user, channel_route_key(text))
+                .await,
+            SenderRoute::NotInVoice
+        );
+
+        membership.set_user(user, voice, false, false);
+        assert_eq!(
+            membership
+                .resolve_channel_for_sender(user, channel_route_key(voice))
+                .await,
+            SenderRoute::Channel(voice)
+        );
+
+        let lenient = MembershipCache::new().with_voice_requires_join(false);
+        lenient.set_channel_kind(text, pb::ChannelType::Text as i32);
+        lenient.set_user(user, text, false, false);
+        assert_eq!(
+            lenient
+                .resolve_channel_for_sender(user, channel_route_key(text))
+                .await,
+            SenderRoute::Channel(text)
+        );
+    }
+
+    #[tokio::test]
+    async fn membership_cache_ban_survives_presence_removal() {
+        use vp_media::voice_forwarder::MembershipProvider;
+
+        let membership = MembershipCache::new();
+        let channel = ChannelId(uuid::Uuid::new_v4());
+        let user = UserId(uuid::Uuid::new_v4());
+
+        membership.set_user(user, channel, false, false);
+        membership.set_banned(user, true);
+        membership.remove_user(user);
+        assert!(membership.is_banned(channel, user).await);
+
+        membership.set_banned(user, false);
+        assert!(!membership.is_banned(channel, user).await);
+
+        membership.ban_for(user, Duration::from_secs(60));
+        assert!(membership.is_banned(channel, user).await);
+        membership.ban_for(user, Duration::ZERO);
+        assert!(!membership.is_banned(channel, user).await);
+    }
+
+    #[test]
+    fn chat_push_recipients_honor_mute_and_subscribe() {
+        use vp_control::model::ChannelNotificationPref;
+
+        let membership = MembershipCache::new();
+        let channel = ChannelId(uuid::Uuid::new_v4());
+        let joined = UserId(uuid::Uuid::new_v4());
+        let muted = UserId(uuid::Uuid::new_v4());
+        let subscriber = UserId(uuid::Uuid::new_v4());
+
+        membership.set_channel(channel, 4, vec![joined, muted]);
+        membership.set_notification_pref(muted, channel, ChannelNotificationPref::Muted);
+        membership.set_notification_pref(subscriber, channel, ChannelNotificationPref::Subscribed);
+
+        let mut recipients = membership.chat_push_recipients(channel);
+        recipients.sort_by_key(|u| u.0);
+        let mut expected = vec![joined, subscriber];
+        expected.sort_by_key(|u| u.0);
+        assert_eq!(recipients, expected);
+
+        membership.set_notification_pref(muted, channel, ChannelNotificationPref::Default);
+        assert_eq!(
+            membership.notification_pref(muted, channel),
+            ChannelNotificationPref::Default
+        );
+        assert!(membership.chat_push_recipients(channel).contains(&muted));
+    }
+
+    #[test]
+    fn chat_subscriptions_fan_out_per_session_without_voice_membership() {
+        use vp_control::model::ChannelNotificationPref;
+
+        let membership = MembershipCache::new();
+        let voice = ChannelId(uuid::Uuid::new_v4());
+        let text = ChannelId(uuid::Uuid::new_v4());
+        let user = UserId(uuid::Uuid::new_v4());
+
+        membership.set_channel(voice, 4, vec![user]);
+        membership.set_user(user, voice, false, false);
+        membership.set_notification_pref(user, text, ChannelNotificationPref::Muted);
+        membership.subscribe_chat(user, "s1", text);
+        membership.subscribe_chat(user, "s2", text);
+        membership.subscribe_chat(user, "s2", text);
+
+        assert_eq!(membership.chat_push_recipients(text), vec![user]);
+        assert_eq!(membership.channel_of(user), Some(voice));
+
+        membership.unsubscribe_chat(user, "s1", text);
+        assert!(membership.is_chat_subscribed(user, text));
+        // A resuming session takes the subscriptions over.
+        membership.move_session_chat_subscriptions(user, "s2", "s3");
+        assert!(membership
+            .drop_session_chat_subscriptions(user, "s2")
+            .is_empty());
+        assert!(membership.is_chat_subscribed(user, text));
+        assert_eq!(
+            membership.drop_session_chat_subscriptions(user, "s3"),
+            vec![text]
+        );
+        assert!(!membership.is_chat_subscribed(user, text));
+        assert!(membership.chat_push_recipients(text).is_empty());
+
+        membership.subscribe_chat(user, "s1", text);
+        membership.drop_channel_chat_subscriptions(text);
+        assert!(membership.chat_push_recipients(text).is_empty());
+    }
+
     #[test]
     fn session_user_index_lifecycle_multi_session_and_reconnect() {
         let sessions = super::SessionMap::new();
@@ -563,6 +1438,29 @@ mod tests {
         assert!(sessions.user_index.get(&user).is_none());
     }
 
+    #[test]
+    fn client_caps_live_until_the_session_is_unregistered() {
+        let sessions = super::SessionMap::new();
+        let user = UserId(uuid::Uuid::new_v4());
+        let caps = pb::ClientCaps {
+            features: Some(pb::FeatureCaps {
+                supports_session_resume: true,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        assert!(sessions.client_caps("s1").is_none());
+        sessions.set_client_caps("s1", caps.clone());
+        sessions.set_client_caps("s2", pb::ClientCaps::default());
+        assert_eq!(sessions.client_caps("s1").as_deref(), Some(&caps));
+
+        sessions.unregister(user, "s1");
+        assert!(sessions.client_caps("s1").is_none());
+        sessions.unregister_by_session_id("s2");
+        assert!(sessions.client_caps("s2").is_none());
+    }
+
     #[test]
     fn stream_registry_stop_share_removes_all_tags() {
         let mut registry = StreamSessionRegistry::new();
@@ -1,7 +1,16 @@
 use anyhow::{anyhow, Context, Result};
+use arc_swap::ArcSwap;
+use metrics::counter;
 use rcgen::{CertificateParams, DnType, KeyPair, SanType};
 use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::{ClientHello, ResolvesServerCert};
+use rustls::sign::CertifiedKey;
 use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::time::MissedTickBehavior;
+use tracing::{info, warn};
+use vp_metrics::metric_name;
 
 const DEFAULT_SELF_SIGNED_DNS_SAN: &str = "localhost";
 const DEFAULT_SELF_SIGNED_IP_SANS: [IpAddr; 2] = [
@@ -15,24 +24,7 @@ pub fn load_or_generate_tls(
     extra_self_signed_sans: &[String],
 ) -> Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>)> {
     match (cert_pem, key_pem) {
-        (Some(cert_path), Some(key_path)) => {
-            let cert_data = std::fs::read(cert_path).context("read cert PEM")?;
-            let key_data = std::fs::read(key_path).context("read key PEM")?;
-
-            let certs: Vec<CertificateDer<'static>> = rustls_pemfile::certs(&mut &cert_data[..])
-                .collect::<std::result::Result<Vec<_>, _>>()
-                .context("parse cert PEM")?;
-
-            if certs.is_empty() {
-                return Err(anyhow!("no certificates found in {}", cert_path));
-            }
-
-            let key = rustls_pemfile::private_key(&mut &key_data[..])
-                .context("parse key PEM")?
-                .ok_or_else(|| anyhow!("no private key found in {}", key_path))?;
-
-            Ok((certs, key))
-        }
+        (Some(cert_path), Some(key_path)) => load_pem_files(cert_path, key_path),
         (None, None) => {
             let cert = generate_self_signed(extra_self_signed_sans)
                 .context("failed generating self-signed cert")?;
@@ -46,6 +38,133 @@ pub fn load_or_generate_tls(
     }
 }
 
+fn load_pem_files(
+    cert_path: &str,
+    key_path: &str,
+) -> Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>)> {
+    let cert_data = std::fs::read(cert_path).context("read cert PEM")?;
+    let key_data = std::fs::read(key_path).context("read key PEM")?;
+
+    let certs: Vec<CertificateDer<'static>> = rustls_pemfile::certs(&mut &cert_data[..])
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .context("parse cert PEM")?;
+
+    if certs.is_empty() {
+        return Err(anyhow!("no certificates found in {}", cert_path));
+    }
+
+    let key = rustls_pemfile::private_key(&mut &key_data[..])
+        .context("parse key PEM")?
+        .ok_or_else(|| anyhow!("no private key found in {}", key_path))?;
+
+    Ok((certs, key))
+}
+
+pub fn certified_key(
+    certs: Vec<CertificateDer<'static>>,
+    key: PrivateKeyDer<'static>,
+) -> Result<Arc<CertifiedKey>> {
+    let signing_key =
+        rustls::crypto::ring::sign::any_supported_type(&key).context("unsupported private key")?;
+    let certified = CertifiedKey::new(certs, signing_key);
+    certified
+        .keys_match()
+        .context("private key does not match certificate")?;
+    Ok(Arc::new(certified))
+}
+
+/// Hands every new handshake the most recently loaded certificate.
+/// Connections that already completed their handshake are unaffected by a swap.
+#[derive(Debug)]
+pub struct ReloadableCertResolver {
+    current: ArcSwap<CertifiedKey>,
+}
+
+impl ReloadableCertResolver {
+    pub fn new(key: Arc<CertifiedKey>) -> Self {
+        Self {
+            current: ArcSwap::new(key),
+        }
+    }
+
+    pub fn replace(&self, key: Arc<CertifiedKey>) {
+        self.current.store(key);
+    }
+}
+
+impl ResolvesServerCert for ReloadableCertResolver {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(self.current.load_full())
+    }
+}
+
+/// Modification time and size of a file; a renewal changes at least one.
+type FileStamp = (SystemTime, u64);
+
+fn file_stamp(path: &str) -> std::io::Result<FileStamp> {
+    let meta = std::fs::metadata(path)?;
+    Ok((meta.modified()?, meta.len()))
+}
+
+/// Tracks the cert/key files and reloads them into a resolver when either
+/// changes on disk.
+pub struct CertFileWatch {
+    cert_path: String,
+    key_path: String,
+    last: Option<(FileStamp, FileStamp)>,
+}
+
+impl CertFileWatch {
+    pub fn new(cert_path: String, key_path: String) -> Self {
+        let last = file_stamp(&cert_path).ok().zip(file_stamp(&key_path).ok());
+        Self {
+            cert_path,
+            key_path,
+            last,
+        }
+    }
+
+    /// Reload if the files changed since the last successful load. A pair
+    /// that fails to parse or whose key doesn't match the certificate (e.g.
+    /// caught between the two writes of a renewal) is retried on the next
+    /// poll while the resolver keeps serving the previous certificate.
+    pub fn poll(&mut self, resolver: &ReloadableCertResolver) -> Result<bool> {
+        let stamps = (file_stamp(&self.cert_path)?, file_stamp(&self.key_path)?);
+        if self.last == Some(stamps) {
+            return Ok(false);
+        }
+        let (certs, key) = load_pem_files(&self.cert_path, &self.key_path)?;
+        resolver.replace(certified_key(certs, key)?);
+        self.last = Some(stamps);
+        Ok(true)
+    }
+}
+
+pub async fn run_cert_reloader(
+    resolver: Arc<ReloadableCertResolver>,
+    mut watch: CertFileWatch,
+    interval: Duration,
+) {
+    let mut tick = tokio::time::interval(interval);
+    tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    tick.tick().await;
+    loop {
+        tick.tick().await;
+        match watch.poll(&resolver) {
+            Ok(false) => {}
+            Ok(true) => {
+                counter!(metric_name("gateway_tls_reloads_total"), "result" => "ok").increment(1);
+                info!(cert = %watch.cert_path, "reloaded TLS certificate");
+            }
+            Err(e) => {
+                counter!(metric_name("gateway_tls_reloads_total"), "result" => "error")
+                    .increment(1);
+                warn!(cert = %watch.cert_path, "TLS certificate reload failed: {:#}", e);
+            }
+        }
+    }
+}
+
 fn generate_self_signed(extra_self_signed_sans: &[String]) -> Result<rcgen::CertifiedKey<KeyPair>> {
     let mut params = CertificateParams::new(vec![DEFAULT_SELF_SIGNED_DNS_SAN.to_string()])?;
     params
@@ -79,3 +198,52 @@ fn generate_self_signed(extra_self_signed_sans: &[String]) -> Result<rcgen::Cert
         signing_key: key_pair,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_pair(dir: &std::path::Path, sans: &[String]) -> CertificateDer<'static> {
+        let cert = generate_self_signed(sans).unwrap();
+        std::fs::write(dir.join("cert.pem"), cert.cert.pem()).unwrap();
+        std::fs::write(dir.join("key.pem"), cert.signing_key.serialize_pem()).unwrap();
+        cert.cert.der().clone()
+    }
+
+    #[test]
+    fn watch_reloads_changed_files_and_keeps_old_cert_on_bad_pem() {
+        let dir = std::env::temp_dir().join(format!("vp-tls-reload-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let cert_path = dir.join("cert.pem").to_string_lossy().into_owned();
+        let key_path = dir.join("key.pem").to_string_lossy().into_owned();
+
+        let first = write_pair(&dir, &[]);
+        let (certs, key) = load_or_generate_tls(Some(&cert_path), Some(&key_path), &[]).unwrap();
+        let resolver = ReloadableCertResolver::new(certified_key(certs, key).unwrap());
+        let mut watch = CertFileWatch::new(cert_path.clone(), key_path.clone());
+        assert!(!watch.poll(&resolver).unwrap());
+        assert_eq!(resolver.current.load().cert[0], first);
+
+        // The extra SAN changes the file size, so the swap is detected even
+        // when the filesystem's mtime granularity hides the rewrite.
+        let second = write_pair(&dir, &["rotated.example.com".to_string()]);
+        assert!(watch.poll(&resolver).unwrap());
+        assert_eq!(resolver.current.load().cert[0], second);
+
+        std::fs::write(&cert_path, "not a certificate").unwrap();
+        assert!(watch.poll(&resolver).is_err());
+        assert_eq!(resolver.current.load().cert[0], second);
+
+        // A renewed certificate whose key hasn't been written yet.
+        let renewed = generate_self_signed(&[]).unwrap();
+        std::fs::write(&cert_path, renewed.cert.pem()).unwrap();
+        let err = watch.poll(&resolver).unwrap_err();
+        assert!(format!("{err:#}").contains("does not match"), "{err:#}");
+        assert_eq!(resolver.current.load().cert[0], second);
+        std::fs::write(&key_path, renewed.signing_key.serialize_pem()).unwrap();
+        assert!(watch.poll(&resolver).unwrap());
+        assert_eq!(resolver.current.load().cert[0], *renewed.cert.der());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,217 @@
+//! Forwarder CPU per 20 ms of channel audio, SFU fanout vs server-side mixing.
+//!
+//! Run with `cargo bench -p vp-media --bench mix_vs_sfu`. Each case is a
+//! voice channel of `MEMBERS` users of whom 4, 8 or 16 talk at once. The
+//! fake sessions only count datagrams, so the socket cost of SFU's larger
+//! fanout is reported as a send count rather than timed.
+
+use std::{
+    hint::black_box,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use bytes::{BufMut, Bytes, BytesMut};
+use tokio::sync::mpsc;
+use vp_control::ids::{ChannelId, UserId};
+use vp_media::datagram_send_policy::DatagramSendPolicyMetrics;
+use vp_media::voice_forwarder::{
+    DatagramTx, ForwardMode, MembershipProvider, NoopMetrics, SenderRoute, SessionRegistry,
+    VoiceForwarder, VoiceForwarderConfig,
+};
+use vp_media::voice_mixer::{MixCodec, OpusMixCodec, MIX_FRAME_SAMPLES, MIX_SAMPLE_RATE};
+
+const MEMBERS: usize = 32;
+const TALKERS: [usize; 3] = [4, 8, 16];
+const WARMUP_TICKS: u32 = 50;
+const MEASURED_TICKS: u32 = 500;
+const TICK: Duration = Duration::from_millis(20);
+
+struct CountingTx {
+    id: String,
+    sent: Arc<AtomicUsize>,
+}
+
+#[async_trait::async_trait]
+impl DatagramTx for CountingTx {
+    async fn send(&self, _bytes: Bytes) -> Result<()> {
+        Ok(())
+    }
+    fn session_id(&self) -> &str {
+        &self.id
+    }
+    fn max_datagram_size(&self) -> Option<usize> {
+        None
+    }
+    fn supports_voice_multi_frame(&self) -> bool {
+        true
+    }
+    fn send_voice(
+        &self,
+        _now_ms: u64,
+        _channel_id: ChannelId,
+        pkt: Bytes,
+        _prune_tx: &mpsc::Sender<()>,
+        _metrics: &dyn DatagramSendPolicyMetrics,
+    ) {
+        black_box(pkt);
+        self.sent.fetch_add(1, Ordering::Relaxed);
+    }
+    fn send_video_best_effort(
+        &self,
+        _now_ms: u64,
+        _channel_id: ChannelId,
+        _pkt: Bytes,
+        _prune_tx: &mpsc::Sender<()>,
+        _metrics: &dyn DatagramSendPolicyMetrics,
+    ) {
+    }
+}
+
+struct Room {
+    channel: ChannelId,
+    members: Vec<UserId>,
+    sessions: Vec<Arc<dyn DatagramTx>>,
+}
+
+#[async_trait::async_trait]
+impl SessionRegistry for Room {
+    async fn get_sessions(&self, user: UserId) -> Vec<(String, Arc<dyn DatagramTx>)> {
+        let Some(i) = self.members.iter().position(|m| *m == user) else {
+            return Vec::new();
+        };
+        let sess = self.sessions[i].clone();
+        vec![(sess.session_id().to_string(), sess)]
+    }
+}
+
+#[async_trait::async_trait]
+impl MembershipProvider for Room {
+    async fn resolve_channel_for_sender(&self, _sender: UserId, _route_key: u32) -> SenderRoute {
+        SenderRoute::Channel(self.channel)
+    }
+    async fn list_members(&self, _channel: ChannelId) -> Vec<UserId> {
+        self.members.clone()
+    }
+    async fn is_muted(&self, _channel: ChannelId, _sender: UserId) -> bool {
+        false
+    }
+    async fn is_deafened(&self, _channel: ChannelId, _user: UserId) -> bool {
+        false
+    }
+    async fn is_banned(&self, _channel: ChannelId, _user: UserId) -> bool {
+        false
+    }
+    async fn max_talkers(&self, _channel: ChannelId) -> usize {
+        MEMBERS
+    }
+}
+
+/// One second of a distinct tone per talker, Opus-encoded the way a client
+/// would send it.
+fn talker_frames(talker: usize) -> Vec<Vec<u8>> {
+    let mut enc = OpusMixCodec.encoder().expect("opus encoder");
+    let freq = 180.0 + 40.0 * talker as f32;
+    let mut out = vec![0u8; 1275];
+    (0..50)
+        .map(|frame| {
+            let pcm: Vec<i16> = (0..MIX_FRAME_SAMPLES)
+                .map(|i| {
+                    let t = (frame * MIX_FRAME_SAMPLES + i) as f32 / MIX_SAMPLE_RATE as f32;
+                    ((t * freq * std::f32::consts::TAU).sin() * 8_000.0) as i16
+                })
+                .collect();
+            let n = enc.encode(&pcm, &mut out).expect("opus encode");
+            out[..n].to_vec()
+        })
+        .collect()
+}
+
+fn voice_datagram(ssrc: u32, seq: u32, payload: &[u8]) -> Bytes {
+    let mut b = BytesMut::with_capacity(vp_voice::CLIENT_VOICE_HEADER_BYTES + payload.len());
+    b.extend_from_slice(&[1, vp_voice::VOICE_FLAG_VAD]);
+    b.put_u16(vp_voice::CLIENT_VOICE_HEADER_BYTES as u16);
+    b.put_u32(1);
+    b.put_u32(ssrc);
+    b.put_u32(seq);
+    b.put_u32(seq * 20);
+    b.extend_from_slice(payload);
+    b.freeze()
+}
+
+async fn run_case(mode: ForwardMode, talkers: usize) -> (Duration, f64) {
+    let sent = Arc::new(AtomicUsize::new(0));
+    let members: Vec<UserId> = (0..MEMBERS).map(|_| UserId::new()).collect();
+    let sessions = (0..MEMBERS)
+        .map(|i| {
+            Arc::new(CountingTx {
+                id: format!("s{i}"),
+                sent: sent.clone(),
+            }) as Arc<dyn DatagramTx>
+        })
+        .collect();
+    let room = Arc::new(Room {
+        channel: ChannelId::new(),
+        members: members.clone(),
+        sessions,
+    });
+    let (prune_tx, _prune_rx) = mpsc::channel(1);
+    let forwarder = VoiceForwarder::new(
+        VoiceForwarderConfig {
+            mode,
+            sender_pps_limit: 1_000_000,
+            sender_bps_limit: 1_000_000_000,
+            ..VoiceForwarderConfig::default()
+        },
+        room.clone(),
+        room,
+        Arc::new(NoopMetrics),
+        prune_tx,
+    );
+    let frames: Vec<Vec<Vec<u8>>> = (0..talkers).map(talker_frames).collect();
+
+    let mut elapsed = Duration::ZERO;
+    for tick in 0..WARMUP_TICKS + MEASURED_TICKS {
+        if tick == WARMUP_TICKS {
+            sent.store(0, Ordering::Relaxed);
+        }
+        let started = Instant::now();
+        for (t, talker_frames) in frames.iter().enumerate() {
+            let payload = &talker_frames[tick as usize % talker_frames.len()];
+            let datagram = voice_datagram(t as u32, tick, payload);
+            forwarder.handle_incoming(members[t], datagram).await;
+        }
+        forwarder.mix_tick().await;
+        if tick >= WARMUP_TICKS {
+            elapsed += started.elapsed();
+        }
+    }
+    let per_tick = elapsed / MEASURED_TICKS;
+    let sends = sent.load(Ordering::Relaxed) as f64 / f64::from(MEASURED_TICKS);
+    (per_tick, sends)
+}
+
+fn main() {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("tokio runtime");
+    println!("{MEMBERS} members, per 20 ms of audio:");
+    println!(
+        "{:>8} {:>5} {:>12} {:>10} {:>12}",
+        "talkers", "mode", "cpu/tick", "% of core", "sends/tick"
+    );
+    for talkers in TALKERS {
+        for (mode, name) in [(ForwardMode::Sfu, "sfu"), (ForwardMode::Mix, "mix")] {
+            let (per_tick, sends) = rt.block_on(run_case(mode, talkers));
+            println!(
+                "{talkers:>8} {name:>5} {:>10.1}us {:>9.2}% {sends:>12.1}",
+                per_tick.as_secs_f64() * 1e6,
+                100.0 * per_tick.as_secs_f64() / TICK.as_secs_f64(),
+            );
+        }
+    }
+}
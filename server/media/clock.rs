@@ -0,0 +1,63 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+/// Time source for the media fast path.
+///
+/// Rate limiting, timestamp sanity and talker windows all read the clock, so
+/// injecting it lets tests step time instead of sleeping.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+    /// Process-relative milliseconds, same epoch as `datagram_send_policy::now_ms`.
+    fn now_ms(&self) -> u64;
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+    fn now_ms(&self) -> u64 {
+        crate::datagram_send_policy::now_ms()
+    }
+}
+
+/// Manually advanced clock for deterministic tests. Starts at the instant it
+/// was created and only moves when `advance` is called.
+#[derive(Debug)]
+pub struct ManualClock {
+    base: Instant,
+    elapsed_ms: AtomicU64,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self {
+            base: Instant::now(),
+            elapsed_ms: AtomicU64::new(0),
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.elapsed_ms
+            .fetch_add(by.as_millis() as u64, Ordering::Relaxed);
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.base + Duration::from_millis(self.elapsed_ms.load(Ordering::Relaxed))
+    }
+    fn now_ms(&self) -> u64 {
+        self.elapsed_ms.load(Ordering::Relaxed)
+    }
+}
@@ -61,6 +61,9 @@ pub struct SessionSendCtx {
     pub conn: quinn::Connection,
     pub last_prune_ms: AtomicU64,
     pub prune: PruneState,
+    /// Client advertised `supports_voice_multi_frame` in its hello caps.
+    pub voice_multi_frame: bool,
+    pub connected_at: std::time::SystemTime,
 }
 
 impl SessionSendCtx {
@@ -71,9 +74,16 @@ impl SessionSendCtx {
             conn,
             last_prune_ms: AtomicU64::new(0),
             prune: PruneState::default(),
+            voice_multi_frame: false,
+            connected_at: std::time::SystemTime::now(),
         }
     }
 
+    pub fn with_voice_multi_frame(mut self, enabled: bool) -> Self {
+        self.voice_multi_frame = enabled;
+        self
+    }
+
     pub fn send_voice(
         &self,
         now_ms: u64,
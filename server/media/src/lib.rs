@@ -1,9 +1,18 @@
 #[path = "../datagram_send_policy.rs"]
 pub mod datagram_send_policy;
 
+#[path = "../clock.rs"]
+pub mod clock;
+
 #[path = "../voice_forwarder.rs"]
 pub mod voice_forwarder;
 
+#[path = "../voice_mixer.rs"]
+pub mod voice_mixer;
+
+#[path = "../voice_archive.rs"]
+pub mod voice_archive;
+
 #[path = "../stream_forwarder.rs"]
 pub mod stream_forwarder;
 
@@ -1131,6 +1131,9 @@ mod tests {
         fn max_datagram_size(&self) -> Option<usize> {
             Some(vp_voice::QUIC_MAX_DATAGRAM_BYTES)
         }
+        fn supports_voice_multi_frame(&self) -> bool {
+            false
+        }
         fn send_voice(
             &self,
             _now_ms: u64,
@@ -1169,6 +1172,9 @@ mod tests {
         fn max_datagram_size(&self) -> Option<usize> {
             Some(vp_voice::QUIC_MAX_DATAGRAM_BYTES)
         }
+        fn supports_voice_multi_frame(&self) -> bool {
+            false
+        }
         fn send_voice(
             &self,
             _now_ms: u64,
@@ -0,0 +1,408 @@
+//! Compliance recording of forwarded voice as Ogg Opus files.
+//!
+//! [`OggOpusFileSink`] implements [`VoiceSink`] by handing each frame to a
+//! bounded queue; a dedicated writer thread muxes them into one `.opus` file
+//! per (channel, ssrc). The fanout path never waits on disk: when the queue
+//! is full the frame is dropped from the recording and counted.
+
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, RecvTimeoutError, SyncSender, TrySendError},
+        Arc,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use tracing::{debug, warn};
+use vp_control::ids::{ChannelId, UserId};
+
+use crate::voice_forwarder::VoiceSink;
+
+/// Frames buffered between the forwarder and the writer thread.
+const QUEUE_CAPACITY: usize = 4096;
+/// A stream with no frames for this long is finished and its file closed.
+const STREAM_IDLE_CLOSE: Duration = Duration::from_secs(30);
+/// Opus granule positions always count 48 kHz samples.
+const OPUS_GRANULE_RATE_HZ: u32 = 48_000;
+/// A timestamp jump further ahead than this is taken as the sender's clock
+/// restarting rather than a pause, since a pause that long closes the stream.
+const MAX_TIMESTAMP_GAP_MS: u32 = STREAM_IDLE_CLOSE.as_millis() as u32;
+
+struct ArchivedFrame {
+    channel: ChannelId,
+    sender: UserId,
+    ssrc: u32,
+    ts_ms: u32,
+    payload: Vec<u8>,
+}
+
+/// Records every voice frame that passes moderation to `<dir>/<channel>-<ssrc>-<unix_ms>.opus`.
+pub struct OggOpusFileSink {
+    tx: SyncSender<ArchivedFrame>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl OggOpusFileSink {
+    /// Create `dir` if needed and start the writer thread.
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("create voice archive dir {}", dir.display()))?;
+        let (tx, rx) = mpsc::sync_channel(QUEUE_CAPACITY);
+        std::thread::Builder::new()
+            .name("voice-archive".into())
+            .spawn(move || run_writer(dir, rx))
+            .context("spawn voice archive writer")?;
+        Ok(Self {
+            tx,
+            dropped: Arc::new(AtomicU64::new(0)),
+        })
+    }
+
+    /// Frames left out of the recording because the writer fell behind.
+    pub fn dropped_frames(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+#[async_trait::async_trait]
+impl VoiceSink for OggOpusFileSink {
+    async fn on_packet(
+        &self,
+        channel: ChannelId,
+        sender: UserId,
+        ssrc: u32,
+        _seq: u32,
+        ts_ms: u32,
+        payload: &[u8],
+    ) {
+        let frame = ArchivedFrame {
+            channel,
+            sender,
+            ssrc,
+            ts_ms,
+            payload: payload.to_vec(),
+        };
+        match self.tx.try_send(frame) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                if self.dropped.fetch_add(1, Ordering::Relaxed) == 0 {
+                    warn!("[voice-archive] writer is behind; dropping frames from the recording");
+                }
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+fn run_writer(dir: PathBuf, rx: mpsc::Receiver<ArchivedFrame>) {
+    let mut streams = HashMap::<(ChannelId, u32), (OggOpusWriter<BufWriter<File>>, Instant)>::new();
+    loop {
+        match rx.recv_timeout(STREAM_IDLE_CLOSE / 2) {
+            Ok(frame) => {
+                let key = (frame.channel, frame.ssrc);
+                let (writer, last_frame) = match streams.entry(key) {
+                    Entry::Occupied(open) => open.into_mut(),
+                    Entry::Vacant(slot) => match open_stream(&dir, &frame) {
+                        Ok(writer) => slot.insert((writer, Instant::now())),
+                        Err(e) => {
+                            warn!("[voice-archive] open recording failed: {e:#}");
+                            continue;
+                        }
+                    },
+                };
+                *last_frame = Instant::now();
+                if let Err(e) = writer.write_packet(frame.ts_ms, &frame.payload) {
+                    warn!("[voice-archive] write failed; closing recording: {e:#}");
+                    streams.remove(&key);
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        let now = Instant::now();
+        let idle: Vec<_> = streams
+            .iter()
+            .filter(|(_, (_, last_frame))| now.duration_since(*last_frame) >= STREAM_IDLE_CLOSE)
+            .map(|(key, _)| *key)
+            .collect();
+        for key in idle {
+            if let Some((writer, _)) = streams.remove(&key) {
+                close_stream(writer);
+            }
+        }
+    }
+    for (_, (writer, _)) in streams.drain() {
+        close_stream(writer);
+    }
+}
+
+fn open_stream(dir: &Path, frame: &ArchivedFrame) -> Result<OggOpusWriter<BufWriter<File>>> {
+    let started_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let path = dir.join(format!(
+        "{}-{:08x}-{started_ms}.opus",
+        frame.channel.0, frame.ssrc
+    ));
+    let file = File::create(&path).with_context(|| format!("create {}", path.display()))?;
+    debug!("[voice-archive] recording {}", path.display());
+    let channels = opus_packet_channels(&frame.payload);
+    let tags = [
+        format!("CHANNEL={}", frame.channel.0),
+        format!("SENDER={}", frame.sender.0),
+        format!("SSRC={}", frame.ssrc),
+    ];
+    OggOpusWriter::new(BufWriter::new(file), frame.ssrc, channels, &tags)
+}
+
+fn close_stream(writer: OggOpusWriter<BufWriter<File>>) {
+    if let Err(e) = writer
+        .finish()
+        .and_then(|mut out| out.flush().map_err(Into::into))
+    {
+        warn!("[voice-archive] closing recording failed: {e:#}");
+    }
+}
+
+/// Minimal Ogg Opus muxer (RFC 7845): ID and comment header pages, then one
+/// audio packet per page. The newest page is held back so the last one can
+/// carry the end-of-stream flag.
+pub struct OggOpusWriter<W: Write> {
+    out: W,
+    serial: u32,
+    page_seq: u32,
+    granule: u64,
+    /// Timestamp and starting granule of the previous packet.
+    last_start: Option<(u32, u64)>,
+    pending: Option<(Vec<u8>, u64)>,
+}
+
+impl<W: Write> OggOpusWriter<W> {
+    const HEADER_BOS: u8 = 0x02;
+    const HEADER_EOS: u8 = 0x04;
+
+    pub fn new(out: W, serial: u32, channels: u8, tags: &[String]) -> Result<Self> {
+        let mut writer = Self {
+            out,
+            serial,
+            page_seq: 0,
+            granule: 0,
+            last_start: None,
+            pending: None,
+        };
+
+        let mut head = Vec::with_capacity(19);
+        head.extend_from_slice(b"OpusHead");
+        head.push(1);
+        head.push(channels.max(1));
+        head.extend_from_slice(&0u16.to_le_bytes()); // pre-skip
+        head.extend_from_slice(&OPUS_GRANULE_RATE_HZ.to_le_bytes());
+        head.extend_from_slice(&0i16.to_le_bytes()); // output gain
+        head.push(0); // mapping family: mono/stereo
+        writer.write_page(Self::HEADER_BOS, 0, &head)?;
+
+        let vendor = concat!("vp-media ", env!("CARGO_PKG_VERSION"));
+        let mut comments = Vec::new();
+        comments.extend_from_slice(b"OpusTags");
+        comments.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+        comments.extend_from_slice(vendor.as_bytes());
+        comments.extend_from_slice(&(tags.len() as u32).to_le_bytes());
+        for tag in tags {
+            comments.extend_from_slice(&(tag.len() as u32).to_le_bytes());
+            comments.extend_from_slice(tag.as_bytes());
+        }
+        writer.write_page(0, 0, &comments)?;
+        Ok(writer)
+    }
+
+    /// Append one Opus packet sent at `ts_ms`. It starts where its timestamp
+    /// puts it, so time skipped by DTX or lost packets stays in the
+    /// recording, and its granule position advances by its decoded duration.
+    /// A timestamp that goes backwards or jumps implausibly far continues
+    /// straight on from the previous packet instead.
+    pub fn write_packet(&mut self, ts_ms: u32, packet: &[u8]) -> Result<()> {
+        let start = match self.last_start {
+            Some((last_ts, last_start)) => {
+                let gap_ms = ts_ms.wrapping_sub(last_ts);
+                if gap_ms <= MAX_TIMESTAMP_GAP_MS {
+                    let samples_per_ms = u64::from(OPUS_GRANULE_RATE_HZ / 1000);
+                    self.granule
+                        .max(last_start + u64::from(gap_ms) * samples_per_ms)
+                } else {
+                    self.granule
+                }
+            }
+            None => 0,
+        };
+        self.last_start = Some((ts_ms, start));
+        self.granule = start + opus_packet_samples(packet);
+        if let Some((prev, granule)) = self.pending.replace((packet.to_vec(), self.granule)) {
+            self.write_page(0, granule, &prev)?;
+        }
+        Ok(())
+    }
+
+    /// Write the held-back page with end-of-stream set and return the sink.
+    pub fn finish(mut self) -> Result<W> {
+        if let Some((last, granule)) = self.pending.take() {
+            self.write_page(Self::HEADER_EOS, granule, &last)?;
+        }
+        Ok(self.out)
+    }
+
+    fn write_page(&mut self, header_type: u8, granule: u64, packet: &[u8]) -> Result<()> {
+        // Lacing: runs of 255 plus a final short segment (0 if the length is a multiple of 255).
+        let mut lacing = vec![255u8; packet.len() / 255];
+        lacing.push((packet.len() % 255) as u8);
+        anyhow::ensure!(lacing.len() <= 255, "packet too large for one Ogg page");
+
+        let mut page = Vec::with_capacity(27 + lacing.len() + packet.len());
+        page.extend_from_slice(b"OggS");
+        page.push(0);
+        page.push(header_type);
+        page.extend_from_slice(&granule.to_le_bytes());
+        page.extend_from_slice(&self.serial.to_le_bytes());
+        page.extend_from_slice(&self.page_seq.to_le_bytes());
+        page.extend_from_slice(&0u32.to_le_bytes());
+        page.push(lacing.len() as u8);
+        page.extend_from_slice(&lacing);
+        page.extend_from_slice(packet);
+        let crc = ogg_crc32(&page);
+        page[22..26].copy_from_slice(&crc.to_le_bytes());
+
+        self.out.write_all(&page)?;
+        self.page_seq = self.page_seq.wrapping_add(1);
+        Ok(())
+    }
+}
+
+/// Decoded length of an Opus packet in 48 kHz samples, from its TOC byte
+/// (RFC 6716 §3.1). Malformed packets count as zero.
+pub fn opus_packet_samples(packet: &[u8]) -> u64 {
+    let Some(&toc) = packet.first() else {
+        return 0;
+    };
+    let config = toc >> 3;
+    let frame_samples: u64 = match config {
+        0..=11 => [480, 960, 1920, 2880][(config % 4) as usize],
+        12..=15 => [480, 960][(config % 2) as usize],
+        _ => [120, 240, 480, 960][(config % 4) as usize],
+    };
+    let frames = match toc & 0x03 {
+        0 => 1,
+        1 | 2 => 2,
+        _ => packet.get(1).map_or(0, |count| u64::from(count & 0x3F)),
+    };
+    frame_samples * frames
+}
+
+fn opus_packet_channels(packet: &[u8]) -> u8 {
+    match packet.first() {
+        Some(toc) if toc & 0x04 != 0 => 2,
+        _ => 1,
+    }
+}
+
+/// CRC-32 as Ogg defines it: polynomial 0x04C11DB7, no reflection, zero init.
+fn ogg_crc32(data: &[u8]) -> u32 {
+    let mut crc = 0u32;
+    for &byte in data {
+        crc ^= u32::from(byte) << 24;
+        for _ in 0..8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ 0x04C1_1DB7
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pages(mut data: &[u8]) -> Vec<(u8, u64, u32, Vec<u8>)> {
+        let mut out = Vec::new();
+        while !data.is_empty() {
+            assert_eq!(&data[..4], b"OggS");
+            let header_type = data[5];
+            let granule = u64::from_le_bytes(data[6..14].try_into().unwrap());
+            let page_seq = u32::from_le_bytes(data[18..22].try_into().unwrap());
+            let crc = u32::from_le_bytes(data[22..26].try_into().unwrap());
+            let segments = data[26] as usize;
+            let body_len: usize = data[27..27 + segments].iter().map(|&l| l as usize).sum();
+            let page_len = 27 + segments + body_len;
+            let mut zeroed = data[..page_len].to_vec();
+            zeroed[22..26].fill(0);
+            assert_eq!(ogg_crc32(&zeroed), crc);
+            out.push((
+                header_type,
+                granule,
+                page_seq,
+                data[27 + segments..page_len].to_vec(),
+            ));
+            data = &data[page_len..];
+        }
+        out
+    }
+
+    #[test]
+    fn toc_byte_gives_packet_duration() {
+        // CELT 20 ms, one frame.
+        assert_eq!(opus_packet_samples(&[0xF8, 0xFF]), 960);
+        // SILK 60 ms, one frame.
+        assert_eq!(opus_packet_samples(&[0x18]), 2880);
+        // Hybrid 10 ms, two frames.
+        assert_eq!(opus_packet_samples(&[0x61]), 960);
+        // CELT 2.5 ms, code 3 with three frames.
+        assert_eq!(opus_packet_samples(&[0x83, 0x03]), 360);
+        assert_eq!(opus_packet_samples(&[]), 0);
+    }
+
+    #[test]
+    fn writes_headers_then_audio_pages_with_running_granule() {
+        let mut writer = OggOpusWriter::new(Vec::new(), 7, 1, &["SSRC=7".to_string()]).unwrap();
+        writer.write_packet(1_000, &[0xF8, 1, 2, 3]).unwrap();
+        writer.write_packet(1_020, &[0xF8; 300]).unwrap();
+        let bytes = writer.finish().unwrap();
+
+        let pages = pages(&bytes);
+        assert_eq!(pages.len(), 4);
+        assert_eq!(pages[0].0, 0x02);
+        assert!(pages[0].3.starts_with(b"OpusHead"));
+        assert!(pages[1].3.starts_with(b"OpusTags"));
+        assert_eq!((pages[2].0, pages[2].1), (0, 960));
+        assert_eq!((pages[3].0, pages[3].1), (0x04, 1920));
+        assert_eq!(pages[3].3.len(), 300);
+        assert_eq!(
+            pages.iter().map(|p| p.2).collect::<Vec<_>>(),
+            vec![0, 1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn granule_follows_timestamps_across_gaps() {
+        let mut writer = OggOpusWriter::new(Vec::new(), 7, 1, &[]).unwrap();
+        for ts_ms in [500, 520, 600, 580, u32::MAX] {
+            writer.write_packet(ts_ms, &[0xF8]).unwrap();
+        }
+        let bytes = writer.finish().unwrap();
+
+        let granules: Vec<u64> = pages(&bytes)[2..].iter().map(|p| p.1).collect();
+        // 540..600 went silent under DTX; the late packet and the clock
+        // reset are appended after what came before.
+        assert_eq!(granules, vec![960, 1920, 4800 + 960, 6720, 7680]);
+    }
+}
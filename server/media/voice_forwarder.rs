@@ -1,22 +1,28 @@
 use std::{
     collections::{HashMap, VecDeque},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
     time::{Duration, Instant},
 };
 
 use anyhow::{anyhow, Result};
 use bytes::{BufMut, Bytes, BytesMut};
 use tokio::sync::{mpsc, RwLock};
-use tracing::warn;
+use tracing::{debug, warn};
 use vp_control::ids::{ChannelId, UserId};
 
-use crate::datagram_send_policy::now_ms;
+use crate::clock::{Clock, SystemClock};
+use crate::voice_mixer::{MixCodec, MixEngine, MixedFrame, OpusMixCodec};
 
 #[async_trait::async_trait]
 pub trait DatagramTx: Send + Sync {
     async fn send(&self, bytes: Bytes) -> Result<()>;
     fn session_id(&self) -> &str;
     fn max_datagram_size(&self) -> Option<usize>;
+    /// Whether this session can unpack coalesced multi-frame voice datagrams.
+    fn supports_voice_multi_frame(&self) -> bool;
     fn send_voice(
         &self,
         now_ms: u64,
@@ -40,14 +46,53 @@ pub trait SessionRegistry: Send + Sync {
     async fn get_sessions(&self, user: UserId) -> Vec<(String, Arc<dyn DatagramTx>)>;
 }
 
+/// Where a voice datagram may go, decided from one read of the sender's
+/// current presence so a concurrent channel switch can't mix old and new.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SenderRoute {
+    /// The route hash matches the voice channel the sender is joined to.
+    Channel(ChannelId),
+    /// No presence, or the route hash names a channel other than the current one.
+    NotMember,
+    /// The sender is joined to a channel that carries no voice.
+    NotInVoice,
+}
+
 #[async_trait::async_trait]
 pub trait MembershipProvider: Send + Sync {
-    async fn resolve_channel_for_sender(&self, sender: UserId, route_key: u32)
-        -> Option<ChannelId>;
+    async fn resolve_channel_for_sender(&self, sender: UserId, route_key: u32) -> SenderRoute;
     async fn list_members(&self, channel: ChannelId) -> Vec<UserId>;
     async fn is_muted(&self, channel: ChannelId, sender: UserId) -> bool;
     async fn is_deafened(&self, channel: ChannelId, user: UserId) -> bool;
+    /// Server-level ban check for the server owning `channel`. Consulted on the
+    /// fast path, so implementations must answer from memory.
+    async fn is_banned(&self, channel: ChannelId, user: UserId) -> bool;
     async fn max_talkers(&self, channel: ChannelId) -> usize;
+    /// Members of `channel` named by a whisper's target hashes (see
+    /// [`vp_voice::whisper_target_hash`]). Hashes matching nobody are ignored.
+    async fn resolve_whisper_targets(&self, channel: ChannelId, targets: &[u32]) -> Vec<UserId> {
+        let mut members = self.list_members(channel).await;
+        members.retain(|uid| targets.contains(&vp_voice::whisper_target_hash(uid.0.as_bytes())));
+        members
+    }
+}
+
+/// Receives every voice frame that passes moderation, e.g. for compliance
+/// recording. Called inline on the fanout path, so implementations must
+/// hand the frame off (queue, channel) rather than do I/O.
+#[async_trait::async_trait]
+pub trait VoiceSink: Send + Sync {
+    /// `payload` is a single Opus frame; multi-frame bundles are split first,
+    /// with `seq`/`ts_ms` advanced per frame.
+    async fn on_packet(
+        &self,
+        channel: ChannelId,
+        sender: UserId,
+        ssrc: u32,
+        seq: u32,
+        ts_ms: u32,
+        payload: &[u8],
+    );
 }
 
 pub trait VoiceMetrics:
@@ -57,8 +102,16 @@ pub trait VoiceMetrics:
     fn inc_rx_bytes(&self, n: usize);
     fn inc_drop_invalid(&self);
     fn inc_drop_rate_limited(&self);
+    /// Dropped as a duplicate or a sequence number too far behind the
+    /// stream, i.e. a replayed datagram.
+    fn inc_drop_replay(&self);
     fn inc_drop_not_member(&self);
+    /// Dropped because the sender sits in a text channel with no voice join.
+    fn inc_drop_not_in_voice(&self);
+    /// Dropped by the tightened rate limit of a sender caught probing routes.
+    fn inc_drop_probe_penalty(&self);
     fn inc_drop_muted(&self);
+    fn inc_drop_banned(&self);
     fn inc_drop_talker_limit(&self);
     fn inc_drop_send_queue_full(&self);
     fn inc_forwarded(&self, fanout: usize);
@@ -66,6 +119,9 @@ pub trait VoiceMetrics:
     fn observe_recipient_enumeration_us(&self, micros: u64);
     fn observe_packet_fanout_us(&self, micros: u64);
     fn observe_handle_incoming_us(&self, micros: u64);
+    /// Sender loudness (0 = silence, 127 = full scale) from a datagram that
+    /// passed talker gating and carried one.
+    fn observe_loudness(&self, loudness: u8);
 }
 
 pub struct NoopMetrics;
@@ -74,8 +130,12 @@ impl VoiceMetrics for NoopMetrics {
     fn inc_rx_bytes(&self, _n: usize) {}
     fn inc_drop_invalid(&self) {}
     fn inc_drop_rate_limited(&self) {}
+    fn inc_drop_replay(&self) {}
     fn inc_drop_not_member(&self) {}
+    fn inc_drop_not_in_voice(&self) {}
+    fn inc_drop_probe_penalty(&self) {}
     fn inc_drop_muted(&self) {}
+    fn inc_drop_banned(&self) {}
     fn inc_drop_talker_limit(&self) {}
     fn inc_drop_send_queue_full(&self) {}
     fn inc_forwarded(&self, _fanout: usize) {}
@@ -83,6 +143,7 @@ impl VoiceMetrics for NoopMetrics {
     fn observe_recipient_enumeration_us(&self, _micros: u64) {}
     fn observe_packet_fanout_us(&self, _micros: u64) {}
     fn observe_handle_incoming_us(&self, _micros: u64) {}
+    fn observe_loudness(&self, _loudness: u8) {}
 }
 
 #[async_trait::async_trait]
@@ -98,6 +159,9 @@ impl DatagramTx for crate::datagram_send_policy::SessionSendCtx {
     fn max_datagram_size(&self) -> Option<usize> {
         self.conn.max_datagram_size()
     }
+    fn supports_voice_multi_frame(&self) -> bool {
+        self.voice_multi_frame
+    }
     fn send_voice(
         &self,
         now_ms: u64,
@@ -133,36 +197,109 @@ impl crate::datagram_send_policy::DatagramSendPolicyMetrics for NoopMetrics {
     fn inc_video_dropped_due_to_space(&self) {}
 }
 
+/// How voice reaches listeners.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ForwardMode {
+    /// Copy each talker's datagram to every listener untouched.
+    #[default]
+    Sfu,
+    /// Decode talkers and send each listener one mixed stream; see
+    /// [`crate::voice_mixer`]. Needs [`VoiceForwarder::mix_tick`] driven
+    /// every [`crate::voice_mixer::MIX_FRAME_MS`].
+    Mix,
+}
+
 #[derive(Clone, Debug)]
 pub struct VoiceForwarderConfig {
+    pub mode: ForwardMode,
     pub max_datagram_bytes: usize,
     pub min_datagram_bytes: usize,
     pub sender_pps_limit: u32,
     pub sender_bps_limit: u32,
     pub talker_activity_window: Duration,
     pub vad_required_for_talker: bool,
+    /// Not-member datagrams a sender may send per second before being
+    /// treated as probing route hashes. A handful is normal right after a
+    /// channel switch.
+    pub not_member_strike_limit: u32,
+    /// How long a probing sender runs at a reduced rate limit; further
+    /// probes restart the clock.
+    pub not_member_penalty: Duration,
+    /// How many sequence numbers behind the newest one a datagram may arrive
+    /// and still be forwarded. Older ones, and any sequence number already
+    /// seen, are dropped as replays. Capped at the duplicate history kept
+    /// per stream (64).
+    pub replay_reorder_window: u32,
 }
 impl Default for VoiceForwarderConfig {
     fn default() -> Self {
         Self {
+            mode: ForwardMode::Sfu,
             max_datagram_bytes: vp_voice::MAX_INBOUND_VOICE_DATAGRAM_BYTES,
             min_datagram_bytes: vp_voice::CLIENT_VOICE_HEADER_BYTES,
             sender_pps_limit: 200,
             sender_bps_limit: 512 * 1024,
             talker_activity_window: Duration::from_millis(800),
             vad_required_for_talker: false,
+            not_member_strike_limit: 20,
+            not_member_penalty: Duration::from_secs(30),
+            replay_reorder_window: 32,
+        }
+    }
+}
+
+/// A talker slot taken over by a newly speaking sender because its holder
+/// had gone quiet. See [`VoiceForwarder::with_preemption_events`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TalkerPreemption {
+    pub channel: ChannelId,
+    /// The slot holder that was cut off.
+    pub preempted: UserId,
+    /// The sender that took the slot.
+    pub by: UserId,
+}
+
+/// Talker-gating knobs that operators may retune on a live forwarder.
+///
+/// Shrinking `activity_window` takes effect on the next talker check for a
+/// channel: senders whose last packet is older than the new window stop
+/// counting as active, so talker slots can free up immediately.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TalkerTuning {
+    pub activity_window: Duration,
+    pub vad_required: bool,
+}
+
+impl From<&VoiceForwarderConfig> for TalkerTuning {
+    fn from(cfg: &VoiceForwarderConfig) -> Self {
+        Self {
+            activity_window: cfg.talker_activity_window,
+            vad_required: cfg.vad_required_for_talker,
         }
     }
 }
 
 pub struct VoiceForwarder {
     cfg: VoiceForwarderConfig,
+    // Read on every datagram, so kept in atomics rather than behind a lock.
+    talker_window_ns: AtomicU64,
+    vad_required_for_talker: AtomicBool,
     sessions: Arc<dyn SessionRegistry>,
     membership: Arc<dyn MembershipProvider>,
     metrics: Arc<dyn VoiceMetrics>,
     prune_tx: mpsc::Sender<()>,
+    clock: Arc<dyn Clock>,
     talkers: RwLock<HashMap<ChannelId, TalkerSet>>,
-    rate: RwLock<HashMap<(UserId, u32), RateState>>,
+    loudness: RwLock<HashMap<ChannelId, ChannelLoudness>>,
+    rate: RwLock<HashMap<UserId, SenderRate>>,
+    probes: RwLock<HashMap<UserId, ProbeState>>,
+    /// Latest penalty deadline handed out, in ns since `epoch`. Until then
+    /// somebody may be penalized; past it nobody is and `probes` is skipped.
+    probe_penalty_horizon_ns: AtomicU64,
+    epoch: Instant,
+    sink: Option<Arc<dyn VoiceSink>>,
+    mix: Option<MixEngines>,
+    preemption_tx: Option<mpsc::Sender<TalkerPreemption>>,
 }
 
 impl VoiceForwarder {
@@ -173,17 +310,87 @@ impl VoiceForwarder {
         metrics: Arc<dyn VoiceMetrics>,
         prune_tx: mpsc::Sender<()>,
     ) -> Self {
+        Self::new_with_clock(
+            cfg,
+            sessions,
+            membership,
+            metrics,
+            prune_tx,
+            Arc::new(SystemClock),
+        )
+    }
+
+    pub fn new_with_clock(
+        cfg: VoiceForwarderConfig,
+        sessions: Arc<dyn SessionRegistry>,
+        membership: Arc<dyn MembershipProvider>,
+        metrics: Arc<dyn VoiceMetrics>,
+        prune_tx: mpsc::Sender<()>,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        let mix = (cfg.mode == ForwardMode::Mix).then(|| MixEngines::new(Arc::new(OpusMixCodec)));
+        let epoch = clock.now();
         Self {
+            talker_window_ns: AtomicU64::new(duration_ns(cfg.talker_activity_window)),
+            vad_required_for_talker: AtomicBool::new(cfg.vad_required_for_talker),
             cfg,
             sessions,
             membership,
             metrics,
             prune_tx,
+            clock,
             talkers: RwLock::new(HashMap::new()),
+            loudness: RwLock::new(HashMap::new()),
             rate: RwLock::new(HashMap::new()),
+            probes: RwLock::new(HashMap::new()),
+            probe_penalty_horizon_ns: AtomicU64::new(0),
+            epoch,
+            sink: None,
+            mix,
+            preemption_tx: None,
+        }
+    }
+
+    /// Mirror moderated voice frames into `sink`. Without one the forwarder
+    /// records nothing.
+    pub fn with_voice_sink(mut self, sink: Arc<dyn VoiceSink>) -> Self {
+        self.sink = Some(sink);
+        self
+    }
+
+    /// Report talker slots taken over by a new speaker on `tx`, e.g. so
+    /// clients can show who got cut off. Events are dropped while `tx` is full.
+    pub fn with_preemption_events(mut self, tx: mpsc::Sender<TalkerPreemption>) -> Self {
+        self.preemption_tx = Some(tx);
+        self
+    }
+
+    /// Mix with `codec` instead of libopus. Only meaningful in
+    /// [`ForwardMode::Mix`].
+    pub fn with_mix_codec(mut self, codec: Arc<dyn MixCodec>) -> Self {
+        if self.mix.is_some() {
+            self.mix = Some(MixEngines::new(codec));
+        }
+        self
+    }
+
+    pub fn talker_tuning(&self) -> TalkerTuning {
+        TalkerTuning {
+            activity_window: Duration::from_nanos(self.talker_window_ns.load(Ordering::Relaxed)),
+            vad_required: self.vad_required_for_talker.load(Ordering::Relaxed),
         }
     }
 
+    /// Replace the talker-gating settings without restarting the forwarder.
+    /// Existing per-channel talker sets adopt the new window on their next
+    /// check; see [`TalkerTuning`] for the effect of shrinking it.
+    pub fn set_talker_tuning(&self, tuning: TalkerTuning) {
+        self.talker_window_ns
+            .store(duration_ns(tuning.activity_window), Ordering::Relaxed);
+        self.vad_required_for_talker
+            .store(tuning.vad_required, Ordering::Relaxed);
+    }
+
     pub async fn handle_incoming(&self, sender: UserId, datagram: Bytes) {
         let handle_started = Instant::now();
         self.metrics.inc_rx_packets();
@@ -201,38 +408,119 @@ impl VoiceForwarder {
                 return;
             }
         };
-        if !self
-            .allow_rate(sender, parsed.ssrc, datagram.len() as u32, parsed.ts_ms)
-            .await
-        {
-            self.metrics.inc_drop_rate_limited();
+        let multi_frame = parsed.is_multi_frame();
+        if multi_frame && vp_voice::split_multi_frame_payload(parsed.payload(&datagram)).is_none() {
+            self.metrics.inc_drop_invalid();
             return;
         }
+        // Route validation comes before rate accounting: a datagram for a
+        // channel the sender is not in must not spend their budget, and
+        // repeated ones mark the sender as probing.
         let channel = match self
             .membership
             .resolve_channel_for_sender(sender, parsed.channel_route)
             .await
         {
-            Some(c) => c,
-            None => {
+            SenderRoute::Channel(c) => c,
+            SenderRoute::NotMember => {
                 self.metrics.inc_drop_not_member();
+                self.record_not_member(sender).await;
+                return;
+            }
+            // A client that just moved to a text channel may still flush a
+            // few frames; that isn't probing, so no strike.
+            SenderRoute::NotInVoice => {
+                self.metrics.inc_drop_not_in_voice();
                 return;
             }
         };
+        let penalized = self.is_penalized(sender).await;
+        match self
+            .allow_rate(sender, &parsed, datagram.len() as u32, penalized)
+            .await
+        {
+            RateDecision::Allow => {}
+            RateDecision::Replay => {
+                self.metrics.inc_drop_replay();
+                return;
+            }
+            RateDecision::Limited if penalized => {
+                self.metrics.inc_drop_probe_penalty();
+                return;
+            }
+            RateDecision::Limited => {
+                self.metrics.inc_drop_rate_limited();
+                return;
+            }
+        }
+        if self.membership.is_banned(channel, sender).await {
+            self.metrics.inc_drop_banned();
+            return;
+        }
         if self.membership.is_muted(channel, sender).await
             || self.membership.is_deafened(channel, sender).await
         {
             self.metrics.inc_drop_muted();
             return;
         }
-        let vad_ok = !self.cfg.vad_required_for_talker || parsed.vad;
-        if vad_ok && !self.allow_talker(channel, sender).await {
-            self.metrics.inc_drop_talker_limit();
+        // A whisper must not light up the sender for the whole channel: it
+        // takes no talker slot, is not recorded and stays out of the channel
+        // mix; it is forwarded to its targets only.
+        let whisper_targets = parsed.whisper_targets(&datagram);
+        if whisper_targets.is_none() {
+            let tuning = self.talker_tuning();
+            let vad_ok = !tuning.vad_required || parsed.vad;
+            if vad_ok
+                && !self
+                    .allow_talker(channel, sender, parsed.vad, tuning.activity_window)
+                    .await
+            {
+                self.metrics.inc_drop_talker_limit();
+                return;
+            }
+            if let Some(sink) = &self.sink {
+                let payload = parsed.payload(&datagram);
+                sink_voice_frames(sink.as_ref(), &parsed, sender, channel, payload).await;
+            }
+        }
+        if let Some(loudness) = parsed.loudness.filter(|_| whisper_targets.is_none()) {
+            self.metrics.observe_loudness(loudness);
+            self.record_loudness(channel, sender, loudness).await;
+        }
+        if let Some(mix) = self.mix.as_ref().filter(|_| whisper_targets.is_none()) {
+            let payload = parsed.payload(&datagram);
+            let frames = if multi_frame {
+                vp_voice::split_multi_frame_payload(payload).map_or_else(Vec::new, |(_, f)| f)
+            } else {
+                vec![payload]
+            };
+            let frames: Vec<Bytes> = frames.into_iter().map(|f| datagram.slice_ref(f)).collect();
+            let route = parsed.channel_route;
+            let engine = mix.engine(channel);
+            let pushed = tokio::task::spawn_blocking(move || {
+                let mut engine = engine.lock().expect("mix engine poisoned");
+                frames
+                    .iter()
+                    .try_for_each(|frame| engine.push(channel, route, sender, frame))
+            })
+            .await;
+            if !matches!(pushed, Ok(Ok(()))) {
+                self.metrics.inc_drop_invalid();
+            }
+            self.metrics
+                .observe_handle_incoming_us(handle_started.elapsed().as_micros() as u64);
             return;
         }
 
         let recipients_started = Instant::now();
-        let members = self.membership.list_members(channel).await;
+        let members = match &whisper_targets {
+            Some(targets) => {
+                self.membership
+                    .resolve_whisper_targets(channel, targets)
+                    .await
+            }
+            None => self.membership.list_members(channel).await,
+        };
         let mut recipients = Vec::new();
         let session_lookup_started = Instant::now();
         for uid in members {
@@ -252,14 +540,41 @@ impl VoiceForwarder {
         self.metrics
             .observe_recipient_enumeration_us(recipients_started.elapsed().as_micros() as u64);
 
-        let now = now_ms();
+        let now = self.clock.now_ms();
         let fanout_started = Instant::now();
         let mut packet_by_wire_max = HashMap::<usize, Option<Bytes>>::new();
+        let mut split_by_wire_max = HashMap::<usize, Option<Vec<Bytes>>>::new();
         let mut forwarded = 0;
         for sess in recipients {
             let max_wire = sess
                 .max_datagram_size()
                 .unwrap_or(vp_voice::QUIC_MAX_DATAGRAM_BYTES);
+            if multi_frame && !sess.supports_voice_multi_frame() {
+                // Legacy receiver: unpack the bundle into ordinary single-frame datagrams.
+                let frames = split_by_wire_max
+                    .entry(max_wire)
+                    .or_insert_with(|| {
+                        build_split_voice_datagrams(max_wire, &parsed, sender, channel, &datagram)
+                    })
+                    .clone();
+                if let Some(frames) = frames {
+                    for outbound in frames {
+                        sess.send_voice(
+                            now,
+                            channel,
+                            outbound,
+                            &self.prune_tx,
+                            self.metrics.as_ref(),
+                        );
+                    }
+                    forwarded += 1;
+                } else {
+                    crate::datagram_send_policy::DatagramSendPolicyMetrics::inc_oversize_drop(
+                        self.metrics.as_ref(),
+                    );
+                }
+                continue;
+            }
             let outbound = packet_by_wire_max
                 .entry(max_wire)
                 .or_insert_with(|| {
@@ -289,49 +604,236 @@ impl VoiceForwarder {
         self.metrics.inc_forwarded(forwarded);
     }
 
-    async fn allow_rate(&self, sender: UserId, ssrc: u32, bytes: u32, ts_ms: u32) -> bool {
-        self.allow_rate_at(sender, ssrc, bytes, ts_ms, Instant::now())
+    /// Mix one frame for every channel with talkers and send it to each
+    /// listener. Does nothing outside [`ForwardMode::Mix`].
+    pub async fn mix_tick(&self) {
+        let Some(mix) = &self.mix else {
+            return;
+        };
+        // Each channel encodes on the blocking pool, so channels mix in
+        // parallel and never stall the runtime or each other.
+        let mut mixing = Vec::new();
+        for (channel, engine) in mix.active() {
+            let mut receivers = Vec::new();
+            for uid in self.membership.list_members(channel).await {
+                if !self.membership.is_deafened(channel, uid).await {
+                    receivers.push(uid);
+                }
+            }
+            let task = tokio::task::spawn_blocking(move || {
+                engine
+                    .lock()
+                    .expect("mix engine poisoned")
+                    .mix(channel, &receivers)
+            });
+            mixing.push((channel, task));
+        }
+        let now = self.clock.now_ms();
+        let mut forwarded = 0;
+        for (channel, task) in mixing {
+            let fanout_started = Instant::now();
+            let Ok(frames) = task.await else {
+                continue;
+            };
+            for frame in frames {
+                for (_, sess) in self.sessions.get_sessions(frame.receiver).await {
+                    let max_wire = sess
+                        .max_datagram_size()
+                        .unwrap_or(vp_voice::QUIC_MAX_DATAGRAM_BYTES);
+                    let Some(outbound) = encode_mixed_voice(max_wire, channel, &frame) else {
+                        crate::datagram_send_policy::DatagramSendPolicyMetrics::inc_oversize_drop(
+                            self.metrics.as_ref(),
+                        );
+                        continue;
+                    };
+                    sess.send_voice(
+                        now,
+                        channel,
+                        outbound,
+                        &self.prune_tx,
+                        self.metrics.as_ref(),
+                    );
+                    forwarded += 1;
+                }
+            }
+            self.metrics
+                .observe_packet_fanout_us(fanout_started.elapsed().as_micros() as u64);
+        }
+        mix.prune_idle();
+        self.metrics.inc_forwarded(forwarded);
+    }
+
+    /// Forget the per-user state kept for `user` once their last session is
+    /// gone: rate limiter and replay windows, talker slots, loudness samples
+    /// and mixing state. An active probing penalty is kept so reconnecting
+    /// does not clear it.
+    pub async fn remove_receiver(&self, user: UserId) {
+        let now = self.clock.now();
+        self.rate.write().await.remove(&user);
+        self.probes
+            .write()
+            .await
+            .retain(|u, st| *u != user || st.is_penalized(now));
+        {
+            let mut map = self.talkers.write().await;
+            for set in map.values_mut() {
+                set.remove(user);
+            }
+            map.retain(|_, set| !set.last_seen.is_empty());
+        }
+        for levels in self.loudness.write().await.values_mut() {
+            levels.remove(user);
+        }
+        if let Some(mix) = &self.mix {
+            let engines = mix.active();
+            let _ = tokio::task::spawn_blocking(move || {
+                for (_, engine) in engines {
+                    engine
+                        .lock()
+                        .expect("mix engine poisoned")
+                        .remove_user(user);
+                }
+            })
+            .await;
+        }
+    }
+
+    /// Loudest recent talkers of every channel whose levels changed since the
+    /// previous call, loudest first and capped at `limit`. A channel whose
+    /// talkers have all gone quiet is reported once with an empty list so
+    /// listeners can clear their meters.
+    pub async fn take_loudness_updates(
+        &self,
+        limit: usize,
+    ) -> Vec<(ChannelId, Vec<TalkerLoudness>)> {
+        let now = self.clock.now();
+        let mut map = self.loudness.write().await;
+        let updates = map
+            .iter_mut()
+            .filter_map(|(channel, levels)| Some((*channel, levels.take(now, limit)?)))
+            .collect();
+        map.retain(|_, levels| !levels.talkers.is_empty());
+        updates
+    }
+
+    async fn record_loudness(&self, channel: ChannelId, sender: UserId, loudness: u8) {
+        let now = self.clock.now();
+        self.loudness
+            .write()
+            .await
+            .entry(channel)
+            .or_default()
+            .record(sender, loudness, now);
+    }
+
+    async fn allow_rate(
+        &self,
+        sender: UserId,
+        parsed: &VoicePacket,
+        bytes: u32,
+        penalized: bool,
+    ) -> RateDecision {
+        self.allow_rate_at(sender, parsed, bytes, penalized, self.clock.now())
             .await
     }
     async fn allow_rate_at(
         &self,
         sender: UserId,
-        ssrc: u32,
+        parsed: &VoicePacket,
         bytes: u32,
-        ts_ms: u32,
+        penalized: bool,
         now: Instant,
-    ) -> bool {
+    ) -> RateDecision {
+        let (pps_limit, bps_limit) = if penalized {
+            (
+                (self.cfg.sender_pps_limit / PROBE_PENALTY_DIVISOR).max(1),
+                (self.cfg.sender_bps_limit / PROBE_PENALTY_DIVISOR).max(1),
+            )
+        } else {
+            (self.cfg.sender_pps_limit, self.cfg.sender_bps_limit)
+        };
         let mut map = self.rate.write().await;
-        let st = map.entry((sender, ssrc)).or_insert_with(|| {
-            RateState::new(self.cfg.sender_pps_limit, self.cfg.sender_bps_limit)
-        });
-        if !st.check_monotonic_ts(ts_ms, now) {
-            return false;
+        let sender_rate = map
+            .entry(sender)
+            .or_insert_with(|| SenderRate::new(pps_limit, bps_limit, now));
+        let window = self.cfg.replay_reorder_window;
+        if !sender_rate.check_replay(parsed.ssrc, parsed.seq, parsed.ts_ms, window, now) {
+            return RateDecision::Replay;
         }
-        st.refill(self.cfg.sender_pps_limit, self.cfg.sender_bps_limit, now);
+        let st = &mut sender_rate.budget;
+        st.refill(pps_limit, bps_limit, now);
         if st.tokens_pkts == 0 || st.tokens_bytes < bytes {
-            return false;
+            return RateDecision::Limited;
         }
         st.tokens_pkts -= 1;
         st.tokens_bytes -= bytes;
-        true
+        RateDecision::Allow
     }
-    async fn allow_talker(&self, channel: ChannelId, sender: UserId) -> bool {
-        let max = self.membership.max_talkers(channel).await.max(1);
-        let mut map = self.talkers.write().await;
-        let set = map
-            .entry(channel)
-            .or_insert_with(|| TalkerSet::new(self.cfg.talker_activity_window));
-        set.prune();
-        if set.is_active(sender) {
-            set.touch(sender);
-            return true;
+    async fn record_not_member(&self, sender: UserId) {
+        let now = self.clock.now();
+        let mut map = self.probes.write().await;
+        let st = map.entry(sender).or_insert_with(|| ProbeState::new(now));
+        if st.strike(
+            self.cfg.not_member_strike_limit,
+            self.cfg.not_member_penalty,
+            now,
+        ) {
+            warn!(user_id = %sender.0, "voice sender probing route hashes; tightening rate limit");
+        }
+        if let Some(until) = st.penalized_until {
+            self.probe_penalty_horizon_ns
+                .fetch_max(self.since_epoch_ns(until), Ordering::Relaxed);
         }
-        if set.active_count() >= max {
+    }
+    async fn is_penalized(&self, sender: UserId) -> bool {
+        let now = self.clock.now();
+        if self.since_epoch_ns(now) >= self.probe_penalty_horizon_ns.load(Ordering::Relaxed) {
             return false;
         }
-        set.touch(sender);
-        true
+        self.probes
+            .read()
+            .await
+            .get(&sender)
+            .is_some_and(|st| st.is_penalized(now))
+    }
+    fn since_epoch_ns(&self, at: Instant) -> u64 {
+        duration_ns(at.saturating_duration_since(self.epoch))
+    }
+    async fn allow_talker(
+        &self,
+        channel: ChannelId,
+        sender: UserId,
+        vad: bool,
+        window: Duration,
+    ) -> bool {
+        let max = self.membership.max_talkers(channel).await.max(1);
+        let now = self.clock.now();
+        let result = {
+            let mut map = self.talkers.write().await;
+            let set = map.entry(channel).or_insert_with(|| TalkerSet::new(window));
+            set.set_window(window);
+            set.try_acquire(sender, vad, max, now)
+        };
+        match result {
+            AcquireResult::Granted => true,
+            AcquireResult::Denied => false,
+            AcquireResult::Preempted(preempted) => {
+                debug!(
+                    channel_id = %channel.0,
+                    preempted = %preempted.0,
+                    by = %sender.0,
+                    "talker slot preempted"
+                );
+                if let Some(tx) = &self.preemption_tx {
+                    let _ = tx.try_send(TalkerPreemption {
+                        channel,
+                        preempted,
+                        by: sender,
+                    });
+                }
+                true
+            }
+        }
     }
 }
 
@@ -342,7 +844,139 @@ pub fn build_forwarded_voice_datagram(
     channel: ChannelId,
     datagram: &Bytes,
 ) -> Option<Bytes> {
-    let payload = &datagram[vp_voice::CLIENT_VOICE_HEADER_BYTES..];
+    let payload = parsed.payload(datagram);
+    encode_forwarded_voice(max_wire, parsed, sender, channel, payload)
+}
+
+/// Re-emit a multi-frame datagram as one forwarded datagram per frame, with
+/// per-frame seq/ts derived from the bundle header.
+pub fn build_split_voice_datagrams(
+    max_wire: usize,
+    parsed: &VoicePacket,
+    sender: UserId,
+    channel: ChannelId,
+    datagram: &Bytes,
+) -> Option<Vec<Bytes>> {
+    let payload = parsed.payload(datagram);
+    let (frame_ms, frames) = vp_voice::split_multi_frame_payload(payload)?;
+    frames
+        .into_iter()
+        .enumerate()
+        .map(|(i, frame)| {
+            let frame_pkt = VoicePacket {
+                flags: parsed.flags & !vp_voice::VOICE_FLAG_MULTI_FRAME,
+                seq: parsed.seq.wrapping_add(i as u32),
+                ts_ms: parsed.ts_ms.wrapping_add(i as u32 * u32::from(frame_ms)),
+                ..*parsed
+            };
+            encode_forwarded_voice(max_wire, &frame_pkt, sender, channel, frame)
+        })
+        .collect()
+}
+
+/// Hand each Opus frame of a datagram to `sink`, splitting multi-frame
+/// bundles the same way [`build_split_voice_datagrams`] does.
+async fn sink_voice_frames(
+    sink: &dyn VoiceSink,
+    parsed: &VoicePacket,
+    sender: UserId,
+    channel: ChannelId,
+    payload: &[u8],
+) {
+    let bundle = parsed
+        .is_multi_frame()
+        .then(|| vp_voice::split_multi_frame_payload(payload))
+        .flatten();
+    let Some((frame_ms, frames)) = bundle else {
+        sink.on_packet(
+            channel,
+            sender,
+            parsed.ssrc,
+            parsed.seq,
+            parsed.ts_ms,
+            payload,
+        )
+        .await;
+        return;
+    };
+    for (i, frame) in frames.into_iter().enumerate() {
+        let i = i as u32;
+        let seq = parsed.seq.wrapping_add(i);
+        let ts_ms = parsed.ts_ms.wrapping_add(i * u32::from(frame_ms));
+        sink.on_packet(channel, sender, parsed.ssrc, seq, ts_ms, frame)
+            .await;
+    }
+}
+
+/// One [`MixEngine`] per channel, so a busy channel's codec work never holds
+/// up pushes or ticks in another. Engine locks are only taken on the
+/// blocking pool.
+struct MixEngines {
+    codec: Arc<dyn MixCodec>,
+    channels: std::sync::Mutex<HashMap<ChannelId, Arc<std::sync::Mutex<MixEngine>>>>,
+}
+
+impl MixEngines {
+    fn new(codec: Arc<dyn MixCodec>) -> Self {
+        Self {
+            codec,
+            channels: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn engine(&self, channel: ChannelId) -> Arc<std::sync::Mutex<MixEngine>> {
+        let mut channels = self.channels.lock().expect("mix engines poisoned");
+        channels
+            .entry(channel)
+            .or_insert_with(|| Arc::new(std::sync::Mutex::new(MixEngine::new(self.codec.clone()))))
+            .clone()
+    }
+
+    fn active(&self) -> Vec<(ChannelId, Arc<std::sync::Mutex<MixEngine>>)> {
+        let channels = self.channels.lock().expect("mix engines poisoned");
+        channels.iter().map(|(c, e)| (*c, e.clone())).collect()
+    }
+
+    /// Drop engines whose channel has gone idle. An engine busy on the
+    /// blocking pool is left for the next tick.
+    fn prune_idle(&self) {
+        let mut channels = self.channels.lock().expect("mix engines poisoned");
+        channels.retain(|_, engine| {
+            !matches!(engine.try_lock(), Ok(engine) if engine.active_channels().is_empty())
+        });
+    }
+}
+
+/// Wrap a mixed frame in the forwarded layout. The channel itself stands in
+/// as the sender, so clients treat the mix as one ordinary talker.
+fn encode_mixed_voice(max_wire: usize, channel: ChannelId, frame: &MixedFrame) -> Option<Bytes> {
+    let parsed = VoicePacket {
+        flags: vp_voice::VOICE_FLAG_VAD,
+        header_len: vp_voice::FORWARDED_VOICE_HEADER_BYTES,
+        payload_end: vp_voice::FORWARDED_VOICE_HEADER_BYTES + frame.payload.len(),
+        channel_route: frame.channel_route,
+        ssrc: crate::voice_mixer::MIX_SSRC,
+        seq: frame.seq,
+        ts_ms: frame.ts_ms,
+        vad: true,
+        loudness: None,
+    };
+    encode_forwarded_voice(
+        max_wire,
+        &parsed,
+        UserId(channel.0),
+        channel,
+        &frame.payload,
+    )
+}
+
+fn encode_forwarded_voice(
+    max_wire: usize,
+    parsed: &VoicePacket,
+    sender: UserId,
+    channel: ChannelId,
+    payload: &[u8],
+) -> Option<Bytes> {
     let total = vp_voice::FORWARDED_VOICE_HEADER_BYTES + payload.len();
     // Receivers enforce APP_MEDIA_MTU regardless of advertised QUIC datagram max.
     let max_app = max_wire.min(vp_voice::APP_MEDIA_MTU);
@@ -351,7 +985,9 @@ pub fn build_forwarded_voice_datagram(
     }
     let mut out = BytesMut::with_capacity(total);
     out.put_u8(1);
-    out.put_u8(parsed.flags);
+    // Loudness is consumed here; the forwarded layout has no byte for it.
+    // The whisper flag is kept (its trailer is not) so receivers can tell.
+    out.put_u8(parsed.flags & !vp_voice::VOICE_FLAG_LOUDNESS);
     out.put_u16(vp_voice::FORWARDED_VOICE_HEADER_BYTES as u16);
     out.put_u32(parsed.channel_route);
     out.put_u32(parsed.ssrc);
@@ -366,52 +1002,166 @@ pub fn build_forwarded_voice_datagram(
 #[derive(Clone, Copy, Debug)]
 pub struct VoicePacket {
     flags: u8,
+    header_len: usize,
+    /// End of the voice payload; short of the datagram length when a whisper
+    /// trailer follows.
+    payload_end: usize,
     channel_route: u32,
     ssrc: u32,
     seq: u32,
     ts_ms: u32,
     vad: bool,
+    loudness: Option<u8>,
 }
 impl VoicePacket {
     fn parse(b: &Bytes) -> Result<Self> {
-        if b.len() < vp_voice::CLIENT_VOICE_HEADER_BYTES {
-            return Err(anyhow!("short"));
-        }
-        if b[0] != 1 {
-            return Err(anyhow!("bad version"));
-        }
-        let flags = b[1];
-        if u16::from_be_bytes([b[2], b[3]]) as usize != vp_voice::CLIENT_VOICE_HEADER_BYTES {
+        let (hdr, body) =
+            vp_voice::parse_voice_header(b).ok_or_else(|| anyhow!("malformed voice header"))?;
+        // Clients never send the forwarded layout; only the forwarder adds it.
+        if hdr.header_len != vp_voice::CLIENT_VOICE_HEADER_BYTES
+            && hdr.header_len != vp_voice::CLIENT_VOICE_HEADER_WITH_LOUDNESS_BYTES
+        {
             return Err(anyhow!("bad header len"));
         }
+        let payload_len = if hdr.flags & vp_voice::VOICE_FLAG_WHISPER != 0 {
+            let (payload, _) = vp_voice::split_whisper_trailer(body)
+                .ok_or_else(|| anyhow!("malformed whisper trailer"))?;
+            payload.len()
+        } else {
+            body.len()
+        };
         Ok(Self {
-            flags,
-            channel_route: u32::from_be_bytes([b[4], b[5], b[6], b[7]]),
-            ssrc: u32::from_be_bytes([b[8], b[9], b[10], b[11]]),
-            seq: u32::from_be_bytes([b[12], b[13], b[14], b[15]]),
-            ts_ms: u32::from_be_bytes([b[16], b[17], b[18], b[19]]),
-            vad: (flags & 0x01) != 0,
+            flags: hdr.flags,
+            header_len: hdr.header_len,
+            payload_end: hdr.header_len + payload_len,
+            channel_route: hdr.channel_route,
+            ssrc: hdr.ssrc,
+            seq: hdr.seq,
+            ts_ms: hdr.ts_ms,
+            vad: (hdr.flags & vp_voice::VOICE_FLAG_VAD) != 0,
+            loudness: hdr.loudness,
         })
     }
+    fn is_multi_frame(&self) -> bool {
+        self.flags & vp_voice::VOICE_FLAG_MULTI_FRAME != 0
+    }
+    fn payload<'a>(&self, datagram: &'a [u8]) -> &'a [u8] {
+        &datagram[self.header_len..self.payload_end]
+    }
+    /// Target hashes of a whisper, `None` for ordinary voice.
+    fn whisper_targets(&self, datagram: &[u8]) -> Option<Vec<u32>> {
+        if self.flags & vp_voice::VOICE_FLAG_WHISPER == 0 {
+            return None;
+        }
+        vp_voice::split_whisper_trailer(&datagram[self.header_len..]).map(|(_, t)| t)
+    }
 }
 
 const REFILL_QUANTUM: Duration = Duration::from_millis(10);
 const STREAM_IDLE_RESET: Duration = Duration::from_secs(10);
+/// Accepted sequence numbers remembered per stream for duplicate detection.
+const RECENT_SEQS: usize = 64;
+/// Replay windows kept per sender; past this the least recently heard
+/// stream is forgotten.
+const MAX_STREAMS_PER_SENDER: usize = 8;
+const PROBE_WINDOW: Duration = Duration::from_secs(1);
+/// Probing senders get this fraction of the normal pps/bps budget.
+const PROBE_PENALTY_DIVISOR: u32 = 4;
+
+struct ProbeState {
+    window_start: Instant,
+    strikes: u32,
+    penalized_until: Option<Instant>,
+}
+impl ProbeState {
+    fn new(now: Instant) -> Self {
+        Self {
+            window_start: now,
+            strikes: 0,
+            penalized_until: None,
+        }
+    }
+    /// Count one not-member datagram; returns true when this strike starts
+    /// a new penalty (an ongoing one is only extended).
+    fn strike(&mut self, limit: u32, penalty: Duration, now: Instant) -> bool {
+        if now.duration_since(self.window_start) >= PROBE_WINDOW {
+            self.window_start = now;
+            self.strikes = 0;
+        }
+        self.strikes = self.strikes.saturating_add(1);
+        if self.strikes <= limit {
+            return false;
+        }
+        let started = !self.is_penalized(now);
+        self.penalized_until = Some(now + penalty);
+        started
+    }
+    fn is_penalized(&self, now: Instant) -> bool {
+        self.penalized_until.is_some_and(|until| now < until)
+    }
+}
+
+fn duration_ns(d: Duration) -> u64 {
+    u64::try_from(d.as_nanos()).unwrap_or(u64::MAX)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RateDecision {
+    Allow,
+    Replay,
+    Limited,
+}
+
+/// One sender's voice budget and replay windows. The budget is shared by
+/// all of their streams, so rotating the ssrc doesn't buy a fresh one.
+struct SenderRate {
+    budget: RateState,
+    streams: HashMap<u32, ReplayWindow>,
+}
+impl SenderRate {
+    fn new(pps_limit: u32, bps_limit: u32, now: Instant) -> Self {
+        Self {
+            budget: RateState::new(pps_limit, bps_limit, now),
+            streams: HashMap::new(),
+        }
+    }
+    /// [`ReplayWindow::check_replay`] against the window of stream `ssrc`.
+    fn check_replay(
+        &mut self,
+        ssrc: u32,
+        seq: u32,
+        ts: u32,
+        reorder_window: u32,
+        now: Instant,
+    ) -> bool {
+        if !self.streams.contains_key(&ssrc) && self.streams.len() >= MAX_STREAMS_PER_SENDER {
+            let oldest = self
+                .streams
+                .iter()
+                .min_by_key(|(_, w)| w.last_seen)
+                .map(|(&ssrc, _)| ssrc);
+            if let Some(oldest) = oldest {
+                self.streams.remove(&oldest);
+            }
+        }
+        self.streams
+            .entry(ssrc)
+            .or_insert_with(|| ReplayWindow::new(now))
+            .check_replay(seq, ts, reorder_window, now)
+    }
+}
+
 struct RateState {
     last: Instant,
     tokens_pkts: u32,
     tokens_bytes: u32,
-    last_ts_ms: Option<u32>,
-    last_seen: Instant,
 }
 impl RateState {
-    fn new(pps_limit: u32, bps_limit: u32) -> Self {
+    fn new(pps_limit: u32, bps_limit: u32, now: Instant) -> Self {
         Self {
-            last: Instant::now(),
+            last: now,
             tokens_pkts: pps_limit,
             tokens_bytes: bps_limit,
-            last_ts_ms: None,
-            last_seen: Instant::now(),
         }
     }
     fn refill(&mut self, pps_limit: u32, bps_limit: u32, now: Instant) {
@@ -424,6 +1174,56 @@ impl RateState {
         self.tokens_bytes = (self.tokens_bytes + (secs * bps_limit as f32) as u32).min(bps_limit);
         self.last = now;
     }
+}
+
+struct ReplayWindow {
+    last_ts_ms: Option<u32>,
+    last_seen: Instant,
+    highest_seq: Option<u32>,
+    recent_seqs: VecDeque<u32>,
+}
+impl ReplayWindow {
+    fn new(now: Instant) -> Self {
+        Self {
+            last_ts_ms: None,
+            last_seen: now,
+            highest_seq: None,
+            recent_seqs: VecDeque::with_capacity(RECENT_SEQS),
+        }
+    }
+    /// Reject a datagram whose timestamp jumps implausibly, whose sequence
+    /// number was already accepted, or which is more than `reorder_window`
+    /// sequence numbers behind the newest one. Accepted ones are remembered.
+    fn check_replay(&mut self, seq: u32, ts: u32, reorder_window: u32, now: Instant) -> bool {
+        if now.duration_since(self.last_seen) > STREAM_IDLE_RESET {
+            self.highest_seq = None;
+            self.recent_seqs.clear();
+        }
+        if !self.is_fresh_seq(seq, reorder_window) || !self.check_monotonic_ts(ts, now) {
+            return false;
+        }
+        if self
+            .highest_seq
+            .is_none_or(|highest| (seq.wrapping_sub(highest) as i32) > 0)
+        {
+            self.highest_seq = Some(seq);
+        }
+        if self.recent_seqs.len() == RECENT_SEQS {
+            self.recent_seqs.pop_front();
+        }
+        self.recent_seqs.push_back(seq);
+        true
+    }
+    fn is_fresh_seq(&self, seq: u32, reorder_window: u32) -> bool {
+        let Some(highest) = self.highest_seq else {
+            return true;
+        };
+        if (seq.wrapping_sub(highest) as i32) > 0 {
+            return true;
+        }
+        let behind = highest.wrapping_sub(seq);
+        behind <= reorder_window.min(RECENT_SEQS as u32) && !self.recent_seqs.contains(&seq)
+    }
     fn check_monotonic_ts(&mut self, ts: u32, now: Instant) -> bool {
         if now.duration_since(self.last_seen) > STREAM_IDLE_RESET {
             self.last_ts_ms = None;
@@ -439,38 +1239,170 @@ impl RateState {
         true
     }
 }
-struct TalkerSet {
-    window: Duration,
-    last_seen: HashMap<UserId, Instant>,
-    order: VecDeque<(UserId, Instant)>,
+/// How long a talker's last reported loudness is kept once they stop sending.
+const LOUDNESS_HOLD: Duration = Duration::from_millis(600);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TalkerLoudness {
+    pub user: UserId,
+    /// Peak level since the previous update, on the [`vp_voice::loudness_from_dbfs`] scale.
+    pub loudness: u8,
 }
-impl TalkerSet {
-    fn new(window: Duration) -> Self {
+
+#[derive(Default)]
+struct ChannelLoudness {
+    talkers: HashMap<UserId, LoudnessSample>,
+    changed: bool,
+}
+struct LoudnessSample {
+    level: u8,
+    last_seen: Instant,
+    published: bool,
+}
+impl ChannelLoudness {
+    /// Holds the peak between publishes so a single quiet frame right before
+    /// one doesn't hide a talker.
+    fn record(&mut self, user: UserId, level: u8, now: Instant) {
+        let sample = self.talkers.entry(user).or_insert(LoudnessSample {
+            level,
+            last_seen: now,
+            published: true,
+        });
+        sample.level = if sample.published {
+            level
+        } else {
+            sample.level.max(level)
+        };
+        sample.last_seen = now;
+        sample.published = false;
+        self.changed = true;
+    }
+    /// The next [`Self::take`] reports the channel without `user`.
+    fn remove(&mut self, user: UserId) {
+        if self.talkers.remove(&user).is_some() {
+            self.changed = true;
+        }
+    }
+    fn take(&mut self, now: Instant, limit: usize) -> Option<Vec<TalkerLoudness>> {
+        let before = self.talkers.len();
+        self.talkers
+            .retain(|_, s| now.duration_since(s.last_seen) <= LOUDNESS_HOLD);
+        if self.talkers.len() != before {
+            self.changed = true;
+        }
+        if !std::mem::take(&mut self.changed) {
+            return None;
+        }
+        let mut loudest: Vec<TalkerLoudness> = self
+            .talkers
+            .iter_mut()
+            .map(|(user, s)| {
+                s.published = true;
+                TalkerLoudness {
+                    user: *user,
+                    loudness: s.level,
+                }
+            })
+            .collect();
+        loudest.sort_by(|a, b| b.loudness.cmp(&a.loudness).then(a.user.0.cmp(&b.user.0)));
+        loudest.truncate(limit);
+        Some(loudest)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum AcquireResult {
+    Granted,
+    Denied,
+    /// Granted by evicting this slot holder.
+    Preempted(UserId),
+}
+
+struct TalkerSet {
+    window: Duration,
+    last_seen: HashMap<UserId, Instant>,
+    /// Last packet each active talker sent with VAD set.
+    last_voiced: HashMap<UserId, Instant>,
+    order: VecDeque<(UserId, Instant)>,
+}
+impl TalkerSet {
+    fn new(window: Duration) -> Self {
         Self {
             window,
             last_seen: HashMap::new(),
+            last_voiced: HashMap::new(),
             order: VecDeque::new(),
         }
     }
-    fn touch(&mut self, user: UserId) {
-        let now = Instant::now();
+    /// Claim a talker slot for `sender`. With every slot taken, a sender whose
+    /// packet has VAD set evicts the holder that has been silent longest,
+    /// provided it sent no voiced packet in the last half window; otherwise
+    /// the pause between two speakers' turns would lock the next one out
+    /// until the window expires.
+    fn try_acquire(
+        &mut self,
+        sender: UserId,
+        vad: bool,
+        max: usize,
+        now: Instant,
+    ) -> AcquireResult {
+        self.prune(now);
+        if self.is_active(sender, now) || self.active_count(now) < max {
+            self.touch(sender, vad, now);
+            return AcquireResult::Granted;
+        }
+        if !vad {
+            return AcquireResult::Denied;
+        }
+        let quiet = self.window / 2;
+        let victim = self
+            .last_seen
+            .iter()
+            .filter(|(_, seen)| now.duration_since(**seen) <= self.window)
+            .map(|(user, seen)| (*user, self.last_voiced.get(user).copied().unwrap_or(*seen)))
+            .filter(|(user, voiced)| {
+                !self.last_voiced.contains_key(user) || now.duration_since(*voiced) > quiet
+            })
+            .min_by_key(|(_, voiced)| *voiced)
+            .map(|(user, _)| user);
+        let Some(victim) = victim else {
+            return AcquireResult::Denied;
+        };
+        self.last_seen.remove(&victim);
+        self.last_voiced.remove(&victim);
+        self.touch(sender, vad, now);
+        AcquireResult::Preempted(victim)
+    }
+    /// Activity is recomputed against the current window on every check, so
+    /// a changed window needs no migration of recorded timestamps.
+    fn set_window(&mut self, window: Duration) {
+        self.window = window;
+    }
+    fn touch(&mut self, user: UserId, vad: bool, now: Instant) {
         self.last_seen.insert(user, now);
+        if vad {
+            self.last_voiced.insert(user, now);
+        }
         self.order.push_back((user, now));
     }
-    fn is_active(&self, user: UserId) -> bool {
+    fn remove(&mut self, user: UserId) {
+        self.last_seen.remove(&user);
+        self.last_voiced.remove(&user);
+        self.order.retain(|(u, _)| *u != user);
+    }
+    fn is_active(&self, user: UserId, now: Instant) -> bool {
         self.last_seen
             .get(&user)
-            .map(|t| t.elapsed() <= self.window)
+            .map(|t| now.duration_since(*t) <= self.window)
             .unwrap_or(false)
     }
-    fn active_count(&self) -> usize {
+    fn active_count(&self, now: Instant) -> usize {
         self.last_seen
             .values()
-            .filter(|t| t.elapsed() <= self.window)
+            .filter(|t| now.duration_since(**t) <= self.window)
             .count()
     }
-    fn prune(&mut self) {
-        let now = Instant::now();
+    fn prune(&mut self, now: Instant) {
         while let Some((u, t)) = self.order.front().cloned() {
             if now.duration_since(t) <= self.window {
                 break;
@@ -479,6 +1411,7 @@ impl TalkerSet {
             if let Some(cur) = self.last_seen.get(&u).copied() {
                 if cur == t && now.duration_since(cur) > self.window {
                     self.last_seen.remove(&u);
+                    self.last_voiced.remove(&u);
                 }
             }
         }
@@ -492,6 +1425,7 @@ fn _log_forward_failures(failure_count: usize) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clock::ManualClock;
     use std::{
         collections::HashSet,
         sync::{
@@ -504,13 +1438,20 @@ mod tests {
     struct TestMetrics {
         forwarded: AtomicUsize,
         invalid: AtomicUsize,
+        rate_limited: AtomicUsize,
+        replay: AtomicUsize,
+        not_member: AtomicUsize,
+        not_in_voice: AtomicUsize,
+        probe_penalty: AtomicUsize,
         muted: AtomicUsize,
+        banned: AtomicUsize,
         talker_limit: AtomicUsize,
         oversize: AtomicUsize,
         session_lookup_samples: AtomicUsize,
         recipient_samples: AtomicUsize,
         fanout_samples: AtomicUsize,
         incoming_samples: AtomicUsize,
+        loudness_samples: AtomicUsize,
     }
 
     impl VoiceMetrics for TestMetrics {
@@ -519,11 +1460,27 @@ mod tests {
         fn inc_drop_invalid(&self) {
             self.invalid.fetch_add(1, Ordering::Relaxed);
         }
-        fn inc_drop_rate_limited(&self) {}
-        fn inc_drop_not_member(&self) {}
+        fn inc_drop_rate_limited(&self) {
+            self.rate_limited.fetch_add(1, Ordering::Relaxed);
+        }
+        fn inc_drop_replay(&self) {
+            self.replay.fetch_add(1, Ordering::Relaxed);
+        }
+        fn inc_drop_not_member(&self) {
+            self.not_member.fetch_add(1, Ordering::Relaxed);
+        }
+        fn inc_drop_not_in_voice(&self) {
+            self.not_in_voice.fetch_add(1, Ordering::Relaxed);
+        }
+        fn inc_drop_probe_penalty(&self) {
+            self.probe_penalty.fetch_add(1, Ordering::Relaxed);
+        }
         fn inc_drop_muted(&self) {
             self.muted.fetch_add(1, Ordering::Relaxed);
         }
+        fn inc_drop_banned(&self) {
+            self.banned.fetch_add(1, Ordering::Relaxed);
+        }
         fn inc_drop_talker_limit(&self) {
             self.talker_limit.fetch_add(1, Ordering::Relaxed);
         }
@@ -543,6 +1500,9 @@ mod tests {
         fn observe_handle_incoming_us(&self, _micros: u64) {
             self.incoming_samples.fetch_add(1, Ordering::Relaxed);
         }
+        fn observe_loudness(&self, _loudness: u8) {
+            self.loudness_samples.fetch_add(1, Ordering::Relaxed);
+        }
     }
 
     impl crate::datagram_send_policy::DatagramSendPolicyMetrics for TestMetrics {
@@ -556,22 +1516,42 @@ mod tests {
         fn inc_video_dropped_due_to_space(&self) {}
     }
 
+    /// Route hash that never matches the sender's channel.
+    const FOREIGN_ROUTE: u32 = 0xdead_beef;
+    /// Stands in for a sender whose current channel is a text channel.
+    const TEXT_ROUTE: u32 = 0x7e57_0000;
+
     struct TestMembership {
         channel: ChannelId,
         members: Vec<UserId>,
         muted: HashSet<UserId>,
         deafened: HashSet<UserId>,
+        banned: HashSet<UserId>,
         max_talkers: usize,
     }
 
+    /// `members` in `channel`, nobody muted, deafened or banned, four talkers.
+    fn membership(channel: ChannelId, members: &[UserId]) -> TestMembership {
+        TestMembership {
+            channel,
+            members: members.to_vec(),
+            muted: HashSet::new(),
+            deafened: HashSet::new(),
+            banned: HashSet::new(),
+            max_talkers: 4,
+        }
+    }
+
     #[async_trait::async_trait]
     impl MembershipProvider for TestMembership {
-        async fn resolve_channel_for_sender(
-            &self,
-            sender: UserId,
-            _route_key: u32,
-        ) -> Option<ChannelId> {
-            self.members.contains(&sender).then_some(self.channel)
+        async fn resolve_channel_for_sender(&self, sender: UserId, route_key: u32) -> SenderRoute {
+            if !self.members.contains(&sender) || route_key == FOREIGN_ROUTE {
+                SenderRoute::NotMember
+            } else if route_key == TEXT_ROUTE {
+                SenderRoute::NotInVoice
+            } else {
+                SenderRoute::Channel(self.channel)
+            }
         }
 
         async fn list_members(&self, _channel: ChannelId) -> Vec<UserId> {
@@ -586,6 +1566,10 @@ mod tests {
             self.deafened.contains(&user)
         }
 
+        async fn is_banned(&self, _channel: ChannelId, user: UserId) -> bool {
+            self.banned.contains(&user)
+        }
+
         async fn max_talkers(&self, _channel: ChannelId) -> usize {
             self.max_talkers
         }
@@ -594,6 +1578,7 @@ mod tests {
     struct TestTx {
         session_id: String,
         max_wire: Option<usize>,
+        multi_frame: bool,
         sent: Arc<Mutex<Vec<Bytes>>>,
     }
 
@@ -608,6 +1593,9 @@ mod tests {
         fn max_datagram_size(&self) -> Option<usize> {
             self.max_wire
         }
+        fn supports_voice_multi_frame(&self) -> bool {
+            self.multi_frame
+        }
         fn send_voice(
             &self,
             _now_ms: u64,
@@ -629,11 +1617,46 @@ mod tests {
         }
     }
 
+    impl TestTx {
+        /// A single-frame session with no size limit that records what it's sent.
+        fn new(session_id: &str) -> Self {
+            Self {
+                session_id: session_id.to_string(),
+                max_wire: None,
+                multi_frame: false,
+                sent: Arc::new(Mutex::new(Vec::new())),
+            }
+        }
+
+        fn sent_count(&self) -> usize {
+            self.sent.lock().expect("test tx lock poisoned").len()
+        }
+    }
+
+    fn test_tx(session_id: &str) -> Arc<TestTx> {
+        Arc::new(TestTx::new(session_id))
+    }
+
     #[derive(Default)]
     struct TestSessions {
         sessions: HashMap<UserId, Vec<(String, Arc<dyn DatagramTx>)>>,
     }
 
+    impl TestSessions {
+        /// One entry per session; a user may appear more than once.
+        fn of(entries: impl IntoIterator<Item = (UserId, Arc<TestTx>)>) -> Self {
+            let mut sessions = HashMap::<UserId, Vec<(String, Arc<dyn DatagramTx>)>>::new();
+            for (user, tx) in entries {
+                let id = tx.session_id.clone();
+                sessions
+                    .entry(user)
+                    .or_default()
+                    .push((id, tx as Arc<dyn DatagramTx>));
+            }
+            Self { sessions }
+        }
+    }
+
     #[async_trait::async_trait]
     impl SessionRegistry for TestSessions {
         async fn get_sessions(&self, user: UserId) -> Vec<(String, Arc<dyn DatagramTx>)> {
@@ -641,6 +1664,39 @@ mod tests {
         }
     }
 
+    struct Harness {
+        forwarder: VoiceForwarder,
+        metrics: Arc<TestMetrics>,
+        clock: Arc<ManualClock>,
+        _prune_rx: mpsc::Receiver<()>,
+    }
+
+    /// A forwarder over `membership` and `sessions` with counting metrics and
+    /// a clock that only moves when the test advances it.
+    fn forwarder_with(
+        cfg: VoiceForwarderConfig,
+        membership: TestMembership,
+        sessions: TestSessions,
+    ) -> Harness {
+        let metrics = Arc::new(TestMetrics::default());
+        let clock = Arc::new(ManualClock::new());
+        let (prune_tx, prune_rx) = mpsc::channel(8);
+        let forwarder = VoiceForwarder::new_with_clock(
+            cfg,
+            Arc::new(sessions),
+            Arc::new(membership),
+            metrics.clone(),
+            prune_tx,
+            clock.clone(),
+        );
+        Harness {
+            forwarder,
+            metrics,
+            clock,
+            _prune_rx: prune_rx,
+        }
+    }
+
     fn make_voice_datagram(channel_route: u32, vad: bool) -> Bytes {
         let mut bytes = BytesMut::new();
         bytes.extend_from_slice(&[1, if vad { 0x01 } else { 0x00 }]);
@@ -653,6 +1709,45 @@ mod tests {
         bytes.freeze()
     }
 
+    /// Like [`make_voice_datagram`] but with VAD set and the given `seq`, for
+    /// tests that send one stream repeatedly.
+    fn make_voice_datagram_seq(channel_route: u32, seq: u32) -> Bytes {
+        let mut bytes = BytesMut::new();
+        bytes.extend_from_slice(&[1, vp_voice::VOICE_FLAG_VAD]);
+        bytes.put_u16(vp_voice::CLIENT_VOICE_HEADER_BYTES as u16);
+        bytes.put_u32(channel_route);
+        bytes.put_u32(2);
+        bytes.put_u32(seq);
+        bytes.put_u32(seq * 20);
+        bytes.extend_from_slice(&[7; 64]);
+        bytes.freeze()
+    }
+
+    /// A voiced whisper on route 1 addressed to `targets`.
+    fn make_whisper_datagram(seq: u32, targets: &[UserId]) -> Bytes {
+        let mut whisper = make_voice_datagram_seq(1, seq).to_vec();
+        whisper[1] |= vp_voice::VOICE_FLAG_WHISPER;
+        let hashes: Vec<u32> = targets
+            .iter()
+            .map(|uid| vp_voice::whisper_target_hash(uid.0.as_bytes()))
+            .collect();
+        assert!(vp_voice::encode_whisper_trailer(&hashes, &mut whisper));
+        whisper.into()
+    }
+
+    fn make_loud_voice_datagram(channel_route: u32, seq: u32, loudness: u8) -> Bytes {
+        let mut bytes = BytesMut::new();
+        bytes.extend_from_slice(&[1, vp_voice::VOICE_FLAG_VAD | vp_voice::VOICE_FLAG_LOUDNESS]);
+        bytes.put_u16(vp_voice::CLIENT_VOICE_HEADER_WITH_LOUDNESS_BYTES as u16);
+        bytes.put_u32(channel_route);
+        bytes.put_u32(2);
+        bytes.put_u32(seq);
+        bytes.put_u32(seq * 20);
+        bytes.put_u8(loudness);
+        bytes.extend_from_slice(&[7; 64]);
+        bytes.freeze()
+    }
+
     #[test]
     fn build_forwarded_voice_respects_max() {
         let sender = UserId::new();
@@ -683,69 +1778,134 @@ mod tests {
         .is_some());
     }
 
-    #[tokio::test]
-    async fn forwards_to_all_eligible_sessions_without_duplicates() {
-        let channel = ChannelId::new();
+    #[test]
+    fn parse_rejects_forwarded_layout_and_header_only_datagrams() {
         let sender = UserId::new();
-        let r1 = UserId::new();
-        let r2 = UserId::new();
-        let membership = Arc::new(TestMembership {
+        let channel = ChannelId::new();
+        let client = make_voice_datagram(1, true);
+        let parsed = VoicePacket::parse(&client).unwrap();
+        let forwarded = build_forwarded_voice_datagram(
+            vp_voice::APP_MEDIA_MTU,
+            &parsed,
+            sender,
             channel,
-            members: vec![sender, r1, r2],
-            muted: HashSet::new(),
-            deafened: HashSet::new(),
-            max_talkers: 10,
-        });
+            &client,
+        )
+        .unwrap();
+        assert!(VoicePacket::parse(&forwarded).is_err());
 
-        let r1s1 = Arc::new(TestTx {
-            session_id: "r1s1".to_string(),
-            max_wire: None,
-            sent: Arc::new(Mutex::new(Vec::new())),
-        });
-        let r1s2 = Arc::new(TestTx {
-            session_id: "r1s2".to_string(),
-            max_wire: None,
-            sent: Arc::new(Mutex::new(Vec::new())),
-        });
-        let r2s1 = Arc::new(TestTx {
-            session_id: "r2s1".to_string(),
-            max_wire: None,
-            sent: Arc::new(Mutex::new(Vec::new())),
-        });
+        let header_only = client.slice(..vp_voice::CLIENT_VOICE_HEADER_BYTES);
+        assert!(VoicePacket::parse(&header_only).is_err());
+    }
 
-        let sessions = Arc::new(TestSessions {
-            sessions: HashMap::from([
-                (
-                    r1,
-                    vec![
-                        ("r1s1".into(), r1s1.clone() as Arc<dyn DatagramTx>),
-                        ("r1s2".into(), r1s2.clone() as Arc<dyn DatagramTx>),
-                    ],
-                ),
-                (
-                    r2,
-                    vec![("r2s1".into(), r2s1.clone() as Arc<dyn DatagramTx>)],
-                ),
+    #[tokio::test]
+    async fn whisper_reaches_only_its_targets_without_the_trailer() {
+        let channel = ChannelId::new();
+        let sender = UserId::new();
+        let target = UserId::new();
+        let bystander = UserId::new();
+        let (target_tx, bystander_tx) = (test_tx("target"), test_tx("bystander"));
+        let Harness {
+            forwarder, metrics, ..
+        } = forwarder_with(
+            VoiceForwarderConfig::default(),
+            membership(channel, &[sender, target, bystander]),
+            TestSessions::of([
+                (target, target_tx.clone()),
+                (bystander, bystander_tx.clone()),
             ]),
-        });
+        );
 
-        let metrics = Arc::new(TestMetrics::default());
-        let (prune_tx, _prune_rx) = mpsc::channel(4);
-        let forwarder = VoiceForwarder::new(
+        let mut whisper = make_voice_datagram(1, true).to_vec();
+        whisper[1] |= vp_voice::VOICE_FLAG_WHISPER;
+        let hashes = [vp_voice::whisper_target_hash(target.0.as_bytes())];
+        assert!(vp_voice::encode_whisper_trailer(&hashes, &mut whisper));
+        forwarder.handle_incoming(sender, whisper.into()).await;
+
+        assert_eq!(bystander_tx.sent_count(), 0);
+        let sent = target_tx.sent.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        let (hdr, payload) = vp_voice::parse_voice_header(&sent[0]).unwrap();
+        assert_ne!(hdr.flags & vp_voice::VOICE_FLAG_WHISPER, 0);
+        assert_eq!(payload, &[7; 64]);
+        drop(sent);
+
+        // A flagged datagram without a valid trailer goes nowhere.
+        let mut bad = make_voice_datagram(1, true).to_vec();
+        bad[1] |= vp_voice::VOICE_FLAG_WHISPER;
+        forwarder.handle_incoming(sender, bad.into()).await;
+        assert_eq!(target_tx.sent_count(), 1);
+        assert_eq!(metrics.invalid.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn whisper_takes_no_talker_slot_and_preempts_nobody() {
+        let channel = ChannelId::new();
+        let talker = UserId::new();
+        let whisperer = UserId::new();
+        let listener = UserId::new();
+        let ltx = test_tx("listener");
+        let Harness {
+            forwarder,
+            metrics,
+            clock,
+            ..
+        } = forwarder_with(
             VoiceForwarderConfig::default(),
-            sessions,
-            membership,
-            metrics.clone(),
-            prune_tx,
+            TestMembership {
+                max_talkers: 1,
+                ..membership(channel, &[talker, whisperer, listener])
+            },
+            TestSessions::of([(listener, ltx.clone())]),
+        );
+        let (preempt_tx, mut preempt_rx) = mpsc::channel(4);
+        let forwarder = forwarder.with_preemption_events(preempt_tx);
+
+        forwarder
+            .handle_incoming(talker, make_voice_datagram_seq(1, 1))
+            .await;
+        // Long enough for a voiced newcomer to take over the slot.
+        clock.advance(Duration::from_millis(500));
+        forwarder
+            .handle_incoming(whisperer, make_whisper_datagram(1, &[listener]))
+            .await;
+        assert_eq!(ltx.sent_count(), 2);
+        assert!(preempt_rx.try_recv().is_err());
+
+        // The talker still holds the only slot.
+        forwarder
+            .handle_incoming(talker, make_voice_datagram_seq(1, 2))
+            .await;
+        assert_eq!(ltx.sent_count(), 3);
+        assert_eq!(metrics.talker_limit.load(Ordering::Relaxed), 0);
+        assert!(preempt_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn forwards_to_all_eligible_sessions_without_duplicates() {
+        let channel = ChannelId::new();
+        let sender = UserId::new();
+        let r1 = UserId::new();
+        let r2 = UserId::new();
+        let (r1s1, r1s2, r2s1) = (test_tx("r1s1"), test_tx("r1s2"), test_tx("r2s1"));
+        let Harness {
+            forwarder, metrics, ..
+        } = forwarder_with(
+            VoiceForwarderConfig::default(),
+            TestMembership {
+                max_talkers: 10,
+                ..membership(channel, &[sender, r1, r2])
+            },
+            TestSessions::of([(r1, r1s1.clone()), (r1, r1s2.clone()), (r2, r2s1.clone())]),
         );
 
         forwarder
             .handle_incoming(sender, make_voice_datagram(1, true))
             .await;
 
-        assert_eq!(r1s1.sent.lock().unwrap().len(), 1);
-        assert_eq!(r1s2.sent.lock().unwrap().len(), 1);
-        assert_eq!(r2s1.sent.lock().unwrap().len(), 1);
+        assert_eq!(r1s1.sent_count(), 1);
+        assert_eq!(r1s2.sent_count(), 1);
+        assert_eq!(r2s1.sent_count(), 1);
         assert_eq!(metrics.forwarded.load(Ordering::Relaxed), 3);
         assert_eq!(metrics.session_lookup_samples.load(Ordering::Relaxed), 1);
         assert_eq!(metrics.recipient_samples.load(Ordering::Relaxed), 1);
@@ -760,29 +1920,20 @@ mod tests {
         let sender_b = UserId::new();
         let sender_c = UserId::new();
         let listener = UserId::new();
-        let membership = Arc::new(TestMembership {
-            channel,
-            members: vec![sender_a, sender_b, sender_c, listener],
-            muted: HashSet::from([sender_a]),
-            deafened: HashSet::new(),
-            max_talkers: 1,
-        });
-        let ltx = Arc::new(TestTx {
-            session_id: "listener".to_string(),
-            max_wire: None,
-            sent: Arc::new(Mutex::new(Vec::new())),
-        });
-        let sessions = Arc::new(TestSessions {
-            sessions: HashMap::from([(
-                listener,
-                vec![("listener".into(), ltx as Arc<dyn DatagramTx>)],
-            )]),
-        });
-        let metrics = Arc::new(TestMetrics::default());
-        let (prune_tx, _prune_rx) = mpsc::channel(4);
-        let mut cfg = VoiceForwarderConfig::default();
-        cfg.vad_required_for_talker = true;
-        let forwarder = VoiceForwarder::new(cfg, sessions, membership, metrics.clone(), prune_tx);
+        let Harness {
+            forwarder, metrics, ..
+        } = forwarder_with(
+            VoiceForwarderConfig {
+                vad_required_for_talker: true,
+                ..VoiceForwarderConfig::default()
+            },
+            TestMembership {
+                muted: HashSet::from([sender_a]),
+                max_talkers: 1,
+                ..membership(channel, &[sender_a, sender_b, sender_c, listener])
+            },
+            TestSessions::of([(listener, test_tx("listener"))]),
+        );
 
         forwarder
             .handle_incoming(sender_a, make_voice_datagram(1, true))
@@ -798,58 +1949,842 @@ mod tests {
         assert_eq!(metrics.talker_limit.load(Ordering::Relaxed), 1);
     }
 
+    #[test]
+    fn talker_set_preempts_only_quiet_holders_for_voiced_senders() {
+        let clock = ManualClock::new();
+        let window = Duration::from_millis(800);
+        let (a, b, c) = (UserId::new(), UserId::new(), UserId::new());
+        let mut set = TalkerSet::new(window);
+        assert_eq!(
+            set.try_acquire(a, true, 1, clock.now()),
+            AcquireResult::Granted
+        );
+
+        clock.advance(Duration::from_millis(300));
+        assert_eq!(
+            set.try_acquire(a, false, 1, clock.now()),
+            AcquireResult::Granted
+        );
+        // A still voiced within the last half window.
+        assert_eq!(
+            set.try_acquire(b, true, 1, clock.now()),
+            AcquireResult::Denied
+        );
+
+        clock.advance(Duration::from_millis(200));
+        assert_eq!(
+            set.try_acquire(c, false, 1, clock.now()),
+            AcquireResult::Denied
+        );
+        assert_eq!(
+            set.try_acquire(b, true, 1, clock.now()),
+            AcquireResult::Preempted(a)
+        );
+        assert!(!set.is_active(a, clock.now()));
+        assert_eq!(
+            set.try_acquire(a, true, 1, clock.now()),
+            AcquireResult::Denied
+        );
+    }
+
     #[tokio::test]
-    async fn load_style_50_member_multi_session_fanout() {
+    async fn voiced_sender_preempts_quiet_talker_and_reports_it() {
+        let channel = ChannelId::new();
+        let sender_a = UserId::new();
+        let sender_b = UserId::new();
+        let listener = UserId::new();
+        let ltx = test_tx("listener");
+        let Harness {
+            forwarder,
+            metrics,
+            clock,
+            ..
+        } = forwarder_with(
+            VoiceForwarderConfig::default(),
+            TestMembership {
+                max_talkers: 1,
+                ..membership(channel, &[sender_a, sender_b, listener])
+            },
+            TestSessions::of([(listener, ltx.clone())]),
+        );
+        let (preempt_tx, mut preempt_rx) = mpsc::channel(4);
+        let forwarder = forwarder.with_preemption_events(preempt_tx);
+
+        forwarder
+            .handle_incoming(sender_a, make_voice_datagram_seq(1, 1))
+            .await;
+        clock.advance(Duration::from_millis(500));
+        forwarder
+            .handle_incoming(sender_b, make_voice_datagram_seq(1, 1))
+            .await;
+
+        assert_eq!(ltx.sent_count(), 2);
+        assert_eq!(metrics.talker_limit.load(Ordering::Relaxed), 0);
+        assert_eq!(
+            preempt_rx.try_recv().unwrap(),
+            TalkerPreemption {
+                channel,
+                preempted: sender_a,
+                by: sender_b,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn talker_slot_frees_once_manual_clock_passes_window() {
+        let channel = ChannelId::new();
+        let sender_a = UserId::new();
+        let sender_b = UserId::new();
+        let listener = UserId::new();
+        let ltx = test_tx("listener");
+        let cfg = VoiceForwarderConfig::default();
+        let window = cfg.talker_activity_window;
+        let Harness {
+            forwarder,
+            metrics,
+            clock,
+            ..
+        } = forwarder_with(
+            cfg,
+            TestMembership {
+                max_talkers: 1,
+                ..membership(channel, &[sender_a, sender_b, listener])
+            },
+            TestSessions::of([(listener, ltx.clone())]),
+        );
+
+        forwarder
+            .handle_incoming(sender_a, make_voice_datagram(1, true))
+            .await;
+        forwarder
+            .handle_incoming(sender_b, make_voice_datagram_seq(1, 1))
+            .await;
+        assert_eq!(metrics.talker_limit.load(Ordering::Relaxed), 1);
+
+        // Still inside the window: A holds the only slot.
+        clock.advance(window / 2);
+        forwarder
+            .handle_incoming(sender_b, make_voice_datagram_seq(1, 2))
+            .await;
+        assert_eq!(metrics.talker_limit.load(Ordering::Relaxed), 2);
+
+        clock.advance(window);
+        forwarder
+            .handle_incoming(sender_b, make_voice_datagram_seq(1, 3))
+            .await;
+        assert_eq!(metrics.talker_limit.load(Ordering::Relaxed), 2);
+        assert_eq!(ltx.sent_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn shrinking_talker_window_frees_slot_immediately() {
+        let channel = ChannelId::new();
+        let sender_a = UserId::new();
+        let sender_b = UserId::new();
+        let listener = UserId::new();
+        let ltx = test_tx("listener");
+        let Harness {
+            forwarder,
+            metrics,
+            clock,
+            ..
+        } = forwarder_with(
+            VoiceForwarderConfig::default(),
+            TestMembership {
+                max_talkers: 1,
+                ..membership(channel, &[sender_a, sender_b, listener])
+            },
+            TestSessions::of([(listener, ltx.clone())]),
+        );
+
+        forwarder
+            .handle_incoming(sender_a, make_voice_datagram(1, true))
+            .await;
+        clock.advance(Duration::from_millis(300));
+        forwarder
+            .handle_incoming(sender_b, make_voice_datagram_seq(1, 1))
+            .await;
+        assert_eq!(metrics.talker_limit.load(Ordering::Relaxed), 1);
+
+        let mut tuning = forwarder.talker_tuning();
+        tuning.activity_window = Duration::from_millis(200);
+        forwarder.set_talker_tuning(tuning);
+
+        // A was last heard 300ms ago, outside the new window.
+        forwarder
+            .handle_incoming(sender_b, make_voice_datagram_seq(1, 2))
+            .await;
+        assert_eq!(metrics.talker_limit.load(Ordering::Relaxed), 1);
+        assert_eq!(ltx.sent_count(), 2);
+    }
+
+    #[test]
+    fn rate_state_refills_only_after_quantum() {
+        let clock = ManualClock::new();
+        let mut st = RateState::new(100, 10_000, clock.now());
+        st.tokens_pkts = 0;
+        st.tokens_bytes = 0;
+
+        clock.advance(Duration::from_millis(5));
+        st.refill(100, 10_000, clock.now());
+        assert_eq!(st.tokens_pkts, 0);
+
+        clock.advance(Duration::from_millis(495));
+        st.refill(100, 10_000, clock.now());
+        assert_eq!(st.tokens_pkts, 50);
+        assert_eq!(st.tokens_bytes, 5_000);
+
+        clock.advance(Duration::from_secs(5));
+        st.refill(100, 10_000, clock.now());
+        assert_eq!(st.tokens_pkts, 100);
+        assert_eq!(st.tokens_bytes, 10_000);
+    }
+
+    #[test]
+    fn timestamp_skew_rejected_until_stream_idle_reset() {
+        let clock = ManualClock::new();
+        let mut st = ReplayWindow::new(clock.now());
+        assert!(st.check_monotonic_ts(1_000, clock.now()));
+
+        clock.advance(Duration::from_millis(20));
+        assert!(st.check_monotonic_ts(1_020, clock.now()));
+        assert!(!st.check_monotonic_ts(60_000, clock.now()));
+
+        clock.advance(STREAM_IDLE_RESET + Duration::from_millis(1));
+        assert!(st.check_monotonic_ts(60_000, clock.now()));
+    }
+
+    #[test]
+    fn replay_check_allows_reordering_within_window_only() {
+        let clock = ManualClock::new();
+        let mut st = ReplayWindow::new(clock.now());
+        assert!(st.check_replay(100, 2_000, 4, clock.now()));
+        assert!(st.check_replay(102, 2_040, 4, clock.now()));
+        assert!(st.check_replay(101, 2_020, 4, clock.now()));
+        assert!(!st.check_replay(101, 2_020, 4, clock.now()));
+        assert!(!st.check_replay(102, 2_040, 4, clock.now()));
+        assert!(st.check_replay(98, 1_960, 4, clock.now()));
+        assert!(!st.check_replay(97, 1_940, 4, clock.now()));
+
+        // A stream that went quiet may restart its counters.
+        clock.advance(STREAM_IDLE_RESET + Duration::from_millis(1));
+        assert!(st.check_replay(5, 100, 4, clock.now()));
+
+        // Sequence numbers wrap like any other u32 counter.
+        let mut st = ReplayWindow::new(clock.now());
+        assert!(st.check_replay(u32::MAX, 0, 4, clock.now()));
+        assert!(st.check_replay(0, 20, 4, clock.now()));
+        assert!(!st.check_replay(u32::MAX, 0, 4, clock.now()));
+        assert!(st.check_replay(u32::MAX - 1, 0, 4, clock.now()));
+    }
+
+    #[tokio::test]
+    async fn replayed_datagram_is_dropped() {
         let channel = ChannelId::new();
         let sender = UserId::new();
-        let mut members = vec![sender];
-        let mut sessions_map = HashMap::<UserId, Vec<(String, Arc<dyn DatagramTx>)>>::new();
-        let mut recipients = Vec::new();
+        let listener = UserId::new();
+        let ltx = test_tx("listener");
+        let Harness {
+            forwarder, metrics, ..
+        } = forwarder_with(
+            VoiceForwarderConfig::default(),
+            membership(channel, &[sender, listener]),
+            TestSessions::of([(listener, ltx.clone())]),
+        );
 
-        for idx in 0..49 {
-            let user = UserId::new();
-            members.push(user);
-            recipients.push(user);
-            let s1 = Arc::new(TestTx {
-                session_id: format!("{idx}-a"),
-                max_wire: Some(vp_voice::QUIC_MAX_DATAGRAM_BYTES),
-                sent: Arc::new(Mutex::new(Vec::new())),
-            }) as Arc<dyn DatagramTx>;
-            let s2 = Arc::new(TestTx {
-                session_id: format!("{idx}-b"),
-                max_wire: Some(vp_voice::APP_MEDIA_MTU),
-                sent: Arc::new(Mutex::new(Vec::new())),
-            }) as Arc<dyn DatagramTx>;
-            sessions_map.insert(
-                user,
-                vec![(format!("{idx}-a"), s1), (format!("{idx}-b"), s2)],
-            );
+        let datagram = make_voice_datagram_seq(1, 7);
+        forwarder.handle_incoming(sender, datagram.clone()).await;
+        forwarder.handle_incoming(sender, datagram).await;
+
+        assert_eq!(ltx.sent_count(), 1);
+        assert_eq!(metrics.replay.load(Ordering::Relaxed), 1);
+        assert_eq!(metrics.rate_limited.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn remove_receiver_forgets_per_user_state() {
+        let channel = ChannelId::new();
+        let sender = UserId::new();
+        let listener = UserId::new();
+        let ltx = test_tx("listener");
+        let Harness {
+            forwarder, metrics, ..
+        } = forwarder_with(
+            VoiceForwarderConfig::default(),
+            membership(channel, &[sender, listener]),
+            TestSessions::of([(listener, ltx.clone())]),
+        );
+
+        forwarder
+            .handle_incoming(sender, make_voice_datagram_seq(1, 7))
+            .await;
+        assert_eq!(forwarder.rate.read().await.len(), 1);
+        assert_eq!(forwarder.talkers.read().await.len(), 1);
+
+        forwarder.remove_receiver(sender).await;
+        assert!(forwarder.rate.read().await.is_empty());
+        assert!(forwarder.talkers.read().await.is_empty());
+
+        // A reconnect that restarts its sequence numbers is not a replay.
+        forwarder
+            .handle_incoming(sender, make_voice_datagram_seq(1, 7))
+            .await;
+        assert_eq!(ltx.sent_count(), 2);
+        assert_eq!(metrics.replay.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn drops_banned_sender_before_fanout() {
+        let channel = ChannelId::new();
+        let banned = UserId::new();
+        let listener = UserId::new();
+        let ltx = test_tx("listener");
+        let Harness {
+            forwarder, metrics, ..
+        } = forwarder_with(
+            VoiceForwarderConfig::default(),
+            TestMembership {
+                banned: HashSet::from([banned]),
+                ..membership(channel, &[banned, listener])
+            },
+            TestSessions::of([(listener, ltx.clone())]),
+        );
+
+        forwarder
+            .handle_incoming(banned, make_voice_datagram(1, true))
+            .await;
+
+        assert_eq!(metrics.banned.load(Ordering::Relaxed), 1);
+        assert_eq!(metrics.forwarded.load(Ordering::Relaxed), 0);
+        assert_eq!(ltx.sent_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn configured_pps_limit_drops_excess_packets() {
+        let channel = ChannelId::new();
+        let sender = UserId::new();
+        let listener = UserId::new();
+        let ltx = test_tx("listener");
+        let Harness {
+            forwarder,
+            metrics,
+            clock,
+            ..
+        } = forwarder_with(
+            VoiceForwarderConfig {
+                sender_pps_limit: 5,
+                ..VoiceForwarderConfig::default()
+            },
+            membership(channel, &[sender, listener]),
+            TestSessions::of([(listener, ltx.clone())]),
+        );
+
+        for seq in 0..8 {
+            forwarder
+                .handle_incoming(sender, make_voice_datagram_seq(1, seq))
+                .await;
         }
+        assert_eq!(ltx.sent_count(), 5);
+        assert_eq!(metrics.rate_limited.load(Ordering::Relaxed), 3);
 
-        let membership = Arc::new(TestMembership {
-            channel,
-            members,
-            muted: HashSet::new(),
-            deafened: HashSet::new(),
-            max_talkers: 10,
-        });
-        let sessions = Arc::new(TestSessions {
-            sessions: sessions_map,
+        clock.advance(Duration::from_secs(1));
+        forwarder
+            .handle_incoming(sender, make_voice_datagram_seq(1, 8))
+            .await;
+        assert_eq!(ltx.sent_count(), 6);
+    }
+
+    #[tokio::test]
+    async fn rotating_ssrc_shares_one_pps_budget() {
+        let channel = ChannelId::new();
+        let sender = UserId::new();
+        let listener = UserId::new();
+        let ltx = test_tx("listener");
+        let Harness {
+            forwarder, metrics, ..
+        } = forwarder_with(
+            VoiceForwarderConfig {
+                sender_pps_limit: 5,
+                ..VoiceForwarderConfig::default()
+            },
+            membership(channel, &[sender, listener]),
+            TestSessions::of([(listener, ltx.clone())]),
+        );
+
+        // Every datagram claims a new stream, so none of them is a replay.
+        for ssrc in 0..20u32 {
+            let mut datagram = make_voice_datagram_seq(1, 1).to_vec();
+            datagram[8..12].copy_from_slice(&ssrc.to_be_bytes());
+            forwarder.handle_incoming(sender, datagram.into()).await;
+        }
+        assert_eq!(ltx.sent_count(), 5);
+        assert_eq!(metrics.rate_limited.load(Ordering::Relaxed), 15);
+        assert_eq!(metrics.replay.load(Ordering::Relaxed), 0);
+        assert_eq!(
+            forwarder.rate.read().await[&sender].streams.len(),
+            MAX_STREAMS_PER_SENDER
+        );
+    }
+
+    #[tokio::test]
+    async fn probing_sender_spends_no_budget_then_gets_tightened_limit() {
+        let channel = ChannelId::new();
+        let sender = UserId::new();
+        let listener = UserId::new();
+        let ltx = test_tx("listener");
+        let Harness {
+            forwarder,
+            metrics,
+            clock,
+            ..
+        } = forwarder_with(
+            VoiceForwarderConfig {
+                sender_pps_limit: 40,
+                not_member_strike_limit: 3,
+                not_member_penalty: Duration::from_secs(5),
+                ..VoiceForwarderConfig::default()
+            },
+            membership(channel, &[sender, listener]),
+            TestSessions::of([(listener, ltx.clone())]),
+        );
+
+        // Within the strike limit: dropped, no budget spent, no penalty.
+        for _ in 0..3 {
+            forwarder
+                .handle_incoming(sender, make_voice_datagram(FOREIGN_ROUTE, true))
+                .await;
+        }
+        assert_eq!(metrics.not_member.load(Ordering::Relaxed), 3);
+        assert!(!forwarder.is_penalized(sender).await);
+
+        forwarder
+            .handle_incoming(sender, make_voice_datagram(FOREIGN_ROUTE, true))
+            .await;
+        assert!(forwarder.is_penalized(sender).await);
+
+        for seq in 0..40 {
+            forwarder
+                .handle_incoming(sender, make_voice_datagram_seq(1, seq))
+                .await;
+        }
+        assert_eq!(ltx.sent_count(), 10);
+        assert_eq!(metrics.probe_penalty.load(Ordering::Relaxed), 30);
+        assert_eq!(metrics.rate_limited.load(Ordering::Relaxed), 0);
+
+        clock.advance(Duration::from_secs(6));
+        assert!(!forwarder.is_penalized(sender).await);
+        for seq in 40..80 {
+            forwarder
+                .handle_incoming(sender, make_voice_datagram_seq(1, seq))
+                .await;
+        }
+        assert_eq!(ltx.sent_count(), 50);
+        assert_eq!(metrics.probe_penalty.load(Ordering::Relaxed), 30);
+    }
+
+    #[tokio::test]
+    async fn sender_in_text_channel_is_dropped_without_strikes() {
+        let channel = ChannelId::new();
+        let sender = UserId::new();
+        let listener = UserId::new();
+        let ltx = test_tx("listener");
+        let Harness {
+            forwarder, metrics, ..
+        } = forwarder_with(
+            VoiceForwarderConfig {
+                not_member_strike_limit: 1,
+                ..VoiceForwarderConfig::default()
+            },
+            membership(channel, &[sender, listener]),
+            TestSessions::of([(listener, ltx.clone())]),
+        );
+
+        for _ in 0..5 {
+            forwarder
+                .handle_incoming(sender, make_voice_datagram(TEXT_ROUTE, true))
+                .await;
+        }
+        assert_eq!(metrics.not_in_voice.load(Ordering::Relaxed), 5);
+        assert_eq!(metrics.not_member.load(Ordering::Relaxed), 0);
+        assert!(!forwarder.is_penalized(sender).await);
+        assert_eq!(ltx.sent_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn multi_frame_bundle_is_split_for_legacy_sessions_only() {
+        let channel = ChannelId::new();
+        let sender = UserId::new();
+        let legacy_user = UserId::new();
+        let modern_user = UserId::new();
+        let legacy = test_tx("legacy");
+        let modern = Arc::new(TestTx {
+            multi_frame: true,
+            ..TestTx::new("modern")
         });
-        let metrics = Arc::new(TestMetrics::default());
-        let (prune_tx, _prune_rx) = mpsc::channel(8);
-        let forwarder = VoiceForwarder::new(
+        let Harness {
+            forwarder, metrics, ..
+        } = forwarder_with(
             VoiceForwarderConfig::default(),
+            membership(channel, &[sender, legacy_user, modern_user]),
+            TestSessions::of([(legacy_user, legacy.clone()), (modern_user, modern.clone())]),
+        );
+
+        let mut bundle = Vec::new();
+        assert!(vp_voice::encode_multi_frame_payload(
+            20,
+            &[&[1u8; 10][..], &[2u8; 12][..]],
+            &mut bundle
+        ));
+        let mut d = BytesMut::new();
+        d.extend_from_slice(&[
+            1,
+            vp_voice::VOICE_FLAG_VAD | vp_voice::VOICE_FLAG_MULTI_FRAME,
+        ]);
+        d.put_u16(vp_voice::CLIENT_VOICE_HEADER_BYTES as u16);
+        d.put_u32(1);
+        d.put_u32(2);
+        d.put_u32(100);
+        d.put_u32(4_000);
+        d.extend_from_slice(&bundle);
+        forwarder.handle_incoming(sender, d.freeze()).await;
+
+        let modern_sent = modern.sent.lock().unwrap();
+        assert_eq!(modern_sent.len(), 1);
+        assert_ne!(modern_sent[0][1] & vp_voice::VOICE_FLAG_MULTI_FRAME, 0);
+
+        let legacy_sent = legacy.sent.lock().unwrap();
+        assert_eq!(legacy_sent.len(), 2);
+        for (i, pkt) in legacy_sent.iter().enumerate() {
+            assert_eq!(pkt[1] & vp_voice::VOICE_FLAG_MULTI_FRAME, 0);
+            assert_eq!(
+                u32::from_be_bytes([pkt[12], pkt[13], pkt[14], pkt[15]]),
+                100 + i as u32
+            );
+            assert_eq!(
+                u32::from_be_bytes([pkt[16], pkt[17], pkt[18], pkt[19]]),
+                4_000 + 20 * i as u32
+            );
+        }
+        assert_eq!(
+            legacy_sent[1].len(),
+            vp_voice::FORWARDED_VOICE_HEADER_BYTES + 12
+        );
+        assert_eq!(metrics.forwarded.load(Ordering::Relaxed), 2);
+    }
+
+    #[derive(Default)]
+    struct RecordingSink {
+        frames: Mutex<Vec<(UserId, u32, u32, Vec<u8>)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl VoiceSink for RecordingSink {
+        async fn on_packet(
+            &self,
+            _channel: ChannelId,
+            sender: UserId,
+            _ssrc: u32,
+            seq: u32,
+            ts_ms: u32,
+            payload: &[u8],
+        ) {
+            self.frames
+                .lock()
+                .unwrap()
+                .push((sender, seq, ts_ms, payload.to_vec()));
+        }
+    }
+
+    #[tokio::test]
+    async fn voice_sink_records_admitted_frames_only() {
+        let channel = ChannelId::new();
+        let sender = UserId::new();
+        let banned = UserId::new();
+        let sink = Arc::new(RecordingSink::default());
+        let Harness { forwarder, .. } = forwarder_with(
+            VoiceForwarderConfig::default(),
+            TestMembership {
+                banned: HashSet::from([banned]),
+                ..membership(channel, &[sender, banned])
+            },
+            TestSessions::default(),
+        );
+        let forwarder = forwarder.with_voice_sink(sink.clone());
+
+        forwarder
+            .handle_incoming(banned, make_voice_datagram(1, true))
+            .await;
+        assert!(sink.frames.lock().unwrap().is_empty());
+
+        let mut bundle = Vec::new();
+        assert!(vp_voice::encode_multi_frame_payload(
+            20,
+            &[&[1u8; 10][..], &[2u8; 12][..]],
+            &mut bundle
+        ));
+        let mut d = BytesMut::new();
+        d.extend_from_slice(&[
+            1,
+            vp_voice::VOICE_FLAG_VAD | vp_voice::VOICE_FLAG_MULTI_FRAME,
+        ]);
+        d.put_u16(vp_voice::CLIENT_VOICE_HEADER_BYTES as u16);
+        d.put_u32(1);
+        d.put_u32(2);
+        d.put_u32(100);
+        d.put_u32(4_000);
+        d.extend_from_slice(&bundle);
+        forwarder.handle_incoming(sender, d.freeze()).await;
+
+        let frames = sink.frames.lock().unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0], (sender, 100, 4_000, vec![1u8; 10]));
+        assert_eq!(frames[1], (sender, 101, 4_020, vec![2u8; 12]));
+    }
+
+    #[tokio::test]
+    async fn voice_sink_does_not_record_whispers() {
+        let channel = ChannelId::new();
+        let sender = UserId::new();
+        let target = UserId::new();
+        let target_tx = test_tx("target");
+        let sink = Arc::new(RecordingSink::default());
+        let Harness { forwarder, .. } = forwarder_with(
+            VoiceForwarderConfig::default(),
+            membership(channel, &[sender, target]),
+            TestSessions::of([(target, target_tx.clone())]),
+        );
+        let forwarder = forwarder.with_voice_sink(sink.clone());
+
+        forwarder
+            .handle_incoming(sender, make_whisper_datagram(1, &[target]))
+            .await;
+        assert_eq!(target_tx.sent_count(), 1);
+        assert!(sink.frames.lock().unwrap().is_empty());
+
+        forwarder
+            .handle_incoming(sender, make_voice_datagram_seq(1, 2))
+            .await;
+        assert_eq!(sink.frames.lock().unwrap().len(), 1);
+    }
+
+    /// Mix codec whose packets are one little-endian i16 filling the frame.
+    struct LevelCodec;
+    struct LevelDecoder;
+    struct LevelEncoder;
+
+    impl crate::voice_mixer::MixDecoder for LevelDecoder {
+        fn decode(&mut self, packet: &[u8], pcm: &mut [i16]) -> Result<usize> {
+            let n = crate::voice_mixer::MIX_FRAME_SAMPLES;
+            pcm[..n].fill(i16::from_le_bytes([packet[0], packet[1]]));
+            Ok(n)
+        }
+    }
+
+    impl crate::voice_mixer::MixEncoder for LevelEncoder {
+        fn encode(&mut self, pcm: &[i16], out: &mut [u8]) -> Result<usize> {
+            out[..2].copy_from_slice(&pcm[0].to_le_bytes());
+            Ok(2)
+        }
+    }
+
+    impl MixCodec for LevelCodec {
+        fn decoder(&self) -> Result<Box<dyn crate::voice_mixer::MixDecoder>> {
+            Ok(Box::new(LevelDecoder))
+        }
+        fn encoder(&self) -> Result<Box<dyn crate::voice_mixer::MixEncoder>> {
+            Ok(Box::new(LevelEncoder))
+        }
+    }
+
+    fn make_level_datagram(level: i16) -> Bytes {
+        let mut bytes = BytesMut::new();
+        bytes.extend_from_slice(&[1, vp_voice::VOICE_FLAG_VAD]);
+        bytes.put_u16(vp_voice::CLIENT_VOICE_HEADER_BYTES as u16);
+        bytes.put_u32(1);
+        bytes.put_u32(2);
+        bytes.put_u32(3);
+        bytes.put_u32(4);
+        bytes.extend_from_slice(&level.to_le_bytes());
+        bytes.freeze()
+    }
+
+    #[tokio::test]
+    async fn mix_mode_subtracts_own_voice_by_user_not_ssrc() {
+        let channel = ChannelId::new();
+        let a = UserId::new();
+        let b = UserId::new();
+        let listener = UserId::new();
+        let txs = HashMap::from([
+            (a, test_tx("a")),
+            (b, test_tx("b")),
+            (listener, test_tx("listener")),
+        ]);
+        let Harness {
+            forwarder, metrics, ..
+        } = forwarder_with(
+            VoiceForwarderConfig {
+                mode: ForwardMode::Mix,
+                ..VoiceForwarderConfig::default()
+            },
+            membership(channel, &[a, b, listener]),
+            TestSessions::of(txs.iter().map(|(user, tx)| (*user, tx.clone()))),
+        );
+        let forwarder = forwarder.with_mix_codec(Arc::new(LevelCodec));
+
+        // Both talkers claim the same ssrc; only the authenticated user counts.
+        forwarder.handle_incoming(a, make_level_datagram(100)).await;
+        forwarder
+            .handle_incoming(b, make_level_datagram(1000))
+            .await;
+        assert!(txs.values().all(|tx| tx.sent_count() == 0));
+
+        forwarder.mix_tick().await;
+        let heard = |user: UserId| {
+            let log = txs[&user].sent.lock().unwrap();
+            assert_eq!(log.len(), 1);
+            let pkt = &log[0];
+            assert_eq!(
+                u32::from_be_bytes([pkt[8], pkt[9], pkt[10], pkt[11]]),
+                crate::voice_mixer::MIX_SSRC
+            );
+            assert_eq!(&pkt[20..36], channel.0.as_bytes());
+            let payload = &pkt[vp_voice::FORWARDED_VOICE_HEADER_BYTES..];
+            i16::from_le_bytes([payload[0], payload[1]])
+        };
+        assert_eq!(heard(a), 1000);
+        assert_eq!(heard(b), 100);
+        assert_eq!(heard(listener), 1100);
+        assert_eq!(metrics.forwarded.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn idle_mix_channel_releases_its_engine() {
+        let channel = ChannelId::new();
+        let talker = UserId::new();
+        let Harness { forwarder, .. } = forwarder_with(
+            VoiceForwarderConfig {
+                mode: ForwardMode::Mix,
+                ..VoiceForwarderConfig::default()
+            },
+            membership(channel, &[talker]),
+            TestSessions::of([(talker, test_tx("talker"))]),
+        );
+        let forwarder = forwarder.with_mix_codec(Arc::new(LevelCodec));
+        let engines = || forwarder.mix.as_ref().map_or(0, |mix| mix.active().len());
+
+        forwarder
+            .handle_incoming(talker, make_level_datagram(100))
+            .await;
+        forwarder.mix_tick().await;
+        assert_eq!(engines(), 1);
+
+        for _ in 0..60 {
+            forwarder.mix_tick().await;
+        }
+        assert_eq!(engines(), 0);
+    }
+
+    #[tokio::test]
+    async fn loudness_byte_is_stripped_and_loudest_talkers_published() {
+        let channel = ChannelId::new();
+        let quiet = UserId::new();
+        let loud = UserId::new();
+        let listener = UserId::new();
+        let ltx = test_tx("listener");
+        let Harness {
+            forwarder,
+            metrics,
+            clock,
+            ..
+        } = forwarder_with(
+            VoiceForwarderConfig::default(),
+            membership(channel, &[quiet, loud, listener]),
+            TestSessions::of([(listener, ltx.clone())]),
+        );
+
+        forwarder
+            .handle_incoming(quiet, make_loud_voice_datagram(1, 1, 40))
+            .await;
+        forwarder
+            .handle_incoming(loud, make_loud_voice_datagram(1, 1, 90))
+            .await;
+        // Peak is held until published even if the next frame is quieter.
+        forwarder
+            .handle_incoming(loud, make_loud_voice_datagram(1, 2, 60))
+            .await;
+        assert_eq!(metrics.loudness_samples.load(Ordering::Relaxed), 3);
+        {
+            let sent = ltx.sent.lock().unwrap();
+            assert_eq!(sent.len(), 3);
+            for pkt in sent.iter() {
+                assert_eq!(pkt[1] & vp_voice::VOICE_FLAG_LOUDNESS, 0);
+                assert_eq!(pkt.len(), vp_voice::FORWARDED_VOICE_HEADER_BYTES + 64);
+                assert!(pkt[vp_voice::FORWARDED_VOICE_HEADER_BYTES..]
+                    .iter()
+                    .all(|b| *b == 7));
+            }
+        }
+
+        let updates = forwarder.take_loudness_updates(1).await;
+        assert_eq!(
+            updates,
+            vec![(
+                channel,
+                vec![TalkerLoudness {
+                    user: loud,
+                    loudness: 90
+                }]
+            )]
+        );
+        assert!(forwarder.take_loudness_updates(4).await.is_empty());
+
+        // Everyone stops talking: one empty update clears the channel.
+        clock.advance(LOUDNESS_HOLD * 2);
+        assert_eq!(
+            forwarder.take_loudness_updates(4).await,
+            vec![(channel, Vec::new())]
+        );
+        assert!(forwarder.take_loudness_updates(4).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn load_style_50_member_multi_session_fanout() {
+        let channel = ChannelId::new();
+        let sender = UserId::new();
+        let recipients: Vec<UserId> = (0..49).map(|_| UserId::new()).collect();
+        let sized_tx = |session_id: String, max_wire: usize| {
+            Arc::new(TestTx {
+                max_wire: Some(max_wire),
+                ..TestTx::new(&session_id)
+            })
+        };
+        let sessions = TestSessions::of(recipients.iter().enumerate().flat_map(|(idx, user)| {
+            [
+                (
+                    *user,
+                    sized_tx(format!("{idx}-a"), vp_voice::QUIC_MAX_DATAGRAM_BYTES),
+                ),
+                (*user, sized_tx(format!("{idx}-b"), vp_voice::APP_MEDIA_MTU)),
+            ]
+        }));
+        let members: Vec<UserId> = std::iter::once(sender)
+            .chain(recipients.iter().copied())
+            .collect();
+        let Harness {
+            forwarder, metrics, ..
+        } = forwarder_with(
+            VoiceForwarderConfig::default(),
+            TestMembership {
+                max_talkers: 10,
+                ..membership(channel, &members)
+            },
             sessions,
-            membership,
-            metrics.clone(),
-            prune_tx,
         );
 
         let start = Instant::now();
-        for _ in 0..100 {
+        for seq in 0..100 {
             forwarder
-                .handle_incoming(sender, make_voice_datagram(1, true))
+                .handle_incoming(sender, make_voice_datagram_seq(1, seq))
                 .await;
         }
         let elapsed = start.elapsed();
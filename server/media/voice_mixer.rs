@@ -0,0 +1,346 @@
+//! Server-side voice mixing for large channels.
+//!
+//! In SFU mode every talker's datagram is copied to every listener, so a
+//! channel costs O(talkers × listeners) sends. The mixer instead decodes each
+//! talker once, sums the channel, and re-encodes one stream per receiver with
+//! that receiver's own voice subtracted. Receivers are identified by their
+//! authenticated [`UserId`]; the ssrc is client-chosen and never trusted here.
+
+use std::{
+    collections::{hash_map::Entry, HashMap, VecDeque},
+    sync::Arc,
+};
+
+use anyhow::Result;
+use vp_control::ids::{ChannelId, UserId};
+
+pub const MIX_SAMPLE_RATE: u32 = 48_000;
+pub const MIX_FRAME_MS: u32 = 20;
+pub const MIX_FRAME_SAMPLES: usize = (MIX_SAMPLE_RATE * MIX_FRAME_MS / 1000) as usize;
+/// Ssrc stamped on mixed streams so clients see one stable stream per channel.
+pub const MIX_SSRC: u32 = 0x4D49_5800;
+/// Decoded audio a talker may queue ahead of the mix clock; anything older
+/// is dropped to bound the latency a bursty sender can add.
+const MAX_BUFFERED_FRAMES: usize = 3;
+/// Mix ticks without a full frame before a talker's decoder is released.
+const TALKER_IDLE_TICKS: u32 = 50;
+/// Largest Opus packet duration (120 ms) at the mix rate.
+const MAX_DECODED_SAMPLES: usize = MIX_FRAME_SAMPLES * 6;
+const MAX_ENCODED_BYTES: usize = 1275;
+const MIX_BITRATE_BPS: i32 = 32_000;
+
+pub trait MixDecoder: Send {
+    /// Decode one packet to mono PCM at [`MIX_SAMPLE_RATE`], returning the
+    /// number of samples written.
+    fn decode(&mut self, packet: &[u8], pcm: &mut [i16]) -> Result<usize>;
+}
+
+pub trait MixEncoder: Send {
+    /// Encode one [`MIX_FRAME_SAMPLES`] mono frame, returning the packet length.
+    fn encode(&mut self, pcm: &[i16], out: &mut [u8]) -> Result<usize>;
+}
+
+pub trait MixCodec: Send + Sync {
+    fn decoder(&self) -> Result<Box<dyn MixDecoder>>;
+    fn encoder(&self) -> Result<Box<dyn MixEncoder>>;
+}
+
+/// Mono 48 kHz libopus codec for the mixer.
+pub struct OpusMixCodec;
+
+struct OpusMixDecoder(opus::Decoder);
+struct OpusMixEncoder(opus::Encoder);
+
+impl MixDecoder for OpusMixDecoder {
+    fn decode(&mut self, packet: &[u8], pcm: &mut [i16]) -> Result<usize> {
+        Ok(self.0.decode(packet, pcm, false)?)
+    }
+}
+
+impl MixEncoder for OpusMixEncoder {
+    fn encode(&mut self, pcm: &[i16], out: &mut [u8]) -> Result<usize> {
+        Ok(self.0.encode(pcm, out)?)
+    }
+}
+
+impl MixCodec for OpusMixCodec {
+    fn decoder(&self) -> Result<Box<dyn MixDecoder>> {
+        let dec = opus::Decoder::new(MIX_SAMPLE_RATE, opus::Channels::Mono)?;
+        Ok(Box::new(OpusMixDecoder(dec)))
+    }
+
+    fn encoder(&self) -> Result<Box<dyn MixEncoder>> {
+        let mut enc = opus::Encoder::new(
+            MIX_SAMPLE_RATE,
+            opus::Channels::Mono,
+            opus::Application::Voip,
+        )?;
+        enc.set_bitrate(opus::Bitrate::Bits(MIX_BITRATE_BPS))?;
+        Ok(Box::new(OpusMixEncoder(enc)))
+    }
+}
+
+/// One re-encoded frame for one receiver.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MixedFrame {
+    pub receiver: UserId,
+    /// Route hash of the channel, copied from its talkers' datagrams.
+    pub channel_route: u32,
+    pub seq: u32,
+    pub ts_ms: u32,
+    pub payload: Vec<u8>,
+}
+
+struct TalkerMix {
+    decoder: Box<dyn MixDecoder>,
+    pcm: VecDeque<i16>,
+    /// This tick's contribution; empty when the talker had no full frame.
+    frame: Vec<i16>,
+    idle_ticks: u32,
+}
+
+struct ListenerMix {
+    encoder: Box<dyn MixEncoder>,
+    seq: u32,
+}
+
+struct ChannelMix {
+    channel_route: u32,
+    talkers: HashMap<UserId, TalkerMix>,
+    listeners: HashMap<UserId, ListenerMix>,
+    ts_ms: u32,
+    sum: Vec<i32>,
+}
+
+/// Per-channel decoder and encoder state for mixing mode. Not thread-safe on
+/// its own; the forwarder keeps one per channel behind a lock.
+pub struct MixEngine {
+    codec: Arc<dyn MixCodec>,
+    channels: HashMap<ChannelId, ChannelMix>,
+    decoded: Vec<i16>,
+    mixed: Vec<i16>,
+    encoded: Vec<u8>,
+}
+
+impl MixEngine {
+    pub fn new(codec: Arc<dyn MixCodec>) -> Self {
+        Self {
+            codec,
+            channels: HashMap::new(),
+            decoded: vec![0; MAX_DECODED_SAMPLES],
+            mixed: vec![0; MIX_FRAME_SAMPLES],
+            encoded: vec![0; MAX_ENCODED_BYTES],
+        }
+    }
+
+    /// Decode one Opus frame from `sender` into the channel's talker queue.
+    pub fn push(
+        &mut self,
+        channel: ChannelId,
+        channel_route: u32,
+        sender: UserId,
+        packet: &[u8],
+    ) -> Result<()> {
+        let mix = self.channels.entry(channel).or_insert_with(|| ChannelMix {
+            channel_route,
+            talkers: HashMap::new(),
+            listeners: HashMap::new(),
+            ts_ms: 0,
+            sum: vec![0; MIX_FRAME_SAMPLES],
+        });
+        mix.channel_route = channel_route;
+        let talker = match mix.talkers.entry(sender) {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(TalkerMix {
+                decoder: self.codec.decoder()?,
+                pcm: VecDeque::new(),
+                frame: Vec::with_capacity(MIX_FRAME_SAMPLES),
+                idle_ticks: 0,
+            }),
+        };
+        let n = talker.decoder.decode(packet, &mut self.decoded)?;
+        talker.pcm.extend(&self.decoded[..n]);
+        let excess = talker
+            .pcm
+            .len()
+            .saturating_sub(MAX_BUFFERED_FRAMES * MIX_FRAME_SAMPLES);
+        talker.pcm.drain(..excess);
+        Ok(())
+    }
+
+    /// Channels with at least one talker, i.e. the ones [`Self::mix`] has
+    /// work for.
+    pub fn active_channels(&self) -> Vec<ChannelId> {
+        self.channels.keys().copied().collect()
+    }
+
+    /// Advance `channel` by one [`MIX_FRAME_MS`] frame and encode it for every
+    /// receiver in `receivers`. A receiver who is the only talker gets
+    /// nothing. Listeners not in `receivers` lose their encoder; a channel
+    /// whose talkers have all gone idle is dropped.
+    pub fn mix(&mut self, channel: ChannelId, receivers: &[UserId]) -> Vec<MixedFrame> {
+        let Some(mix) = self.channels.get_mut(&channel) else {
+            return Vec::new();
+        };
+        mix.sum.fill(0);
+        let mut contributors = 0;
+        for talker in mix.talkers.values_mut() {
+            talker.frame.clear();
+            if talker.pcm.len() < MIX_FRAME_SAMPLES {
+                talker.idle_ticks += 1;
+                continue;
+            }
+            talker.idle_ticks = 0;
+            talker.frame.extend(talker.pcm.drain(..MIX_FRAME_SAMPLES));
+            for (acc, sample) in mix.sum.iter_mut().zip(&talker.frame) {
+                *acc += i32::from(*sample);
+            }
+            contributors += 1;
+        }
+        mix.talkers
+            .retain(|_, talker| talker.idle_ticks <= TALKER_IDLE_TICKS);
+        mix.listeners.retain(|user, _| receivers.contains(user));
+
+        let ts_ms = mix.ts_ms;
+        mix.ts_ms = mix.ts_ms.wrapping_add(MIX_FRAME_MS);
+        let mut out = Vec::new();
+        if contributors > 0 {
+            for &receiver in receivers {
+                let own = mix
+                    .talkers
+                    .get(&receiver)
+                    .map(|t| t.frame.as_slice())
+                    .filter(|frame| !frame.is_empty());
+                if own.is_some() && contributors == 1 {
+                    continue;
+                }
+                match own {
+                    Some(own) => {
+                        for ((dst, acc), own) in self.mixed.iter_mut().zip(&mix.sum).zip(own) {
+                            *dst = clamp_sample(acc - i32::from(*own));
+                        }
+                    }
+                    None => {
+                        for (dst, acc) in self.mixed.iter_mut().zip(&mix.sum) {
+                            *dst = clamp_sample(*acc);
+                        }
+                    }
+                }
+                let listener = match mix.listeners.entry(receiver) {
+                    Entry::Occupied(e) => e.into_mut(),
+                    Entry::Vacant(e) => {
+                        let Ok(encoder) = self.codec.encoder() else {
+                            continue;
+                        };
+                        e.insert(ListenerMix { encoder, seq: 0 })
+                    }
+                };
+                let Ok(len) = listener.encoder.encode(&self.mixed, &mut self.encoded) else {
+                    continue;
+                };
+                out.push(MixedFrame {
+                    receiver,
+                    channel_route: mix.channel_route,
+                    seq: listener.seq,
+                    ts_ms,
+                    payload: self.encoded[..len].to_vec(),
+                });
+                listener.seq = listener.seq.wrapping_add(1);
+            }
+        }
+        if mix.talkers.is_empty() {
+            self.channels.remove(&channel);
+        }
+        out
+    }
+
+    /// Drop `user`'s decoder and encoder in every channel, e.g. once their
+    /// last session has closed.
+    pub fn remove_user(&mut self, user: UserId) {
+        for mix in self.channels.values_mut() {
+            mix.talkers.remove(&user);
+            mix.listeners.remove(&user);
+        }
+        self.channels.retain(|_, mix| !mix.talkers.is_empty());
+    }
+}
+
+fn clamp_sample(v: i32) -> i16 {
+    v.clamp(i32::from(i16::MIN), i32::from(i16::MAX)) as i16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// "Codec" whose packets are a single little-endian i16 that fills the
+    /// whole frame, so mixes can be checked by value.
+    struct ConstCodec;
+    struct ConstDecoder;
+    struct ConstEncoder;
+
+    impl MixDecoder for ConstDecoder {
+        fn decode(&mut self, packet: &[u8], pcm: &mut [i16]) -> Result<usize> {
+            let v = i16::from_le_bytes([packet[0], packet[1]]);
+            pcm[..MIX_FRAME_SAMPLES].fill(v);
+            Ok(MIX_FRAME_SAMPLES)
+        }
+    }
+
+    impl MixEncoder for ConstEncoder {
+        fn encode(&mut self, pcm: &[i16], out: &mut [u8]) -> Result<usize> {
+            assert!(pcm.iter().all(|s| *s == pcm[0]));
+            out[..2].copy_from_slice(&pcm[0].to_le_bytes());
+            Ok(2)
+        }
+    }
+
+    impl MixCodec for ConstCodec {
+        fn decoder(&self) -> Result<Box<dyn MixDecoder>> {
+            Ok(Box::new(ConstDecoder))
+        }
+        fn encoder(&self) -> Result<Box<dyn MixEncoder>> {
+            Ok(Box::new(ConstEncoder))
+        }
+    }
+
+    fn value(frame: &MixedFrame) -> i16 {
+        i16::from_le_bytes([frame.payload[0], frame.payload[1]])
+    }
+
+    #[test]
+    fn each_receiver_hears_everyone_but_themselves() {
+        let channel = ChannelId::new();
+        let (a, b, listener) = (UserId::new(), UserId::new(), UserId::new());
+        let mut engine = MixEngine::new(Arc::new(ConstCodec));
+        engine.push(channel, 7, a, &100i16.to_le_bytes()).unwrap();
+        engine.push(channel, 7, b, &1000i16.to_le_bytes()).unwrap();
+
+        let frames = engine.mix(channel, &[a, b, listener]);
+        let heard: HashMap<UserId, i16> = frames.iter().map(|f| (f.receiver, value(f))).collect();
+        assert_eq!(heard[&a], 1000);
+        assert_eq!(heard[&b], 100);
+        assert_eq!(heard[&listener], 1100);
+        assert!(frames.iter().all(|f| f.channel_route == 7 && f.seq == 0));
+    }
+
+    #[test]
+    fn lone_talker_gets_nothing_and_idle_channels_are_dropped() {
+        let channel = ChannelId::new();
+        let (a, listener) = (UserId::new(), UserId::new());
+        let mut engine = MixEngine::new(Arc::new(ConstCodec));
+        engine.push(channel, 1, a, &i16::MAX.to_le_bytes()).unwrap();
+        engine.push(channel, 1, a, &i16::MAX.to_le_bytes()).unwrap();
+
+        let frames = engine.mix(channel, &[a, listener]);
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].receiver, listener);
+        let frames = engine.mix(channel, &[a, listener]);
+        assert_eq!((frames[0].seq, frames[0].ts_ms), (1, MIX_FRAME_MS));
+
+        for _ in 0..=TALKER_IDLE_TICKS {
+            assert!(engine.mix(channel, &[a, listener]).is_empty());
+        }
+        assert!(engine.active_channels().is_empty());
+    }
+}
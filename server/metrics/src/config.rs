@@ -1,17 +1,103 @@
+use crate::labels::StaticLabels;
+
 #[derive(Clone, Debug)]
 pub struct MetricsConfig {
     /// Bind address for Prometheus scrape endpoint, e.g. 0.0.0.0:9100
     pub listen: String,
 
-    /// Optional namespace prefix, e.g. "vp"
-    pub namespace: &'static str,
+    /// Metric name prefix, e.g. "vp"
+    pub namespace: String,
+
+    /// Labels applied to every exported series, e.g. region/instance.
+    pub static_labels: StaticLabels,
+
+    /// Credential required on `/metrics`; `MetricsAuth::None` keeps the endpoint open.
+    pub auth: MetricsAuth,
 }
 
 impl Default for MetricsConfig {
     fn default() -> Self {
         Self {
             listen: "0.0.0.0:9100".to_string(),
-            namespace: "vp",
+            namespace: "vp".to_string(),
+            static_labels: StaticLabels::default(),
+            auth: MetricsAuth::None,
+        }
+    }
+}
+
+/// Scrape authentication for the metrics endpoint.
+#[derive(Clone, Debug, Default)]
+pub enum MetricsAuth {
+    #[default]
+    None,
+    /// `Authorization: Bearer <token>`
+    Bearer(String),
+    /// `Authorization: Basic base64(<username>:<password>)`
+    Basic { username: String, password: String },
+}
+
+impl MetricsAuth {
+    /// Exact `Authorization` header value a scraper must send, if any.
+    pub(crate) fn expected_header(&self) -> Option<String> {
+        match self {
+            MetricsAuth::None => None,
+            MetricsAuth::Bearer(token) => Some(format!("Bearer {token}")),
+            MetricsAuth::Basic { username, password } => Some(format!(
+                "Basic {}",
+                base64_encode(format!("{username}:{password}").as_bytes())
+            )),
         }
     }
 }
+
+fn base64_encode(input: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+        out.push(ALPHABET[(n >> 18) as usize & 63] as char);
+        out.push(ALPHABET[(n >> 12) as usize & 63] as char);
+        out.push(if chunk.len() > 1 {
+            ALPHABET[(n >> 6) as usize & 63] as char
+        } else {
+            '='
+        });
+        out.push(if chunk.len() > 2 {
+            ALPHABET[n as usize & 63] as char
+        } else {
+            '='
+        });
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MetricsAuth;
+
+    #[test]
+    fn expected_header_matches_scraper_encoding() {
+        assert_eq!(MetricsAuth::None.expected_header(), None);
+        assert_eq!(
+            MetricsAuth::Bearer("s3cret".into())
+                .expected_header()
+                .as_deref(),
+            Some("Bearer s3cret")
+        );
+        // RFC 7617 example credentials.
+        let basic = MetricsAuth::Basic {
+            username: "Aladdin".into(),
+            password: "open sesame".into(),
+        };
+        assert_eq!(
+            basic.expected_header().as_deref(),
+            Some("Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==")
+        );
+    }
+}
@@ -1,12 +1,16 @@
 use metrics::{counter, histogram};
 
+use crate::labels::BoundedLabel;
+
 pub struct ControlMetrics {
-    ns: &'static str,
+    ns: String,
 }
 
 impl ControlMetrics {
-    pub fn new(namespace: &'static str) -> Self {
-        Self { ns: namespace }
+    pub fn new(namespace: &str) -> Self {
+        Self {
+            ns: namespace.to_string(),
+        }
     }
 
     pub fn op_total(&self, op: &'static str) {
@@ -29,3 +33,63 @@ impl ControlMetrics {
         histogram!(format!("{}_control_outbox_lag_seconds", self.ns)).record(seconds);
     }
 }
+
+/// Outbox dispatcher metrics, under: {ns}_control_outbox_*
+pub struct OutboxMetricsImpl {
+    claim_batch_name: &'static str,
+    claim_fill_ratio_name: &'static str,
+    published_name: &'static str,
+    lag_name: &'static str,
+    fanout_name: &'static str,
+    dead_lettered_name: &'static str,
+}
+
+impl OutboxMetricsImpl {
+    pub fn new(namespace: &str) -> Self {
+        Self {
+            claim_batch_name: Box::leak(
+                format!("{namespace}_control_outbox_claim_batch").into_boxed_str(),
+            ),
+            claim_fill_ratio_name: Box::leak(
+                format!("{namespace}_control_outbox_claim_fill_ratio").into_boxed_str(),
+            ),
+            published_name: Box::leak(
+                format!("{namespace}_control_outbox_published_total").into_boxed_str(),
+            ),
+            lag_name: Box::leak(format!("{namespace}_control_outbox_lag_seconds").into_boxed_str()),
+            fanout_name: Box::leak(format!("{namespace}_control_outbox_fanout").into_boxed_str()),
+            dead_lettered_name: Box::leak(
+                format!("{namespace}_control_outbox_dead_lettered_total").into_boxed_str(),
+            ),
+        }
+    }
+
+    /// One non-empty claim. A fill ratio pinned at 1.0 means the backlog is
+    /// outrunning `batch_size`; well below it with rows still pending points
+    /// at other dispatchers holding the locks.
+    #[inline]
+    pub fn claimed(&self, requested: usize, claimed: usize) {
+        histogram!(self.claim_batch_name).record(claimed as f64);
+        if requested > 0 {
+            histogram!(self.claim_fill_ratio_name).record(claimed as f64 / requested as f64);
+        }
+    }
+
+    /// A record acked after delivery; `lag_seconds` is created_at -> ack.
+    #[inline]
+    pub fn published(&self, topic: BoundedLabel, lag_seconds: f64) {
+        let topic = topic.into_static();
+        counter!(self.published_name, "topic" => topic).increment(1);
+        histogram!(self.lag_name, "topic" => topic).record(lag_seconds.max(0.0));
+    }
+
+    #[inline]
+    pub fn fanout(&self, recipients: usize) {
+        histogram!(self.fanout_name).record(recipients as f64);
+    }
+
+    #[inline]
+    pub fn dead_lettered(&self, topic: BoundedLabel) {
+        counter!(self.dead_lettered_name, "topic" => topic.into_static()).increment(1);
+    }
+}
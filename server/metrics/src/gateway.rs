@@ -1,22 +1,50 @@
-use metrics::{counter, histogram};
+use metrics::{counter, gauge, histogram};
 
 pub struct GatewayMetrics {
-    ns: &'static str,
+    ns: String,
 }
 
 impl GatewayMetrics {
-    pub fn new(namespace: &'static str) -> Self {
-        Self { ns: namespace }
+    pub fn new(namespace: &str) -> Self {
+        Self {
+            ns: namespace.to_string(),
+        }
     }
 
+    /// QUIC handshake completed; pair with [`Self::conn_closed`].
     #[inline]
     pub fn conn_accepted(&self) {
         counter!(format!("{}_gateway_connections_total", self.ns)).increment(1);
+        gauge!(format!("{}_gateway_connections_active", self.ns)).increment(1.0);
     }
 
     #[inline]
     pub fn conn_closed(&self) {
         counter!(format!("{}_gateway_connections_closed_total", self.ns)).increment(1);
+        gauge!(format!("{}_gateway_connections_active", self.ns)).decrement(1.0);
+    }
+
+    /// Client never opened its control stream within the handshake window.
+    #[inline]
+    pub fn handshake_timeout(&self) {
+        counter!(format!("{}_gateway_handshake_timeouts_total", self.ns)).increment(1);
+    }
+
+    #[inline]
+    pub fn alpn_mismatch(&self) {
+        counter!(format!("{}_gateway_alpn_mismatch_total", self.ns)).increment(1);
+    }
+
+    /// Authenticated session registered; pair with [`Self::session_closed`].
+    /// Connections minus sessions is the number still unauthenticated.
+    #[inline]
+    pub fn session_opened(&self) {
+        gauge!(format!("{}_gateway_active_sessions", self.ns)).increment(1.0);
+    }
+
+    #[inline]
+    pub fn session_closed(&self) {
+        gauge!(format!("{}_gateway_active_sessions", self.ns)).decrement(1.0);
     }
 
     #[inline]
@@ -44,3 +72,37 @@ impl GatewayMetrics {
         histogram!(format!("{}_gateway_handshake_seconds", self.ns)).record(seconds);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::GatewayMetrics;
+    use metrics_exporter_prometheus::PrometheusBuilder;
+
+    #[test]
+    fn paired_calls_leave_active_gauges_at_zero() {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        metrics::with_local_recorder(&recorder, || {
+            let m = GatewayMetrics::new("vp");
+            m.conn_accepted();
+            m.conn_accepted();
+            m.session_opened();
+            m.session_closed();
+            m.conn_closed();
+        });
+
+        let rendered = handle.render();
+        assert!(
+            rendered.contains("vp_gateway_connections_total 2"),
+            "{rendered}"
+        );
+        assert!(
+            rendered.contains("vp_gateway_connections_active 1"),
+            "{rendered}"
+        );
+        assert!(
+            rendered.contains("vp_gateway_active_sessions 0"),
+            "{rendered}"
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
 use http_body_util::Full;
 use hyper::{body::Bytes, Request, Response};
 use hyper_util::rt::TokioIo;
@@ -7,25 +7,49 @@ use std::{net::SocketAddr, sync::Arc};
 use tokio::net::TcpListener;
 use tracing::info;
 
-use crate::MetricsConfig;
+use crate::labels::is_valid_namespace;
+use crate::{MetricsConfig, NAMESPACE};
+
+/// Readiness probe behind `/readyz`: `Ok` when the process should receive
+/// traffic, otherwise a short reason for the 503 body.
+pub type ReadinessCheck = Arc<dyn Fn() -> Result<(), &'static str> + Send + Sync>;
 
 pub struct MetricsServer {
     handle: PrometheusHandle,
     cfg: MetricsConfig,
+    readiness: Option<ReadinessCheck>,
 }
 
 impl MetricsServer {
     pub fn install(cfg: MetricsConfig) -> Result<Self> {
+        if !is_valid_namespace(&cfg.namespace) {
+            bail!("invalid metrics namespace: {:?}", cfg.namespace);
+        }
+
         // Install global recorder once. Panics if installed twice; call from main init.
-        let handle = PrometheusBuilder::new()
+        let mut builder = PrometheusBuilder::new()
             // Optional: allow only vp_* metrics or keep default.
             .set_buckets_for_metric(
                 Matcher::Prefix(format!("{}_voice_", cfg.namespace)),
                 &[0.001, 0.005, 0.01, 0.02, 0.05, 0.1, 0.25, 0.5, 1.0, 2.0, 5.0],
-            )?
-            .install_recorder()?;
+            )?;
+        for (key, value) in cfg.static_labels.iter() {
+            builder = builder.add_global_label(key, value);
+        }
+        let handle = builder.install_recorder()?;
+        let _ = NAMESPACE.set(cfg.namespace.clone());
+
+        Ok(Self {
+            handle,
+            cfg,
+            readiness: None,
+        })
+    }
 
-        Ok(Self { handle, cfg })
+    /// Gate `/readyz` on `check`; without one it reports ready once serving.
+    pub fn with_readiness(mut self, check: ReadinessCheck) -> Self {
+        self.readiness = Some(check);
+        self
     }
 
     pub async fn serve(self) -> Result<()> {
@@ -34,18 +58,28 @@ impl MetricsServer {
         info!("metrics listening on http://{}/metrics", addr);
 
         let handle = Arc::new(self.handle);
+        let readiness = self.readiness;
+        let expected_auth: Option<Arc<str>> = self.cfg.auth.expected_header().map(Arc::from);
+        if expected_auth.is_some() {
+            info!("metrics endpoint requires authorization");
+        }
 
         loop {
             let (stream, _) = listener.accept().await?;
             let handle = handle.clone();
+            let expected_auth = expected_auth.clone();
+            let readiness = readiness.clone();
 
             tokio::spawn(async move {
                 let io = TokioIo::new(stream);
 
-                let service = hyper::service::service_fn(move |req: Request<hyper::body::Incoming>| {
-                    let handle = handle.clone();
-                    async move { metrics_handler(req, handle).await }
-                });
+                let service =
+                    hyper::service::service_fn(move |req: Request<hyper::body::Incoming>| {
+                        let handle = handle.clone();
+                        let expected_auth = expected_auth.clone();
+                        let readiness = readiness.clone();
+                        async move { metrics_handler(req, handle, expected_auth, readiness).await }
+                    });
 
                 let _ = hyper::server::conn::http1::Builder::new()
                     .serve_connection(io, service)
@@ -58,7 +92,35 @@ impl MetricsServer {
 async fn metrics_handler(
     req: Request<hyper::body::Incoming>,
     handle: Arc<PrometheusHandle>,
+    expected_auth: Option<Arc<str>>,
+    readiness: Option<ReadinessCheck>,
 ) -> Result<Response<Full<Bytes>>, hyper::Error> {
+    // Orchestrator probes don't carry scrape credentials and expose nothing
+    // sensitive, so they are answered before the auth check.
+    if let Some(resp) = probe_response(req.uri().path(), readiness.as_ref()) {
+        return Ok(resp);
+    }
+
+    if let Some(expected) = expected_auth {
+        let presented = req
+            .headers()
+            .get(hyper::header::AUTHORIZATION)
+            .map(|v| v.as_bytes())
+            .unwrap_or_default();
+        if !constant_time_eq(presented, expected.as_bytes()) {
+            let challenge = if expected.starts_with("Basic ") {
+                "Basic realm=\"metrics\""
+            } else {
+                "Bearer realm=\"metrics\""
+            };
+            return Ok(Response::builder()
+                .status(401)
+                .header(hyper::header::WWW_AUTHENTICATE, challenge)
+                .body(Full::new(Bytes::from("unauthorized")))
+                .unwrap());
+        }
+    }
+
     if req.uri().path() != "/metrics" {
         return Ok(Response::builder()
             .status(404)
@@ -73,3 +135,63 @@ async fn metrics_handler(
         .body(Full::new(Bytes::from(body)))
         .unwrap())
 }
+
+/// `/healthz` (liveness: 200 whenever this server answers) and `/readyz`.
+fn probe_response(path: &str, readiness: Option<&ReadinessCheck>) -> Option<Response<Full<Bytes>>> {
+    let (status, body) = match path {
+        "/healthz" => (200, r#"{"status":"ok"}"#.to_string()),
+        "/readyz" => match readiness.map_or(Ok(()), |check| check()) {
+            Ok(()) => (200, r#"{"status":"ready"}"#.to_string()),
+            Err(reason) => (
+                503,
+                format!(r#"{{"status":"not_ready","reason":"{reason}"}}"#),
+            ),
+        },
+        _ => return None,
+    };
+    Some(
+        Response::builder()
+            .status(status)
+            .header("content-type", "application/json")
+            .body(Full::new(Bytes::from(body)))
+            .unwrap(),
+    )
+}
+
+/// Compare credentials without short-circuiting on the first mismatching byte.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{probe_response, ReadinessCheck};
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    };
+
+    #[test]
+    fn readyz_follows_the_readiness_check() {
+        let ready = Arc::new(AtomicBool::new(false));
+        let flag = ready.clone();
+        let check: ReadinessCheck = Arc::new(move || {
+            if flag.load(Ordering::Relaxed) {
+                Ok(())
+            } else {
+                Err("database unreachable")
+            }
+        });
+
+        let readyz = |check| probe_response("/readyz", check).unwrap().status();
+        assert_eq!(readyz(Some(&check)), 503);
+        ready.store(true, Ordering::Relaxed);
+        assert_eq!(readyz(Some(&check)), 200);
+        assert_eq!(readyz(None), 200);
+        assert_eq!(probe_response("/healthz", None).unwrap().status(), 200);
+        assert!(probe_response("/metrics", Some(&check)).is_none());
+    }
+}
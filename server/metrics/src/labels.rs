@@ -1,3 +1,4 @@
+use anyhow::{bail, Result};
 use once_cell::sync::Lazy;
 use std::borrow::Cow;
 
@@ -55,4 +56,136 @@ impl LabelPolicy {
     pub fn reason(reason: &'static str) -> BoundedLabel {
         BoundedLabel(Cow::Borrowed(reason))
     }
+
+    /// Collapse an outbox topic (`chat.message_posted`) to its family
+    /// (`chat`). Topics are stored as free text, so anything outside the
+    /// known families is exported as `other`.
+    pub fn outbox_topic(topic: &str) -> BoundedLabel {
+        let family = topic.split('.').next().unwrap_or_default();
+        let label = OUTBOX_TOPIC_FAMILIES
+            .iter()
+            .find(|f| **f == family)
+            .copied()
+            .unwrap_or("other");
+        BoundedLabel(Cow::Borrowed(label))
+    }
+}
+
+const OUTBOX_TOPIC_FAMILIES: &[&str] =
+    &["channel", "chat", "moderation", "perm", "poke", "presence"];
+
+/// Process-wide labels stamped on every exported series (e.g. `region`,
+/// `instance`). Bounded so config can't explode series cardinality.
+#[derive(Clone, Debug, Default)]
+pub struct StaticLabels(Vec<(String, String)>);
+
+impl StaticLabels {
+    pub const MAX_LABELS: usize = 8;
+    pub const MAX_VALUE_LEN: usize = 64;
+
+    pub fn new(pairs: Vec<(String, String)>) -> Result<Self> {
+        if pairs.len() > Self::MAX_LABELS {
+            bail!(
+                "too many static metric labels: {} > {}",
+                pairs.len(),
+                Self::MAX_LABELS
+            );
+        }
+        for (i, (key, value)) in pairs.iter().enumerate() {
+            if !is_valid_label_key(key) {
+                bail!("invalid static metric label key: {key:?}");
+            }
+            if pairs[..i].iter().any(|(k, _)| k == key) {
+                bail!("duplicate static metric label key: {key:?}");
+            }
+            if value.is_empty() || value.len() > Self::MAX_VALUE_LEN {
+                bail!(
+                    "static metric label {key:?} value must be 1..={} bytes",
+                    Self::MAX_VALUE_LEN
+                );
+            }
+        }
+        Ok(Self(pairs))
+    }
+
+    /// Parse `key=value,key=value` (as taken from CLI/env). Empty input is no labels.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut pairs = Vec::new();
+        for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let Some((key, value)) = part.split_once('=') else {
+                bail!("static metric label must be key=value: {part:?}");
+            };
+            pairs.push((key.trim().to_string(), value.trim().to_string()));
+        }
+        Self::new(pairs)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+}
+
+/// Prometheus label name rules, minus the reserved `__` prefix and the
+/// histogram/summary labels the exporter emits itself.
+fn is_valid_label_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    let Some(first) = chars.next() else {
+        return false;
+    };
+    (first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !key.starts_with("__")
+        && !matches!(key, "le" | "quantile")
+}
+
+/// Prometheus metric-name prefix rules (no colons; those are for recording rules).
+pub fn is_valid_namespace(ns: &str) -> bool {
+    is_valid_label_key(ns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_valid_namespace, LabelPolicy, StaticLabels};
+
+    #[test]
+    fn static_labels_parse_and_bound() {
+        let labels = StaticLabels::parse("region=eu-west, instance=gw1").unwrap();
+        assert_eq!(
+            labels.iter().collect::<Vec<_>>(),
+            vec![("region", "eu-west"), ("instance", "gw1")]
+        );
+        assert!(StaticLabels::parse("").unwrap().iter().next().is_none());
+
+        assert!(StaticLabels::parse("region").is_err());
+        assert!(StaticLabels::parse("region=a,region=b").is_err());
+        assert!(StaticLabels::parse("__name__=x").is_err());
+        assert!(StaticLabels::parse("le=1").is_err());
+        assert!(StaticLabels::parse("1bad=x").is_err());
+        assert!(StaticLabels::parse("region=").is_err());
+
+        let too_many = (0..=StaticLabels::MAX_LABELS)
+            .map(|i| format!("k{i}=v"))
+            .collect::<Vec<_>>()
+            .join(",");
+        assert!(StaticLabels::parse(&too_many).is_err());
+    }
+
+    #[test]
+    fn outbox_topics_collapse_to_known_families() {
+        let family = |topic: &str| LabelPolicy::outbox_topic(topic).into_static();
+        assert_eq!(family("chat.message_posted"), "chat");
+        assert_eq!(family("presence.member_left"), "presence");
+        assert_eq!(family("poke.received"), "poke");
+        assert_eq!(family("billing.invoice_paid"), "other");
+        assert_eq!(family("chat"), "chat");
+        assert_eq!(family(""), "other");
+    }
+
+    #[test]
+    fn namespace_must_be_metric_safe() {
+        assert!(is_valid_namespace("vp"));
+        assert!(is_valid_namespace("vp_staging"));
+        assert!(!is_valid_namespace("vp-staging"));
+        assert!(!is_valid_namespace(""));
+    }
 }
@@ -6,6 +6,21 @@ pub mod labels;
 pub mod stream;
 pub mod voice;
 
-pub use config::MetricsConfig;
-pub use http::MetricsServer;
-pub use labels::{BoundedLabel, LabelPolicy};
+pub use config::{MetricsAuth, MetricsConfig};
+pub use http::{MetricsServer, ReadinessCheck};
+pub use labels::{BoundedLabel, LabelPolicy, StaticLabels};
+
+use std::sync::OnceLock;
+
+static NAMESPACE: OnceLock<String> = OnceLock::new();
+
+/// Metric namespace installed by `MetricsServer::install` ("vp" before that).
+pub fn namespace() -> &'static str {
+    NAMESPACE.get().map(String::as_str).unwrap_or("vp")
+}
+
+/// Full metric name for ad-hoc `counter!`/`gauge!` sites, e.g.
+/// `metric_name("gateway_db_up")` -> `vp_gateway_db_up`.
+pub fn metric_name(suffix: &str) -> String {
+    format!("{}_{suffix}", namespace())
+}
@@ -26,7 +26,7 @@ pub struct StreamMetricsImpl {
 }
 
 impl StreamMetricsImpl {
-    pub fn new(namespace: &'static str, policy: LabelPolicy) -> Self {
+    pub fn new(namespace: &str, policy: LabelPolicy) -> Self {
         Self {
             rx_packets_name: Box::leak(
                 format!("{namespace}_stream_rx_packets_total").into_boxed_str(),
@@ -15,11 +15,12 @@ pub struct VoiceMetricsImpl {
     recipient_enumeration_us_name: &'static str,
     packet_fanout_us_name: &'static str,
     handle_incoming_us_name: &'static str,
+    loudness_name: &'static str,
     policy: LabelPolicy,
 }
 
 impl VoiceMetricsImpl {
-    pub fn new(namespace: &'static str, policy: LabelPolicy) -> Self {
+    pub fn new(namespace: &str, policy: LabelPolicy) -> Self {
         Self {
             rx_packets_name: Box::leak(
                 format!("{namespace}_voice_rx_packets_total").into_boxed_str(),
@@ -48,6 +49,7 @@ impl VoiceMetricsImpl {
             handle_incoming_us_name: Box::leak(
                 format!("{namespace}_voice_handle_incoming_us").into_boxed_str(),
             ),
+            loudness_name: Box::leak(format!("{namespace}_voice_loudness").into_boxed_str()),
             policy,
         }
     }
@@ -107,70 +109,9 @@ impl VoiceMetricsImpl {
     pub fn handle_incoming_us(&self, micros: u64) {
         histogram!(self.handle_incoming_us_name).record(micros as f64);
     }
-}
-
-/// Adapter implementing the `VoiceMetrics` trait used by voice_forwarder.rs
-/// Copy/paste the trait path to match your module.
-pub mod adapter {
-    use super::VoiceMetricsImpl;
 
-    // This must match the trait in your media/voice_forwarder.rs.
-    // If your path is different, adjust imports.
-    pub trait VoiceMetrics: Send + Sync {
-        fn inc_rx_packets(&self);
-        fn inc_rx_bytes(&self, n: usize);
-        fn inc_drop_invalid(&self);
-        fn inc_drop_rate_limited(&self);
-        fn inc_drop_not_member(&self);
-        fn inc_drop_muted(&self);
-        fn inc_drop_talker_limit(&self);
-        fn inc_drop_send_queue_full(&self);
-        fn inc_forwarded(&self, fanout: usize);
-        fn observe_session_lookup_us(&self, micros: u64);
-        fn observe_recipient_enumeration_us(&self, micros: u64);
-        fn observe_packet_fanout_us(&self, micros: u64);
-        fn observe_handle_incoming_us(&self, micros: u64);
-    }
-
-    impl VoiceMetrics for VoiceMetricsImpl {
-        fn inc_rx_packets(&self) {
-            self.rx_packet();
-        }
-        fn inc_rx_bytes(&self, n: usize) {
-            self.rx_bytes(n);
-        }
-        fn inc_drop_invalid(&self) {
-            self.drop_reason("invalid");
-        }
-        fn inc_drop_rate_limited(&self) {
-            self.drop_reason("rate_limited");
-        }
-        fn inc_drop_not_member(&self) {
-            self.drop_reason("not_member");
-        }
-        fn inc_drop_muted(&self) {
-            self.drop_reason("muted");
-        }
-        fn inc_drop_talker_limit(&self) {
-            self.drop_reason("talker_limit");
-        }
-        fn inc_drop_send_queue_full(&self) {
-            self.enqueue_drop();
-        }
-        fn inc_forwarded(&self, fanout: usize) {
-            self.forwarded(fanout);
-        }
-        fn observe_session_lookup_us(&self, micros: u64) {
-            self.session_lookup_us(micros);
-        }
-        fn observe_recipient_enumeration_us(&self, micros: u64) {
-            self.recipient_enumeration_us(micros);
-        }
-        fn observe_packet_fanout_us(&self, micros: u64) {
-            self.packet_fanout_us(micros);
-        }
-        fn observe_handle_incoming_us(&self, micros: u64) {
-            self.handle_incoming_us(micros);
-        }
+    #[inline]
+    pub fn loudness(&self, loudness: u8) {
+        histogram!(self.loudness_name).record(loudness as f64);
     }
 }
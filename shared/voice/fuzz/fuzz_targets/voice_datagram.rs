@@ -0,0 +1,41 @@
+//! Feeds arbitrary bytes through the voice datagram parsers used by both the
+//! server forwarder and the client receive loop.
+//!
+//! Run with `cargo +nightly fuzz run voice_datagram` from `shared/voice`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use vp_voice::{
+    multi_frame_payload_len, parse_voice_header, split_multi_frame_payload,
+    CLIENT_VOICE_HEADER_BYTES, CLIENT_VOICE_HEADER_WITH_LOUDNESS_BYTES,
+    FORWARDED_VOICE_HEADER_BYTES, VOICE_FLAG_LOUDNESS, VOICE_VERSION,
+};
+
+fuzz_target!(|data: &[u8]| {
+    let Some((hdr, payload)) = parse_voice_header(data) else {
+        return;
+    };
+    assert_eq!(data[0], VOICE_VERSION);
+    assert!(
+        hdr.header_len == CLIENT_VOICE_HEADER_BYTES
+            || hdr.header_len == CLIENT_VOICE_HEADER_WITH_LOUDNESS_BYTES
+            || hdr.header_len == FORWARDED_VOICE_HEADER_BYTES
+    );
+    assert!(!payload.is_empty());
+    assert_eq!(hdr.header_len + payload.len(), data.len());
+    assert_eq!(
+        hdr.forwarded_ids.is_some(),
+        hdr.header_len == FORWARDED_VOICE_HEADER_BYTES
+    );
+    if hdr.loudness.is_some() {
+        assert_eq!(hdr.header_len, CLIENT_VOICE_HEADER_WITH_LOUDNESS_BYTES);
+        assert_ne!(hdr.flags & VOICE_FLAG_LOUDNESS, 0);
+    }
+
+    if let Some((frame_ms, frames)) = split_multi_frame_payload(payload) {
+        assert!(frame_ms > 0);
+        assert!(frames.iter().all(|f| !f.is_empty()));
+        assert_eq!(multi_frame_payload_len(&frames), payload.len());
+    }
+});
@@ -5,6 +5,8 @@ pub const FORWARDER_ADDED_HEADER_BYTES: usize = 32;
 /// Max client->server voice datagram size so forwarded metadata still fits APP_MEDIA_MTU.
 pub const MAX_INBOUND_VOICE_DATAGRAM_BYTES: usize = APP_MEDIA_MTU - FORWARDER_ADDED_HEADER_BYTES;
 pub const CLIENT_VOICE_HEADER_BYTES: usize = 20;
+/// Client header plus the trailing loudness byte (see `VOICE_FLAG_LOUDNESS`).
+pub const CLIENT_VOICE_HEADER_WITH_LOUDNESS_BYTES: usize = CLIENT_VOICE_HEADER_BYTES + 1;
 pub const FORWARDED_VOICE_HEADER_BYTES: usize =
     CLIENT_VOICE_HEADER_BYTES + FORWARDER_ADDED_HEADER_BYTES;
 pub const MAX_OPUS_PAYLOAD_BYTES: usize =
@@ -35,6 +37,262 @@ pub fn datagram_kind(buf: &[u8]) -> Option<u8> {
     Some(buf[1])
 }
 
+// ── Voice datagram header ──────────────────────────────────────────────
+//
+// Big-endian, shared by client->server and forwarded datagrams:
+//   0:  u8  version           (1)
+//   1:  u8  flags             (see VOICE_FLAG_*)
+//   2:  u16 header_len        (CLIENT_VOICE_HEADER_BYTES, CLIENT_VOICE_HEADER_WITH_LOUDNESS_BYTES
+//                              or FORWARDED_VOICE_HEADER_BYTES)
+//   4:  u32 channel_route
+//   8:  u32 ssrc
+//  12:  u32 seq
+//  16:  u32 ts_ms
+//  20:  u8  loudness          (client->server with VOICE_FLAG_LOUDNESS only)
+//  20:  [16] sender user id   (forwarded only)
+//  36:  [16] channel id       (forwarded only)
+//  header_len: ... payload bytes (non-empty)
+//
+// The forwarder consumes the loudness byte; forwarded datagrams never carry it.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VoiceHeader {
+    pub flags: u8,
+    pub header_len: usize,
+    pub channel_route: u32,
+    pub ssrc: u32,
+    pub seq: u32,
+    pub ts_ms: u32,
+    /// Sender-measured frame loudness; see [`loudness_from_dbfs`].
+    pub loudness: Option<u8>,
+    /// Raw sender/channel UUID bytes; only present on forwarded datagrams.
+    pub forwarded_ids: Option<([u8; 16], [u8; 16])>,
+}
+
+/// Parse a voice datagram into its header and payload. This is the only
+/// place that reads voice headers off the wire; it never indexes past
+/// `buf` and rejects unknown header lengths and empty payloads. The
+/// loudness layout is only accepted with `VOICE_FLAG_LOUDNESS` set.
+pub fn parse_voice_header(buf: &[u8]) -> Option<(VoiceHeader, &[u8])> {
+    if buf.len() < CLIENT_VOICE_HEADER_BYTES || buf[0] != VOICE_VERSION {
+        return None;
+    }
+    let header_len = u16::from_be_bytes([buf[2], buf[3]]) as usize;
+    let with_loudness = header_len == CLIENT_VOICE_HEADER_WITH_LOUDNESS_BYTES;
+    if with_loudness {
+        if buf[1] & VOICE_FLAG_LOUDNESS == 0 {
+            return None;
+        }
+    } else if header_len != CLIENT_VOICE_HEADER_BYTES && header_len != FORWARDED_VOICE_HEADER_BYTES
+    {
+        return None;
+    }
+    let (header, payload) = buf.split_at_checked(header_len)?;
+    if payload.is_empty() {
+        return None;
+    }
+    let be_u32 = |at: usize| {
+        u32::from_be_bytes([header[at], header[at + 1], header[at + 2], header[at + 3]])
+    };
+    let forwarded_ids = (header_len == FORWARDED_VOICE_HEADER_BYTES).then(|| {
+        let mut sender = [0u8; 16];
+        let mut channel = [0u8; 16];
+        sender.copy_from_slice(&header[20..36]);
+        channel.copy_from_slice(&header[36..52]);
+        (sender, channel)
+    });
+    Some((
+        VoiceHeader {
+            flags: buf[1],
+            header_len,
+            channel_route: be_u32(4),
+            ssrc: be_u32(8),
+            seq: be_u32(12),
+            ts_ms: be_u32(16),
+            loudness: with_loudness.then(|| header[CLIENT_VOICE_HEADER_BYTES]),
+            forwarded_ids,
+        },
+        payload,
+    ))
+}
+
+// ── Voice header flags ─────────────────────────────────────────────────
+//
+// Byte 1 of a voice datagram carries flags rather than a kind.
+
+pub const VOICE_FLAG_VAD: u8 = 0x01;
+/// Header carries a loudness byte after `ts_ms`, growing it to
+/// `CLIENT_VOICE_HEADER_WITH_LOUDNESS_BYTES`. Client->server only, and only
+/// to gateways advertising `ServerFeature.VOICE_LOUDNESS`.
+pub const VOICE_FLAG_LOUDNESS: u8 = 0x02;
+/// Payload is a coalesced multi-frame bundle (see below). Only sent to
+/// receivers that advertised `FeatureCaps.supports_voice_multi_frame`.
+pub const VOICE_FLAG_MULTI_FRAME: u8 = 0x04;
+/// Voice meant only for a few channel members. Client->server datagrams carry
+/// a whisper trailer (see below) naming them; the forwarder strips it but
+/// keeps the flag so receivers can tell a whisper apart. Only sent to
+/// gateways advertising `ServerFeature.WHISPER`.
+pub const VOICE_FLAG_WHISPER: u8 = 0x08;
+
+// ── Voice loudness ─────────────────────────────────────────────────────
+//
+// One byte, higher is louder: the frame's RMS level in dBFS offset by 127,
+// so 127 is full scale and 0 is -127 dBFS or quieter.
+
+pub const MAX_VOICE_LOUDNESS: u8 = 127;
+
+pub fn loudness_from_dbfs(dbfs: f32) -> u8 {
+    if dbfs.is_nan() {
+        return 0;
+    }
+    (dbfs.clamp(-127.0, 0.0) + 127.0).round() as u8
+}
+
+pub fn loudness_to_dbfs(loudness: u8) -> f32 {
+    f32::from(loudness.min(MAX_VOICE_LOUDNESS)) - 127.0
+}
+
+// ── Multi-frame voice payload ──────────────────────────────────────────
+//
+// Follows the normal voice header when VOICE_FLAG_MULTI_FRAME is set:
+//   0:  u8  frame_count       (2..=MAX_COALESCED_FRAMES)
+//   1:  u8  frame_ms          (duration of each frame)
+//   2:  frame_count x { u16 len (big-endian), len bytes of Opus }
+//
+// The header seq/ts_ms describe the first frame; frame i carries
+// seq + i and ts_ms + i * frame_ms.
+
+pub const MAX_COALESCED_FRAMES: usize = 4;
+pub const MULTI_FRAME_HEADER_BYTES: usize = 2;
+pub const MULTI_FRAME_LEN_PREFIX_BYTES: usize = 2;
+
+/// Encoded size of a multi-frame payload holding `frames`.
+pub fn multi_frame_payload_len<F: AsRef<[u8]>>(frames: &[F]) -> usize {
+    MULTI_FRAME_HEADER_BYTES
+        + frames
+            .iter()
+            .map(|f| MULTI_FRAME_LEN_PREFIX_BYTES + f.as_ref().len())
+            .sum::<usize>()
+}
+
+/// Append a multi-frame payload to `out`. Returns false (leaving `out`
+/// untouched) if the frame count is out of range or a frame is too large.
+pub fn encode_multi_frame_payload<F: AsRef<[u8]>>(
+    frame_ms: u8,
+    frames: &[F],
+    out: &mut Vec<u8>,
+) -> bool {
+    if frames.len() < 2 || frames.len() > MAX_COALESCED_FRAMES {
+        return false;
+    }
+    if frames.iter().any(|f| f.as_ref().len() > u16::MAX as usize) {
+        return false;
+    }
+    out.reserve(multi_frame_payload_len(frames));
+    out.push(frames.len() as u8);
+    out.push(frame_ms);
+    for f in frames {
+        let f = f.as_ref();
+        out.extend_from_slice(&(f.len() as u16).to_be_bytes());
+        out.extend_from_slice(f);
+    }
+    true
+}
+
+/// Split a multi-frame payload into `(frame_ms, frames)`. Rejects bundles
+/// with a bad count, truncated frames, empty frames or trailing bytes.
+pub fn split_multi_frame_payload(payload: &[u8]) -> Option<(u8, Vec<&[u8]>)> {
+    if payload.len() < MULTI_FRAME_HEADER_BYTES {
+        return None;
+    }
+    let count = payload[0] as usize;
+    let frame_ms = payload[1];
+    if !(2..=MAX_COALESCED_FRAMES).contains(&count) || frame_ms == 0 {
+        return None;
+    }
+    let mut frames = Vec::with_capacity(count);
+    let mut rest = &payload[MULTI_FRAME_HEADER_BYTES..];
+    for _ in 0..count {
+        if rest.len() < MULTI_FRAME_LEN_PREFIX_BYTES {
+            return None;
+        }
+        let len = u16::from_be_bytes([rest[0], rest[1]]) as usize;
+        rest = &rest[MULTI_FRAME_LEN_PREFIX_BYTES..];
+        if len == 0 || rest.len() < len {
+            return None;
+        }
+        frames.push(&rest[..len]);
+        rest = &rest[len..];
+    }
+    if !rest.is_empty() {
+        return None;
+    }
+    Some((frame_ms, frames))
+}
+
+// ── Whisper trailer ────────────────────────────────────────────────────
+//
+// Appended after the payload of a client->server datagram with
+// VOICE_FLAG_WHISPER set:
+//   n x u32 target hash      (big-endian, see whisper_target_hash)
+//   u8  n                    (1..=MAX_WHISPER_TARGETS)
+//
+// Living at the tail leaves the header layout, and with it the ordinary
+// voice path, untouched.
+
+pub const MAX_WHISPER_TARGETS: usize = 8;
+pub const WHISPER_TARGET_HASH_BYTES: usize = 4;
+
+/// Encoded size of a whisper trailer naming `targets` users.
+pub fn whisper_trailer_len(targets: usize) -> usize {
+    targets * WHISPER_TARGET_HASH_BYTES + 1
+}
+
+/// 32-bit FNV-1a over a user's raw UUID bytes, the same hash the channel
+/// route uses. Only has to tell apart the members of one channel.
+pub fn whisper_target_hash(user_id: &[u8; 16]) -> u32 {
+    let mut h: u32 = 0x811C_9DC5;
+    for &b in user_id {
+        h ^= u32::from(b);
+        h = h.wrapping_mul(0x0100_0193);
+    }
+    h
+}
+
+/// Append a whisper trailer to `out`. Returns false (leaving `out`
+/// untouched) for an empty or oversized target list.
+pub fn encode_whisper_trailer(targets: &[u32], out: &mut Vec<u8>) -> bool {
+    if targets.is_empty() || targets.len() > MAX_WHISPER_TARGETS {
+        return false;
+    }
+    out.reserve(whisper_trailer_len(targets.len()));
+    for target in targets {
+        out.extend_from_slice(&target.to_be_bytes());
+    }
+    out.push(targets.len() as u8);
+    true
+}
+
+/// Split everything after the voice header into `(payload, target hashes)`.
+/// Rejects a bad count and trailers that would leave no payload.
+pub fn split_whisper_trailer(body: &[u8]) -> Option<(&[u8], Vec<u32>)> {
+    let (&count, rest) = body.split_last()?;
+    let count = count as usize;
+    if !(1..=MAX_WHISPER_TARGETS).contains(&count) {
+        return None;
+    }
+    let split_at = rest.len().checked_sub(count * WHISPER_TARGET_HASH_BYTES)?;
+    let (payload, hashes) = rest.split_at(split_at);
+    if payload.is_empty() {
+        return None;
+    }
+    let targets = hashes
+        .chunks_exact(WHISPER_TARGET_HASH_BYTES)
+        .map(|h| u32::from_be_bytes([h[0], h[1], h[2], h[3]]))
+        .collect();
+    Some((payload, targets))
+}
+
 // ── Video datagram header ──────────────────────────────────────────────
 //
 // Fixed 22-byte header (little-endian for multi-byte fields):
@@ -56,6 +314,16 @@ pub const VIDEO_FLAG_END_OF_FRAME: u8 = 0x04;
 pub const MAX_VIDEO_DATAGRAM_BYTES: usize = APP_MEDIA_MTU;
 pub const MAX_VIDEO_PAYLOAD_BYTES: usize = MAX_VIDEO_DATAGRAM_BYTES - VIDEO_HEADER_BYTES;
 
+// ── Connection close codes ─────────────────────────────────────────────
+//
+// QUIC application close codes the gateway sends and the client acts on.
+// The gateway also uses 0x1a (abuse guard), 0x1b (admin disconnect) and
+// 0x1d (session resumed on a newer connection).
+
+/// Negotiated ALPN doesn't match the gateway's; the client and server speak
+/// different protocol versions and reconnecting won't help.
+pub const CLOSE_CODE_ALPN_MISMATCH: u32 = 0x1c;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -106,4 +374,203 @@ mod tests {
             Some(DATAGRAM_KIND_VIDEO)
         );
     }
+
+    /// xorshift64*: deterministic, dependency-free input generator for the
+    /// randomized parser tests below.
+    struct Rng(u64);
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 >> 12;
+            self.0 ^= self.0 << 25;
+            self.0 ^= self.0 >> 27;
+            self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+        }
+        fn below(&mut self, n: usize) -> usize {
+            (self.next() % n as u64) as usize
+        }
+    }
+
+    /// Random datagrams biased towards almost-valid voice headers, so both the
+    /// accept and reject paths get exercised.
+    fn random_voice_datagram(rng: &mut Rng) -> Vec<u8> {
+        let len = rng.below(FORWARDED_VOICE_HEADER_BYTES + 48);
+        let mut buf: Vec<u8> = (0..len).map(|_| rng.next() as u8).collect();
+        if rng.below(4) != 0 && buf.len() >= 4 {
+            buf[0] = VOICE_VERSION;
+            let header_len: u16 = match rng.below(7) {
+                0 => 0,
+                1 => CLIENT_VOICE_HEADER_BYTES as u16,
+                2 => FORWARDED_VOICE_HEADER_BYTES as u16,
+                3 => u16::MAX,
+                4 => buf.len() as u16,
+                5 => CLIENT_VOICE_HEADER_WITH_LOUDNESS_BYTES as u16,
+                _ => rng.next() as u16,
+            };
+            buf[2..4].copy_from_slice(&header_len.to_be_bytes());
+        }
+        if rng.below(3) == 0 && buf.len() > CLIENT_VOICE_HEADER_BYTES + 2 {
+            buf[1] |= VOICE_FLAG_MULTI_FRAME;
+            buf[CLIENT_VOICE_HEADER_BYTES] = rng.below(MAX_COALESCED_FRAMES + 2) as u8;
+        }
+        buf
+    }
+
+    #[test]
+    fn voice_header_parser_matches_reference_on_random_input() {
+        let mut rng = Rng(0x9E37_79B9_7F4A_7C15);
+        let mut accepted = 0;
+        for _ in 0..50_000 {
+            let buf = random_voice_datagram(&mut rng);
+            let expected_header_len = (buf.len() >= CLIENT_VOICE_HEADER_BYTES
+                && buf[0] == VOICE_VERSION)
+                .then(|| u16::from_be_bytes([buf[2], buf[3]]) as usize)
+                .filter(|hl| {
+                    (*hl == CLIENT_VOICE_HEADER_BYTES
+                        || *hl == FORWARDED_VOICE_HEADER_BYTES
+                        || (*hl == CLIENT_VOICE_HEADER_WITH_LOUDNESS_BYTES
+                            && buf[1] & VOICE_FLAG_LOUDNESS != 0))
+                        && buf.len() > *hl
+                });
+
+            let parsed = parse_voice_header(&buf);
+            assert_eq!(
+                parsed.map(|(h, _)| h.header_len),
+                expected_header_len,
+                "acceptance mismatch for {buf:?}"
+            );
+            let Some((hdr, payload)) = parsed else {
+                continue;
+            };
+            accepted += 1;
+            assert_eq!(payload, &buf[hdr.header_len..]);
+            assert_eq!(hdr.flags, buf[1]);
+            assert_eq!(hdr.ssrc.to_be_bytes(), buf[8..12]);
+            assert_eq!(hdr.ts_ms.to_be_bytes(), buf[16..20]);
+            assert_eq!(
+                hdr.forwarded_ids.is_some(),
+                hdr.header_len == FORWARDED_VOICE_HEADER_BYTES
+            );
+            assert_eq!(
+                hdr.loudness,
+                (hdr.header_len == CLIENT_VOICE_HEADER_WITH_LOUDNESS_BYTES).then(|| buf[20])
+            );
+            if let Some((_, frames)) = split_multi_frame_payload(payload) {
+                assert_eq!(multi_frame_payload_len(&frames), payload.len());
+            }
+        }
+        assert!(accepted > 1_000, "generator rarely produced valid headers");
+    }
+
+    #[test]
+    fn voice_header_rejects_truncated_forwarded_and_empty_payload() {
+        let mut buf = vec![0u8; FORWARDED_VOICE_HEADER_BYTES];
+        buf[0] = VOICE_VERSION;
+        buf[2..4].copy_from_slice(&(FORWARDED_VOICE_HEADER_BYTES as u16).to_be_bytes());
+        // Claims a forwarded header but carries no payload.
+        assert!(parse_voice_header(&buf).is_none());
+        // Claims a forwarded header on a client-sized datagram.
+        assert!(parse_voice_header(&buf[..CLIENT_VOICE_HEADER_BYTES + 1]).is_none());
+        buf.push(0xAA);
+        let (hdr, payload) = parse_voice_header(&buf).unwrap();
+        assert_eq!(hdr.forwarded_ids, Some(([0; 16], [0; 16])));
+        assert_eq!(payload, &[0xAA]);
+    }
+
+    #[test]
+    fn loudness_header_requires_flag_and_maps_dbfs() {
+        let mut buf = vec![0u8; CLIENT_VOICE_HEADER_WITH_LOUDNESS_BYTES + 1];
+        buf[0] = VOICE_VERSION;
+        buf[2..4].copy_from_slice(&(CLIENT_VOICE_HEADER_WITH_LOUDNESS_BYTES as u16).to_be_bytes());
+        buf[CLIENT_VOICE_HEADER_BYTES] = loudness_from_dbfs(-20.0);
+        assert!(parse_voice_header(&buf).is_none());
+
+        buf[1] = VOICE_FLAG_VAD | VOICE_FLAG_LOUDNESS;
+        let (hdr, payload) = parse_voice_header(&buf).unwrap();
+        assert_eq!(hdr.loudness, Some(107));
+        assert_eq!(payload.len(), 1);
+        assert_eq!(loudness_to_dbfs(hdr.loudness.unwrap()), -20.0);
+
+        assert_eq!(loudness_from_dbfs(3.0), MAX_VOICE_LOUDNESS);
+        assert_eq!(loudness_from_dbfs(f32::NEG_INFINITY), 0);
+        assert_eq!(loudness_from_dbfs(f32::NAN), 0);
+        assert_eq!(loudness_to_dbfs(u8::MAX), 0.0);
+    }
+
+    #[test]
+    fn whisper_trailer_round_trips_and_rejects_malformed() {
+        let targets = [whisper_target_hash(&[1; 16]), whisper_target_hash(&[2; 16])];
+        let mut body = b"opus".to_vec();
+        assert!(encode_whisper_trailer(&targets, &mut body));
+        assert_eq!(body.len(), 4 + whisper_trailer_len(targets.len()));
+
+        let (payload, split) = split_whisper_trailer(&body).unwrap();
+        assert_eq!(payload, b"opus");
+        assert_eq!(split, targets);
+
+        let mut out = Vec::new();
+        assert!(!encode_whisper_trailer(&[], &mut out));
+        let too_many = [7; MAX_WHISPER_TARGETS + 1];
+        assert!(!encode_whisper_trailer(&too_many, &mut out));
+        assert!(out.is_empty());
+
+        assert!(split_whisper_trailer(&[]).is_none());
+        // Trailer with no payload in front of it.
+        assert!(split_whisper_trailer(&body[4..]).is_none());
+        let mut zero = body.clone();
+        *zero.last_mut().unwrap() = 0;
+        assert!(split_whisper_trailer(&zero).is_none());
+        let mut too_many = body.clone();
+        *too_many.last_mut().unwrap() = MAX_WHISPER_TARGETS as u8 + 1;
+        assert!(split_whisper_trailer(&too_many).is_none());
+    }
+
+    #[test]
+    fn whisper_target_hash_matches_channel_route_hash() {
+        // Same FNV-1a as vp-route-hash's known-UUID vector.
+        let id = [
+            0x12, 0x3e, 0x45, 0x67, 0xe8, 0x9b, 0x12, 0xd3, 0xa4, 0x56, 0x42, 0x66, 0x14, 0x17,
+            0x40, 0x00,
+        ];
+        assert_eq!(whisper_target_hash(&id), 0xC586_1100);
+    }
+
+    #[test]
+    fn multi_frame_payload_round_trips() {
+        let frames: [&[u8]; 3] = [b"aa", b"bbbb", b"c"];
+        let mut out = Vec::new();
+        assert!(encode_multi_frame_payload(20, &frames, &mut out));
+        assert_eq!(out.len(), multi_frame_payload_len(&frames));
+
+        let (frame_ms, split) = split_multi_frame_payload(&out).unwrap();
+        assert_eq!(frame_ms, 20);
+        assert_eq!(split, frames.to_vec());
+    }
+
+    #[test]
+    fn multi_frame_encode_rejects_bad_counts() {
+        let mut out = Vec::new();
+        assert!(!encode_multi_frame_payload(20, &[b"a"], &mut out));
+        assert!(!encode_multi_frame_payload(
+            20,
+            &[b"a"; MAX_COALESCED_FRAMES + 1],
+            &mut out
+        ));
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn multi_frame_split_rejects_malformed() {
+        let mut out = Vec::new();
+        assert!(encode_multi_frame_payload(20, &[b"ab", b"cd"], &mut out));
+
+        assert!(split_multi_frame_payload(&out[..out.len() - 1]).is_none());
+        let mut trailing = out.clone();
+        trailing.push(0);
+        assert!(split_multi_frame_payload(&trailing).is_none());
+        let mut zero_ms = out.clone();
+        zero_ms[1] = 0;
+        assert!(split_multi_frame_payload(&zero_ms).is_none());
+        assert!(split_multi_frame_payload(&[1, 20, 0, 1, 9]).is_none());
+        assert!(split_multi_frame_payload(&[2, 20, 0, 0, 0, 1, 9]).is_none());
+    }
 }
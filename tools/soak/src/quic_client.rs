@@ -153,6 +153,8 @@ fn default_caps(alpn: &str) -> pb::ClientCaps {
             supports_noise_suppression: false,
             supports_echo_cancellation: false,
             supports_agc: false,
+            supports_voice_multi_frame: false,
+            supports_session_resume: false,
         }),
         voice_audio: None,
         screen_video: None,